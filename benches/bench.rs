@@ -0,0 +1,72 @@
+//! Times move generation, moves, evaluation and search on the positions of each stage of the
+//! `bench` module.
+
+extern crate criterion;
+extern crate rusty_reversi;
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use rusty_reversi::bench::{self, Stage};
+use rusty_reversi::engine::{Engine, Limits};
+use rusty_reversi::eval::{Evaluator, Heuristic, Phase};
+use rusty_reversi::search::AlphaBeta;
+
+// Depth of the timed searches, shallow enough for many samples.
+const DEPTH: u32 = 4;
+
+fn legal_moves(c: &mut Criterion) {
+    let mut group = c.benchmark_group("legal_moves");
+    for stage in Stage::ALL {
+        let positions = bench::stage(stage);
+        group.bench_with_input(BenchmarkId::from_parameter(stage), &positions, |b, positions| {
+            b.iter(|| positions.iter().map(|x| black_box(x.board).legal_moves(x.color).len()).sum::<usize>())
+        });
+    }
+    group.finish();
+}
+
+fn apply(c: &mut Criterion) {
+    let mut group = c.benchmark_group("apply");
+    for stage in Stage::ALL {
+        let moves = bench::stage(stage).iter().flat_map(|x| x.board.legal_moves(x.color).into_iter().map(move |y| (x.board, y))).collect::<Vec<_>>();
+        group.bench_with_input(BenchmarkId::from_parameter(stage), &moves, |b, moves| {
+            b.iter(|| {
+                for &(board, legal_move) in moves {
+                    black_box(legal_move.apply(black_box(board)));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+fn evaluate(c: &mut Criterion) {
+    let evaluator = Heuristic::default();
+    let mut group = c.benchmark_group("evaluate");
+    for stage in Stage::ALL {
+        let positions = bench::stage(stage);
+        group.bench_with_input(BenchmarkId::from_parameter(stage), &positions, |b, positions| {
+            b.iter(|| positions.iter().map(|x| evaluator.evaluate(black_box(&x.board), x.color, Phase::of(&x.board))).sum::<i32>())
+        });
+    }
+    group.finish();
+}
+
+fn search(c: &mut Criterion) {
+    let limits = Limits { depth: Some(DEPTH), ..Limits::default() };
+    let mut group = c.benchmark_group("search");
+    group.sample_size(10);
+    for stage in Stage::ALL {
+        let positions = bench::stage(stage);
+        group.bench_with_input(BenchmarkId::from_parameter(stage), &positions, |b, positions| {
+            let mut engine = AlphaBeta::new(DEPTH, Heuristic::default());
+            b.iter(|| positions.iter().map(|x| engine.best_move(black_box(&x.board), x.color, &limits).nodes).sum::<u64>())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, legal_moves, apply, evaluate, search);
+criterion_main!(benches);
@@ -0,0 +1,3 @@
+fn main() {
+    uniffi::generate_scaffolding("src/reversi.udl").expect("the interface definition is valid");
+}
@@ -0,0 +1,219 @@
+//! This crate exposes the rules and the engine of `rusty_reversi` to Swift and Kotlin through
+//! UniFFI, so that iOS and Android apps share them with the rest of the project without glue code
+//! of their own. It lives apart from the main crate as the code generated by UniFFI needs a newer
+//! edition.
+//!
+//! The interface is defined in `src/reversi.udl`. The bindings are generated from the built
+//! library with
+//! `cargo run -p rusty_reversi_ffi --features cli --bin uniffi-bindgen -- generate --library <library> --language swift --out-dir <directory>`,
+//! or `kotlin` as the language.
+//!
+//! The objects can be shared between threads. Searches block until they are done, so apps run
+//! them away from their UI thread.
+
+// the scaffolding generated by UniFFI has a blank line after a doc comment
+#![allow(clippy::empty_line_after_doc_comments)]
+
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use rusty_reversi::engine::{Engine as _, Limits};
+use rusty_reversi::eval::Heuristic;
+use rusty_reversi::formats::json::{self, Value};
+use rusty_reversi::game::{Color, Coord};
+use rusty_reversi::record;
+use rusty_reversi::search::AlphaBeta;
+use rusty_reversi::session::{GameSession, Player as SessionPlayer};
+
+uniffi::include_scaffolding!("reversi");
+
+/// A side of the game.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Player {
+    Black,
+    White
+}
+impl From<Color> for Player {
+    fn from(color: Color) -> Player {
+        match color {
+            Color::Black => Player::Black,
+            Color::White => Player::White
+        }
+    }
+}
+impl From<Player> for Color {
+    fn from(player: Player) -> Color {
+        match player {
+            Player::Black => Color::Black,
+            Player::White => Color::White
+        }
+    }
+}
+
+/// The errors thrown to the apps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReversiError {
+    InvalidCell { cell: String },
+    IllegalMove { cell: String, reason: String },
+    GameOver,
+    InvalidGame { message: String },
+    InvalidOption { message: String }
+}
+impl fmt::Display for ReversiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReversiError::InvalidCell { cell } => write!(f, "invalid cell: {}", cell),
+            ReversiError::IllegalMove { cell, reason } => write!(f, "illegal move {}: {}", cell, reason),
+            ReversiError::GameOver => write!(f, "the game is over"),
+            ReversiError::InvalidGame { message } => write!(f, "invalid game: {}", message),
+            ReversiError::InvalidOption { message } => write!(f, "invalid option: {}", message)
+        }
+    }
+}
+impl std::error::Error for ReversiError {}
+
+/// The result of a search, with cells in algebraic notation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Analysis {
+    pub best: Option<String>,
+    pub score: i32,
+    pub depth: u32,
+    pub nodes: u64,
+    pub line: Vec<String>
+}
+
+/// A game being played.
+#[derive(Debug)]
+pub struct Session {
+    session: Mutex<GameSession>
+}
+impl Session {
+    pub fn new(black: String, white: String) -> Session {
+        Session { session: Mutex::new(GameSession::new(SessionPlayer::Human(black), SessionPlayer::Human(white))) }
+    }
+
+    /// Reads a game from its JSON document, replaying its moves.
+    pub fn from_json(json: String) -> Result<Session, ReversiError> {
+        let invalid = |message: String| ReversiError::InvalidGame { message };
+        let record = Value::parse(&json).and_then(|x| json::game_from_json(&x)).map_err(|e| invalid(e.to_string()))?;
+        let mut session = GameSession::new(SessionPlayer::Human(record.black), SessionPlayer::Human(record.white));
+        session.event = record.event;
+        session.date = record.date;
+        for (i, &position) in record.moves.iter().enumerate() {
+            session.play(position, Duration::ZERO).map_err(|e| invalid(format!("move {}: {}", i + 1, e)))?;
+        }
+        Ok(Session { session: Mutex::new(session) })
+    }
+
+    /// Plays a move of the player to move. The opponent skips if they have no legal move.
+    pub fn play(&self, cell: String) -> Result<(), ReversiError> {
+        let position = parse_cell(&cell)?;
+        let mut session = self.lock();
+        if session.is_over() {
+            return Err(ReversiError::GameOver);
+        }
+        let color = session.color();
+        session.board().test_move(color, position).map_err(|e| ReversiError::IllegalMove { cell: cell.clone(), reason: e.to_string() })?;
+        session.play(position, Duration::ZERO).expect("the move is legal");
+        Ok(())
+    }
+
+    /// Takes back the last move and returns it, `None` if there was none.
+    pub fn undo(&self) -> Option<String> {
+        self.lock().undo().map(|x| record::format_move(x.position))
+    }
+
+    /// The cells the player to move can play, none once the game is over.
+    pub fn legal_moves(&self) -> Vec<String> {
+        let session = self.lock();
+        session.board().legal_moves(session.color()).iter().map(|x| record::format_move(x.position)).collect()
+    }
+
+    pub fn is_legal(&self, cell: String) -> bool {
+        let session = self.lock();
+        record::parse_move(&cell).is_some_and(|x| session.board().test_move(session.color(), x).is_ok())
+    }
+
+    /// The player to move, `None` once the game is over.
+    pub fn to_move(&self) -> Option<Player> {
+        let session = self.lock();
+        if session.is_over() { None } else { Some(session.color().into()) }
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.lock().is_over()
+    }
+
+    /// The 64 cells row by row, from a8 to h1.
+    pub fn board(&self) -> Vec<Option<Player>> {
+        let session = self.lock();
+        let cells = &session.board().cells;
+        (0..8).flat_map(|y| (0..8).map(move |x| cells[x][y].map(Player::from))).collect()
+    }
+
+    pub fn disks(&self, player: Player) -> u32 {
+        self.lock().board().count(player.into()) as u32
+    }
+
+    /// The moves played, skips left out.
+    pub fn moves(&self) -> Vec<String> {
+        self.lock().history().iter().map(|x| record::format_move(x.position)).collect()
+    }
+
+    /// The position with the player to move.
+    pub fn position(&self) -> String {
+        let session = self.lock();
+        record::format_position(session.board(), session.color())
+    }
+
+    /// The JSON document of the game.
+    pub fn to_json(&self) -> String {
+        json::game_to_json(&self.lock().record()).to_string()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, GameSession> {
+        self.session.lock().expect("the session is not poisoned")
+    }
+}
+
+/// The alpha-beta engine with the heuristic evaluation. It searches one position at a time.
+pub struct Engine {
+    engine: Mutex<AlphaBeta<Heuristic>>
+}
+impl Engine {
+    /// An engine searching to a depth, or as deep as it can within the time of a search.
+    pub fn new(depth: u32) -> Engine {
+        Engine { engine: Mutex::new(AlphaBeta::new(depth, Heuristic::default())) }
+    }
+
+    /// Changes a setting of the engine, like `hash` or `endgame`.
+    pub fn set_option(&self, name: String, value: String) -> Result<(), ReversiError> {
+        let mut engine = self.engine.lock().expect("the engine is not poisoned");
+        engine.set_option(&name, &value).map_err(|e| ReversiError::InvalidOption { message: e.to_string() })
+    }
+
+    /// Searches the best move of the player to move, for about `time_ms` milliseconds if given.
+    pub fn best_move(&self, session: Arc<Session>, time_ms: Option<u32>) -> Result<Analysis, ReversiError> {
+        let (board, color) = {
+            let session = session.lock();
+            if session.is_over() {
+                return Err(ReversiError::GameOver);
+            }
+            (*session.board(), session.color())
+        };
+        let limits = Limits { time: time_ms.map(|x| Duration::from_millis(u64::from(x))), ..Limits::default() };
+        let found = self.engine.lock().expect("the engine is not poisoned").best_move(&board, color, &limits);
+        Ok(Analysis {
+            best: found.best.map(record::format_move),
+            score: found.score,
+            depth: found.depth,
+            nodes: found.nodes,
+            line: found.pv.iter().map(|&x| record::format_move(x)).collect()
+        })
+    }
+}
+
+fn parse_cell(cell: &str) -> Result<Coord, ReversiError> {
+    record::parse_move(cell).ok_or_else(|| ReversiError::InvalidCell { cell: cell.to_string() })
+}
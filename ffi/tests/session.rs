@@ -0,0 +1,45 @@
+use std::sync::Arc;
+
+use rusty_reversi_ffi::{Engine, Player, ReversiError, Session};
+
+#[test]
+fn sessions_follow_the_rules() {
+    let session = Session::new("Ann".to_string(), "Bob".to_string());
+    assert_eq!(session.to_move(), Some(Player::Black));
+    assert_eq!(session.legal_moves(), vec!["c4", "d3", "e6", "f5"]);
+    assert!(matches!(session.play("z9".to_string()), Err(ReversiError::InvalidCell { .. })));
+    assert!(matches!(session.play("d4".to_string()), Err(ReversiError::IllegalMove { .. })));
+
+    session.play("f5".to_string()).unwrap();
+    assert_eq!(session.to_move(), Some(Player::White));
+    assert_eq!((session.disks(Player::Black), session.disks(Player::White)), (4, 1));
+    let board = session.board();
+    assert_eq!(board.len(), 64);
+    // f5 is on the fourth row from the top
+    assert_eq!(board[3 * 8 + 5], Some(Player::Black));
+    assert_eq!(board[0], None);
+
+    let copy = Session::from_json(session.to_json()).unwrap();
+    assert_eq!(copy.moves(), vec!["f5"]);
+    assert_eq!(copy.position(), session.position());
+    assert!(matches!(Session::from_json("{".to_string()), Err(ReversiError::InvalidGame { .. })));
+
+    assert_eq!(session.undo(), Some("f5".to_string()));
+    assert_eq!(session.undo(), None);
+}
+
+#[test]
+fn the_engine_searches_sessions() {
+    let session = Arc::new(Session::new("Ann".to_string(), "Bob".to_string()));
+    let engine = Engine::new(3);
+    assert!(matches!(engine.set_option("depth".to_string(), "deep".to_string()), Err(ReversiError::InvalidOption { .. })));
+    engine.set_option("hash".to_string(), "1".to_string()).unwrap();
+
+    let analysis = engine.best_move(session.clone(), None).unwrap();
+    assert_eq!(analysis.depth, 3);
+    assert_eq!(analysis.line.len(), 3);
+    assert!(session.is_legal(analysis.best.clone().unwrap()));
+    let analysis = engine.best_move(session.clone(), Some(20)).unwrap();
+    session.play(analysis.best.unwrap()).unwrap();
+    assert_eq!(session.moves().len(), 1);
+}
@@ -0,0 +1,224 @@
+//! This crate runs an engine of `rusty_reversi` as a gRPC service, so that the analysis backend
+//! of a website can use it as a remote microservice. It lives apart from the main crate as gRPC
+//! needs async code, which the edition of the main crate doesn't allow.
+//!
+//! The service is defined in `proto/analysis.proto`. Searches run one at a time on a thread owning
+//! the engine, so that its transposition table is shared by all the requests, while endgames are
+//! solved on threads of their own. The time of a request counts from its arrival, and is shared by
+//! all the searches it asks for.
+
+// the errors are the statuses of tonic, sent to the clients as they are
+#![allow(clippy::result_large_err)]
+
+pub mod proto;
+
+use std::io;
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc as channel, oneshot};
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tonic::{Request, Response, Status};
+
+use rusty_reversi::analysis;
+use rusty_reversi::endgame;
+use rusty_reversi::engine::{self, Engine, Limits};
+use rusty_reversi::game::{Board, Color, Coord};
+use rusty_reversi::net::proto::{Move, Position};
+use rusty_reversi::search::EndgameMode;
+
+use proto::analysis_server::{Analysis, AnalysisServer};
+
+// A search run on the thread of the engine.
+type Job = Box<dyn FnOnce(&mut dyn Engine) + Send>;
+
+// Results of a streamed search waiting for the client.
+const STREAM_BUFFER: usize = 16;
+
+/// The service, searching with one engine.
+pub struct AnalysisService {
+    /// Most empty cells of the positions solved, as solving takes exponentially longer with them.
+    pub max_empties: usize,
+    /// Deepest search, deeper ones asked by clients are cut to it.
+    pub max_depth: u32,
+    /// Longest time of a request, also given to the requests without a time limit. It is split
+    /// between the moves of an evaluation, and the engines look at it during their searches.
+    pub max_time: Duration,
+    jobs: Mutex<mpsc::Sender<Job>>
+}
+impl AnalysisService {
+    /// A service searching with the engine made by `engine` on a thread of its own, as engines
+    /// can't be shared between threads.
+    pub fn new<E: Engine + 'static, F: FnOnce() -> E + Send + 'static>(engine: F) -> AnalysisService {
+        AnalysisService::try_new(move || Ok(engine())).expect("the engine is made")
+    }
+
+    /// Same as [`new`](#method.new), failing if the engine can't be made.
+    pub fn try_new<E: Engine + 'static, F: FnOnce() -> io::Result<E> + Send + 'static>(engine: F) -> io::Result<AnalysisService> {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let (made, was_made) = mpsc::sync_channel(1);
+        thread::spawn(move || {
+            let mut engine = match engine() {
+                Ok(engine) => engine,
+                Err(error) => {
+                    let _ = made.send(Err(error));
+                    return;
+                }
+            };
+            let _ = made.send(Ok(()));
+            for job in receiver {
+                job(&mut engine);
+            }
+        });
+        was_made.recv().map_err(|_| io::Error::other("the engine panicked"))??;
+        Ok(AnalysisService { max_empties: 20, max_depth: 32, max_time: Duration::from_secs(10), jobs: Mutex::new(sender) })
+    }
+
+    // Runs a job on the thread of the engine.
+    fn submit(&self, job: Job) -> Result<(), Status> {
+        let jobs = self.jobs.lock().expect("no thread panics holding the jobs");
+        jobs.send(job).map_err(|_| Status::internal("the engine stopped"))
+    }
+
+    // Runs a job on the thread of the engine and waits for its result.
+    async fn search<T, F>(&self, job: F) -> Result<T, Status>
+    where T: Send + 'static, F: FnOnce(&mut dyn Engine) -> T + Send + 'static {
+        let (sender, receiver) = oneshot::channel();
+        self.submit(Box::new(move |engine| {
+            // the client may have left
+            let _ = sender.send(job(engine));
+        }))?;
+        receiver.await.map_err(|_| Status::internal("the engine stopped"))
+    }
+
+    // The limits asked by a client, within the ones of the service, and the deadline of the
+    // request.
+    fn limits(&self, limits: Option<proto::Limits>) -> (Limits, Instant) {
+        let limits = limits.unwrap_or_default();
+        let time = limits.time_ms.map_or(self.max_time, |x| Duration::from_millis(x).min(self.max_time));
+        let deadline = Instant::now() + time;
+        (Limits { depth: limits.depth.map(|x| x.min(self.max_depth)), nodes: limits.nodes, time: Some(time) }, deadline)
+    }
+}
+
+#[tonic::async_trait]
+impl Analysis for AnalysisService {
+    async fn evaluate_position(&self, request: Request<proto::SearchRequest>) -> Result<Response<proto::Evaluation>, Status> {
+        let request = request.into_inner();
+        let (board, color) = position(request.position)?;
+        let (limits, deadline) = self.limits(request.limits);
+        let moves = self.search(move |mut engine| {
+            let moves = board.legal_moves(color);
+            let mut scores = moves.iter().enumerate()
+                .map(|(i, x)| (x.position, analysis::score_move(&mut engine, &board, x, &remaining(&limits, deadline, moves.len() - i))))
+                .collect::<Vec<_>>();
+            scores.sort_by_key(|x| -x.1);
+            scores
+        }).await?;
+        let moves = moves.into_iter().map(|(position, score)| proto::MoveScore { r#move: Some(Move::new(color, Some(position))), score }).collect();
+        Ok(Response::new(proto::Evaluation { moves }))
+    }
+
+    async fn best_move(&self, request: Request<proto::SearchRequest>) -> Result<Response<proto::SearchResult>, Status> {
+        let request = request.into_inner();
+        let (board, color) = position(request.position)?;
+        let (limits, deadline) = self.limits(request.limits);
+        let found = self.search(move |engine| engine.best_move(&board, color, &remaining(&limits, deadline, 1))).await?;
+        Ok(Response::new(search_result(color, &found)))
+    }
+
+    async fn solve_endgame(&self, request: Request<proto::SolveRequest>) -> Result<Response<proto::Solution>, Status> {
+        let request = request.into_inner();
+        let (board, color) = position(request.position)?;
+        let empties = empties(&board);
+        if empties > self.max_empties {
+            return Err(Status::invalid_argument(format!("{} empty cells, at most {} are solved", empties, self.max_empties)));
+        }
+        let mode = if request.win_loss_draw { EndgameMode::WinLossDraw } else { EndgameMode::Exact };
+        let start = Instant::now();
+        let (solution, line, nodes) = tokio::task::spawn_blocking(move || endgame::solve_line(&board, color, mode))
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        Ok(Response::new(proto::Solution {
+            score: i32::from(solution.score),
+            line: line.into_iter().map(cell).collect(),
+            nodes,
+            time_ms: start.elapsed().as_millis() as u64
+        }))
+    }
+
+    type StreamSearchInfoStream = ReceiverStream<Result<proto::SearchResult, Status>>;
+
+    async fn stream_search_info(&self, request: Request<proto::SearchRequest>) -> Result<Response<Self::StreamSearchInfoStream>, Status> {
+        let request = request.into_inner();
+        let (board, color) = position(request.position)?;
+        if request.limits.as_ref().is_none_or(|x| x.depth.is_none() && x.time_ms.is_none()) {
+            return Err(Status::invalid_argument("a depth or a time limit is needed"));
+        }
+        let (limits, deadline) = self.limits(request.limits);
+        // deeper searches than the rest of the game find nothing more
+        let deepest = limits.depth.unwrap_or(self.max_depth).min(empties(&board).max(1) as u32);
+        let (sender, receiver) = channel::channel(STREAM_BUFFER);
+        self.submit(Box::new(move |engine| {
+            for depth in 1..=deepest {
+                let found = engine.best_move(&board, color, &Limits { depth: Some(depth), ..remaining(&limits, deadline, 1) });
+                // the client left
+                if sender.blocking_send(Ok(search_result(color, &found))).is_err() {
+                    break;
+                }
+                // engines without depths search the same way every time
+                if found.depth < depth || Instant::now() >= deadline {
+                    break;
+                }
+            }
+        }))?;
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+}
+
+/// Serves requests until the server fails.
+pub async fn serve(listener: TcpListener, service: AnalysisService) -> Result<(), tonic::transport::Error> {
+    tonic::transport::Server::builder()
+        .add_service(AnalysisServer::new(service))
+        .serve_with_incoming(TcpListenerStream::new(listener))
+        .await
+}
+
+/// A service searching with the engine given like `--engine` of the command line.
+pub fn service_from_spec(spec: &str) -> io::Result<AnalysisService> {
+    let spec = spec.to_string();
+    AnalysisService::try_new(move || engine::from_spec(&spec))
+}
+
+// The limits of the next of `searches` searches left before the deadline, which share the time.
+fn remaining(limits: &Limits, deadline: Instant, searches: usize) -> Limits {
+    let time = deadline.saturating_duration_since(Instant::now()) / searches.max(1) as u32;
+    Limits { time: Some(time), ..*limits }
+}
+
+fn position(position: Option<Position>) -> Result<(Board, Color), Status> {
+    let position = position.ok_or_else(|| Status::invalid_argument("missing position"))?;
+    position.to_board().map_err(|e| Status::invalid_argument(e.to_string()))
+}
+
+fn search_result(color: Color, found: &engine::SearchResult) -> proto::SearchResult {
+    proto::SearchResult {
+        best: found.best.map(|x| Move::new(color, Some(x))),
+        score: found.score,
+        depth: found.depth,
+        nodes: found.nodes,
+        time_ms: found.time.as_millis() as u64,
+        pv: found.pv.iter().cloned().map(cell).collect()
+    }
+}
+
+fn empties(board: &Board) -> usize {
+    64 - board.count(Color::Black) - board.count(Color::White)
+}
+
+// The number of a cell in the messages.
+fn cell((x, y): Coord) -> u32 {
+    y as u32 * 8 + x as u32
+}
@@ -0,0 +1,56 @@
+use std::env;
+use std::io;
+use std::process;
+use std::time::Duration;
+
+use tokio::net::TcpListener;
+
+use rusty_reversi_grpc::{serve, service_from_spec};
+
+const USAGE: &str = "usage: rusty_reversi_grpc [--port n] [--engine name[:option=value,...]] [--max-empties n]
+                         [--max-depth n] [--max-time seconds]
+
+Serves the analysis service of proto/analysis.proto over gRPC, on port 7500 by default, with the
+engine named like in rusty_reversi, alphabeta by default. Endgames with more than --max-empties
+empty cells, 20 by default, are not solved. Searches go at most --max-depth plies deep, 32 by
+default, and last at most --max-time, 10 seconds by default, which is also the limit of the
+searches asked without one.";
+
+#[tokio::main]
+async fn main() {
+    if let Err(error) = run(env::args().skip(1).collect()).await {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
+
+async fn run(args: Vec<String>) -> io::Result<()> {
+    let (mut port, mut engine, mut max_empties, mut max_depth, mut max_time) = (7500u16, "alphabeta".to_string(), None, None, None);
+    let mut args = args.into_iter();
+    while let Some(flag) = args.next() {
+        let mut value = || args.next().ok_or_else(|| invalid(&format!("missing value for {}", flag)));
+        match flag.as_str() {
+            "--port" => port = value()?.parse().map_err(|_| invalid("invalid port"))?,
+            "--engine" => engine = value()?,
+            "--max-empties" => max_empties = Some(value()?.parse().map_err(|_| invalid("invalid number of empty cells"))?),
+            "--max-depth" => max_depth = Some(value()?.parse().map_err(|_| invalid("invalid depth"))?),
+            "--max-time" => max_time = Some(value()?.parse().ok().and_then(|x| Duration::try_from_secs_f64(x).ok()).ok_or_else(|| invalid("invalid time"))?),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            _ => return Err(invalid(&format!("unknown argument: {}\n\n{}", flag, USAGE)))
+        }
+    }
+    let mut service = service_from_spec(&engine)?;
+    service.max_empties = max_empties.unwrap_or(service.max_empties);
+    service.max_depth = max_depth.unwrap_or(service.max_depth);
+    service.max_time = max_time.unwrap_or(service.max_time);
+    let listener = TcpListener::bind(("0.0.0.0", port)).await?;
+    println!("Serving on {}", listener.local_addr()?);
+    serve(listener, service).await.map_err(io::Error::other)
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message.to_string())
+}
@@ -0,0 +1,302 @@
+//! The messages and the service of `proto/analysis.proto`, as generated by `tonic-build` with
+//! the messages of `rusty_reversi.proto` mapped to the ones of the crate. Regenerate them when
+//! the schema changes.
+
+use rusty_reversi::net::proto::{Move, Position};
+
+/// Limits of a search, the engine using its own for the ones absent.
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct Limits {
+    #[prost(uint32, optional, tag = "1")]
+    pub depth: Option<u32>,
+    #[prost(uint64, optional, tag = "2")]
+    pub nodes: Option<u64>,
+    #[prost(uint64, optional, tag = "3")]
+    pub time_ms: Option<u64>
+}
+
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct SearchRequest {
+    #[prost(message, optional, tag = "1")]
+    pub position: Option<Position>,
+    #[prost(message, optional, tag = "2")]
+    pub limits: Option<Limits>
+}
+
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct MoveScore {
+    #[prost(message, optional, tag = "1")]
+    pub r#move: Option<Move>,
+    #[prost(sint32, tag = "2")]
+    pub score: i32
+}
+
+/// The scores of the legal moves, best first.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Evaluation {
+    #[prost(message, repeated, tag = "1")]
+    pub moves: Vec<MoveScore>
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SearchResult {
+    /// Absent when the player to move has no legal move.
+    #[prost(message, optional, tag = "1")]
+    pub best: Option<Move>,
+    #[prost(sint32, tag = "2")]
+    pub score: i32,
+    #[prost(uint32, tag = "3")]
+    pub depth: u32,
+    #[prost(uint64, tag = "4")]
+    pub nodes: u64,
+    #[prost(uint64, tag = "5")]
+    pub time_ms: u64,
+    /// The cells expected to be played, starting with the best move, skips left out.
+    #[prost(uint32, repeated, tag = "6")]
+    pub pv: Vec<u32>
+}
+
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct SolveRequest {
+    #[prost(message, optional, tag = "1")]
+    pub position: Option<Position>,
+    /// Only finds whether the player to move wins, loses or draws, which is faster.
+    #[prost(bool, tag = "2")]
+    pub win_loss_draw: bool
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Solution {
+    /// The final disk differential for the player to move, only its sign in win-loss-draw mode.
+    #[prost(sint32, tag = "1")]
+    pub score: i32,
+    /// The cells played by both players to the end of the game, skips left out.
+    #[prost(uint32, repeated, tag = "2")]
+    pub line: Vec<u32>,
+    #[prost(uint64, tag = "3")]
+    pub nodes: u64,
+    #[prost(uint64, tag = "4")]
+    pub time_ms: u64
+}
+
+/// Generated client implementations.
+pub mod analysis_client {
+    #![allow(clippy::wildcard_imports)]
+    use tonic::codegen::http::Uri;
+    use tonic::codegen::*;
+
+    #[derive(Debug, Clone)]
+    pub struct AnalysisClient<T> {
+        inner: tonic::client::Grpc<T>
+    }
+    impl AnalysisClient<tonic::transport::Channel> {
+        /// Attempts to create a new client by connecting to a given endpoint.
+        pub async fn connect<D>(dst: D) -> Result<Self, tonic::transport::Error>
+        where D: TryInto<tonic::transport::Endpoint>, D::Error: Into<StdError> {
+            let conn = tonic::transport::Endpoint::new(dst)?.connect().await?;
+            Ok(Self::new(conn))
+        }
+    }
+    impl<T> AnalysisClient<T>
+    where
+        T: tonic::client::GrpcService<tonic::body::BoxBody>,
+        T::Error: Into<StdError>,
+        T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+        <T::ResponseBody as Body>::Error: Into<StdError> + Send
+    {
+        pub fn new(inner: T) -> Self {
+            Self { inner: tonic::client::Grpc::new(inner) }
+        }
+
+        pub fn with_origin(inner: T, origin: Uri) -> Self {
+            Self { inner: tonic::client::Grpc::with_origin(inner, origin) }
+        }
+
+        /// Scores every legal move of a position.
+        pub async fn evaluate_position(&mut self, request: impl tonic::IntoRequest<super::SearchRequest>)
+            -> Result<tonic::Response<super::Evaluation>, tonic::Status> {
+            self.ready().await?;
+            let path = http::uri::PathAndQuery::from_static("/rusty_reversi.analysis.Analysis/EvaluatePosition");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("rusty_reversi.analysis.Analysis", "EvaluatePosition"));
+            self.inner.unary(req, path, tonic::codec::ProstCodec::default()).await
+        }
+
+        pub async fn best_move(&mut self, request: impl tonic::IntoRequest<super::SearchRequest>)
+            -> Result<tonic::Response<super::SearchResult>, tonic::Status> {
+            self.ready().await?;
+            let path = http::uri::PathAndQuery::from_static("/rusty_reversi.analysis.Analysis/BestMove");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("rusty_reversi.analysis.Analysis", "BestMove"));
+            self.inner.unary(req, path, tonic::codec::ProstCodec::default()).await
+        }
+
+        /// Solves a position by searching to the end of the game.
+        pub async fn solve_endgame(&mut self, request: impl tonic::IntoRequest<super::SolveRequest>)
+            -> Result<tonic::Response<super::Solution>, tonic::Status> {
+            self.ready().await?;
+            let path = http::uri::PathAndQuery::from_static("/rusty_reversi.analysis.Analysis/SolveEndgame");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("rusty_reversi.analysis.Analysis", "SolveEndgame"));
+            self.inner.unary(req, path, tonic::codec::ProstCodec::default()).await
+        }
+
+        /// Searches a position one depth after another, sending the result of each, until the
+        /// depth or the time of the limits is reached. One of them is needed.
+        pub async fn stream_search_info(&mut self, request: impl tonic::IntoRequest<super::SearchRequest>)
+            -> Result<tonic::Response<tonic::codec::Streaming<super::SearchResult>>, tonic::Status> {
+            self.ready().await?;
+            let path = http::uri::PathAndQuery::from_static("/rusty_reversi.analysis.Analysis/StreamSearchInfo");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("rusty_reversi.analysis.Analysis", "StreamSearchInfo"));
+            self.inner.server_streaming(req, path, tonic::codec::ProstCodec::default()).await
+        }
+
+        async fn ready(&mut self) -> Result<(), tonic::Status> {
+            self.inner.ready().await.map_err(|e| tonic::Status::unknown(format!("Service was not ready: {}", e.into())))
+        }
+    }
+}
+
+/// Generated server implementations.
+pub mod analysis_server {
+    #![allow(clippy::wildcard_imports)]
+    use tonic::codegen::*;
+
+    /// Generated trait containing gRPC methods that should be implemented for use with
+    /// AnalysisServer.
+    #[async_trait]
+    pub trait Analysis: Send + Sync + 'static {
+        /// Scores every legal move of a position.
+        async fn evaluate_position(&self, request: tonic::Request<super::SearchRequest>)
+            -> Result<tonic::Response<super::Evaluation>, tonic::Status>;
+
+        async fn best_move(&self, request: tonic::Request<super::SearchRequest>)
+            -> Result<tonic::Response<super::SearchResult>, tonic::Status>;
+
+        /// Solves a position by searching to the end of the game.
+        async fn solve_endgame(&self, request: tonic::Request<super::SolveRequest>)
+            -> Result<tonic::Response<super::Solution>, tonic::Status>;
+
+        /// Server streaming response type for the StreamSearchInfo method.
+        type StreamSearchInfoStream: tokio_stream::Stream<Item = Result<super::SearchResult, tonic::Status>> + Send + 'static;
+
+        /// Searches a position one depth after another, sending the result of each, until the
+        /// depth or the time of the limits is reached. One of them is needed.
+        async fn stream_search_info(&self, request: tonic::Request<super::SearchRequest>)
+            -> Result<tonic::Response<Self::StreamSearchInfoStream>, tonic::Status>;
+    }
+
+    #[derive(Debug)]
+    pub struct AnalysisServer<T> {
+        inner: Arc<T>
+    }
+    impl<T> AnalysisServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            Self { inner }
+        }
+    }
+    impl<T, B> Service<http::Request<B>> for AnalysisServer<T>
+    where T: Analysis, B: Body + Send + 'static, B::Error: Into<StdError> + Send + 'static {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            match req.uri().path() {
+                "/rusty_reversi.analysis.Analysis/EvaluatePosition" => {
+                    struct EvaluatePositionSvc<T: Analysis>(Arc<T>);
+                    impl<T: Analysis> tonic::server::UnaryService<super::SearchRequest> for EvaluatePositionSvc<T> {
+                        type Response = super::Evaluation;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+
+                        fn call(&mut self, request: tonic::Request<super::SearchRequest>) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            Box::pin(async move { <T as Analysis>::evaluate_position(&inner, request).await })
+                        }
+                    }
+                    Box::pin(async move {
+                        let mut grpc = tonic::server::Grpc::new(tonic::codec::ProstCodec::default());
+                        Ok(grpc.unary(EvaluatePositionSvc(inner), req).await)
+                    })
+                }
+                "/rusty_reversi.analysis.Analysis/BestMove" => {
+                    struct BestMoveSvc<T: Analysis>(Arc<T>);
+                    impl<T: Analysis> tonic::server::UnaryService<super::SearchRequest> for BestMoveSvc<T> {
+                        type Response = super::SearchResult;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+
+                        fn call(&mut self, request: tonic::Request<super::SearchRequest>) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            Box::pin(async move { <T as Analysis>::best_move(&inner, request).await })
+                        }
+                    }
+                    Box::pin(async move {
+                        let mut grpc = tonic::server::Grpc::new(tonic::codec::ProstCodec::default());
+                        Ok(grpc.unary(BestMoveSvc(inner), req).await)
+                    })
+                }
+                "/rusty_reversi.analysis.Analysis/SolveEndgame" => {
+                    struct SolveEndgameSvc<T: Analysis>(Arc<T>);
+                    impl<T: Analysis> tonic::server::UnaryService<super::SolveRequest> for SolveEndgameSvc<T> {
+                        type Response = super::Solution;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+
+                        fn call(&mut self, request: tonic::Request<super::SolveRequest>) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            Box::pin(async move { <T as Analysis>::solve_endgame(&inner, request).await })
+                        }
+                    }
+                    Box::pin(async move {
+                        let mut grpc = tonic::server::Grpc::new(tonic::codec::ProstCodec::default());
+                        Ok(grpc.unary(SolveEndgameSvc(inner), req).await)
+                    })
+                }
+                "/rusty_reversi.analysis.Analysis/StreamSearchInfo" => {
+                    struct StreamSearchInfoSvc<T: Analysis>(Arc<T>);
+                    impl<T: Analysis> tonic::server::ServerStreamingService<super::SearchRequest> for StreamSearchInfoSvc<T> {
+                        type Response = super::SearchResult;
+                        type ResponseStream = T::StreamSearchInfoStream;
+                        type Future = BoxFuture<tonic::Response<Self::ResponseStream>, tonic::Status>;
+
+                        fn call(&mut self, request: tonic::Request<super::SearchRequest>) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            Box::pin(async move { <T as Analysis>::stream_search_info(&inner, request).await })
+                        }
+                    }
+                    Box::pin(async move {
+                        let mut grpc = tonic::server::Grpc::new(tonic::codec::ProstCodec::default());
+                        Ok(grpc.server_streaming(StreamSearchInfoSvc(inner), req).await)
+                    })
+                }
+                _ => Box::pin(async move {
+                    let mut response = http::Response::new(empty_body());
+                    let headers = response.headers_mut();
+                    headers.insert(tonic::Status::GRPC_STATUS, (tonic::Code::Unimplemented as i32).into());
+                    headers.insert(http::header::CONTENT_TYPE, tonic::metadata::GRPC_CONTENT_TYPE);
+                    Ok(response)
+                })
+            }
+        }
+    }
+    impl<T> Clone for AnalysisServer<T> {
+        fn clone(&self) -> Self {
+            Self { inner: self.inner.clone() }
+        }
+    }
+
+    /// Generated gRPC service name
+    pub const SERVICE_NAME: &str = "rusty_reversi.analysis.Analysis";
+    impl<T> tonic::server::NamedService for AnalysisServer<T> {
+        const NAME: &'static str = SERVICE_NAME;
+    }
+}
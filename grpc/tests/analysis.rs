@@ -0,0 +1,135 @@
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+use tokio::net::TcpListener;
+use tokio_stream::StreamExt;
+
+use rusty_reversi::eval::WeightedSquares;
+use rusty_reversi::game::{Board, Color};
+use rusty_reversi::net::proto::Position;
+use rusty_reversi::random::Random;
+use rusty_reversi::record;
+use rusty_reversi::search::AlphaBeta;
+use rusty_reversi_grpc::proto::analysis_client::AnalysisClient;
+use rusty_reversi_grpc::proto::{Limits, SearchRequest, SolveRequest};
+use rusty_reversi_grpc::{serve, service_from_spec, AnalysisService};
+
+async fn start() -> SocketAddr {
+    start_service(AnalysisService::new(|| AlphaBeta::new(3, WeightedSquares::default()))).await
+}
+
+async fn start_service(service: AnalysisService) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(serve(listener, service));
+    address
+}
+
+async fn connect(address: SocketAddr) -> AnalysisClient<tonic::transport::Channel> {
+    AnalysisClient::connect(format!("http://{}", address)).await.unwrap()
+}
+
+fn request(board: &Board, color: Color, depth: u32) -> SearchRequest {
+    SearchRequest {
+        position: Some(Position::new(board, color)),
+        limits: Some(Limits { depth: Some(depth), ..Limits::default() })
+    }
+}
+
+#[tokio::test]
+async fn searches_answer_like_the_engine() {
+    let mut client = connect(start().await).await;
+    let board = Board::new();
+
+    let evaluation = client.evaluate_position(request(&board, Color::Black, 2)).await.unwrap().into_inner();
+    assert_eq!(evaluation.moves.len(), 4);
+    assert!(evaluation.moves.windows(2).all(|x| x[0].score >= x[1].score));
+
+    let result = client.best_move(request(&board, Color::Black, 2)).await.unwrap().into_inner();
+    let best = result.best.unwrap().to_move().unwrap();
+    assert_eq!(best.0, Color::Black);
+    assert!(board.legal_moves(Color::Black).iter().any(|x| Some(x.position) == best.1));
+    assert_eq!(result.depth, 2);
+
+    let mut stream = client.stream_search_info(request(&board, Color::Black, 3)).await.unwrap().into_inner();
+    let mut depths = Vec::new();
+    while let Some(result) = stream.next().await {
+        depths.push(result.unwrap().depth);
+    }
+    assert_eq!(depths, vec![1, 2, 3]);
+}
+
+#[tokio::test]
+async fn endgames_are_solved_within_the_limit() {
+    let mut client = connect(start().await).await;
+    let solve = |board: &Board| SolveRequest { position: Some(Position::new(board, Color::Black)), win_loss_draw: false };
+
+    let error = client.solve_endgame(solve(&Board::new())).await.unwrap_err();
+    assert_eq!(error.code(), tonic::Code::InvalidArgument);
+    let error = client.stream_search_info(SearchRequest { limits: None, ..request(&Board::new(), Color::Black, 1) }).await.unwrap_err();
+    assert_eq!(error.code(), tonic::Code::InvalidArgument);
+
+    let rows = "XXXXXXXX XXXXXXXX OOOOOOOO XXXXOOOO XXOOXXOO OOOOXXXX OOOOOOOO --O-----";
+    let (board, _) = record::parse_position(&(rows.replace(' ', "") + "X")).unwrap();
+    let solution = client.solve_endgame(solve(&board)).await.unwrap().into_inner();
+    assert_eq!(solution.line.len(), 7);
+    assert!(solution.nodes > 0);
+    let exact = rusty_reversi::endgame::solve(&board, Color::Black);
+    assert_eq!(solution.score, i32::from(exact.score));
+}
+
+#[tokio::test]
+async fn searches_stay_within_the_limits_of_the_service() {
+    let mut service = AnalysisService::new(|| AlphaBeta::new(60, WeightedSquares::default()));
+    service.max_depth = 2;
+    service.max_time = Duration::from_millis(200);
+    let mut client = connect(start_service(service).await).await;
+    let board = Board::new();
+
+    let result = client.best_move(request(&board, Color::Black, u32::MAX)).await.unwrap().into_inner();
+    assert_eq!(result.depth, 2);
+    let evaluation = client.evaluate_position(request(&board, Color::Black, u32::MAX)).await.unwrap().into_inner();
+    assert_eq!(evaluation.moves.len(), 4);
+    let mut stream = client.stream_search_info(request(&board, Color::Black, u32::MAX)).await.unwrap().into_inner();
+    let mut depths = Vec::new();
+    while let Some(result) = stream.next().await {
+        depths.push(result.unwrap().depth);
+    }
+    assert_eq!(depths, vec![1, 2]);
+
+    // searches asked without a limit are given the longest time of the service
+    let start = Instant::now();
+    let unlimited = SearchRequest { limits: None, ..request(&board, Color::Black, 1) };
+    assert!(client.best_move(unlimited).await.unwrap().into_inner().best.is_some());
+    let asked = SearchRequest { limits: Some(Limits { time_ms: Some(u64::MAX), ..Limits::default() }), ..unlimited };
+    assert!(client.best_move(asked).await.unwrap().into_inner().best.is_some());
+    assert!(start.elapsed() < Duration::from_secs(5), "{:?}", start.elapsed());
+}
+
+#[tokio::test]
+async fn requests_share_their_time_between_the_moves() {
+    // random moves until 40 empty cells, where iterations take long
+    let mut random = Random::new(5);
+    let (mut board, mut color) = (Board::new(), Color::Black);
+    while board.empties() > 40 {
+        let moves = board.legal_moves(color);
+        board = moves[random.below(moves.len())].apply(board);
+        color = color.flip();
+    }
+    let mut service = AnalysisService::new(|| AlphaBeta::new(60, WeightedSquares::default()));
+    service.max_time = Duration::from_millis(200);
+    let mut client = connect(start_service(service).await).await;
+
+    let start = Instant::now();
+    let unlimited = SearchRequest { position: Some(Position::new(&board, color)), limits: None };
+    let evaluation = client.evaluate_position(unlimited).await.unwrap().into_inner();
+    assert!(start.elapsed() < Duration::from_millis(500), "{:?}", start.elapsed());
+    assert_eq!(evaluation.moves.len(), board.legal_moves(color).len());
+    assert!(evaluation.moves.len() > 1);
+}
+
+#[test]
+fn services_need_an_engine() {
+    assert!(service_from_spec("alphabeta:depth=2").is_ok());
+    assert!(service_from_spec("nonsense").is_err());
+}
@@ -0,0 +1,123 @@
+//! This module annotates played games with the opinion of an engine: the score of each move
+//! played and the move the engine would have played instead.
+
+use std::io;
+use std::time::Duration;
+
+use engine::{Engine, Limits};
+use eval::Score;
+use game::{Board, Color, Coord, LegalMove};
+use record::{self, GameRecord};
+use search::WIN;
+
+/// The opinion of an engine on a move.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Annotation {
+    /// Score of the move played for the player who played it.
+    pub score: Score,
+    /// The move the engine prefers.
+    pub best_move: Option<Coord>,
+    /// Score of the preferred move.
+    pub best_score: Score,
+    /// Time spent on the move by the player, when known.
+    pub time: Option<Duration>,
+    pub comment: String
+}
+impl Annotation {
+    /// How much worse the move played is than the preferred one.
+    pub fn loss(&self) -> Score {
+        (self.best_score - self.score).max(0)
+    }
+
+    /// Marks a move `??` when it loses at least `blunder` against the preferred move, `?` when
+    /// it loses at least half of it, and leaves other moves unmarked.
+    pub fn mark(&self, blunder: Score) -> &'static str {
+        match self.loss() {
+            loss if loss >= blunder => "??",
+            loss if loss >= (blunder + 1) / 2 => "?",
+            _ => ""
+        }
+    }
+}
+
+/// A game with an annotation for each of its moves.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct AnnotatedGame {
+    pub record: GameRecord,
+    pub annotations: Vec<Annotation>
+}
+
+/// Scores a legal move for the player making it by searching the position it leads to.
+pub fn score_move<E: Engine>(engine: &mut E, board: &Board, legal_move: &LegalMove, limits: &Limits) -> Score {
+    let color = legal_move.color;
+    let next = legal_move.apply(*board);
+    if !next.legal_moves(color.flip()).is_empty() {
+        -engine.best_move(&next, color.flip(), limits).score
+    } else if !next.legal_moves(color).is_empty() {
+        engine.best_move(&next, color, limits).score
+    } else {
+        let diff = next.count(color) as Score - next.count(color.flip()) as Score;
+        diff.signum() * WIN + diff
+    }
+}
+
+/// Annotates every move of a game. Fails with the index of the first illegal move.
+pub fn annotate<E: Engine>(record: &GameRecord, engine: &mut E, limits: &Limits) -> Result<AnnotatedGame, usize> {
+    let positions = record.positions()?;
+    let annotations = record.moves.iter().zip(&positions).map(|(&position, &(board, color))| {
+        let moves = board.legal_moves(color);
+        let scores = moves.iter().map(|x| (x.position, score_move(engine, &board, x, limits))).collect::<Vec<_>>();
+        let score = scores.iter().find(|x| x.0 == position).map(|x| x.1).expect("the move is legal");
+        let best = best(&scores);
+        Annotation { score, best_move: best.map(|x| x.0), best_score: best.map(|x| x.1).unwrap_or(score), time: None, comment: String::new() }
+    }).collect();
+    Ok(AnnotatedGame { record: record.clone(), annotations })
+}
+
+/// Writes an annotated game as a log with a line per move, like `12. White d6 -4, best c5 +30 ??`,
+/// marking the moves losing `blunder` or more as with [`Annotation::mark`](struct.Annotation.html#method.mark).
+/// Fails if one of the moves is illegal.
+pub fn format_log(game: &AnnotatedGame, blunder: Score) -> io::Result<String> {
+    let record = &game.record;
+    let positions = record.positions().map_err(|i| {
+        io::Error::new(io::ErrorKind::InvalidInput, format!("illegal move {}", i + 1))
+    })?;
+    let mut text = String::new();
+    if !record.black.is_empty() || !record.white.is_empty() {
+        text += &format!("{} - {}\n", record.black, record.white);
+    }
+    for (i, (annotation, &(_, color))) in game.annotations.iter().zip(&positions).enumerate() {
+        text += &format!("{:>3}. {} {} {:+}", i + 1, color, record::format_move(record.moves[i]), annotation.score);
+        match annotation.best_move {
+            Some(best) if annotation.loss() > 0 => text += &format!(", best {} {:+}", record::format_move(best), annotation.best_score),
+            _ => text += ", best"
+        }
+        if let Some(time) = annotation.time {
+            text += &format!(", {:.1}s", time.as_secs_f64());
+        }
+        let mark = annotation.mark(blunder);
+        if !mark.is_empty() {
+            text.push(' ');
+            text += mark;
+        }
+        text.push('\n');
+    }
+    let blunders = game.annotations.iter().filter(|x| x.mark(blunder) == "??").count();
+    let (board, _) = positions[positions.len() - 1];
+    let plural = if blunders == 1 { "" } else { "s" };
+    text += &format!("Final score {}-{}, {} blunder{}\n", board.count(Color::Black), board.count(Color::White), blunders, plural);
+    Ok(text)
+}
+
+/// Counts the disks of Black minus the ones of White.
+pub fn disk_difference(board: &Board) -> i32 {
+    board.count(Color::Black) as i32 - board.count(Color::White) as i32
+}
+
+// The first of the best scoring moves.
+fn best(scores: &[(Coord, Score)]) -> Option<(Coord, Score)> {
+    scores.iter().fold(None, |best: Option<(Coord, Score)>, &x| match best {
+        Some(best) if best.1 >= x.1 => Some(best),
+        _ => Some(x)
+    })
+}
@@ -0,0 +1,251 @@
+//! This module serves games over HTTP as a REST API, so that web and mobile frontends can be
+//! built without Rust on the client. Bodies are documents of the
+//! [JSON schema](../formats/json/index.html).
+//!
+//! Games are resources under `/games`, named by a number:
+//!
+//! - `POST /games` creates a game, the names of the `black` and `white` players given in the body
+//!   if any, and answers `201 Created` with its state,
+//! - `GET /games` lists the states of the games,
+//! - `GET /games/{id}` gives the `state` of a game: its `id`, the `black` and `white` players, the
+//!   `position`, the `moves` played as cells, whether it is `over`, the `black_disks` and
+//!   `white_disks`,
+//! - `GET /games/{id}/moves` gives the `legal` moves of the player to move as cells,
+//! - `POST /games/{id}/moves` plays the `cell` of the body, a `move` document or an object with
+//!   only the cell, and answers with the new state,
+//! - `POST /games/{id}/engine` plays the move of the engine and answers with an `engine_move`: the
+//!   `move`, its `score` for the player who made it, the `stats` of the search and the new
+//!   `state`. The stats have the `depth`, `nodes` and `time` in seconds of the search, the
+//!   `nodes_by_ply`, the `tt_hit_rate`, the `cutoff_rate` with the `cutoff_rate_by_move`, and
+//!   the `iterations` with their `depth`, `nodes` and `time`, empty for engines not
+//!   collecting details,
+//! - `GET /games/{id}/history` gives the `game` played so far,
+//! - `DELETE /games/{id}` forgets a game.
+//!
+//! Requests that can't be carried out are answered with an `error` and its `message`: `400` for
+//! invalid bodies, `404` for unknown games and `409` for moves not allowed in the position.
+//! Responses allow any origin, so that pages served elsewhere can use the API.
+
+use std::collections::BTreeMap;
+use std::io::{self, Read};
+use std::net::TcpListener;
+use std::time::{Duration, Instant};
+
+use tiny_http::{self, Header, Method};
+
+use engine::{Engine, Limits, SearchResult};
+use files::invalid;
+use formats::json::{self, Value};
+use game::{Color, Coord};
+use record;
+use session::{GameSession, Player};
+
+// Largest body read, far more than any document of the API.
+const MAX_BODY: u64 = 64 * 1024;
+
+/// An answer of the API.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    /// The HTTP status code.
+    pub status: u16,
+    pub body: Value
+}
+impl Response {
+    fn ok(body: Value) -> Response {
+        Response { status: 200, body }
+    }
+
+    fn error(status: u16, message: &str) -> Response {
+        Response { status, body: json::document("error", vec![("message", Value::String(message.to_string()))]) }
+    }
+}
+
+/// Games played through the API, with the engine asked for moves in all of them.
+pub struct Api<E: Engine> {
+    pub engine: E,
+    pub limits: Limits,
+    games: BTreeMap<u64, GameSession>,
+    next: u64
+}
+impl<E: Engine> Api<E> {
+    pub fn new(engine: E) -> Api<E> {
+        Api { engine, limits: Limits::default(), games: BTreeMap::new(), next: 1 }
+    }
+
+    /// Carries out a request for `path`, its query left out, with the text of its body.
+    pub fn handle(&mut self, method: &str, path: &str, body: &str) -> Response {
+        let path = path.split('?').next().unwrap_or_default();
+        let segments = path.split('/').filter(|x| !x.is_empty()).collect::<Vec<_>>();
+        match (method, segments.as_slice()) {
+            ("GET", ["games"]) => {
+                Response::ok(Value::Array(self.games.iter().map(|(&id, game)| state(id, game)).collect()))
+            }
+            ("POST", ["games"]) => {
+                let document = match parse_body(body) {
+                    Ok(document) => document,
+                    Err(error) => return Response::error(400, &error.to_string())
+                };
+                let name = |key: &str| document.get(key).and_then(Value::as_str).unwrap_or_default().to_string();
+                let game = GameSession::new(Player::Human(name("black")), Player::Human(name("white")));
+                let id = self.next;
+                self.next += 1;
+                let response = Response { status: 201, body: state(id, &game) };
+                self.games.insert(id, game);
+                response
+            }
+            (_, ["games"]) => Response::error(405, "method not allowed"),
+            (method, ["games", id, rest @ ..]) => {
+                let id = match id.parse::<u64>() {
+                    Ok(id) if self.games.contains_key(&id) => id,
+                    _ => return Response::error(404, "no such game")
+                };
+                self.game(method, id, rest, body)
+            }
+            _ => Response::error(404, "no such resource")
+        }
+    }
+
+    // Carries out a request for a game that exists.
+    fn game(&mut self, method: &str, id: u64, rest: &[&str], body: &str) -> Response {
+        match (method, rest) {
+            ("GET", []) => Response::ok(state(id, &self.games[&id])),
+            ("DELETE", []) => {
+                self.games.remove(&id);
+                Response { status: 204, body: Value::Null }
+            }
+            ("GET", ["moves"]) => {
+                let game = &self.games[&id];
+                let legal = game.board().legal_moves(game.color()).into_iter().map(|x| Value::String(record::format_move(x.position))).collect();
+                Response::ok(json::document("legal_moves", vec![("id", Value::Number(id as f64)), ("legal", Value::Array(legal))]))
+            }
+            ("POST", ["moves"]) => {
+                let game = self.games.get_mut(&id).expect("the game exists");
+                let position = match parse_body(body).and_then(|x| cell(&x, game.color())) {
+                    Ok(position) => position,
+                    Err(error) => return Response::error(400, &error.to_string())
+                };
+                match game.play(position, Duration::from_millis(0)) {
+                    Ok(()) => Response::ok(state(id, game)),
+                    Err(_) if game.is_over() => Response::error(409, "the game is over"),
+                    Err(error) => Response::error(409, &error.to_string())
+                }
+            }
+            ("POST", ["engine"]) => {
+                let game = self.games.get_mut(&id).expect("the game exists");
+                if game.is_over() {
+                    return Response::error(409, "the game is over");
+                }
+                let color = game.color();
+                let start = Instant::now();
+                let result = self.engine.best_move(game.board(), color, &self.limits);
+                let position = match result.best {
+                    Some(position) => position,
+                    None => return Response::error(500, "the engine found no move")
+                };
+                if let Err(error) = game.play(position, start.elapsed()) {
+                    return Response::error(500, &format!("the engine played an illegal move: {}", error));
+                }
+                Response::ok(json::document("engine_move", vec![
+                    ("move", json::move_to_json(color, Some(position))),
+                    ("score", Value::Number(f64::from(result.score))),
+                    ("stats", stats(&result)),
+                    ("state", state(id, game))
+                ]))
+            }
+            ("GET", ["history"]) => Response::ok(json::game_to_json(&self.games[&id].record())),
+            (_, []) | (_, ["moves"]) | (_, ["engine"]) | (_, ["history"]) => Response::error(405, "method not allowed"),
+            _ => Response::error(404, "no such resource")
+        }
+    }
+
+    /// Serves requests one at a time until accepting connections fails.
+    pub fn serve(&mut self, listener: TcpListener) -> io::Result<()> {
+        let server = tiny_http::Server::from_listener(listener, None).map_err(|x| io::Error::other(x.to_string()))?;
+        loop {
+            let mut request = server.recv()?;
+            let response = if *request.method() == Method::Options {
+                // preflight of pages served elsewhere
+                Response { status: 204, body: Value::Null }
+            } else {
+                let mut body = String::new();
+                match request.as_reader().take(MAX_BODY).read_to_string(&mut body) {
+                    Ok(_) => self.handle(request.method().as_str(), request.url(), &body),
+                    Err(error) => Response::error(400, &error.to_string())
+                }
+            };
+            let text = if response.body == Value::Null { String::new() } else { response.body.to_string() };
+            let mut answer = tiny_http::Response::from_string(text).with_status_code(response.status);
+            for &(name, value) in &[
+                ("Content-Type", "application/json"),
+                ("Access-Control-Allow-Origin", "*"),
+                ("Access-Control-Allow-Methods", "GET, POST, DELETE, OPTIONS"),
+                ("Access-Control-Allow-Headers", "Content-Type")
+            ] {
+                answer.add_header(Header::from_bytes(name, value).expect("the headers are valid"));
+            }
+            // a client going away only loses its own answer
+            let _ = request.respond(answer);
+        }
+    }
+}
+
+// The state of a game.
+fn state(id: u64, game: &GameSession) -> Value {
+    let board = game.board();
+    json::document("state", vec![
+        ("id", Value::Number(id as f64)),
+        ("black", Value::String(game.black.name().to_string())),
+        ("white", Value::String(game.white.name().to_string())),
+        ("position", json::position_to_json(board, game.color())),
+        ("moves", Value::Array(game.history().iter().map(|x| Value::String(record::format_move(x.position))).collect())),
+        ("over", Value::Bool(game.is_over())),
+        ("black_disks", Value::Number(board.count(Color::Black) as f64)),
+        ("white_disks", Value::Number(board.count(Color::White) as f64))
+    ])
+}
+
+// The details of a search.
+fn stats(result: &SearchResult) -> Value {
+    let numbers = |values: Vec<f64>| Value::Array(values.into_iter().map(Value::Number).collect());
+    let stats = &result.stats;
+    let iterations = stats.iterations.iter().map(|x| Value::Object(vec![
+        ("depth".to_string(), Value::Number(f64::from(x.depth))),
+        ("nodes".to_string(), Value::Number(x.nodes as f64)),
+        ("time".to_string(), Value::Number(x.time.as_secs_f64()))
+    ])).collect();
+    Value::Object(vec![
+        ("depth".to_string(), Value::Number(f64::from(result.depth))),
+        ("nodes".to_string(), Value::Number(result.nodes as f64)),
+        ("time".to_string(), Value::Number(result.time.as_secs_f64())),
+        ("nodes_by_ply".to_string(), numbers(stats.nodes_by_ply.iter().map(|&x| x as f64).collect())),
+        ("tt_hit_rate".to_string(), Value::Number(stats.tt_hit_rate())),
+        ("cutoff_rate".to_string(), Value::Number(stats.cutoff_rate())),
+        ("cutoff_rate_by_move".to_string(), numbers(stats.cutoff_rate_by_move())),
+        ("iterations".to_string(), Value::Array(iterations))
+    ])
+}
+
+// The document of a body, an empty object if there is none. The body is up to `MAX_BODY` long,
+// enough for nesting that would overflow the stack, so the parser's depth limit is relied on.
+fn parse_body(body: &str) -> io::Result<Value> {
+    if body.trim().is_empty() {
+        return Ok(Value::Object(Vec::new()));
+    }
+    let document = Value::parse(body)?;
+    if let Some(kind) = document.get("kind").and_then(Value::as_str) {
+        json::negotiate(&document, kind)?;
+    }
+    Ok(document)
+}
+
+// The cell of a move played by `color`, given as a move document or on its own.
+fn cell(document: &Value, color: Color) -> io::Result<Coord> {
+    if document.get("kind").is_some() {
+        return match json::move_from_json(document)? {
+            (played, _) if played != color => Err(invalid("not the player to move")),
+            (_, Some(position)) => Ok(position),
+            (_, None) => Err(invalid("skips are played for the players"))
+        };
+    }
+    document.get("cell").and_then(Value::as_str).and_then(record::parse_move).ok_or_else(|| invalid("invalid cell"))
+}
@@ -0,0 +1,256 @@
+//! This module plays matches between two players to measure their difference in strength.
+//!
+//! Games start from random openings, and each opening is played twice with the players swapping
+//! colors, so neither gets luckier openings. Players are [engines](../engine/trait.Engine.html),
+//! typically the same search with different settings. Matches may stop early once a
+//! [sequential probability ratio test](../stats/struct.Sprt.html) is conclusive.
+//!
+//! Players may be given a clock each: the search of every move is then limited to a share of the
+//! time left, and a player running out of time forfeits. The games of a match are kept as
+//! records, the first player being named `first` and the second `second`, with the
+//! [details](../engine/struct.SearchStats.html) of the searches of each player.
+
+use std::io;
+use std::time::{Duration, Instant};
+
+use engine::{Engine, Limits, MemoryUsage, SearchResult, SearchStats};
+use game::{Board, Color, Coord};
+use record::{GameRecord, Termination};
+use random::Random;
+use session::Clock;
+use stats::{Decision, MatchResult, Sprt};
+
+/// Settings of a match.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Options {
+    /// Maximum number of games, rounded up to an even number.
+    pub games: u32,
+    /// Number of random moves in the openings.
+    pub opening_plies: usize,
+    /// Seed of the random openings.
+    pub seed: u64,
+    /// Stops the match once the test decides.
+    pub sprt: Option<Sprt>,
+    /// Limits of the searches of both players.
+    pub limits: Limits,
+    /// The clock each player starts a game with, `None` for untimed games.
+    pub clock: Option<Clock>
+}
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            games: 1000,
+            opening_plies: 6,
+            seed: 0,
+            sprt: None,
+            limits: Limits::default(),
+            clock: None
+        }
+    }
+}
+
+/// The outcome of a match.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    /// Results of the first player.
+    pub result: MatchResult,
+    /// Decision of the test, if there was one.
+    pub decision: Option<Decision>,
+    /// The games in the order they were played. Forfeited games have a `termination` tag,
+    /// `time` or `forfeit`, and count as losing all 64 disks.
+    pub games: Vec<GameRecord>,
+    /// Details of the searches of the first and second players, over all the games.
+    pub stats: [SearchStats; 2]
+}
+impl Report {
+    /// Elo difference of the first player to the second, with the half width of its 95%
+    /// confidence interval.
+    pub fn elo(&self) -> (f64, f64) {
+        (self.result.elo(), self.result.elo_error())
+    }
+}
+
+/// Plays a match and returns the results of the first player.
+pub fn run<A: Engine, B: Engine>(options: &Options, first: &mut A, second: &mut B) -> Report {
+    let (mut first, mut second) = (Measured { engine: first, stats: SearchStats::default() }, Measured { engine: second, stats: SearchStats::default() });
+    let mut random = Random::new(options.seed);
+    let mut result = MatchResult::default();
+    let mut decision = None;
+    let mut games = Vec::new();
+    while result.games() < options.games {
+        let (board, color, moves) = opening(options.opening_plies, &mut random);
+        let (first_black, as_black) = game(&board, color, &moves, &mut first, &mut second, options);
+        let (second_black, as_white) = game(&board, color, &moves, &mut second, &mut first, options);
+        games.push(GameRecord { black: "first".to_string(), white: "second".to_string(), ..first_black });
+        games.push(GameRecord { black: "second".to_string(), white: "first".to_string(), ..second_black });
+        for &diff in &[as_black, -as_white] {
+            result.add(&match diff.signum() {
+                1 => MatchResult { wins: 1, draws: 0, losses: 0 },
+                0 => MatchResult { wins: 0, draws: 1, losses: 0 },
+                _ => MatchResult { wins: 0, draws: 0, losses: 1 }
+            });
+        }
+        if let Some(sprt) = options.sprt {
+            decision = Some(sprt.decide(&result));
+            if decision != Some(Decision::Continue) {
+                break;
+            }
+        }
+    }
+    Report { result, decision, games, stats: [first.stats, second.stats] }
+}
+
+// An engine adding up the details of its searches.
+struct Measured<E: Engine> {
+    engine: E,
+    stats: SearchStats
+}
+impl<E: Engine> Engine for Measured<E> {
+    fn best_move(&mut self, board: &Board, color: Color, limits: &Limits) -> SearchResult {
+        let result = self.engine.best_move(board, color, limits);
+        self.stats.add(&result.stats);
+        result
+    }
+
+    fn set_option(&mut self, name: &str, value: &str) -> io::Result<()> {
+        self.engine.set_option(name, value)
+    }
+
+    fn memory_usage(&self) -> MemoryUsage {
+        self.engine.memory_usage()
+    }
+}
+
+/// Plays a game of a match from an opening and returns its record with the final disk
+/// differential for Black, a forfeit counting as losing all 64 disks.
+pub fn game<B: Engine, W: Engine>(board: &Board, color: Color, opening: &[Coord], black: &mut B, white: &mut W, options: &Options) -> (GameRecord, i32) {
+    let (moves, end) = play_game(board, color, black, white, &options.limits, options.clock);
+    let mut record = GameRecord { moves: opening.to_vec(), ..GameRecord::default() };
+    record.moves.extend(moves);
+    let diff = match end {
+        End::Finished(board) => {
+            record.black_disks = Some(board.count(Color::Black) as u8);
+            return (record, board.count(Color::Black) as i32 - board.count(Color::White) as i32);
+        }
+        End::Forfeit(color, _) => if color == Color::Black { -64 } else { 64 }
+    };
+    let termination = if let End::Forfeit(_, true) = end { Termination::Timeout } else { Termination::Forfeit };
+    record.forfeit(if diff > 0 { Color::White } else { Color::Black }, termination);
+    (record, diff)
+}
+
+// How a game ended: with the final board, or by a player forfeiting, on time or not.
+enum End {
+    Finished(Board),
+    Forfeit(Color, bool)
+}
+
+/// Plays a game to the end and returns the final disk differential for Black.
+///
+/// A player choosing an illegal move, or no move when it has one, forfeits the game, which
+/// counts as losing all 64 disks.
+pub fn play<B: Engine, W: Engine>(board: &Board, color: Color, black: &mut B, white: &mut W, limits: &Limits) -> i32 {
+    match play_game(board, color, black, white, limits, None).1 {
+        End::Finished(board) => board.count(Color::Black) as i32 - board.count(Color::White) as i32,
+        End::Forfeit(Color::Black, _) => -64,
+        End::Forfeit(Color::White, _) => 64
+    }
+}
+
+// Plays a game to the end, with the same clock for both players if any, and returns its moves.
+fn play_game<B: Engine, W: Engine>(board: &Board, color: Color, black: &mut B, white: &mut W, limits: &Limits, clock: Option<Clock>) -> (Vec<Coord>, End) {
+    let (mut board, mut color) = (*board, color);
+    let mut clocks = [clock, clock];
+    let mut played = Vec::new();
+    loop {
+        let moves = board.legal_moves(color);
+        if moves.is_empty() {
+            if board.legal_moves(color.flip()).is_empty() {
+                return (played, End::Finished(board));
+            }
+        } else {
+            let clock = &mut clocks[color as usize];
+            let mut limits = *limits;
+            if let Some(clock) = *clock {
+                limits.time = Some(move_time(&board, clock));
+            }
+            let start = Instant::now();
+            let chosen = match color {
+                Color::Black => black.best_move(&board, color, &limits),
+                Color::White => white.best_move(&board, color, &limits)
+            };
+            if let Some(clock) = clock.as_mut() {
+                match clock.remaining.checked_sub(start.elapsed()) {
+                    Some(remaining) => clock.remaining = remaining + clock.increment,
+                    None => return (played, End::Forfeit(color, true))
+                }
+            }
+            match moves.iter().find(|x| Some(x.position) == chosen.best) {
+                Some(legal_move) => {
+                    played.push(legal_move.position);
+                    board = legal_move.apply(board);
+                }
+                None => return (played, End::Forfeit(color, false))
+            }
+        }
+        color = color.flip();
+    }
+}
+
+// The time of a move: an even share of the time left for the moves still to play, with the
+// increment, keeping a margin for the moves after the search.
+fn move_time(board: &Board, clock: Clock) -> Duration {
+    let moves = (board.empties() as u32).div_ceil(2).max(1);
+    (clock.remaining / moves + clock.increment).min(clock.remaining * 9 / 10)
+}
+
+/// Lets an engine play games against itself from random openings, to gather positions for
+/// training. Games stop early if the engine forfeits, and then have no final disk count.
+pub fn self_play<E: Engine>(options: &Options, engine: &mut E) -> Vec<GameRecord> {
+    let mut random = Random::new(options.seed);
+    (0..options.games).map(|_| {
+        let (mut board, mut color, moves) = opening(options.opening_plies, &mut random);
+        let mut record = GameRecord { moves, ..GameRecord::default() };
+        loop {
+            let moves = board.legal_moves(color);
+            if moves.is_empty() {
+                if board.legal_moves(color.flip()).is_empty() {
+                    record.black_disks = Some(board.count(Color::Black) as u8);
+                    return record;
+                }
+            } else {
+                let chosen = engine.best_move(&board, color, &options.limits).best;
+                match moves.iter().find(|x| Some(x.position) == chosen) {
+                    Some(legal_move) => {
+                        record.moves.push(legal_move.position);
+                        board = legal_move.apply(board);
+                    }
+                    None => return record
+                }
+            }
+            color = color.flip();
+        }
+    }).collect()
+}
+
+/// Plays random moves from the start, without ending the game, and returns the position reached
+/// with the player to move and the moves.
+pub fn opening(plies: usize, random: &mut Random) -> (Board, Color, Vec<Coord>) {
+    let (mut board, mut color) = (Board::new(), Color::Black);
+    let mut played = Vec::with_capacity(plies);
+    for _ in 0..plies {
+        let mut moves = board.legal_moves(color);
+        if moves.is_empty() {
+            color = color.flip();
+            moves = board.legal_moves(color);
+        }
+        if moves.is_empty() {
+            break;
+        }
+        let legal_move = moves[random.below(moves.len())];
+        played.push(legal_move.position);
+        board = legal_move.apply(board);
+        color = color.flip();
+    }
+    (board, color, played)
+}
@@ -0,0 +1,83 @@
+//! This module holds a fixed set of positions on which the speed of the crate is measured, so
+//! that changes claiming to make it faster can be checked. They are searched by the `bench`
+//! command, and timed by the [Criterion](https://docs.rs/criterion) benchmarks of move
+//! generation, moves, evaluation and search, run with `cargo bench --features ai`.
+//!
+//! The positions come from random games of 10 to 38 moves, followed by the
+//! [FFO](../ffo/index.html) positions #40 to #42. All have Black to move.
+
+use std::fmt;
+
+use game::{Board, Color};
+use record;
+
+/// Number of empty cells from which positions are in the opening.
+pub const OPENING_EMPTIES: usize = 44;
+
+/// Number of empty cells up to which positions are in the endgame.
+pub const ENDGAME_EMPTIES: usize = 22;
+
+const POSITIONS: &[&str] = &[
+    "---------X-------OXO-O-----XO------OXX---OOX------X------------- X",
+    "---OX-----XXX-----XOO-----XXOOX----OOOO----O-------------------- X",
+    "O-O-----XXOX-----XOXXX--X-OOXX-----OOO------OO--------O--------- X",
+    "O--O-----O-OXX----XOO---OOOOX---OXOXXX--OO---X--O-----X--------X X",
+    "-------------O---XXX-OOX---OXOXO---XXOX---XXOOX--OOOOOOO-----OX- X",
+    "-X--OOO--OXOOO---OOXO-O---OXXO--OOOOO---X-XOX----XOXXX--XO------ X",
+    "O---X---XO-OX--O-XOOOOO-XXXOXOX--XXXOXO----OOOOO--OXX-----XXXX-- X",
+    "-X-------XXXXXX-OXXXXXX--OXOXOOO-XOXXOOX--XOOOX---XXOXXX-----OXO X",
+    "O--OOOOX-OOOOOOXOOXXOOOXOOXOOOXXOOOOOOXX---OOOOX----O--X-------- X",
+    "-OOOOO----OOOOX--OOOOOO-XXXXXOO--XXOOX--OOXOXX----OXXO---OOO--O- X",
+    "--OOO-------XX-OOOOOOXOO-OOOOXOOX-OOOXXO---OOXOO---OOOXO--OOOO-- X"
+];
+
+/// The stage of the game of a position, by its number of empty cells.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Stage {
+    Opening,
+    Midgame,
+    Endgame
+}
+impl Stage {
+    /// All the stages, in the order of the game.
+    pub const ALL: [Stage; 3] = [Stage::Opening, Stage::Midgame, Stage::Endgame];
+
+    /// The stage of a board.
+    pub fn of(board: &Board) -> Stage {
+        match board.empties() {
+            x if x >= OPENING_EMPTIES => Stage::Opening,
+            x if x > ENDGAME_EMPTIES => Stage::Midgame,
+            _ => Stage::Endgame
+        }
+    }
+}
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Stage::Opening => "opening",
+            Stage::Midgame => "midgame",
+            Stage::Endgame => "endgame"
+        })
+    }
+}
+
+/// A position measured on.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Position {
+    pub board: Board,
+    pub color: Color,
+    pub stage: Stage
+}
+
+/// All the positions, from the opening to the endgame.
+pub fn positions() -> Vec<Position> {
+    POSITIONS.iter().map(|text| {
+        let (board, color) = record::parse_position(text).expect("the positions are valid");
+        Position { board, color, stage: Stage::of(&board) }
+    }).collect()
+}
+
+/// The positions of a stage.
+pub fn stage(stage: Stage) -> Vec<Position> {
+    positions().into_iter().filter(|x| x.stage == stage).collect()
+}
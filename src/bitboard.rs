@@ -0,0 +1,141 @@
+//! This module generates moves on bitboards: the disks of each player as a 64-bit mask, one bit
+//! per cell, so that the moves of all cells are found at once by shifting the masks. It is meant
+//! to replace the array-based generation of the [board](../game/struct.Board.html).
+//!
+//! Until it does, [`cross_check`](fn.cross_check.html) compares both generators on a position,
+//! and with the `crosscheck` feature debug builds compare them on every position whose moves are
+//! generated, panicking with the cells they disagree on.
+
+use std::fmt;
+
+use game::{Board, Color, Coord, DIRECTIONS};
+use record;
+
+// The cells outside the first and the last column, where moves to the right and to the left
+// can't come from the other side of the board.
+const NOT_FIRST_COLUMN: u64 = 0xfefe_fefe_fefe_fefe;
+const NOT_LAST_COLUMN: u64 = 0x7f7f_7f7f_7f7f_7f7f;
+
+/// The bit of a cell: `y * 8 + x`.
+pub fn bit(position: Coord) -> u64 {
+    1 << (position.1 * 8 + position.0)
+}
+
+/// The cells of the bits of a mask, in the order of the bits.
+pub fn cells(mask: u64) -> Vec<Coord> {
+    (0..64).filter(|i| mask & (1 << i) != 0).map(|i| ((i % 8) as i8, (i / 8) as i8)).collect()
+}
+
+/// The disks of the player to move and the ones of the opponent.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Bitboards {
+    pub own: u64,
+    pub opponent: u64
+}
+impl Bitboards {
+    /// The disks of a board, `color` being the player to move.
+    pub fn new(board: &Board, color: Color) -> Bitboards {
+        let (mut own, mut opponent) = (0, 0);
+        for (x, column) in board.cells.iter().enumerate() {
+            for (y, &cell) in column.iter().enumerate() {
+                match cell {
+                    Some(found) if found == color => own |= bit((x as i8, y as i8)),
+                    Some(_) => opponent |= bit((x as i8, y as i8)),
+                    None => ()
+                }
+            }
+        }
+        Bitboards { own, opponent }
+    }
+
+    /// The cells where the player can move.
+    pub fn moves(&self) -> u64 {
+        let empty = !(self.own | self.opponent);
+        let mut moves = 0;
+        for &direction in DIRECTIONS.iter() {
+            // the opponent's disks reached from the player's ones, up to 6 in a row
+            let mut run = shift(self.own, direction) & self.opponent;
+            for _ in 0..5 {
+                run |= shift(run, direction) & self.opponent;
+            }
+            moves |= shift(run, direction) & empty;
+        }
+        moves
+    }
+
+    /// The disks flipped by a move of the player, none if the move is illegal.
+    pub fn flips(&self, position: Coord) -> u64 {
+        let start = bit(position);
+        if (self.own | self.opponent) & start != 0 {
+            return 0;
+        }
+        let mut flips = 0;
+        for &direction in DIRECTIONS.iter() {
+            let (mut run, mut cursor) = (0, shift(start, direction));
+            while cursor & self.opponent != 0 {
+                run |= cursor;
+                cursor = shift(cursor, direction);
+            }
+            if cursor & self.own != 0 {
+                flips |= run;
+            }
+        }
+        flips
+    }
+}
+
+// Moves the bits one cell in a direction, dropping the ones leaving the board.
+fn shift(bits: u64, (dx, dy): Coord) -> u64 {
+    let by = dy * 8 + dx;
+    let moved = if by > 0 { bits << by } else { bits >> -by };
+    match dx {
+        1 => moved & NOT_FIRST_COLUMN,
+        -1 => moved & NOT_LAST_COLUMN,
+        _ => moved
+    }
+}
+
+/// A cell on which the generators disagree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mismatch {
+    pub board: Board,
+    pub color: Color,
+    pub position: Coord,
+    /// The disks flipped by the move according to the array-based generator, none if it is
+    /// illegal.
+    pub expected: Vec<Coord>,
+    /// The disks flipped by the move according to the bitboards.
+    pub found: Vec<Coord>,
+    /// Whether the bitboards list the move among the moves of the player.
+    pub listed: bool
+}
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let disks = |cells: &[Coord]| match cells.len() {
+            0 => "nothing".to_string(),
+            _ => cells.iter().map(|&x| record::format_move(x)).collect::<Vec<_>>().join(" ")
+        };
+        write!(f, "the move generators disagree on {} for {} in {}: the arrays flip {}, the bitboards flip {} and {} the move",
+            record::format_move(self.position), self.color, record::format_position(&self.board, self.color),
+            disks(&self.expected), disks(&self.found), if self.listed { "list" } else { "don't list" })
+    }
+}
+
+/// Compares the moves of a player generated on bitboards with the ones of the array-based
+/// generator, cell by cell. Returns the cells they disagree on, none if they agree.
+pub fn cross_check(board: &Board, color: Color) -> Vec<Mismatch> {
+    let bitboards = Bitboards::new(board, color);
+    let moves = bitboards.moves();
+    let mut mismatches = Vec::new();
+    for (x, column) in board.test(color).into_iter().enumerate() {
+        for (y, tested) in column.into_iter().enumerate() {
+            let position = (x as i8, y as i8);
+            let expected = tested.map(|x| x.flipped().into_iter().fold(0, |mask, cell| mask | bit(cell))).unwrap_or(0);
+            let (found, listed) = (bitboards.flips(position), moves & bit(position) != 0);
+            if found != expected || listed != (expected != 0) {
+                mismatches.push(Mismatch { board: *board, color, position, expected: cells(expected), found: cells(found), listed });
+            }
+        }
+    }
+    mismatches
+}
@@ -0,0 +1,682 @@
+//! This module contains the opening book: known good moves of positions early in the game.
+//!
+//! Positions are keyed by their [canonical hash](../zobrist/fn.canonical.html), so a line stored
+//! once is found in all its rotations and reflections. Moves are stored mapped to the canonical
+//! image of the position and mapped back when probing.
+//!
+//! Engines probe the book before searching and only search when the position is not in it.
+//! Books can be grown automatically with [`build`](fn.build.html), or from the games of human
+//! masters with [`import`](fn.import.html). Large books can be probed without loading them with
+//! [`MappedBook`](struct.MappedBook.html), which engines play from like loaded books through
+//! [`OpeningBook`](trait.OpeningBook.html).
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::mem;
+use std::path::Path;
+
+#[cfg(feature = "mmap")]
+use memmap2::Mmap;
+
+use engine::{parse_option, Engine, Limits, MemoryUsage, SearchResult};
+use eval::{Evaluator, Score};
+use files::{invalid, invalid_input};
+use formats::wthor;
+use game::{Board, Color, Coord};
+use random::Random;
+#[cfg(feature = "trace")]
+use record;
+use search::alpha_beta;
+use stats::MatchResult;
+use time::Instant;
+use zobrist;
+
+const MAGIC: &[u8; 6] = b"RRBOOK";
+const VERSION: u16 = 3;
+const HEADER_SIZE: usize = 12;
+const INDEX_ENTRY_SIZE: usize = 16;
+const MOVE_SIZE: usize = 14;
+
+/// A move stored in the book.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BookMove {
+    pub position: Coord,
+    /// The expected final disk differential for the player making the move.
+    pub score: i8,
+    /// Results of the games known to have continued with the move, for the player making it.
+    pub results: MatchResult
+}
+
+/// Controls how much [`Book::choose`](struct.Book.html#method.choose) varies the moves it plays.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Variety {
+    /// Randomness of the choice in disks: a move scoring this much less than another is played
+    /// `e` times less often. Zero always plays the best move.
+    pub temperature: f64,
+    /// Moves played in fewer games are not considered. Moves without results, like the ones
+    /// found by [`build`](fn.build.html), are only considered when this is zero.
+    pub min_games: u32,
+    /// Moves scoring more disks less than the best move are not considered.
+    pub max_deviation: i8
+}
+impl Default for Variety {
+    fn default() -> Variety {
+        Variety {
+            temperature: 0.0,
+            min_games: 0,
+            max_deviation: 0
+        }
+    }
+}
+
+/// A book an engine can play from, loaded or probed from its file.
+pub trait OpeningBook {
+    /// Finds the moves stored for a position, best first. Fails if they can't be read.
+    fn moves(&self, board: &Board, color: Color) -> io::Result<Vec<BookMove>>;
+
+    /// The memory held by the book, in bytes.
+    fn memory_usage(&self) -> usize;
+}
+impl<B: OpeningBook + ?Sized> OpeningBook for Box<B> {
+    fn moves(&self, board: &Board, color: Color) -> io::Result<Vec<BookMove>> {
+        (**self).moves(board, color)
+    }
+
+    fn memory_usage(&self) -> usize {
+        (**self).memory_usage()
+    }
+}
+
+/// Maps positions to scored moves.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Book {
+    entries: HashMap<u64, Vec<BookMove>>
+}
+impl Book {
+    /// Creates an empty book.
+    pub fn new() -> Book {
+        Book::default()
+    }
+
+    /// Number of positions in the book.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// An estimate of the memory taken by the positions and their moves, in bytes.
+    pub fn memory_usage(&self) -> usize {
+        let moves = self.entries.values().map(|x| x.capacity()).sum::<usize>() * mem::size_of::<BookMove>();
+        self.entries.capacity() * (mem::size_of::<u64>() + mem::size_of::<Vec<BookMove>>() + 1) + moves
+    }
+
+    /// Stores a move of a position, replacing it if it is already in the book.
+    pub fn insert(&mut self, board: &Board, color: Color, book_move: BookMove) {
+        let (key, symmetry) = zobrist::canonical(board, color);
+        let stored = BookMove { position: symmetry.apply(book_move.position), ..book_move };
+        let moves = self.entries.entry(key).or_default();
+        match moves.iter_mut().find(|x| x.position == stored.position) {
+            Some(existing) => *existing = stored,
+            None => moves.push(stored)
+        }
+    }
+
+    /// Stores each move of a line played from the starting position with the same score, given
+    /// for Black. Stops at the first illegal move and returns the number of moves stored.
+    pub fn insert_line(&mut self, line: &[Coord], score: i8) -> usize {
+        let mut board = Board::new();
+        let mut color = Color::Black;
+        for (i, &position) in line.iter().enumerate() {
+            if board.legal_moves(color).is_empty() {
+                color = color.flip();
+            }
+            let legal_move = match board.legal_moves(color).into_iter().find(|x| x.position == position) {
+                Some(legal_move) => legal_move,
+                None => return i
+            };
+            let score = if color == Color::Black { score } else { score.saturating_neg() };
+            self.insert(&board, color, BookMove { position, score, results: MatchResult::default() });
+            board = legal_move.apply(board);
+            color = color.flip();
+        }
+        line.len()
+    }
+
+    /// Finds the moves stored for a position, best first.
+    pub fn probe(&self, board: &Board, color: Color) -> Vec<BookMove> {
+        let (key, symmetry) = zobrist::canonical(board, color);
+        let inverse = symmetry.inverse();
+        let mut moves = self.entries.get(&key).map(|moves| {
+            moves.iter().map(|x| BookMove { position: inverse.apply(x.position), ..*x }).collect::<Vec<_>>()
+        }).unwrap_or_default();
+        moves.sort_by_key(|x| -i32::from(x.score));
+        moves
+    }
+
+    /// The best move stored for a position.
+    pub fn best(&self, board: &Board, color: Color) -> Option<BookMove> {
+        self.probe(board, color).into_iter().next()
+    }
+
+    /// Picks a random move for a position among the moves close to the best one, so that games
+    /// don't always follow the same line.
+    ///
+    /// Returns `None` when no stored move satisfies the criteria, and the engine has to search.
+    pub fn choose(&self, board: &Board, color: Color, variety: &Variety, random: &mut Random) -> Option<BookMove> {
+        choose(self.probe(board, color), variety, random)
+    }
+
+    // Scores the moves leading to positions in the book by the best move stored there, and
+    // returns the best score of the position.
+    fn propagate(&mut self, board: &Board, color: Color, visited: &mut HashSet<u64>) -> Option<i8> {
+        let moves = self.probe(board, color);
+        if moves.is_empty() {
+            return None;
+        }
+        if !visited.insert(zobrist::canonical(board, color).0) {
+            return Some(moves[0].score);
+        }
+        let legal_moves = board.legal_moves(color);
+        let mut best = None;
+        for book_move in moves {
+            let score = match legal_moves.iter().find(|x| x.position == book_move.position) {
+                Some(legal_move) => {
+                    let after = legal_move.apply(*board);
+                    let opponent = if after.legal_moves(color.flip()).is_empty() { color } else { color.flip() };
+                    match self.propagate(&after, opponent, visited) {
+                        Some(score) if opponent == color => score,
+                        Some(score) => score.saturating_neg(),
+                        None => book_move.score
+                    }
+                }
+                None => book_move.score
+            };
+            self.insert(board, color, BookMove { score, ..book_move });
+            best = best.max(Some(score));
+        }
+        best
+    }
+
+    /// Reads a book written by [`write`](#method.write).
+    ///
+    /// The format starts with the magic bytes `RRBOOK`, a 16 bit version and the 32 bit number
+    /// of positions. An index follows with the 64 bit key of each position in increasing order
+    /// and the 64 bit offset of its moves from the start of the file. The moves of a position are
+    /// its 8 bit number of moves, then for each move its cell as `y * 8 + x` and its score, one
+    /// byte each, followed by the 32 bit number of wins, draws and losses. All integers are
+    /// little endian. The index lets [`MappedBook`](struct.MappedBook.html) find positions
+    /// without reading the whole file.
+    ///
+    /// Books of the older versions, without index, are also accepted. The first version doesn't
+    /// have results either.
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Book> {
+        let mut header = [0; HEADER_SIZE];
+        reader.read_exact(&mut header)?;
+        let (version, count) = parse_header(&header)?;
+        let mut keys = Vec::with_capacity(count.min(1 << 20) as usize);
+        let mut offset = HEADER_SIZE as u64;
+        if version >= 3 {
+            for i in 0..u64::from(count) {
+                let mut entry = [0; INDEX_ENTRY_SIZE];
+                reader.read_exact(&mut entry)?;
+                keys.push(read_u64(&entry[..8]));
+                if i == 0 {
+                    offset = read_u64(&entry[8..]);
+                } else if keys[keys.len() - 2] >= keys[keys.len() - 1] {
+                    return Err(invalid("positions out of order"));
+                }
+            }
+            if offset != HEADER_SIZE as u64 + u64::from(count) * INDEX_ENTRY_SIZE as u64 {
+                return Err(invalid("moves don't follow the index"));
+            }
+        }
+        let move_size = if version == 1 { 2 } else { MOVE_SIZE };
+        let mut book = Book::new();
+        for i in 0..count as usize {
+            // older versions store the key with the moves
+            let key = match keys.get(i) {
+                Some(&key) => key,
+                None => {
+                    let mut key = [0; 8];
+                    reader.read_exact(&mut key)?;
+                    u64::from_le_bytes(key)
+                }
+            };
+            let mut move_count = [0; 1];
+            reader.read_exact(&mut move_count)?;
+            let mut bytes = vec![0; move_count[0] as usize * move_size];
+            reader.read_exact(&mut bytes)?;
+            let moves = bytes.chunks(move_size).map(decode_move).collect::<io::Result<Vec<_>>>()?;
+            book.entries.insert(key, moves);
+        }
+        Ok(book)
+    }
+
+    /// Writes the book in the format accepted by [`read`](#method.read).
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&(self.entries.len() as u32).to_le_bytes())?;
+        let mut keys = self.entries.keys().collect::<Vec<_>>();
+        keys.sort();
+        let mut offset = (HEADER_SIZE + keys.len() * INDEX_ENTRY_SIZE) as u64;
+        for key in &keys {
+            writer.write_all(&key.to_le_bytes())?;
+            writer.write_all(&offset.to_le_bytes())?;
+            offset += 1 + (self.entries[key].len().min(255) * MOVE_SIZE) as u64;
+        }
+        for key in keys {
+            let moves = &self.entries[key];
+            writer.write_all(&[moves.len().min(255) as u8])?;
+            for book_move in moves.iter().take(255) {
+                let (x, y) = book_move.position;
+                writer.write_all(&[(y * 8 + x) as u8, book_move.score as u8])?;
+                let results = &book_move.results;
+                for count in &[results.wins, results.draws, results.losses] {
+                    writer.write_all(&count.to_le_bytes())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a book from a file.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Book> {
+        Book::read(BufReader::new(File::open(path)?))
+    }
+
+    /// Writes the book to a file, replacing its contents.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()
+    }
+}
+
+impl OpeningBook for Book {
+    fn moves(&self, board: &Board, color: Color) -> io::Result<Vec<BookMove>> {
+        Ok(self.probe(board, color))
+    }
+
+    fn memory_usage(&self) -> usize {
+        Book::memory_usage(self)
+    }
+}
+
+/// An engine playing a move of a book in the positions of the book, and searching in the other
+/// ones or when the book can't be read.
+///
+/// Its options are `book_temperature`, `book_min_games` and `book_max_deviation`, setting the
+/// [`Variety`](struct.Variety.html) of its book moves, besides the options of the engine
+/// searching.
+pub struct Booked<E: Engine, B: OpeningBook = Book> {
+    pub book: B,
+    pub engine: E,
+    /// How the book moves are chosen, the best one by default.
+    pub variety: Variety,
+    pub random: Random
+}
+impl<E: Engine, B: OpeningBook> Booked<E, B> {
+    /// Plays the best moves of the book, the book moves being chosen with `Random::new(0)` once
+    /// a [`variety`](#structfield.variety) is set.
+    pub fn new(book: B, engine: E) -> Booked<E, B> {
+        Booked { book, engine, variety: Variety::default(), random: Random::new(0) }
+    }
+}
+impl<E: Engine, B: OpeningBook> Engine for Booked<E, B> {
+    /// Plays a move of the book [chosen](struct.Book.html#method.choose) with the variety of the
+    /// engine, scored by its disk differential, if it is legal.
+    fn best_move(&mut self, board: &Board, color: Color, limits: &Limits) -> SearchResult {
+        let start = Instant::now();
+        let legal = board.legal_moves(color);
+        let moves = self.book.moves(board, color).unwrap_or_else(|_error| {
+            #[cfg(feature = "trace")]
+            tracing::warn!(error = %_error, "unreadable book moves");
+            Vec::new()
+        });
+        match choose(moves, &self.variety, &mut self.random).filter(|x| legal.iter().any(|m| m.position == x.position)) {
+            Some(book_move) => {
+                #[cfg(feature = "trace")]
+                tracing::debug!(position = record::format_move(book_move.position).as_str(), score = book_move.score, "book move");
+                SearchResult::new(Score::from(book_move.score), 0, 0, 0, vec![book_move.position], start.elapsed())
+            }
+            None => self.engine.best_move(board, color, limits)
+        }
+    }
+
+    /// Sets an option of the book moves, or of the engine searching.
+    fn set_option(&mut self, name: &str, value: &str) -> io::Result<()> {
+        match name {
+            "book_temperature" => {
+                let temperature: f64 = parse_option(name, value)?;
+                if !(temperature.is_finite() && temperature >= 0.0) {
+                    return Err(invalid_input(&format!("invalid value for {}: {}", name, value)));
+                }
+                self.variety.temperature = temperature;
+            }
+            "book_min_games" => self.variety.min_games = parse_option(name, value)?,
+            "book_max_deviation" => self.variety.max_deviation = parse_option(name, value)?,
+            _ => return self.engine.set_option(name, value)
+        }
+        Ok(())
+    }
+
+    /// The book with the memory of the engine searching.
+    fn memory_usage(&self) -> MemoryUsage {
+        let mut usage = MemoryUsage { book: OpeningBook::memory_usage(&self.book), ..MemoryUsage::default() };
+        usage.add(&self.engine.memory_usage());
+        usage
+    }
+}
+
+/// Controls how [`build`](fn.build.html) grows a book.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BuildOptions {
+    /// Number of positions to expand.
+    pub positions: usize,
+    /// Depth of the searches choosing the moves of the self-play games.
+    pub depth: u32,
+    /// Positions deeper than this many moves from the start are not expanded.
+    pub max_ply: usize,
+    /// Cost of going one move deeper, in disks. The higher it is, the wider the book, the lower,
+    /// the deeper along the best lines.
+    pub ply_cost: i32
+}
+impl Default for BuildOptions {
+    fn default() -> BuildOptions {
+        BuildOptions {
+            positions: 100,
+            depth: 4,
+            max_ply: 20,
+            ply_cost: 4
+        }
+    }
+}
+
+/// Grows a book by self-play and returns the number of positions expanded.
+///
+/// The book is expanded with the drop-out method: the next position expanded is the one
+/// reachable from the start at the lowest cost, where the cost of a line is the number of disks
+/// its moves lose compared to the best moves, plus [`ply_cost`](struct.BuildOptions.html) for
+/// each move. This way the book is deep along the best lines, but also covers the reasonable
+/// alternatives an opponent may choose.
+///
+/// Expanding a position scores each of its moves by playing the game to the end with searches of
+/// the given depth on both sides, the final disk differential being the score. Finally scores are
+/// propagated from the deepest positions towards the start, so every move is scored by the best
+/// continuation known in the book.
+pub fn build<E: Evaluator>(book: &mut Book, options: &BuildOptions, evaluator: &E) -> usize {
+    // positions waiting for expansion, cheapest first
+    let mut queue = BinaryHeap::new();
+    let mut nodes = vec![(Board::new(), Color::Black, 0)];
+    queue.push((Reverse(0), 0));
+    let mut expanded = HashSet::new();
+    while let Some((Reverse(cost), node)) = queue.pop() {
+        if expanded.len() >= options.positions {
+            break;
+        }
+        let (board, color, ply) = nodes[node];
+        let moves = board.legal_moves(color);
+        if moves.is_empty() {
+            // skip, unless the game has ended
+            if !board.legal_moves(color.flip()).is_empty() {
+                nodes.push((board, color.flip(), ply));
+                queue.push((Reverse(cost), nodes.len() - 1));
+            }
+            continue;
+        }
+        if ply >= options.max_ply || !expanded.insert(zobrist::canonical(&board, color).0) {
+            continue;
+        }
+        let scores = moves.iter().map(|legal_move| {
+            let after = legal_move.apply(board);
+            let final_board = play_out(&after, color.flip(), options.depth, evaluator);
+            let diff = final_board.count(color) as i32 - final_board.count(color.flip()) as i32;
+            diff as i8
+        }).collect::<Vec<_>>();
+        let best = scores.iter().cloned().max().unwrap_or(0);
+        for (legal_move, &score) in moves.iter().zip(scores.iter()) {
+            book.insert(&board, color, BookMove { position: legal_move.position, score, results: MatchResult::default() });
+            nodes.push((legal_move.apply(board), color.flip(), ply + 1));
+            let child_cost = cost + i32::from(best) - i32::from(score) + options.ply_cost;
+            queue.push((Reverse(child_cost), nodes.len() - 1));
+        }
+    }
+    book.propagate(&Board::new(), Color::Black, &mut HashSet::new());
+    expanded.len()
+}
+
+// Plays the game to the end, both players choosing their moves by searching.
+fn play_out<E: Evaluator>(board: &Board, color: Color, depth: u32, evaluator: &E) -> Board {
+    let (mut board, mut color) = (*board, color);
+    loop {
+        let moves = board.legal_moves(color);
+        if moves.is_empty() {
+            if board.legal_moves(color.flip()).is_empty() {
+                return board;
+            }
+        } else {
+            let best = alpha_beta(&board, color, depth, evaluator).best;
+            let legal_move = moves.iter().find(|x| Some(x.position) == best).unwrap_or(&moves[0]);
+            board = legal_move.apply(board);
+        }
+        color = color.flip();
+    }
+}
+
+/// Adds the first `max_ply` moves of games from a WTHOR database to a book and returns the number
+/// of games used.
+///
+/// Each move is scored by the average final disk differential of the games it was played in, and
+/// its results count how often the player making it won. Moves already in the book are merged
+/// with the imported ones. A game is only followed up to its first illegal move.
+pub fn import(book: &mut Book, games: &[wthor::Game], max_ply: usize) -> usize {
+    // disk differentials summed over the games, by canonical position and move
+    let mut found: HashMap<(u64, Coord), (i64, MatchResult)> = HashMap::new();
+    let mut used = 0;
+    for game in games {
+        let black_diff = 2 * i64::from(game.black_disks) - 64;
+        let (mut board, mut color) = (Board::new(), Color::Black);
+        let mut plies = 0;
+        for &position in game.moves.iter().take(max_ply) {
+            if board.legal_moves(color).is_empty() {
+                color = color.flip();
+            }
+            let legal_move = match board.legal_moves(color).into_iter().find(|x| x.position == position) {
+                Some(legal_move) => legal_move,
+                None => break
+            };
+            let diff = if color == Color::Black { black_diff } else { -black_diff };
+            let (key, symmetry) = zobrist::canonical(&board, color);
+            let entry = found.entry((key, symmetry.apply(position))).or_default();
+            entry.0 += diff;
+            entry.1.add(&match diff.signum() {
+                1 => MatchResult { wins: 1, draws: 0, losses: 0 },
+                0 => MatchResult { wins: 0, draws: 1, losses: 0 },
+                _ => MatchResult { wins: 0, draws: 0, losses: 1 }
+            });
+            board = legal_move.apply(board);
+            color = color.flip();
+            plies += 1;
+        }
+        if plies > 0 {
+            used += 1;
+        }
+    }
+    for ((key, position), (mut sum, mut results)) in found {
+        let moves = book.entries.entry(key).or_default();
+        if let Some(index) = moves.iter().position(|x| x.position == position) {
+            let existing = moves.remove(index);
+            sum += i64::from(existing.score) * i64::from(existing.results.games());
+            results.add(&existing.results);
+        }
+        let games = i64::from(results.games().max(1));
+        let score = ((2 * sum + sum.signum() * games) / (2 * games)).clamp(-64, 64) as i8;
+        moves.push(BookMove { position, score, results });
+    }
+    used
+}
+
+/// A book probed directly from its serialized form, like a memory-mapped file.
+///
+/// Positions are found by a binary search in the index of the file, so only the pages holding
+/// the searched keys and moves are ever read. This makes books too large for the memory usable.
+/// Only the latest version of the format can be probed this way.
+pub struct MappedBook<D: AsRef<[u8]>> {
+    data: D,
+    count: usize
+}
+impl<D: AsRef<[u8]>> MappedBook<D> {
+    /// Checks the header and the size of the index.
+    pub fn new(data: D) -> io::Result<MappedBook<D>> {
+        let bytes = data.as_ref();
+        if bytes.len() < HEADER_SIZE {
+            return Err(invalid("not an opening book"));
+        }
+        let (version, count) = parse_header(&bytes[..HEADER_SIZE])?;
+        if version != VERSION {
+            return Err(invalid("only the latest opening book version can be mapped"));
+        }
+        let index = usize::try_from(count).ok().and_then(|x| x.checked_mul(INDEX_ENTRY_SIZE)).and_then(|x| x.checked_add(HEADER_SIZE));
+        if index.is_none_or(|x| bytes.len() < x) {
+            return Err(invalid("opening book index is truncated"));
+        }
+        Ok(MappedBook { data, count: count as usize })
+    }
+
+    /// Number of positions in the book.
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Finds the moves stored for a position, best first. Fails if the moves are corrupt.
+    pub fn probe(&self, board: &Board, color: Color) -> io::Result<Vec<BookMove>> {
+        let (key, symmetry) = zobrist::canonical(board, color);
+        let bytes = self.data.as_ref();
+        let entry = |i: usize| &bytes[HEADER_SIZE + i * INDEX_ENTRY_SIZE..HEADER_SIZE + (i + 1) * INDEX_ENTRY_SIZE];
+        let (mut low, mut high) = (0, self.count);
+        while low < high {
+            let middle = (low + high) / 2;
+            let found = read_u64(&entry(middle)[..8]);
+            if found < key {
+                low = middle + 1;
+            } else if found > key {
+                high = middle;
+            } else {
+                // offsets past the end of the file may not even fit in an usize
+                let outside = || invalid("moves outside of the file");
+                let offset = usize::try_from(read_u64(&entry(middle)[8..])).map_err(|_| outside())?;
+                let move_count = *bytes.get(offset).ok_or_else(outside)? as usize;
+                let moves = bytes.get(offset + 1..offset + 1 + move_count * MOVE_SIZE).ok_or_else(outside)?;
+                let inverse = symmetry.inverse();
+                let mut moves = moves.chunks(MOVE_SIZE).map(|x| {
+                    decode_move(x).map(|x| BookMove { position: inverse.apply(x.position), ..x })
+                }).collect::<io::Result<Vec<_>>>()?;
+                moves.sort_by_key(|x| -i32::from(x.score));
+                return Ok(moves);
+            }
+        }
+        Ok(Vec::new())
+    }
+
+    /// The best move stored for a position.
+    pub fn best(&self, board: &Board, color: Color) -> io::Result<Option<BookMove>> {
+        Ok(self.probe(board, color)?.into_iter().next())
+    }
+
+    /// Picks a random move like [`Book::choose`](struct.Book.html#method.choose).
+    pub fn choose(&self, board: &Board, color: Color, variety: &Variety, random: &mut Random) -> io::Result<Option<BookMove>> {
+        Ok(choose(self.probe(board, color)?, variety, random))
+    }
+}
+
+impl<D: AsRef<[u8]>> OpeningBook for MappedBook<D> {
+    fn moves(&self, board: &Board, color: Color) -> io::Result<Vec<BookMove>> {
+        self.probe(board, color)
+    }
+
+    /// Nothing, the pages read being the operating system's to keep or drop.
+    fn memory_usage(&self) -> usize {
+        0
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl MappedBook<Mmap> {
+    /// Maps a book file to memory.
+    ///
+    /// The file must not be modified while it is mapped.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<MappedBook<Mmap>> {
+        let file = File::open(path)?;
+        // safe as long as no one modifies the file, as documented
+        MappedBook::new(unsafe { Mmap::map(&file)? })
+    }
+}
+
+// Picks a move among the moves of a position, sorted best first.
+fn choose(moves: Vec<BookMove>, variety: &Variety, random: &mut Random) -> Option<BookMove> {
+    let candidates = moves.into_iter()
+        .filter(|x| x.results.games() >= variety.min_games)
+        .collect::<Vec<_>>();
+    let best = i32::from(candidates.first()?.score);
+    let candidates = candidates.into_iter()
+        .take_while(|x| best - i32::from(x.score) <= i32::from(variety.max_deviation))
+        .collect::<Vec<_>>();
+    if variety.temperature <= 0.0 {
+        return candidates.first().cloned();
+    }
+    let weights = candidates.iter()
+        .map(|x| ((i32::from(x.score) - best) as f64 / variety.temperature).exp())
+        .collect::<Vec<_>>();
+    let mut threshold = random.next_f64() * weights.iter().sum::<f64>();
+    for (candidate, weight) in candidates.iter().zip(weights) {
+        if threshold < weight {
+            return Some(*candidate);
+        }
+        threshold -= weight;
+    }
+    candidates.last().cloned()
+}
+
+// Checks the magic bytes and returns the version and the number of positions.
+fn parse_header(header: &[u8]) -> io::Result<(u16, u32)> {
+    if &header[..6] != MAGIC {
+        return Err(invalid("not an opening book"));
+    }
+    let version = u16::from_le_bytes([header[6], header[7]]);
+    if version == 0 || version > VERSION {
+        return Err(invalid("unsupported opening book version"));
+    }
+    Ok((version, u32::from_le_bytes([header[8], header[9], header[10], header[11]])))
+}
+
+// Decodes a move of the current format, or of the first one without results.
+fn decode_move(bytes: &[u8]) -> io::Result<BookMove> {
+    if bytes[0] >= 64 {
+        return Err(invalid("move outside of the board"));
+    }
+    let position = ((bytes[0] % 8) as i8, (bytes[0] / 8) as i8);
+    let count = |i: usize| u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+    let results = if bytes.len() < MOVE_SIZE {
+        MatchResult::default()
+    } else {
+        MatchResult { wins: count(2), draws: count(6), losses: count(10) }
+    };
+    Ok(BookMove { position, score: bytes[1] as i8, results })
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut word = [0; 8];
+    word.copy_from_slice(bytes);
+    u64::from_le_bytes(word)
+}
@@ -0,0 +1,373 @@
+//! This module plays games by messages in the channels of chat services, a game in each channel,
+//! so that their members can play the engine or each other. The [Discord](../discord/index.html)
+//! and [Telegram](../telegram/index.html) bots pass it the messages they receive.
+//!
+//! Members write in the channel, with the [prefix](struct.Bot.html#structfield.prefix) of the
+//! commands, `!` by default:
+//!
+//! - `!challenge` to play the engine, or `!challenge @member` to play another member, the one
+//!   challenging playing Black. The game played in the channel has to be over first,
+//! - a cell like `d3` to play it on their turn,
+//! - `!board` to see the board again and `!resign` to give up,
+//! - `!scores` for the games won, lost and drawn by the players of the channel.
+//!
+//! The bot answers with the board drawn as a PNG image, the last move and the legal moves
+//! marked, and the disks of both players. The engine plays right after its opponent.
+//!
+//! The game and the scores of a channel can be [saved](struct.Bot.html#method.save) after each
+//! message as a `channel` document of the [JSON schema](../formats/json/index.html), with the
+//! `game` so far, the `black` and `white` members, each an object with the `id` and `name` of
+//! the member or `null` for the engine, the color that `resigned` if any, and the `scores` as an
+//! object of players with their `wins`, `losses` and `draws`.
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use engine::{Engine, Limits};
+use files::{self, invalid};
+use formats::json::{self, Value};
+use game::Color;
+use record;
+use render::{self, Style};
+use session::{GameSession, Player};
+
+/// A member of a chat service.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct User {
+    pub id: String,
+    pub name: String
+}
+
+/// An answer of the bot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reply {
+    pub text: String,
+    /// The board as a PNG image.
+    pub image: Option<Vec<u8>>
+}
+
+/// The games of a player finished in a channel.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Score {
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32
+}
+
+// The game of a channel, the engine playing the side without a member.
+struct Table {
+    session: GameSession,
+    members: [Option<User>; 2],
+    resigned: Option<Color>
+}
+impl Table {
+    fn is_over(&self) -> bool {
+        self.resigned.is_some() || self.session.is_over()
+    }
+
+    fn color_of(&self, user: &User) -> Option<Color> {
+        [Color::Black, Color::White].iter().cloned().find(|&x| self.members[x as usize].as_ref().is_some_and(|member| member.id == user.id))
+    }
+
+    // The winner once the game is over, `None` for a draw.
+    fn winner(&self) -> Option<Color> {
+        if let Some(color) = self.resigned {
+            return Some(color.flip());
+        }
+        let board = self.session.board();
+        let (black, white) = (board.count(Color::Black), board.count(Color::White));
+        match black.cmp(&white) {
+            Ordering::Greater => Some(Color::Black),
+            Ordering::Less => Some(Color::White),
+            Ordering::Equal => None
+        }
+    }
+}
+
+/// The games of the channels the bot is in.
+pub struct Bot<E: Engine> {
+    pub engine: E,
+    pub limits: Limits,
+    /// How the boards are drawn, with the last move and the legal moves marked.
+    pub style: Style,
+    /// The character starting the commands.
+    pub prefix: char,
+    name: String,
+    tables: HashMap<String, Table>,
+    scores: HashMap<String, BTreeMap<String, Score>>
+}
+impl<E: Engine> Bot<E> {
+    /// A bot whose engine plays under a name, searching to its depth.
+    pub fn new(engine: E, name: &str) -> Bot<E> {
+        Bot {
+            engine,
+            limits: Limits::default(),
+            style: Style::default(),
+            prefix: '!',
+            name: name.to_string(),
+            tables: HashMap::new(),
+            scores: HashMap::new()
+        }
+    }
+
+    /// The game of a channel, the last one while no other is started.
+    pub fn session(&self, channel: &str) -> Option<&GameSession> {
+        self.tables.get(channel).map(|x| &x.session)
+    }
+
+    /// Whether the game of a channel is being played.
+    pub fn is_playing(&self, channel: &str) -> bool {
+        self.tables.get(channel).is_some_and(|x| !x.is_over())
+    }
+
+    /// The scores of the players of a channel by name.
+    pub fn scores(&self, channel: &str) -> Option<&BTreeMap<String, Score>> {
+        self.scores.get(channel)
+    }
+
+    /// Answers a message of a member in a channel, mentioning some members besides the bot.
+    /// Messages for someone else are left unanswered.
+    pub fn handle(&mut self, channel: &str, author: &User, content: &str, mentions: &[User]) -> Option<Reply> {
+        let words = content.split_whitespace().collect::<Vec<_>>();
+        let command = words.first()?.strip_prefix(self.prefix);
+        match (command, words.len()) {
+            (Some("challenge"), _) => Some(self.challenge(channel, author, mentions.first())),
+            (Some("board"), 1) => Some(match self.tables.get(channel) {
+                Some(table) => self.show(table, String::new()),
+                None => text(&format!("No game was played in this channel yet, start one with {}challenge.", self.prefix))
+            }),
+            (Some("resign"), 1) => self.resign(channel, author),
+            (Some("scores"), 1) => Some(self.standings(channel)),
+            (None, 1) => self.play(channel, author, words[0]),
+            _ => None
+        }
+    }
+
+    /// Writes the game and the scores of a channel to `<channel>.json` in a directory, replacing
+    /// the previous save atomically. Channels are named by letters, digits and `-`.
+    pub fn save<P: AsRef<Path>>(&self, directory: P, channel: &str) -> io::Result<()> {
+        let path = directory.as_ref().join(file_name(channel)?);
+        let (game, members, resigned) = match self.tables.get(channel) {
+            Some(table) => {
+                let resigned = table.resigned.map(|x| Value::String(x.to_string().to_lowercase())).unwrap_or(Value::Null);
+                (json::game_to_json(&table.session.record()), [member_to_json(&table.members[0]), member_to_json(&table.members[1])], resigned)
+            }
+            None => (Value::Null, [Value::Null, Value::Null], Value::Null)
+        };
+        let count = |x: u32| Value::Number(f64::from(x));
+        let scores = self.scores.get(channel).into_iter().flatten().map(|(name, x)| {
+            (name.clone(), Value::Object(vec![("wins".to_string(), count(x.wins)), ("losses".to_string(), count(x.losses)), ("draws".to_string(), count(x.draws))]))
+        }).collect();
+        let [black, white] = members;
+        let document = json::document("channel", vec![("game", game), ("black", black), ("white", white), ("resigned", resigned), ("scores", Value::Object(scores))]);
+        files::save_atomically(path, |writer| write!(writer, "{}", document))
+    }
+
+    /// Reads back the channels saved in a directory, replacing their games and scores. Returns
+    /// the channels read.
+    pub fn load<P: AsRef<Path>>(&mut self, directory: P) -> io::Result<Vec<String>> {
+        let mut channels = Vec::new();
+        for entry in fs::read_dir(directory)? {
+            let path = entry?.path();
+            let channel = match (path.file_stem().and_then(|x| x.to_str()), path.extension()) {
+                (Some(channel), Some(extension)) if extension == "json" && file_name(channel).is_ok() => channel.to_string(),
+                _ => continue
+            };
+            let mut text = String::new();
+            File::open(&path)?.read_to_string(&mut text)?;
+            self.restore(&channel, &Value::parse(&text)?).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+            channels.push(channel);
+        }
+        channels.sort();
+        Ok(channels)
+    }
+
+    fn restore(&mut self, channel: &str, document: &Value) -> io::Result<()> {
+        json::negotiate(document, "channel")?;
+        let members = [member_from_json(document.get("black"))?, member_from_json(document.get("white"))?];
+        let resigned = match document.get("resigned").and_then(Value::as_str) {
+            None => None,
+            Some("black") => Some(Color::Black),
+            Some("white") => Some(Color::White),
+            Some(_) => return Err(invalid("invalid color"))
+        };
+        let mut scores = BTreeMap::new();
+        if let Some(Value::Object(players)) = document.get("scores") {
+            for (name, score) in players {
+                let count = |key: &str| score.get(key).and_then(Value::as_f64).filter(|x| x.fract() == 0.0 && *x >= 0.0).map(|x| x as u32).ok_or_else(|| invalid("invalid score"));
+                scores.insert(name.clone(), Score { wins: count("wins")?, losses: count("losses")?, draws: count("draws")? });
+            }
+        }
+        match document.get("game") {
+            None | Some(&Value::Null) => {
+                self.tables.remove(channel);
+            }
+            Some(game) => {
+                let record = json::game_from_json(game)?;
+                let player = |member: &Option<User>, name: String| match *member {
+                    Some(_) => Player::Human(name),
+                    None => Player::Engine { name, options: Vec::new() }
+                };
+                let mut session = GameSession::new(player(&members[0], record.black), player(&members[1], record.white));
+                for (i, &position) in record.moves.iter().enumerate() {
+                    session.play(position, Duration::from_secs(0)).map_err(|_| invalid(&format!("illegal move {}", i + 1)))?;
+                }
+                self.tables.insert(channel.to_string(), Table { session, members, resigned });
+            }
+        }
+        if scores.is_empty() {
+            self.scores.remove(channel);
+        } else {
+            self.scores.insert(channel.to_string(), scores);
+        }
+        Ok(())
+    }
+
+    fn challenge(&mut self, channel: &str, author: &User, opponent: Option<&User>) -> Reply {
+        if self.is_playing(channel) {
+            return text("A game is being played in this channel, finish it first.");
+        }
+        if opponent.is_some_and(|x| x.id == author.id) {
+            return text("You can't challenge yourself.");
+        }
+        let white = match opponent {
+            Some(user) => Player::Human(user.name.clone()),
+            None => Player::Engine { name: self.name.clone(), options: Vec::new() }
+        };
+        let table = Table {
+            session: GameSession::new(Player::Human(author.name.clone()), white),
+            members: [Some(author.clone()), opponent.cloned()],
+            resigned: None
+        };
+        let reply = self.show(&table, format!("{} (Black) against {} (White).", author.name, table.session.white.name()));
+        self.tables.insert(channel.to_string(), table);
+        reply
+    }
+
+    fn play(&mut self, channel: &str, author: &User, cell: &str) -> Option<Reply> {
+        let position = record::parse_move(cell)?;
+        let table = self.tables.get_mut(channel).filter(|x| !x.is_over())?;
+        let color = table.color_of(author)?;
+        if color != table.session.color() {
+            return Some(text(&format!("It is the turn of {}.", table.session.player(color.flip()).name())));
+        }
+        if table.session.play(position, Duration::from_secs(0)).is_err() {
+            return Some(text(&format!("{} isn't a legal move.", record::format_move(position))));
+        }
+        let mut played = vec![format!("{} plays {}.", author.name, record::format_move(position))];
+        played.extend(passed(&table.session));
+        while !table.is_over() && table.members[table.session.color() as usize].is_none() {
+            let color = table.session.color();
+            // engines run by other programs or scripts may fail to move, which forfeits the game
+            match self.engine.best_move(table.session.board(), color, &self.limits).best {
+                Some(best) if table.session.play(best, Duration::from_secs(0)).is_ok() => {
+                    played.push(format!("{} plays {}.", table.session.player(color).name(), record::format_move(best)));
+                    played.extend(passed(&table.session));
+                }
+                _ => {
+                    table.resigned = Some(color);
+                    played.push(format!("{} fails to move and forfeits.", table.session.player(color).name()));
+                }
+            }
+        }
+        if self.tables[channel].is_over() {
+            self.finish(channel);
+        }
+        Some(self.show(&self.tables[channel], played.join(" ")))
+    }
+
+    fn resign(&mut self, channel: &str, author: &User) -> Option<Reply> {
+        let table = self.tables.get_mut(channel).filter(|x| !x.is_over())?;
+        table.resigned = Some(table.color_of(author)?);
+        self.finish(channel);
+        Some(self.show(&self.tables[channel], format!("{} resigns.", author.name)))
+    }
+
+    // Counts the game of a channel just finished in the scores of its players.
+    fn finish(&mut self, channel: &str) {
+        let table = &self.tables[channel];
+        let winner = table.winner();
+        let scores = self.scores.entry(channel.to_string()).or_default();
+        for &color in &[Color::Black, Color::White] {
+            let score = scores.entry(table.session.player(color).name().to_string()).or_default();
+            match winner {
+                Some(found) if found == color => score.wins += 1,
+                Some(_) => score.losses += 1,
+                None => score.draws += 1
+            }
+        }
+    }
+
+    // The board of a game after some text, with the disks of both players.
+    fn show(&self, table: &Table, text: String) -> Reply {
+        let session = &table.session;
+        let board = session.board();
+        let style = Style { hints: Some(session.color()).filter(|_| !table.is_over()), last_move: session.last_move(), ..self.style };
+        let mut image = Vec::new();
+        render::render(board, &style).write_png(&mut image).expect("images are written to memory");
+        let disks = format!(
+            "{} (Black) {} - {} {} (White).",
+            session.black.name(),
+            board.count(Color::Black),
+            board.count(Color::White),
+            session.white.name()
+        );
+        let state = if !table.is_over() {
+            format!("{} to move.", session.player(session.color()).name())
+        } else {
+            match table.winner() {
+                Some(color) => format!("{} wins.", session.player(color).name()),
+                None => "The game is drawn.".to_string()
+            }
+        };
+        let lines = [text, disks, state];
+        Reply { text: lines.iter().filter(|x| !x.is_empty()).cloned().collect::<Vec<_>>().join("\n"), image: Some(image) }
+    }
+
+    fn standings(&self, channel: &str) -> Reply {
+        match self.scores.get(channel).filter(|x| !x.is_empty()) {
+            Some(scores) => text(&scores.iter().map(|(name, x)| format!("{}: {} won, {} lost, {} drawn", name, x.wins, x.losses, x.draws)).collect::<Vec<_>>().join("\n")),
+            None => text("No game was finished in this channel yet.")
+        }
+    }
+}
+
+// The pass following a move, if any.
+fn passed(session: &GameSession) -> Option<String> {
+    let last = session.last_move()?;
+    Some(format!("{} passes.", session.player(last.color.flip()).name())).filter(|_| !session.is_over() && session.color() == last.color)
+}
+
+fn member_to_json(member: &Option<User>) -> Value {
+    match *member {
+        Some(ref user) => Value::Object(vec![("id".to_string(), Value::String(user.id.clone())), ("name".to_string(), Value::String(user.name.clone()))]),
+        None => Value::Null
+    }
+}
+
+// A member of a game, `None` for the engine.
+fn member_from_json(value: Option<&Value>) -> io::Result<Option<User>> {
+    match value {
+        None | Some(&Value::Null) => Ok(None),
+        Some(user) => {
+            let field = |key: &str| user.get(key).and_then(Value::as_str).map(str::to_string).ok_or_else(|| invalid("invalid member"));
+            Ok(Some(User { id: field("id")?, name: field("name")? }))
+        }
+    }
+}
+
+// The file of a channel, refusing names that could lead out of the directory.
+fn file_name(channel: &str) -> io::Result<String> {
+    if channel.is_empty() || !channel.chars().all(|x| x.is_ascii_alphanumeric() || x == '-') {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("invalid channel: {}", channel)));
+    }
+    Ok(format!("{}.json", channel))
+}
+
+fn text(text: &str) -> Reply {
+    Reply { text: text.to_string(), image: None }
+}
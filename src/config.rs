@@ -0,0 +1,232 @@
+//! This module reads the configuration file of the command line program, so that engine
+//! settings, the theme and key bindings don't have to be given again on every run.
+//!
+//! The file is `~/.config/rusty_reversi/config.toml`, or `rusty_reversi/config.toml` under
+//! `$XDG_CONFIG_HOME` when it is set. It is written in the subset of [TOML](https://toml.io)
+//! made of tables, comments and `key = value` lines with strings, numbers and booleans:
+//!
+//! ```toml
+//! [engine]
+//! name = "alphabeta"      # as given to --engine
+//! book = "/home/me/othello.book"
+//! book_temperature = 2.0  # the variety of the book moves, like --book-temperature
+//! book_min_games = 10
+//! book_max_deviation = 4
+//! depth = 10              # the limits of --depth, --nodes and --time
+//! time = 5.0
+//! memory = 512           # the cap of --memory, in MiB
+//!
+//! [engine.options]        # as given to --option, passed to the engine as they are
+//! hash = 64
+//! endgame = 18
+//!
+//! [ui]
+//! theme = "high-contrast"
+//! unicode = true
+//! accessible = false
+//!
+//! [keys]                  # actions of the full-screen interface
+//! up = "k"
+//! down = "j"
+//! undo = "backspace"
+//! ```
+//!
+//! Every setting is optional, and flags given on the command line take precedence.
+
+use std::convert::TryFrom;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use files::invalid;
+use terminal::Theme;
+
+// The settings with a fixed name.
+const SETTINGS: &[&str] = &["engine.name", "engine.book", "engine.book_temperature", "engine.book_min_games", "engine.book_max_deviation", "engine.depth", "engine.nodes", "engine.time", "engine.memory", "ui.theme", "ui.unicode", "ui.accessible"];
+
+/// A value of the configuration file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool)
+}
+impl Value {
+    /// The value as written in an engine option or a flag.
+    pub fn to_text(&self) -> String {
+        match *self {
+            Value::String(ref text) => text.clone(),
+            Value::Integer(number) => number.to_string(),
+            Value::Float(number) => number.to_string(),
+            Value::Boolean(value) => value.to_string()
+        }
+    }
+}
+
+/// Defaults of the engine.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct EngineConfig {
+    /// The engine, with its options as given to `--engine`.
+    pub name: Option<String>,
+    /// Options given to the engine before the ones of the command line.
+    pub options: Vec<(String, String)>,
+    /// Opening book played before searching.
+    pub book: Option<PathBuf>,
+    /// Settings of the [variety](../book/struct.Variety.html) of the book moves.
+    pub book_temperature: Option<f64>,
+    pub book_min_games: Option<u32>,
+    pub book_max_deviation: Option<i8>,
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    /// Time limit, given in seconds.
+    pub time: Option<Duration>,
+    /// Memory cap of the engines in MiB.
+    pub memory: Option<usize>
+}
+
+/// The settings of the configuration file.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Config {
+    pub engine: EngineConfig,
+    pub theme: Option<Theme>,
+    pub unicode: bool,
+    pub accessible: bool,
+    /// Keys of the actions of the full-screen interface, by action name.
+    pub keys: Vec<(String, String)>
+}
+impl Config {
+    /// Where the configuration file is looked for, if the home directory is known.
+    pub fn path() -> Option<PathBuf> {
+        let directory = match env::var_os("XDG_CONFIG_HOME").filter(|x| !x.is_empty()) {
+            Some(directory) => PathBuf::from(directory),
+            None => PathBuf::from(env::var_os("HOME").filter(|x| !x.is_empty())?).join(".config")
+        };
+        Some(directory.join("rusty_reversi").join("config.toml"))
+    }
+
+    /// Reads the configuration file at its usual [path](#method.path), the defaults if there is
+    /// none.
+    pub fn load_default() -> io::Result<Config> {
+        match Config::path() {
+            Some(ref path) if path.is_file() => Config::load(path),
+            _ => Ok(Config::default())
+        }
+    }
+
+    /// Reads a configuration file.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Config> {
+        let path = path.as_ref();
+        Config::parse(&fs::read_to_string(path)?).map_err(|e| invalid(&format!("{}: {}", path.display(), e)))
+    }
+
+    /// Parses a configuration. Unknown settings and values of the wrong type are errors, so that
+    /// typos don't go unnoticed.
+    pub fn parse(text: &str) -> io::Result<Config> {
+        let mut config = Config::default();
+        for (number, table, key, value) in parse_toml(text)? {
+            let wrong = || invalid(&format!("line {}: invalid value for {}.{}", number, table, key));
+            match (table.as_str(), key.as_str(), &value) {
+                ("engine", "name", Value::String(name)) => config.engine.name = Some(name.clone()),
+                ("engine", "book", Value::String(path)) => config.engine.book = Some(PathBuf::from(path)),
+                ("engine", "book_temperature", &Value::Integer(temperature)) if temperature >= 0 => config.engine.book_temperature = Some(temperature as f64),
+                ("engine", "book_temperature", &Value::Float(temperature)) if temperature.is_finite() && temperature >= 0.0 => config.engine.book_temperature = Some(temperature),
+                ("engine", "book_min_games", &Value::Integer(games)) => config.engine.book_min_games = Some(u32::try_from(games).map_err(|_| wrong())?),
+                ("engine", "book_max_deviation", &Value::Integer(disks)) => config.engine.book_max_deviation = Some(i8::try_from(disks).map_err(|_| wrong())?),
+                ("engine", "depth", &Value::Integer(depth)) => config.engine.depth = Some(u32::try_from(depth).map_err(|_| wrong())?),
+                ("engine", "nodes", &Value::Integer(nodes)) => config.engine.nodes = Some(u64::try_from(nodes).map_err(|_| wrong())?),
+                ("engine", "time", &Value::Integer(seconds)) => config.engine.time = Some(Duration::try_from_secs_f64(seconds as f64).map_err(|_| wrong())?),
+                ("engine", "time", &Value::Float(seconds)) => config.engine.time = Some(Duration::try_from_secs_f64(seconds).map_err(|_| wrong())?),
+                ("engine", "memory", &Value::Integer(mib)) => config.engine.memory = Some(usize::try_from(mib).map_err(|_| wrong())?),
+                ("engine.options", _, _) => config.engine.options.push((key.clone(), value.to_text())),
+                ("ui", "theme", Value::String(name)) => config.theme = Some(name.parse().map_err(|_| wrong())?),
+                ("ui", "unicode", &Value::Boolean(unicode)) => config.unicode = unicode,
+                ("ui", "accessible", &Value::Boolean(accessible)) => config.accessible = accessible,
+                ("keys", _, Value::String(name)) => config.keys.push((key.clone(), name.clone())),
+                _ if table == "keys" || SETTINGS.contains(&format!("{}.{}", table, key).as_str()) => return Err(wrong()),
+                _ => return Err(invalid(&format!("line {}: unknown setting {}.{}", number, table, key)))
+            }
+        }
+        Ok(config)
+    }
+}
+
+// Reads the values of a TOML document with their line, table and key.
+fn parse_toml(text: &str) -> io::Result<Vec<(usize, String, String, Value)>> {
+    let mut values = Vec::new();
+    let mut table = String::new();
+    for (i, line) in text.lines().enumerate() {
+        let number = i + 1;
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[') {
+            let name = name.strip_suffix(']').ok_or_else(|| invalid(&format!("line {}: unterminated table name", number)))?;
+            table = name.split('.').map(|x| x.trim()).collect::<Vec<_>>().join(".");
+            continue;
+        }
+        let (key, value) = line.split_once('=').ok_or_else(|| invalid(&format!("line {}: expected key = value", number)))?;
+        let key = key.trim().trim_matches('"').to_string();
+        let value = parse_value(value.trim()).ok_or_else(|| invalid(&format!("line {}: invalid value for {}", number, key)))?;
+        values.push((number, table.clone(), key, value));
+    }
+    Ok(values)
+}
+
+// The line without its comment, `#` in strings being kept.
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match (quote, c) {
+            (Some('"'), '\\') if !escaped => {
+                escaped = true;
+                continue;
+            }
+            (Some(q), c) if c == q && !escaped => quote = None,
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '#') => return &line[..i],
+            _ => {}
+        }
+        escaped = false;
+    }
+    line
+}
+
+fn parse_value(text: &str) -> Option<Value> {
+    if let Some(literal) = text.strip_prefix('\'') {
+        return literal.strip_suffix('\'').map(|x| Value::String(x.to_string()));
+    }
+    if let Some(quoted) = text.strip_prefix('"') {
+        let quoted = quoted.strip_suffix('"')?;
+        let mut string = String::new();
+        let mut chars = quoted.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                string.push(c);
+                continue;
+            }
+            string.push(match chars.next()? {
+                'n' => '\n',
+                't' => '\t',
+                '"' => '"',
+                '\\' => '\\',
+                _ => return None
+            });
+        }
+        return Some(Value::String(string));
+    }
+    match text {
+        "true" => return Some(Value::Boolean(true)),
+        "false" => return Some(Value::Boolean(false)),
+        _ => {}
+    }
+    let digits = text.replace('_', "");
+    if let Ok(number) = digits.parse() {
+        return Some(Value::Integer(number));
+    }
+    digits.parse().ok().filter(|x: &f64| x.is_finite()).map(Value::Float)
+}
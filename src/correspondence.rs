@@ -0,0 +1,99 @@
+//! This module writes games played by correspondence as tokens, short strings safe in URLs, so
+//! that two people can play over email or chat. Each sends the other the token of the game with
+//! the move they propose; the other [decodes](fn.decode.html) it, which replays and checks the
+//! whole game, and [replies](struct.Correspondence.html#method.reply) with the token of their
+//! own move.
+//!
+//! A token is the version `1`, then a character for each move from the starting position in the
+//! URL-safe base64 alphabet, `A` for a1 to `_` for h8 row by row, then a check character
+//! catching most mistyped tokens. The last move is the one proposed. Like the
+//! [transcripts](../record/index.html), tokens leave the skips out.
+
+use std::io;
+
+use files::invalid;
+use game::{Board, Color, Coord};
+use record::GameRecord;
+
+/// Version of the tokens written and read.
+pub const VERSION: char = '1';
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// A game received by correspondence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Correspondence {
+    /// The moves before the one proposed.
+    pub history: Vec<Coord>,
+    pub proposed: Coord,
+    /// The position after the proposed move.
+    pub board: Board,
+    /// The player to move next, the one who proposed the move when the opponent skips.
+    pub color: Color
+}
+impl Correspondence {
+    /// All the moves, the proposed one last.
+    pub fn moves(&self) -> Vec<Coord> {
+        let mut moves = self.history.clone();
+        moves.push(self.proposed);
+        moves
+    }
+
+    /// Whether neither player can move after the proposed move.
+    pub fn is_over(&self) -> bool {
+        self.board.legal_moves(Color::Black).is_empty() && self.board.legal_moves(Color::White).is_empty()
+    }
+
+    /// The token proposing a move in answer. Fails if the move isn't legal.
+    pub fn reply(&self, position: Coord) -> io::Result<String> {
+        if self.board.test_move(self.color, position).is_err() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "illegal move"));
+        }
+        Ok(write(&[&self.moves()[..], &[position]].concat()))
+    }
+}
+
+/// The token of a game so far and a move proposed. Fails with the index of the first illegal
+/// move, the proposed one being the last.
+pub fn encode(history: &[Coord], proposed: Coord) -> Result<String, usize> {
+    let moves = [history, &[proposed]].concat();
+    GameRecord { moves: moves.clone(), ..GameRecord::default() }.positions()?;
+    Ok(write(&moves))
+}
+
+/// Reads a token, replaying the whole game to check that every move is legal.
+pub fn decode(token: &str) -> io::Result<Correspondence> {
+    let token = token.trim();
+    let mut characters = token.chars();
+    match characters.next() {
+        Some(VERSION) => {}
+        Some(version) if version.is_ascii_digit() => return Err(invalid(&format!("unsupported token version {}", version))),
+        _ => return Err(invalid("not a token"))
+    }
+    let mut indexes = characters.map(|x| ALPHABET.iter().position(|&y| char::from(y) == x).ok_or_else(|| invalid(&format!("invalid character {}", x)))).collect::<io::Result<Vec<_>>>()?;
+    let check = indexes.pop().ok_or_else(|| invalid("missing check character"))?;
+    if indexes.is_empty() {
+        return Err(invalid("missing move"));
+    }
+    if checksum(&indexes) != check {
+        return Err(invalid("mistyped token"));
+    }
+    let mut moves = indexes.iter().map(|&i| ((i % 8) as i8, 7 - (i / 8) as i8)).collect::<Vec<_>>();
+    let record = GameRecord { moves: moves.clone(), ..GameRecord::default() };
+    let positions = record.positions().map_err(|i| invalid(&format!("illegal move {}", i + 1)))?;
+    let (board, color) = *positions.last().expect("the last position is always there");
+    let proposed = moves.pop().expect("tokens have a move");
+    Ok(Correspondence { history: moves, proposed, board, color })
+}
+
+fn write(moves: &[Coord]) -> String {
+    let indexes = moves.iter().map(|&(x, y)| (7 - y) as usize * 8 + x as usize).collect::<Vec<_>>();
+    let mut token = VERSION.to_string();
+    token.extend(indexes.iter().chain(Some(&checksum(&indexes))).map(|&i| char::from(ALPHABET[i])));
+    token
+}
+
+// Weighs the cells by odd numbers, so that changing any single character changes the sum.
+fn checksum(indexes: &[usize]) -> usize {
+    indexes.iter().enumerate().map(|(i, &x)| (2 * i + 1) * x).sum::<usize>() % 64
+}
@@ -0,0 +1,360 @@
+//! This module keeps games in an SQLite database, with their players, the positions they went
+//! through and the analysis of their moves, so that large collections can be searched.
+//!
+//! Games come from [WTHOR](../formats/wthor/index.html) and [GGF](../formats/ggf/index.html)
+//! archives or from games played against the engine, all as [records](../record/struct.GameRecord.html).
+//! Each position of a game is stored with its [canonical hash](../zobrist/fn.canonical.html) and
+//! the move played from it, mapped to the canonical image, so that transpositions and symmetric
+//! games meet in the same rows.
+//!
+//! The tables are:
+//!
+//! * `players`: `id` and unique `name`.
+//! * `games`: `id`, `black` and `white` players, `event`, `date`, `moves` as a transcript like
+//!   `f5d6c3`, `black_disks` and `theoretical_disks` when known, and the `source` of the game.
+//! * `tags`: other information of the games, as `game`, `name` and `value`.
+//! * `positions`: `game`, `ply` from 0, the canonical `hash` of the position as a signed 64 bit
+//!   integer and the `move` played, as `8 * y + x` in the canonical image, null after the last.
+//! * `analysis`: `game`, `ply`, the `score` of the move played, the `best_move` as in
+//!   `positions` but not mapped, its `best_score` and a `comment`.
+//!
+//! The version of the schema is kept in `PRAGMA user_version`.
+//!
+//! Games are [found](struct.Database.html#method.games) by player, result and year, or among the
+//! ones [reaching a position](struct.Database.html#method.games_reaching) in any orientation,
+//! and the moves played from a position are [summed up](struct.Database.html#method.continuations)
+//! with their results, to explore openings or find where games went wrong.
+
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Transaction};
+
+use analysis::Annotation;
+use game::{Board, Color, Coord};
+use record::{self, GameRecord};
+use stats::MatchResult;
+use zobrist;
+
+const VERSION: i32 = 1;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS players (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE
+);
+CREATE TABLE IF NOT EXISTS games (
+    id INTEGER PRIMARY KEY,
+    black INTEGER NOT NULL REFERENCES players,
+    white INTEGER NOT NULL REFERENCES players,
+    event TEXT NOT NULL,
+    date TEXT NOT NULL,
+    moves TEXT NOT NULL,
+    black_disks INTEGER,
+    theoretical_disks INTEGER,
+    source TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS tags (
+    game INTEGER NOT NULL REFERENCES games,
+    name TEXT NOT NULL,
+    value TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS tags_game ON tags (game);
+CREATE TABLE IF NOT EXISTS positions (
+    game INTEGER NOT NULL REFERENCES games,
+    ply INTEGER NOT NULL,
+    hash INTEGER NOT NULL,
+    move INTEGER,
+    PRIMARY KEY (game, ply)
+);
+CREATE INDEX IF NOT EXISTS positions_hash ON positions (hash);
+CREATE TABLE IF NOT EXISTS analysis (
+    game INTEGER NOT NULL REFERENCES games,
+    ply INTEGER NOT NULL,
+    score INTEGER NOT NULL,
+    best_move INTEGER,
+    best_score INTEGER NOT NULL,
+    comment TEXT NOT NULL,
+    PRIMARY KEY (game, ply)
+);
+";
+
+/// The result of a game, by its final disk count.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Winner {
+    Black,
+    White,
+    Draw
+}
+impl FromStr for Winner {
+    type Err = ();
+
+    /// Parses `black`, `white` or `draw`.
+    fn from_str(s: &str) -> Result<Winner, ()> {
+        match s {
+            "black" => Ok(Winner::Black),
+            "white" => Ok(Winner::White),
+            "draw" => Ok(Winner::Draw),
+            _ => Err(())
+        }
+    }
+}
+
+/// Which games a query looks at, all of them by default.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Filter {
+    /// Games where a player played either color.
+    pub player: Option<String>,
+    /// Games won by a color or drawn. Games without a final disk count are left out.
+    pub winner: Option<Winner>,
+    /// Games played in a year.
+    pub year: Option<u16>
+}
+impl Filter {
+    // The conditions on the games `g` of the filter with their parameters.
+    fn conditions(&self) -> (String, Vec<Value>) {
+        let mut conditions = vec!["1".to_string()];
+        let mut values = Vec::new();
+        if let Some(ref player) = self.player {
+            conditions.push("EXISTS (SELECT 1 FROM players p WHERE p.name = ? AND p.id IN (g.black, g.white))".to_string());
+            values.push(Value::Text(player.clone()));
+        }
+        if let Some(winner) = self.winner {
+            conditions.push(match winner {
+                Winner::Black => "g.black_disks > 32",
+                Winner::White => "g.black_disks < 32",
+                Winner::Draw => "g.black_disks = 32"
+            }.to_string());
+        }
+        if let Some(year) = self.year {
+            conditions.push("substr(g.date, 1, 4) = ?".to_string());
+            values.push(Value::Text(format!("{:04}", year)));
+        }
+        (conditions.join(" AND "), values)
+    }
+}
+
+/// A move played from a position, with the games continuing with it.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Continuation {
+    pub position: Coord,
+    pub games: u32,
+    /// Results for the player making the move, of the games with a final disk count.
+    pub results: MatchResult,
+    /// Average final disk differential for the player making the move.
+    pub disks: f64
+}
+
+/// A game database.
+pub struct Database {
+    connection: Connection
+}
+impl Database {
+    /// Opens a database file, creating it if it doesn't exist.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Database> {
+        Database::setup(Connection::open(path).map_err(error)?)
+    }
+
+    /// Opens a database kept in memory, lost when dropped.
+    pub fn open_in_memory() -> io::Result<Database> {
+        Database::setup(Connection::open_in_memory().map_err(error)?)
+    }
+
+    fn setup(connection: Connection) -> io::Result<Database> {
+        let version = connection.query_row("PRAGMA user_version", [], |row| row.get::<_, i32>(0)).map_err(error)?;
+        if version != 0 && version != VERSION {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "unsupported database version"));
+        }
+        connection.execute_batch(SCHEMA).map_err(error)?;
+        connection.pragma_update(None, "user_version", VERSION).map_err(error)?;
+        Ok(Database { connection })
+    }
+
+    /// Stores a game with its positions and returns its id. Fails on games with illegal moves.
+    pub fn add_game(&mut self, game: &GameRecord, source: &str) -> io::Result<i64> {
+        let transaction = self.connection.transaction().map_err(error)?;
+        let id = insert_game(&transaction, game, source)?;
+        transaction.commit().map_err(error)?;
+        Ok(id)
+    }
+
+    /// Stores games in a single transaction, much faster than one by one for large archives.
+    /// Games with illegal moves are skipped, the number of games stored is returned.
+    pub fn add_games(&mut self, games: &[GameRecord], source: &str) -> io::Result<usize> {
+        let transaction = self.connection.transaction().map_err(error)?;
+        let mut added = 0;
+        for game in games {
+            match insert_game(&transaction, game, source) {
+                Ok(_) => added += 1,
+                Err(ref e) if e.kind() == io::ErrorKind::InvalidData => {}
+                Err(e) => return Err(e)
+            }
+        }
+        transaction.commit().map_err(error)?;
+        Ok(added)
+    }
+
+    /// Stores the analysis of the moves of a game, one annotation per move in order, replacing
+    /// any earlier analysis.
+    pub fn add_analysis(&mut self, game: i64, annotations: &[Annotation]) -> io::Result<()> {
+        let transaction = self.connection.transaction().map_err(error)?;
+        transaction.execute("DELETE FROM analysis WHERE game = ?1", [game]).map_err(error)?;
+        for (ply, annotation) in annotations.iter().enumerate() {
+            transaction.execute(
+                "INSERT INTO analysis (game, ply, score, best_move, best_score, comment) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![game, ply as i64, annotation.score, annotation.best_move.map(index), annotation.best_score, annotation.comment]
+            ).map_err(error)?;
+        }
+        transaction.commit().map_err(error)
+    }
+
+    /// The game with an id, if there is one.
+    pub fn game(&self, id: i64) -> io::Result<Option<GameRecord>> {
+        let row = self.connection.query_row(
+            "SELECT b.name, w.name, g.event, g.date, g.moves, g.black_disks, g.theoretical_disks
+             FROM games g JOIN players b ON b.id = g.black JOIN players w ON w.id = g.white WHERE g.id = ?1",
+            [id],
+            |row| Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, Option<u8>>(5)?,
+                row.get::<_, Option<u8>>(6)?
+            ))
+        ).optional().map_err(error)?;
+        let (black, white, event, date, moves, black_disks, theoretical_disks) = match row {
+            Some(row) => row,
+            None => return Ok(None)
+        };
+        let moves = record::parse_transcript(&moves).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid moves"))?;
+        let mut statement = self.connection.prepare("SELECT name, value FROM tags WHERE game = ?1 ORDER BY rowid").map_err(error)?;
+        let tags = statement.query_map([id], |row| Ok((row.get(0)?, row.get(1)?))).map_err(error)?
+            .collect::<Result<Vec<_>, _>>().map_err(error)?;
+        Ok(Some(GameRecord { black, white, event, date, moves, black_disks, theoretical_disks, tags }))
+    }
+
+    /// The analysis stored for a game, in the order of its moves.
+    pub fn analysis(&self, game: i64) -> io::Result<Vec<Annotation>> {
+        let mut statement = self.connection.prepare("SELECT score, best_move, best_score, comment FROM analysis WHERE game = ?1 ORDER BY ply").map_err(error)?;
+        let annotations = statement.query_map([game], |row| Ok(Annotation {
+            score: row.get(0)?,
+            best_move: row.get::<_, Option<i64>>(1)?.map(coord),
+            best_score: row.get(2)?,
+            time: None,
+            comment: row.get(3)?
+        })).map_err(error)?;
+        annotations.collect::<Result<Vec<_>, _>>().map_err(error)
+    }
+
+    /// The ids of the games passing a filter, in the order they were stored.
+    pub fn games(&self, filter: &Filter) -> io::Result<Vec<i64>> {
+        let (conditions, values) = filter.conditions();
+        self.ids(&format!("SELECT g.id FROM games g WHERE {} ORDER BY g.id", conditions), values)
+    }
+
+    /// The ids of the games passing a filter which reach a position, or one of its rotations
+    /// and reflections.
+    pub fn games_reaching(&self, board: &Board, color: Color, filter: &Filter) -> io::Result<Vec<i64>> {
+        let (conditions, mut values) = filter.conditions();
+        values.insert(0, Value::Integer(zobrist::canonical(board, color).0 as i64));
+        let query = format!("SELECT g.id FROM games g WHERE EXISTS (SELECT 1 FROM positions p WHERE p.game = g.id AND p.hash = ?) AND {} ORDER BY g.id", conditions);
+        self.ids(&query, values)
+    }
+
+    /// The moves played from a position in the games passing a filter, in any orientation but
+    /// given for this one, the most played first.
+    pub fn continuations(&self, board: &Board, color: Color, filter: &Filter) -> io::Result<Vec<Continuation>> {
+        let (key, symmetry) = zobrist::canonical(board, color);
+        let inverse = symmetry.inverse();
+        let (conditions, mut values) = filter.conditions();
+        values.insert(0, Value::Integer(key as i64));
+        let query = format!("SELECT p.move, g.black_disks FROM positions p JOIN games g ON g.id = p.game WHERE p.hash = ? AND p.move IS NOT NULL AND {}", conditions);
+        let mut statement = self.connection.prepare(&query).map_err(error)?;
+        let rows = statement.query_map(params_from_iter(values), |row| Ok((row.get::<_, i64>(0)?, row.get::<_, Option<u8>>(1)?))).map_err(error)?;
+        let mut continuations: Vec<(Continuation, i64)> = Vec::new();
+        for row in rows {
+            let (index, black_disks) = row.map_err(error)?;
+            let position = inverse.apply(coord(index));
+            let i = match continuations.iter().position(|x| x.0.position == position) {
+                Some(i) => i,
+                None => {
+                    let empty = Continuation { position, games: 0, results: MatchResult::default(), disks: 0.0 };
+                    continuations.push((empty, 0));
+                    continuations.len() - 1
+                }
+            };
+            let (continuation, total) = &mut continuations[i];
+            continuation.games += 1;
+            if let Some(black_disks) = black_disks {
+                let difference = (2 * i64::from(black_disks) - 64) * if color == Color::Black { 1 } else { -1 };
+                continuation.results.add(&match difference.signum() {
+                    1 => MatchResult { wins: 1, draws: 0, losses: 0 },
+                    0 => MatchResult { wins: 0, draws: 1, losses: 0 },
+                    _ => MatchResult { wins: 0, draws: 0, losses: 1 }
+                });
+                *total += difference;
+            }
+        }
+        let mut continuations = continuations.into_iter().map(|(continuation, total)| {
+            let known = continuation.results.games();
+            Continuation { disks: if known == 0 { 0.0 } else { total as f64 / known as f64 }, ..continuation }
+        }).collect::<Vec<_>>();
+        continuations.sort_by_key(|x| std::cmp::Reverse(x.games));
+        Ok(continuations)
+    }
+
+    fn ids(&self, query: &str, values: Vec<Value>) -> io::Result<Vec<i64>> {
+        let mut statement = self.connection.prepare(query).map_err(error)?;
+        let ids = statement.query_map(params_from_iter(values), |row| row.get(0)).map_err(error)?;
+        ids.collect::<Result<Vec<_>, _>>().map_err(error)
+    }
+
+    /// Number of games stored.
+    pub fn count(&self) -> io::Result<u64> {
+        self.connection.query_row("SELECT COUNT(*) FROM games", [], |row| row.get(0)).map_err(error)
+    }
+}
+
+// Stores a game, its players, tags and positions in a transaction.
+fn insert_game(transaction: &Transaction, game: &GameRecord, source: &str) -> io::Result<i64> {
+    let positions = game.positions().map_err(|i| io::Error::new(io::ErrorKind::InvalidData, format!("illegal move {}", i + 1)))?;
+    let black = player(transaction, &game.black)?;
+    let white = player(transaction, &game.white)?;
+    transaction.execute(
+        "INSERT INTO games (black, white, event, date, moves, black_disks, theoretical_disks, source) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![black, white, game.event, game.date, record::format_transcript(&game.moves), game.black_disks, game.theoretical_disks, source]
+    ).map_err(error)?;
+    let id = transaction.last_insert_rowid();
+    for (name, value) in &game.tags {
+        transaction.execute("INSERT INTO tags (game, name, value) VALUES (?1, ?2, ?3)", params![id, name, value]).map_err(error)?;
+    }
+    let mut statement = transaction.prepare_cached("INSERT INTO positions (game, ply, hash, move) VALUES (?1, ?2, ?3, ?4)").map_err(error)?;
+    for (ply, (board, color)) in positions.iter().enumerate() {
+        let (hash, symmetry) = zobrist::canonical(board, *color);
+        let played = game.moves.get(ply).map(|&x| index(symmetry.apply(x)));
+        statement.execute(params![id, ply as i64, hash as i64, played]).map_err(error)?;
+    }
+    Ok(id)
+}
+
+// The id of a player, added if new.
+fn player(transaction: &Transaction, name: &str) -> io::Result<i64> {
+    transaction.execute("INSERT OR IGNORE INTO players (name) VALUES (?1)", [name]).map_err(error)?;
+    transaction.query_row("SELECT id FROM players WHERE name = ?1", [name], |row| row.get(0)).map_err(error)
+}
+
+fn index(position: Coord) -> i64 {
+    i64::from(position.1) * 8 + i64::from(position.0)
+}
+
+fn coord(index: i64) -> Coord {
+    ((index % 8) as i8, (index / 8) as i8)
+}
+
+fn error(error: rusqlite::Error) -> io::Error {
+    io::Error::other(error.to_string())
+}
@@ -0,0 +1,161 @@
+//! This module connects a [chat bot](../chat/index.html) to Discord, so that the members of a
+//! server play the engine or each other in its text channels, with commands like `!challenge`
+//! and cells like `d3`. Replies come with the board as an attached PNG image.
+//!
+//! [`run`](fn.run.html) follows the gateway of Discord with the token of a bot application
+//! allowed to read the content of messages.
+
+use std::io::{self, ErrorKind};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+use tungstenite::protocol::CloseFrame;
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{self, Message, WebSocket};
+use ureq;
+
+use chat::{Bot, Reply, User};
+use engine::Engine;
+use files::invalid;
+use formats::json::Value;
+
+const API: &str = "https://discord.com/api/v10";
+
+// Messages in servers and direct messages, and their content.
+const INTENTS: u32 = (1 << 9) | (1 << 12) | (1 << 15);
+
+// How often the gateway is left to send the heartbeats due.
+const POLL: Duration = Duration::from_millis(500);
+
+const BOUNDARY: &str = "rusty-reversi-board";
+
+/// Plays on Discord with the token of a bot application, joining the gateway again whenever
+/// Discord asks for it, until the connection fails.
+pub fn run<E: Engine>(token: &str, bot: &mut Bot<E>) -> io::Result<()> {
+    let mut response = ureq::get(format!("{}/gateway/bot", API)).header("Authorization", format!("Bot {}", token)).call().map_err(ureq::Error::into_io)?;
+    let body = response.body_mut().read_to_string().map_err(ureq::Error::into_io)?;
+    let url = Value::parse(&body)?.get("url").and_then(Value::as_str).ok_or_else(|| invalid("missing gateway"))?.to_string();
+    loop {
+        follow(&url, token, bot)?;
+    }
+}
+
+// Answers the messages of the gateway until Discord closes the connection or asks to connect
+// again.
+#[cfg_attr(feature = "trace", tracing::instrument(name = "discord", skip_all))]
+fn follow<E: Engine>(url: &str, token: &str, bot: &mut Bot<E>) -> io::Result<()> {
+    let (mut socket, _) = tungstenite::connect(format!("{}/?v=10&encoding=json", url)).map_err(io::Error::other)?;
+    match socket.get_mut() {
+        MaybeTlsStream::Plain(stream) => stream.set_read_timeout(Some(POLL))?,
+        MaybeTlsStream::Rustls(stream) => stream.get_mut().set_read_timeout(Some(POLL))?,
+        _ => {}
+    }
+    // the interval of the heartbeats and when the next one is due
+    let mut heartbeat: Option<(Duration, Instant)> = None;
+    let mut sequence = Value::Null;
+    let mut own = String::new();
+    loop {
+        if let Some((interval, ref mut due)) = heartbeat {
+            if Instant::now() >= *due {
+                send(&mut socket, 1, sequence.clone())?;
+                *due += interval;
+            }
+        }
+        let text = match socket.read() {
+            Ok(Message::Text(text)) => text,
+            Ok(Message::Close(frame)) => return closed(frame),
+            Ok(_) => continue,
+            Err(tungstenite::Error::Io(ref error)) if matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => continue,
+            Err(tungstenite::Error::ConnectionClosed) => return Ok(()),
+            Err(error) => return Err(io::Error::other(error))
+        };
+        let event = Value::parse(&text)?;
+        if let Some(number) = event.get("s").filter(|x| **x != Value::Null) {
+            sequence = number.clone();
+        }
+        let data = event.get("d").cloned().unwrap_or(Value::Null);
+        match event.get("op").and_then(Value::as_f64).map(|x| x as u32) {
+            // hello
+            Some(10) => {
+                let interval = Duration::from_millis(data.get("heartbeat_interval").and_then(Value::as_f64).ok_or_else(|| invalid("missing heartbeat interval"))? as u64);
+                heartbeat = Some((interval, Instant::now() + interval));
+                let properties = object(vec![("os", string(std::env::consts::OS)), ("browser", string("rusty_reversi")), ("device", string("rusty_reversi"))]);
+                send(&mut socket, 2, object(vec![("token", string(token)), ("intents", Value::Number(f64::from(INTENTS))), ("properties", properties)]))?;
+            }
+            // heartbeat requested
+            Some(1) => send(&mut socket, 1, sequence.clone())?,
+            // reconnect and invalid session
+            Some(7) | Some(9) => return Ok(()),
+            Some(0) => match event.get("t").and_then(Value::as_str) {
+                Some("READY") => own = data.get("user").and_then(user).map(|x| x.id).unwrap_or_default(),
+                Some("MESSAGE_CREATE") => {
+                    let author = match data.get("author") {
+                        Some(author) if author.get("bot") != Some(&Value::Bool(true)) => user(author).ok_or_else(|| invalid("invalid author"))?,
+                        _ => continue
+                    };
+                    let channel = data.get("channel_id").and_then(Value::as_str).ok_or_else(|| invalid("missing channel"))?;
+                    let content = data.get("content").and_then(Value::as_str).unwrap_or("");
+                    let mentions = data.get("mentions").and_then(Value::as_array).unwrap_or(&[]).iter().filter_map(user).filter(|x| x.id != own).collect::<Vec<_>>();
+                    if let Some(reply) = bot.handle(channel, &author, content, &mentions) {
+                        // a channel the bot can't write in doesn't stop the others
+                        if let Err(_error) = post(token, channel, &reply) {
+                            #[cfg(feature = "trace")]
+                            tracing::warn!(channel, error = %_error, "reply failed");
+                        }
+                    }
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+}
+
+// Sends a message of the gateway.
+fn send(socket: &mut WebSocket<MaybeTlsStream<TcpStream>>, op: u32, data: Value) -> io::Result<()> {
+    let message = object(vec![("op", Value::Number(f64::from(op))), ("d", data)]);
+    socket.send(Message::Text(message.to_string())).map_err(io::Error::other)
+}
+
+// Ends following the gateway when it closes, failing for the codes that connecting again
+// doesn't fix, like an invalid token or intents not allowed.
+fn closed(frame: Option<CloseFrame>) -> io::Result<()> {
+    match frame {
+        Some(frame) if u16::from(frame.code) == 4004 || u16::from(frame.code) >= 4010 => Err(io::Error::other(format!("gateway closed: {}", frame.reason))),
+        _ => Ok(())
+    }
+}
+
+// Posts a reply in a channel, with its image attached.
+fn post(token: &str, channel: &str, reply: &Reply) -> io::Result<()> {
+    let request = ureq::post(format!("{}/channels/{}/messages", API, channel)).header("Authorization", format!("Bot {}", token));
+    let result = match reply.image {
+        None => request.content_type("application/json").send(object(vec![("content", string(&reply.text))]).to_string()),
+        Some(ref image) => {
+            let attachment = object(vec![("id", Value::Number(0.0)), ("filename", string("board.png"))]);
+            let payload = object(vec![("content", string(&reply.text)), ("attachments", Value::Array(vec![attachment]))]);
+            let mut body = format!(
+                "--{0}\r\nContent-Disposition: form-data; name=\"payload_json\"\r\nContent-Type: application/json\r\n\r\n{1}\r\n--{0}\r\nContent-Disposition: form-data; name=\"files[0]\"; filename=\"board.png\"\r\nContent-Type: image/png\r\n\r\n",
+                BOUNDARY, payload
+            ).into_bytes();
+            body.extend_from_slice(image);
+            body.extend_from_slice(format!("\r\n--{}--\r\n", BOUNDARY).as_bytes());
+            request.content_type(format!("multipart/form-data; boundary={}", BOUNDARY)).send(body)
+        }
+    };
+    result.map(|_| ()).map_err(ureq::Error::into_io)
+}
+
+fn user(document: &Value) -> Option<User> {
+    let id = document.get("id").and_then(Value::as_str)?;
+    let name = document.get("global_name").and_then(Value::as_str).or_else(|| document.get("username").and_then(Value::as_str))?;
+    Some(User { id: id.to_string(), name: name.to_string() })
+}
+
+fn object(members: Vec<(&str, Value)>) -> Value {
+    Value::Object(members.into_iter().map(|(key, value)| (key.to_string(), value)).collect())
+}
+
+fn string(text: &str) -> Value {
+    Value::String(text.to_string())
+}
@@ -0,0 +1,311 @@
+//! This module solves endgames exactly: it searches to the end of the game and finds the final
+//! disk differential with perfect play from both sides.
+//!
+//! Solving is expensive, so results can be kept in a [`SolutionCache`](struct.SolutionCache.html)
+//! saved between sessions. Positions are keyed by their
+//! [canonical hash](../zobrist/fn.canonical.html), so a solved position is also found in all its
+//! rotations and reflections.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::mem;
+use std::path::Path;
+
+use engine::{parse_option, unknown_option, Engine, Limits, MemoryUsage, Reservation, SearchResult};
+use eval::Score;
+use game::{Board, Color, Coord};
+use search::{EndgameMode, WIN};
+use time::Instant;
+use zobrist;
+
+const MAGIC: &[u8; 6] = b"RRSOLV";
+const VERSION: u16 = 1;
+
+// Cell byte of a skip.
+const NO_MOVE: u8 = 64;
+
+/// The result of perfect play.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Solution {
+    /// A best move, `None` when the player has to skip or the game has ended.
+    pub best_move: Option<Coord>,
+    /// Final disk differential for the player to move.
+    pub score: i8
+}
+
+/// Solves a position.
+pub fn solve(board: &Board, color: Color) -> Solution {
+    run(board, color, -65, 65, None).0
+}
+
+/// Finds only whether the player to move wins, loses or draws, which is faster than finding the
+/// exact differential. The score of the solution is 1, -1 or 0.
+pub fn solve_win_loss_draw(board: &Board, color: Color) -> Solution {
+    let solution = run(board, color, -1, 1, None).0;
+    Solution { score: solution.score.signum(), ..solution }
+}
+
+/// Solves a position exactly or as a win, loss or draw, then follows the best moves to the end
+/// of the game. Returns the solution, the line starting with its best move, skips left out, and
+/// the number of positions searched.
+pub fn solve_line(board: &Board, color: Color, mode: EndgameMode) -> (Solution, Vec<Coord>, u64) {
+    let wld = mode == EndgameMode::WinLossDraw;
+    let (solution, mut nodes) = if wld { run(board, color, -1, 1, None) } else { run(board, color, -65, 65, None) };
+    let solution = if wld { Solution { score: solution.score.signum(), ..solution } } else { solution };
+    let (mut board, mut color, mut score) = (*board, color, i32::from(solution.score));
+    let mut line = Vec::new();
+    let mut best_move = solution.best_move;
+    loop {
+        match best_move {
+            Some(position) => {
+                line.push(position);
+                let legal_move = board.legal_moves(color).into_iter().find(|x| x.position == position).expect("the best move is legal");
+                board = legal_move.apply(board);
+            }
+            None if board.legal_moves(color.flip()).is_empty() => break,
+            None => {}
+        }
+        color = color.flip();
+        score = -score;
+        // the score is known, a window around it is enough to find a move reaching it
+        let (next, searched) = if wld { run(&board, color, -1, 1, None) } else { run(&board, color, score - 1, score + 1, None) };
+        nodes += searched;
+        best_move = next.best_move;
+    }
+    (solution, line, nodes)
+}
+
+/// Solves a position, reusing and storing results in a cache.
+///
+/// Only positions with at least [`min_empties`](struct.SolutionCache.html#structfield.min_empties)
+/// empty cells are stored, as the rest are quick to solve again.
+pub fn solve_cached(board: &Board, color: Color, cache: &mut SolutionCache) -> Solution {
+    run(board, color, -65, 65, Some(cache)).0
+}
+
+/// The solver as an [engine](../engine/trait.Engine.html). Its scores are the ones of finished
+/// games in the searches: the final disk differential added to
+/// [`WIN`](../search/constant.WIN.html) for a won game, or subtracted from `-WIN` for a lost one.
+/// In win-loss-draw mode the differential is left out.
+///
+/// Its option is `mode`: `exact` or `wld`. The cache, when given, is only used in `exact` mode.
+/// Limits are ignored, the solver always searches to the end of the game.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solver {
+    pub mode: EndgameMode,
+    pub cache: Option<SolutionCache>
+}
+impl Default for Solver {
+    fn default() -> Solver {
+        Solver { mode: EndgameMode::Exact, cache: None }
+    }
+}
+impl Engine for Solver {
+    fn best_move(&mut self, board: &Board, color: Color, _: &Limits) -> SearchResult {
+        let start = Instant::now();
+        let (solution, nodes) = match self.mode {
+            EndgameMode::WinLossDraw => run(board, color, -1, 1, None),
+            EndgameMode::Exact => run(board, color, -65, 65, self.cache.as_mut())
+        };
+        let diff = Score::from(solution.score);
+        let score = match self.mode {
+            EndgameMode::WinLossDraw => diff.signum() * WIN,
+            EndgameMode::Exact => diff.signum() * WIN + diff
+        };
+        let pv = solution.best_move.into_iter().collect();
+        SearchResult::new(score, board.empties() as u32, nodes, 0, pv, start.elapsed())
+    }
+
+    fn set_option(&mut self, name: &str, value: &str) -> io::Result<()> {
+        match name {
+            "mode" => self.mode = parse_option(name, value)?,
+            _ => return Err(unknown_option(name))
+        }
+        Ok(())
+    }
+
+    fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage { caches: self.cache.as_ref().map(SolutionCache::memory_usage).unwrap_or(0), ..MemoryUsage::default() }
+    }
+}
+
+// What stays the same during a search.
+struct Context<'a> {
+    cache: Option<&'a mut SolutionCache>,
+    nodes: u64
+}
+
+// Solves a position within a window, and returns the solution with the number of nodes searched.
+fn run(board: &Board, color: Color, alpha: i32, beta: i32, cache: Option<&mut SolutionCache>) -> (Solution, u64) {
+    let mut context = Context { cache, nodes: 0 };
+    let (best_move, score) = negamax(board, color, alpha, beta, &mut context);
+    (Solution { best_move, score: score as i8 }, context.nodes)
+}
+
+// The score is exact only if it falls within (alpha, beta), otherwise it is a bound.
+fn negamax(board: &Board, color: Color, mut alpha: i32, beta: i32, context: &mut Context) -> (Option<Coord>, i32) {
+    context.nodes += 1;
+    let mut moves = board.legal_moves(color);
+    if moves.is_empty() {
+        return if board.legal_moves(color.flip()).is_empty() {
+            (None, board.count(color) as i32 - board.count(color.flip()) as i32)
+        } else {
+            (None, -negamax(board, color.flip(), -beta, -alpha, context).1)
+        };
+    }
+    let stored = context.cache.as_ref().filter(|cache| board.empties() >= cache.min_empties);
+    if let Some(solution) = stored.and_then(|cache| cache.get(board, color)) {
+        return (solution.best_move, i32::from(solution.score));
+    }
+    // moves leaving the opponent few replies first, they usually cut off the others
+    if board.empties() > 6 {
+        moves.sort_by_key(|x| x.apply(*board).legal_moves(color.flip()).len());
+    }
+    let original_alpha = alpha;
+    let mut best = (None, -65);
+    for legal_move in moves {
+        let score = -negamax(&legal_move.apply(*board), color.flip(), -beta, -alpha, context).1;
+        if score > best.1 {
+            best = (Some(legal_move.position), score);
+        }
+        alpha = alpha.max(score);
+        if alpha >= beta {
+            break;
+        }
+    }
+    if let Some(cache) = context.cache.as_mut() {
+        if board.empties() >= cache.min_empties && original_alpha < best.1 && best.1 < beta {
+            cache.insert(board, color, Solution { best_move: best.0, score: best.1 as i8 });
+        }
+    }
+    best
+}
+
+// The memory of a position in a cache, counting a byte of bookkeeping.
+const CACHED_BYTES: usize = mem::size_of::<u64>() + mem::size_of::<Solution>() + 1;
+
+/// Solved positions, kept between sessions. Their memory is taken from the
+/// [budget](../engine/fn.set_memory_cap.html) of the process, and positions are no longer stored
+/// once it is exhausted.
+#[derive(Debug, Clone)]
+pub struct SolutionCache {
+    /// Positions with fewer empty cells are not stored.
+    pub min_empties: usize,
+    entries: HashMap<u64, Solution>,
+    reservation: Reservation
+}
+impl Default for SolutionCache {
+    fn default() -> SolutionCache {
+        SolutionCache { min_empties: 10, entries: HashMap::new(), reservation: Reservation::default() }
+    }
+}
+impl PartialEq for SolutionCache {
+    fn eq(&self, other: &SolutionCache) -> bool {
+        self.min_empties == other.min_empties && self.entries == other.entries
+    }
+}
+impl SolutionCache {
+    pub fn new() -> SolutionCache {
+        SolutionCache::default()
+    }
+
+    /// Number of positions in the cache.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The memory taken by the positions, in bytes, counting a byte of bookkeeping for each.
+    pub fn memory_usage(&self) -> usize {
+        self.entries.capacity() * CACHED_BYTES
+    }
+
+    /// Finds the solution of a position.
+    pub fn get(&self, board: &Board, color: Color) -> Option<Solution> {
+        let (key, symmetry) = zobrist::canonical(board, color);
+        let inverse = symmetry.inverse();
+        self.entries.get(&key).map(|x| Solution { best_move: x.best_move.map(|x| inverse.apply(x)), ..*x })
+    }
+
+    /// Stores the solution of a position. Returns whether it was stored, which it isn't when the
+    /// cache would have to grow past the budget of the process.
+    pub fn insert(&mut self, board: &Board, color: Color, solution: Solution) -> bool {
+        let (key, symmetry) = zobrist::canonical(board, color);
+        if self.entries.len() == self.entries.capacity() && !self.entries.contains_key(&key) {
+            // the table about doubles when it grows
+            let grown = (self.entries.capacity() * 2).max(3) + 1;
+            if self.reservation.resize(grown * CACHED_BYTES, "endgame cache").is_err() {
+                return false;
+            }
+        }
+        self.entries.insert(key, Solution { best_move: solution.best_move.map(|x| symmetry.apply(x)), ..solution });
+        // keeps the estimate when the table grew more than expected
+        let _ = self.reservation.resize(self.memory_usage(), "endgame cache");
+        true
+    }
+
+    /// Reads a cache written by [`write`](#method.write).
+    ///
+    /// The format starts with the magic bytes `RRSOLV`, a 16 bit version and the 32 bit number
+    /// of positions. Each position follows in increasing order of keys: its 64 bit key, its
+    /// score and the cell of its best move as `y * 8 + x`, or 64 when there is none, one byte
+    /// each. All integers are little endian. The minimum number of empty cells is not stored.
+    pub fn read<R: Read>(mut reader: R) -> io::Result<SolutionCache> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+        let mut header = [0; 12];
+        reader.read_exact(&mut header)?;
+        if &header[..6] != MAGIC {
+            return Err(invalid("not an endgame cache"));
+        }
+        if u16::from_le_bytes([header[6], header[7]]) != VERSION {
+            return Err(invalid("unsupported endgame cache version"));
+        }
+        let mut cache = SolutionCache::new();
+        for _ in 0..u32::from_le_bytes([header[8], header[9], header[10], header[11]]) {
+            let mut entry = [0; 10];
+            reader.read_exact(&mut entry)?;
+            let mut key = [0; 8];
+            key.copy_from_slice(&entry[..8]);
+            let best_move = match entry[9] {
+                NO_MOVE => None,
+                cell if cell < NO_MOVE => Some(((cell % 8) as i8, (cell / 8) as i8)),
+                _ => return Err(invalid("move outside of the board"))
+            };
+            cache.entries.insert(u64::from_le_bytes(key), Solution { best_move, score: entry[8] as i8 });
+        }
+        cache.reservation.resize(cache.memory_usage(), "endgame cache")?;
+        Ok(cache)
+    }
+
+    /// Writes the cache in the format accepted by [`read`](#method.read).
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&(self.entries.len() as u32).to_le_bytes())?;
+        let mut keys = self.entries.keys().collect::<Vec<_>>();
+        keys.sort();
+        for key in keys {
+            let solution = &self.entries[key];
+            let cell = solution.best_move.map(|(x, y)| (y * 8 + x) as u8).unwrap_or(NO_MOVE);
+            writer.write_all(&key.to_le_bytes())?;
+            writer.write_all(&[solution.score as u8, cell])?;
+        }
+        Ok(())
+    }
+
+    /// Reads a cache from a file.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<SolutionCache> {
+        SolutionCache::read(BufReader::new(File::open(path)?))
+    }
+
+    /// Writes the cache to a file, replacing its contents.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()
+    }
+}
@@ -0,0 +1,434 @@
+//! This module defines the interface shared by the engines, so that protocols, matches and the
+//! command line can drive any of them.
+//!
+//! The engines are [`Minimax`](../search/struct.Minimax.html) and
+//! [`AlphaBeta`](../search/struct.AlphaBeta.html) searches, the [`Mcts`](../mcts/struct.Mcts.html)
+//! tree search and the exact endgame [`Solver`](../endgame/struct.Solver.html). Engines written
+//! elsewhere can be checked for consistency with [`verify`](fn.verify.html).
+//!
+//! Engines report the [memory](struct.MemoryUsage.html) they hold. Their transposition tables,
+//! endgame caches and search trees are [reserved](struct.Reservation.html) from a budget shared
+//! by the whole process, [capped](fn.set_memory_cap.html) so that a server running many engines
+//! can predict how much memory it takes.
+
+use std::io;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use endgame::Solver;
+use eval::Score;
+use game::{Board, Color, Coord, Symmetry};
+use mcts::{Config, Mcts};
+use plugin::Pluggable;
+use protocol::external::{Dialect, External};
+use random::Random;
+#[cfg(feature = "scripting")]
+use script::Script;
+use search::{AlphaBeta, Minimax};
+
+/// Limits of a search. Engines use their configured defaults for the limits not given, and
+/// ignore the ones they can't apply.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct Limits {
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub time: Option<Duration>
+}
+
+/// The outcome of a search, shared by all engines so that protocols, logs and matches can report
+/// it the same way.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult {
+    /// The move found, `None` when the player has to skip or the game has ended.
+    pub best: Option<Coord>,
+    /// The score of the move for the player to move.
+    pub score: Score,
+    /// Depth of the deepest search completed.
+    pub depth: u32,
+    /// Number of positions searched.
+    pub nodes: u64,
+    /// Number of positions found in the transposition table.
+    pub tt_hits: u64,
+    /// The moves expected to be played from the position, starting with the best move. Skips
+    /// are left out, like in game transcripts.
+    pub pv: Vec<Coord>,
+    pub time: Duration,
+    /// Details of the search, left empty by the engines not collecting them.
+    pub stats: SearchStats
+}
+impl SearchResult {
+    /// A result whose best move is the first of the principal variation, without details.
+    pub fn new(score: Score, depth: u32, nodes: u64, tt_hits: u64, pv: Vec<Coord>, time: Duration) -> SearchResult {
+        SearchResult { best: pv.first().cloned(), score, depth, nodes, tt_hits, pv, time, stats: SearchStats::default() }
+    }
+}
+
+/// Details of how searches went, to see where their time goes and how well they prune. They are
+/// collected by the [alpha-beta searches](../search/index.html), and add up over the iterations
+/// of a search, or over several searches.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SearchStats {
+    /// Number of positions searched at each ply from the root. A player passing counts as one.
+    pub nodes_by_ply: Vec<u64>,
+    /// Number of positions looked up in the transposition table.
+    pub tt_probes: u64,
+    /// Number of positions found in the transposition table.
+    pub tt_hits: u64,
+    /// Number of positions whose moves were searched.
+    pub expanded: u64,
+    /// Number of positions cut off by their move at each index, in the order the moves were
+    /// searched.
+    pub cutoffs_by_move: Vec<u64>,
+    /// The iterations of the searches, from the first.
+    pub iterations: Vec<Iteration>
+}
+impl SearchStats {
+    /// Number of positions searched.
+    pub fn nodes(&self) -> u64 {
+        self.nodes_by_ply.iter().sum()
+    }
+
+    /// The share of the positions looked up that were found in the transposition table.
+    pub fn tt_hit_rate(&self) -> f64 {
+        self.tt_hits as f64 / self.tt_probes.max(1) as f64
+    }
+
+    /// The share of the positions whose moves were searched that were cut off.
+    pub fn cutoff_rate(&self) -> f64 {
+        self.cutoffs_by_move.iter().sum::<u64>() as f64 / self.expanded.max(1) as f64
+    }
+
+    /// The share of the positions whose moves were searched that were cut off by each move.
+    pub fn cutoff_rate_by_move(&self) -> Vec<f64> {
+        self.cutoffs_by_move.iter().map(|&x| x as f64 / self.expanded.max(1) as f64).collect()
+    }
+
+    /// Adds the details of another search.
+    pub fn add(&mut self, other: &SearchStats) {
+        add_counts(&mut self.nodes_by_ply, &other.nodes_by_ply);
+        self.tt_probes += other.tt_probes;
+        self.tt_hits += other.tt_hits;
+        self.expanded += other.expanded;
+        add_counts(&mut self.cutoffs_by_move, &other.cutoffs_by_move);
+        self.iterations.extend_from_slice(&other.iterations);
+    }
+}
+
+// Adds counts by index, extending the first ones as needed.
+fn add_counts(counts: &mut Vec<u64>, other: &[u64]) {
+    if counts.len() < other.len() {
+        counts.resize(other.len(), 0);
+    }
+    for (count, &x) in counts.iter_mut().zip(other) {
+        *count += x;
+    }
+}
+
+/// An iteration of an iteratively deepening search.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Iteration {
+    pub depth: u32,
+    /// Number of positions searched.
+    pub nodes: u64,
+    pub time: Duration
+}
+
+/// An engine choosing moves.
+pub trait Engine {
+    /// Searches a position within the limits.
+    fn best_move(&mut self, board: &Board, color: Color, limits: &Limits) -> SearchResult;
+
+    /// Changes a setting of the engine by name. Fails if the engine has no such setting or the
+    /// value is invalid.
+    fn set_option(&mut self, name: &str, value: &str) -> io::Result<()>;
+
+    /// The memory held by the engine between searches. Nothing by default.
+    fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage::default()
+    }
+}
+impl<E: Engine + ?Sized> Engine for &mut E {
+    fn best_move(&mut self, board: &Board, color: Color, limits: &Limits) -> SearchResult {
+        (**self).best_move(board, color, limits)
+    }
+
+    fn set_option(&mut self, name: &str, value: &str) -> io::Result<()> {
+        (**self).set_option(name, value)
+    }
+
+    fn memory_usage(&self) -> MemoryUsage {
+        (**self).memory_usage()
+    }
+}
+impl<E: Engine + ?Sized> Engine for Box<E> {
+    fn best_move(&mut self, board: &Board, color: Color, limits: &Limits) -> SearchResult {
+        (**self).best_move(board, color, limits)
+    }
+
+    fn set_option(&mut self, name: &str, value: &str) -> io::Result<()> {
+        (**self).set_option(name, value)
+    }
+
+    fn memory_usage(&self) -> MemoryUsage {
+        (**self).memory_usage()
+    }
+}
+
+/// The memory held by the components of an engine, in bytes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct MemoryUsage {
+    /// The transposition table.
+    pub table: usize,
+    /// The opening book.
+    pub book: usize,
+    /// The weights of the evaluation.
+    pub weights: usize,
+    /// The positions kept between searches, like solved endgames.
+    pub caches: usize
+}
+impl MemoryUsage {
+    /// The memory of all the components.
+    pub fn total(&self) -> usize {
+        self.table + self.book + self.weights + self.caches
+    }
+
+    /// Adds the memory of another engine, like the one searching behind a book.
+    pub fn add(&mut self, other: &MemoryUsage) {
+        self.table += other.table;
+        self.book += other.book;
+        self.weights += other.weights;
+        self.caches += other.caches;
+    }
+}
+
+// The memory cap of the process in bytes, `usize::MAX` for none.
+static MEMORY_CAP: AtomicUsize = AtomicUsize::new(usize::MAX);
+// The memory reserved by all the engines of the process.
+static MEMORY_RESERVED: AtomicUsize = AtomicUsize::new(0);
+
+/// Caps the memory the engines of the process reserve together, `None` removing the cap.
+/// Engines then refuse transposition tables that would take the process past it, stop keeping
+/// positions in their caches and end tree searches early once it is reached. Memory reserved
+/// before the cap is lowered is kept.
+pub fn set_memory_cap(bytes: Option<usize>) {
+    MEMORY_CAP.store(bytes.unwrap_or(usize::MAX), Ordering::SeqCst);
+}
+
+/// The memory the engines of the process may reserve together, `None` without a cap.
+pub fn memory_cap() -> Option<usize> {
+    Some(MEMORY_CAP.load(Ordering::SeqCst)).filter(|&x| x != usize::MAX)
+}
+
+/// The memory reserved by all the engines of the process.
+pub fn memory_reserved() -> usize {
+    MEMORY_RESERVED.load(Ordering::SeqCst)
+}
+
+/// Memory reserved from the budget of the process, given back when dropped. A copy reserves
+/// the same memory again, even past the cap.
+#[derive(Debug, Default)]
+pub struct Reservation {
+    bytes: usize
+}
+impl Reservation {
+    /// Reserves memory for a component of an engine, failing with the component if the memory
+    /// reserved by the process would exceed the cap.
+    pub fn new(bytes: usize, component: &str) -> io::Result<Reservation> {
+        let mut reservation = Reservation::default();
+        reservation.resize(bytes, component)?;
+        Ok(reservation)
+    }
+
+    /// The memory reserved, in bytes.
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// Grows or shrinks the reservation. Growing it past the cap fails, keeping it as it is.
+    pub fn resize(&mut self, bytes: usize, component: &str) -> io::Result<()> {
+        if bytes <= self.bytes {
+            MEMORY_RESERVED.fetch_sub(self.bytes - bytes, Ordering::SeqCst);
+            self.bytes = bytes;
+            return Ok(());
+        }
+        let (more, cap) = (bytes - self.bytes, MEMORY_CAP.load(Ordering::SeqCst));
+        MEMORY_RESERVED.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |reserved| reserved.checked_add(more).filter(|&x| x <= cap))
+            .map_err(|reserved| io::Error::new(io::ErrorKind::OutOfMemory, format!(
+                "the {} of {} bytes exceeds the memory cap of {} bytes, {} being reserved", component, bytes, cap, reserved)))?;
+        self.bytes = bytes;
+        Ok(())
+    }
+}
+impl Clone for Reservation {
+    fn clone(&self) -> Reservation {
+        MEMORY_RESERVED.fetch_add(self.bytes, Ordering::SeqCst);
+        Reservation { bytes: self.bytes }
+    }
+}
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        MEMORY_RESERVED.fetch_sub(self.bytes, Ordering::SeqCst);
+    }
+}
+
+/// Parses the value of an option, for implementations of
+/// [`set_option`](trait.Engine.html#tymethod.set_option).
+pub fn parse_option<T: FromStr>(name: &str, value: &str) -> io::Result<T> {
+    value.trim().parse().map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, format!("invalid value for {}: {}", name, value)))
+}
+
+/// The error of setting an option an engine doesn't have.
+pub fn unknown_option(name: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, format!("unknown option: {}", name))
+}
+
+/// Makes an engine from its name followed by its options, like `alphabeta:depth=8,hash=64`: one
+/// of `alphabeta`, `minimax`, `mcts` and `solver`, or an [external](../protocol/external/index.html)
+/// program: `external:command` for a program speaking the edax protocol, `nboard:command` for one
+/// speaking the NBoard protocol, and `remote:host:port` and `remote-nboard:host:port` for programs
+/// listening over TCP. With the `scripting` feature, `script:path` plays with a
+/// [script](../script/index.html). With the `plugins` feature, on Unix, `alphabeta` and
+/// `minimax` take a `plugin` option loading an [evaluation plugin](../plugin/index.html).
+pub fn from_spec(spec: &str) -> io::Result<Box<dyn Engine>> {
+    let (name, options) = match spec.find(':') {
+        Some(i) => (&spec[..i], &spec[i + 1..]),
+        None => (spec, "")
+    };
+    // the command or the address of a program, or the path of a script, may hold anything
+    match name {
+        "external" => return Ok(Box::new(External::spawn(options)?)),
+        "nboard" => return Ok(Box::new(External::spawn(options)?.speaking(Dialect::NBoard)?)),
+        "remote" => return Ok(Box::new(External::connect(options)?)),
+        "remote-nboard" => return Ok(Box::new(External::connect(options)?.speaking(Dialect::NBoard)?)),
+        #[cfg(feature = "scripting")]
+        "script" => return Ok(Box::new(Script::load(options)?)),
+        #[cfg(not(feature = "scripting"))]
+        "script" => return Err(io::Error::other("scripts need the scripting feature")),
+        _ => {}
+    }
+    let mut engine: Box<dyn Engine> = match name {
+        "alphabeta" => Box::new(AlphaBeta::new(6, Pluggable::default())),
+        "minimax" => Box::new(Minimax { depth: 4, evaluator: Pluggable::default() }),
+        "mcts" => Box::new(Mcts::new(Config::default())?),
+        "solver" => Box::new(Solver::default()),
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("unknown engine: {}", name)))
+    };
+    for option in options.split(',').filter(|x| !x.is_empty()) {
+        match option.find('=') {
+            Some(i) => engine.set_option(option[..i].trim(), &option[i + 1..])?,
+            None => return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("options are written name=value: {}", option)))
+        }
+    }
+    Ok(engine)
+}
+
+/// Settings of [`verify`](fn.verify.html).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct VerifyOptions {
+    /// Number of random positions checked.
+    pub positions: usize,
+    /// Depth of the searches.
+    pub depth: u32,
+    pub seed: u64
+}
+impl Default for VerifyOptions {
+    fn default() -> VerifyOptions {
+        VerifyOptions {
+            positions: 20,
+            depth: 3,
+            seed: 0
+        }
+    }
+}
+
+/// An inconsistency found by [`verify`](fn.verify.html).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Failure {
+    /// A rotation or reflection of a position scored differently.
+    Symmetry { board: Board, color: Color, symmetry: Symmetry, expected: Score, found: Score },
+    /// Taking back a move didn't restore the board.
+    Unmake { board: Board, position: Coord },
+    /// Enabling the transposition table changed the score or chose a worse move.
+    Table { board: Board, color: Color, expected: Box<SearchResult>, found: Box<SearchResult> }
+}
+
+/// Checks that an engine and the move generation behave consistently on random positions:
+///
+/// - all 8 rotations and reflections of a position get the same score,
+/// - making and taking back every legal move restores the board exactly,
+/// - the search finds the same score with the transposition table enabled and disabled, and a
+///   best move as good. This is only checked for engines with a `hash` option setting the table
+///   size in MiB, and leaves the table disabled.
+///
+/// Searches are limited to the given depth, and alternative best moves are only compared from
+/// depth 2. Returns the failures found, none if the engine is
+/// consistent.
+pub fn verify<E: Engine>(engine: &mut E, options: &VerifyOptions) -> Vec<Failure> {
+    let limits = Limits { depth: Some(options.depth), ..Limits::default() };
+    let mut random = Random::new(options.seed);
+    let mut failures = Vec::new();
+    for _ in 0..options.positions {
+        let (board, color) = random_position(&mut random);
+        let moves = board.legal_moves(color);
+        for legal_move in &moves {
+            let mut restored = board;
+            restored.make(legal_move);
+            restored.unmake(legal_move);
+            if restored != board {
+                failures.push(Failure::Unmake { board, position: legal_move.position });
+            }
+        }
+        let has_table = engine.set_option("hash", "0").is_ok();
+        let expected = engine.best_move(&board, color, &limits);
+        check_symmetries(engine, &board, color, &limits, expected.score, &mut failures);
+        if !has_table || engine.set_option("hash", "16").is_err() {
+            continue;
+        }
+        let found = engine.best_move(&board, color, &limits);
+        engine.set_option("hash", "0").expect("the table could be disabled before");
+        // a different move is fine when it is just as good
+        let as_good = found.best == expected.best || options.depth < 2 || {
+            let found_reply = reply_score(engine, &board, color, found.best, options.depth - 1);
+            found_reply.is_some() && found_reply == reply_score(engine, &board, color, expected.best, options.depth - 1)
+        };
+        if found.score != expected.score || !as_good {
+            failures.push(Failure::Table { board, color, expected: Box::new(expected), found: Box::new(found) });
+        }
+    }
+    failures
+}
+
+fn check_symmetries<E: Engine>(engine: &mut E, board: &Board, color: Color, limits: &Limits, expected: Score, failures: &mut Vec<Failure>) {
+    for &symmetry in &Symmetry::all()[1..] {
+        let found = engine.best_move(&board.transform(symmetry), color, limits).score;
+        if found != expected {
+            failures.push(Failure::Symmetry { board: *board, color, symmetry, expected, found });
+        }
+    }
+}
+
+// The score of a move, searching the position after it. `None` if the move is illegal.
+fn reply_score<E: Engine>(engine: &mut E, board: &Board, color: Color, position: Option<Coord>, depth: u32) -> Option<Score> {
+    let legal_move = board.legal_moves(color).into_iter().find(|x| Some(x.position) == position)?;
+    let limits = Limits { depth: Some(depth), ..Limits::default() };
+    Some(-engine.best_move(&legal_move.apply(*board), color.flip(), &limits).score)
+}
+
+// Plays a random number of random moves from the start, without ending the game.
+fn random_position(random: &mut Random) -> (Board, Color) {
+    let (mut board, mut color) = (Board::new(), Color::Black);
+    for _ in 0..4 + random.below(40) {
+        let mut moves = board.legal_moves(color);
+        if moves.is_empty() {
+            color = color.flip();
+            moves = board.legal_moves(color);
+        }
+        if moves.is_empty() {
+            break;
+        }
+        board = moves[random.below(moves.len())].apply(board);
+        color = color.flip();
+    }
+    (board, color)
+}
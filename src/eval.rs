@@ -0,0 +1,519 @@
+//! This module contains the static evaluation of positions used by the search engines.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::mem;
+use std::path::Path;
+
+use engine::unknown_option;
+use files::invalid;
+use game::{Board, Color, Coord, LegalMove, DIRECTIONS};
+
+/// A heuristic value of a position. Higher is better for the player it was computed for.
+pub type Score = i32;
+
+/// The stage of the game, measured by the number of empty cells.
+///
+/// The game is considered to move from the opening, with 60 empty cells, through the midgame,
+/// at [`MIDGAME_EMPTIES`](constant.MIDGAME_EMPTIES.html), to the endgame on the full board.
+/// Weights in between are interpolated, see [`Phased`](struct.Phased.html).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Phase {
+    pub empties: usize
+}
+impl Phase {
+    /// The phase of the game on a board.
+    pub fn of(board: &Board) -> Phase {
+        Phase { empties: board.empties() }
+    }
+}
+
+/// Number of empty cells where the midgame weights apply fully.
+pub const MIDGAME_EMPTIES: usize = 30;
+
+/// Scores a board from the point of view of a player.
+pub trait Evaluator {
+    /// Estimates how good `board` is for `color`, the result is negated for the opponent.
+    ///
+    /// The caller passes the phase of the board, so evaluators and their terms can weight
+    /// features differently as the game advances without computing it again.
+    fn evaluate(&self, board: &Board, color: Color, phase: Phase) -> Score;
+
+    /// Called by the search when it makes a move on `board`, before searching the position after
+    /// it. Evaluators keeping state derived from the searched board update it here instead of
+    /// recomputing it in [`evaluate`](#tymethod.evaluate). Does nothing by default.
+    fn make(&self, _board: &Board, _legal_move: &LegalMove) {}
+
+    /// Called by the search when it takes back a move, `board` being the position restored.
+    fn unmake(&self, _board: &Board, _legal_move: &LegalMove) {}
+
+    /// Sets an option of the evaluator, reached through the options of the engines searching
+    /// with it. Evaluators have no options by default.
+    fn set_option(&mut self, name: &str, _value: &str) -> io::Result<()> {
+        Err(unknown_option(name))
+    }
+
+    /// The memory held by the weights of the evaluator, in bytes. Its own size by default.
+    fn memory_usage(&self) -> usize {
+        mem::size_of_val(self)
+    }
+}
+
+/// Evaluates positions by summing a fixed weight for each occupied cell.
+///
+/// Cells owned by the player count positively, the ones owned by the opponent negatively.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct WeightedSquares {
+    /// Weight of each cell, indexed the same way as [`Board::cells`](../game/struct.Board.html).
+    pub weights: [[Score; 8]; 8]
+}
+impl WeightedSquares {
+    /// Builds a table symmetric to both axes and both diagonals from the weights of one octant.
+    ///
+    /// The octant lists the cells from the corner towards the center row by row:
+    /// a1, b1, c1, d1, b2, c2, d2, c3, d3, d4.
+    pub fn from_octant(octant: [Score; 10]) -> WeightedSquares {
+        let mut weights = [[0; 8]; 8];
+        for (x, column) in weights.iter_mut().enumerate() {
+            for (y, weight) in column.iter_mut().enumerate() {
+                *weight = octant[octant_index(x, y)];
+            }
+        }
+        WeightedSquares { weights }
+    }
+
+    /// Returns the weights of the octant the table would be built from, see
+    /// [`from_octant`](#method.from_octant).
+    pub fn octant(&self) -> [Score; 10] {
+        let mut octant = [0; 10];
+        for (x, column) in self.weights.iter().enumerate().take(4) {
+            for (y, &weight) in column.iter().enumerate().take(x + 1) {
+                octant[octant_index(x, y)] = weight;
+            }
+        }
+        octant
+    }
+}
+
+/// Finds the entry of the octant that a cell is mirrored to, see
+/// [`WeightedSquares::from_octant`](struct.WeightedSquares.html#method.from_octant).
+pub fn octant_index(x: usize, y: usize) -> usize {
+    // fold into the lower left quadrant, then below the diagonal
+    let (i, j) = (x.min(7 - x), y.min(7 - y));
+    let (i, j) = if j > i { (j, i) } else { (i, j) };
+    // rows of the octant have 4, 3, 2 and 1 entries
+    let offset = match j {
+        0 => 0,
+        1 => 4,
+        2 => 7,
+        _ => 9
+    };
+    offset + i - j
+}
+impl Default for WeightedSquares {
+    /// The classic table: corners are precious, the squares next to them (X and C squares) are
+    /// dangerous as they give away the corner.
+    fn default() -> WeightedSquares {
+        WeightedSquares::from_octant([100, -20, 10, 5, -50, -2, -2, -1, -1, -1])
+    }
+}
+impl Evaluator for WeightedSquares {
+    fn evaluate(&self, board: &Board, color: Color, _: Phase) -> Score {
+        let mut score = 0;
+        for (cells, weights) in board.cells.iter().zip(self.weights.iter()) {
+            for (cell, weight) in cells.iter().zip(weights.iter()) {
+                match *cell {
+                    Some(found) if found == color => score += weight,
+                    Some(_) => score -= weight,
+                    None => ()
+                }
+            }
+        }
+        score
+    }
+}
+
+/// Separate weights for each phase of the game.
+///
+/// Between two phases the weights are blended linearly, so the evaluation changes gradually
+/// instead of jumping when the board reaches a phase boundary.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Phased<T> {
+    /// Applies fully in the starting position, with 60 empty cells.
+    pub opening: T,
+    /// Applies fully with [`MIDGAME_EMPTIES`](constant.MIDGAME_EMPTIES.html) empty cells.
+    pub midgame: T,
+    /// Applies fully on the full board.
+    pub endgame: T
+}
+impl<T> Phased<T> {
+    /// Uses the same weights in all phases.
+    pub fn uniform(value: T) -> Phased<T> where T: Clone {
+        Phased { opening: value.clone(), midgame: value.clone(), endgame: value }
+    }
+
+    /// Interpolates the scores given by the weights of the two phases around `phase`.
+    pub fn blend<F: Fn(&T) -> Score>(&self, phase: Phase, score: F) -> Score {
+        let (opening, midgame, endgame) = phase_factors(phase);
+        let mut sum = 0;
+        for &(factor, weights) in &[(opening, &self.opening), (midgame, &self.midgame), (endgame, &self.endgame)] {
+            if factor != 0 {
+                sum += factor * score(weights);
+            }
+        }
+        sum / MIDGAME_EMPTIES as Score
+    }
+}
+impl Phased<Score> {
+    /// The weight interpolated for `phase`.
+    pub fn at(&self, phase: Phase) -> Score {
+        self.blend(phase, |&x| x)
+    }
+}
+
+/// Returns how much the opening, midgame and endgame weights count in a phase, the three factors
+/// adding up to [`MIDGAME_EMPTIES`](constant.MIDGAME_EMPTIES.html).
+pub fn phase_factors(phase: Phase) -> (Score, Score, Score) {
+    let (empties, midgame) = (phase.empties.min(60) as Score, MIDGAME_EMPTIES as Score);
+    if empties >= midgame {
+        // between the opening and the midgame
+        let opening = (empties - midgame) * midgame / (60 - midgame);
+        (opening, midgame - opening, 0)
+    } else {
+        (0, empties, midgame - empties)
+    }
+}
+
+/// Counts the legal moves of a player.
+pub fn mobility(board: &Board, color: Color) -> usize {
+    board.legal_moves(color).len()
+}
+
+/// Counts the empty cells next to the pieces of the opponent of `color`.
+///
+/// These are the cells where the player may get a move later, so it estimates future mobility.
+pub fn potential_mobility(board: &Board, color: Color) -> usize {
+    let opponent = Some(color.flip());
+    let mut count = 0;
+    for x in 0..8 {
+        for y in 0..8 {
+            if board.cells[x as usize][y as usize].is_some() {
+                continue;
+            }
+            let frontier = DIRECTIONS.iter().any(|&(dx, dy)| {
+                let (nx, ny) = (x + dx, y + dy);
+                (0..8).contains(&nx) && (0..8).contains(&ny) && board.cells[nx as usize][ny as usize] == opponent
+            });
+            if frontier {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+/// Finds the pieces of a player that can never be flipped again.
+///
+/// A piece is stable if along each of the four axes through it the line is full, or it has a
+/// stable neighbor of the same color or the edge of the board on one side. Stability grows from
+/// the corners along the edges and inwards: every piece is checked once, and a piece found
+/// stable has its neighbors of the same color checked again, as they may now be anchored to it.
+/// The result is conservative: every reported piece is stable, but some stable pieces may be
+/// missed.
+pub fn stable_disks(board: &Board, color: Color) -> [[bool; 8]; 8] {
+    const AXES: [Coord; 4] = [(1, 0), (0, 1), (1, 1), (1, -1)];
+    // a line without empty cells cannot be flipped along, whatever the pieces are
+    let mut full_columns = [true; 8];
+    let mut full_rows = [true; 8];
+    let mut full_diagonals = [true; 15];
+    let mut full_antidiagonals = [true; 15];
+    for x in 0..8 {
+        for y in 0..8 {
+            if board.cells[x][y].is_none() {
+                full_columns[x] = false;
+                full_rows[y] = false;
+                full_diagonals[x + 7 - y] = false;
+                full_antidiagonals[x + y] = false;
+            }
+        }
+    }
+    let mut stable = [[false; 8]; 8];
+    let anchored = |stable: &[[bool; 8]; 8], x: i8, y: i8| {
+        !(0..8).contains(&x) || !(0..8).contains(&y) || stable[x as usize][y as usize]
+    };
+    let mut pending = Vec::with_capacity(64);
+    for x in 0..8 {
+        for y in 0..8 {
+            if board.cells[x as usize][y as usize] == Some(color) {
+                pending.push((x, y));
+            }
+        }
+    }
+    while let Some((x, y)) = pending.pop() {
+        if stable[x as usize][y as usize] {
+            continue;
+        }
+        let is_stable = AXES.iter().enumerate().all(|(axis, &(dx, dy))| {
+            let full = match axis {
+                0 => full_rows[y as usize],
+                1 => full_columns[x as usize],
+                2 => full_diagonals[(x + 7 - y) as usize],
+                _ => full_antidiagonals[(x + y) as usize]
+            };
+            full || anchored(&stable, x - dx, y - dy) || anchored(&stable, x + dx, y + dy)
+        });
+        if !is_stable {
+            continue;
+        }
+        stable[x as usize][y as usize] = true;
+        for &(dx, dy) in &DIRECTIONS {
+            let (nx, ny) = (x + dx, y + dy);
+            if (0..8).contains(&nx) && (0..8).contains(&ny) && !stable[nx as usize][ny as usize] && board.cells[nx as usize][ny as usize] == Some(color) {
+                pending.push((nx, ny));
+            }
+        }
+    }
+    stable
+}
+
+/// Counts the stable pieces of a player, see [`stable_disks`](fn.stable_disks.html).
+pub fn stability(board: &Board, color: Color) -> usize {
+    stable_disks(board, color).iter().map(|column| column.iter().filter(|&&x| x).count()).sum()
+}
+
+/// The contribution of each term of a [`Heuristic`](struct.Heuristic.html) to the score.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Breakdown {
+    /// Value of the occupied cells.
+    pub squares: Score,
+    /// Value of having more legal moves than the opponent.
+    pub mobility: Score,
+    /// Value of having more empty cells next to the opponent's pieces than the opponent does.
+    pub potential_mobility: Score,
+    /// Value of the pieces that cannot be flipped anymore.
+    pub stability: Score
+}
+impl Breakdown {
+    /// The score is the sum of the terms.
+    pub fn total(&self) -> Score {
+        self.squares + self.mobility + self.potential_mobility + self.stability
+    }
+}
+
+/// Evaluates positions by combining the positional value of cells with mobility and stability.
+///
+/// Each term has its own weights for every phase of the game. Mobility matters most in the
+/// opening and the midgame, where having moves to choose from prevents being forced to give away
+/// corners. Stable pieces are certain to count at the end of the game, so they are worth much
+/// more than any other piece.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Heuristic {
+    pub squares: Phased<WeightedSquares>,
+    /// Weight of one legal move more than the opponent.
+    pub mobility: Phased<Score>,
+    /// Weight of one frontier cell more than the opponent.
+    pub potential_mobility: Phased<Score>,
+    /// Weight of one stable piece more than the opponent.
+    pub stability: Phased<Score>
+}
+impl Heuristic {
+    /// Computes the value of each term separately.
+    pub fn breakdown(&self, board: &Board, color: Color, phase: Phase) -> Breakdown {
+        let opponent = color.flip();
+        let mobility = mobility(board, color) as Score - mobility(board, opponent) as Score;
+        let potential = potential_mobility(board, color) as Score - potential_mobility(board, opponent) as Score;
+        let stability = stability(board, color) as Score - stability(board, opponent) as Score;
+        Breakdown {
+            squares: self.squares.blend(phase, |squares| squares.evaluate(board, color, phase)),
+            mobility: self.mobility.at(phase) * mobility,
+            potential_mobility: self.potential_mobility.at(phase) * potential,
+            stability: self.stability.at(phase) * stability
+        }
+    }
+}
+impl Default for Heuristic {
+    fn default() -> Heuristic {
+        Heuristic {
+            squares: Phased::uniform(WeightedSquares::default()),
+            mobility: Phased { opening: 10, midgame: 6, endgame: 2 },
+            potential_mobility: Phased { opening: 4, midgame: 2, endgame: 0 },
+            stability: Phased { opening: 30, midgame: 22, endgame: 15 }
+        }
+    }
+}
+impl Heuristic {
+    /// Reads weights written by [`write`](#method.write).
+    ///
+    /// The format is line based, each line holding a term name, an equals sign and the weights
+    /// separated by whitespace. The squares are given by their octant, on separate lines for each
+    /// phase, the other terms by their opening, midgame and endgame weights. Empty lines and lines
+    /// starting with `#` are ignored.
+    pub fn read<R: BufRead>(reader: R) -> io::Result<Heuristic> {
+        fn phased(values: &[Score]) -> Option<Phased<Score>> {
+            match *values {
+                [opening, midgame, endgame] => Some(Phased { opening, midgame, endgame }),
+                _ => None
+            }
+        }
+        let mut heuristic = Heuristic::default();
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.splitn(2, '=');
+            let name = parts.next().unwrap_or("").trim();
+            let values = parts.next().ok_or_else(|| invalid(&format!("missing '=' in line '{}'", line)))?;
+            let values = values.split_whitespace().map(|x| x.parse()).collect::<Result<Vec<Score>, _>>()
+                .map_err(|e| invalid(&format!("invalid weight for {}: {}", name, e)))?;
+            let wrong_count = || invalid(&format!("wrong number of weights for {}", name));
+            let octant = || {
+                let mut octant = [0; 10];
+                if values.len() != octant.len() {
+                    return Err(wrong_count());
+                }
+                octant.copy_from_slice(&values);
+                Ok(WeightedSquares::from_octant(octant))
+            };
+            match name {
+                "squares.opening" => heuristic.squares.opening = octant()?,
+                "squares.midgame" => heuristic.squares.midgame = octant()?,
+                "squares.endgame" => heuristic.squares.endgame = octant()?,
+                "mobility" => heuristic.mobility = phased(&values).ok_or_else(wrong_count)?,
+                "potential_mobility" => heuristic.potential_mobility = phased(&values).ok_or_else(wrong_count)?,
+                "stability" => heuristic.stability = phased(&values).ok_or_else(wrong_count)?,
+                _ => return Err(invalid(&format!("unknown term {}", name)))
+            }
+        }
+        Ok(heuristic)
+    }
+
+    /// Writes the weights in the format accepted by [`read`](#method.read).
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let squares = [("opening", &self.squares.opening), ("midgame", &self.squares.midgame), ("endgame", &self.squares.endgame)];
+        for &(phase, squares) in &squares {
+            let octant = squares.octant().iter().map(|x| x.to_string()).collect::<Vec<_>>();
+            writeln!(writer, "squares.{} = {}", phase, octant.join(" "))?;
+        }
+        let terms = [("mobility", &self.mobility), ("potential_mobility", &self.potential_mobility), ("stability", &self.stability)];
+        for &(name, weights) in &terms {
+            writeln!(writer, "{} = {} {} {}", name, weights.opening, weights.midgame, weights.endgame)?;
+        }
+        Ok(())
+    }
+
+    /// Reads the weights from a file.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Heuristic> {
+        Heuristic::read(BufReader::new(File::open(path)?))
+    }
+
+    /// Writes the weights to a file, replacing its contents.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()
+    }
+}
+impl Evaluator for Heuristic {
+    fn evaluate(&self, board: &Board, color: Color, phase: Phase) -> Score {
+        self.breakdown(board, color, phase).total()
+    }
+}
+
+/// Version of the layout of [`FeatureVector`](struct.FeatureVector.html).
+///
+/// It is increased whenever features are added, removed or reordered, so that weights trained
+/// on an older layout are not silently misinterpreted.
+pub const FEATURES_VERSION: u32 = 1;
+
+/// Number of features in a [`FeatureVector`](struct.FeatureVector.html).
+pub const FEATURE_COUNT: usize = 18;
+
+/// Names of the features, in the order they appear in a [`FeatureVector`](struct.FeatureVector.html).
+pub const FEATURE_NAMES: [&str; FEATURE_COUNT] = [
+    "own_disks",
+    "opponent_disks",
+    "empties",
+    "own_mobility",
+    "opponent_mobility",
+    "own_potential_mobility",
+    "opponent_potential_mobility",
+    "own_stable_disks",
+    "opponent_stable_disks",
+    "parity",
+    "edge_south",
+    "edge_north",
+    "edge_west",
+    "edge_east",
+    "corner_southwest",
+    "corner_southeast",
+    "corner_northwest",
+    "corner_northeast"
+];
+
+/// Numeric description of a position from the point of view of a player, meant as the input of
+/// machine learning pipelines training evaluation weights.
+///
+/// The values are, in order:
+///
+/// * `0..3`: number of own pieces, opponent pieces and empty cells.
+/// * `3..5`: number of legal moves of the player and of the opponent.
+/// * `5..7`: [potential mobility](fn.potential_mobility.html) of the player and of the opponent.
+/// * `7..9`: number of [stable](fn.stable_disks.html) pieces of the player and of the opponent.
+/// * `9`: 1 if the number of empty cells is odd, meaning the player to move would get the last
+///   move if nobody skips, 0 otherwise.
+/// * `10..14`: pattern index of the south (`y = 0`), north (`y = 7`), west (`x = 0`) and east
+///   (`x = 7`) edges, read in increasing coordinate order.
+/// * `14..18`: pattern index of the 3x3 squares in the southwest, southeast, northwest and
+///   northeast corners, read from the corner outwards column by column.
+///
+/// A pattern index is the cells read as a base 3 number, the first cell being the least
+/// significant digit, where an empty cell is 0, an own piece is 1 and an opponent piece is 2.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FeatureVector {
+    pub values: [i32; FEATURE_COUNT]
+}
+
+/// Extracts the features of `board` from the point of view of `color`.
+pub fn features(board: &Board, color: Color) -> FeatureVector {
+    let opponent = color.flip();
+    let pattern = |cells: &mut dyn Iterator<Item = Coord>| {
+        cells.fold((0, 1), |(index, place), (x, y)| {
+            let digit = match board.cells[x as usize][y as usize] {
+                None => 0,
+                Some(found) if found == color => 1,
+                Some(_) => 2
+            };
+            (index + digit * place, place * 3)
+        }).0
+    };
+    let corner = |cx: i8, cy: i8| {
+        // steps away from the corner
+        let (sx, sy) = (if cx == 0 { 1 } else { -1 }, if cy == 0 { 1 } else { -1 });
+        pattern(&mut (0..3).flat_map(|i| (0..3).map(move |j| (cx + sx * i, cy + sy * j))))
+    };
+    let empties = board.empties() as i32;
+    FeatureVector {
+        values: [
+            board.count(color) as i32,
+            board.count(opponent) as i32,
+            empties,
+            mobility(board, color) as i32,
+            mobility(board, opponent) as i32,
+            potential_mobility(board, color) as i32,
+            potential_mobility(board, opponent) as i32,
+            stability(board, color) as i32,
+            stability(board, opponent) as i32,
+            empties % 2,
+            pattern(&mut (0..8).map(|x| (x, 0))),
+            pattern(&mut (0..8).map(|x| (x, 7))),
+            pattern(&mut (0..8).map(|y| (0, y))),
+            pattern(&mut (0..8).map(|y| (7, y))),
+            corner(0, 0),
+            corner(7, 0),
+            corner(0, 7),
+            corner(7, 7)
+        ]
+    }
+}
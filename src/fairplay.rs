@@ -0,0 +1,117 @@
+//! This module rates how closely a player's moves follow an engine over some games, to help the
+//! operators of game servers spot players getting help from an engine.
+//!
+//! The games are first [annotated](../analysis/fn.annotate.html) with the engine. Moves without
+//! a choice tell nothing and are left out. A [`Report`](struct.Report.html) gives the share of
+//! the moves reaching the best score of the engine, the average loss against it, and a suspicion
+//! score: how many standard deviations the number of best moves is above the one expected of a
+//! strong player without help. Above 3 it deserves a closer look, but few moves or an engine
+//! weaker than the player make it a hint rather than a proof.
+
+use analysis::AnnotatedGame;
+use eval::Score;
+use formats::json::Value;
+use game::Color;
+use search::WIN;
+
+/// What is expected of a strong player without help.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Options {
+    /// Share of the moves where the player finds a best move of the engine.
+    pub expected_accuracy: f64
+}
+impl Default for Options {
+    fn default() -> Options {
+        Options { expected_accuracy: 0.6 }
+    }
+}
+
+/// The quality of the moves of a player over some games.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub player: String,
+    /// Games the player played in.
+    pub games: usize,
+    /// Moves of the player with more than one legal choice.
+    pub moves: usize,
+    /// Moves reaching the best score of the engine.
+    pub best_moves: usize,
+    /// Percentage of the moves reaching the best score.
+    pub accuracy: f64,
+    /// Average loss of a move against the best one, in disks for solved positions and in the
+    /// units of the engine otherwise.
+    pub average_loss: f64,
+    /// Standard deviations of the best moves above the expected ones.
+    pub suspicion: f64
+}
+impl Report {
+    /// Writes the report on a few lines.
+    pub fn format(&self) -> String {
+        format!(
+            "{}: {} games, {} moves\naccuracy {:.1}% ({} best moves), average loss {:.2}\nsuspicion {:+.2}\n",
+            self.player, self.games, self.moves, self.accuracy, self.best_moves, self.average_loss, self.suspicion
+        )
+    }
+
+    /// The report as a JSON object, for the tools of servers.
+    pub fn to_json(&self) -> Value {
+        Value::Object(vec![
+            ("player".to_string(), Value::String(self.player.clone())),
+            ("games".to_string(), Value::Number(self.games as f64)),
+            ("moves".to_string(), Value::Number(self.moves as f64)),
+            ("best_moves".to_string(), Value::Number(self.best_moves as f64)),
+            ("accuracy".to_string(), Value::Number(self.accuracy)),
+            ("average_loss".to_string(), Value::Number(self.average_loss)),
+            ("suspicion".to_string(), Value::Number(self.suspicion))
+        ])
+    }
+}
+
+/// Reports on the moves of a player, by name, in annotated games. Games with illegal moves are
+/// left out.
+pub fn report(games: &[AnnotatedGame], player: &str, options: &Options) -> Report {
+    let (mut played, mut moves, mut best_moves, mut loss) = (0, 0, 0, 0i64);
+    for game in games {
+        let record = &game.record;
+        if record.black != player && record.white != player {
+            continue;
+        }
+        let positions = match record.positions() {
+            Ok(positions) => positions,
+            Err(_) => continue
+        };
+        played += 1;
+        for (annotation, &(board, color)) in game.annotations.iter().zip(&positions) {
+            let name = if color == Color::Black { &record.black } else { &record.white };
+            if name != player || board.legal_moves(color).len() < 2 {
+                continue;
+            }
+            moves += 1;
+            if annotation.loss() == 0 {
+                best_moves += 1;
+            }
+            loss += i64::from((disks(annotation.best_score) - disks(annotation.score)).max(0));
+        }
+    }
+    let n = moves as f64;
+    let p = options.expected_accuracy;
+    let deviation = (n * p * (1.0 - p)).sqrt();
+    Report {
+        player: player.to_string(),
+        games: played,
+        moves,
+        best_moves,
+        accuracy: if moves == 0 { 0.0 } else { 100.0 * best_moves as f64 / n },
+        average_loss: if moves == 0 { 0.0 } else { loss as f64 / n },
+        suspicion: if deviation > 0.0 { (best_moves as f64 - n * p) / deviation } else { 0.0 }
+    }
+}
+
+// The disk differential of a solved score, other scores being left as they are.
+fn disks(score: Score) -> Score {
+    if score.abs() >= WIN / 2 {
+        score - score.signum() * WIN
+    } else {
+        score
+    }
+}
@@ -0,0 +1,139 @@
+//! This module runs endgame test suites, the standard benchmark of Othello solvers.
+//!
+//! Suites are written one problem per line as in the `.obf` files of Edax: a position as in the
+//! [records](../record/index.html), then the best moves with their exact scores, like
+//! `...X; A2:+38;`. Text after `%` is a comment.
+//!
+//! The usual benchmark is the FFO suite of the French federation's forum, from #40 to #59, which
+//! Edax distributes as `fforum-40-59.obf`. Its first positions, #40 to #43, are included as
+//! [`FFO_40_43`](constant.FFO_40_43.html), with their scores and best moves checked by another
+//! solver; the others are [loaded](fn.load.html) from the file.
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use std::time::Duration;
+
+use endgame::Solver;
+use engine::{Engine, Limits};
+use game::{Board, Color, Coord};
+use record;
+use search::WIN;
+
+/// The positions #40 to #43 of the FFO suite, named by their number.
+pub const FFO_40_43: &str = include_str!("ffo_40_43.obf");
+
+/// A position with its known solution.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    pub name: String,
+    pub board: Board,
+    pub color: Color,
+    /// The moves reaching the exact score, any of them is a correct answer.
+    pub best_moves: Vec<Coord>,
+    /// Final disk differential for the player to move.
+    pub score: i8
+}
+
+/// The outcome of solving a problem.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub name: String,
+    /// The move found, `None` when the player has to skip or the game has ended.
+    pub best_move: Option<Coord>,
+    pub score: i8,
+    /// Whether the score is the known one and the move one of the known best moves.
+    pub correct: bool,
+    pub nodes: u64,
+    pub time: Duration
+}
+
+/// Reads problems, named by their line number unless the comment of a line gives a name.
+pub fn parse(text: &str) -> io::Result<Vec<Problem>> {
+    let mut problems = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let (content, comment) = match line.find('%') {
+            Some(j) => (&line[..j], line[j + 1..].trim()),
+            None => (line, "")
+        };
+        if content.trim().is_empty() {
+            continue;
+        }
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", i + 1, message));
+        let mut fields = content.split(';').map(str::trim).filter(|x| !x.is_empty());
+        let (board, color) = fields.next().and_then(record::parse_position).ok_or_else(|| invalid("invalid position"))?;
+        let mut solutions = Vec::new();
+        for field in fields {
+            let mut parts = field.splitn(2, ':');
+            let cell = parts.next().unwrap_or("").trim();
+            let best_move = if cell.eq_ignore_ascii_case("ps") { None } else { Some(record::parse_move(cell).ok_or_else(|| invalid("invalid move"))?) };
+            let score = parts.next().and_then(|x| x.trim().parse::<i8>().ok()).ok_or_else(|| invalid("invalid score"))?;
+            solutions.push((best_move, score));
+        }
+        let score = solutions.iter().map(|x| x.1).max().ok_or_else(|| invalid("missing solution"))?;
+        problems.push(Problem {
+            name: if comment.is_empty() { (i + 1).to_string() } else { comment.to_string() },
+            board,
+            color,
+            best_moves: solutions.iter().filter(|x| x.1 == score).filter_map(|x| x.0).collect(),
+            score
+        });
+    }
+    Ok(problems)
+}
+
+/// Writes problems one per line, in the format read by [`parse`](fn.parse.html), with their
+/// names as comments.
+pub fn format(problems: &[Problem]) -> String {
+    let mut text = String::new();
+    for problem in problems {
+        text += &record::format_position(&problem.board, problem.color);
+        for &position in &problem.best_moves {
+            text += &format!("; {}:{:+}", record::format_move(position).to_uppercase(), problem.score);
+        }
+        if problem.best_moves.is_empty() {
+            text += &format!("; PS:{:+}", problem.score);
+        }
+        text += &format!("; % {}\n", problem.name);
+    }
+    text
+}
+
+/// Reads problems from a file.
+pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Vec<Problem>> {
+    let mut text = String::new();
+    File::open(path)?.read_to_string(&mut text)?;
+    parse(&text)
+}
+
+/// The FFO positions #40 to #43.
+pub fn ffo_40_43() -> Vec<Problem> {
+    parse(FFO_40_43).expect("the included suite is valid")
+}
+
+/// Solves a problem exactly.
+pub fn solve(problem: &Problem, solver: &mut Solver) -> Report {
+    let result = solver.best_move(&problem.board, problem.color, &Limits::default());
+    let score = (result.score - result.score.signum() * WIN) as i8;
+    let best_move_correct = match result.best {
+        Some(position) => problem.best_moves.contains(&position),
+        None => problem.best_moves.is_empty()
+    };
+    Report {
+        name: problem.name.clone(),
+        best_move: result.best,
+        score,
+        correct: score == problem.score && best_move_correct,
+        nodes: result.nodes,
+        time: result.time
+    }
+}
+
+/// Solves problems in order, calling back with the report of each as soon as it is solved.
+pub fn run<F: FnMut(&Report)>(problems: &[Problem], solver: &mut Solver, mut callback: F) -> Vec<Report> {
+    problems.iter().map(|problem| {
+        let report = solve(problem, solver);
+        callback(&report);
+        report
+    }).collect()
+}
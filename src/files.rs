@@ -0,0 +1,61 @@
+//! Helpers shared by the modules reading and writing files and messages.
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Writes a file through `write`, replacing its contents. The file is written to a temporary
+/// file next to it first and then renamed over it, so a crash while saving leaves the previous
+/// save intact.
+pub fn save_atomically<P: AsRef<Path>, F: FnOnce(&mut BufWriter<File>) -> io::Result<()>>(path: P, write: F) -> io::Result<()> {
+    let path = path.as_ref();
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    let mut writer = BufWriter::new(File::create(&temporary)?);
+    write(&mut writer)?;
+    writer.into_inner().map_err(|x| x.into_error())?.sync_all()?;
+    fs::rename(&temporary, path)
+}
+
+/// Reads a fixed number of bytes.
+pub fn read_bytes<R: Read, const N: usize>(reader: &mut R) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Reads a little-endian 16-bit number.
+pub fn read_u16<R: Read>(reader: &mut R) -> io::Result<u16> {
+    read_bytes(reader).map(u16::from_le_bytes)
+}
+
+/// Reads a little-endian 64-bit float.
+pub fn read_f64<R: Read>(reader: &mut R) -> io::Result<f64> {
+    read_bytes(reader).map(f64::from_le_bytes)
+}
+
+/// Reads a text preceded by its length in bytes on 16 bits.
+pub fn read_string<R: Read>(reader: &mut R) -> io::Result<String> {
+    let mut bytes = vec![0; usize::from(read_u16(reader)?)];
+    reader.read_exact(&mut bytes)?;
+    String::from_utf8(bytes).map_err(|_| invalid("invalid text"))
+}
+
+/// Writes a text preceded by its length in bytes on 16 bits, refusing longer ones.
+pub fn write_string<W: Write>(writer: &mut W, text: &str) -> io::Result<()> {
+    if text.len() > usize::from(u16::MAX) {
+        return Err(invalid_input("text too long"));
+    }
+    writer.write_all(&(text.len() as u16).to_le_bytes())?;
+    writer.write_all(text.as_bytes())
+}
+
+/// The error of malformed data.
+pub fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// The error of malformed arguments or commands.
+pub fn invalid_input(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message.to_string())
+}
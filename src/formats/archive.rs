@@ -0,0 +1,294 @@
+//! This module stores large numbers of games, like the ones of self-play runs, compressed with
+//! zstd while keeping each game readable by its number.
+//!
+//! An archive starts with the magic bytes `RRARCH` and a 16 bit version, followed by blocks of
+//! consecutive games, each compressed as a zstd frame, then the index of the blocks. Each entry
+//! of the index gives the offset of a block from the start of the file as 64 bits, its
+//! compressed length and its number of games as 32 bits. The archive ends with the number of
+//! blocks as 32 bits and the offset of the index as 64 bits. All integers are little endian.
+//!
+//! In a block, a game is written as its players, its event and its date, each a string of its
+//! 16 bit length followed by its UTF-8 bytes, the disks of Black and the theoretical disks of
+//! Black, 255 when unknown, the number of moves as a byte followed by their cells `y * 8 + x`,
+//! then the 16 bit number of tags followed by their names and values.
+//!
+//! Games are numbered from 0 in the order they were added. Reading a game decompresses its
+//! block only, and the last block read is kept, so games close to each other are read fast.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use files::{invalid, read_bytes, read_string, write_string};
+use random::Random;
+use record::GameRecord;
+
+const MAGIC: &[u8; 6] = b"RRARCH";
+const VERSION: u16 = 1;
+
+// Size of the end of the archive, after the index.
+const FOOTER_SIZE: u64 = 12;
+
+// Disk count of an unknown result.
+const UNKNOWN: u8 = 255;
+
+/// Writes an archive, a block at a time.
+pub struct ArchiveWriter<W: Write> {
+    writer: W,
+    /// Number of games in a block.
+    block_size: u32,
+    /// Compression level of zstd, from 1 to 22.
+    level: i32,
+    // offset of the next block
+    offset: u64,
+    // number of games added
+    len: u64,
+    block: Vec<u8>,
+    block_games: u32,
+    index: Vec<Block>
+}
+impl<W: Write> ArchiveWriter<W> {
+    /// Starts an archive with blocks of the given number of games. Larger blocks compress
+    /// better, smaller ones are faster to read a game from.
+    pub fn new(mut writer: W, block_size: u32) -> io::Result<ArchiveWriter<W>> {
+        if block_size == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "empty blocks"));
+        }
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        Ok(ArchiveWriter {
+            writer,
+            block_size,
+            level: 19,
+            offset: (MAGIC.len() + 2) as u64,
+            len: 0,
+            block: Vec::new(),
+            block_games: 0,
+            index: Vec::new()
+        })
+    }
+
+    /// Sets the compression level of the next blocks, from 1 to 22.
+    pub fn set_level(&mut self, level: i32) {
+        self.level = level;
+    }
+
+    /// Adds a game and returns its number. Fails if it has more than 255 moves or a string
+    /// longer than 65535 bytes.
+    pub fn push(&mut self, record: &GameRecord) -> io::Result<u64> {
+        let length = self.block.len();
+        if let Err(error) = write_record(&mut self.block, record) {
+            self.block.truncate(length);
+            return Err(error);
+        }
+        self.block_games += 1;
+        self.len += 1;
+        if self.block_games == self.block_size {
+            self.write_block()?;
+        }
+        Ok(self.len - 1)
+    }
+
+    /// Writes the last block and the index, and returns the writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        if self.block_games > 0 {
+            self.write_block()?;
+        }
+        for block in &self.index {
+            self.writer.write_all(&block.offset.to_le_bytes())?;
+            self.writer.write_all(&block.length.to_le_bytes())?;
+            self.writer.write_all(&block.games.to_le_bytes())?;
+        }
+        self.writer.write_all(&(self.index.len() as u32).to_le_bytes())?;
+        self.writer.write_all(&self.offset.to_le_bytes())?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write_block(&mut self) -> io::Result<()> {
+        let compressed = zstd::bulk::compress(&self.block, self.level)?;
+        self.writer.write_all(&compressed)?;
+        self.index.push(Block { offset: self.offset, length: compressed.len() as u32, games: self.block_games });
+        self.offset += compressed.len() as u64;
+        self.block.clear();
+        self.block_games = 0;
+        Ok(())
+    }
+}
+
+impl ArchiveWriter<BufWriter<File>> {
+    /// Creates an archive file, replacing its contents.
+    pub fn create<P: AsRef<Path>>(path: P, block_size: u32) -> io::Result<ArchiveWriter<BufWriter<File>>> {
+        ArchiveWriter::new(BufWriter::new(File::create(path)?), block_size)
+    }
+}
+
+/// Reads the games of an archive by their numbers.
+pub struct Archive<R: Read + Seek> {
+    reader: R,
+    index: Vec<Block>,
+    // number of the first game of each block
+    starts: Vec<u64>,
+    len: u64,
+    // the last block read, by its position in the index
+    cache: Option<(usize, Vec<GameRecord>)>
+}
+impl<R: Read + Seek> Archive<R> {
+    /// Reads the index of an archive.
+    pub fn new(mut reader: R) -> io::Result<Archive<R>> {
+        let mut header = [0; 8];
+        reader.seek(SeekFrom::Start(0))?;
+        reader.read_exact(&mut header)?;
+        if &header[..6] != MAGIC {
+            return Err(invalid("not a game archive"));
+        }
+        if u16::from_le_bytes([header[6], header[7]]) != VERSION {
+            return Err(invalid("unsupported game archive version"));
+        }
+        let end = reader.seek(SeekFrom::End(0))?;
+        if end < header.len() as u64 + FOOTER_SIZE {
+            return Err(invalid("truncated game archive"));
+        }
+        reader.seek(SeekFrom::Start(end - FOOTER_SIZE))?;
+        let blocks = u32::from_le_bytes(read_bytes(&mut reader)?);
+        let offset = u64::from_le_bytes(read_bytes(&mut reader)?);
+        if offset.checked_add(u64::from(blocks) * 16) != Some(end - FOOTER_SIZE) {
+            return Err(invalid("invalid index"));
+        }
+        reader.seek(SeekFrom::Start(offset))?;
+        let mut index = Vec::with_capacity(blocks as usize);
+        let mut starts = Vec::with_capacity(blocks as usize);
+        let mut len = 0;
+        for _ in 0..blocks {
+            let block = Block {
+                offset: u64::from_le_bytes(read_bytes(&mut reader)?),
+                length: u32::from_le_bytes(read_bytes(&mut reader)?),
+                games: u32::from_le_bytes(read_bytes(&mut reader)?)
+            };
+            if block.offset > offset || u64::from(block.length) > offset - block.offset {
+                return Err(invalid("block outside of the archive"));
+            }
+            starts.push(len);
+            len += u64::from(block.games);
+            index.push(block);
+        }
+        Ok(Archive { reader, index, starts, len, cache: None })
+    }
+
+    /// Number of games.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Reads a game by its number.
+    pub fn get(&mut self, number: u64) -> io::Result<GameRecord> {
+        if number >= self.len {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "no such game"));
+        }
+        let block = match self.starts.binary_search(&number) {
+            Ok(mut i) => {
+                // skips empty blocks
+                while self.index[i].games == 0 {
+                    i += 1;
+                }
+                i
+            }
+            Err(i) => i - 1
+        };
+        let start = self.starts[block];
+        let games = self.block(block)?;
+        Ok(games[(number - start) as usize].clone())
+    }
+
+    /// Reads distinct games picked at random, in the order of their numbers.
+    pub fn sample(&mut self, count: usize, random: &mut Random) -> io::Result<Vec<GameRecord>> {
+        let count = (count as u64).min(self.len);
+        // Floyd's algorithm picks distinct numbers without going through all of them
+        let mut numbers = Vec::with_capacity(count as usize);
+        for i in self.len - count..self.len {
+            let number = random.below(i as usize + 1) as u64;
+            numbers.push(if numbers.contains(&number) { i } else { number });
+        }
+        numbers.sort_unstable();
+        numbers.into_iter().map(|x| self.get(x)).collect()
+    }
+
+    // The games of a block, reading it unless it is the cached one.
+    fn block(&mut self, i: usize) -> io::Result<&[GameRecord]> {
+        if self.cache.as_ref().map(|x| x.0) != Some(i) {
+            let block = self.index[i];
+            self.reader.seek(SeekFrom::Start(block.offset))?;
+            let mut compressed = vec![0; block.length as usize];
+            self.reader.read_exact(&mut compressed)?;
+            let bytes = zstd::decode_all(&compressed[..]).map_err(|_| invalid("corrupt block"))?;
+            let mut reader = &bytes[..];
+            let games = (0..block.games).map(|_| read_record(&mut reader)).collect::<io::Result<Vec<_>>>()?;
+            self.cache = Some((i, games));
+        }
+        Ok(&self.cache.as_ref().expect("the block was just read").1)
+    }
+}
+
+impl Archive<BufReader<File>> {
+    /// Opens an archive file.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Archive<BufReader<File>>> {
+        Archive::new(BufReader::new(File::open(path)?))
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Block {
+    offset: u64,
+    length: u32,
+    games: u32
+}
+
+fn write_record(bytes: &mut Vec<u8>, record: &GameRecord) -> io::Result<()> {
+    for text in &[&record.black, &record.white, &record.event, &record.date] {
+        write_string(bytes, text)?;
+    }
+    if record.moves.len() > 255 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "too many moves"));
+    }
+    if record.tags.len() > usize::from(u16::MAX) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "too many tags"));
+    }
+    bytes.push(record.black_disks.unwrap_or(UNKNOWN));
+    bytes.push(record.theoretical_disks.unwrap_or(UNKNOWN));
+    bytes.push(record.moves.len() as u8);
+    bytes.extend(record.moves.iter().map(|&(x, y)| (y * 8 + x) as u8));
+    bytes.extend_from_slice(&(record.tags.len() as u16).to_le_bytes());
+    for (name, value) in &record.tags {
+        write_string(bytes, name)?;
+        write_string(bytes, value)?;
+    }
+    Ok(())
+}
+
+fn read_record(reader: &mut &[u8]) -> io::Result<GameRecord> {
+    let mut record = GameRecord {
+        black: read_string(reader)?,
+        white: read_string(reader)?,
+        event: read_string(reader)?,
+        date: read_string(reader)?,
+        ..GameRecord::default()
+    };
+    let [black_disks, theoretical_disks, moves] = read_bytes(reader)?;
+    record.black_disks = if black_disks == UNKNOWN { None } else { Some(black_disks) };
+    record.theoretical_disks = if theoretical_disks == UNKNOWN { None } else { Some(theoretical_disks) };
+    for _ in 0..moves {
+        let [cell] = read_bytes(reader)?;
+        if cell >= 64 {
+            return Err(invalid("move outside of the board"));
+        }
+        record.moves.push(((cell % 8) as i8, (cell / 8) as i8));
+    }
+    for _ in 0..u16::from_le_bytes(read_bytes(reader)?) {
+        record.tags.push((read_string(reader)?, read_string(reader)?));
+    }
+    Ok(record)
+}
@@ -0,0 +1,37 @@
+//! This module exports the moves of annotated games as CSV tables for spreadsheets.
+//!
+//! Each move is a row with its number from 1, the player, the cell, the score of the engine for
+//! the player, the move preferred by the engine and its score, the disk difference of Black
+//! after the move, the number of legal moves the player had and the time spent in
+//! milliseconds. Unknown times are left empty.
+
+use std::io::{self, Write};
+
+use analysis::{self, AnnotatedGame};
+use record;
+
+/// The first line of the table.
+pub const HEADER: &str = "move,player,cell,score,best_move,best_score,disk_difference,mobility,time_ms";
+
+/// Writes the moves of a game with the header line. Fails if one of its moves is illegal.
+pub fn write_moves<W: Write>(mut writer: W, game: &AnnotatedGame) -> io::Result<()> {
+    let positions = game.record.positions().map_err(|i| {
+        io::Error::new(io::ErrorKind::InvalidInput, format!("illegal move {}", i + 1))
+    })?;
+    writeln!(writer, "{}", HEADER)?;
+    let rows = game.record.moves.iter().zip(&game.annotations).zip(positions.windows(2));
+    for (i, ((&position, annotation), pair)) in rows.enumerate() {
+        let ((board, color), (next, _)) = (pair[0], pair[1]);
+        writeln!(writer, "{},{},{},{},{},{},{},{},{}",
+            i + 1,
+            color.to_string().to_lowercase(),
+            record::format_move(position),
+            annotation.score,
+            annotation.best_move.map(record::format_move).unwrap_or_default(),
+            annotation.best_score,
+            analysis::disk_difference(&next),
+            board.legal_moves(color).len(),
+            annotation.time.map(|x| x.as_millis().to_string()).unwrap_or_default())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,64 @@
+//! This module writes positions as diagrams to paste into blog posts, issues and forum posts: a
+//! Markdown table, or an HTML table styled inline so that it needs no style sheet.
+//!
+//! Row 1 is on top, as on the [text board](../../game/struct.Board.html), and the legal moves
+//! of the player to move are marked with a dot.
+
+use game::{Board, Color};
+
+// Colors of the HTML board.
+const BOARD: &str = "#00783c";
+const GRID: &str = "#003c1e";
+
+/// Writes a position as a Markdown table followed by the player to move.
+pub fn markdown(board: &Board, color: Color) -> String {
+    let moves = board.legal_moves(color);
+    let mut text = "|   | a | b | c | d | e | f | g | h |\n".to_string();
+    text += &format!("|{}\n", ":-:|".repeat(9));
+    for y in (0..8).rev() {
+        text += &format!("| **{}** |", 8 - y);
+        for (x, column) in board.cells.iter().enumerate() {
+            let cell = match column[y] {
+                Some(Color::Black) => "⚫",
+                Some(Color::White) => "⚪",
+                None if moves.iter().any(|m| m.position == (x as i8, y as i8)) => "·",
+                None => " "
+            };
+            text += &format!(" {} |", cell);
+        }
+        text.push('\n');
+    }
+    text += &format!("\n{} to move\n", color);
+    text
+}
+
+/// Writes a position as an HTML table with inline styles, followed by the player to move.
+pub fn html(board: &Board, color: Color) -> String {
+    let moves = board.legal_moves(color);
+    let style = format!("width:32px;height:32px;padding:0;border:1px solid {};background:{};text-align:center", GRID, BOARD);
+    let label = "width:20px;height:20px;padding:0;text-align:center;font:12px sans-serif";
+    let mut html = "<table style=\"border-collapse:collapse\">\n<tr><th></th>".to_string();
+    for column in "abcdefgh".chars() {
+        html += &format!("<th style=\"{}\">{}</th>", label, column);
+    }
+    html += "</tr>\n";
+    for y in (0..8).rev() {
+        html += &format!("<tr><th style=\"{}\">{}</th>", label, 8 - y);
+        for (x, column) in board.cells.iter().enumerate() {
+            let disk = match column[y] {
+                Some(Color::Black) => disk("#141414", 26),
+                Some(Color::White) => disk("#f0f0f0", 26),
+                None if moves.iter().any(|m| m.position == (x as i8, y as i8)) => disk(GRID, 6),
+                None => String::new()
+            };
+            html += &format!("<td style=\"{}\">{}</td>", style, disk);
+        }
+        html += "</tr>\n";
+    }
+    html += &format!("</table>\n<p>{} to move</p>\n", color);
+    html
+}
+
+fn disk(fill: &str, size: u32) -> String {
+    format!("<span style=\"display:inline-block;vertical-align:middle;width:{0}px;height:{0}px;border-radius:50%;background:{1}\"></span>", size, fill)
+}
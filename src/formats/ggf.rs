@@ -0,0 +1,206 @@
+//! This module reads and writes the Generic Game Format of the Generic Game Server, also used by
+//! NBoard.
+//!
+//! A game is enclosed in `(;` and `;)` and lists properties as `KEY[value]`: `PB` and `PW` for
+//! the players, `PC` for the place, `DT` for the date, `RB` and `RW` for their ratings, `TI` for
+//! the clock, `TY` for the board type, `RE` for the disk differential of Black, `BO` for the
+//! starting position, then the moves as `B[f5]` or `W[d6//1.23]`, a skip being `PA`. The
+//! starting position lists the rows from 1 to 8 with `*` for Black, `O` for White and `-` for
+//! an empty cell, preceded by the board size and followed by the player to move.
+//!
+//! Only 8x8 games from the usual starting position are supported. Properties without a field in
+//! [`GameRecord`](../../record/struct.GameRecord.html) are kept as its tags, move annotations
+//! are dropped.
+//!
+//! The archives of the server are dumps of thousands of such games, which are
+//! [imported](fn.import.html) leaving out the ones that can't be read instead of failing.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use files::invalid;
+use game::{Board, Color};
+use record::{self, GameRecord};
+
+/// The games of an archive that could be read, and the ones left out.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Import {
+    pub records: Vec<GameRecord>,
+    /// The numbers of the games left out, from 1, with the reason.
+    pub skipped: Vec<(usize, String)>
+}
+
+/// Reads the games of a text.
+pub fn parse(text: &str) -> io::Result<Vec<GameRecord>> {
+    let mut records = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("(;") {
+        let end = rest[start..].find(";)").ok_or_else(|| invalid("unterminated game"))? + start;
+        records.push(parse_game(&rest[start + 2..end])?);
+        rest = &rest[end + 2..];
+    }
+    Ok(records)
+}
+
+/// Writes a game. Fails if one of its moves is illegal.
+pub fn format(record: &GameRecord) -> io::Result<String> {
+    let positions = record.positions().map_err(|i| {
+        io::Error::new(io::ErrorKind::InvalidInput, format!("illegal move {}", i + 1))
+    })?;
+    let mut text = "(;GM[Othello]".to_string();
+    for &(key, value) in &[("PC", &record.event), ("DT", &record.date), ("PB", &record.black), ("PW", &record.white)] {
+        if !value.is_empty() {
+            text += &property(key, value);
+        }
+    }
+    for (key, value) in &record.tags {
+        text += &property(key, value);
+    }
+    if record.tag("TY").is_none() {
+        text += "TY[8]";
+    }
+    if let Some(disks) = record.black_disks {
+        text += &format!("RE[{:+}.000]", 2 * i32::from(disks) - 64);
+    }
+    text += &property("BO", &board_text(&Board::new(), Color::Black));
+    for (&position, &(_, color)) in record.moves.iter().zip(&positions) {
+        let key = if color == Color::Black { "B" } else { "W" };
+        text += &property(key, &record::format_move(position));
+    }
+    text += ";)";
+    Ok(text)
+}
+
+/// Reads a dump of the archive of the Generic Game Server. Games on other boards, from other
+/// starting positions, with illegal moves or cut off at the end of a truncated dump are skipped.
+/// Unfinished games, without a result, are kept without a final disk count, and the two games of
+/// a synchro match, played at once with colors swapped, are kept as two games. The ratings, the
+/// clocks and the type of game stay in the tags.
+pub fn import(text: &str) -> Import {
+    let mut import = Import::default();
+    let mut rest = text;
+    let mut number = 0;
+    while let Some(start) = rest.find("(;") {
+        number += 1;
+        let end = match rest[start..].find(";)") {
+            Some(end) => end + start,
+            None => {
+                import.skipped.push((number, "unterminated game".to_string()));
+                break;
+            }
+        };
+        let game = parse_game(&rest[start + 2..end]).and_then(|record| match record.positions() {
+            Ok(_) => Ok(record),
+            Err(i) => Err(invalid(&format!("illegal move {}", i + 1)))
+        });
+        match game {
+            Ok(record) => import.records.push(record),
+            Err(error) => import.skipped.push((number, error.to_string()))
+        }
+        rest = &rest[end + 2..];
+    }
+    import
+}
+
+/// Imports the archive of a file.
+pub fn import_file<P: AsRef<Path>>(path: P) -> io::Result<Import> {
+    let mut text = String::new();
+    File::open(path)?.read_to_string(&mut text)?;
+    Ok(import(&text))
+}
+
+/// Reads all the games of a reader.
+pub fn read<R: Read>(mut reader: R) -> io::Result<Vec<GameRecord>> {
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+    parse(&text)
+}
+
+/// Writes games, one per line.
+pub fn write<W: Write>(mut writer: W, records: &[GameRecord]) -> io::Result<()> {
+    for record in records {
+        writeln!(writer, "{}", format(record)?)?;
+    }
+    Ok(())
+}
+
+/// Reads the games of a file.
+pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Vec<GameRecord>> {
+    read(BufReader::new(File::open(path)?))
+}
+
+/// Writes games to a file, replacing its contents.
+pub fn save<P: AsRef<Path>>(path: P, records: &[GameRecord]) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write(&mut writer, records)?;
+    writer.flush()
+}
+
+fn parse_game(text: &str) -> io::Result<GameRecord> {
+    let mut record = GameRecord::default();
+    let mut rest = text.trim_start();
+    while !rest.is_empty() {
+        let open = rest.find('[').ok_or_else(|| invalid("property without a value"))?;
+        let close = rest[open..].find(']').ok_or_else(|| invalid("unterminated property"))? + open;
+        let (key, value) = (rest[..open].trim(), &rest[open + 1..close]);
+        rest = rest[close + 1..].trim_start();
+        match key {
+            "GM" if value != "Othello" => return Err(invalid("not an Othello game")),
+            "GM" => {}
+            "PB" => record.black = value.to_string(),
+            "PW" => record.white = value.to_string(),
+            "PC" => record.event = value.to_string(),
+            "DT" => record.date = value.to_string(),
+            "RE" => {
+                let diff = value.split([':', '.']).next().and_then(|x| x.parse::<i32>().ok());
+                match diff {
+                    Some(diff) if (-64..=64).contains(&diff) => record.black_disks = Some(((64 + diff) / 2) as u8),
+                    _ => record.tags.push((key.to_string(), value.to_string()))
+                }
+            }
+            "TY" => {
+                if value.trim_start_matches('s').trim_end_matches('r') != "8" {
+                    return Err(invalid("only 8x8 games are supported"));
+                }
+                if value != "8" {
+                    record.tags.push((key.to_string(), value.to_string()));
+                }
+            }
+            "BO" => {
+                if value.split_whitespace().collect::<String>() != board_text(&Board::new(), Color::Black).replace(' ', "") {
+                    return Err(invalid("only games from the starting position are supported"));
+                }
+            }
+            "B" | "W" => {
+                let cell = value.split('/').next().unwrap_or("");
+                if !cell.eq_ignore_ascii_case("pa") {
+                    record.moves.push(record::parse_move(cell).ok_or_else(|| invalid("invalid move"))?);
+                }
+            }
+            _ => record.tags.push((key.to_string(), value.to_string()))
+        }
+    }
+    Ok(record)
+}
+
+fn property(key: &str, value: &str) -> String {
+    format!("{}[{}]", key, value.replace(']', ")"))
+}
+
+// The size, the rows from 1 to 8 and the player to move, separated by spaces.
+fn board_text(board: &Board, color: Color) -> String {
+    let mut text = "8".to_string();
+    for y in (0..8).rev() {
+        text.push(' ');
+        for column in &board.cells {
+            text.push(match column[y] {
+                Some(Color::Black) => '*',
+                Some(Color::White) => 'O',
+                None => '-'
+            });
+        }
+    }
+    text.push_str(if color == Color::Black { " *" } else { " O" });
+    text
+}
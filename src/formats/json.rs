@@ -0,0 +1,402 @@
+//! This module writes positions, moves and games as JSON documents for web frontends and other
+//! tools.
+//!
+//! The documents follow a versioned schema kept stable independently of the types of the crate.
+//! Every document is an object with the `schema` version and its `kind`:
+//!
+//! - a `position` has the `board` as 8 strings, the rows from 1 to 8 with `X` for Black, `O` for
+//!   White and `-` for an empty cell, and the player `to_move`, `black` or `white`,
+//! - a `move` has its `color` and its `cell` like `f5`, `null` for a skip,
+//! - a `game` has the `black` and `white` players, the `event`, the `date`, the `moves` as cells,
+//!   the final `black_disks` and `theoretical_disks`, `null` when unknown, and the other `tags`
+//!   as an object.
+//!
+//! Readers accept the versions from [`OLDEST_SCHEMA_VERSION`](constant.OLDEST_SCHEMA_VERSION.html)
+//! to [`SCHEMA_VERSION`](constant.SCHEMA_VERSION.html) and refuse newer documents instead of
+//! misreading them.
+
+use std::fmt;
+use std::io;
+
+use files::invalid;
+use game::{Board, Color, Coord};
+use record::{self, GameRecord};
+
+/// Version of the documents written.
+pub const SCHEMA_VERSION: u32 = 1;
+/// Oldest version of the documents still read.
+pub const OLDEST_SCHEMA_VERSION: u32 = 1;
+/// Arrays and objects nested deeper than this are refused, so that untrusted texts can't
+/// exhaust the stack of the parser.
+pub const MAX_DEPTH: usize = 128;
+
+/// A JSON value. Objects keep the order of their members.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>)
+}
+impl Value {
+    /// Parses a JSON text, with arrays and objects nested up to [`MAX_DEPTH`](constant.MAX_DEPTH.html).
+    pub fn parse(text: &str) -> io::Result<Value> {
+        let mut parser = Parser { chars: text.chars().collect(), index: 0, depth: 0 };
+        let value = parser.value()?;
+        parser.whitespace();
+        if parser.index < parser.chars.len() {
+            return Err(invalid("trailing characters"));
+        }
+        Ok(value)
+    }
+
+    /// The member of an object.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match *self {
+            Value::Object(ref members) => members.iter().find(|x| x.0 == key).map(|x| &x.1),
+            _ => None
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Value::String(ref text) => Some(text),
+            _ => None
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Value::Number(number) => Some(number),
+            _ => None
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match *self {
+            Value::Array(ref values) => Some(values),
+            _ => None
+        }
+    }
+}
+impl fmt::Display for Value {
+    /// Writes the value compactly, on a single line.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Value::Null => write!(f, "null"),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Number(number) if !number.is_finite() => write!(f, "null"),
+            Value::Number(number) if number.fract() == 0.0 && number.abs() < 1e15 => write!(f, "{}", number as i64),
+            Value::Number(number) => write!(f, "{}", number),
+            Value::String(ref text) => write_string(f, text),
+            Value::Array(ref values) => {
+                write!(f, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    write!(f, "{}{}", if i == 0 { "" } else { "," }, value)?;
+                }
+                write!(f, "]")
+            }
+            Value::Object(ref members) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in members.iter().enumerate() {
+                    write!(f, "{}", if i == 0 { "" } else { "," })?;
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+/// Checks that a document is of the given kind and of a supported version, and returns its
+/// version.
+pub fn negotiate(document: &Value, kind: &str) -> io::Result<u32> {
+    let version = document.get("schema").and_then(Value::as_f64).ok_or_else(|| invalid("missing schema version"))?;
+    if version.fract() != 0.0 || version < f64::from(OLDEST_SCHEMA_VERSION) {
+        return Err(invalid(&format!("unsupported schema version {}", version)));
+    }
+    if version > f64::from(SCHEMA_VERSION) {
+        return Err(invalid(&format!("schema version {} is newer than the supported {}", version, SCHEMA_VERSION)));
+    }
+    match document.get("kind").and_then(Value::as_str) {
+        Some(found) if found == kind => Ok(version as u32),
+        Some(found) => Err(invalid(&format!("expected a {}, found a {}", kind, found))),
+        None => Err(invalid("missing kind"))
+    }
+}
+
+/// Writes a position.
+pub fn position_to_json(board: &Board, color: Color) -> Value {
+    let rows = (0..8).rev().map(|y| {
+        Value::String(board.cells.iter().map(|column| match column[y] {
+            Some(Color::Black) => 'X',
+            Some(Color::White) => 'O',
+            None => '-'
+        }).collect())
+    }).collect();
+    document("position", vec![("board", Value::Array(rows)), ("to_move", color_to_json(color))])
+}
+
+/// Reads a position.
+pub fn position_from_json(document: &Value) -> io::Result<(Board, Color)> {
+    negotiate(document, "position")?;
+    let rows = document.get("board").and_then(Value::as_array).ok_or_else(|| invalid("missing board"))?;
+    let mut text = String::with_capacity(66);
+    for row in rows {
+        text += row.as_str().filter(|x| x.chars().count() == 8).ok_or_else(|| invalid("invalid row"))?;
+    }
+    let color = color_from_json(document.get("to_move"))?;
+    text.push(if color == Color::Black { 'X' } else { 'O' });
+    record::parse_position(&text).ok_or_else(|| invalid("invalid board"))
+}
+
+/// Writes a move, `None` for a skip.
+pub fn move_to_json(color: Color, position: Option<Coord>) -> Value {
+    let cell = position.map(|x| Value::String(record::format_move(x))).unwrap_or(Value::Null);
+    document("move", vec![("color", color_to_json(color)), ("cell", cell)])
+}
+
+/// Reads a move.
+pub fn move_from_json(document: &Value) -> io::Result<(Color, Option<Coord>)> {
+    negotiate(document, "move")?;
+    let color = color_from_json(document.get("color"))?;
+    match document.get("cell") {
+        None | Some(&Value::Null) => Ok((color, None)),
+        Some(cell) => Ok((color, Some(cell_from_json(cell)?)))
+    }
+}
+
+/// Writes a game.
+pub fn game_to_json(game: &GameRecord) -> Value {
+    let disks = |x: Option<u8>| x.map(|x| Value::Number(f64::from(x))).unwrap_or(Value::Null);
+    document("game", vec![
+        ("black", Value::String(game.black.clone())),
+        ("white", Value::String(game.white.clone())),
+        ("event", Value::String(game.event.clone())),
+        ("date", Value::String(game.date.clone())),
+        ("moves", Value::Array(game.moves.iter().map(|&x| Value::String(record::format_move(x))).collect())),
+        ("black_disks", disks(game.black_disks)),
+        ("theoretical_disks", disks(game.theoretical_disks)),
+        ("tags", Value::Object(game.tags.iter().map(|x| (x.0.clone(), Value::String(x.1.clone()))).collect()))
+    ])
+}
+
+/// Reads a game. Missing players, event and date are left empty.
+pub fn game_from_json(document: &Value) -> io::Result<GameRecord> {
+    negotiate(document, "game")?;
+    let text = |key: &str| document.get(key).and_then(Value::as_str).unwrap_or("").to_string();
+    let disks = |key: &str| match document.get(key) {
+        None | Some(&Value::Null) => Ok(None),
+        Some(value) => value.as_f64().filter(|&x| x.fract() == 0.0 && (0.0..=64.0).contains(&x)).map(|x| Some(x as u8)).ok_or_else(|| invalid("invalid disk count"))
+    };
+    let moves = match document.get("moves") {
+        None => Vec::new(),
+        Some(moves) => moves.as_array().ok_or_else(|| invalid("invalid moves"))?.iter().map(cell_from_json).collect::<io::Result<_>>()?
+    };
+    let tags = match document.get("tags") {
+        None => Vec::new(),
+        Some(Value::Object(members)) => members.iter().map(|(key, value)| {
+            value.as_str().map(|x| (key.clone(), x.to_string())).ok_or_else(|| invalid("invalid tag"))
+        }).collect::<io::Result<_>>()?,
+        Some(_) => return Err(invalid("invalid tags"))
+    };
+    Ok(GameRecord {
+        black: text("black"),
+        white: text("white"),
+        event: text("event"),
+        date: text("date"),
+        moves,
+        black_disks: disks("black_disks")?,
+        theoretical_disks: disks("theoretical_disks")?,
+        tags
+    })
+}
+
+/// A document of a kind, the schema version and the kind followed by the members.
+pub fn document(kind: &str, members: Vec<(&str, Value)>) -> Value {
+    let mut all = vec![
+        ("schema".to_string(), Value::Number(f64::from(SCHEMA_VERSION))),
+        ("kind".to_string(), Value::String(kind.to_string()))
+    ];
+    all.extend(members.into_iter().map(|(key, value)| (key.to_string(), value)));
+    Value::Object(all)
+}
+
+fn color_to_json(color: Color) -> Value {
+    Value::String(if color == Color::Black { "black" } else { "white" }.to_string())
+}
+
+fn color_from_json(value: Option<&Value>) -> io::Result<Color> {
+    match value.and_then(Value::as_str) {
+        Some("black") => Ok(Color::Black),
+        Some("white") => Ok(Color::White),
+        _ => Err(invalid("invalid color"))
+    }
+}
+
+fn cell_from_json(value: &Value) -> io::Result<Coord> {
+    value.as_str().and_then(record::parse_move).ok_or_else(|| invalid("invalid cell"))
+}
+
+fn write_string(f: &mut fmt::Formatter, text: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in text.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?
+        }
+    }
+    write!(f, "\"")
+}
+
+struct Parser {
+    chars: Vec<char>,
+    index: usize,
+    /// The arrays and objects being parsed.
+    depth: usize
+}
+impl Parser {
+    fn whitespace(&mut self) {
+        while self.index < self.chars.len() && self.chars[self.index].is_whitespace() {
+            self.index += 1;
+        }
+    }
+
+    fn next(&mut self) -> io::Result<char> {
+        let c = self.chars.get(self.index).cloned().ok_or_else(|| invalid("unexpected end"))?;
+        self.index += 1;
+        Ok(c)
+    }
+
+    fn expect(&mut self, word: &str) -> io::Result<()> {
+        for expected in word.chars() {
+            if self.next()? != expected {
+                return Err(invalid("unexpected character"));
+            }
+        }
+        Ok(())
+    }
+
+    fn value(&mut self) -> io::Result<Value> {
+        self.whitespace();
+        match self.chars.get(self.index).cloned() {
+            Some('n') => self.expect("null").map(|_| Value::Null),
+            Some('t') => self.expect("true").map(|_| Value::Bool(true)),
+            Some('f') => self.expect("false").map(|_| Value::Bool(false)),
+            Some('"') => self.string().map(Value::String),
+            Some(c) if c == '[' || c == '{' => {
+                if self.depth >= MAX_DEPTH {
+                    return Err(invalid("nested too deeply"));
+                }
+                self.depth += 1;
+                let value = if c == '[' { self.array() } else { self.object() };
+                self.depth -= 1;
+                value
+            }
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                let start = self.index;
+                while self.index < self.chars.len() && "+-.eE0123456789".contains(self.chars[self.index]) {
+                    self.index += 1;
+                }
+                let text = self.chars[start..self.index].iter().collect::<String>();
+                text.parse().map(Value::Number).map_err(|_| invalid("invalid number"))
+            }
+            _ => Err(invalid("unexpected character"))
+        }
+    }
+
+    fn array(&mut self) -> io::Result<Value> {
+        self.expect("[")?;
+        let mut values = Vec::new();
+        self.whitespace();
+        if self.chars.get(self.index) == Some(&']') {
+            self.index += 1;
+            return Ok(Value::Array(values));
+        }
+        loop {
+            values.push(self.value()?);
+            self.whitespace();
+            match self.next()? {
+                ',' => {}
+                ']' => return Ok(Value::Array(values)),
+                _ => return Err(invalid("expected , or ]"))
+            }
+        }
+    }
+
+    fn object(&mut self) -> io::Result<Value> {
+        self.expect("{")?;
+        let mut members = Vec::new();
+        self.whitespace();
+        if self.chars.get(self.index) == Some(&'}') {
+            self.index += 1;
+            return Ok(Value::Object(members));
+        }
+        loop {
+            self.whitespace();
+            let key = self.string()?;
+            self.whitespace();
+            self.expect(":")?;
+            members.push((key, self.value()?));
+            self.whitespace();
+            match self.next()? {
+                ',' => {}
+                '}' => return Ok(Value::Object(members)),
+                _ => return Err(invalid("expected , or }"))
+            }
+        }
+    }
+
+    fn string(&mut self) -> io::Result<String> {
+        self.expect("\"")?;
+        let mut text = String::new();
+        loop {
+            match self.next()? {
+                '"' => return Ok(text),
+                '\\' => match self.next()? {
+                    'n' => text.push('\n'),
+                    'r' => text.push('\r'),
+                    't' => text.push('\t'),
+                    'b' => text.push('\u{8}'),
+                    'f' => text.push('\u{c}'),
+                    'u' => {
+                        let mut code = self.hex()?;
+                        // characters outside of the basic plane are written as surrogate pairs
+                        if (0xd800..0xdc00).contains(&code) {
+                            self.expect("\\u").map_err(|_| invalid("unpaired surrogate"))?;
+                            let low = self.hex()?;
+                            if !(0xdc00..=0xdfff).contains(&low) {
+                                return Err(invalid("unpaired surrogate"));
+                            }
+                            code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                        } else if (0xdc00..=0xdfff).contains(&code) {
+                            return Err(invalid("unpaired surrogate"));
+                        }
+                        text.push(::std::char::from_u32(code).ok_or_else(|| invalid("invalid escape"))?);
+                    }
+                    c @ ('"' | '\\' | '/') => text.push(c),
+                    _ => return Err(invalid("invalid escape"))
+                },
+                // control characters are only written escaped
+                c if c < '\u{20}' => return Err(invalid("control character in a string")),
+                c => text.push(c)
+            }
+        }
+    }
+
+    fn hex(&mut self) -> io::Result<u32> {
+        let mut code = 0;
+        for _ in 0..4 {
+            code = code * 16 + self.next()?.to_digit(16).ok_or_else(|| invalid("invalid escape"))?;
+        }
+        Ok(code)
+    }
+}
@@ -0,0 +1,250 @@
+//! This module writes games and positions compactly in the URL-safe base64 alphabet, so that they
+//! can be shared as links, like `https://example.org/?game=AQA8...`, and opened again by pasting
+//! the link.
+//!
+//! A game is written as bytes: the version `1`, then flags telling whether the final disks
+//! (`1`) and the theoretical disks (`2`) of Black follow the moves, the number of moves and the
+//! moves packed in 6 bits each, `0` for a1 to `63` for h8 row by row, the disk counts, then the
+//! players, the event, the date and the tags, each text as its length and its UTF-8 bytes.
+//! Lengths and counts are written 7 bits a byte, the lowest first, the high bit set on all bytes
+//! but the last. A position is the version, the cells from a1 to h8 in 2 bits each, `0` for an
+//! empty cell, `1` for Black and `2` for White, then the player to move, `0` for Black.
+//!
+//! Links carry a game as the `game` parameter of their query or fragment, and a position as the
+//! `position` parameter.
+
+use std::io;
+
+use files::invalid;
+use game::{Board, Color};
+use record::GameRecord;
+
+/// Version of the encodings written and read.
+pub const VERSION: u8 = 1;
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+// Flags of the disk counts of a game.
+const BLACK_DISKS: u8 = 1;
+const THEORETICAL_DISKS: u8 = 2;
+
+/// What a link shares.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Shared {
+    Game(GameRecord),
+    /// A position with the player to move.
+    Position(Board, Color)
+}
+
+/// Writes a game.
+pub fn encode_game(game: &GameRecord) -> String {
+    let mut bytes = vec![VERSION];
+    bytes.push(if game.black_disks.is_some() { BLACK_DISKS } else { 0 } | if game.theoretical_disks.is_some() { THEORETICAL_DISKS } else { 0 });
+    write_count(&mut bytes, game.moves.len());
+    let (mut bits, mut pending) = (0u32, 0);
+    for &(x, y) in &game.moves {
+        bits |= ((7 - y) as u32 * 8 + x as u32) << pending;
+        pending += 6;
+        while pending >= 8 {
+            bytes.push(bits as u8);
+            bits >>= 8;
+            pending -= 8;
+        }
+    }
+    if pending > 0 {
+        bytes.push(bits as u8);
+    }
+    bytes.extend(game.black_disks.iter().chain(&game.theoretical_disks));
+    for text in &[&game.black, &game.white, &game.event, &game.date] {
+        write_text(&mut bytes, text);
+    }
+    write_count(&mut bytes, game.tags.len());
+    for (name, value) in &game.tags {
+        write_text(&mut bytes, name);
+        write_text(&mut bytes, value);
+    }
+    base64(&bytes)
+}
+
+/// Reads a game, checking that its moves are legal.
+pub fn decode_game(text: &str) -> io::Result<GameRecord> {
+    let bytes = unbase64(text)?;
+    let mut reader = Reader { bytes: &bytes, read: 0 };
+    version(reader.byte()?)?;
+    let flags = reader.byte()?;
+    let count = reader.count()?;
+    let mut moves = Vec::with_capacity(count.min(64));
+    let (mut bits, mut pending) = (0u32, 0);
+    for _ in 0..count {
+        if pending < 6 {
+            bits |= u32::from(reader.byte()?) << pending;
+            pending += 8;
+        }
+        let index = (bits & 63) as i8;
+        moves.push((index % 8, 7 - index / 8));
+        bits >>= 6;
+        pending -= 6;
+    }
+    let mut disks = |flag: u8| -> io::Result<Option<u8>> {
+        if flags & flag == 0 {
+            return Ok(None);
+        }
+        Some(reader.byte()?).filter(|&x| x <= 64).map(Some).ok_or_else(|| invalid("invalid disk count"))
+    };
+    let (black_disks, theoretical_disks) = (disks(BLACK_DISKS)?, disks(THEORETICAL_DISKS)?);
+    let (black, white, event, date) = (reader.text()?, reader.text()?, reader.text()?, reader.text()?);
+    let tags = (0..reader.count()?).map(|_| Ok((reader.text()?, reader.text()?))).collect::<io::Result<_>>()?;
+    if reader.read != bytes.len() {
+        return Err(invalid("trailing bytes"));
+    }
+    let game = GameRecord { black, white, event, date, moves, black_disks, theoretical_disks, tags };
+    game.positions().map_err(|i| invalid(&format!("illegal move {}", i + 1)))?;
+    Ok(game)
+}
+
+/// Writes a position with the player to move.
+pub fn encode_position(board: &Board, color: Color) -> String {
+    let mut bytes = vec![VERSION];
+    for row in (0..8).rev() {
+        let mut byte = 0u16;
+        for column in 0..8 {
+            let cell = match board.cells[column][row] {
+                None => 0,
+                Some(Color::Black) => 1,
+                Some(Color::White) => 2
+            };
+            byte |= cell << (2 * column);
+        }
+        bytes.extend_from_slice(&byte.to_le_bytes());
+    }
+    bytes.push(if color == Color::Black { 0 } else { 1 });
+    base64(&bytes)
+}
+
+/// Reads a position with the player to move.
+pub fn decode_position(text: &str) -> io::Result<(Board, Color)> {
+    let bytes = unbase64(text)?;
+    if bytes.len() != 18 {
+        return Err(invalid("invalid position length"));
+    }
+    version(bytes[0])?;
+    let mut board = Board { cells: [[None; 8]; 8] };
+    for (i, pair) in bytes[1..17].chunks(2).enumerate() {
+        let row = u16::from_le_bytes([pair[0], pair[1]]);
+        for column in 0..8 {
+            board.cells[column][7 - i] = match (row >> (2 * column)) & 3 {
+                0 => None,
+                1 => Some(Color::Black),
+                2 => Some(Color::White),
+                _ => return Err(invalid("invalid cell"))
+            };
+        }
+    }
+    match bytes[17] {
+        0 => Ok((board, Color::Black)),
+        1 => Ok((board, Color::White)),
+        _ => Err(invalid("invalid player to move"))
+    }
+}
+
+/// A link to a game on a page, like `https://example.org/`.
+pub fn game_link(page: &str, game: &GameRecord) -> String {
+    format!("{}?game={}", page, encode_game(game))
+}
+
+/// A link to a position on a page.
+pub fn position_link(page: &str, board: &Board, color: Color) -> String {
+    format!("{}?position={}", page, encode_position(board, color))
+}
+
+/// Reads the game or the position of a link, in its query or its fragment.
+pub fn parse_link(link: &str) -> io::Result<Shared> {
+    for parameter in link.trim().split(['?', '#', '&']) {
+        match parameter.split_once('=') {
+            Some(("game", value)) => return decode_game(value).map(Shared::Game),
+            Some(("position", value)) => return decode_position(value).map(|(board, color)| Shared::Position(board, color)),
+            _ => {}
+        }
+    }
+    Err(invalid("no game or position in the link"))
+}
+
+// Reads the bytes of a game after the version and flags.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    read: usize
+}
+impl<'a> Reader<'a> {
+    fn byte(&mut self) -> io::Result<u8> {
+        let byte = *self.bytes.get(self.read).ok_or_else(|| invalid("truncated"))?;
+        self.read += 1;
+        Ok(byte)
+    }
+
+    fn count(&mut self) -> io::Result<usize> {
+        let mut count = 0usize;
+        for shift in (0..32).step_by(7) {
+            let byte = self.byte()?;
+            count |= usize::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(count);
+            }
+        }
+        Err(invalid("invalid count"))
+    }
+
+    fn text(&mut self) -> io::Result<String> {
+        let length = self.count()?;
+        let end = self.read.checked_add(length).filter(|&x| x <= self.bytes.len()).ok_or_else(|| invalid("truncated"))?;
+        let text = String::from_utf8(self.bytes[self.read..end].to_vec()).map_err(|_| invalid("invalid text"))?;
+        self.read = end;
+        Ok(text)
+    }
+}
+
+fn write_count(bytes: &mut Vec<u8>, mut count: usize) {
+    while count >= 0x80 {
+        bytes.push(count as u8 | 0x80);
+        count >>= 7;
+    }
+    bytes.push(count as u8);
+}
+
+fn write_text(bytes: &mut Vec<u8>, text: &str) {
+    write_count(bytes, text.len());
+    bytes.extend_from_slice(text.as_bytes());
+}
+
+fn version(version: u8) -> io::Result<()> {
+    if version != VERSION {
+        return Err(invalid(&format!("unsupported version {}", version)));
+    }
+    Ok(())
+}
+
+// Base64 without padding.
+fn base64(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, &x)| group | u32::from(x) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            text.push(char::from(ALPHABET[(group >> (18 - 6 * i)) as usize & 63]));
+        }
+    }
+    text
+}
+
+fn unbase64(text: &str) -> io::Result<Vec<u8>> {
+    let digits = text.trim().trim_end_matches('=').bytes().map(|x| {
+        ALPHABET.iter().position(|&y| y == x).map(|x| x as u32).ok_or_else(|| invalid(&format!("invalid character {}", char::from(x))))
+    }).collect::<io::Result<Vec<_>>>()?;
+    if digits.len() % 4 == 1 {
+        return Err(invalid("truncated"));
+    }
+    let mut bytes = Vec::with_capacity(digits.len() * 3 / 4);
+    for chunk in digits.chunks(4) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, &x)| group | x << (18 - 6 * i));
+        bytes.extend((0..chunk.len() - 1).map(|i| (group >> (16 - 8 * i)) as u8));
+    }
+    Ok(bytes)
+}
@@ -0,0 +1,32 @@
+//! This module reads and writes the file formats of other Othello programs, and [recognizes](fn.sniff.html)
+//! the format of text pasted from them.
+//!
+//! Only the [JSON documents](json/index.html) are part of the core of the crate, as sessions log
+//! their events with them, along with the [links](link/index.html) sharing games and positions.
+//! The other formats need the `formats` feature.
+
+#[cfg(feature = "archive")]
+pub mod archive;
+#[cfg(feature = "ai")]
+pub mod csv;
+#[cfg(feature = "formats")]
+pub mod diagram;
+#[cfg(feature = "formats")]
+pub mod ggf;
+pub mod json;
+pub mod link;
+#[cfg(feature = "formats")]
+pub mod pgn;
+#[cfg(feature = "formats")]
+pub mod scoresheet;
+#[cfg(feature = "formats")]
+pub mod sgf;
+#[cfg(feature = "formats")]
+mod sniff;
+#[cfg(feature = "formats")]
+pub mod training;
+#[cfg(feature = "formats")]
+pub mod wthor;
+
+#[cfg(feature = "formats")]
+pub use self::sniff::{sniff, Format, Pasted};
@@ -0,0 +1,304 @@
+//! This module reads and writes games in a text format modelled on the Portable Game Notation
+//! of chess, easy to read and to edit by hand:
+//!
+//! ```text
+//! [Event "Club championship"]
+//! [Date "2024.05.04"]
+//! [Black "Alice"]
+//! [White "Bob"]
+//! [Result "40-24"]
+//!
+//! 1. f5 d6 2. c3 {a solid choice} d3 3. c4 -- 4. ...
+//! 40-24
+//! ```
+//!
+//! The headers are `[Name "value"]` lines, with `\"` and `\\` escaped in values. `Event`,
+//! `Date`, `Black` and `White` give the fields of the same name of the
+//! [record](../../record/struct.GameRecord.html), `Result` and `Theoretical` give the disks of
+//! Black and White, or `*` when unknown. Other headers are kept as tags.
+//!
+//! The moves are numbered by pairs, Black's then White's, a skip being `--`, and are followed by
+//! the result. A comment in braces after a move is about that move. Games of a file are
+//! separated by blank lines.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+#[cfg(feature = "ai")]
+use analysis::AnnotatedGame;
+use files::invalid;
+use game::Color;
+use record::{self, GameRecord};
+
+// Lines of moves are wrapped before this width.
+const WIDTH: usize = 80;
+
+/// Reads the games of a text, dropping the comments.
+pub fn parse(text: &str) -> io::Result<Vec<GameRecord>> {
+    Ok(parse_commented(text)?.into_iter().map(|x| x.0).collect())
+}
+
+/// Reads the games of a text with the comment of each move, empty for the moves without one.
+pub fn parse_commented(text: &str) -> io::Result<Vec<(GameRecord, Vec<String>)>> {
+    let mut games = Vec::new();
+    let mut current: Option<(GameRecord, Vec<String>)> = None;
+    // whether the current game has moves or a result, so that a header starts the next one
+    let mut started = false;
+    for token in tokens(text)? {
+        if let Token::Header(..) = token {
+            if started {
+                games.extend(current.take());
+                started = false;
+            }
+        }
+        let (record, comments) = current.get_or_insert_with(Default::default);
+        match token {
+            Token::Header(name, value) => match name.as_str() {
+                "Event" => record.event = value,
+                "Date" => record.date = value,
+                "Black" => record.black = value,
+                "White" => record.white = value,
+                "Result" => record.black_disks = parse_result(&value)?,
+                "Theoretical" => record.theoretical_disks = parse_result(&value)?,
+                _ => record.tags.push((name, value))
+            },
+            // comments before the first move are about the whole game and dropped
+            Token::Comment(comment) => match comments.last_mut() {
+                Some(last) if last.is_empty() => *last = comment,
+                Some(last) => {
+                    last.push(' ');
+                    last.push_str(&comment);
+                }
+                None => {}
+            },
+            Token::Word(word) => {
+                started = true;
+                if word == "*" || word.contains('-') && word != "--" {
+                    record.black_disks = parse_result(&word)?.or(record.black_disks);
+                    games.extend(current.take());
+                    started = false;
+                } else if word != "--" && !word.ends_with('.') {
+                    record.moves.push(record::parse_move(&word).ok_or_else(|| invalid(&format!("invalid move: {}", word)))?);
+                    comments.push(String::new());
+                }
+            }
+        }
+    }
+    games.extend(current);
+    Ok(games)
+}
+
+/// Writes a game. Fails if one of its moves is illegal.
+pub fn format(record: &GameRecord) -> io::Result<String> {
+    format_commented(record, &[])
+}
+
+/// Writes a game with the opinion of the engine on each move as its comment, like
+/// `{+2, best d3 +6, 1.2s}`, followed by the comment of the annotation if any.
+#[cfg(feature = "ai")]
+pub fn format_annotated(game: &AnnotatedGame) -> io::Result<String> {
+    let comments = game.annotations.iter().map(|annotation| {
+        let mut comment = format!("{:+}", annotation.score);
+        match annotation.best_move {
+            Some(best) if annotation.loss() > 0 => comment += &format!(", best {} {:+}", record::format_move(best), annotation.best_score),
+            _ => comment += ", best"
+        }
+        if let Some(time) = annotation.time {
+            comment += &format!(", {:.1}s", time.as_secs_f64());
+        }
+        if !annotation.comment.is_empty() {
+            comment += ", ";
+            comment += &annotation.comment;
+        }
+        comment
+    }).collect::<Vec<_>>();
+    format_commented(&game.record, &comments)
+}
+
+/// Writes a game with a comment for each of its first moves, empty comments being left out.
+pub fn format_commented(record: &GameRecord, comments: &[String]) -> io::Result<String> {
+    let positions = record.positions().map_err(|i| {
+        io::Error::new(io::ErrorKind::InvalidInput, format!("illegal move {}", i + 1))
+    })?;
+    let mut text = String::new();
+    for &(name, value) in &[("Event", &record.event), ("Date", &record.date), ("Black", &record.black), ("White", &record.white)] {
+        text += &header(name, value);
+    }
+    let result = format_result(record.black_disks);
+    text += &header("Result", &result);
+    if record.theoretical_disks.is_some() {
+        text += &header("Theoretical", &format_result(record.theoretical_disks));
+    }
+    for (name, value) in &record.tags {
+        text += &header(name, value);
+    }
+    text.push('\n');
+    let mut words = Vec::new();
+    // the player expected to move, to write the skips
+    let mut expected = Color::Black;
+    let mut number = 0;
+    for (i, (&position, &(_, color))) in record.moves.iter().zip(&positions).enumerate() {
+        if color != expected {
+            if expected == Color::Black {
+                number += 1;
+                words.push(format!("{}.", number));
+            }
+            words.push("--".to_string());
+        }
+        if color == Color::Black {
+            number += 1;
+            words.push(format!("{}.", number));
+        }
+        words.push(record::format_move(position));
+        match comments.get(i) {
+            Some(comment) if !comment.is_empty() => words.push(format!("{{{}}}", comment.replace('}', ")"))),
+            _ => {}
+        }
+        expected = color.flip();
+    }
+    words.push(result);
+    let mut line = String::new();
+    for word in words {
+        if !line.is_empty() && line.len() + 1 + word.len() >= WIDTH {
+            text += &line;
+            text.push('\n');
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line += &word;
+    }
+    text += &line;
+    text.push('\n');
+    Ok(text)
+}
+
+/// Reads all the games of a reader.
+pub fn read<R: Read>(mut reader: R) -> io::Result<Vec<GameRecord>> {
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+    parse(&text)
+}
+
+/// Writes games separated by blank lines.
+pub fn write<W: Write>(mut writer: W, records: &[GameRecord]) -> io::Result<()> {
+    for (i, record) in records.iter().enumerate() {
+        if i > 0 {
+            writeln!(writer)?;
+        }
+        write!(writer, "{}", format(record)?)?;
+    }
+    Ok(())
+}
+
+/// Writes annotated games separated by blank lines.
+#[cfg(feature = "ai")]
+pub fn write_annotated<W: Write>(mut writer: W, games: &[AnnotatedGame]) -> io::Result<()> {
+    for (i, game) in games.iter().enumerate() {
+        if i > 0 {
+            writeln!(writer)?;
+        }
+        write!(writer, "{}", format_annotated(game)?)?;
+    }
+    Ok(())
+}
+
+/// Reads the games of a file.
+pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Vec<GameRecord>> {
+    read(BufReader::new(File::open(path)?))
+}
+
+/// Writes games to a file, replacing its contents.
+pub fn save<P: AsRef<Path>>(path: P, records: &[GameRecord]) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write(&mut writer, records)?;
+    writer.flush()
+}
+
+enum Token {
+    Header(String, String),
+    Comment(String),
+    Word(String)
+}
+
+fn tokens(text: &str) -> io::Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '[' => {
+                let name = chars.by_ref().take_while(|&x| x != '"').collect::<String>();
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\\') => value.extend(chars.next()),
+                        Some('"') => break,
+                        Some(c) => value.push(c),
+                        None => return Err(invalid("unterminated header"))
+                    }
+                }
+                if chars.by_ref().find(|&x| x == ']').is_none() {
+                    return Err(invalid("unterminated header"));
+                }
+                tokens.push(Token::Header(name.trim().to_string(), value));
+            }
+            '{' => {
+                let mut comment = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => comment.push(c),
+                        None => return Err(invalid("unterminated comment"))
+                    }
+                }
+                tokens.push(Token::Comment(comment.split_whitespace().collect::<Vec<_>>().join(" ")));
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                let mut word = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '{' || c == '[' {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                // a move number may be glued to its move, like `1.f5`
+                match word.rfind('.') {
+                    Some(i) if i + 1 < word.len() => {
+                        tokens.push(Token::Word(word[..=i].to_string()));
+                        tokens.push(Token::Word(word[i + 1..].to_string()));
+                    }
+                    _ => tokens.push(Token::Word(word))
+                }
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn header(name: &str, value: &str) -> String {
+    format!("[{} \"{}\"]\n", name, value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn format_result(disks: Option<u8>) -> String {
+    match disks {
+        Some(disks) => format!("{}-{}", disks, 64 - disks),
+        None => "*".to_string()
+    }
+}
+
+// The disks of Black in a result like `40-24`, `None` for `*`. Games ended with empty cells
+// count them for the winner, so only the disks of Black are kept.
+fn parse_result(text: &str) -> io::Result<Option<u8>> {
+    if text == "*" {
+        return Ok(None);
+    }
+    let mut parts = text.splitn(2, '-').map(|x| x.trim().parse::<u8>().ok());
+    match (parts.next(), parts.next()) {
+        (Some(Some(black)), Some(Some(white))) if black <= 64 && white <= 64 && black + white <= 64 => Ok(Some(black)),
+        _ => Err(invalid(&format!("invalid result: {}", text)))
+    }
+}
@@ -0,0 +1,131 @@
+//! This module prints games as the scoresheets of tournaments: a grid with the number of each
+//! move written in the cell it was played in, the four starting disks, and the players and the
+//! result above. Club players review games from such sheets away from a computer.
+//!
+//! Sheets are written as [text](fn.text.html), for terminals and plain printers, or as
+//! [SVG](fn.svg.html), for browsers and for printing at any size.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use game::{Board, Color};
+use record::GameRecord;
+
+// Size of a cell of the SVG grid and of its margins, in pixels.
+const CELL: usize = 40;
+const MARGIN: usize = 30;
+
+/// Writes a sheet as text. Fails if one of the moves is illegal.
+pub fn text(record: &GameRecord) -> io::Result<String> {
+    let numbers = numbers(record)?;
+    let mut text = String::new();
+    for line in headers(record) {
+        text += &line;
+        text.push('\n');
+    }
+    text += "\n    a   b   c   d   e   f   g   h\n";
+    let separator = format!("  +{}\n", ["---"; 8].join("+") + "+");
+    text += &separator;
+    for y in (0..8).rev() {
+        text += &format!("{} |", 8 - y);
+        for (x, column) in numbers.iter().enumerate() {
+            let cell = match (column[y], start(x, y)) {
+                (Some(number), _) => format!("{:>2} ", number),
+                (None, Some(Color::Black)) => " X ".to_string(),
+                (None, Some(Color::White)) => " O ".to_string(),
+                (None, None) => "   ".to_string()
+            };
+            text += &cell;
+            text.push('|');
+        }
+        text.push('\n');
+        text += &separator;
+    }
+    Ok(text)
+}
+
+/// Writes a sheet as a standalone SVG image. Fails if one of the moves is illegal.
+pub fn svg(record: &GameRecord) -> io::Result<String> {
+    let numbers = numbers(record)?;
+    let headers = headers(record);
+    let top = MARGIN + headers.len() * 20 + 10;
+    let (width, height) = (2 * MARGIN + 8 * CELL, top + 8 * CELL + MARGIN);
+    let mut svg = format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\" \
+        font-family=\"sans-serif\" text-anchor=\"middle\">\n", width, height);
+    svg += &format!("<rect width=\"{}\" height=\"{}\" fill=\"white\"/>\n", width, height);
+    for (i, line) in headers.iter().enumerate() {
+        svg += &format!("<text x=\"{}\" y=\"{}\" font-size=\"14\" text-anchor=\"start\">{}</text>\n", MARGIN, MARGIN + i * 20 + 10, escape(line));
+    }
+    for i in 0..8 {
+        let center = MARGIN + i * CELL + CELL / 2;
+        svg += &format!("<text x=\"{}\" y=\"{}\" font-size=\"12\">{}</text>\n", center, top - 6, (b'a' + i as u8) as char);
+        svg += &format!("<text x=\"{}\" y=\"{}\" font-size=\"12\">{}</text>\n", MARGIN / 2, top + i * CELL + CELL / 2 + 4, i + 1);
+    }
+    for i in 0..=8 {
+        let offset = i * CELL;
+        svg += &format!("<line x1=\"{0}\" y1=\"{1}\" x2=\"{0}\" y2=\"{2}\" stroke=\"black\"/>\n", MARGIN + offset, top, top + 8 * CELL);
+        svg += &format!("<line x1=\"{0}\" y1=\"{1}\" x2=\"{2}\" y2=\"{1}\" stroke=\"black\"/>\n", MARGIN, top + offset, MARGIN + 8 * CELL);
+    }
+    for (x, column) in numbers.iter().enumerate() {
+        for (y, &number) in column.iter().enumerate() {
+            let (cx, cy) = (MARGIN + x * CELL + CELL / 2, top + (7 - y) * CELL + CELL / 2);
+            match (number, start(x, y)) {
+                (Some(number), _) => svg += &format!("<text x=\"{}\" y=\"{}\" font-size=\"16\">{}</text>\n", cx, cy + 6, number),
+                (None, Some(color)) => {
+                    let fill = if color == Color::Black { "black" } else { "white" };
+                    svg += &format!("<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\" stroke=\"black\"/>\n", cx, cy, CELL * 2 / 5, fill);
+                }
+                (None, None) => {}
+            }
+        }
+    }
+    svg += "</svg>\n";
+    Ok(svg)
+}
+
+/// Writes the text sheet of a game to a file, or its SVG sheet if the path ends with `.svg`.
+pub fn save<P: AsRef<Path>>(path: P, record: &GameRecord) -> io::Result<()> {
+    let path = path.as_ref();
+    let sheet = match path.extension().and_then(|x| x.to_str()) {
+        Some(extension) if extension.eq_ignore_ascii_case("svg") => svg(record)?,
+        _ => text(record)?
+    };
+    let mut writer = BufWriter::new(File::create(path)?);
+    writer.write_all(sheet.as_bytes())?;
+    writer.flush()
+}
+
+// The number of the move played in each cell, checking that the moves are legal.
+fn numbers(record: &GameRecord) -> io::Result<[[Option<usize>; 8]; 8]> {
+    record.positions().map_err(|i| {
+        io::Error::new(io::ErrorKind::InvalidInput, format!("illegal move {}", i + 1))
+    })?;
+    let mut numbers = [[None; 8]; 8];
+    for (i, &(x, y)) in record.moves.iter().enumerate() {
+        numbers[x as usize][y as usize] = Some(i + 1);
+    }
+    Ok(numbers)
+}
+
+// The disk of a cell in the starting position.
+fn start(x: usize, y: usize) -> Option<Color> {
+    Board::new().cells[x][y]
+}
+
+fn headers(record: &GameRecord) -> Vec<String> {
+    let mut lines = Vec::new();
+    if !record.event.is_empty() || !record.date.is_empty() {
+        lines.push(format!("Event: {}   Date: {}", record.event, record.date));
+    }
+    let result = match record.black_disks {
+        Some(disks) => format!("{}-{}", disks, 64 - disks),
+        None => "*".to_string()
+    };
+    lines.push(format!("Black (X): {}   White (O): {}   Result: {}", record.black, record.white, result));
+    lines
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
@@ -0,0 +1,69 @@
+//! This module exports games to the Smart Game Format, read by many viewers and archival tools
+//! for other games than Go too.
+//!
+//! A game is written as `(;FF[4]GM[2]SZ[8]...;B[fe];W[dc]...)`: game 2 is Othello, and a cell is
+//! its column and its row as letters, the row `a` being row 1, so `fe` is `f5`. Skips are written
+//! as empty moves. The players go to `PB` and `PW`, the event to `EV`, the date to `DT` and the
+//! result of Black to `RE`, like `B+34`, `W+10` or `0` for a draw.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use game::Color;
+use record::GameRecord;
+
+/// Writes a game. Fails if one of its moves is illegal.
+pub fn format(record: &GameRecord) -> io::Result<String> {
+    let positions = record.positions().map_err(|i| {
+        io::Error::new(io::ErrorKind::InvalidInput, format!("illegal move {}", i + 1))
+    })?;
+    let mut text = "(;FF[4]GM[2]SZ[8]".to_string();
+    for &(key, value) in &[("EV", &record.event), ("DT", &record.date), ("PB", &record.black), ("PW", &record.white)] {
+        if !value.is_empty() {
+            text += &format!("{}[{}]", key, escape(value));
+        }
+    }
+    if let Some(disks) = record.black_disks {
+        let diff = 2 * i32::from(disks) - 64;
+        text += &match diff {
+            0 => "RE[0]".to_string(),
+            diff if diff > 0 => format!("RE[B+{}]", diff),
+            diff => format!("RE[W+{}]", -diff)
+        };
+    }
+    // the player expected to move, to write the skips
+    let mut expected = Color::Black;
+    for (&(x, y), &(_, color)) in record.moves.iter().zip(&positions) {
+        if color != expected {
+            text += &format!(";{}[]", key(expected));
+        }
+        text += &format!(";{}[{}{}]", key(color), (b'a' + x as u8) as char, (b'a' + (7 - y) as u8) as char);
+        expected = color.flip();
+    }
+    text += ")";
+    Ok(text)
+}
+
+/// Writes games as a collection, one per line.
+pub fn write<W: Write>(mut writer: W, records: &[GameRecord]) -> io::Result<()> {
+    for record in records {
+        writeln!(writer, "{}", format(record)?)?;
+    }
+    Ok(())
+}
+
+/// Writes games to a file, replacing its contents.
+pub fn save<P: AsRef<Path>>(path: P, records: &[GameRecord]) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write(&mut writer, records)?;
+    writer.flush()
+}
+
+fn key(color: Color) -> &'static str {
+    if color == Color::Black { "B" } else { "W" }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace(']', "\\]")
+}
@@ -0,0 +1,117 @@
+//! This module recognizes the format of text pasted from other programs.
+
+use std::io;
+
+use formats::link::{self, Shared};
+use formats::{ggf, json, pgn};
+use game::{Board, Color};
+use record::{self, GameRecord};
+
+/// The formats recognized by [`sniff`](fn.sniff.html).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Format {
+    /// A [JSON](json/index.html) position or game.
+    Json,
+    /// Games in the [Generic Game Format](ggf/index.html).
+    Ggf,
+    /// Games in the [PGN-like format](pgn/index.html).
+    Pgn,
+    /// A board drawn as text, in plain ASCII, with box-drawing characters or as a Markdown table.
+    Diagram,
+    /// A position on one line as in the [records](../record/index.html), like FEN in chess.
+    Position,
+    /// The moves of a game, like `f5d6c3`.
+    Transcript,
+    /// A [link](link/index.html) sharing a game or a position.
+    Link
+}
+
+/// What a text holds.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pasted {
+    /// A position with the player to move.
+    Position(Board, Color),
+    Games(Vec<GameRecord>)
+}
+
+/// Detects the format of a text pasted from another program and reads it. Diagrams are for
+/// Black to move unless they say `White to move`.
+pub fn sniff(text: &str) -> io::Result<(Format, Pasted)> {
+    let text = text.trim();
+    if text.starts_with('{') {
+        let document = json::Value::parse(text)?;
+        return match document.get("kind").and_then(json::Value::as_str) {
+            Some("position") => json::position_from_json(&document).map(|(board, color)| (Format::Json, Pasted::Position(board, color))),
+            _ => json::game_from_json(&document).map(|game| (Format::Json, Pasted::Games(vec![game])))
+        };
+    }
+    if text.contains("game=") || text.contains("position=") {
+        return Ok((Format::Link, match link::parse_link(text)? {
+            Shared::Game(game) => Pasted::Games(vec![game]),
+            Shared::Position(board, color) => Pasted::Position(board, color)
+        }));
+    }
+    if text.contains("(;") && text.contains("GM[Othello]") {
+        return Ok((Format::Ggf, Pasted::Games(ggf::parse(text)?)));
+    }
+    if text.starts_with('[') {
+        return Ok((Format::Pgn, Pasted::Games(pgn::parse(text)?)));
+    }
+    if let Some((board, color)) = parse_diagram(text) {
+        return Ok((Format::Diagram, Pasted::Position(board, color)));
+    }
+    if let Some((board, color)) = record::parse_position(text) {
+        return Ok((Format::Position, Pasted::Position(board, color)));
+    }
+    if let Some(moves) = record::parse_transcript(text) {
+        let game = GameRecord { moves, ..GameRecord::default() };
+        if let Err(i) = game.positions() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("illegal move {}", i + 1)));
+        }
+        return Ok((Format::Transcript, Pasted::Games(vec![game])));
+    }
+    Err(io::Error::new(io::ErrorKind::InvalidData, "unrecognized format"))
+}
+
+// Reads the rows of a diagram, each starting with its number, with its cells separated by
+// spaces or bars.
+fn parse_diagram(text: &str) -> Option<(Board, Color)> {
+    let mut board = Board { cells: [[None; 8]; 8] };
+    let mut rows = 0u8;
+    for line in text.lines() {
+        // the last move may be marked by `>` before its disk, or `▶` in the place of a border
+        let line = line.replace('>', " ");
+        let fields = if line.contains(['|', '│']) {
+            let mut fields = line.split(['|', '│', '▶']).map(str::trim).collect::<Vec<_>>();
+            if fields.first() == Some(&"") {
+                fields.remove(0);
+            }
+            fields
+        } else {
+            line.split_whitespace().collect()
+        };
+        let row = match fields.first().and_then(|x| x.trim_matches('*').parse::<i8>().ok()) {
+            Some(row) if (1..=8).contains(&row) => row,
+            _ => continue
+        };
+        if fields.len() < 9 {
+            return None;
+        }
+        for (x, field) in fields[1..9].iter().enumerate() {
+            board.cells[x][(8 - row) as usize] = match *field {
+                "X" | "x" | "*" | "B" | "⚫" | "●" => Some(Color::Black),
+                "O" | "o" | "W" | "⚪" | "○" => Some(Color::White),
+                // legal moves may be marked on empty cells
+                "" | "." | "-" | "·" | "+" => None,
+                _ => return None
+            };
+        }
+        rows |= 1 << (row - 1);
+    }
+    if rows != 0xff {
+        return None;
+    }
+    let lowercase = text.to_lowercase();
+    let color = if lowercase.contains("white to move") || lowercase.contains("o to move") { Color::White } else { Color::Black };
+    Some((board, color))
+}
@@ -0,0 +1,156 @@
+//! This module exports the positions of played games as samples for training evaluation
+//! networks outside of the crate.
+//!
+//! A file starts with the magic bytes `RRDATA`, a 16 bit version and the 32 bit number of
+//! samples, followed by the samples of 20 bytes each:
+//!
+//! | bytes | content                                                                   |
+//! |-------|---------------------------------------------------------------------------|
+//! | 0-7   | the disks of the player to move, bit `y * 8 + x` for the cell `(x, y)`     |
+//! | 8-15  | the disks of the opponent, in the same order                              |
+//! | 16    | the player to move, 0 for Black and 1 for White                           |
+//! | 17    | the final disk differential for the player to move, signed                |
+//! | 18    | the best move as `y * 8 + x`                                              |
+//! | 19    | the outcome for the player to move: 2 for a win, 1 for a draw, 0 for a loss |
+//!
+//! All integers are little endian. The two bitboards are the [input planes](../../nn/fn.planes.html)
+//! of the networks of the crate. The best move is the one played in the game unless the samples
+//! are [relabeled](fn.relabel.html) by an engine.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+#[cfg(feature = "ai")]
+use engine::{Engine, Limits};
+use files::invalid;
+use game::{Board, Color, Coord};
+use record::GameRecord;
+
+const MAGIC: &[u8; 6] = b"RRDATA";
+const VERSION: u16 = 1;
+const SAMPLE_SIZE: usize = 20;
+
+/// A position of a game with its outcome.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Sample {
+    pub board: Board,
+    pub color: Color,
+    /// Final disk differential for the player to move.
+    pub diff: i8,
+    pub best_move: Coord
+}
+
+/// The positions of a finished game before each move. Unfinished games have no outcome and give
+/// no samples; fails with the index of the first illegal move.
+pub fn samples(record: &GameRecord) -> Result<Vec<Sample>, usize> {
+    let positions = record.positions()?;
+    let (last, _) = positions[positions.len() - 1];
+    if !last.legal_moves(Color::Black).is_empty() || !last.legal_moves(Color::White).is_empty() {
+        return Ok(Vec::new());
+    }
+    let black_diff = last.count(Color::Black) as i8 - last.count(Color::White) as i8;
+    Ok(record.moves.iter().zip(&positions).map(|(&best_move, &(board, color))| {
+        let diff = if color == Color::Black { black_diff } else { -black_diff };
+        Sample { board, color, diff, best_move }
+    }).collect())
+}
+
+/// Replaces the best moves of samples by the choices of an engine.
+#[cfg(feature = "ai")]
+pub fn relabel<E: Engine>(samples: &mut [Sample], engine: &mut E, limits: &Limits) {
+    for sample in samples {
+        if let Some(best) = engine.best_move(&sample.board, sample.color, limits).best {
+            sample.best_move = best;
+        }
+    }
+}
+
+/// Writes samples in the format described above.
+pub fn write<W: Write>(mut writer: W, samples: &[Sample]) -> io::Result<()> {
+    writer.write_all(MAGIC)?;
+    writer.write_all(&VERSION.to_le_bytes())?;
+    writer.write_all(&(samples.len() as u32).to_le_bytes())?;
+    for sample in samples {
+        let (own, opponent) = bitboards(&sample.board, sample.color);
+        let (x, y) = sample.best_move;
+        let outcome = 1 + sample.diff.signum();
+        writer.write_all(&own.to_le_bytes())?;
+        writer.write_all(&opponent.to_le_bytes())?;
+        writer.write_all(&[sample.color as u8, sample.diff as u8, (y * 8 + x) as u8, outcome as u8])?;
+    }
+    Ok(())
+}
+
+/// Reads samples written by [`write`](fn.write.html).
+pub fn read<R: Read>(mut reader: R) -> io::Result<Vec<Sample>> {
+    let mut header = [0; 12];
+    reader.read_exact(&mut header)?;
+    if &header[..6] != MAGIC {
+        return Err(invalid("not a training data file"));
+    }
+    if u16::from_le_bytes([header[6], header[7]]) != VERSION {
+        return Err(invalid("unsupported training data version"));
+    }
+    let count = u32::from_le_bytes([header[8], header[9], header[10], header[11]]);
+    let mut samples = Vec::with_capacity(count.min(1 << 20) as usize);
+    for _ in 0..count {
+        let mut bytes = [0; SAMPLE_SIZE];
+        reader.read_exact(&mut bytes)?;
+        let mut own = [0; 8];
+        own.copy_from_slice(&bytes[..8]);
+        let mut opponent = [0; 8];
+        opponent.copy_from_slice(&bytes[8..16]);
+        let color = match bytes[16] {
+            0 => Color::Black,
+            1 => Color::White,
+            _ => return Err(invalid("invalid player"))
+        };
+        if bytes[18] >= 64 {
+            return Err(invalid("move outside of the board"));
+        }
+        let (own, opponent) = (u64::from_le_bytes(own), u64::from_le_bytes(opponent));
+        let mut board = Board { cells: [[None; 8]; 8] };
+        for i in 0..64 {
+            if own & opponent & (1 << i) != 0 {
+                return Err(invalid("cell occupied twice"));
+            }
+            board.cells[i % 8][i / 8] = if own & (1 << i) != 0 {
+                Some(color)
+            } else if opponent & (1 << i) != 0 {
+                Some(color.flip())
+            } else {
+                None
+            };
+        }
+        samples.push(Sample { board, color, diff: bytes[17] as i8, best_move: ((bytes[18] % 8) as i8, (bytes[18] / 8) as i8) });
+    }
+    Ok(samples)
+}
+
+/// Reads samples from a file.
+pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Vec<Sample>> {
+    read(BufReader::new(File::open(path)?))
+}
+
+/// Writes samples to a file, replacing its contents.
+pub fn save<P: AsRef<Path>>(path: P, samples: &[Sample]) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write(&mut writer, samples)?;
+    writer.flush()
+}
+
+// The disks of the player and the ones of the opponent.
+fn bitboards(board: &Board, color: Color) -> (u64, u64) {
+    let (mut own, mut opponent) = (0, 0);
+    for (x, column) in board.cells.iter().enumerate() {
+        for (y, &cell) in column.iter().enumerate() {
+            match cell {
+                Some(found) if found == color => own |= 1 << (y * 8 + x),
+                Some(_) => opponent |= 1 << (y * 8 + x),
+                None => ()
+            }
+        }
+    }
+    (own, opponent)
+}
@@ -0,0 +1,268 @@
+//! This module reads and writes the game databases of the French Othello federation, the archives of
+//! millions of tournament games.
+//!
+//! A WTHOR database (`.wtb`) starts with a 16 byte header and stores each game in 68 bytes: the
+//! tournament, black and white player numbers, the final number of black disks, the theoretical
+//! number of black disks with perfect play from a given depth, and 60 moves. A move is stored as
+//! `10 * row + column`, both counted from 1, so `a1` is 11 and `h8` is 88. Unused moves are 0.
+//! Integers are little endian.
+//!
+//! The names of the players and tournaments are in separate files sharing the header: `.jou`
+//! files list player names of 20 bytes, `.trn` files tournament names of 26 bytes, padded with
+//! zeros. Games refer to them by their index.
+
+use std::fs::File;
+use std::collections::HashMap;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use files::invalid;
+use game::{Color, Coord};
+use record::GameRecord;
+
+const HEADER_SIZE: usize = 16;
+const GAME_SIZE: usize = 68;
+const PLAYER_SIZE: usize = 20;
+const TOURNAMENT_SIZE: usize = 26;
+
+/// The header of a database.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct Header {
+    /// Creation date of the file.
+    pub created: (u16, u8, u8),
+    /// Number of games in the file.
+    pub games: u32,
+    /// Number of names in the file.
+    pub names: u16,
+    /// The year the games were played.
+    pub year: u16,
+    /// Number of empty cells at which the theoretical score was computed.
+    pub depth: u8
+}
+
+/// A game of a database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Game {
+    pub tournament: u16,
+    pub black: u16,
+    pub white: u16,
+    /// Disks of Black at the end of the game.
+    pub black_disks: u8,
+    /// Disks of Black with perfect play after the first `60 - depth` moves.
+    pub theoretical_disks: u8,
+    pub moves: Vec<Coord>
+}
+
+/// Reads a database, checking that it contains as many games as its header says.
+pub fn read<R: Read>(mut reader: R) -> io::Result<(Header, Vec<Game>)> {
+    let header = read_header(&mut reader)?;
+    let mut games = Vec::with_capacity(header.games.min(1 << 20) as usize);
+    for _ in 0..header.games {
+        let mut bytes = [0; GAME_SIZE];
+        reader.read_exact(&mut bytes)?;
+        games.push(game(&bytes)?);
+    }
+    Ok((header, games))
+}
+
+/// Reads a database from a file.
+pub fn load<P: AsRef<Path>>(path: P) -> io::Result<(Header, Vec<Game>)> {
+    read(BufReader::new(File::open(path)?))
+}
+
+/// Writes a database. The number of games in the header is replaced by the number of games
+/// given, which must all have at most 60 moves.
+pub fn write<W: Write>(mut writer: W, header: &Header, games: &[Game]) -> io::Result<()> {
+    write_header(&mut writer, header, games.len() as u32, 0)?;
+    for game in games {
+        if game.moves.len() > 60 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "more than 60 moves"));
+        }
+        writer.write_all(&game.tournament.to_le_bytes())?;
+        writer.write_all(&game.black.to_le_bytes())?;
+        writer.write_all(&game.white.to_le_bytes())?;
+        let mut moves = [0; 60];
+        for (byte, &(x, y)) in moves.iter_mut().zip(&game.moves) {
+            *byte = (10 * (8 - y) + x + 1) as u8;
+        }
+        writer.write_all(&[game.black_disks, game.theoretical_disks])?;
+        writer.write_all(&moves)?;
+    }
+    Ok(())
+}
+
+/// Writes a database to a file, replacing its contents.
+pub fn save<P: AsRef<Path>>(path: P, header: &Header, games: &[Game]) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write(&mut writer, header, games)?;
+    writer.flush()
+}
+
+/// Reads the player names of a `.jou` file.
+pub fn read_players<R: Read>(reader: R) -> io::Result<Vec<String>> {
+    read_names(reader, PLAYER_SIZE)
+}
+
+/// Reads the tournament names of a `.trn` file.
+pub fn read_tournaments<R: Read>(reader: R) -> io::Result<Vec<String>> {
+    read_names(reader, TOURNAMENT_SIZE)
+}
+
+/// Writes player names as a `.jou` file. Names are cut to 19 bytes, characters outside of
+/// Latin-1 are replaced by `?`.
+pub fn write_players<W: Write>(writer: W, header: &Header, names: &[String]) -> io::Result<()> {
+    write_names(writer, header, names, PLAYER_SIZE)
+}
+
+/// Writes tournament names as a `.trn` file. Names are cut to 25 bytes, characters outside of
+/// Latin-1 are replaced by `?`.
+pub fn write_tournaments<W: Write>(writer: W, header: &Header, names: &[String]) -> io::Result<()> {
+    write_names(writer, header, names, TOURNAMENT_SIZE)
+}
+
+/// Converts the games of a database to records, looking up the names of their players and
+/// tournaments. Unknown names are left empty.
+pub fn to_records(header: &Header, games: &[Game], players: &[String], tournaments: &[String]) -> Vec<GameRecord> {
+    let name = |names: &[String], index: u16| names.get(index as usize).cloned().unwrap_or_default();
+    games.iter().map(|game| GameRecord {
+        black: name(players, game.black),
+        white: name(players, game.white),
+        event: name(tournaments, game.tournament),
+        date: if header.year == 0 { String::new() } else { header.year.to_string() },
+        moves: game.moves.clone(),
+        black_disks: Some(game.black_disks),
+        theoretical_disks: Some(game.theoretical_disks),
+        tags: vec![("theoretical_depth".to_string(), header.depth.to_string())]
+    }).collect()
+}
+
+/// Converts records to games, numbering their players and tournaments in order of appearance.
+/// Returns the games with the player and tournament names.
+///
+/// Missing final disk counts are taken from the final position, missing theoretical ones from
+/// the final disk counts.
+pub fn from_records(records: &[GameRecord]) -> (Vec<Game>, Vec<String>, Vec<String>) {
+    let (mut players, mut tournaments) = (Names::default(), Names::default());
+    let games = records.iter().map(|record| {
+        let black_disks = record.black_disks.unwrap_or_else(|| {
+            record.positions().ok().and_then(|x| x.last().map(|x| x.0.count(Color::Black) as u8)).unwrap_or(0)
+        });
+        Game {
+            tournament: tournaments.index(&record.event),
+            black: players.index(&record.black),
+            white: players.index(&record.white),
+            black_disks,
+            theoretical_disks: record.theoretical_disks.unwrap_or(black_disks),
+            moves: record.moves.clone()
+        }
+    }).collect();
+    (games, players.names, tournaments.names)
+}
+
+/// Writes records as a database with its player and tournament files.
+pub fn save_records<P: AsRef<Path>, Q: AsRef<Path>, T: AsRef<Path>>(records: &[GameRecord], header: &Header, games: P, players: Q, tournaments: T) -> io::Result<()> {
+    let (records, player_names, tournament_names) = from_records(records);
+    save(games, header, &records)?;
+    let mut writer = BufWriter::new(File::create(players)?);
+    write_players(&mut writer, header, &player_names)?;
+    writer.flush()?;
+    let mut writer = BufWriter::new(File::create(tournaments)?);
+    write_tournaments(&mut writer, header, &tournament_names)?;
+    writer.flush()
+}
+
+/// Reads the games of a database with the names of their players and tournaments from the
+/// `.jou` and `.trn` files.
+pub fn load_records<P: AsRef<Path>, Q: AsRef<Path>, T: AsRef<Path>>(games: P, players: Q, tournaments: T) -> io::Result<Vec<GameRecord>> {
+    let (header, games) = load(games)?;
+    let players = read_players(BufReader::new(File::open(players)?))?;
+    let tournaments = read_tournaments(BufReader::new(File::open(tournaments)?))?;
+    Ok(to_records(&header, &games, &players, &tournaments))
+}
+
+fn read_header<R: Read>(reader: &mut R) -> io::Result<Header> {
+    let mut bytes = [0; HEADER_SIZE];
+    reader.read_exact(&mut bytes)?;
+    let board_size = bytes[12];
+    if board_size != 0 && board_size != 8 {
+        return Err(invalid("only 8x8 games are supported"));
+    }
+    Ok(Header {
+        created: (u16::from(bytes[0]) * 100 + u16::from(bytes[1]), bytes[2], bytes[3]),
+        games: u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]),
+        names: u16::from_le_bytes([bytes[8], bytes[9]]),
+        year: u16::from_le_bytes([bytes[10], bytes[11]]),
+        depth: bytes[14]
+    })
+}
+
+fn write_header<W: Write>(writer: &mut W, header: &Header, games: u32, names: u16) -> io::Result<()> {
+    let (year, month, day) = header.created;
+    writer.write_all(&[(year / 100) as u8, (year % 100) as u8, month, day])?;
+    writer.write_all(&games.to_le_bytes())?;
+    writer.write_all(&names.to_le_bytes())?;
+    writer.write_all(&header.year.to_le_bytes())?;
+    writer.write_all(&[8, 0, header.depth, 0])
+}
+
+// Interns names, numbering them in order of appearance.
+#[derive(Default)]
+struct Names {
+    names: Vec<String>,
+    indices: HashMap<String, u16>
+}
+impl Names {
+    fn index(&mut self, name: &str) -> u16 {
+        if let Some(&index) = self.indices.get(name) {
+            return index;
+        }
+        let index = self.names.len() as u16;
+        self.names.push(name.to_string());
+        self.indices.insert(name.to_string(), index);
+        index
+    }
+}
+
+fn write_names<W: Write>(mut writer: W, header: &Header, names: &[String], size: usize) -> io::Result<()> {
+    if names.len() > usize::from(u16::MAX) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "too many names"));
+    }
+    write_header(&mut writer, header, 0, names.len() as u16)?;
+    for name in names {
+        let mut bytes = name.chars().map(|x| if (x as u32) < 256 { x as u8 } else { b'?' }).take(size - 1).collect::<Vec<_>>();
+        bytes.resize(size, 0);
+        writer.write_all(&bytes)?;
+    }
+    Ok(())
+}
+
+// Names are stored in Latin-1, padded with zeros.
+fn read_names<R: Read>(mut reader: R, size: usize) -> io::Result<Vec<String>> {
+    let header = read_header(&mut reader)?;
+    let mut names = Vec::with_capacity(header.names as usize);
+    let mut bytes = vec![0; size];
+    for _ in 0..header.names {
+        reader.read_exact(&mut bytes)?;
+        names.push(bytes.iter().take_while(|&&x| x != 0).map(|&x| char::from(x)).collect::<String>().trim_end().to_string());
+    }
+    Ok(names)
+}
+
+fn game(bytes: &[u8; GAME_SIZE]) -> io::Result<Game> {
+    let mut moves = Vec::new();
+    for &byte in bytes[8..].iter().take_while(|&&x| x != 0) {
+        let (row, column) = (byte / 10, byte % 10);
+        if !(1..=8).contains(&row) || !(1..=8).contains(&column) {
+            return Err(invalid("move outside of the board"));
+        }
+        moves.push((column as i8 - 1, 8 - row as i8));
+    }
+    Ok(Game {
+        tournament: u16::from_le_bytes([bytes[0], bytes[1]]),
+        black: u16::from_le_bytes([bytes[2], bytes[3]]),
+        white: u16::from_le_bytes([bytes[4], bytes[5]]),
+        black_disks: bytes[6],
+        theoretical_disks: bytes[7],
+        moves
+    })
+}
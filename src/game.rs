@@ -2,15 +2,18 @@
 
 use std::fmt;
 
+#[cfg(all(debug_assertions, feature = "crosscheck"))]
+use bitboard;
+
 /// Associates a piece with a player.
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Color {
     Black,
     White
 }
 impl Color {
     /// Flipping a piece results in a piece of opposite color.
-    fn flip(&self) -> Color {
+    pub fn flip(&self) -> Color {
         match *self {
             Color::Black => Color::White,
             Color::White => Color::Black
@@ -43,25 +46,77 @@ pub const DIRECTIONS: [Coord; 8] = [
     (-1, 1) // NW
 ];
 
+/// One of the eight symmetries of the board, the rotations and reflections.
+///
+/// The cell is transposed if bit 2 is set, then mirrored horizontally if bit 0 is set and
+/// vertically if bit 1 is set.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Symmetry(pub u8);
+impl Symmetry {
+    /// Lists all the symmetries, starting with the identity.
+    pub fn all() -> [Symmetry; 8] {
+        [Symmetry(0), Symmetry(1), Symmetry(2), Symmetry(3), Symmetry(4), Symmetry(5), Symmetry(6), Symmetry(7)]
+    }
+
+    /// Maps a position to its image.
+    pub fn apply(&self, position: Coord) -> Coord {
+        let (x, y) = if self.0 & 4 != 0 { (position.1, position.0) } else { position };
+        let x = if self.0 & 1 != 0 { 7 - x } else { x };
+        let y = if self.0 & 2 != 0 { 7 - y } else { y };
+        (x, y)
+    }
+
+    /// The symmetry mapping images back to the original positions.
+    pub fn inverse(&self) -> Symmetry {
+        // mirroring before transposing is the same as transposing and mirroring the other axis
+        if self.0 & 4 != 0 {
+            Symmetry(4 | (self.0 & 1) << 1 | (self.0 & 2) >> 1)
+        } else {
+            *self
+        }
+    }
+}
+
 /// Lists the reasons why a move is illegal.
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum IllegalMove {
     /// The cell is already occupied by the given color.
     Occupied(Color),
     /// The move does not cause any disks to be flipped over.
-    Ineffective
+    Ineffective,
+    /// The cell is not on the board.
+    Outside
 }
 impl fmt::Display for IllegalMove {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             IllegalMove::Occupied(color) => write!(f, "Occupied by {}", color),
-            IllegalMove::Ineffective => write!(f, "Ineffective")
+            IllegalMove::Ineffective => write!(f, "Ineffective"),
+            IllegalMove::Outside => write!(f, "Outside of the board")
         }
     }
 }
 
+/// What [`Board::draw_highlighted`](struct.Board.html#method.draw_highlighted) marks on a
+/// board.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct Highlights {
+    /// Marks the legal moves of a player.
+    pub hints: Option<Color>,
+    /// Marks the disk placed by a move and the disks it flipped.
+    pub last_move: Option<LegalMove>
+}
+
+// How a cell is marked for the last move.
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Mark {
+    None,
+    Placed,
+    Flipped
+}
+
 /// Holds the description of a legal move on the board.
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct LegalMove {
     /// The color being placed.
     pub color: Color,
@@ -71,8 +126,16 @@ pub struct LegalMove {
     pub position: Coord
 }
 impl LegalMove {
+    /// Lists the positions of the pieces flipped by the move.
+    pub fn flipped(&self) -> Vec<Coord> {
+        let (x, y) = self.position;
+        DIRECTIONS.iter().zip(self.flips.iter())
+            .flat_map(|(&(dx, dy), &n)| (1..n + 1).map(move |i| (x + i * dx, y + i * dy)))
+            .collect()
+    }
+
     /// Applying a legal move returns a changed board.
-    fn apply(&self, board: Board) -> Board {
+    pub fn apply(&self, board: Board) -> Board {
         let &(x, y) = &self.position;
         let mut fresh_board = board;
         // closures cannot be recursive, so I used a regular function
@@ -80,7 +143,7 @@ impl LegalMove {
         // but as this will always reenter exactly 8 times, no need to worry about overflowing the stack
         fn flip_direction(cells: &mut [[Option<Color>; 8]; 8], position: Coord, direction: Coord, n: i8) {
             match n {
-                0 => return,
+                0 => (),
                 n => {
                     let &(x, y) = &position;
                     let &(dx, dy) = &direction;
@@ -90,7 +153,7 @@ impl LegalMove {
                 }
             }
         }
-        for (i, &(h, v)) in DIRECTIONS.into_iter().enumerate() {
+        for (i, &(h, v)) in DIRECTIONS.iter().enumerate() {
             flip_direction(&mut fresh_board.cells, (x, y), (h, v), self.flips[i]);
         }
         fresh_board.cells[x as usize][y as usize] = Some(self.color);
@@ -98,12 +161,24 @@ impl LegalMove {
     }
 }
 
+/// The characters a board is drawn with.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Charset {
+    /// Letters and dots, readable everywhere.
+    #[default]
+    Ascii,
+    /// Box-drawing characters with `⚫` for Black and `⚪` for White, for terminals with Unicode
+    /// fonts.
+    Unicode
+}
 /// An 8x8 matrix of cells holding disks.
 ///
-/// It is immutable and represents a constellation of pieces.
+/// It represents a constellation of pieces. Moves applied to it result in new
+/// boards, only the search changes boards in place with [`make`](#method.make)
+/// and [`unmake`](#method.unmake) to avoid copying them at every node.
 /// If and only if two boards have the same constellation, are they considered
 /// equal.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct Board {
     pub cells: [[Option<Color>; 8]; 8]
 }
@@ -116,11 +191,136 @@ impl Board {
         cells[3][4] = Some(Color::White);
         cells[4][3] = Some(Color::White);
         cells[4][4] = Some(Color::Black);
-        Board { cells: cells }
+        Board { cells }
+    }
+
+    /// Maps every piece to its image under a symmetry.
+    pub fn transform(&self, symmetry: Symmetry) -> Board {
+        let mut cells = [[None; 8]; 8];
+        for (x, column) in self.cells.iter().enumerate() {
+            for (y, &cell) in column.iter().enumerate() {
+                let (tx, ty) = symmetry.apply((x as i8, y as i8));
+                cells[tx as usize][ty as usize] = cell;
+            }
+        }
+        Board { cells }
+    }
+
+    /// Counts the pieces of the given color.
+    pub fn count(&self, color: Color) -> usize {
+        self.cells.iter().map(|column| column.iter().filter(|&&cell| cell == Some(color)).count()).sum()
+    }
+
+    /// Counts the cells not yet occupied by any piece.
+    pub fn empties(&self) -> usize {
+        self.cells.iter().map(|column| column.iter().filter(|cell| cell.is_none()).count()).sum()
+    }
+
+    /// Lists the legal moves of a player, column by column.
+    pub fn legal_moves(&self, color: Color) -> Vec<LegalMove> {
+        #[cfg(all(debug_assertions, feature = "crosscheck"))]
+        {
+            let mismatches = bitboard::cross_check(self, color);
+            assert!(mismatches.is_empty(), "{}", mismatches.iter().map(|x| x.to_string()).collect::<Vec<_>>().join("\n"));
+        }
+        self.test(color).into_iter().flat_map(|column| column.into_iter().filter_map(|x| x.ok())).collect()
+    }
+
+    /// Tests a move of a player, giving the disks it flips or why it is illegal. Moves coming
+    /// from elsewhere, like the clients of a server, are checked this way rather than trusted.
+    pub fn test_move(&self, color: Color, position: Coord) -> Result<LegalMove, IllegalMove> {
+        let (x, y) = position;
+        if !(0..8).contains(&x) || !(0..8).contains(&y) {
+            return Err(IllegalMove::Outside);
+        }
+        self.test(color)[x as usize][y as usize]
+    }
+
+    /// Applies a legal move in place.
+    pub fn make(&mut self, legal_move: &LegalMove) {
+        *self = legal_move.apply(*self);
+    }
+
+    /// Takes back a legal move, which must be the last one made on the board.
+    pub fn unmake(&mut self, legal_move: &LegalMove) {
+        let (x, y) = legal_move.position;
+        for (fx, fy) in legal_move.flipped() {
+            self.cells[fx as usize][fy as usize] = Some(legal_move.color.flip());
+        }
+        self.cells[x as usize][y as usize] = None;
+    }
+
+    /// Draws the board as text, row 1 on top. The circles of `Charset::Unicode` take two columns
+    /// in terminals, so every cell does.
+    pub fn draw(&self, charset: Charset) -> String {
+        self.draw_with_hints(charset, None)
+    }
+
+    /// Draws the board like [`draw`](#method.draw), marking the legal moves of a player with `+`
+    /// in ASCII and `·` in Unicode.
+    pub fn draw_with_hints(&self, charset: Charset, hints: Option<Color>) -> String {
+        self.draw_highlighted(charset, &Highlights { hints, last_move: None })
+    }
+
+    /// Draws the board like [`draw_with_hints`](#method.draw_with_hints), also marking the last
+    /// move: in ASCII its disk follows a `>` and the disks it flipped are in lower case, in
+    /// Unicode its disk follows a `▶` and the disks it flipped are drawn as `●` and `○`.
+    pub fn draw_highlighted(&self, charset: Charset, highlights: &Highlights) -> String {
+        let moves = highlights.hints.map(|color| self.legal_moves(color)).unwrap_or_default();
+        let hint = |x: usize, y: usize| moves.iter().any(|m| m.position == (x as i8, y as i8));
+        let flipped = highlights.last_move.map(|x| x.flipped()).unwrap_or_default();
+        let mark = |x: usize, y: usize| {
+            let position = (x as i8, y as i8);
+            match highlights.last_move {
+                Some(last) if last.position == position => Mark::Placed,
+                Some(_) if flipped.contains(&position) => Mark::Flipped,
+                _ => Mark::None
+            }
+        };
+        if charset == Charset::Ascii {
+            let mut text = "  a b c d e f g h\n".to_string();
+            for y in (0..8).rev() {
+                text += &(8 - y).to_string();
+                for (x, column) in self.cells.iter().enumerate() {
+                    text += match (column[y], mark(x, y)) {
+                        (Some(Color::Black), Mark::Placed) => ">X",
+                        (Some(Color::White), Mark::Placed) => ">O",
+                        (Some(Color::Black), Mark::Flipped) => " x",
+                        (Some(Color::White), Mark::Flipped) => " o",
+                        (Some(Color::Black), _) => " X",
+                        (Some(Color::White), _) => " O",
+                        (None, _) if hint(x, y) => " +",
+                        (None, _) => " ."
+                    };
+                }
+                text.push('\n');
+            }
+            return text;
+        }
+        let line = |left: &str, middle: &str, right: &str| format!(" {}{}{}\n", left, ["──"; 8].join(middle), right);
+        let mut text = "  a  b  c  d  e  f  g  h\n".to_string();
+        text += &line("┌", "┬", "┐");
+        for y in (0..8).rev() {
+            text += &(8 - y).to_string();
+            for (x, column) in self.cells.iter().enumerate() {
+                text += if mark(x, y) == Mark::Placed { "▶" } else { "│" };
+                text += match (column[y], mark(x, y)) {
+                    (Some(Color::Black), Mark::Flipped) => "● ",
+                    (Some(Color::White), Mark::Flipped) => "○ ",
+                    (Some(Color::Black), _) => "⚫",
+                    (Some(Color::White), _) => "⚪",
+                    (None, _) if hint(x, y) => " ·",
+                    (None, _) => "  "
+                };
+            }
+            text += "│\n";
+            text += &if y > 0 { line("├", "┼", "┤") } else { line("└", "┴", "┘") };
+        }
+        text
     }
 
     /// Tests all the moves a given player can take on the board.
-    fn test(&self, color: Color) -> Vec<Vec<Result<LegalMove, IllegalMove>>> {
+    pub(crate) fn test(&self, color: Color) -> Vec<Vec<Result<LegalMove, IllegalMove>>> {
 
         // Tests whether there are valid flips on the position and returns them.
         // Otherwise it gives an error with the reason.
@@ -142,13 +342,13 @@ impl Board {
                         Some(_) => test_direction(cells, color, next, direction, n + 1)
                     }
                 }
-            };
+            }
             let &(x, y) = &position;
             match cells[x as usize][y as usize] {
                 // can't put on already occupied field
                 Some(color) => Err(IllegalMove::Occupied(color)),
                 None => {
-                    for (i, &(h, v)) in DIRECTIONS.into_iter().enumerate() {
+                    for (i, &(h, v)) in DIRECTIONS.iter().enumerate() {
                         let n = test_direction(cells, color, (x, y), (h, v), 0);
                         sum += n;
                         flips[i] = n;
@@ -158,17 +358,56 @@ impl Board {
                         Err(IllegalMove::Ineffective)
                     } else {
                         Ok(LegalMove {
-                            color: color,
-                            flips: flips,
-                            position: position
+                            color,
+                            flips,
+                            position
                         })
                     }
                 }
             }
         }
-        (0..7).map(|i| (0..7).map(|j| test_position(&self.cells, color, (i, j))).collect()).collect()
+        (0..8).map(|i| (0..8).map(|j| test_position(&self.cells, color, (i, j))).collect()).collect()
+    }
+
+    /// Counts the move sequences of the given length from the board, a skip counting as a move
+    /// and a finished game ending its sequences early. Checks move generation against known counts.
+    pub fn perft(&self, color: Color, depth: u32) -> u64 {
+        let divided = self.divide(color, depth);
+        if divided.is_empty() {
+            return 1;
+        }
+        divided.iter().map(|x| x.1).sum()
+    }
+
+    /// Splits the count of [`perft`](#method.perft) by first move, `None` standing for a skip.
+    /// Empty when the game is over or the depth is 0.
+    pub fn divide(&self, color: Color, depth: u32) -> Vec<(Option<Coord>, u64)> {
+        if depth == 0 {
+            return Vec::new();
+        }
+        let moves = self.legal_moves(color);
+        if moves.is_empty() {
+            if self.legal_moves(color.flip()).is_empty() {
+                return Vec::new();
+            }
+            return vec![(None, self.perft(color.flip(), depth - 1))];
+        }
+        moves.iter().map(|x| (Some(x.position), x.apply(*self).perft(color.flip(), depth - 1))).collect()
+    }
+}
+impl Default for Board {
+    fn default() -> Board {
+        Board::new()
     }
 }
+impl fmt::Display for Board {
+    /// Draws the board in plain text, row 1 on top, with `X` for Black, `O` for White and `.`
+    /// for empty cells, like [`draw`](#method.draw) with `Charset::Ascii`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.draw(Charset::Ascii))
+    }
+}
+
 /// Enumerates possible states of the game.
 pub enum Game {
     /// The player should place a piece on the board.
@@ -264,9 +503,9 @@ pub fn new_game() -> Game {
     let moves = board.test(player);
 
     Game::Place(Place {
-        player: player,
-        board: board,
-        moves: moves,
+        player,
+        board,
+        moves,
         retry_reason: None
     })
 }
\ No newline at end of file
@@ -1 +1,129 @@
-pub mod game;
\ No newline at end of file
+#[cfg(all(feature = "plugins", unix))]
+extern crate libc;
+#[cfg(feature = "mmap")]
+extern crate memmap2;
+#[cfg(feature = "onnx")]
+extern crate tract_onnx;
+#[cfg(feature = "archive")]
+extern crate zstd;
+#[cfg(feature = "image")]
+extern crate gif;
+#[cfg(feature = "image")]
+extern crate png;
+#[cfg(feature = "testing")]
+extern crate proptest;
+#[cfg(feature = "net")]
+extern crate prost;
+#[cfg(feature = "tui")]
+extern crate crossterm;
+#[cfg(feature = "scripting")]
+extern crate rhai;
+#[cfg(feature = "db")]
+extern crate rusqlite;
+#[cfg(feature = "trace")]
+extern crate tracing;
+#[cfg(feature = "api")]
+extern crate tiny_http;
+#[cfg(any(feature = "web", feature = "discord"))]
+extern crate tungstenite;
+#[cfg(any(feature = "discord", feature = "telegram"))]
+extern crate ureq;
+#[cfg(feature = "wasm")]
+extern crate wasm_bindgen;
+#[cfg(feature = "wasm")]
+extern crate web_time;
+
+pub mod game;
+#[cfg(feature = "ai")]
+pub mod analysis;
+#[cfg(feature = "api")]
+pub mod api;
+#[cfg(feature = "ai")]
+pub mod arena;
+#[cfg(feature = "ai")]
+pub mod bench;
+pub mod bitboard;
+#[cfg(feature = "ai")]
+pub mod book;
+#[cfg(feature = "chat")]
+pub mod chat;
+#[cfg(feature = "ui")]
+pub mod config;
+pub mod correspondence;
+#[cfg(feature = "db")]
+pub mod db;
+#[cfg(feature = "discord")]
+pub mod discord;
+#[cfg(feature = "ai")]
+pub mod endgame;
+#[cfg(feature = "ai")]
+pub mod engine;
+#[cfg(feature = "ai")]
+pub mod eval;
+#[cfg(feature = "ai")]
+pub mod fairplay;
+#[cfg(feature = "ai")]
+pub mod ffo;
+pub mod files;
+pub mod formats;
+#[cfg(feature = "net")]
+pub mod lobby;
+#[cfg(feature = "ai")]
+pub mod mcts;
+#[cfg(feature = "net")]
+pub mod net;
+#[cfg(feature = "ai")]
+pub mod nn;
+#[cfg(feature = "ai")]
+pub mod nnue;
+#[cfg(feature = "ai")]
+pub mod plugin;
+#[cfg(feature = "ai")]
+pub mod protocol;
+#[cfg(feature = "ai")]
+pub mod puzzle;
+pub mod random;
+#[cfg(feature = "ai")]
+pub mod rating;
+pub mod record;
+#[cfg(feature = "ui")]
+pub mod render;
+#[cfg(feature = "ai")]
+pub mod rollout;
+#[cfg(feature = "scripting")]
+pub mod script;
+#[cfg(feature = "ai")]
+pub mod search;
+pub mod session;
+#[cfg(feature = "ai")]
+pub mod simulate;
+pub mod small;
+#[cfg(feature = "telegram")]
+pub mod telegram;
+#[cfg(feature = "ui")]
+pub mod speech;
+pub mod stats;
+#[cfg(feature = "ui")]
+pub mod terminal;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "ai")]
+mod time;
+#[cfg(feature = "ai")]
+pub mod tournament;
+#[cfg(feature = "ai")]
+pub mod tree;
+#[cfg(feature = "ai")]
+pub mod tt;
+#[cfg(feature = "tui")]
+pub mod tui;
+#[cfg(feature = "ai")]
+pub mod tune;
+pub mod verify;
+#[cfg(feature = "ui")]
+pub mod viewer;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "web")]
+pub mod web;
+pub mod zobrist;
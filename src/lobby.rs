@@ -0,0 +1,182 @@
+//! This module keeps the lobby of a game server: the players looking for an opponent, the
+//! challenges between them with the time control they propose, and the pairings made when a
+//! challenge is accepted. It is driven by the [WebSocket server](../web/index.html) but doesn't
+//! depend on it.
+//!
+//! A player answers a challenge by accepting it, declining it, or challenging back with another
+//! time control, which replaces it. Finished games can be kept in an
+//! [`Archive`](struct.Archive.html).
+
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use files::invalid;
+use formats::pgn;
+use game::Color;
+use record::GameRecord;
+use session::Clock;
+
+/// Identifies a player of the lobby, like the connection of a client.
+pub type PlayerId = u64;
+
+/// A player of the lobby.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub id: PlayerId,
+    pub name: String,
+    /// Whether the player is in a game of the lobby.
+    pub playing: bool
+}
+
+/// A game proposed by a player to another.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Challenge {
+    pub from: PlayerId,
+    pub to: PlayerId,
+    /// The color of the player challenging.
+    pub color: Color,
+    /// The clock of both players, `None` for an untimed game.
+    pub clock: Option<Clock>
+}
+
+/// Two players paired by an accepted challenge.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Pairing {
+    pub black: PlayerId,
+    pub white: PlayerId,
+    pub clock: Option<Clock>
+}
+
+/// The players of a server looking for games.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Lobby {
+    entries: Vec<Entry>,
+    challenges: Vec<Challenge>
+}
+impl Lobby {
+    pub fn new() -> Lobby {
+        Lobby::default()
+    }
+
+    /// The players in the order they entered.
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// The challenges not answered yet.
+    pub fn challenges(&self) -> &[Challenge] {
+        &self.challenges
+    }
+
+    pub fn get(&self, id: PlayerId) -> Option<&Entry> {
+        self.entries.iter().find(|x| x.id == id)
+    }
+
+    /// The player of a name, compared without case.
+    pub fn find(&self, name: &str) -> Option<&Entry> {
+        self.entries.iter().find(|x| x.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Lists a player under a name, or renames them. Fails if another player has the name.
+    pub fn enter(&mut self, id: PlayerId, name: &str) -> io::Result<()> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(invalid("missing name"));
+        }
+        if self.find(name).is_some_and(|x| x.id != id) {
+            return Err(invalid("the name is taken"));
+        }
+        match self.entries.iter_mut().find(|x| x.id == id) {
+            Some(entry) => entry.name = name.to_string(),
+            None => self.entries.push(Entry { id, name: name.to_string(), playing: false })
+        }
+        Ok(())
+    }
+
+    /// Takes a player out of the lobby with their challenges. Returns whether they were in it.
+    pub fn leave(&mut self, id: PlayerId) -> bool {
+        self.challenges.retain(|x| x.from != id && x.to != id);
+        let count = self.entries.len();
+        self.entries.retain(|x| x.id != id);
+        self.entries.len() != count
+    }
+
+    /// Marks a player as playing or back from a game.
+    pub fn set_playing(&mut self, id: PlayerId, playing: bool) {
+        if let Some(entry) = self.entries.iter_mut().find(|x| x.id == id) {
+            entry.playing = playing;
+        }
+    }
+
+    /// Challenges the player of a name, replacing the challenges between the two, so that a
+    /// challenge can be answered with another time control.
+    pub fn challenge(&mut self, from: PlayerId, to: &str, color: Color, clock: Option<Clock>) -> io::Result<Challenge> {
+        let challenger = self.get(from).ok_or_else(|| invalid("not in the lobby"))?;
+        if challenger.playing {
+            return Err(invalid("already playing"));
+        }
+        let opponent = self.find(to).ok_or_else(|| invalid("no player with this name"))?;
+        if opponent.id == from {
+            return Err(invalid("players can't challenge themselves"));
+        }
+        if opponent.playing {
+            return Err(invalid("the player is in a game"));
+        }
+        let challenge = Challenge { from, to: opponent.id, color, clock };
+        self.challenges.retain(|x| !is_between(x, from, challenge.to));
+        self.challenges.push(challenge);
+        Ok(challenge)
+    }
+
+    /// Accepts the challenge of the player of a name, pairing both players. Their other
+    /// challenges are dropped.
+    pub fn accept(&mut self, id: PlayerId, from: &str) -> io::Result<Pairing> {
+        let challenge = self.pending(id, from)?;
+        self.challenges.retain(|x| ![challenge.from, challenge.to].iter().any(|&player| x.from == player || x.to == player));
+        self.set_playing(challenge.from, true);
+        self.set_playing(challenge.to, true);
+        let (black, white) = if challenge.color == Color::Black { (challenge.from, challenge.to) } else { (challenge.to, challenge.from) };
+        Ok(Pairing { black, white, clock: challenge.clock })
+    }
+
+    /// Declines the challenge of the player of a name.
+    pub fn decline(&mut self, id: PlayerId, from: &str) -> io::Result<Challenge> {
+        let challenge = self.pending(id, from)?;
+        self.challenges.retain(|x| *x != challenge);
+        Ok(challenge)
+    }
+
+    // The challenge of the player of a name to `id`.
+    fn pending(&self, id: PlayerId, from: &str) -> io::Result<Challenge> {
+        let from = self.find(from).ok_or_else(|| invalid("no player with this name"))?.id;
+        self.challenges.iter().find(|x| x.from == from && x.to == id).cloned().ok_or_else(|| invalid("no challenge from this player"))
+    }
+}
+
+/// A file where finished games are appended in PGN.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Archive {
+    path: PathBuf
+}
+impl Archive {
+    pub fn new<P: AsRef<Path>>(path: P) -> Archive {
+        Archive { path: path.as_ref().to_path_buf() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Appends a game, separated from the previous ones by a blank line.
+    pub fn append(&self, record: &GameRecord) -> io::Result<()> {
+        let text = pgn::format(record)?;
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        let separator = if file.metadata()?.len() > 0 { "\n" } else { "" };
+        write!(file, "{}{}", separator, text)
+    }
+}
+
+fn is_between(challenge: &Challenge, a: PlayerId, b: PlayerId) -> bool {
+    (challenge.from == a && challenge.to == b) || (challenge.from == b && challenge.to == a)
+}
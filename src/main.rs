@@ -0,0 +1,1610 @@
+extern crate rusty_reversi;
+#[cfg(feature = "trace")]
+extern crate tracing_subscriber;
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, BufReader, IsTerminal, Write};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::OnceLock;
+use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use rusty_reversi::analysis;
+use rusty_reversi::arena::{self, Options};
+use rusty_reversi::bench;
+use rusty_reversi::book::{self, Book, Booked, BuildOptions, OpeningBook};
+use rusty_reversi::config;
+use rusty_reversi::correspondence;
+use rusty_reversi::endgame::{self, Solver};
+use rusty_reversi::engine::{from_spec, set_memory_cap, Engine, Limits, SearchStats};
+use rusty_reversi::eval::Heuristic;
+use rusty_reversi::fairplay;
+use rusty_reversi::ffo;
+use rusty_reversi::files::invalid_input;
+use rusty_reversi::formats::{self, diagram, json, link, pgn, wthor, Pasted};
+use rusty_reversi::game::{Board, Charset, Color, Highlights};
+use rusty_reversi::protocol::edax::Edax;
+use rusty_reversi::protocol::ggs::{Ggs, Policy};
+use rusty_reversi::protocol::gtp::Gtp;
+use rusty_reversi::puzzle;
+use rusty_reversi::random::Random;
+use rusty_reversi::rating::{Outcome, Ratings};
+use rusty_reversi::record::{self, GameRecord};
+use rusty_reversi::search::{self, EndgameMode};
+use rusty_reversi::session::{Clock, GameSession, Player};
+use rusty_reversi::simulate::{self, BatchConfig, Sink};
+use rusty_reversi::tournament::{Pairing, Tournament};
+use rusty_reversi::tree::SearchTree;
+use rusty_reversi::speech;
+use rusty_reversi::terminal::{Theme, THEME_VARIABLE};
+use rusty_reversi::viewer::Viewer;
+
+const USAGE: &str = "usage: rusty_reversi [--unicode] [--accessible] [--theme classic|high-contrast|monochrome] [--config file]
+                    [--memory mib] <command> [arguments]
+
+commands:
+  play [--color black|white] [--save file] [--load file] [--profile name] [--ratings file]
+       [--db database]                   play against the engine on the console, saving the
+                                         game after each move or resuming a saved game
+  tui [--color black|white] [--clock minutes] [--no-hints]
+                                         play against the engine in a full-screen interface
+  host [--port n] [--color black|white] [--clock minutes] [--increment seconds] [--name name]
+       [--lag ms]                        host a game on a TCP port and play it on the console,
+                                         not charging the guest up to the lag for each move
+  join <host:port> [--name name] [--token token]
+                                         join a hosted game and play it on the console, or
+                                         come back to it with the token it was left with
+  correspond <move> [token]              play a move of a game by correspondence, checking the
+                                         game of the token received, and print the token of the
+                                         move to send back
+  serve [--port n] [--archive file] [--delay seconds] [--eval-depth n] [--lag ms]
+                                         serve games to browsers over WebSocket, joined by code
+                                         or by challenges in a lobby, appending finished games
+                                         to a PGN file; spectators watch with a delay and the
+                                         evaluations of the engine
+  api [--port n] [--book file]           serve games over an HTTP REST API, with engine moves
+  edax, gtp                              speak the Edax or the Go Text Protocol on the standard streams
+  ggs <host:port> --login name [--games n] [--rated] [--min-rating n] [--records games.pgn]
+      [--db database]                    play the games requested on a Generic Game Server,
+                                         logging in with the password in GGS_PASSWORD, and
+                                         keep the finished games
+  discord [--name name]                  play the games challenged in the channels of Discord,
+                                         with the token of the bot in DISCORD_TOKEN
+  telegram <directory> [--name name]     play the games challenged in the chats of Telegram,
+                                         with the token of the bot in TELEGRAM_TOKEN, keeping
+                                         them in the directory
+  analyze <position> [--format text|markdown|html]
+                                         draw a position with the scores of its moves
+  analyze <game> [--format text|pgn] [--blunder n]
+                                         score each move of a transcript, a game file or a
+                                         saved game, marking the ones losing n or more (50)
+  fairplay <games> [--player name] [--format text|json]
+                                         compare the moves of the players of some games with
+                                         the engine, reporting their accuracy, average loss and
+                                         how suspiciously close to the engine they played
+  solve <position> [--wld]               solve a position exactly, or as a win, loss or draw,
+                                         printing the score, the best line, nodes and time
+  perft <depth> [position] [--divide]    count the move sequences of each length up to depth,
+                                         or of the given length by first move
+  tree <depth> [position] [--limit n]    print the tree of an alpha-beta search as Graphviz DOT,
+                                         up to n nodes (1000 by default)
+  bench                                  search built-in positions to depth 6, printing the
+                                         total of the nodes, the nodes per second and the
+                                         details of the searches
+  match [--first engine] [--second engine] [--games n] [--plies n] [--seed n]
+        [--clock minutes] [--increment seconds] [--records games.pgn] [--ratings file]
+                                         play a match between two engines, each opening with
+                                         both colors, saving the games, and print the details
+                                         of the searches of each
+  simulate [--first engine] [--second engine] [--games n] [--threads n] [--plies n] [--seed n]
+        [--pgn games.pgn] [--samples file]
+                                         play many games between two engines on all processors,
+                                         writing the games and their positions as training
+                                         samples, and print statistics
+  tournament <state> [--player engine]... [--pairing round-robin|swiss] [--rounds n] [--games n]
+        [--seed n] [--records games.pgn] [--ratings file]
+                                         play a tournament between engines, or resume the one
+                                         saved in the state file, printing the standings
+  book build <book> [--positions n] [--book-depth n] [--max-ply n] [--ply-cost n]
+                                         grow a book by self-play
+  book probe <book> [position]           list the moves of a book in a position
+  db import <database> <games> [--players file.jou] [--tournaments file.trn]
+                                         store the games of a WTHOR database, a GGF archive or
+                                         another game file in a game database
+  db games <database> [position] [--player name] [--winner black|white|draw] [--year n]
+                                         list the games of a game database, or the ones reaching
+                                         a position in any orientation
+  db explore <database> [position] [--player name] [--winner black|white|draw] [--year n]
+                                         list the moves played from a position, the starting
+                                         one by default, with their results
+  ffo [suite.obf]                        solve the problems of a suite, FFO #40 to #43 by default
+  puzzles [--count n] [--empties min-max] [--gap n] [--seed n] [--games file]
+                                         write a suite of positions where only one move wins or
+                                         draws, from random games or the games of a file
+  quiz <suite.obf>                       solve the puzzles of a suite, counting streaks of
+                                         correct answers
+  ratings <file>                         list the Elo and Glicko-2 ratings of the players
+  view <game> [--game n] [--tui]         step through the moves of a game, flipping the board
+                                         and showing the evaluation of the engine on demand
+  replay <moves> <output.gif|output.png> animate a game
+  share <game|position> [--page url]     print links sharing the games of a file or a position,
+                                         read back wherever games and positions are
+  help                                   print this message
+
+engines of play, tui, api, edax, gtp, ggs, discord, telegram, analyze, fairplay, view and bench:
+  --engine name[:option=value,...]       alphabeta (the default), minimax, mcts or solver, or
+                                         external:command for a program speaking the edax protocol,
+                                         nboard:command for one speaking the NBoard protocol, or
+                                         remote:host:port or remote-nboard:host:port for a program
+                                         listening over TCP, or script:path for a Rhai script
+  --option option=value                  set an option of the engine, may be repeated, like
+                                         plugin=path to evaluate with a plugin in alphabeta
+                                         and minimax
+  --depth n, --nodes n, --time seconds   limit the searches, also of the engines of match,
+                                         simulate and tournament, which are named by --first,
+                                         --second and --player instead of --engine
+  --book file                            play the moves of a book before searching, in play,
+                                         tui, api, edax, gtp, ggs, discord and telegram
+  --book-temperature disks               vary the book moves, a move scoring this much less
+                                         than another being played e times less often
+  --book-min-games n                     only play book moves played in this many games
+  --book-max-deviation disks             only play book moves scoring at most this much less
+                                         than the best one
+
+Positions are 64 cells, X, O or -, from a1 to h8 row by row, followed by the player to move.
+
+Boards are colored in terminals, with the theme named by --theme or by the RUSTY_REVERSI_THEME
+environment variable, unless NO_COLOR is set. With --accessible, play, analyze and quiz describe
+the boards and the moves in sentences instead, for screen readers.
+
+With --memory, the engines of the process reserve at most the given MiB together: transposition
+tables past it are refused, caches stop growing and tree searches stop early when it is reached.
+
+Defaults of the engine, the theme and the keys of tui are read from
+~/.config/rusty_reversi/config.toml, or the file given by --config; flags take precedence.
+
+Built with the trace feature, the searches, the protocols and the network log to the standard
+error at the levels given by RUST_LOG, like RUST_LOG=rusty_reversi=debug.";
+
+// Flags of the commands using an engine.
+const ENGINE_FLAGS: &[&str] = &["engine", "option", "depth", "nodes", "time"];
+
+// Flags of the commands playing the moves of a book.
+const BOOK_FLAGS: &[&str] = &["book", "book-temperature", "book-min-games", "book-max-deviation"];
+
+// The configuration file, read once before the command.
+static CONFIG: OnceLock<config::Config> = OnceLock::new();
+
+// How boards are drawn on the console.
+#[derive(Copy, Clone)]
+struct Screen {
+    charset: Charset,
+    theme: Theme,
+    // sentences instead of boards
+    accessible: bool
+}
+
+fn main() {
+    // events of the searches, the protocols and the network, filtered by RUST_LOG
+    #[cfg(feature = "trace")]
+    tracing_subscriber::fmt().with_env_filter(tracing_subscriber::EnvFilter::from_default_env()).with_writer(io::stderr).init();
+    let mut args = env::args().skip(1).collect::<Vec<_>>();
+    let mut charset = Charset::Ascii;
+    let mut theme = None;
+    let mut accessible = false;
+    let mut config_path = None;
+    let mut memory = None;
+    // the options of all commands come before the command
+    loop {
+        match args.first().map(String::as_str) {
+            Some("--unicode") => {
+                args.remove(0);
+                charset = Charset::Unicode;
+            }
+            Some("--accessible") => {
+                args.remove(0);
+                accessible = true;
+            }
+            Some("--theme") if args.len() > 1 => {
+                match args[1].parse() {
+                    Ok(name) => theme = Some(name),
+                    Err(()) => {
+                        eprintln!("error: unknown theme: {}", args[1]);
+                        process::exit(2);
+                    }
+                }
+                args.drain(..2);
+            }
+            Some("--config") if args.len() > 1 => {
+                config_path = Some(args[1].clone());
+                args.drain(..2);
+            }
+            Some("--memory") if args.len() > 1 => {
+                match args[1].parse::<usize>() {
+                    Ok(mib) => memory = Some(mib),
+                    Err(_) => {
+                        eprintln!("error: invalid memory: {}", args[1]);
+                        process::exit(2);
+                    }
+                }
+                args.drain(..2);
+            }
+            _ => break
+        }
+    }
+    let loaded = match config_path {
+        Some(path) => config::Config::load(path),
+        None => config::Config::load_default()
+    };
+    let config = CONFIG.get_or_init(|| loaded.unwrap_or_else(|error| {
+        eprintln!("error: {}", error);
+        process::exit(2);
+    }));
+    if config.unicode {
+        charset = Charset::Unicode;
+    }
+    accessible |= config.accessible;
+    if let Some(mib) = memory.or(config.engine.memory) {
+        set_memory_cap(Some(mib.saturating_mul(1024 * 1024)));
+    }
+    // colors would end up in files and pipes, and the environment names the theme of a session
+    let theme = theme.unwrap_or_else(|| match config.theme {
+        _ if !io::stdout().is_terminal() => Theme::Monochrome,
+        Some(theme) if env::var_os(THEME_VARIABLE).is_none() && Theme::from_env() != Theme::Monochrome => theme,
+        _ => Theme::from_env()
+    });
+    let screen = Screen { charset, theme, accessible };
+    let (command, rest) = match args.split_first() {
+        Some((command, rest)) => (command.as_str(), rest),
+        None => usage()
+    };
+    let result = match command {
+        "play" => play(rest, screen),
+        "tui" => tui(rest, screen),
+        "host" | "join" => network(command, rest, screen),
+        "correspond" => correspond(rest, screen),
+        "serve" => serve(rest),
+        "api" => api(rest),
+        "edax" | "gtp" => protocol(command, rest, charset),
+        "ggs" => ggs(rest),
+        "discord" => discord(rest),
+        "telegram" => telegram(rest),
+        "analyze" => analyze(rest, screen),
+        "fairplay" => fairplay(rest),
+        "solve" => solve(rest),
+        "perft" => perft(rest),
+        "tree" => tree(rest),
+        "bench" => bench(rest),
+        "match" => run_match(rest),
+        "simulate" => simulate(rest),
+        "tournament" => run_tournament(rest),
+        "book" => run_book(rest),
+        "db" => run_db(rest),
+        "ffo" => run_suite(rest),
+        "puzzles" => puzzles(rest),
+        "quiz" => quiz(rest, screen),
+        "ratings" => ratings(rest),
+        "view" => view(rest, screen),
+        "replay" => replay(rest),
+        "share" => share(rest),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        _ => usage()
+    };
+    if let Err(error) = result {
+        eprintln!("error: {}", error);
+        process::exit(1);
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2)
+}
+
+// The arguments of a command, split into positional arguments and flags.
+struct Arguments {
+    positional: Vec<String>,
+    flags: Vec<(String, Option<String>)>
+}
+impl Arguments {
+    // Reads `--flag value` or `--flag=value` for the flags taking a value and `--flag` for the
+    // switches. Anything after `--` is positional.
+    fn parse(args: &[String], flags: &[&str], switches: &[&str]) -> io::Result<Arguments> {
+        let mut arguments = Arguments { positional: Vec::new(), flags: Vec::new() };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if arg == "--" {
+                arguments.positional.extend(args.cloned());
+                break;
+            }
+            // positions may start with empty cells, which aren't flags
+            if !arg.starts_with("--") || !arg[2..].starts_with(|c: char| c.is_ascii_alphabetic()) {
+                arguments.positional.push(arg.clone());
+                continue;
+            }
+            let (name, value) = match arg.find('=') {
+                Some(i) => (&arg[2..i], Some(arg[i + 1..].to_string())),
+                None => (&arg[2..], None)
+            };
+            if switches.contains(&name) {
+                if value.is_some() {
+                    return Err(invalid_input(&format!("--{} takes no value", name)));
+                }
+                arguments.flags.push((name.to_string(), None));
+            } else if flags.contains(&name) {
+                let value = value.or_else(|| args.next().cloned()).ok_or_else(|| invalid_input(&format!("--{} needs a value", name)))?;
+                arguments.flags.push((name.to_string(), Some(value)));
+            } else {
+                return Err(invalid_input(&format!("unknown flag: --{}", name)));
+            }
+        }
+        Ok(arguments)
+    }
+
+    // Fails unless there are between `min` and `max` positional arguments.
+    fn expect(&self, min: usize, max: usize) -> io::Result<()> {
+        match self.positional.len() {
+            n if n < min => Err(invalid_input("missing arguments")),
+            n if n > max => Err(invalid_input(&format!("unexpected argument: {}", self.positional[max]))),
+            _ => Ok(())
+        }
+    }
+
+    fn has(&self, name: &str) -> bool {
+        self.flags.iter().any(|x| x.0 == name)
+    }
+
+    // All the values of a flag given several times.
+    fn all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.flags.iter().filter(move |x| x.0 == name).filter_map(|x| x.1.as_deref())
+    }
+
+    // The last value of a flag.
+    fn get<T: FromStr>(&self, name: &str) -> io::Result<Option<T>> {
+        match self.all(name).last() {
+            Some(value) => value.parse().map(Some).map_err(|_| invalid_input(&format!("invalid value for --{}: {}", name, value))),
+            None => Ok(None)
+        }
+    }
+}
+
+fn set_option(engine: &mut Box<dyn Engine>, option: &str) -> io::Result<()> {
+    match option.find('=') {
+        Some(i) => engine.set_option(option[..i].trim(), &option[i + 1..]),
+        None => Err(invalid_input(&format!("options are written name=value: {}", option)))
+    }
+}
+
+// The engine given by `--engine` and `--option`, or by the configuration file. Options of the
+// configuration only apply to its engine.
+fn configured_engine(args: &Arguments) -> io::Result<Box<dyn Engine>> {
+    let defaults = &configuration().engine;
+    let mut engine = match args.get::<String>("engine")? {
+        Some(spec) => from_spec(&spec)?,
+        None => {
+            let mut engine = from_spec(defaults.name.as_deref().unwrap_or("alphabeta"))?;
+            for (name, value) in &defaults.options {
+                engine.set_option(name, value)?;
+            }
+            engine
+        }
+    };
+    for option in args.all("option") {
+        set_option(&mut engine, option)?;
+    }
+    Ok(engine)
+}
+
+// The configured engine playing the moves of the book given by `--book` or the configuration
+// file, if any, with the variety of its moves given the same way.
+fn playing_engine(args: &Arguments) -> io::Result<Box<dyn Engine>> {
+    let engine = configured_engine(args)?;
+    let defaults = &configuration().engine;
+    let book = match args.get::<String>("book")? {
+        Some(path) => Some(PathBuf::from(path)),
+        None => defaults.book.clone()
+    };
+    let path = match book {
+        Some(path) => path,
+        None => return Ok(engine)
+    };
+    let mut booked = Booked::new(open_book(&path)?, engine);
+    // games of different runs start differently
+    booked.random = Random::new(SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |x| x.as_nanos() as u64));
+    let variety = [
+        ("book_temperature", defaults.book_temperature.map(|x| x.to_string())),
+        ("book_min_games", defaults.book_min_games.map(|x| x.to_string())),
+        ("book_max_deviation", defaults.book_max_deviation.map(|x| x.to_string()))
+    ];
+    for (name, default) in variety {
+        if let Some(value) = args.get::<String>(&name.replace('_', "-"))?.or(default) {
+            booked.set_option(name, &value)?;
+        }
+    }
+    Ok(Box::new(booked))
+}
+
+// Opens a book, mapped to memory with the mmap feature so that large books don't have to fit in
+// it. Books of the older versions can't be mapped, and are loaded.
+fn open_book(path: &Path) -> io::Result<Box<dyn OpeningBook>> {
+    #[cfg(feature = "mmap")]
+    {
+        if let Ok(book) = book::MappedBook::open(path) {
+            return Ok(Box::new(book));
+        }
+    }
+    Ok(Box::new(Book::load(path)?))
+}
+
+// The limits given by `--depth`, `--nodes` and `--time`, or by the configuration file.
+fn configured_limits(args: &Arguments) -> io::Result<Limits> {
+    let defaults = &configuration().engine;
+    let mut limits = limits(args)?;
+    limits.depth = limits.depth.or(defaults.depth);
+    limits.nodes = limits.nodes.or(defaults.nodes);
+    limits.time = limits.time.or(defaults.time);
+    Ok(limits)
+}
+
+fn configuration() -> &'static config::Config {
+    CONFIG.get_or_init(config::Config::default)
+}
+
+// The limits given by `--depth`, `--nodes` and `--time`.
+fn limits(args: &Arguments) -> io::Result<Limits> {
+    Ok(Limits { depth: args.get("depth")?, nodes: args.get("nodes")?, time: duration(args, "time", 1.0)? })
+}
+
+// The duration given by a flag in units of `unit` seconds, refusing negative ones and ones too
+// long to be represented.
+fn duration(args: &Arguments, flag: &str, unit: f64) -> io::Result<Option<Duration>> {
+    match args.get::<f64>(flag)? {
+        Some(value) => match Duration::try_from_secs_f64(value * unit) {
+            Ok(duration) => Ok(Some(duration)),
+            Err(_) => Err(invalid_input(&format!("invalid value for --{}: {}", flag, value)))
+        },
+        None => Ok(None)
+    }
+}
+
+fn position(text: &str) -> io::Result<(Board, Color)> {
+    record::parse_position(text).ok_or_else(|| invalid_input("invalid position"))
+}
+
+// Plays a game between a human, entering moves like `f5`, and an engine.
+fn play(args: &[String], screen: Screen) -> io::Result<()> {
+    let flags = [ENGINE_FLAGS, BOOK_FLAGS, &["color", "save", "load", "profile", "ratings", "db"]].concat();
+    let args = Arguments::parse(args, &flags, &[])?;
+    args.expect(0, 0)?;
+    let mut human = human_color(&args)?;
+    let mut engine = playing_engine(&args)?;
+    let limits = configured_limits(&args)?;
+    let mut session = match args.get::<String>("load")? {
+        Some(path) => {
+            let session = GameSession::load(path)?;
+            // the human keeps their side
+            match (&session.black, &session.white) {
+                (&Player::Human(_), &Player::Engine { .. }) => human = Color::Black,
+                (&Player::Engine { .. }, &Player::Human(_)) => human = Color::White,
+                _ => {}
+            }
+            session
+        }
+        None => session(&args, human)?
+    };
+    let database = args.get::<String>("db")?;
+    if database.is_some() && cfg!(not(feature = "db")) {
+        return Err(io::Error::other("game databases need the db feature"));
+    }
+    let save = args.get::<String>("save")?;
+    let save = |session: &GameSession| save.as_ref().map_or(Ok(()), |path| session.save(path));
+    println!("Enter moves like f5, undo to take back your last move, or quit.");
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    while !session.is_over() {
+        let color = session.color();
+        if color != human {
+            let result = engine.best_move(session.board(), color, &limits);
+            let position = result.best.ok_or_else(|| io::Error::other("the engine found no move"))?;
+            session.play(position, result.time)?;
+            save(&session)?;
+            if !screen.accessible {
+                println!("{} plays {}", color, record::format_move(position));
+            }
+            announce(&session, screen);
+            continue;
+        }
+        if screen.accessible {
+            print!("{}{} to move: ", speech::describe_board(session.board(), Some(human)), color);
+        } else {
+            let highlights = Highlights { hints: Some(human), last_move: session.last_move() };
+            let board = session.board().draw_highlighted(screen.charset, &highlights);
+            print!("{}{} to move: ", screen.theme.paint(&board), color);
+        }
+        io::stdout().flush()?;
+        let start = Instant::now();
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => return Ok(())
+        };
+        match line.trim() {
+            "quit" | "q" => return Ok(()),
+            "undo" | "u" => {
+                // takes back the answers of the engine too
+                loop {
+                    match session.undo() {
+                        Some(turn) if turn.color == human => break,
+                        Some(_) => {}
+                        None => {
+                            println!("no move to take back");
+                            break;
+                        }
+                    }
+                }
+                save(&session)?;
+            }
+            text => match record::parse_move(text) {
+                Some(position) => {
+                    match session.play(position, start.elapsed()) {
+                        Ok(()) => {
+                            save(&session)?;
+                            announce(&session, screen);
+                        }
+                        Err(error) => println!("error: {}", error)
+                    }
+                }
+                None => println!("error: invalid move: {}", text)
+            }
+        }
+    }
+    let board = session.board();
+    let difference = board.count(Color::Black) as i32 - board.count(Color::White) as i32;
+    rate(args.get("ratings")?, &[Outcome::new(session.black.name(), session.white.name(), difference)])?;
+    if let Some(path) = database {
+        store(&path, &session.record())?;
+    }
+    if screen.accessible {
+        println!("{}", speech::describe_result(board));
+        return Ok(());
+    }
+    let text = screen.theme.paint(&board.draw_highlighted(screen.charset, &Highlights { hints: None, last_move: session.last_move() }));
+    println!("{}Game over: {}-{}", text, board.count(Color::Black), board.count(Color::White));
+    Ok(())
+}
+
+// Hosts or joins a game over the network and plays it on the console.
+#[cfg(feature = "net")]
+fn network(command: &str, args: &[String], screen: Screen) -> io::Result<()> {
+    use std::net::TcpListener;
+
+    use rusty_reversi::net::remote::{self, Ending, RemoteGame, Update};
+
+    // Connects a guest again after it lost the connection, or passes other errors on.
+    fn recover(game: &mut RemoteGame, error: io::Error) -> io::Result<()> {
+        if !remote::is_disconnect(&error) {
+            return Err(error);
+        }
+        println!("Connection lost, reconnecting...");
+        game.reconnect()
+    }
+
+    let hosting = command == "host";
+    let flags: &[&str] = if hosting { &["port", "color", "clock", "increment", "name", "lag"] } else { &["name", "token"] };
+    let args = Arguments::parse(args, flags, &[])?;
+    args.expect(if hosting { 0 } else { 1 }, if hosting { 0 } else { 1 })?;
+    let name = args.get::<String>("name")?.or_else(|| env::var("USER").ok()).unwrap_or_else(|| "player".to_string());
+    let mut game = if hosting {
+        let clock = match (duration(&args, "clock", 60.0)?, duration(&args, "increment", 1.0)?) {
+            (None, None) => None,
+            (remaining, increment) => Some(Clock {
+                remaining: remaining.unwrap_or(Duration::from_secs(5 * 60)),
+                increment: increment.unwrap_or_default()
+            })
+        };
+        let listener = TcpListener::bind(("0.0.0.0", args.get("port")?.unwrap_or(7470u16)))?;
+        println!("Waiting on port {}...", listener.local_addr()?.port());
+        let mut game = RemoteGame::host(&listener, &name, human_color(&args)?, clock)?;
+        game.set_lag_compensation(Duration::from_millis(args.get("lag")?.unwrap_or(0)));
+        game
+    } else {
+        let token = args.get::<String>("token")?.unwrap_or_default();
+        let game = RemoteGame::rejoin(args.positional[0].as_str(), &name, &token)?;
+        println!("Come back to the game with --token {} if you leave it.", game.token());
+        game
+    };
+    let color = game.color();
+    println!("Playing {} against {}. Enter moves like f5, say something, or resign.", color, game.opponent());
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    let ending = loop {
+        if let Some(ending) = game.ending() {
+            break ending;
+        }
+        if !game.is_my_turn() {
+            println!("Waiting for {}...", game.opponent());
+            match game.wait() {
+                Ok(Update::Moved(turn)) => {
+                    if !screen.accessible {
+                        println!("{} plays {}", turn.color, record::format_move(turn.position));
+                    }
+                    announce(game.session(), screen);
+                }
+                Ok(Update::Chat(text)) => println!("{}: {}", game.opponent(), text),
+                Ok(Update::Over(_)) => (),
+                Err(error) => recover(&mut game, error)?
+            }
+            continue;
+        }
+        let session = game.session();
+        let clock = session.clock(color).map(|x| format!(" ({:.0}s left)", x.remaining.as_secs_f64())).unwrap_or_default();
+        if screen.accessible {
+            print!("{}{} to move{}: ", speech::describe_board(session.board(), Some(color)), color, clock);
+        } else {
+            let highlights = Highlights { hints: Some(color), last_move: session.last_move() };
+            print!("{}{} to move{}: ", screen.theme.paint(&session.board().draw_highlighted(screen.charset, &highlights)), color, clock);
+        }
+        io::stdout().flush()?;
+        let start = Instant::now();
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => "resign".to_string()
+        };
+        match line.trim() {
+            "resign" | "quit" | "q" => if let Err(error) = game.resign() {
+                recover(&mut game, error)?;
+            },
+            text if text.starts_with("say ") => match game.say(text[4..].trim()) {
+                Ok(()) => (),
+                Err(ref error) if error.kind() == io::ErrorKind::InvalidInput => println!("error: {}", error),
+                Err(error) => recover(&mut game, error)?
+            },
+            text => match record::parse_move(text) {
+                Some(position) => match game.play(position, start.elapsed()) {
+                    Ok(()) => announce(game.session(), screen),
+                    Err(ref error) if error.kind() == io::ErrorKind::InvalidInput => println!("error: {}", error),
+                    Err(error) => recover(&mut game, error)?
+                },
+                None => println!("error: invalid move: {}", text)
+            }
+        }
+    };
+    match ending {
+        Ending::Resigned(loser) => println!("{} resigned.", loser),
+        Ending::Time(loser) => println!("{} ran out of time.", loser),
+        Ending::Finished { black_disks, white_disks } => {
+            let board = game.session().board();
+            if screen.accessible {
+                println!("{}", speech::describe_result(board));
+            } else {
+                let text = screen.theme.paint(&board.draw_highlighted(screen.charset, &Highlights { hints: None, last_move: game.session().last_move() }));
+                println!("{}Game over: {}-{}", text, black_disks, white_disks);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "net"))]
+fn network(_: &str, _: &[String], _: Screen) -> io::Result<()> {
+    Err(io::Error::other("network games need the net feature"))
+}
+
+// Plays a move of a game by correspondence and prints the token to send back.
+fn correspond(args: &[String], screen: Screen) -> io::Result<()> {
+    let args = Arguments::parse(args, &[], &[])?;
+    args.expect(1, 2)?;
+    let position = record::parse_move(&args.positional[0]).ok_or_else(|| invalid_input(&format!("invalid move: {}", args.positional[0])))?;
+    let token = match args.positional.get(1) {
+        Some(token) => {
+            let received = correspondence::decode(token)?;
+            let last = received.history.len() + 1;
+            println!("move {}: {}", last, record::format_move(received.proposed));
+            received.reply(position)?
+        }
+        None => correspondence::encode(&[], position).map_err(|_| invalid_input("illegal move"))?
+    };
+    let sent = correspondence::decode(&token)?;
+    print!("{}", screen.theme.paint(&sent.board.draw_with_hints(screen.charset, None)));
+    if sent.is_over() {
+        println!("game over: {}-{}", sent.board.count(Color::Black), sent.board.count(Color::White));
+    } else {
+        println!("{} to move", sent.color);
+    }
+    println!("token {}", token);
+    Ok(())
+}
+
+// Serves games to browsers until stopped.
+#[cfg(feature = "web")]
+fn serve(args: &[String]) -> io::Result<()> {
+    use rusty_reversi::search::AlphaBeta;
+    use rusty_reversi::web::Server;
+
+    let args = Arguments::parse(args, &["port", "archive", "delay", "eval-depth", "lag"], &[])?;
+    args.expect(0, 0)?;
+    let mut server = Server::bind(("0.0.0.0", args.get("port")?.unwrap_or(7480u16)))?;
+    if let Some(path) = args.get::<String>("archive")? {
+        server.set_archive(path);
+    }
+    if let Some(lag) = args.get::<u64>("lag")? {
+        server.set_lag_compensation(Duration::from_millis(lag));
+    }
+    if let Some(seconds) = args.get::<u64>("delay")? {
+        server.set_spectator_delay(Duration::from_secs(seconds));
+    }
+    if let Some(depth) = args.get::<u32>("eval-depth")? {
+        let limits = Limits { depth: Some(depth), ..Limits::default() };
+        server.set_evaluation(AlphaBeta::new(depth, Heuristic::default()), limits);
+    }
+    println!("Serving on ws://{}", server.local_addr()?);
+    server.run()
+}
+
+#[cfg(not(feature = "web"))]
+fn serve(_: &[String]) -> io::Result<()> {
+    Err(io::Error::other("the game server needs the web feature"))
+}
+
+// Serves games over HTTP until stopped.
+#[cfg(feature = "api")]
+fn api(args: &[String]) -> io::Result<()> {
+    use rusty_reversi::api::Api;
+    use std::net::TcpListener;
+
+    let flags = [ENGINE_FLAGS, BOOK_FLAGS, &["port"]].concat();
+    let args = Arguments::parse(args, &flags, &[])?;
+    args.expect(0, 0)?;
+    let mut api = Api::new(playing_engine(&args)?);
+    api.limits = configured_limits(&args)?;
+    let listener = TcpListener::bind(("0.0.0.0", args.get("port")?.unwrap_or(7490u16)))?;
+    println!("Serving on http://{}", listener.local_addr()?);
+    api.serve(listener)
+}
+
+#[cfg(not(feature = "api"))]
+fn api(_: &[String]) -> io::Result<()> {
+    Err(io::Error::other("the HTTP API needs the api feature"))
+}
+
+// Describes the move just played, and the pass following it if any, in accessible mode.
+fn announce(session: &GameSession, screen: Screen) {
+    if !screen.accessible {
+        return;
+    }
+    if let Some(last) = session.last_move() {
+        println!("{}.", speech::describe_move(session.board(), &last));
+        if !session.is_over() && session.color() == last.color {
+            println!("{} passes.", last.color.flip());
+        }
+    }
+}
+
+// The color of the human player given by `--color`.
+fn human_color(args: &Arguments) -> io::Result<Color> {
+    match args.get::<String>("color")?.as_deref() {
+        None | Some("black") => Ok(Color::Black),
+        Some("white") => Ok(Color::White),
+        Some(color) => Err(invalid_input(&format!("invalid value for --color: {}", color)))
+    }
+}
+
+// A session between the human profile given by `--profile` and the engine given by `--engine`.
+fn session(args: &Arguments, human: Color) -> io::Result<GameSession> {
+    let name = args.get::<String>("engine")?.or_else(|| configuration().engine.name.clone()).unwrap_or_else(|| "alphabeta".to_string());
+    let profile = args.get::<String>("profile")?.unwrap_or_else(|| "human".to_string());
+    let players = (Player::Human(profile), Player::Engine { name, options: Vec::new() });
+    Ok(match human {
+        Color::Black => GameSession::new(players.0, players.1),
+        Color::White => GameSession::new(players.1, players.0)
+    })
+}
+
+// Plays against an engine in a full-screen interface.
+#[cfg(feature = "tui")]
+fn tui(args: &[String], screen: Screen) -> io::Result<()> {
+    use rusty_reversi::tui::{self, App};
+
+    let flags = [ENGINE_FLAGS, BOOK_FLAGS, &["color", "clock"]].concat();
+    let args = Arguments::parse(args, &flags, &["no-hints"])?;
+    args.expect(0, 0)?;
+    let human = human_color(&args)?;
+    let mut session = session(&args, human)?;
+    if let Some(minutes) = args.get::<u64>("clock")? {
+        let clock = Clock { remaining: Duration::from_secs(minutes * 60), increment: Duration::default() };
+        session.clocks = Some([clock, clock]);
+    }
+    let mut app = App::new(playing_engine(&args)?, session, human);
+    app.limits = configured_limits(&args)?;
+    for (action, key) in &configuration().keys {
+        app.keys.bind(action, key)?;
+    }
+    app.charset = screen.charset;
+    app.theme = screen.theme;
+    app.hints = !args.has("no-hints");
+    tui::run(&mut app)
+}
+
+#[cfg(not(feature = "tui"))]
+fn tui(_: &[String], _: Screen) -> io::Result<()> {
+    Err(io::Error::other("the full-screen interface needs the tui feature"))
+}
+
+fn protocol(name: &str, args: &[String], charset: Charset) -> io::Result<()> {
+    let flags = [ENGINE_FLAGS, BOOK_FLAGS].concat();
+    let args = Arguments::parse(args, &flags, &[])?;
+    args.expect(0, 0)?;
+    let engine = playing_engine(&args)?;
+    let limits = configured_limits(&args)?;
+    let stdin = io::stdin();
+    if name == "edax" {
+        let mut edax = Edax::new(engine);
+        edax.limits = limits;
+        edax.charset = charset;
+        edax.run(stdin.lock(), io::stdout())
+    } else {
+        let mut gtp = Gtp::new(engine);
+        gtp.limits = limits;
+        gtp.charset = charset;
+        gtp.run(stdin.lock(), io::stdout())
+    }
+}
+
+// Plays on a Generic Game Server until it closes the connection.
+fn ggs(args: &[String]) -> io::Result<()> {
+    let flags = [ENGINE_FLAGS, BOOK_FLAGS, &["login", "games", "min-rating", "records", "db"]].concat();
+    let args = Arguments::parse(args, &flags, &["rated"])?;
+    args.expect(1, 1)?;
+    let login = args.get::<String>("login")?.ok_or_else(|| invalid_input("missing --login"))?;
+    let password = env::var("GGS_PASSWORD").map_err(|_| invalid_input("missing GGS_PASSWORD"))?;
+    let mut client = Ggs::new(playing_engine(&args)?, &login, &password);
+    client.limits = configured_limits(&args)?;
+    client.policy = Policy { games: args.get("games")?.unwrap_or(1), unrated: !args.has("rated"), min_rating: args.get("min-rating")? };
+    let stream = TcpStream::connect(&args.positional[0])?;
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        match client.handle(&line) {
+            Ok(answers) => {
+                for answer in answers {
+                    writeln!(writer, "{}", answer)?;
+                }
+            }
+            Err(error) => eprintln!("ignored {:?}: {}", line, error)
+        }
+        for record in client.take_finished() {
+            let score = record.black_disks.map_or("?".to_string(), |x| format!("{}-{}", x, 64 - x));
+            println!("{} - {}: {}", record.black, record.white, score);
+            if let Some(path) = args.get::<String>("records")? {
+                let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+                let separator = if file.metadata()?.len() > 0 { "\n" } else { "" };
+                write!(file, "{}{}", separator, pgn::format(&record)?)?;
+            }
+            if let Some(path) = args.get::<String>("db")? {
+                store(&path, &record)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Plays in the channels of Discord until the connection fails.
+#[cfg(feature = "discord")]
+fn discord(args: &[String]) -> io::Result<()> {
+    use rusty_reversi::chat::Bot;
+    use rusty_reversi::discord;
+
+    let flags = [ENGINE_FLAGS, BOOK_FLAGS, &["name"]].concat();
+    let args = Arguments::parse(args, &flags, &[])?;
+    args.expect(0, 0)?;
+    let token = env::var("DISCORD_TOKEN").map_err(|_| invalid_input("missing DISCORD_TOKEN"))?;
+    let name = args.get::<String>("name")?.unwrap_or_else(|| "rusty_reversi".to_string());
+    let mut bot = Bot::new(playing_engine(&args)?, &name);
+    bot.limits = configured_limits(&args)?;
+    discord::run(&token, &mut bot)
+}
+
+#[cfg(not(feature = "discord"))]
+fn discord(_: &[String]) -> io::Result<()> {
+    Err(io::Error::other("the Discord bot needs the discord feature"))
+}
+
+// Plays in the chats of Telegram until a request fails.
+#[cfg(feature = "telegram")]
+fn telegram(args: &[String]) -> io::Result<()> {
+    use rusty_reversi::chat::Bot;
+    use rusty_reversi::telegram;
+
+    let flags = [ENGINE_FLAGS, BOOK_FLAGS, &["name"]].concat();
+    let args = Arguments::parse(args, &flags, &[])?;
+    args.expect(1, 1)?;
+    let token = env::var("TELEGRAM_TOKEN").map_err(|_| invalid_input("missing TELEGRAM_TOKEN"))?;
+    let name = args.get::<String>("name")?.unwrap_or_else(|| "rusty_reversi".to_string());
+    let mut bot = Bot::new(playing_engine(&args)?, &name);
+    bot.limits = configured_limits(&args)?;
+    fs::create_dir_all(&args.positional[0])?;
+    telegram::run(&token, &mut bot, &args.positional[0])
+}
+
+#[cfg(not(feature = "telegram"))]
+fn telegram(_: &[String]) -> io::Result<()> {
+    Err(io::Error::other("the Telegram bot needs the telegram feature"))
+}
+
+// Prints a position as a diagram, as text or in a format to paste, with the scores of its moves.
+fn analyze(args: &[String], screen: Screen) -> io::Result<()> {
+    let flags = [ENGINE_FLAGS, &["format", "blunder"]].concat();
+    let args = Arguments::parse(args, &flags, &[])?;
+    args.expect(1, 1)?;
+    let mut engine = configured_engine(&args)?;
+    let limits = configured_limits(&args)?;
+    let format = args.get::<String>("format")?;
+    let (board, color) = match pasted(&args.positional[0])? {
+        (Pasted::Position(board, color), _) => (board, color),
+        (Pasted::Games(games), times) => {
+            let blunder = args.get("blunder")?.unwrap_or(50);
+            for (i, record) in games.iter().enumerate() {
+                let mut game = analysis::annotate(record, &mut engine, &limits)
+                    .map_err(|i| invalid_input(&format!("illegal move {}", i + 1)))?;
+                for (annotation, &time) in game.annotations.iter_mut().zip(&times) {
+                    annotation.time = Some(time);
+                }
+                if i > 0 {
+                    println!();
+                }
+                match format.as_deref() {
+                    None | Some("text") => print!("{}", analysis::format_log(&game, blunder)?),
+                    Some("pgn") => print!("{}", pgn::format_annotated(&game)?),
+                    Some(format) => return Err(invalid_input(&format!("unknown log format: {}", format)))
+                }
+            }
+            return Ok(());
+        }
+    };
+    match format.as_deref() {
+        None | Some("text") if screen.accessible => println!("{}{} to move", speech::describe_board(&board, Some(color)), color),
+        None | Some("text") => println!("{}{} to move", screen.theme.paint(&board.draw_with_hints(screen.charset, Some(color))), color),
+        Some("markdown") => print!("{}", diagram::markdown(&board, color)),
+        Some("html") => print!("{}", diagram::html(&board, color)),
+        Some(format) => return Err(invalid_input(&format!("unknown diagram format: {}", format)))
+    }
+    let mut scores = board.legal_moves(color).iter().map(|legal_move| {
+        (legal_move.position, analysis::score_move(&mut engine, &board, legal_move, &limits))
+    }).collect::<Vec<_>>();
+    scores.sort_by_key(|x| -x.1);
+    println!();
+    for (position, score) in scores {
+        println!("{} {:+}", record::format_move(position), score);
+    }
+    Ok(())
+}
+
+// Reports on the moves of the players of some games, or of one of them.
+fn fairplay(args: &[String]) -> io::Result<()> {
+    let flags = [ENGINE_FLAGS, &["player", "format"]].concat();
+    let args = Arguments::parse(args, &flags, &[])?;
+    args.expect(1, 1)?;
+    let mut engine = configured_engine(&args)?;
+    let limits = configured_limits(&args)?;
+    let records = match pasted(&args.positional[0])? {
+        (Pasted::Games(records), _) => records,
+        (Pasted::Position(..), _) => return Err(invalid_input("expected games, found a position"))
+    };
+    let games = records.iter().map(|record| {
+        analysis::annotate(record, &mut engine, &limits).map_err(|i| invalid_input(&format!("illegal move {}", i + 1)))
+    }).collect::<io::Result<Vec<_>>>()?;
+    let players = match args.get::<String>("player")? {
+        Some(player) => vec![player],
+        None => {
+            let mut players = Vec::new();
+            for name in records.iter().flat_map(|x| vec![x.black.clone(), x.white.clone()]) {
+                if !players.contains(&name) {
+                    players.push(name);
+                }
+            }
+            players
+        }
+    };
+    let reports = players.iter().map(|x| fairplay::report(&games, x, &fairplay::Options::default()));
+    match args.get::<String>("format")?.as_deref() {
+        None | Some("text") => reports.for_each(|x| print!("{}", x.format())),
+        Some("json") => println!("{}", json::Value::Array(reports.map(|x| x.to_json()).collect())),
+        Some(format) => return Err(invalid_input(&format!("unknown report format: {}", format)))
+    }
+    Ok(())
+}
+
+// Reads a position or games from a file, a saved game or the text itself, with the times spent
+// on the moves of saved games.
+fn pasted(argument: &str) -> io::Result<(Pasted, Vec<Duration>)> {
+    let path = Path::new(argument);
+    if !path.is_file() {
+        return formats::sniff(argument).map(|x| (x.1, Vec::new()));
+    }
+    if let Ok(session) = GameSession::load(path) {
+        let times = session.history().iter().map(|x| x.time).collect();
+        return Ok((Pasted::Games(vec![session.record()]), times));
+    }
+    formats::sniff(&fs::read_to_string(path)?).map(|x| (x.1, Vec::new()))
+}
+
+fn solve(args: &[String]) -> io::Result<()> {
+    let args = Arguments::parse(args, &[], &["wld"])?;
+    args.expect(1, 1)?;
+    let (board, color) = position(&args.positional[0])?;
+    let start = Instant::now();
+    let mode = if args.has("wld") { EndgameMode::WinLossDraw } else { EndgameMode::Exact };
+    let (solution, line, nodes) = endgame::solve_line(&board, color, mode);
+    let time = start.elapsed();
+    let score = match (mode, solution.score.signum()) {
+        (EndgameMode::Exact, _) => format!("{:+}", solution.score),
+        (_, 1) => "win".to_string(),
+        (_, -1) => "loss".to_string(),
+        _ => "draw".to_string()
+    };
+    let line = if line.is_empty() { "ps".to_string() } else { line.iter().map(|&x| record::format_move(x)).collect::<Vec<_>>().join(" ") };
+    println!("score {} for {}", score, color);
+    println!("line {}", line);
+    println!("nodes {} ({:.0} per second)", nodes, nodes as f64 / time.as_secs_f64().max(1e-9));
+    println!("time {:.3}s", time.as_secs_f64());
+    Ok(())
+}
+
+// Prints the tree of a search with the heuristic, showing how it pruned.
+fn tree(args: &[String]) -> io::Result<()> {
+    let args = Arguments::parse(args, &["limit"], &[])?;
+    args.expect(1, 2)?;
+    let depth = args.positional[0].parse::<u32>().map_err(|_| invalid_input(&format!("invalid depth: {}", args.positional[0])))?;
+    let (board, color) = match args.positional.get(1) {
+        Some(text) => position(text)?,
+        None => (Board::new(), Color::Black)
+    };
+    let mut tree = SearchTree::new(args.get("limit")?.unwrap_or(1000));
+    search::alpha_beta_with_options(&board, color, depth, &Heuristic::default(), &mut search::Options { tree: Some(&mut tree), ..search::Options::default() });
+    tree.write_dot(io::stdout().lock())
+}
+
+fn perft(args: &[String]) -> io::Result<()> {
+    let args = Arguments::parse(args, &[], &["divide"])?;
+    args.expect(1, 2)?;
+    let depth = args.positional[0].parse::<u32>().map_err(|_| invalid_input(&format!("invalid depth: {}", args.positional[0])))?;
+    let (board, color) = match args.positional.get(1) {
+        Some(text) => position(text)?,
+        None => (Board::new(), Color::Black)
+    };
+    if args.has("divide") {
+        let start = Instant::now();
+        let divided = board.divide(color, depth);
+        for &(position, count) in &divided {
+            println!("{} {:>14}", position.map(record::format_move).unwrap_or_else(|| "ps".to_string()), count);
+        }
+        let total = if divided.is_empty() { 1 } else { divided.iter().map(|x| x.1).sum() };
+        println!("{} moves {:>14} {:>9.3}s", divided.len(), total, start.elapsed().as_secs_f64());
+        return Ok(());
+    }
+    for depth in 1..=depth {
+        let start = Instant::now();
+        let count = board.perft(color, depth);
+        println!("{:>2} {:>14} {:>9.3}s", depth, count, start.elapsed().as_secs_f64());
+    }
+    Ok(())
+}
+
+// Searches the bench positions, to depth 6 unless limited otherwise. Without a time limit the
+// total of the nodes is the same on every machine, so it tells whether a change altered the search.
+fn bench(args: &[String]) -> io::Result<()> {
+    let args = Arguments::parse(args, ENGINE_FLAGS, &[])?;
+    args.expect(0, 0)?;
+    let mut engine = configured_engine(&args)?;
+    let mut limits = limits(&args)?;
+    if limits == Limits::default() {
+        limits.depth = Some(6);
+    }
+    let (mut nodes, mut time) = (0, Duration::default());
+    let mut stats = SearchStats::default();
+    for (i, position) in bench::positions().iter().enumerate() {
+        let result = engine.best_move(&position.board, position.color, &limits);
+        let best = result.best.map(record::format_move).unwrap_or_else(|| "ps".to_string());
+        println!("{:>2} {} {:+7} depth {:>2} {:>12} nodes {:>9.3}s", i + 1, best, result.score, result.depth, result.nodes, result.time.as_secs_f64());
+        nodes += result.nodes;
+        time += result.time;
+        stats.add(&result.stats);
+    }
+    println!("nodes {}", nodes);
+    println!("nps {:.0}", nodes as f64 / time.as_secs_f64().max(1e-9));
+    print_stats("", &stats);
+    Ok(())
+}
+
+// Prints the details of searches, if the engine collected them, each line starting with the prefix.
+fn print_stats(prefix: &str, stats: &SearchStats) {
+    if stats.iterations.is_empty() {
+        return;
+    }
+    let nodes_by_ply = stats.nodes_by_ply.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+    println!("{}nodes by ply {}", prefix, nodes_by_ply.join(" "));
+    println!("{}tt hits {:.1}% of {} probes", prefix, stats.tt_hit_rate() * 100.0, stats.tt_probes);
+    let by_move = stats.cutoff_rate_by_move().iter().map(|x| format!("{:.1}%", x * 100.0)).collect::<Vec<_>>();
+    println!("{}cutoffs {:.1}% of {} expanded, by move {}", prefix, stats.cutoff_rate() * 100.0, stats.expanded, by_move.join(" "));
+    let mut by_depth = BTreeMap::new();
+    for iteration in &stats.iterations {
+        let entry = by_depth.entry(iteration.depth).or_insert((0, 0, Duration::default()));
+        *entry = (entry.0 + 1, entry.1 + iteration.nodes, entry.2 + iteration.time);
+    }
+    for (depth, (count, nodes, time)) in by_depth {
+        println!("{}depth {:>2} {:>6} iterations {:>12} nodes {:>9.3}s", prefix, depth, count, nodes, time.as_secs_f64());
+    }
+}
+
+fn run_match(args: &[String]) -> io::Result<()> {
+    let flags = ["first", "second", "games", "plies", "seed", "depth", "nodes", "time", "clock", "increment", "records", "ratings"];
+    let args = Arguments::parse(args, &flags, &[])?;
+    args.expect(0, 0)?;
+    let names = [args.get::<String>("first")?.unwrap_or_else(|| "alphabeta".to_string()), args.get::<String>("second")?.unwrap_or_else(|| "alphabeta".to_string())];
+    let mut first = from_spec(&names[0])?;
+    let mut second = from_spec(&names[1])?;
+    let defaults = Options::default();
+    let clock = match (duration(&args, "clock", 60.0)?, duration(&args, "increment", 1.0)?) {
+        (None, None) => None,
+        (remaining, increment) => Some(Clock {
+            remaining: remaining.unwrap_or(Duration::from_secs(60)),
+            increment: increment.unwrap_or_default()
+        })
+    };
+    let options = Options {
+        games: args.get("games")?.unwrap_or(100),
+        opening_plies: args.get("plies")?.unwrap_or(defaults.opening_plies),
+        seed: args.get("seed")?.unwrap_or(defaults.seed),
+        limits: limits(&args)?,
+        clock,
+        ..defaults
+    };
+    let mut report = arena::run(&options, &mut first, &mut second);
+    let (elo, error) = report.elo();
+    let result = report.result;
+    println!("{} wins, {} draws, {} losses, score {:.3}, elo {:+.1} +/- {:.1}", result.wins, result.draws, result.losses, result.score(), elo, error);
+    let forfeits = report.games.iter().filter(|x| x.tag("termination").is_some()).count();
+    if forfeits > 0 {
+        println!("{} games forfeited", forfeits);
+    }
+    print_stats("first ", &report.stats[0]);
+    print_stats("second ", &report.stats[1]);
+    if let Some(path) = args.get::<String>("records")? {
+        for record in &mut report.games {
+            let rename = |name: &mut String| *name = names[if name == "first" { 0 } else { 1 }].clone();
+            rename(&mut record.black);
+            rename(&mut record.white);
+        }
+        pgn::save(path, &report.games)?;
+    }
+    let outcomes = [(result.wins, 1), (result.draws, 0), (result.losses, -1)].iter()
+        .flat_map(|&(games, difference)| vec![Outcome::new(&names[0], &names[1], difference); games as usize])
+        .collect::<Vec<_>>();
+    rate(args.get("ratings")?, &outcomes)
+}
+
+fn simulate(args: &[String]) -> io::Result<()> {
+    let flags = ["first", "second", "games", "threads", "plies", "seed", "depth", "nodes", "time", "pgn", "samples"];
+    let args = Arguments::parse(args, &flags, &[])?;
+    args.expect(0, 0)?;
+    let defaults = BatchConfig::default();
+    let config = BatchConfig {
+        first: args.get::<String>("first")?.unwrap_or_else(|| "alphabeta".to_string()),
+        second: args.get::<String>("second")?.unwrap_or_else(|| "alphabeta".to_string()),
+        games: args.get("games")?.unwrap_or(defaults.games),
+        threads: args.get("threads")?.unwrap_or(defaults.threads),
+        opening_plies: args.get("plies")?.unwrap_or(defaults.opening_plies),
+        seed: args.get("seed")?.unwrap_or(defaults.seed),
+        limits: limits(&args)?
+    };
+    // the engines are made again on each thread
+    from_spec(&config.first)?;
+    from_spec(&config.second)?;
+    let make = |name: &str| from_spec(name).expect("the engine was made before");
+    let mut pgn = match args.get::<String>("pgn")? {
+        Some(path) => Some(simulate::Pgn::new(io::BufWriter::new(fs::File::create(path)?))),
+        None => None
+    };
+    let mut samples = match args.get::<String>("samples")? {
+        Some(path) => Some(simulate::Samples::new(io::BufWriter::new(fs::File::create(path)?))),
+        None => None
+    };
+    let mut sinks = Vec::<&mut dyn Sink>::new();
+    sinks.extend(pgn.as_mut().map(|x| x as &mut dyn Sink));
+    sinks.extend(samples.as_mut().map(|x| x as &mut dyn Sink));
+    let summary = simulate::run(&config, || make(&config.first), || make(&config.second), &mut sinks)?;
+    print!("{}", summary.format());
+    Ok(())
+}
+
+// Plays the remaining rounds of a tournament, saving it after each round.
+fn run_tournament(args: &[String]) -> io::Result<()> {
+    let flags = ["player", "pairing", "rounds", "games", "seed", "depth", "nodes", "time", "records", "ratings"];
+    let args = Arguments::parse(args, &flags, &[])?;
+    args.expect(1, 1)?;
+    let path = &args.positional[0];
+    let mut tournament = if Path::new(path).exists() {
+        Tournament::load(path)?
+    } else {
+        let players = args.all("player").map(str::to_string).collect::<Vec<_>>();
+        if players.len() < 2 {
+            return Err(invalid_input("a new tournament needs at least two --player"));
+        }
+        let pairing = match args.get::<String>("pairing")?.as_deref() {
+            None => Pairing::RoundRobin,
+            Some(name) => name.parse().map_err(|_| invalid_input(&format!("unknown pairing: {}", name)))?
+        };
+        let mut tournament = Tournament::new(players, pairing, args.get("games")?.unwrap_or(2));
+        tournament.rounds = args.get("rounds")?.unwrap_or(tournament.rounds);
+        tournament.seed = args.get("seed")?.unwrap_or(0);
+        tournament
+    };
+    let mut engines = tournament.players.iter().map(|x| from_spec(x)).collect::<io::Result<Vec<_>>>()?;
+    let options = Options { limits: limits(&args)?, ..Options::default() };
+    let records = args.get::<String>("records")?;
+    if tournament.is_over() {
+        print!("{}", tournament.format_standings());
+    }
+    while !tournament.is_over() {
+        let games = tournament.play_round(&mut engines, &options);
+        tournament.save(path)?;
+        if let Some(ref records) = records {
+            let mut saved = if Path::new(records).exists() { pgn::load(records)? } else { Vec::new() };
+            saved.extend(games);
+            pgn::save(records, &saved)?;
+        }
+        // each round is a rating period
+        let round = tournament.completed - 1;
+        let outcomes = tournament.results.iter().filter(|x| x.round == round).map(|x| {
+            Outcome::new(&tournament.players[x.black], &tournament.players[x.white], i32::from(x.difference))
+        }).collect::<Vec<_>>();
+        rate(args.get("ratings")?, &outcomes)?;
+        println!("Round {} of {}", tournament.completed, tournament.rounds);
+        print!("{}", tournament.format_standings());
+    }
+    Ok(())
+}
+
+// Rates the games of a rating period in the ratings file, if one is given.
+fn rate(path: Option<String>, outcomes: &[Outcome]) -> io::Result<()> {
+    let path = match path {
+        Some(path) => path,
+        None => return Ok(())
+    };
+    let mut ratings = if Path::new(&path).exists() { Ratings::load(&path)? } else { Ratings::new() };
+    ratings.update(outcomes);
+    ratings.save(&path)
+}
+
+fn ratings(args: &[String]) -> io::Result<()> {
+    let args = Arguments::parse(args, &[], &[])?;
+    args.expect(1, 1)?;
+    print!("{}", Ratings::load(&args.positional[0])?.format());
+    Ok(())
+}
+
+fn run_book(args: &[String]) -> io::Result<()> {
+    let args = Arguments::parse(args, &["positions", "book-depth", "max-ply", "ply-cost"], &[])?;
+    match args.positional.first().map(String::as_str) {
+        Some("build") => {
+            args.expect(2, 2)?;
+            let defaults = BuildOptions::default();
+            let options = BuildOptions {
+                positions: args.get("positions")?.unwrap_or(defaults.positions),
+                depth: args.get("book-depth")?.unwrap_or(defaults.depth),
+                max_ply: args.get("max-ply")?.unwrap_or(defaults.max_ply),
+                ply_cost: args.get("ply-cost")?.unwrap_or(defaults.ply_cost)
+            };
+            let mut book = Book::new();
+            let expanded = book::build(&mut book, &options, &Heuristic::default());
+            book.save(&args.positional[1])?;
+            println!("{} positions expanded, {} positions in the book", expanded, book.len());
+            Ok(())
+        }
+        Some("probe") => {
+            args.expect(2, 3)?;
+            let book = open_book(Path::new(&args.positional[1]))?;
+            let (board, color) = match args.positional.get(2) {
+                Some(text) => position(text)?,
+                None => (Board::new(), Color::Black)
+            };
+            for book_move in book.moves(&board, color)? {
+                let results = book_move.results;
+                println!("{} {:+} {}/{}/{}", record::format_move(book_move.position), book_move.score, results.wins, results.draws, results.losses);
+            }
+            Ok(())
+        }
+        _ => Err(invalid_input("book needs build or probe"))
+    }
+}
+
+// Stores games in a game database, or searches it.
+#[cfg(feature = "db")]
+fn run_db(args: &[String]) -> io::Result<()> {
+    use rusty_reversi::db::{Database, Filter};
+    use rusty_reversi::formats::ggf;
+
+    let args = Arguments::parse(args, &["players", "tournaments", "player", "winner", "year"], &[])?;
+    match args.positional.first().map(String::as_str) {
+        Some("import") => {
+            args.expect(3, 3)?;
+            let path = &args.positional[2];
+            let names = |flag: &str| -> io::Result<Vec<String>> {
+                match args.get::<String>(flag)? {
+                    Some(names) if flag == "players" => wthor::read_players(fs::File::open(names)?),
+                    Some(names) => wthor::read_tournaments(fs::File::open(names)?),
+                    None => Ok(Vec::new())
+                }
+            };
+            let (games, skipped) = if path.to_lowercase().ends_with(".wtb") {
+                let (header, games) = wthor::load(path)?;
+                (wthor::to_records(&header, &games, &names("players")?, &names("tournaments")?), 0)
+            } else if path.to_lowercase().ends_with(".ggf") {
+                let import = ggf::import_file(path)?;
+                (import.records, import.skipped.len())
+            } else {
+                match pasted(path)? {
+                    (Pasted::Games(records), _) => (records, 0),
+                    (Pasted::Position(..), _) => return Err(invalid_input("expected games, found a position"))
+                }
+            };
+            let mut database = Database::open(&args.positional[1])?;
+            let added = database.add_games(&games, path)?;
+            println!("{} games stored, {} skipped, {} in the database", added, games.len() - added + skipped, database.count()?);
+        }
+        Some(command @ ("games" | "explore")) => {
+            args.expect(2, 3)?;
+            let database = Database::open(&args.positional[1])?;
+            let winner = match args.get::<String>("winner")? {
+                Some(name) => Some(name.parse().map_err(|_| invalid_input(&format!("invalid value for --winner: {}", name)))?),
+                None => None
+            };
+            let filter = Filter { player: args.get("player")?, winner, year: args.get("year")? };
+            let given = args.positional.get(2).map(|x| position(x)).transpose()?;
+            if command == "games" {
+                let ids = match given {
+                    Some((board, color)) => database.games_reaching(&board, color, &filter)?,
+                    None => database.games(&filter)?
+                };
+                for id in ids {
+                    let game = database.game(id)?.ok_or_else(|| io::Error::other("game removed while listed"))?;
+                    let score = game.black_disks.map_or("?".to_string(), |x| format!("{}-{}", x, 64 - x));
+                    let line = format!("{:>6} {} - {} {} {} {}", id, game.black, game.white, score, game.date, game.event);
+                    println!("{}", line.trim_end());
+                }
+            } else {
+                let (board, color) = given.unwrap_or((Board::new(), Color::Black));
+                for continuation in database.continuations(&board, color, &filter)? {
+                    let results = continuation.results;
+                    println!("{} {:>8} games {:+6.1} disks {:>5.1}% won", record::format_move(continuation.position), continuation.games, continuation.disks, 100.0 * f64::from(results.wins) / f64::from(results.games().max(1)));
+                }
+            }
+        }
+        _ => return Err(invalid_input("db needs import, games or explore"))
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "db"))]
+fn run_db(_: &[String]) -> io::Result<()> {
+    Err(io::Error::other("game databases need the db feature"))
+}
+
+// Stores a game played on the console in a game database.
+#[cfg(feature = "db")]
+fn store(path: &str, game: &GameRecord) -> io::Result<()> {
+    rusty_reversi::db::Database::open(path)?.add_game(game, "play").map(|_| ())
+}
+
+#[cfg(not(feature = "db"))]
+fn store(_: &str, _: &GameRecord) -> io::Result<()> {
+    Ok(())
+}
+
+// Solves the problems of a suite, the FFO positions #40 to #43 by default.
+fn run_suite(args: &[String]) -> io::Result<()> {
+    let args = Arguments::parse(args, &[], &[])?;
+    args.expect(0, 1)?;
+    let problems = match args.positional.first() {
+        Some(path) => ffo::load(path)?,
+        None => ffo::ffo_40_43()
+    };
+    let reports = ffo::run(&problems, &mut Solver::default(), |report| {
+        let best = report.best_move.map(record::format_move).unwrap_or_else(|| "ps".to_string());
+        let status = if report.correct { "ok" } else { "WRONG" };
+        println!("{:>4} {} {:+3} {:>14} nodes {:>9.3}s {}", report.name, best, report.score, report.nodes, report.time.as_secs_f64(), status);
+    });
+    let correct = reports.iter().filter(|x| x.correct).count();
+    let nodes = reports.iter().map(|x| x.nodes).sum::<u64>();
+    let time = reports.iter().map(|x| x.time.as_secs_f64()).sum::<f64>();
+    println!("{}/{} correct, {} nodes in {:.3}s", correct, reports.len(), nodes, time);
+    Ok(())
+}
+
+// Writes a suite of puzzles found in random games or in the games of a file.
+fn puzzles(args: &[String]) -> io::Result<()> {
+    let args = Arguments::parse(args, &["count", "empties", "gap", "seed", "games"], &[])?;
+    args.expect(0, 0)?;
+    let mut options = puzzle::Options::default();
+    if let Some(range) = args.get::<String>("empties")? {
+        let parsed = range.split_once('-').and_then(|(min, max)| Some((min.parse().ok()?, max.parse().ok()?)));
+        match parsed {
+            Some((min, max)) if min <= max => (options.min_empties, options.max_empties) = (min, max),
+            _ => return Err(invalid_input(&format!("invalid value for --empties: {}", range)))
+        }
+    }
+    options.gap = args.get("gap")?.unwrap_or(options.gap);
+    let count = args.get("count")?.unwrap_or(10);
+    let problems = match args.get::<String>("games")? {
+        Some(path) if path.to_lowercase().ends_with(".wtb") => {
+            let (header, games) = wthor::load(&path)?;
+            puzzle::from_records(&wthor::to_records(&header, &games, &[], &[]), count, &options)
+        }
+        Some(path) => match pasted(&path)? {
+            (Pasted::Games(records), _) => puzzle::from_records(&records, count, &options),
+            (Pasted::Position(..), _) => return Err(invalid_input("expected games, found a position"))
+        },
+        None => {
+            let mut random = Random::new(args.get("seed")?.unwrap_or(0));
+            puzzle::generate(&mut random, count, count * 100, &options)
+        }
+    };
+    print!("{}", ffo::format(&problems));
+    Ok(())
+}
+
+// Asks for the solutions of the puzzles of a suite, one after the other.
+fn quiz(args: &[String], screen: Screen) -> io::Result<()> {
+    let args = Arguments::parse(args, &[], &[])?;
+    args.expect(1, 1)?;
+    let problems = ffo::load(&args.positional[0])?;
+    println!("Enter moves like f5, skip to see the solution, or quit.");
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    let mut streak = puzzle::Streak::default();
+    for (i, problem) in problems.iter().enumerate() {
+        let goal = match problem.score.signum() {
+            1 => "win",
+            0 => "draw",
+            _ => "lose the least"
+        };
+        println!("Puzzle {} of {}: {}", i + 1, problems.len(), problem.name);
+        if screen.accessible {
+            print!("{}", speech::describe_board(&problem.board, Some(problem.color)));
+        } else {
+            print!("{}", screen.theme.paint(&problem.board.draw_with_hints(screen.charset, Some(problem.color))));
+        }
+        let solution = problem.best_moves.iter().map(|&x| record::format_move(x)).collect::<Vec<_>>().join(" or ");
+        loop {
+            print!("{} to move and {}: ", problem.color, goal);
+            io::stdout().flush()?;
+            let line = match lines.next() {
+                Some(line) => line?,
+                None => return Ok(())
+            };
+            let correct = match line.trim() {
+                "quit" | "q" => {
+                    print_streak(&streak);
+                    return Ok(());
+                }
+                "skip" | "s" => false,
+                text => match record::parse_move(text) {
+                    Some(position) if problem.board.legal_moves(problem.color).iter().any(|x| x.position == position) => {
+                        puzzle::check(problem, position)
+                    }
+                    Some(_) => {
+                        println!("error: illegal move: {}", text);
+                        continue;
+                    }
+                    None => {
+                        println!("error: invalid move: {}", text);
+                        continue;
+                    }
+                }
+            };
+            streak.answer(correct);
+            if correct {
+                println!("Correct! Streak {}", streak.current);
+            } else {
+                println!("The solution is {}, scoring {:+}.", solution, problem.score);
+            }
+            break;
+        }
+    }
+    print_streak(&streak);
+    Ok(())
+}
+
+fn print_streak(streak: &puzzle::Streak) {
+    println!("Solved {} of {}, longest streak {}", streak.solved, streak.attempted, streak.best);
+}
+
+// Prints links to the games of a file or a text, or to a position.
+fn share(args: &[String]) -> io::Result<()> {
+    let args = Arguments::parse(args, &["page"], &[])?;
+    args.expect(1, 1)?;
+    let page = args.get::<String>("page")?.unwrap_or_default();
+    match pasted(&args.positional[0])?.0 {
+        Pasted::Games(games) => {
+            for game in &games {
+                println!("{}", link::game_link(&page, game));
+            }
+        }
+        Pasted::Position(board, color) => println!("{}", link::position_link(&page, &board, color))
+    }
+    Ok(())
+}
+
+// Reviews a game move by move, on the console or full-screen.
+fn view(args: &[String], screen: Screen) -> io::Result<()> {
+    let flags = [ENGINE_FLAGS, &["game"]].concat();
+    let args = Arguments::parse(args, &flags, &["tui"])?;
+    args.expect(1, 1)?;
+    let mut records = match pasted(&args.positional[0])? {
+        (Pasted::Games(records), _) => records,
+        (Pasted::Position(..), _) => return Err(invalid_input("expected a game, found a position"))
+    };
+    let index = args.get::<usize>("game")?.unwrap_or(1);
+    if index == 0 || index > records.len() {
+        return Err(invalid_input(&format!("no game {}, there are {}", index, records.len())));
+    }
+    let record = records.swap_remove(index - 1);
+    let mut viewer = Viewer::new(configured_engine(&args)?, record).map_err(|i| invalid_input(&format!("illegal move {}", i + 1)))?;
+    viewer.limits = configured_limits(&args)?;
+    viewer.charset = screen.charset;
+    viewer.theme = screen.theme;
+    if args.has("tui") {
+        return view_tui(&mut viewer);
+    }
+    println!("Enter or n for the next move, p for the previous one, s and e for the start and the end,");
+    println!("a number for that move, f to flip the board, v to show the evaluation, or q to quit.");
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    while !viewer.is_done() {
+        print!("{}> ", viewer.draw());
+        io::stdout().flush()?;
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => return Ok(())
+        };
+        match line.parse() {
+            Ok(command) => viewer.handle(command),
+            Err(()) => println!("error: unknown command: {}", line.trim())
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "tui")]
+fn view_tui(viewer: &mut Viewer<Box<dyn Engine>>) -> io::Result<()> {
+    rusty_reversi::tui::view(viewer)
+}
+
+#[cfg(not(feature = "tui"))]
+fn view_tui(_: &mut Viewer<Box<dyn Engine>>) -> io::Result<()> {
+    Err(io::Error::other("the full-screen interface needs the tui feature"))
+}
+
+// Animates the game of a transcript.
+#[cfg(feature = "image")]
+fn replay(args: &[String]) -> io::Result<()> {
+    use rusty_reversi::render::{self, Animation};
+
+    let args = Arguments::parse(args, &[], &[])?;
+    args.expect(2, 2)?;
+    let moves = record::parse_transcript(&args.positional[0]).ok_or_else(|| invalid_input("invalid moves"))?;
+    let game = GameRecord { moves, ..GameRecord::default() };
+    let frames = render::animate(&game, &Animation::default()).map_err(|i| invalid_input(&format!("illegal move {}", i + 1)))?;
+    render::save_animation(&args.positional[1], &frames)
+}
+
+#[cfg(not(feature = "image"))]
+fn replay(_: &[String]) -> io::Result<()> {
+    Err(io::Error::other("replays need the image feature"))
+}
@@ -0,0 +1,286 @@
+//! This module implements Monte Carlo tree search.
+//!
+//! The tree grows one node per simulation. Children are selected by the PUCT rule used by
+//! AlphaZero: the average value of a move plus an exploration bonus proportional to its prior
+//! probability, which decreases as the move is visited more. When a network is loaded, it
+//! provides the priors and the values of the new nodes. Otherwise every move is equally likely
+//! and the values come from random [rollouts](../rollout/index.html) or the static evaluation.
+
+use std::io;
+use std::mem;
+use std::path::PathBuf;
+
+use engine::{parse_option, unknown_option, Engine, Limits, MemoryUsage, Reservation, SearchResult};
+use eval::{Evaluator, Heuristic, Phase, Score};
+use game::{Board, Color, Coord, LegalMove};
+use nn::{self, Network};
+use rollout::Rollouts;
+use search::final_score;
+use time::Instant;
+
+/// Settings of the search.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    /// Number of nodes added to the tree per search.
+    pub simulations: u32,
+    /// Weight of the exploration bonus relative to the average value.
+    pub exploration: f64,
+    /// A network guiding the search, see [`nn::load`](../nn/fn.load.html).
+    pub model: Option<PathBuf>,
+    /// Without a network, the number of random playouts estimating the value of new nodes.
+    /// Zero uses the static evaluation instead.
+    pub rollouts: u32
+}
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            simulations: 1000,
+            exploration: 1.5,
+            model: None,
+            rollouts: 0
+        }
+    }
+}
+
+// The most nodes added by a simulation, the children of a position.
+const MAX_CHILDREN: usize = 64;
+
+struct Node {
+    board: Board,
+    /// The player to move.
+    color: Color,
+    /// The move leading here, `None` at the root and after skipping.
+    position: Option<Coord>,
+    prior: f64,
+    visits: u32,
+    /// Sum of the values for the player who moved here.
+    value: f64,
+    children: Vec<usize>,
+    expanded: bool
+}
+
+/// A Monte Carlo tree search engine.
+pub struct Mcts {
+    pub config: Config,
+    network: Option<Box<dyn Network>>,
+    evaluator: Heuristic,
+    rollouts: Rollouts
+}
+impl Mcts {
+    /// Creates an engine, loading the model given in the configuration.
+    pub fn new(config: Config) -> io::Result<Mcts> {
+        let network = match config.model {
+            Some(ref path) => Some(nn::load(path)?),
+            None => None
+        };
+        let rollouts = Rollouts::new(config.rollouts, 0);
+        Ok(Mcts { config, network, evaluator: Heuristic::default(), rollouts })
+    }
+
+    /// Uses the given network instead of the one in the configuration.
+    pub fn with_network(config: Config, network: Box<dyn Network>) -> Mcts {
+        let rollouts = Rollouts::new(config.rollouts, 0);
+        Mcts { config, network: Some(network), evaluator: Heuristic::default(), rollouts }
+    }
+
+    /// Searches for the best move and returns it with its expected outcome, from -1 for a loss to
+    /// 1 for a win, reported as a score from -1000 to 1000. The depth is the one of the deepest
+    /// simulation and the nodes are the nodes of the tree.
+    ///
+    /// There is no move to return when the player has to skip or the game has ended. The tree is
+    /// taken from the [budget](../engine/fn.set_memory_cap.html) of the process, and the search
+    /// stops early when it is exhausted.
+    pub fn search(&mut self, board: &Board, color: Color) -> SearchResult {
+        let start = Instant::now();
+        let mut tree = vec![Node {
+            board: *board,
+            color,
+            position: None,
+            prior: 1.0,
+            visits: 0,
+            value: 0.0,
+            children: Vec::new(),
+            expanded: false
+        }];
+        let mut depth = 0;
+        // each node is held with its index in the children of its parent, and nodes are reserved
+        // a thousand at a time
+        let node_bytes = mem::size_of::<Node>() + mem::size_of::<usize>();
+        let mut reservation = Reservation::default();
+        for simulation in 0..self.config.simulations.max(1) {
+            let needed = (tree.len() + MAX_CHILDREN) * node_bytes;
+            // the first simulations, expanding the root and a move, are run anyway to have a move
+            if needed > reservation.bytes() && reservation.resize(needed + 1000 * node_bytes, "search tree").is_err() && simulation > 1 {
+                break;
+            }
+            depth = depth.max(self.simulate(&mut tree));
+        }
+        let root = &tree[0];
+        let value = match root.children.iter().max_by_key(|&&child| tree[child].visits) {
+            Some(&child) if tree[child].position.is_some() => tree[child].value / tree[child].visits.max(1) as f64,
+            _ => -root.value / root.visits.max(1) as f64
+        };
+        // the most visited line, skips left out
+        let mut pv = Vec::new();
+        let mut node = 0;
+        while let Some(&child) = tree[node].children.iter().filter(|&&x| tree[x].visits > 0).max_by_key(|&&x| tree[x].visits) {
+            match tree[child].position {
+                Some(position) => pv.push(position),
+                None if node == 0 => break,
+                None => ()
+            }
+            node = child;
+        }
+        SearchResult::new((value * 1000.0) as Score, depth, tree.len() as u64, 0, pv, start.elapsed())
+    }
+
+    // Walks down the tree to a leaf, expands it and propagates its value back to the root.
+    // Returns the depth of the leaf.
+    fn simulate(&self, tree: &mut Vec<Node>) -> u32 {
+        let mut path = vec![0];
+        let mut current = 0;
+        while tree[current].expanded && !tree[current].children.is_empty() {
+            current = self.select(tree, current);
+            path.push(current);
+        }
+        // value for the player to move at the leaf
+        let mut value = if tree[current].expanded {
+            // no children after expansion means the game has ended
+            terminal_value(&tree[current].board, tree[current].color)
+        } else {
+            self.expand(tree, current)
+        };
+        for &node in path.iter().rev() {
+            // the player who moved into a node is the opponent of the one to move in it
+            value = -value;
+            tree[node].visits += 1;
+            tree[node].value += value;
+        }
+        path.len() as u32 - 1
+    }
+
+    fn select(&self, tree: &[Node], parent: usize) -> usize {
+        let sqrt_visits = (tree[parent].visits as f64).sqrt();
+        let score = |child: &Node| {
+            let average = if child.visits == 0 { 0.0 } else { child.value / child.visits as f64 };
+            average + self.config.exploration * child.prior * sqrt_visits / (1.0 + child.visits as f64)
+        };
+        let children = &tree[parent].children;
+        let mut best = children[0];
+        for &child in &children[1..] {
+            if score(&tree[child]) > score(&tree[best]) {
+                best = child;
+            }
+        }
+        best
+    }
+
+    // Adds the children of a leaf and returns its value for the player to move.
+    fn expand(&self, tree: &mut Vec<Node>, leaf: usize) -> f64 {
+        let (board, color) = (tree[leaf].board, tree[leaf].color);
+        tree[leaf].expanded = true;
+        let moves = board.legal_moves(color);
+        if moves.is_empty() {
+            if board.legal_moves(color.flip()).is_empty() {
+                return terminal_value(&board, color);
+            }
+            let child = new_child(tree, board, color.flip(), None, 1.0);
+            tree[leaf].children.push(child);
+        }
+        let (value, priors) = self.guide(&board, color, &moves);
+        for (legal_move, prior) in moves.iter().zip(priors) {
+            let child = new_child(tree, legal_move.apply(board), color.flip(), Some(legal_move.position), prior);
+            tree[leaf].children.push(child);
+        }
+        value
+    }
+
+    // The value of a position and the priors of its moves.
+    fn guide(&self, board: &Board, color: Color, moves: &[LegalMove]) -> (f64, Vec<f64>) {
+        match self.network {
+            Some(ref network) => {
+                let prediction = network.predict(board, color);
+                let mut priors = moves.iter().map(|x| {
+                    let (px, py) = x.position;
+                    f64::from(prediction.policy[py as usize * 8 + px as usize]).max(0.0)
+                }).collect::<Vec<_>>();
+                let sum: f64 = priors.iter().sum();
+                for prior in &mut priors {
+                    *prior = if sum > 0.0 { *prior / sum } else { 1.0 / moves.len() as f64 };
+                }
+                (f64::from(prediction.value), priors)
+            }
+            None => {
+                let value = if self.config.rollouts > 0 {
+                    self.rollouts.value(board, color)
+                } else {
+                    // squash the heuristic score, a corner being worth about a third of a win
+                    (self.evaluator.evaluate(board, color, Phase::of(board)) as f64 / 300.0).tanh()
+                };
+                (value, vec![1.0 / moves.len().max(1) as f64; moves.len()])
+            }
+        }
+    }
+}
+
+/// The search as an [engine](../engine/trait.Engine.html). The node limit sets the number of
+/// simulations.
+///
+/// Its options are `simulations`, `exploration`, `rollouts` and `model`, the path of a network
+/// to load, or an empty path to unload it.
+impl Engine for Mcts {
+    #[cfg_attr(feature = "trace", tracing::instrument(name = "mcts", skip_all, fields(%color, ?limits)))]
+    fn best_move(&mut self, board: &Board, color: Color, limits: &Limits) -> SearchResult {
+        let simulations = self.config.simulations;
+        if let Some(nodes) = limits.nodes {
+            self.config.simulations = nodes.min(u64::from(u32::MAX)) as u32;
+        }
+        let result = self.search(board, color);
+        self.config.simulations = simulations;
+        result
+    }
+
+    fn set_option(&mut self, name: &str, value: &str) -> io::Result<()> {
+        match name {
+            "simulations" => self.config.simulations = parse_option(name, value)?,
+            "exploration" => self.config.exploration = parse_option(name, value)?,
+            "rollouts" => {
+                self.config.rollouts = parse_option(name, value)?;
+                self.rollouts.playouts = self.config.rollouts;
+            }
+            "model" if value.trim().is_empty() => {
+                self.network = None;
+                self.config.model = None;
+            }
+            "model" => {
+                let path = PathBuf::from(value.trim());
+                self.network = Some(nn::load(&path)?);
+                self.config.model = Some(path);
+            }
+            _ => return Err(unknown_option(name))
+        }
+        Ok(())
+    }
+
+    fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage { weights: self.evaluator.memory_usage(), ..MemoryUsage::default() }
+    }
+}
+
+fn new_child(tree: &mut Vec<Node>, board: Board, color: Color, position: Option<Coord>, prior: f64) -> usize {
+    tree.push(Node {
+        board,
+        color,
+        position,
+        prior,
+        visits: 0,
+        value: 0.0,
+        children: Vec::new(),
+        expanded: false
+    });
+    tree.len() - 1
+}
+
+fn terminal_value(board: &Board, color: Color) -> f64 {
+    f64::from(final_score(board, color).signum())
+}
@@ -0,0 +1,168 @@
+//! This module defines the messages of the network protocol, so that clients written in other
+//! languages can implement it from the schema `proto/rusty_reversi.proto`.
+//!
+//! Messages are Protocol Buffers, each sent as a [`Message`](proto/struct.Message.html) preceded
+//! by its length as a varint. Games between two instances over TCP are played through
+//! [`remote`](remote/index.html).
+
+pub mod proto;
+pub mod remote;
+
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+use std::time::Duration;
+
+use prost::Message;
+
+use files::invalid;
+use game::{Board, Color, Coord};
+use session::Clock;
+
+/// Version of the protocol sent in [`Hello`](proto/struct.Hello.html).
+pub const PROTOCOL_VERSION: u32 = 1;
+
+// Cell number of a skip.
+const NO_MOVE: u32 = 64;
+
+// Largest message accepted, far above the size of any valid one.
+const MAX_LENGTH: u64 = 1 << 20;
+
+/// Fills `bytes` from the random generator of the operating system, unpredictable enough for
+/// secrets.
+pub fn secure_random(bytes: &mut [u8]) -> io::Result<()> {
+    getrandom::getrandom(bytes).map_err(io::Error::from)
+}
+
+/// A new token of 128 bits in hexadecimal, for a player to come back to a game with.
+pub fn token() -> io::Result<String> {
+    let mut bytes = [0; 16];
+    secure_random(&mut bytes)?;
+    Ok(bytes.iter().map(|x| format!("{:02x}", x)).collect())
+}
+
+/// Whether a token is the expected one. The comparison takes the same time wherever they
+/// differ, so that it does not tell how much of a guess is right.
+pub fn same_token(token: &str, expected: &str) -> bool {
+    token.len() == expected.len() && token.bytes().zip(expected.bytes()).fold(0, |x, (a, b)| x | (a ^ b)) == 0
+}
+
+/// Sends a message preceded by its length.
+pub fn send<W: Write>(mut writer: W, message: &proto::Message) -> io::Result<()> {
+    #[cfg(feature = "trace")]
+    tracing::trace!(?message, "sending");
+    writer.write_all(&message.encode_length_delimited_to_vec())?;
+    writer.flush()
+}
+
+/// Receives a message sent by [`send`](fn.send.html), `None` at the end of the stream.
+pub fn receive<R: Read>(mut reader: R) -> io::Result<Option<proto::Message>> {
+    let mut length = 0u64;
+    for i in 0..10 {
+        let mut byte = [0];
+        if reader.read(&mut byte)? == 0 {
+            if i == 0 {
+                return Ok(None);
+            }
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        length |= u64::from(byte[0] & 0x7f) << (7 * i);
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+    }
+    if length > MAX_LENGTH {
+        #[cfg(feature = "trace")]
+        tracing::warn!(length, "message too long");
+        return Err(invalid("message too long"));
+    }
+    let mut bytes = vec![0; length as usize];
+    reader.read_exact(&mut bytes)?;
+    let message = proto::Message::decode(&bytes[..]).map_err(|x| invalid(&x.to_string()))?;
+    #[cfg(feature = "trace")]
+    tracing::trace!(?message, "received");
+    Ok(Some(message))
+}
+
+impl proto::Position {
+    pub fn new(board: &Board, color: Color) -> proto::Position {
+        let (mut black, mut white) = (0, 0);
+        for (x, column) in board.cells.iter().enumerate() {
+            for (y, &cell) in column.iter().enumerate() {
+                match cell {
+                    Some(Color::Black) => black |= 1 << (y * 8 + x),
+                    Some(Color::White) => white |= 1 << (y * 8 + x),
+                    None => ()
+                }
+            }
+        }
+        proto::Position { black, white, to_move: proto::Color::from(color) as i32 }
+    }
+
+    /// The board and the player to move. Fails if a cell holds two disks.
+    pub fn to_board(&self) -> io::Result<(Board, Color)> {
+        if self.black & self.white != 0 {
+            return Err(invalid("cell occupied twice"));
+        }
+        let mut board = Board { cells: [[None; 8]; 8] };
+        for i in 0..64 {
+            if self.black & (1 << i) != 0 {
+                board.cells[i % 8][i / 8] = Some(Color::Black);
+            } else if self.white & (1 << i) != 0 {
+                board.cells[i % 8][i / 8] = Some(Color::White);
+            }
+        }
+        Ok((board, color(self.to_move)?))
+    }
+}
+
+impl proto::Move {
+    /// A move, `None` for a skip.
+    pub fn new(color: Color, position: Option<Coord>) -> proto::Move {
+        let cell = position.map_or(NO_MOVE, |(x, y)| (y * 8 + x) as u32);
+        proto::Move { color: proto::Color::from(color) as i32, cell }
+    }
+
+    /// The player and the cell, `None` for a skip.
+    pub fn to_move(&self) -> io::Result<(Color, Option<Coord>)> {
+        let position = match self.cell {
+            NO_MOVE => None,
+            cell if cell < NO_MOVE => Some(((cell % 8) as i8, (cell / 8) as i8)),
+            _ => return Err(invalid("move outside of the board"))
+        };
+        Ok((color(self.color)?, position))
+    }
+}
+
+impl From<Color> for proto::Color {
+    fn from(color: Color) -> proto::Color {
+        match color {
+            Color::Black => proto::Color::Black,
+            Color::White => proto::Color::White
+        }
+    }
+}
+
+impl From<proto::Color> for Color {
+    fn from(color: proto::Color) -> Color {
+        match color {
+            proto::Color::Black => Color::Black,
+            proto::Color::White => Color::White
+        }
+    }
+}
+
+impl From<Clock> for proto::Clock {
+    fn from(clock: Clock) -> proto::Clock {
+        proto::Clock { remaining_ms: clock.remaining.as_millis() as u64, increment_ms: clock.increment.as_millis() as u64 }
+    }
+}
+
+impl From<proto::Clock> for Clock {
+    fn from(clock: proto::Clock) -> Clock {
+        Clock { remaining: Duration::from_millis(clock.remaining_ms), increment: Duration::from_millis(clock.increment_ms) }
+    }
+}
+
+fn color(value: i32) -> io::Result<Color> {
+    proto::Color::try_from(value).map(Color::from).map_err(|_| invalid("unknown color"))
+}
@@ -0,0 +1,166 @@
+//! The messages of `proto/rusty_reversi.proto`, as generated by `prost-build`. Regenerate them
+//! when the schema changes.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum Color {
+    Black = 0,
+    White = 1
+}
+
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct Position {
+    #[prost(fixed64, tag = "1")]
+    pub black: u64,
+    #[prost(fixed64, tag = "2")]
+    pub white: u64,
+    #[prost(enumeration = "Color", tag = "3")]
+    pub to_move: i32
+}
+
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct Move {
+    #[prost(enumeration = "Color", tag = "1")]
+    pub color: i32,
+    /// 64 for a skip.
+    #[prost(uint32, tag = "2")]
+    pub cell: u32
+}
+
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct Clock {
+    #[prost(uint64, tag = "1")]
+    pub remaining_ms: u64,
+    #[prost(uint64, tag = "2")]
+    pub increment_ms: u64
+}
+
+/// Opens a connection, both sides send it first.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Hello {
+    #[prost(uint32, tag = "1")]
+    pub protocol_version: u32,
+    #[prost(string, tag = "2")]
+    pub name: String,
+    /// The token of a guest coming back to its game.
+    #[prost(string, tag = "3")]
+    pub token: String
+}
+
+/// Starts a game from a position, the starting one when absent.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NewGame {
+    #[prost(string, tag = "1")]
+    pub black: String,
+    #[prost(string, tag = "2")]
+    pub white: String,
+    #[prost(message, optional, tag = "3")]
+    pub position: Option<Position>,
+    /// Both clocks are absent for untimed games.
+    #[prost(message, optional, tag = "4")]
+    pub black_clock: Option<Clock>,
+    #[prost(message, optional, tag = "5")]
+    pub white_clock: Option<Clock>,
+    /// The side of the player joining a hosted game.
+    #[prost(enumeration = "Color", tag = "6")]
+    pub guest_color: i32,
+    /// Lets the guest come back to the game after losing the connection.
+    #[prost(string, tag = "7")]
+    pub token: String
+}
+
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct Play {
+    #[prost(message, optional, tag = "1")]
+    pub r#move: Option<Move>,
+    /// Time spent on the move.
+    #[prost(uint64, tag = "2")]
+    pub time_ms: u64
+}
+
+/// Takes back the last move.
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct Undo {}
+
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct Resign {
+    #[prost(enumeration = "Color", tag = "1")]
+    pub color: i32
+}
+
+/// The state of the game after each change.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct State {
+    #[prost(message, optional, tag = "1")]
+    pub position: Option<Position>,
+    #[prost(message, optional, tag = "2")]
+    pub black_clock: Option<Clock>,
+    #[prost(message, optional, tag = "3")]
+    pub white_clock: Option<Clock>,
+    #[prost(message, repeated, tag = "4")]
+    pub moves: Vec<Move>,
+    /// When the host sent the state, in milliseconds since the Unix epoch.
+    #[prost(uint64, tag = "5")]
+    pub timestamp_ms: u64,
+    /// The time the host charged for each move.
+    #[prost(uint64, repeated, tag = "6")]
+    pub times_ms: Vec<u64>
+}
+
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct GameOver {
+    #[prost(uint32, tag = "1")]
+    pub black_disks: u32,
+    #[prost(uint32, tag = "2")]
+    pub white_disks: u32,
+    /// Set when a player resigned or ran out of time.
+    #[prost(enumeration = "Color", optional, tag = "3")]
+    pub forfeit: Option<i32>,
+    /// Set with forfeit when the player ran out of time.
+    #[prost(bool, tag = "4")]
+    pub on_time: bool
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Error {
+    #[prost(string, tag = "1")]
+    pub message: String
+}
+
+/// A message of a player to the other, at most 200 characters and one a second.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Chat {
+    #[prost(string, tag = "1")]
+    pub text: String
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Message {
+    #[prost(oneof = "message::Body", tags = "1, 2, 3, 4, 5, 6, 7, 8, 9")]
+    pub body: Option<message::Body>
+}
+
+/// Nested types of `Message`.
+pub mod message {
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Body {
+        #[prost(message, tag = "1")]
+        Hello(super::Hello),
+        #[prost(message, tag = "2")]
+        NewGame(super::NewGame),
+        #[prost(message, tag = "3")]
+        Play(super::Play),
+        #[prost(message, tag = "4")]
+        Undo(super::Undo),
+        #[prost(message, tag = "5")]
+        Resign(super::Resign),
+        #[prost(message, tag = "6")]
+        State(super::State),
+        #[prost(message, tag = "7")]
+        GameOver(super::GameOver),
+        #[prost(message, tag = "8")]
+        Error(super::Error),
+        #[prost(message, tag = "9")]
+        Chat(super::Chat)
+    }
+}
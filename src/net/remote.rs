@@ -0,0 +1,625 @@
+//! This module plays games between two instances over TCP: one [hosts](struct.RemoteGame.html#method.host)
+//! a game on a port and the other [joins](struct.RemoteGame.html#method.join) it, each playing a
+//! side.
+//!
+//! The host keeps the authoritative [session](../../session/struct.GameSession.html): it checks
+//! the moves of the guest, runs the clocks and decides when the game is over. After each move it
+//! sends the `State` of the game, which the guest follows, and a `GameOver` at the end. The guest
+//! sends its moves as `Play` and may `Resign`; the host answers moves it rejects with an `Error`.
+//! Guests sending data no honest client sends, like messages that can't be read or moves of the
+//! host, are disconnected after an `Error`, and may come back like after losing the connection.
+//! Both players may [`say`](struct.RemoteGame.html#method.say) something to the other in a `Chat`
+//! at any time, which the host checks like moves.
+//! The host keeps the clocks: it times the moves of the guest itself, so a guest running out of
+//! time loses without having to answer, and sends the time it charged for each move with the
+//! state. Up to the [lag compensation](struct.RemoteGame.html#method.set_lag_compensation) of
+//! each move isn't charged when the guest reports thinking for less.
+//!
+//! A guest losing the connection doesn't forfeit: the host waits for it to come back with the
+//! [token](struct.RemoteGame.html#method.token) of the game, while its clock keeps running, and
+//! sends it the game again. The guest [reconnects](struct.RemoteGame.html#method.reconnect), or
+//! [rejoins](struct.RemoteGame.html#method.rejoin) from another process.
+
+use std::collections::VecDeque;
+use std::io::{self, BufReader};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use files::invalid;
+use game::{Color, Coord};
+use net::{self, proto, PROTOCOL_VERSION};
+use net::proto::message::Body;
+use record::{GameRecord, Termination};
+use session::{Clock, GameSession, Player, Turn};
+
+// How often a host waiting for its guest checks for it.
+const POLL: Duration = Duration::from_millis(50);
+
+// Longest wait for the greeting of a guest coming back.
+const GREETING_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Attempts of a guest to connect again, a second apart.
+const RECONNECT_ATTEMPTS: u32 = 5;
+
+/// Most characters of a chat message.
+pub const MAX_CHAT_LENGTH: usize = 200;
+
+/// Shortest time between two chat messages of a player.
+pub const CHAT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How a game ended.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Ending {
+    /// Neither player could move anymore.
+    Finished { black_disks: u8, white_disks: u8 },
+    Resigned(Color),
+    /// A player ran out of time.
+    Time(Color)
+}
+
+/// What happened while waiting for the opponent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Update {
+    Moved(Turn),
+    /// The opponent said something.
+    Chat(String),
+    Over(Ending)
+}
+
+/// A game against another instance, seen from one side.
+///
+/// Players alternate between [`play`](#method.play) on their turn and [`wait`](#method.wait)
+/// otherwise, until the game has an [ending](#method.ending). The game being over on the board
+/// only ends it once `wait` reports it.
+pub struct RemoteGame {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    host: bool,
+    color: Color,
+    name: String,
+    opponent: String,
+    token: String,
+    session: GameSession,
+    ending: Option<Ending>,
+    // when the guest got the turn, to time its move
+    turn_start: Instant,
+    // the listener of the host, waiting for its guest to come back
+    listener: Option<TcpListener>,
+    // the address of the host, for the guest to connect again
+    address: Option<SocketAddr>,
+    // the clock the players started with
+    clock: Option<Clock>,
+    // whether the guest of the host is there
+    connected: bool,
+    // time of each move of the guest put down to lag
+    lag: Duration,
+    // chat messages of the opponent not reported yet
+    chat: VecDeque<String>,
+    // when this side and the guest of the host last said something
+    said: Option<Instant>,
+    guest_said: Option<Instant>
+}
+impl RemoteGame {
+    /// Waits for a guest on the listener and starts a game with it, the host playing `color`.
+    /// Both players start with the same clock, if any.
+    pub fn host(listener: &TcpListener, name: &str, color: Color, clock: Option<Clock>) -> io::Result<RemoteGame> {
+        let (stream, _) = listener.accept()?;
+        let mut game = RemoteGame::connected(stream, true, color, name)?;
+        game.opponent = game.greet("")?.name;
+        let players = (Player::Human(name.to_string()), Player::Human(game.opponent.clone()));
+        game.session = match color {
+            Color::Black => GameSession::new(players.0, players.1),
+            Color::White => GameSession::new(players.1, players.0)
+        };
+        game.session.clocks = clock.map(|x| [x, x]);
+        game.clock = clock;
+        game.token = net::token()?;
+        game.listener = Some(listener.try_clone()?);
+        game.connected = true;
+        game.start(Duration::from_millis(0))?;
+        Ok(game)
+    }
+
+    /// Connects to a host and joins its game.
+    pub fn join<A: ToSocketAddrs>(address: A, name: &str) -> io::Result<RemoteGame> {
+        RemoteGame::rejoin(address, name, "")
+    }
+
+    /// Joins again a game left with its [token](#method.token), getting the game so far from the
+    /// host.
+    pub fn rejoin<A: ToSocketAddrs>(address: A, name: &str, token: &str) -> io::Result<RemoteGame> {
+        let stream = TcpStream::connect(address)?;
+        let mut game = RemoteGame::connected(stream, false, Color::Black, name)?;
+        game.address = Some(game.writer.peer_addr()?);
+        game.opponent = game.greet(token)?.name;
+        let new_game = match game.receive()? {
+            Body::NewGame(new_game) => new_game,
+            Body::Error(error) => return Err(io::Error::new(io::ErrorKind::InvalidInput, error.message)),
+            _ => return Err(invalid("expected a new game"))
+        };
+        if new_game.position.is_some() {
+            return Err(invalid("games from a position are not supported"));
+        }
+        game.color = net::color(new_game.guest_color)?;
+        game.token = new_game.token;
+        game.session = GameSession::new(Player::Human(new_game.black), Player::Human(new_game.white));
+        if let (Some(black), Some(white)) = (new_game.black_clock, new_game.white_clock) {
+            game.session.clocks = Some([Clock::from(black), Clock::from(white)]);
+        }
+        match game.receive()? {
+            Body::State(state) => game.follow(&state)?,
+            _ => return Err(invalid("expected the state of the game"))
+        };
+        Ok(game)
+    }
+
+    fn connected(stream: TcpStream, host: bool, color: Color, name: &str) -> io::Result<RemoteGame> {
+        stream.set_nodelay(true)?;
+        Ok(RemoteGame {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+            host,
+            color,
+            name: name.to_string(),
+            opponent: String::new(),
+            token: String::new(),
+            session: GameSession::new(Player::Human(String::new()), Player::Human(String::new())),
+            ending: None,
+            turn_start: Instant::now(),
+            listener: None,
+            address: None,
+            clock: None,
+            connected: false,
+            lag: Duration::from_millis(0),
+            chat: VecDeque::new(),
+            said: None,
+            guest_said: None
+        })
+    }
+
+    /// The side played on this end.
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    /// The name of the other player.
+    pub fn opponent(&self) -> &str {
+        &self.opponent
+    }
+
+    /// The token for the guest to come back to the game with.
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    pub fn session(&self) -> &GameSession {
+        &self.session
+    }
+
+    pub fn ending(&self) -> Option<Ending> {
+        self.ending
+    }
+
+    /// The record of the game, forfeits counting as losing all the disks.
+    pub fn record(&self) -> GameRecord {
+        let mut record = self.session.record();
+        match self.ending {
+            Some(Ending::Resigned(color)) => record.forfeit(color, Termination::Resignation),
+            Some(Ending::Time(color)) => record.forfeit(color, Termination::Timeout),
+            _ => ()
+        }
+        record
+    }
+
+    /// Puts down up to `allowance` of the time the host measures for each move of the guest to
+    /// the latency of the connection, as long as the guest reports thinking for less. The guest
+    /// runs out of time that much later. None by default.
+    pub fn set_lag_compensation(&mut self, allowance: Duration) {
+        self.lag = allowance;
+    }
+
+    /// Whether this side has to play.
+    pub fn is_my_turn(&self) -> bool {
+        self.ending.is_none() && !self.session.is_over() && self.session.color() == self.color
+    }
+
+    /// Plays a move of this side, having spent `time` on it. Illegal moves, of the host or
+    /// rejected by the host, are errors and leave the game as it was.
+    pub fn play(&mut self, position: Coord, time: Duration) -> io::Result<()> {
+        if !self.is_my_turn() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "not your turn"));
+        }
+        if !self.host {
+            let message = proto::Play { r#move: Some(proto::Move::new(self.color, Some(position))), time_ms: time.as_millis() as u64 };
+            self.send(Body::Play(message))?;
+            return self.answer();
+        }
+        if self.out_of_time(self.color, time) {
+            return self.finish(Ending::Time(self.color));
+        }
+        self.session.play(position, time)?;
+        self.moved()
+    }
+
+    /// Resigns the game.
+    pub fn resign(&mut self) -> io::Result<()> {
+        if self.ending.is_some() {
+            return Ok(());
+        }
+        if self.host {
+            return self.finish(Ending::Resigned(self.color));
+        }
+        self.send(Body::Resign(proto::Resign { color: proto::Color::from(self.color) as i32 }))?;
+        while self.ending.is_none() {
+            self.update()?;
+        }
+        Ok(())
+    }
+
+    /// Connects again after losing the connection. The guest connects to the host a few times
+    /// until it answers, and catches up with the game; the host waits for its guest to come
+    /// back, which [`wait`](#method.wait) does by itself.
+    pub fn reconnect(&mut self) -> io::Result<()> {
+        let address = match self.address {
+            Some(address) => address,
+            None => return self.await_guest()
+        };
+        let mut attempt = 1;
+        loop {
+            match RemoteGame::rejoin(address, &self.name, &self.token) {
+                Ok(game) => {
+                    *self = game;
+                    return Ok(());
+                }
+                Err(ref error) if attempt < RECONNECT_ATTEMPTS && error.kind() != io::ErrorKind::InvalidInput => {
+                    attempt += 1;
+                    thread::sleep(Duration::from_secs(1));
+                }
+                Err(error) => return Err(error)
+            }
+        }
+    }
+
+    /// Says something to the opponent. Messages too long or too close to the previous one are
+    /// errors.
+    pub fn say(&mut self, text: &str) -> io::Result<()> {
+        check_chat(text, self.said).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        self.said = Some(Instant::now());
+        self.send(Body::Chat(proto::Chat { text: text.to_string() }))
+    }
+
+    /// Waits for the move of the opponent, what they say, or the end of the game.
+    pub fn wait(&mut self) -> io::Result<Update> {
+        loop {
+            if let Some(text) = self.chat.pop_front() {
+                return Ok(Update::Chat(text));
+            }
+            if let Some(ending) = self.ending {
+                return Ok(Update::Over(ending));
+            }
+            if let Some(turn) = if self.host { self.guest_move()? } else { self.update()? } {
+                return Ok(Update::Moved(turn));
+            }
+        }
+    }
+
+    // Exchanges names, checking the versions of the protocol, and returns the greeting of the
+    // other side.
+    fn greet(&mut self, token: &str) -> io::Result<proto::Hello> {
+        self.send(Body::Hello(proto::Hello { protocol_version: PROTOCOL_VERSION, name: self.name.clone(), token: token.to_string() }))?;
+        let hello = match self.receive()? {
+            Body::Hello(hello) => hello,
+            _ => return Err(invalid("expected a greeting"))
+        };
+        if hello.protocol_version != PROTOCOL_VERSION {
+            let message = format!("protocol version {} is not supported", hello.protocol_version);
+            self.send(Body::Error(proto::Error { message: message.clone() }))?;
+            return Err(invalid(&message));
+        }
+        Ok(hello)
+    }
+
+    // Sends the guest the game from its start and its state, charging the player to move with
+    // the time spent so far.
+    fn start(&mut self, spent: Duration) -> io::Result<()> {
+        self.send(Body::NewGame(proto::NewGame {
+            black: self.session.black.name().to_string(),
+            white: self.session.white.name().to_string(),
+            position: None,
+            black_clock: self.clock.map(proto::Clock::from),
+            white_clock: self.clock.map(proto::Clock::from),
+            guest_color: proto::Color::from(self.color.flip()) as i32,
+            token: self.token.clone()
+        }))?;
+        self.send_state(spent)
+    }
+
+    // Waits for the guest to come back with the token of the game, flagging it if its time runs
+    // out first.
+    fn await_guest(&mut self) -> io::Result<()> {
+        let listener = self.listener.as_ref().expect("hosts keep their listener").try_clone()?;
+        listener.set_nonblocking(true)?;
+        let result = loop {
+            if self.guest_time_left() == Some(Duration::from_millis(0)) {
+                break self.finish(Ending::Time(self.color.flip()));
+            }
+            match listener.accept() {
+                // others may knock on the port, they are turned away
+                Ok((stream, _)) => if self.resume(stream).is_ok() {
+                    break Ok(());
+                },
+                Err(ref error) if error.kind() == io::ErrorKind::WouldBlock => thread::sleep(POLL),
+                Err(error) => break Err(error)
+            }
+        };
+        listener.set_nonblocking(false)?;
+        result
+    }
+
+    // Takes back a guest connecting with the token of the game, sending it the game so far.
+    fn resume(&mut self, stream: TcpStream) -> io::Result<()> {
+        stream.set_nonblocking(false)?;
+        stream.set_nodelay(true)?;
+        stream.set_read_timeout(Some(GREETING_TIMEOUT))?;
+        self.reader = BufReader::new(stream.try_clone()?);
+        self.writer = stream;
+        self.connected = true;
+        let result = self.greet("").and_then(|hello| {
+            if hello.token.is_empty() || !net::same_token(&hello.token, &self.token) {
+                self.send(Body::Error(proto::Error { message: "no game with this token".to_string() }))?;
+                return Err(invalid("no game with this token"));
+            }
+            self.start(self.turn_start.elapsed())
+        });
+        self.connected = result.is_ok();
+        result
+    }
+
+    // Reads the next message of the host and returns the move of the opponent it brings, if any.
+    fn update(&mut self) -> io::Result<Option<Turn>> {
+        match self.receive()? {
+            Body::State(state) => self.follow(&state),
+            Body::GameOver(over) => {
+                let color = over.forfeit.map(net::color).transpose()?;
+                self.ending = Some(match color {
+                    Some(color) if over.on_time => Ending::Time(color),
+                    Some(color) => Ending::Resigned(color),
+                    None => Ending::Finished { black_disks: over.black_disks as u8, white_disks: over.white_disks as u8 }
+                });
+                Ok(None)
+            }
+            Body::Error(error) => Err(io::Error::new(io::ErrorKind::InvalidInput, error.message)),
+            Body::Chat(chat) => {
+                self.chat.push_back(chat.text);
+                Ok(None)
+            }
+            _ => Err(invalid("unexpected message"))
+        }
+    }
+
+    // Waits for the host to take the move of the guest.
+    fn answer(&mut self) -> io::Result<()> {
+        let played = self.session.history().len();
+        while self.ending.is_none() && self.session.history().len() == played {
+            self.update()?;
+        }
+        Ok(())
+    }
+
+    // Catches up with the state sent by the host, returning the last move of the opponent.
+    fn follow(&mut self, state: &proto::State) -> io::Result<Option<Turn>> {
+        let known = self.session.history().len();
+        if state.moves.len() < known {
+            return Err(invalid("moves were taken back"));
+        }
+        let clocks = match (state.black_clock, state.white_clock) {
+            (Some(black), Some(white)) => Some([Clock::from(black), Clock::from(white)]),
+            _ => None
+        };
+        let mut last = None;
+        for (i, played) in state.moves.iter().enumerate().skip(known) {
+            let (color, position) = played.to_move()?;
+            let position = position.ok_or_else(|| invalid("skips are not moves"))?;
+            if color != self.session.color() {
+                return Err(invalid("move out of turn"));
+            }
+            // hosts sending no times charged what the clock lost
+            let time = match (state.times_ms.get(i), self.session.clock(color), clocks) {
+                (Some(&time), _, _) => Duration::from_millis(time),
+                (None, Some(before), Some(after)) => (before.remaining + before.increment).saturating_sub(after[color as usize].remaining),
+                _ => Duration::from_millis(0)
+            };
+            self.session.play(position, time)?;
+            if color != self.color {
+                last = self.session.history().last().cloned();
+            }
+        }
+        self.session.clocks = clocks;
+        let position = state.position.ok_or_else(|| invalid("missing position"))?.to_board()?;
+        if position != (*self.session.board(), self.session.color()) {
+            return Err(invalid("the position differs from the moves"));
+        }
+        Ok(last)
+    }
+
+    // Reads the messages of the guest until it moves or the game ends, timing it out.
+    fn guest_move(&mut self) -> io::Result<Option<Turn>> {
+        let guest = self.color.flip();
+        if !self.connected {
+            return self.await_guest().map(|_| None);
+        }
+        let timeout = self.guest_time_left();
+        if timeout == Some(Duration::from_millis(0)) {
+            return self.finish(Ending::Time(guest)).map(|_| None);
+        }
+        self.reader.get_ref().set_read_timeout(timeout)?;
+        let body = match self.receive() {
+            Err(ref error) if error.kind() == io::ErrorKind::WouldBlock || error.kind() == io::ErrorKind::TimedOut => {
+                return self.finish(Ending::Time(guest)).map(|_| None);
+            }
+            Err(ref error) if is_disconnect(error) => {
+                self.connected = false;
+                return Ok(None);
+            }
+            Err(ref error) if error.kind() == io::ErrorKind::InvalidData => return self.kick(&error.to_string()).map(|_| None),
+            body => body?
+        };
+        let message = match body {
+            Body::Play(play) => {
+                let measured = self.turn_start.elapsed();
+                let time = Duration::from_millis(play.time_ms).max(measured.saturating_sub(self.lag)).min(measured);
+                match play.r#move.ok_or_else(|| invalid("missing move")).and_then(|x| x.to_move()) {
+                    _ if self.session.color() != guest || self.session.is_over() => "not your turn".to_string(),
+                    Ok((color, Some(position))) if color == guest => {
+                        if self.out_of_time(guest, time) {
+                            return self.finish(Ending::Time(guest)).map(|_| None);
+                        }
+                        // the board says whether the move is legal, whatever the guest thinks
+                        match self.session.play(position, time) {
+                            Ok(()) => {
+                                self.moved()?;
+                                return Ok(self.session.history().last().cloned());
+                            }
+                            Err(error) => error.to_string()
+                        }
+                    }
+                    // the host skips for the guest, which only moves for itself
+                    Ok((_, None)) => return self.kick("skips are not moves").map(|_| None),
+                    Ok(_) => return self.kick("move of the host").map(|_| None),
+                    Err(error) => return self.kick(&error.to_string()).map(|_| None)
+                }
+            }
+            Body::Resign(_) => return self.finish(Ending::Resigned(guest)).map(|_| None),
+            Body::Undo(_) => "moves can't be taken back in network games".to_string(),
+            Body::Chat(chat) => match check_chat(&chat.text, self.guest_said) {
+                Ok(()) => {
+                    self.guest_said = Some(Instant::now());
+                    self.chat.push_back(chat.text);
+                    return Ok(None);
+                }
+                Err(message) => message.to_string()
+            },
+            _ => return self.kick("unexpected message").map(|_| None)
+        };
+        self.send(Body::Error(proto::Error { message }))?;
+        Ok(None)
+    }
+
+    // Disconnects a guest that sent impossible data, telling it why. It may come back with the
+    // token of the game, its clock running meanwhile.
+    fn kick(&mut self, reason: &str) -> io::Result<()> {
+        #[cfg(feature = "trace")]
+        tracing::warn!(guest = self.opponent.as_str(), reason, "guest disconnected");
+        self.send(Body::Error(proto::Error { message: format!("{}, disconnecting", reason) }))?;
+        // the guest may be gone already
+        let _ = self.writer.shutdown(Shutdown::Both);
+        self.connected = false;
+        Ok(())
+    }
+
+    // The time the guest has left for its move, `None` when it isn't timed or not its turn.
+    fn guest_time_left(&self) -> Option<Duration> {
+        let guest = self.color.flip();
+        if self.session.color() != guest || self.session.is_over() {
+            return None;
+        }
+        self.session.clock(guest).map(|x| (x.remaining + self.lag).saturating_sub(self.turn_start.elapsed()))
+    }
+
+    // Whether a player spending `time` on a move runs out of time.
+    fn out_of_time(&self, color: Color, time: Duration) -> bool {
+        self.session.clock(color).is_some_and(|x| time > x.remaining)
+    }
+
+    // Sends the state after a move of the host's session, and the end of the game if it came.
+    fn moved(&mut self) -> io::Result<()> {
+        self.send_state(Duration::from_millis(0))?;
+        self.turn_start = Instant::now();
+        if self.session.is_over() {
+            let board = self.session.board();
+            let (black_disks, white_disks) = (board.count(Color::Black) as u8, board.count(Color::White) as u8);
+            return self.finish(Ending::Finished { black_disks, white_disks });
+        }
+        Ok(())
+    }
+
+    // Sends the state of the game, charging the player to move with the time it spent so far.
+    fn send_state(&mut self, spent: Duration) -> io::Result<()> {
+        let session = &self.session;
+        let clock = |color: Color| session.clock(color).map(|mut clock| {
+            if color == session.color() && !session.is_over() {
+                clock.remaining = clock.remaining.saturating_sub(spent);
+            }
+            proto::Clock::from(clock)
+        });
+        let state = proto::State {
+            position: Some(proto::Position::new(session.board(), session.color())),
+            black_clock: clock(Color::Black),
+            white_clock: clock(Color::White),
+            moves: session.history().iter().map(|x| proto::Move::new(x.color, Some(x.position))).collect(),
+            timestamp_ms: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |x| x.as_millis() as u64),
+            times_ms: session.history().iter().map(|x| x.time.as_millis() as u64).collect()
+        };
+        self.send(Body::State(state))
+    }
+
+    // Ends the game on the host and tells the guest.
+    fn finish(&mut self, ending: Ending) -> io::Result<()> {
+        self.ending = Some(ending);
+        let board = self.session.board();
+        let (forfeit, on_time) = match ending {
+            Ending::Finished { .. } => (None, false),
+            Ending::Resigned(color) => (Some(color), false),
+            Ending::Time(color) => (Some(color), true)
+        };
+        self.send(Body::GameOver(proto::GameOver {
+            black_disks: board.count(Color::Black) as u32,
+            white_disks: board.count(Color::White) as u32,
+            forfeit: forfeit.map(|x| proto::Color::from(x) as i32),
+            on_time
+        }))
+    }
+
+    // Sends a message to the other side. The host doesn't fail when its guest is gone, which
+    // gets the game again when it comes back.
+    fn send(&mut self, body: Body) -> io::Result<()> {
+        if self.host && !self.connected && self.listener.is_some() {
+            return Ok(());
+        }
+        match net::send(&mut self.writer, &proto::Message { body: Some(body) }) {
+            Err(ref error) if self.host && self.listener.is_some() && is_disconnect(error) => {
+                self.connected = false;
+                Ok(())
+            }
+            sent => sent
+        }
+    }
+
+    fn receive(&mut self) -> io::Result<Body> {
+        let message = net::receive(&mut self.reader)?;
+        let message = message.ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "the opponent left"))?;
+        message.body.ok_or_else(|| invalid("empty message"))
+    }
+}
+
+// Checks a chat message said after another at `last`, returning why it is refused.
+fn check_chat(text: &str, last: Option<Instant>) -> Result<(), &'static str> {
+    if text.trim().is_empty() {
+        return Err("empty message");
+    }
+    if text.chars().count() > MAX_CHAT_LENGTH {
+        return Err("message too long");
+    }
+    if last.is_some_and(|x| x.elapsed() < CHAT_INTERVAL) {
+        return Err("too many messages, wait a moment");
+    }
+    Ok(())
+}
+
+/// Whether an error is the loss of the connection, after which the guest can
+/// [reconnect](struct.RemoteGame.html#method.reconnect).
+pub fn is_disconnect(error: &io::Error) -> bool {
+    use std::io::ErrorKind::*;
+    matches!(error.kind(), UnexpectedEof | ConnectionReset | ConnectionAborted | BrokenPipe)
+}
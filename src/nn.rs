@@ -0,0 +1,136 @@
+//! This module lets neural networks evaluate positions.
+//!
+//! A network receives the board as two planes of 8x8 inputs: the first marks the pieces of the
+//! player to move, the second the pieces of the opponent. Within a plane the cell `(x, y)` is at
+//! index `y * 8 + x`. It answers with a value, the expected outcome for the player to move in
+//! `[-1, 1]`, and a policy, the prior probability of each cell being the best move, indexed the
+//! same way.
+//!
+//! Networks in the ONNX format can be loaded with the `onnx` feature enabled. The model must
+//! take a single `1x2x8x8` float input and have two outputs: the value with one element and the
+//! policy with 64 elements.
+
+use std::io;
+use std::path::Path;
+
+use eval::{Evaluator, Phase, Score};
+use game::{Board, Color};
+
+/// Number of inputs of a network.
+pub const INPUTS: usize = 2 * 64;
+
+/// Encodes a board as the input of a network.
+pub fn planes(board: &Board, color: Color) -> [f32; INPUTS] {
+    let mut planes = [0.0; INPUTS];
+    for (x, column) in board.cells.iter().enumerate() {
+        for (y, cell) in column.iter().enumerate() {
+            match *cell {
+                Some(found) if found == color => planes[y * 8 + x] = 1.0,
+                Some(_) => planes[64 + y * 8 + x] = 1.0,
+                None => ()
+            }
+        }
+    }
+    planes
+}
+
+/// The output of a network.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Prediction {
+    /// Expected outcome for the player to move, from -1 for a loss to 1 for a win.
+    pub value: f32,
+    /// Prior probability of each cell being the best move.
+    pub policy: [f32; 64]
+}
+
+/// A trained network predicting the value of positions and the best moves in them.
+pub trait Network {
+    fn predict(&self, board: &Board, color: Color) -> Prediction;
+}
+
+/// Uses the value predicted by a network as the score of a position.
+pub struct NetworkEvaluator<N: Network> {
+    pub network: N,
+    /// The score of a certain win. Values in between are scaled linearly.
+    pub scale: Score
+}
+impl<N: Network> Evaluator for NetworkEvaluator<N> {
+    fn evaluate(&self, board: &Board, color: Color, _: Phase) -> Score {
+        (self.network.predict(board, color).value * self.scale as f32) as Score
+    }
+}
+impl<'a> Network for Box<dyn Network + 'a> {
+    fn predict(&self, board: &Board, color: Color) -> Prediction {
+        (**self).predict(board, color)
+    }
+}
+
+/// Loads a network from a file, choosing the format by the extension.
+///
+/// Only ONNX models are supported, and only when the `onnx` feature is enabled.
+pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Box<dyn Network>> {
+    let path = path.as_ref();
+    match path.extension().and_then(|x| x.to_str()) {
+        #[cfg(feature = "onnx")]
+        Some("onnx") => Ok(Box::new(onnx::OnnxNetwork::load(path)?)),
+        #[cfg(not(feature = "onnx"))]
+        Some("onnx") => Err(io::Error::other("ONNX models need the onnx feature")),
+        _ => Err(io::Error::new(io::ErrorKind::InvalidInput, format!("unknown network format: {}", path.display())))
+    }
+}
+
+#[cfg(feature = "onnx")]
+pub mod onnx {
+    //! Runs ONNX models with the pure Rust tract runtime.
+
+    use std::io;
+    use std::path::Path;
+
+    use tract_onnx::prelude::*;
+
+    use game::{Board, Color};
+    use super::{planes, Network, Prediction};
+
+    type Plan = SimplePlan<TypedFact, Box<dyn TypedOp>, Graph<TypedFact, Box<dyn TypedOp>>>;
+
+    /// A network loaded from an ONNX model.
+    pub struct OnnxNetwork {
+        plan: Plan
+    }
+    impl OnnxNetwork {
+        /// Loads and optimizes the model.
+        pub fn load<P: AsRef<Path>>(path: P) -> io::Result<OnnxNetwork> {
+            let plan = tract_onnx::onnx()
+                .model_for_path(path)
+                .and_then(|model| model.with_input_fact(0, f32::fact([1, 2, 8, 8]).into()))
+                .and_then(|model| model.into_optimized())
+                .and_then(|model| model.into_runnable())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+            let network = OnnxNetwork { plan };
+            // a trial run makes sure predictions won't fail later
+            let outputs = network.plan.run(tvec!(network.input(&Board::new(), Color::Black).into()))
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+            let valid = outputs.len() == 2
+                && outputs[0].as_slice::<f32>().map(|x| !x.is_empty()).unwrap_or(false)
+                && outputs[1].as_slice::<f32>().map(|x| x.len() >= 64).unwrap_or(false);
+            if valid {
+                Ok(network)
+            } else {
+                Err(io::Error::new(io::ErrorKind::InvalidData, "the model should output a value and a policy of 64 cells"))
+            }
+        }
+
+        fn input(&self, board: &Board, color: Color) -> Tensor {
+            Tensor::from_shape(&[1, 2, 8, 8], &planes(board, color)).expect("planes have the input shape")
+        }
+    }
+    impl Network for OnnxNetwork {
+        fn predict(&self, board: &Board, color: Color) -> Prediction {
+            let outputs = self.plan.run(tvec!(self.input(board, color).into())).expect("the model was validated when loaded");
+            let value = outputs[0].as_slice::<f32>().expect("value is a float")[0];
+            let mut policy = [0.0; 64];
+            policy.copy_from_slice(&outputs[1].as_slice::<f32>().expect("policy is a float")[..64]);
+            Prediction { value, policy }
+        }
+    }
+}
@@ -0,0 +1,231 @@
+//! This module implements an efficiently updatable neural network evaluation.
+//!
+//! The network has a single hidden layer. Its inputs are the pieces seen from one player: 64
+//! inputs for the cells of the player's pieces followed by 64 for the opponent's, indexed by
+//! `y * 8 + x` like the [planes](../nn/fn.planes.html) of the other networks. The hidden layer
+//! is computed from the point of view of both players, and these accumulators are kept up to date
+//! while the search makes and takes back moves: a new piece adds the weights of its input, a
+//! flipped piece moves its input from one owner to the other. Evaluating a position then only
+//! takes the small output layer, which makes the network cheap enough for alpha-beta searches.
+//!
+//! Weights are integers, so taking back a move restores the accumulators exactly.
+
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::mem;
+use std::path::Path;
+
+use eval::{Evaluator, Phase, Score};
+use game::{Board, Color, Coord, LegalMove};
+use random::Random;
+
+/// Number of inputs of the network.
+pub const INPUTS: usize = 128;
+
+/// Hidden values are clipped to `0..=ACTIVATION_LIMIT` before the output layer.
+pub const ACTIVATION_LIMIT: i32 = 255;
+
+const MAGIC: &[u8; 6] = b"RRNNUE";
+const VERSION: u16 = 1;
+
+/// The parameters of the network.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Weights {
+    /// Size of the hidden layer.
+    pub hidden: usize,
+    /// Weights of the first layer, `hidden` values for each input.
+    pub input: Vec<i16>,
+    /// Biases of the hidden layer.
+    pub bias: Vec<i16>,
+    /// Weights of the output layer: `hidden` values for the accumulator of the player to move,
+    /// then `hidden` for the opponent's.
+    pub output: Vec<i16>,
+    pub output_bias: i32
+}
+impl Weights {
+    /// Creates small random weights, as a starting point for training.
+    pub fn random(hidden: usize, seed: u64) -> Weights {
+        let mut random = Random::new(seed);
+        let mut values = |n: usize| (0..n).map(|_| random.below(65) as i16 - 32).collect::<Vec<_>>();
+        Weights {
+            hidden,
+            input: values(INPUTS * hidden),
+            bias: values(hidden),
+            output: values(2 * hidden),
+            output_bias: 0
+        }
+    }
+
+    /// Reads weights written by [`write`](#method.write).
+    ///
+    /// The format starts with the magic bytes `RRNNUE`, a 16 bit version and the 32 bit size of
+    /// the hidden layer, followed by the input weights, the biases, the output weights and the
+    /// output bias in this order, all integers being little endian.
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Weights> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+        let mut magic = [0; 6];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("not a network weight file"));
+        }
+        let mut version = [0; 2];
+        reader.read_exact(&mut version)?;
+        if u16::from_le_bytes(version) != VERSION {
+            return Err(invalid("unsupported network weight file version"));
+        }
+        let mut word = [0; 4];
+        reader.read_exact(&mut word)?;
+        let hidden = u32::from_le_bytes(word) as usize;
+        let mut values = |n: usize| -> io::Result<Vec<i16>> {
+            let mut bytes = vec![0; 2 * n];
+            reader.read_exact(&mut bytes)?;
+            Ok(bytes.chunks(2).map(|x| i16::from_le_bytes([x[0], x[1]])).collect())
+        };
+        let input = values(INPUTS * hidden)?;
+        let bias = values(hidden)?;
+        let output = values(2 * hidden)?;
+        reader.read_exact(&mut word)?;
+        Ok(Weights { hidden, input, bias, output, output_bias: i32::from_le_bytes(word) })
+    }
+
+    /// Writes the weights in the format accepted by [`read`](#method.read).
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&(self.hidden as u32).to_le_bytes())?;
+        for value in self.input.iter().chain(self.bias.iter()).chain(self.output.iter()) {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        writer.write_all(&self.output_bias.to_le_bytes())
+    }
+
+    /// Reads the weights from a file.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Weights> {
+        Weights::read(BufReader::new(File::open(path)?))
+    }
+
+    /// Writes the weights to a file, replacing its contents.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()
+    }
+}
+
+// The hidden layer from the point of view of each player, and the board it was computed for.
+#[derive(Debug, Clone, PartialEq)]
+struct Accumulators {
+    board: Board,
+    black: Vec<i32>,
+    white: Vec<i32>
+}
+
+/// Evaluates positions with an efficiently updatable network.
+///
+/// The accumulators follow the moves the search makes and takes back through the
+/// [`Evaluator`](../eval/trait.Evaluator.html) hooks. When asked about any other board, they are
+/// recomputed from scratch, so the evaluation is always correct, only slower.
+pub struct Nnue {
+    weights: Weights,
+    accumulators: RefCell<Accumulators>
+}
+impl Nnue {
+    pub fn new(weights: Weights) -> Nnue {
+        let accumulators = RefCell::new(Accumulators { board: Board::new(), black: Vec::new(), white: Vec::new() });
+        let nnue = Nnue { weights, accumulators };
+        nnue.refresh(&Board::new());
+        nnue
+    }
+
+    pub fn weights(&self) -> &Weights {
+        &self.weights
+    }
+
+    // Recomputes the accumulators for a board.
+    fn refresh(&self, board: &Board) {
+        let mut accumulators = self.accumulators.borrow_mut();
+        accumulators.board = *board;
+        accumulators.black = self.weights.bias.iter().map(|&x| i32::from(x)).collect();
+        accumulators.white = accumulators.black.clone();
+        for (x, column) in board.cells.iter().enumerate() {
+            for (y, cell) in column.iter().enumerate() {
+                if let Some(color) = *cell {
+                    self.update(&mut accumulators, (x as i8, y as i8), color, 1);
+                }
+            }
+        }
+    }
+
+    // Adds (sign 1) or removes (sign -1) the piece of `color` on a cell.
+    fn update(&self, accumulators: &mut Accumulators, cell: Coord, color: Color, sign: i32) {
+        let index = cell.1 as usize * 8 + cell.0 as usize;
+        let hidden = self.weights.hidden;
+        let (black_input, white_input) = match color {
+            Color::Black => (index, 64 + index),
+            Color::White => (64 + index, index)
+        };
+        let column = |input: usize| &self.weights.input[input * hidden..(input + 1) * hidden];
+        add(&mut accumulators.black, column(black_input), sign);
+        add(&mut accumulators.white, column(white_input), sign);
+    }
+
+    // Applies the changes of a move, or reverts them when `sign` is -1.
+    fn update_move(&self, accumulators: &mut Accumulators, legal_move: &LegalMove, sign: i32) {
+        let (color, opponent) = (legal_move.color, legal_move.color.flip());
+        self.update(accumulators, legal_move.position, color, sign);
+        for cell in legal_move.flipped() {
+            self.update(accumulators, cell, opponent, -sign);
+            self.update(accumulators, cell, color, sign);
+        }
+    }
+}
+impl Evaluator for Nnue {
+    fn evaluate(&self, board: &Board, color: Color, _: Phase) -> Score {
+        if self.accumulators.borrow().board != *board {
+            self.refresh(board);
+        }
+        let accumulators = self.accumulators.borrow();
+        let (own, opponent) = match color {
+            Color::Black => (&accumulators.black, &accumulators.white),
+            Color::White => (&accumulators.white, &accumulators.black)
+        };
+        let activations = own.iter().chain(opponent.iter()).map(|&x| x.clamp(0, ACTIVATION_LIMIT));
+        let sum: i32 = activations.zip(self.weights.output.iter()).map(|(a, &w)| a * i32::from(w)).sum();
+        (self.weights.output_bias + sum) / ACTIVATION_LIMIT
+    }
+
+    /// The weights with the accumulators.
+    fn memory_usage(&self) -> usize {
+        let weights = &self.weights;
+        let accumulators = self.accumulators.borrow();
+        let values = (weights.input.capacity() + weights.bias.capacity() + weights.output.capacity()) * mem::size_of::<i16>();
+        mem::size_of::<Nnue>() + values + (accumulators.black.capacity() + accumulators.white.capacity()) * mem::size_of::<i32>()
+    }
+
+    fn make(&self, board: &Board, legal_move: &LegalMove) {
+        if self.accumulators.borrow().board != *board {
+            self.refresh(board);
+        }
+        let mut accumulators = self.accumulators.borrow_mut();
+        self.update_move(&mut accumulators, legal_move, 1);
+        accumulators.board.make(legal_move);
+    }
+
+    fn unmake(&self, board: &Board, legal_move: &LegalMove) {
+        let mut accumulators = self.accumulators.borrow_mut();
+        if accumulators.board == legal_move.apply(*board) {
+            self.update_move(&mut accumulators, legal_move, -1);
+            accumulators.board = *board;
+        } else {
+            drop(accumulators);
+            self.refresh(board);
+        }
+    }
+}
+
+fn add(accumulator: &mut [i32], weights: &[i16], sign: i32) {
+    for (value, &weight) in accumulator.iter_mut().zip(weights.iter()) {
+        *value += sign * i32::from(weight);
+    }
+}
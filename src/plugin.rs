@@ -0,0 +1,158 @@
+//! This module loads evaluation functions written by others as shared libraries, so that the
+//! engines search with them without changing the crate. Loading them needs the `plugins`
+//! feature, on Unix.
+//!
+//! A plugin is a library with a C interface exporting two functions:
+//!
+//! ```c
+//! uint32_t rusty_reversi_plugin_version(void);
+//! int32_t rusty_reversi_evaluate(uint64_t player, uint64_t opponent, uint32_t empties);
+//! ```
+//!
+//! The first returns the [version](constant.VERSION.html) of the interface the plugin was
+//! written for, and plugins of other versions are refused. The second scores a position for the
+//! player to move like an [`Evaluator`](../eval/trait.Evaluator.html), higher being better,
+//! given the cells of the player and of the opponent as bits, `1` for a1 to `1 << 63` for h8 row
+//! by row, and the number of empty cells. It may be called from several threads at once. Its
+//! scores are clamped to [`MAX_SCORE`](constant.MAX_SCORE.html) either way, so that they aren't
+//! taken for the scores of solved positions.
+//!
+//! The engines searching with a [`Pluggable`](struct.Pluggable.html) evaluator, like the
+//! `alphabeta` and `minimax` engines made by [`from_spec`](../engine/fn.from_spec.html), load
+//! a plugin through their `plugin` option, set to the path of the library. Without the feature,
+//! the option is unknown.
+
+use std::io;
+use std::os::raw::c_void;
+use std::path::{Path, PathBuf};
+
+use engine::unknown_option;
+use eval::{Evaluator, Heuristic, Phase, Score};
+#[cfg(all(feature = "plugins", unix))]
+use files::invalid;
+use game::{Board, Color};
+use search::WIN;
+
+/// Version of the interface of the plugins loaded.
+pub const VERSION: u32 = 1;
+/// The highest score of a plugin, below the scores of won positions.
+pub const MAX_SCORE: Score = WIN - 65;
+
+type Evaluate = extern "C" fn(u64, u64, u32) -> i32;
+
+/// An evaluation function loaded from a shared library.
+pub struct Plugin {
+    path: PathBuf,
+    // only closed when plugins can be loaded
+    #[cfg_attr(not(all(feature = "plugins", unix)), allow(dead_code))]
+    library: *mut c_void,
+    evaluate: Evaluate
+}
+// plugins promise their evaluation may be called from any thread
+unsafe impl Send for Plugin {}
+unsafe impl Sync for Plugin {}
+impl Plugin {
+    /// Loads a library, checking the version of its interface.
+    #[cfg(all(feature = "plugins", unix))]
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Plugin> {
+        use std::ffi::{CStr, CString};
+        use std::mem;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = path.as_ref();
+        let name = CString::new(path.as_os_str().as_bytes()).map_err(|_| invalid("invalid path"))?;
+        let error = || unsafe {
+            let message = libc::dlerror();
+            let message = if message.is_null() { "unknown error".into() } else { CStr::from_ptr(message).to_string_lossy() };
+            io::Error::new(io::ErrorKind::InvalidData, message.into_owned())
+        };
+        let library = unsafe { libc::dlopen(name.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+        if library.is_null() {
+            return Err(error());
+        }
+        let symbol = |name: &[u8]| {
+            let name = CStr::from_bytes_with_nul(name).expect("symbol names end with a nul");
+            let symbol = unsafe { libc::dlsym(library, name.as_ptr()) };
+            if symbol.is_null() { Err(error()) } else { Ok(symbol) }
+        };
+        let checked = symbol(b"rusty_reversi_plugin_version\0").and_then(|version| {
+            let version: extern "C" fn() -> u32 = unsafe { mem::transmute(version) };
+            match version() {
+                VERSION => symbol(b"rusty_reversi_evaluate\0"),
+                version => Err(invalid(&format!("unsupported plugin version {}", version)))
+            }
+        });
+        match checked {
+            Ok(evaluate) => Ok(Plugin { path: path.to_path_buf(), library, evaluate: unsafe { mem::transmute::<*mut c_void, Evaluate>(evaluate) } }),
+            Err(error) => {
+                unsafe { libc::dlclose(library) };
+                Err(error)
+            }
+        }
+    }
+
+    /// Loads a library, which needs the `plugins` feature on Unix.
+    #[cfg(not(all(feature = "plugins", unix)))]
+    pub fn load<P: AsRef<Path>>(_path: P) -> io::Result<Plugin> {
+        Err(io::Error::other("evaluation plugins need the plugins feature on Unix"))
+    }
+
+    /// The path the library was loaded from.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+impl Evaluator for Plugin {
+    fn evaluate(&self, board: &Board, color: Color, phase: Phase) -> Score {
+        let (mut player, mut opponent) = (0u64, 0u64);
+        for (x, column) in board.cells.iter().enumerate() {
+            for (y, cell) in column.iter().enumerate() {
+                let bit = 1 << ((7 - y) * 8 + x);
+                match *cell {
+                    Some(owner) if owner == color => player |= bit,
+                    Some(_) => opponent |= bit,
+                    None => {}
+                }
+            }
+        }
+        (self.evaluate)(player, opponent, phase.empties as u32).clamp(-MAX_SCORE, MAX_SCORE)
+    }
+}
+impl Drop for Plugin {
+    fn drop(&mut self) {
+        #[cfg(all(feature = "plugins", unix))]
+        unsafe {
+            libc::dlclose(self.library);
+        }
+    }
+}
+
+/// Evaluates with the [heuristic](../eval/struct.Heuristic.html), or with a plugin once one is
+/// loaded through the `plugin` option. Setting the option to an empty path goes back to the
+/// heuristic. The option is only known with the `plugins` feature, on Unix.
+#[derive(Default)]
+pub struct Pluggable {
+    pub heuristic: Heuristic,
+    pub plugin: Option<Plugin>
+}
+impl Evaluator for Pluggable {
+    fn evaluate(&self, board: &Board, color: Color, phase: Phase) -> Score {
+        match self.plugin {
+            Some(ref plugin) => plugin.evaluate(board, color, phase),
+            None => self.heuristic.evaluate(board, color, phase)
+        }
+    }
+
+    // the value is only read when plugins can be loaded
+    #[cfg_attr(not(all(feature = "plugins", unix)), allow(unused_variables))]
+    fn set_option(&mut self, name: &str, value: &str) -> io::Result<()> {
+        match name {
+            #[cfg(all(feature = "plugins", unix))]
+            "plugin" => {
+                self.plugin = if value.trim().is_empty() { None } else { Some(Plugin::load(value.trim())?) };
+                Ok(())
+            }
+            _ => Err(unknown_option(name))
+        }
+    }
+}
@@ -0,0 +1,148 @@
+//! This module implements the console commands of Edax, so that scripts and interfaces written
+//! for it can drive any engine:
+//!
+//! - `init` starts a new game, `setboard <position>` sets up a position written as in the
+//!   [records](../../record/index.html),
+//! - `play <moves>`, or the moves alone, plays moves, `undo` takes the last one back,
+//! - `go` lets the engine play, `hint [n]` lists its `n` best moves with their scores,
+//! - `level <depth>` sets the depth of its searches,
+//! - `solve [position]` solves the current or given position exactly,
+//! - `board` draws the board and `quit` ends the session.
+//!
+//! Moves are answered in upper case, like `Edax plays F5`.
+
+use std::io::{self, BufRead, Write};
+
+use analysis;
+use endgame::Solver;
+use engine::{Engine, Limits};
+use eval::Score;
+use files::invalid_input;
+use game::{Charset, Coord};
+use protocol::Position;
+use record;
+use search::WIN;
+
+/// A session of the protocol.
+pub struct Edax<E: Engine> {
+    pub engine: E,
+    pub position: Position,
+    pub limits: Limits,
+    /// Characters of the board drawn by `board`.
+    pub charset: Charset
+}
+impl<E: Engine> Edax<E> {
+    /// A session at the starting position, searching to the depth of the engine.
+    pub fn new(engine: E) -> Edax<E> {
+        Edax { engine, position: Position::default(), limits: Limits::default(), charset: Charset::default() }
+    }
+
+    /// Runs a command and returns its answer, empty for the commands without one.
+    pub fn execute(&mut self, line: &str) -> io::Result<String> {
+        let line = line.trim();
+        let (command, arguments) = match line.find(char::is_whitespace) {
+            Some(i) => (&line[..i], line[i..].trim()),
+            None => (line, "")
+        };
+        match command.to_ascii_lowercase().as_str() {
+            "init" | "i" | "new" => {
+                self.position = Position::default();
+                Ok(String::new())
+            }
+            "setboard" => {
+                let (board, color) = record::parse_position(arguments).ok_or_else(|| invalid_input("invalid position"))?;
+                self.position.set(board, color);
+                Ok(String::new())
+            }
+            "play" | "p" => self.play(arguments),
+            "undo" | "u" => {
+                if !self.position.undo() {
+                    return Err(invalid_input("no move to take back"));
+                }
+                Ok(String::new())
+            }
+            "go" => {
+                if self.position.is_over() {
+                    return Err(invalid_input("the game is over"));
+                }
+                let (board, color) = (self.position.board, self.position.color);
+                let best = self.engine.best_move(&board, color, &self.limits).best.ok_or_else(|| invalid_input("no move found"))?;
+                self.position.play(best)?;
+                Ok(format!("Edax plays {}", cell(best)))
+            }
+            "hint" => {
+                let count = if arguments.is_empty() { 1 } else { arguments.parse().map_err(|_| invalid_input("invalid number of moves"))? };
+                let hints = self.hints();
+                Ok(hints.iter().take(count).map(|&(position, score)| format!("{} {:+}", cell(position), score)).collect::<Vec<_>>().join("\n"))
+            }
+            "level" | "l" => {
+                self.limits.depth = Some(arguments.parse().map_err(|_| invalid_input("invalid level"))?);
+                Ok(String::new())
+            }
+            "solve" => {
+                let (board, color) = if arguments.is_empty() {
+                    (self.position.board, self.position.color)
+                } else {
+                    record::parse_position(arguments).ok_or_else(|| invalid_input("invalid position"))?
+                };
+                let result = Solver::default().best_move(&board, color, &Limits::default());
+                let best = result.best.map(cell).unwrap_or_else(|| "PS".to_string());
+                let diff = result.score - result.score.signum() * WIN;
+                Ok(format!("{} {:+} ({} nodes, {:.3}s)", best, diff, result.nodes, result.time.as_secs_f64()))
+            }
+            "board" | "b" => Ok(format!("{}{} to move", self.position.board.draw(self.charset), self.position.color)),
+            _ => match record::parse_transcript(line) {
+                Some(_) => self.play(line),
+                None => Err(invalid_input(&format!("unknown command: {}", command)))
+            }
+        }
+    }
+
+    /// Answers the commands read line by line until `quit` or the end of the input. Errors are
+    /// answered as `error: <message>`.
+    #[cfg_attr(feature = "trace", tracing::instrument(name = "edax", skip_all))]
+    pub fn run<R: BufRead, W: Write>(&mut self, reader: R, mut writer: W) -> io::Result<()> {
+        for line in reader.lines() {
+            let line = line?;
+            #[cfg(feature = "trace")]
+            tracing::debug!(command = line.trim(), "received");
+            match line.trim() {
+                "" => continue,
+                "quit" | "q" | "exit" => break,
+                _ => match self.execute(&line) {
+                    Ok(answer) if answer.is_empty() => {}
+                    Ok(answer) => writeln!(writer, "{}", answer)?,
+                    Err(error) => {
+                        #[cfg(feature = "trace")]
+                        tracing::warn!(command = line.trim(), %error, "command failed");
+                        writeln!(writer, "error: {}", error)?
+                    }
+                }
+            }
+            writer.flush()?;
+        }
+        Ok(())
+    }
+
+    fn play(&mut self, moves: &str) -> io::Result<String> {
+        let moves = record::parse_transcript(moves).ok_or_else(|| invalid_input("invalid moves"))?;
+        for position in moves {
+            self.position.play(position)?;
+        }
+        Ok(String::new())
+    }
+
+    // The legal moves with their scores, best first.
+    fn hints(&mut self) -> Vec<(Coord, Score)> {
+        let board = self.position.board;
+        let mut hints = board.legal_moves(self.position.color).iter().map(|legal_move| {
+            (legal_move.position, analysis::score_move(&mut self.engine, &board, legal_move, &self.limits))
+        }).collect::<Vec<_>>();
+        hints.sort_by_key(|x| -x.1);
+        hints
+    }
+}
+
+fn cell(position: Coord) -> String {
+    record::format_move(position).to_uppercase()
+}
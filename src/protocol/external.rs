@@ -0,0 +1,145 @@
+//! This module drives engines running as other programs, started as children or reached over
+//! TCP, so that they play matches against the engines of this crate.
+//!
+//! The program is spoken to in one of two [dialects](enum.Dialect.html). In the
+//! [Edax console protocol](../edax/index.html), each search sets up the position with
+//! `setboard`, the depth with `level` when the limits give one, and asks for a move with `go`,
+//! answered like `Edax plays F5`. In the protocol of NBoard, the program is greeted with
+//! `nboard 2`, and each search sets the depth with `set depth`, the position with `set game`
+//! followed by a game in the [Generic Game Format](../../formats/ggf/index.html), and asks with
+//! `go`, answered like `=== F5/1.23/0.5`. Lines of the answer not naming a move, like prompts,
+//! banners and the progress of the search, are skipped. Time limits are left to the program.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::process::{Child, Command, Stdio};
+use std::time::Instant;
+
+use engine::{unknown_option, Engine, Limits, SearchResult};
+use files::invalid_input;
+use game::{Board, Color, Coord};
+use record;
+
+/// The protocol spoken to a program.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Dialect {
+    /// The console protocol of Edax.
+    #[default]
+    Edax,
+    /// The protocol through which NBoard drives engines.
+    NBoard
+}
+
+/// An engine running as another program.
+pub struct External {
+    dialect: Dialect,
+    /// The program, when started by this engine rather than reached over TCP.
+    child: Option<Child>,
+    input: Box<dyn Write + Send>,
+    output: Box<dyn BufRead + Send>
+}
+impl External {
+    /// Starts a program given as its path followed by its arguments, separated by spaces.
+    pub fn spawn(command: &str) -> io::Result<External> {
+        let mut words = command.split_whitespace();
+        let program = words.next().ok_or_else(|| invalid_input("empty command"))?;
+        let mut child = Command::new(program).args(words).stdin(Stdio::piped()).stdout(Stdio::piped()).spawn()?;
+        let input = child.stdin.take().ok_or_else(|| io::Error::other("no input to the engine"))?;
+        let output = BufReader::new(child.stdout.take().ok_or_else(|| io::Error::other("no output from the engine"))?);
+        Ok(External { dialect: Dialect::Edax, child: Some(child), input: Box::new(input), output: Box::new(output) })
+    }
+
+    /// Connects to a program listening at an address, like `localhost:7474`.
+    pub fn connect(address: &str) -> io::Result<External> {
+        let stream = TcpStream::connect(address)?;
+        stream.set_nodelay(true)?;
+        let output = BufReader::new(stream.try_clone()?);
+        Ok(External { dialect: Dialect::Edax, child: None, input: Box::new(stream), output: Box::new(output) })
+    }
+
+    /// Speaks another dialect to the program, greeting it as that dialect wants.
+    pub fn speaking(mut self, dialect: Dialect) -> io::Result<External> {
+        if dialect == Dialect::NBoard {
+            writeln!(self.input, "nboard 2")?;
+            self.input.flush()?;
+        }
+        self.dialect = dialect;
+        Ok(self)
+    }
+
+    // Asks for the move of a position and reads it from the answer.
+    fn ask(&mut self, board: &Board, color: Color, limits: &Limits) -> io::Result<Coord> {
+        #[cfg(feature = "trace")]
+        tracing::debug!(position = record::format_position(board, color).as_str(), depth = limits.depth, "asking");
+        match self.dialect {
+            Dialect::Edax => {
+                writeln!(self.input, "setboard {}", record::format_position(board, color))?;
+                if let Some(depth) = limits.depth {
+                    writeln!(self.input, "level {}", depth)?;
+                }
+            }
+            Dialect::NBoard => {
+                if let Some(depth) = limits.depth {
+                    writeln!(self.input, "set depth {}", depth)?;
+                }
+                writeln!(self.input, "set game {}", game(board, color))?;
+            }
+        }
+        writeln!(self.input, "go")?;
+        self.input.flush()?;
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if self.output.read_line(&mut line)? == 0 {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "the engine stopped"));
+            }
+            #[cfg(feature = "trace")]
+            tracing::trace!(line = line.trim_end(), "received");
+            let cell = match self.dialect {
+                Dialect::Edax => line.find(" plays ").map(|i| line[i + 7..].trim()),
+                // the move may be followed by the evaluation and the time taken
+                Dialect::NBoard => line.strip_prefix("=== ").map(|x| x.split('/').next().unwrap_or("").trim())
+            };
+            if let Some(cell) = cell {
+                return record::parse_move(cell).ok_or_else(|| invalid_input(&format!("invalid move: {}", cell)));
+            }
+        }
+    }
+}
+impl Engine for External {
+    /// Searches with the program. A program failing to answer plays no move, which forfeits
+    /// games.
+    #[cfg_attr(feature = "trace", tracing::instrument(name = "external", skip_all, fields(%color, ?limits)))]
+    fn best_move(&mut self, board: &Board, color: Color, limits: &Limits) -> SearchResult {
+        let start = Instant::now();
+        let answer = self.ask(board, color, limits);
+        #[cfg(feature = "trace")]
+        if let Err(ref error) = answer {
+            tracing::warn!(%error, "no move from the engine");
+        }
+        let pv = answer.into_iter().collect();
+        SearchResult::new(0, limits.depth.unwrap_or(0), 0, 0, pv, start.elapsed())
+    }
+
+    fn set_option(&mut self, name: &str, _: &str) -> io::Result<()> {
+        Err(unknown_option(name))
+    }
+}
+impl Drop for External {
+    fn drop(&mut self) {
+        // programs ignoring `quit` are stopped anyway
+        let _ = writeln!(self.input, "quit").and_then(|_| self.input.flush());
+        if let Some(ref mut child) = self.child {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+// A game starting from a position, as NBoard sets it up.
+fn game(board: &Board, color: Color) -> String {
+    let position = record::format_position(board, color).replace('X', "*");
+    let (cells, player) = position.split_at(64);
+    let rows = cells.as_bytes().chunks(8).map(|x| String::from_utf8_lossy(x).into_owned()).collect::<Vec<_>>();
+    format!("(;GM[Othello]PC[rusty_reversi]PB[Black]PW[White]TY[8]BO[8 {} {}];)", rows.join(" "), player.trim())
+}
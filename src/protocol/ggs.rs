@@ -0,0 +1,204 @@
+//! This module implements a client of the Othello service `/os` of the Generic Game Server, so
+//! that an engine can play rated games online unattended.
+//!
+//! The server talks in lines of text. The client logs in when the server asks for its login and
+//! password, then opens itself to match requests. The messages of `/os` it follows are:
+//!
+//! - `/os: + .7 1850.3 ann 15:00//02:00 1700.0 bot 15:00//02:00 8 R`, a match request with the
+//!   ratings, logins and clocks of both players, the game type and `R` for a rated game or `U`,
+//!   answered with `tell /os accept .7` when the [policy](struct.Policy.html) allows it,
+//! - `/os: join .7 (;GM[Othello]...;)` and `/os: update .7 (;GM[Othello]...;)`, the game so far
+//!   in the [Generic Game Format](../../formats/ggf/index.html), after which the engine plays
+//!   with `tell /os play .7 f5` when it has the turn, or `tell /os play .7 pa` to pass,
+//! - `/os: - match .7 ...`, the end of a game, which is then [finished](struct.Ggs.html#method.take_finished).
+//!
+//! Only 8x8 games from the starting position are played. Other lines are ignored.
+
+use std::io::{self, BufRead, Write};
+
+use engine::{Engine, Limits};
+use files::invalid;
+use formats::ggf;
+use game::{Board, Color};
+use record::{self, GameRecord};
+
+/// Which match requests are accepted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Policy {
+    /// Games played at the same time.
+    pub games: usize,
+    /// Whether unrated games are accepted.
+    pub unrated: bool,
+    /// Lowest rating of the opponents accepted.
+    pub min_rating: Option<f64>
+}
+impl Default for Policy {
+    fn default() -> Policy {
+        Policy { games: 1, unrated: true, min_rating: None }
+    }
+}
+
+/// A game being played on the server.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Match {
+    /// The id of the match, like `.7`.
+    pub id: String,
+    /// The color of the engine.
+    pub color: Color,
+    /// The game so far.
+    pub record: GameRecord
+}
+
+/// A session of the client.
+pub struct Ggs<E: Engine> {
+    pub engine: E,
+    pub limits: Limits,
+    pub policy: Policy,
+    login: String,
+    password: String,
+    matches: Vec<Match>,
+    finished: Vec<GameRecord>
+}
+impl<E: Engine> Ggs<E> {
+    /// A client logging in with a login and password, searching to the depth of the engine.
+    pub fn new(engine: E, login: &str, password: &str) -> Ggs<E> {
+        Ggs {
+            engine,
+            limits: Limits::default(),
+            policy: Policy::default(),
+            login: login.to_string(),
+            password: password.to_string(),
+            matches: Vec::new(),
+            finished: Vec::new()
+        }
+    }
+
+    /// The games being played.
+    pub fn matches(&self) -> &[Match] {
+        &self.matches
+    }
+
+    /// Takes the games finished since the last call.
+    pub fn take_finished(&mut self) -> Vec<GameRecord> {
+        self.finished.split_off(0)
+    }
+
+    /// Handles a line from the server and returns the lines to send back.
+    pub fn handle(&mut self, line: &str) -> io::Result<Vec<String>> {
+        let line = line.trim();
+        let prompt = line.to_ascii_lowercase();
+        if prompt.starts_with(": enter login") || prompt.starts_with(": enter your handle") {
+            return Ok(vec![self.login.clone()]);
+        }
+        if prompt.starts_with(": enter your password") {
+            return Ok(vec![self.password.clone(), "tell /os request +".to_string(), format!("tell /os open {}", self.policy.games)]);
+        }
+        let message = match line.strip_prefix("/os:") {
+            Some(message) => message.trim(),
+            None => return Ok(Vec::new())
+        };
+        let words = message.split_whitespace().collect::<Vec<_>>();
+        match words.as_slice() {
+            ["+", id, rest @ ..] if id.starts_with('.') => Ok(self.request(id, rest).into_iter().collect()),
+            ["join", id, ..] | ["update", id, ..] => {
+                let id = id.to_string();
+                let game = message.find("(;").map(|i| &message[i..]).ok_or_else(|| invalid("missing game"))?;
+                self.update(&id, game)
+            }
+            ["-", "match", id, ..] => {
+                if let Some(i) = self.matches.iter().position(|x| x.id == *id) {
+                    let finished = self.matches.remove(i);
+                    self.finished.push(finished.record);
+                }
+                Ok(Vec::new())
+            }
+            _ => Ok(Vec::new())
+        }
+    }
+
+    /// Talks with the server until it closes the connection.
+    #[cfg_attr(feature = "trace", tracing::instrument(name = "ggs", skip_all))]
+    pub fn run<R: BufRead, W: Write>(&mut self, reader: R, mut writer: W) -> io::Result<()> {
+        for line in reader.lines() {
+            let line = line?;
+            match self.handle(&line) {
+                Ok(answers) => {
+                    for answer in answers {
+                        writeln!(writer, "{}", answer)?;
+                    }
+                    writer.flush()?;
+                }
+                // a message not understood shouldn't end the games being played
+                Err(_error) => {
+                    #[cfg(feature = "trace")]
+                    tracing::warn!(line, error = %_error, "message ignored");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Answers a match request: the ratings, logins and clocks of both players, the type and
+    // whether the game is rated.
+    fn request(&self, id: &str, words: &[&str]) -> Option<String> {
+        let (kind, rated) = match words {
+            [_, _, _, _, _, _, kind, rated, ..] => (*kind, *rated),
+            _ => return None
+        };
+        let players = [(words[0], words[1]), (words[3], words[4])];
+        if !players.iter().any(|x| x.1 == self.login) || kind != "8" || self.matches.len() >= self.policy.games {
+            return None;
+        }
+        let opponent_rating = players.iter().find(|x| x.1 != self.login)?.0.parse::<f64>().ok()?;
+        if (rated != "R" && !self.policy.unrated) || self.policy.min_rating.is_some_and(|x| opponent_rating < x) {
+            return None;
+        }
+        Some(format!("tell /os accept {}", id))
+    }
+
+    // Follows a game and plays the move of the engine if it has the turn.
+    fn update(&mut self, id: &str, game: &str) -> io::Result<Vec<String>> {
+        let mut record = ggf::parse(game)?.pop().ok_or_else(|| invalid("missing game"))?;
+        let color = if record.black == self.login {
+            Color::Black
+        } else if record.white == self.login {
+            Color::White
+        } else {
+            return Err(invalid("not a game of the client"));
+        };
+        let (board, _) = *record.positions().map_err(|i| invalid(&format!("illegal move {}", i + 1)))?.last().expect("the last position is always there");
+        // passes are moves on the server, so the turn goes to the opponent of the last mover
+        let to_move = last_mover(game).map(|x| x.flip()).unwrap_or(Color::Black);
+        let over = board.legal_moves(Color::Black).is_empty() && board.legal_moves(Color::White).is_empty();
+        if over && record.black_disks.is_none() {
+            record.black_disks = Some(board.count(Color::Black) as u8);
+        }
+        match self.matches.iter_mut().find(|x| x.id == id) {
+            Some(found) => found.record = record,
+            None => self.matches.push(Match { id: id.to_string(), color, record })
+        }
+        if over || to_move != color {
+            return Ok(Vec::new());
+        }
+        Ok(vec![format!("tell /os play {} {}", id, self.choose(&board, color))])
+    }
+
+    fn choose(&mut self, board: &Board, color: Color) -> String {
+        if board.legal_moves(color).is_empty() {
+            return "pa".to_string();
+        }
+        match self.engine.best_move(board, color, &self.limits).best {
+            Some(best) => record::format_move(best),
+            None => "pa".to_string()
+        }
+    }
+}
+
+// The player of the last move of a game, passes included.
+fn last_mover(game: &str) -> Option<Color> {
+    game.split(']').filter_map(|property| property.split('[').next()).map(|key| key.trim().trim_start_matches(';')).fold(None, |last, key| match key {
+        "B" => Some(Color::Black),
+        "W" => Some(Color::White),
+        _ => last
+    })
+}
@@ -0,0 +1,146 @@
+//! This module implements a text protocol modelled on the Go Text Protocol, so that harnesses
+//! written for board games in general can drive an engine.
+//!
+//! A command may start with a numeric id, which is repeated in its answer. Answers start with
+//! `=` on success and `?` on failure, and end with an empty line. The commands are:
+//!
+//! - `protocol_version`, `name`, `version`, `known_command <name>`, `list_commands` and `quit`,
+//! - `boardsize <size>`, accepting only 8, and `clear_board`,
+//! - `play <color> <move>` with a move like `f5` or `pass`, and `undo`,
+//! - `genmove <color>`, answering the move played by the engine, or `pass`,
+//! - `showboard`,
+//! - `set_time <seconds>`, the time of each engine move, `0` to search to the engine's depth.
+
+use std::io::{self, BufRead, Write};
+use std::time::Duration;
+
+use engine::{Engine, Limits};
+use files::invalid_input;
+use game::{Charset, Color};
+use protocol::Position;
+use record;
+
+const COMMANDS: [&str; 13] = [
+    "boardsize", "clear_board", "genmove", "known_command", "list_commands", "name", "play",
+    "protocol_version", "quit", "set_time", "showboard", "undo", "version"
+];
+
+/// A session of the protocol.
+pub struct Gtp<E: Engine> {
+    pub engine: E,
+    pub position: Position,
+    pub limits: Limits,
+    /// Characters of the board drawn by `showboard`.
+    pub charset: Charset
+}
+impl<E: Engine> Gtp<E> {
+    /// A session at the starting position, searching to the depth of the engine.
+    pub fn new(engine: E) -> Gtp<E> {
+        Gtp { engine, position: Position::default(), limits: Limits::default(), charset: Charset::default() }
+    }
+
+    /// Runs a command without its id and returns its answer.
+    pub fn execute(&mut self, command: &str) -> io::Result<String> {
+        let words = command.split_whitespace().collect::<Vec<_>>();
+        let argument = |i: usize| words.get(i).cloned().ok_or_else(|| invalid_input("missing argument"));
+        match words.first().cloned().unwrap_or("") {
+            "protocol_version" => Ok("2".to_string()),
+            "name" => Ok("Rusty Reversi".to_string()),
+            "version" => Ok(env!("CARGO_PKG_VERSION").to_string()),
+            "known_command" => Ok(COMMANDS.contains(&argument(1)?).to_string()),
+            "list_commands" => Ok(COMMANDS.join("\n")),
+            "quit" => Ok(String::new()),
+            "boardsize" => match argument(1)? {
+                "8" => Ok(String::new()),
+                _ => Err(invalid_input("unacceptable size"))
+            },
+            "clear_board" => {
+                self.position = Position::default();
+                Ok(String::new())
+            }
+            "play" => {
+                let color = parse_color(argument(1)?)?;
+                let cell = argument(2)?;
+                if cell.eq_ignore_ascii_case("pass") {
+                    if !self.position.board.legal_moves(color).is_empty() && color == self.position.color {
+                        return Err(invalid_input("illegal move: pass"));
+                    }
+                    return Ok(String::new());
+                }
+                if color != self.position.color {
+                    return Err(invalid_input("not the turn of this player"));
+                }
+                let position = record::parse_move(cell).ok_or_else(|| invalid_input("invalid move"))?;
+                self.position.play(position)?;
+                Ok(String::new())
+            }
+            "genmove" => {
+                let color = parse_color(argument(1)?)?;
+                if color != self.position.color || self.position.is_over() {
+                    return Ok("pass".to_string());
+                }
+                let (board, color) = (self.position.board, self.position.color);
+                let best = self.engine.best_move(&board, color, &self.limits).best.ok_or_else(|| invalid_input("no move found"))?;
+                self.position.play(best)?;
+                Ok(record::format_move(best).to_uppercase())
+            }
+            "undo" => {
+                if !self.position.undo() {
+                    return Err(invalid_input("cannot undo"));
+                }
+                Ok(String::new())
+            }
+            "showboard" => Ok(format!("\n{}{} to move", self.position.board.draw(self.charset), self.position.color)),
+            "set_time" => {
+                let seconds = argument(1)?.parse::<f64>().map_err(|_| invalid_input("invalid time"))?;
+                let time = Duration::try_from_secs_f64(seconds).map_err(|_| invalid_input("invalid time"))?;
+                self.limits.time = if time > Duration::ZERO { Some(time) } else { None };
+                Ok(String::new())
+            }
+            "" => Err(invalid_input("empty command")),
+            _ => Err(invalid_input("unknown command"))
+        }
+    }
+
+    /// Answers the commands read line by line until `quit` or the end of the input. Comments
+    /// starting with `#` and empty lines are ignored.
+    #[cfg_attr(feature = "trace", tracing::instrument(name = "gtp", skip_all))]
+    pub fn run<R: BufRead, W: Write>(&mut self, reader: R, mut writer: W) -> io::Result<()> {
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (id, command) = match line.find(|x: char| !x.is_ascii_digit()) {
+                Some(0) => ("", line),
+                Some(i) => (&line[..i], line[i..].trim()),
+                None => (line, "")
+            };
+            #[cfg(feature = "trace")]
+            tracing::debug!(id, command, "received");
+            match self.execute(command) {
+                Ok(ref answer) if answer.is_empty() => write!(writer, "={}\n\n", id)?,
+                Ok(answer) => write!(writer, "={} {}\n\n", id, answer)?,
+                Err(error) => {
+                    #[cfg(feature = "trace")]
+                    tracing::warn!(id, command, %error, "command failed");
+                    write!(writer, "?{} {}\n\n", id, error)?
+                }
+            }
+            writer.flush()?;
+            if command.split_whitespace().next() == Some("quit") {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_color(text: &str) -> io::Result<Color> {
+    match text.to_ascii_lowercase().as_str() {
+        "b" | "black" => Ok(Color::Black),
+        "w" | "white" => Ok(Color::White),
+        _ => Err(invalid_input("invalid color"))
+    }
+}
@@ -0,0 +1,70 @@
+//! This module implements the text protocols through which scripts and graphical interfaces
+//! drive an [engine](../engine/trait.Engine.html), one command per line. Through the same
+//! commands, [engines of other programs](external/index.html) play like the engines of this crate,
+//! and an engine plays on the [Generic Game Server](ggs/index.html).
+
+use std::io;
+
+use files::invalid_input;
+use game::{Board, Color, Coord};
+use record;
+
+pub mod edax;
+pub mod external;
+pub mod ggs;
+pub mod gtp;
+
+/// The game followed by a protocol: the position with the player to move, and the previous
+/// positions to take moves back.
+///
+/// Skips are played automatically: after a move, the opponent keeps the turn only if they have
+/// a legal move.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Position {
+    pub board: Board,
+    pub color: Color,
+    history: Vec<(Board, Color)>
+}
+impl Default for Position {
+    fn default() -> Position {
+        Position { board: Board::new(), color: Color::Black, history: Vec::new() }
+    }
+}
+impl Position {
+    /// Starts a game from a position, forgetting the previous moves.
+    pub fn set(&mut self, board: Board, color: Color) {
+        *self = Position { board, color, history: Vec::new() };
+    }
+
+    /// Plays a move of the player to move.
+    pub fn play(&mut self, position: Coord) -> io::Result<()> {
+        let legal_move = self.board.legal_moves(self.color).into_iter().find(|x| x.position == position);
+        let legal_move = legal_move.ok_or_else(|| {
+            invalid_input(&format!("illegal move: {}", record::format_move(position)))
+        })?;
+        self.history.push((self.board, self.color));
+        self.board = legal_move.apply(self.board);
+        self.color = self.color.flip();
+        if self.board.legal_moves(self.color).is_empty() && !self.board.legal_moves(self.color.flip()).is_empty() {
+            self.color = self.color.flip();
+        }
+        Ok(())
+    }
+
+    /// Takes back the last move, returns false if there was none.
+    pub fn undo(&mut self) -> bool {
+        match self.history.pop() {
+            Some((board, color)) => {
+                self.board = board;
+                self.color = color;
+                true
+            }
+            None => false
+        }
+    }
+
+    /// Whether neither player can move.
+    pub fn is_over(&self) -> bool {
+        self.board.legal_moves(self.color).is_empty() && self.board.legal_moves(self.color.flip()).is_empty()
+    }
+}
@@ -0,0 +1,129 @@
+//! This module finds endgame puzzles: positions where a single move wins, or draws when no move
+//! wins, so that the player has to find that move.
+//!
+//! Puzzles are searched in the positions of random games or of a game database, and kept as
+//! [problems](../ffo/struct.Problem.html) with their solution, to be written as a suite with
+//! [`ffo::format`](../ffo/fn.format.html) and checked with the solver like the FFO suite.
+//!
+//! Players [answer](fn.check.html) puzzles one after the other, keeping a [`Streak`](struct.Streak.html)
+//! of correct answers.
+
+use endgame;
+use ffo::Problem;
+use game::{Board, Color, Coord};
+use random::Random;
+use record::GameRecord;
+
+/// The difficulty of the puzzles searched.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// Fewest empty cells of a puzzle.
+    pub min_empties: usize,
+    /// Most empty cells of a puzzle, more are harder to see through but slower to solve.
+    pub max_empties: usize,
+    /// Fewest disks lost by the second best move against the solution.
+    pub gap: i8
+}
+impl Default for Options {
+    fn default() -> Options {
+        Options { min_empties: 8, max_empties: 12, gap: 2 }
+    }
+}
+
+/// A run of answers to puzzles.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct Streak {
+    /// Correct answers in a row up to now.
+    pub current: usize,
+    /// Longest run of correct answers.
+    pub best: usize,
+    pub solved: usize,
+    pub attempted: usize
+}
+impl Streak {
+    /// Counts an answer, a wrong one ending the current run.
+    pub fn answer(&mut self, correct: bool) {
+        self.attempted += 1;
+        if correct {
+            self.solved += 1;
+            self.current += 1;
+            self.best = self.best.max(self.current);
+        } else {
+            self.current = 0;
+        }
+    }
+}
+
+/// Whether a move answers a puzzle: one of its best moves, or another legal move the solver
+/// finds reaching the same score.
+pub fn check(problem: &Problem, position: Coord) -> bool {
+    if problem.best_moves.contains(&position) {
+        return true;
+    }
+    match problem.board.legal_moves(problem.color).into_iter().find(|x| x.position == position) {
+        Some(legal_move) => -endgame::solve(&legal_move.apply(problem.board), problem.color.flip()).score >= problem.score,
+        None => false
+    }
+}
+
+/// The puzzle of a position, if it has one within the options. The problem is named by its empty
+/// cells and the gap to the second best move.
+pub fn find(board: &Board, color: Color, options: &Options) -> Option<Problem> {
+    let empties = board.empties();
+    if empties < options.min_empties || empties > options.max_empties {
+        return None;
+    }
+    let moves = board.legal_moves(color);
+    if moves.len() < 2 {
+        return None;
+    }
+    let mut scores = moves.iter().map(|x| (x.position, -endgame::solve(&x.apply(*board), color.flip()).score)).collect::<Vec<_>>();
+    scores.sort_by_key(|x| -x.1);
+    let (best, second) = (scores[0], scores[1]);
+    // only one move keeps the result, be it a win or a draw
+    if best.1 < 0 || second.1.signum() >= best.1.signum() || best.1 - second.1 < options.gap {
+        return None;
+    }
+    Some(Problem {
+        name: format!("{} empties, gap {}", empties, best.1 - second.1),
+        board: *board,
+        color,
+        best_moves: vec![best.0],
+        score: best.1
+    })
+}
+
+/// Finds puzzles in random games, stopping after `count` puzzles or `games` games. A game gives
+/// at most one puzzle.
+pub fn generate(random: &mut Random, count: usize, games: usize, options: &Options) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    for _ in 0..games {
+        if problems.len() >= count {
+            break;
+        }
+        let (mut board, mut color) = (Board::new(), Color::Black);
+        while board.empties() >= options.min_empties {
+            if let Some(problem) = find(&board, color, options) {
+                problems.push(problem);
+                break;
+            }
+            let moves = board.legal_moves(color);
+            if !moves.is_empty() {
+                board = moves[random.below(moves.len())].apply(board);
+            } else if board.legal_moves(color.flip()).is_empty() {
+                break;
+            }
+            color = color.flip();
+        }
+    }
+    problems
+}
+
+/// Finds puzzles in the positions of played games, at most one per game. Games with illegal
+/// moves are skipped.
+pub fn from_records(records: &[GameRecord], count: usize, options: &Options) -> Vec<Problem> {
+    records.iter()
+        .filter_map(|record| record.positions().ok()?.iter().find_map(|&(board, color)| find(&board, color, options)))
+        .take(count)
+        .collect()
+}
@@ -0,0 +1,36 @@
+//! This module provides a small deterministic pseudo-random number generator.
+//!
+//! Randomized features like tuning and playouts take a seed, so that their results can be
+//! reproduced.
+
+/// A SplitMix64 generator. It is fast and has good statistical quality for games, but it is
+/// not suitable for cryptography.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Random {
+    state: u64
+}
+impl Random {
+    /// Creates a generator with the given seed. Equal seeds produce equal sequences.
+    pub fn new(seed: u64) -> Random {
+        Random { state: seed }
+    }
+
+    /// Returns the next 64 random bits.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `0..n`. `n` must not be zero.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// Returns a number in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
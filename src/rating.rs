@@ -0,0 +1,266 @@
+//! This module keeps the ratings of players, engines and human profiles alike, from the results
+//! of their games, so that their strength can be followed over time.
+//!
+//! Each player has an Elo rating, updated after every game, and a
+//! [Glicko-2](http://www.glicko.net/glicko/glicko2.pdf) rating, updated once per rating period,
+//! like a match or a tournament round. Glicko-2 also estimates how uncertain the rating is: its
+//! deviation grows while a player doesn't play, and shrinks with every game.
+//!
+//! Ratings are saved in a compact binary format, see [`Ratings::write`](struct.Ratings.html#method.write).
+
+use std::f64::consts::PI;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+use std::path::Path;
+
+use files::{self, invalid, read_bytes, read_f64, read_u16};
+use stats::expected_score;
+
+const MAGIC: &[u8; 6] = b"RRRATE";
+const VERSION: u16 = 1;
+
+// Ratio between the Glicko and the Glicko-2 scales.
+const SCALE: f64 = 173.7178;
+
+/// The ratings of a player.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Rating {
+    pub elo: f64,
+    /// Glicko-2 rating, on the same scale as Elo ratings.
+    pub rating: f64,
+    /// Glicko-2 deviation: the rating is within twice the deviation with 95% confidence.
+    pub deviation: f64,
+    /// Glicko-2 volatility, how erratic the results of the player are.
+    pub volatility: f64,
+    /// Number of games played.
+    pub games: u32
+}
+impl Default for Rating {
+    fn default() -> Rating {
+        Rating { elo: 1500.0, rating: 1500.0, deviation: 350.0, volatility: 0.06, games: 0 }
+    }
+}
+impl Rating {
+    /// The 95% confidence interval of the Glicko-2 rating.
+    pub fn interval(&self) -> (f64, f64) {
+        (self.rating - 1.96 * self.deviation, self.rating + 1.96 * self.deviation)
+    }
+}
+
+/// A game between two players, with the score of the first: 1 for a win, 0.5 for a draw, 0 for
+/// a loss.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    pub first: String,
+    pub second: String,
+    pub score: f64
+}
+impl Outcome {
+    /// The outcome of a game from the disk differential of the first player.
+    pub fn new(first: &str, second: &str, difference: i32) -> Outcome {
+        let score = match difference.signum() {
+            1 => 1.0,
+            0 => 0.5,
+            _ => 0.0
+        };
+        Outcome { first: first.to_string(), second: second.to_string(), score }
+    }
+}
+
+/// Ratings of players by name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ratings {
+    pub players: Vec<(String, Rating)>,
+    /// How much an Elo rating changes after a game.
+    pub k: f64,
+    /// Glicko-2 constraint on the changes of volatility, smaller values for more stable players.
+    pub tau: f64
+}
+impl Default for Ratings {
+    fn default() -> Ratings {
+        Ratings { players: Vec::new(), k: 32.0, tau: 0.5 }
+    }
+}
+impl Ratings {
+    pub fn new() -> Ratings {
+        Ratings::default()
+    }
+
+    /// The ratings of a player, the default ones for a new player.
+    pub fn get(&self, name: &str) -> Rating {
+        self.players.iter().find(|x| x.0 == name).map(|x| x.1).unwrap_or_default()
+    }
+
+    /// Rates the games of a rating period. Elo ratings are updated game by game, Glicko-2 ratings
+    /// once with all the games. Players not playing in the period become more uncertain.
+    pub fn update(&mut self, outcomes: &[Outcome]) {
+        for outcome in outcomes {
+            for name in &[&outcome.first, &outcome.second] {
+                if !self.players.iter().any(|x| &x.0 == *name) {
+                    self.players.push((name.to_string(), Rating::default()));
+                }
+            }
+        }
+        let before = self.players.clone();
+        let rating = |name: &str| before.iter().find(|x| x.0 == name).map(|x| x.1).unwrap_or_default();
+        for outcome in outcomes {
+            let (first, second) = (self.get(&outcome.first), self.get(&outcome.second));
+            let expected = expected_score(first.elo - second.elo);
+            let change = self.k * (outcome.score - expected);
+            self.entry(&outcome.first).elo += change;
+            self.entry(&outcome.second).elo -= change;
+        }
+        let tau = self.tau;
+        for (name, rating_now) in &mut self.players {
+            let games = outcomes.iter().filter_map(|x| {
+                if x.first == *name {
+                    Some((rating(&x.second), x.score))
+                } else if x.second == *name {
+                    Some((rating(&x.first), 1.0 - x.score))
+                } else {
+                    None
+                }
+            }).collect::<Vec<_>>();
+            let updated = glicko(&rating(name), &games, tau);
+            rating_now.rating = updated.rating;
+            rating_now.deviation = updated.deviation;
+            rating_now.volatility = updated.volatility;
+            rating_now.games += games.len() as u32;
+        }
+    }
+
+    /// Writes the players from the best Glicko-2 rating, with their intervals.
+    pub fn format(&self) -> String {
+        let mut players = self.players.iter().collect::<Vec<_>>();
+        players.sort_by(|a, b| b.1.rating.total_cmp(&a.1.rating));
+        let width = players.iter().map(|x| x.0.len()).max().unwrap_or(0).max(6);
+        let mut text = format!("  # {:width$}  glicko      95% interval   elo  games\n", "player", width = width);
+        for (i, (name, rating)) in players.iter().enumerate() {
+            let (low, high) = rating.interval();
+            text += &format!("{:>3} {:width$} {:>7.0} {:>7.0} - {:<7.0} {:>5.0} {:>6}\n", i + 1, name, rating.rating, low, high, rating.elo, rating.games, width = width);
+        }
+        text
+    }
+
+    /// Reads ratings written by [`write`](#method.write).
+    ///
+    /// The format starts with the magic bytes `RRRATE` and a 16 bit version, then `k` and `tau`
+    /// as 64 bit floats, and the players as their 32 bit number. Each player is its name, as its
+    /// 16 bit length followed by its UTF-8 bytes, its Elo rating, Glicko-2 rating, deviation and
+    /// volatility as 64 bit floats and its 32 bit number of games. All numbers are little endian.
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Ratings> {
+        let mut header = [0; 8];
+        reader.read_exact(&mut header)?;
+        if &header[..6] != MAGIC {
+            return Err(invalid("not a rating list"));
+        }
+        if u16::from_le_bytes([header[6], header[7]]) != VERSION {
+            return Err(invalid("unsupported rating list version"));
+        }
+        let k = read_f64(&mut reader)?;
+        let tau = read_f64(&mut reader)?;
+        let players = (0..u32::from_le_bytes(read_bytes(&mut reader)?)).map(|_| {
+            let mut name = vec![0; usize::from(read_u16(&mut reader)?)];
+            reader.read_exact(&mut name)?;
+            let name = String::from_utf8(name).map_err(|_| invalid("invalid player name"))?;
+            let rating = Rating {
+                elo: read_f64(&mut reader)?,
+                rating: read_f64(&mut reader)?,
+                deviation: read_f64(&mut reader)?,
+                volatility: read_f64(&mut reader)?,
+                games: u32::from_le_bytes(read_bytes(&mut reader)?)
+            };
+            Ok((name, rating))
+        }).collect::<io::Result<Vec<_>>>()?;
+        Ok(Ratings { players, k, tau })
+    }
+
+    /// Writes the ratings in the format accepted by [`read`](#method.read).
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&self.k.to_le_bytes())?;
+        writer.write_all(&self.tau.to_le_bytes())?;
+        writer.write_all(&(self.players.len() as u32).to_le_bytes())?;
+        for (name, rating) in &self.players {
+            if name.len() > usize::from(u16::MAX) {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, "player name too long"));
+            }
+            writer.write_all(&(name.len() as u16).to_le_bytes())?;
+            writer.write_all(name.as_bytes())?;
+            for &number in &[rating.elo, rating.rating, rating.deviation, rating.volatility] {
+                writer.write_all(&number.to_le_bytes())?;
+            }
+            writer.write_all(&rating.games.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Reads ratings from a file.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Ratings> {
+        Ratings::read(BufReader::new(File::open(path)?))
+    }
+
+    /// Writes the ratings to a file, replacing the previous save atomically.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        files::save_atomically(path, |writer| self.write(writer))
+    }
+
+    fn entry(&mut self, name: &str) -> &mut Rating {
+        let i = self.players.iter().position(|x| x.0 == name).expect("players are added first");
+        &mut self.players[i].1
+    }
+}
+
+/// Updates the Glicko-2 rating of a player after a rating period, from the ratings of their
+/// opponents before the period and their scores against them.
+pub fn glicko(player: &Rating, games: &[(Rating, f64)], tau: f64) -> Rating {
+    let (mu, phi, sigma) = ((player.rating - 1500.0) / SCALE, player.deviation / SCALE, player.volatility);
+    if games.is_empty() {
+        return Rating { deviation: (phi * phi + sigma * sigma).sqrt() * SCALE, ..*player };
+    }
+    let g = |phi: f64| 1.0 / (1.0 + 3.0 * phi * phi / (PI * PI)).sqrt();
+    let terms = games.iter().map(|(opponent, score)| {
+        let (mu_j, phi_j) = ((opponent.rating - 1500.0) / SCALE, opponent.deviation / SCALE);
+        let expected = 1.0 / (1.0 + (-g(phi_j) * (mu - mu_j)).exp());
+        (g(phi_j), expected, *score)
+    }).collect::<Vec<_>>();
+    let v = 1.0 / terms.iter().map(|&(g, e, _)| g * g * e * (1.0 - e)).sum::<f64>();
+    let sum = terms.iter().map(|&(g, e, s)| g * (s - e)).sum::<f64>();
+    let delta = v * sum;
+    // the new volatility is the root of f, found by the Illinois algorithm
+    let a = (sigma * sigma).ln();
+    let f = |x: f64| {
+        let ex = x.exp();
+        ex * (delta * delta - phi * phi - v - ex) / (2.0 * (phi * phi + v + ex).powi(2)) - (x - a) / (tau * tau)
+    };
+    let mut low = a;
+    let mut high = if delta * delta > phi * phi + v {
+        (delta * delta - phi * phi - v).ln()
+    } else {
+        let mut k = 1.0;
+        while f(a - k * tau) < 0.0 {
+            k += 1.0;
+        }
+        a - k * tau
+    };
+    let (mut f_low, mut f_high) = (f(low), f(high));
+    while (high - low).abs() > 1e-6 {
+        let middle = low + (low - high) * f_low / (f_high - f_low);
+        let f_middle = f(middle);
+        if f_middle * f_high <= 0.0 {
+            low = high;
+            f_low = f_high;
+        } else {
+            f_low /= 2.0;
+        }
+        high = middle;
+        f_high = f_middle;
+    }
+    let volatility = (low / 2.0).exp();
+    let phi_star = (phi * phi + volatility * volatility).sqrt();
+    let phi_new = 1.0 / (1.0 / (phi_star * phi_star) + 1.0 / v).sqrt();
+    let mu_new = mu + phi_new * phi_new * sum;
+    Rating { rating: mu_new * SCALE + 1500.0, deviation: phi_new * SCALE, volatility, ..*player }
+}
+
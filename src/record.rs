@@ -0,0 +1,167 @@
+//! This module contains the record of a played game, the common ground of the game file formats.
+//!
+//! Moves are written in the usual algebraic notation: the column from `a` to `h`, then the row
+//! from 1 to 8, `a1` being the cell `(0, 7)` and `h8` the cell `(7, 0)`. Transcripts list the
+//! moves without separators, like `f5d6c3`. Skips are not written, they are implied by the player
+//! to move having no legal move.
+//!
+//! Positions are written as in the Edax and FFO files: the 64 cells from `a1` to `h8` row by
+//! row, `X` for Black, `O` for White and `-` for an empty cell, then the player to move.
+
+use game::{Board, Color, Coord};
+
+/// A played game with its metadata.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct GameRecord {
+    /// Name of the black player.
+    pub black: String,
+    /// Name of the white player.
+    pub white: String,
+    /// The tournament or place of the game.
+    pub event: String,
+    /// When the game was played, as precisely as known, like `2024` or `2024-03-15`.
+    pub date: String,
+    /// Moves from the starting position, skips left out.
+    pub moves: Vec<Coord>,
+    /// Disks of Black at the end of the game, as reported. It may differ from the final board
+    /// when the game ended by time or resignation, or when the empty cells went to the winner.
+    pub black_disks: Option<u8>,
+    /// Disks of Black with perfect play from some point of the game.
+    pub theoretical_disks: Option<u8>,
+    /// Other information kept by the formats, like ratings and clocks, by name.
+    pub tags: Vec<(String, String)>
+}
+impl GameRecord {
+    /// Plays the moves from the starting position and returns the positions before each move
+    /// with the player to move, followed by the final position. Fails with the index of the
+    /// first illegal move.
+    pub fn positions(&self) -> Result<Vec<(Board, Color)>, usize> {
+        let (mut board, mut color) = (Board::new(), Color::Black);
+        let mut positions = Vec::with_capacity(self.moves.len() + 1);
+        for (i, &position) in self.moves.iter().enumerate() {
+            if board.legal_moves(color).is_empty() {
+                color = color.flip();
+            }
+            positions.push((board, color));
+            let legal_move = board.legal_moves(color).into_iter().find(|x| x.position == position).ok_or(i)?;
+            board = legal_move.apply(board);
+            color = color.flip();
+        }
+        if board.legal_moves(color).is_empty() && !board.legal_moves(color.flip()).is_empty() {
+            color = color.flip();
+        }
+        positions.push((board, color));
+        Ok(positions)
+    }
+
+    /// The value of a tag.
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|x| x.0 == name).map(|x| x.1.as_str())
+    }
+
+    /// How the game ended early, if it did.
+    pub fn termination(&self) -> Option<Termination> {
+        self.tag("termination").and_then(Termination::parse)
+    }
+
+    /// Ends the game with the loss of a player, who loses all the disks.
+    pub fn forfeit(&mut self, loser: Color, termination: Termination) {
+        self.black_disks = Some(if loser == Color::Black { 0 } else { 64 });
+        self.tags.retain(|x| x.0 != "termination");
+        self.tags.push(("termination".to_string(), termination.as_str().to_string()));
+    }
+}
+
+/// How a game ended before neither player could move, kept in the `termination` tag.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Termination {
+    Resignation,
+    /// A player ran out of time.
+    Timeout,
+    /// A player couldn't go on, like an engine failing to move.
+    Forfeit
+}
+impl Termination {
+    /// The value of the tag.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Termination::Resignation => "resignation",
+            Termination::Timeout => "time",
+            Termination::Forfeit => "forfeit"
+        }
+    }
+
+    pub fn parse(text: &str) -> Option<Termination> {
+        [Termination::Resignation, Termination::Timeout, Termination::Forfeit].iter().cloned().find(|x| x.as_str() == text)
+    }
+}
+
+/// Writes a cell in algebraic notation.
+pub fn format_move(position: Coord) -> String {
+    let (x, y) = position;
+    format!("{}{}", (b'a' + x as u8) as char, 8 - y)
+}
+
+/// Reads a cell in algebraic notation, in either case.
+pub fn parse_move(text: &str) -> Option<Coord> {
+    let bytes = text.as_bytes();
+    if bytes.len() != 2 {
+        return None;
+    }
+    let column = bytes[0].to_ascii_lowercase();
+    if !(b'a'..=b'h').contains(&column) || !(b'1'..=b'8').contains(&bytes[1]) {
+        return None;
+    }
+    Some(((column - b'a') as i8, 8 - (bytes[1] - b'0') as i8))
+}
+
+/// Writes moves as a transcript.
+pub fn format_transcript(moves: &[Coord]) -> String {
+    moves.iter().map(|&x| format_move(x)).collect()
+}
+
+/// Reads a transcript, ignoring whitespace and separators between the moves.
+pub fn parse_transcript(text: &str) -> Option<Vec<Coord>> {
+    let cells = text.chars().filter(|x| x.is_ascii_alphanumeric()).collect::<String>();
+    if cells.len() % 2 != 0 {
+        return None;
+    }
+    (0..cells.len()).step_by(2).map(|i| parse_move(&cells[i..i + 2])).collect()
+}
+
+/// Writes a position with the player to move.
+pub fn format_position(board: &Board, color: Color) -> String {
+    let mut text = String::with_capacity(66);
+    for y in (0..8).rev() {
+        for column in &board.cells {
+            text.push(match column[y] {
+                Some(Color::Black) => 'X',
+                Some(Color::White) => 'O',
+                None => '-'
+            });
+        }
+    }
+    text.push(' ');
+    text.push(if color == Color::Black { 'X' } else { 'O' });
+    text
+}
+
+/// Reads a position with the player to move, ignoring whitespace. Black may also be written as
+/// `*` or `B`, White as `W` and empty cells as `.`, in either case.
+pub fn parse_position(text: &str) -> Option<(Board, Color)> {
+    let cell = |x: char| match x.to_ascii_uppercase() {
+        'X' | '*' | 'B' => Some(Some(Color::Black)),
+        'O' | 'W' => Some(Some(Color::White)),
+        '-' | '.' => Some(None),
+        _ => None
+    };
+    let cells = text.chars().filter(|x| !x.is_whitespace()).map(cell).collect::<Option<Vec<_>>>()?;
+    if cells.len() != 65 {
+        return None;
+    }
+    let mut board = Board { cells: [[None; 8]; 8] };
+    for (i, &cell) in cells[..64].iter().enumerate() {
+        board.cells[i % 8][7 - i / 8] = cell;
+    }
+    Some((board, cells[64]?))
+}
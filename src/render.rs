@@ -0,0 +1,430 @@
+//! This module draws positions as images, so that bots and reports can attach boards.
+//!
+//! Boards are rasterized to RGB pixels by [`render`](fn.render.html), with the cell a1 at the top
+//! left as on the [text board](../game/struct.Board.html), or drawn as SVG by
+//! [`svg`](fn.svg.html), and whole games by [`animate`](fn.animate.html). With the `image` feature the pixels can be encoded as PNG, and
+//! the animations as GIF or APNG.
+
+#[cfg(feature = "image")]
+use std::fs::File;
+#[cfg(feature = "image")]
+use std::io::{self, BufWriter, Write};
+#[cfg(feature = "image")]
+use std::path::Path;
+use std::time::Duration;
+
+use game::{Board, Color, Coord, LegalMove};
+use record::GameRecord;
+
+// Digits of 3 by 5 pixels, a row in each 3 bits from the top.
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b010, 0b010, 0b010],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111]
+];
+
+/// A color as red, green and blue.
+pub type Rgb = [u8; 3];
+
+/// The colors of a board.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Theme {
+    pub board: Rgb,
+    pub grid: Rgb,
+    pub black: Rgb,
+    pub white: Rgb,
+    /// Marks of the legal moves.
+    pub hint: Rgb,
+    /// Marks of the last move.
+    pub mark: Rgb
+}
+impl Theme {
+    /// A green board like the ones of tournaments.
+    pub fn classic() -> Theme {
+        Theme { board: [0, 120, 60], grid: [0, 60, 30], black: [20, 20, 20], white: [240, 240, 240], hint: [0, 90, 45], mark: [220, 40, 40] }
+    }
+
+    /// Grey tones that print well.
+    pub fn print() -> Theme {
+        Theme { board: [230, 230, 230], grid: [90, 90, 90], black: [0, 0, 0], white: [255, 255, 255], hint: [170, 170, 170], mark: [128, 128, 128] }
+    }
+}
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme::classic()
+    }
+}
+
+/// How a board is drawn.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Style {
+    /// Width and height of the image in pixels, rounded down to a multiple of 8, and at least 8.
+    pub size: u32,
+    pub theme: Theme,
+    /// Marks the legal moves of a player.
+    pub hints: Option<Color>,
+    /// Marks the disk placed by a move with a dot, and the disks it flipped with smaller dots.
+    pub last_move: Option<LegalMove>
+}
+impl Default for Style {
+    fn default() -> Style {
+        Style { size: 400, theme: Theme::default(), hints: None, last_move: None }
+    }
+}
+
+/// How a game is animated.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Animation {
+    pub style: Style,
+    /// Number of frames of a flip, 0 or 1 to show the positions only.
+    pub flip_frames: u32,
+    /// Time each frame of a flip is shown.
+    pub frame_delay: Duration,
+    /// Time each position is shown.
+    pub move_delay: Duration
+}
+impl Default for Animation {
+    fn default() -> Animation {
+        Animation {
+            style: Style::default(),
+            flip_frames: 6,
+            frame_delay: Duration::from_millis(40),
+            move_delay: Duration::from_millis(800)
+        }
+    }
+}
+
+/// An image of an animation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub image: Image,
+    /// Time the frame is shown.
+    pub delay: Duration
+}
+
+/// An image as rows of RGB pixels from the top.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>
+}
+impl Image {
+    /// An image of a single color.
+    pub fn new(width: u32, height: u32, color: Rgb) -> Image {
+        Image { width, height, pixels: color.iter().cloned().cycle().take(width as usize * height as usize * 3).collect() }
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> Rgb {
+        let i = (y as usize * self.width as usize + x as usize) * 3;
+        [self.pixels[i], self.pixels[i + 1], self.pixels[i + 2]]
+    }
+
+    /// Encodes the image as PNG.
+    #[cfg(feature = "image")]
+    pub fn write_png<W: Write>(&self, writer: W) -> io::Result<()> {
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        Ok(writer.finish()?)
+    }
+
+    /// Saves the image as a PNG file.
+    #[cfg(feature = "image")]
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_png(&mut writer)?;
+        writer.flush()
+    }
+
+    // Adds a bar below the image with a number in it.
+    fn with_counter(&self, number: usize, theme: &Theme) -> Image {
+        let bar = self.width / 16;
+        let mut image = Image::new(self.width, self.height + bar, theme.board);
+        image.pixels[..self.pixels.len()].copy_from_slice(&self.pixels);
+        let text = number.to_string();
+        let scale = (bar / 7).max(1);
+        let width = (text.len() as u32 * 4 - 1) * scale;
+        let (left, top) = (self.width.saturating_sub(width) / 2, self.height + (bar.saturating_sub(5 * scale)) / 2);
+        for (i, digit) in text.bytes().enumerate() {
+            for (row, bits) in DIGITS[usize::from(digit - b'0')].iter().enumerate() {
+                for column in 0..3 {
+                    if bits & (0b100 >> column) != 0 {
+                        let x = left + (i as u32 * 4 + column) * scale;
+                        image.fill_rect(x, top + row as u32 * scale, scale, scale, theme.grid);
+                    }
+                }
+            }
+        }
+        image
+    }
+
+    fn fill_rect(&mut self, left: u32, top: u32, width: u32, height: u32, color: Rgb) {
+        for y in top..(top + height).min(self.height) {
+            for x in left..(left + width).min(self.width) {
+                let i = (y as usize * self.width as usize + x as usize) * 3;
+                self.pixels[i..i + 3].copy_from_slice(&color);
+            }
+        }
+    }
+
+    // Fills the ellipse with the given center and radii.
+    fn fill_ellipse(&mut self, center: (f32, f32), radii: (f32, f32), color: Rgb) {
+        let (cx, cy) = center;
+        let (rx, ry) = radii;
+        if rx <= 0.0 || ry <= 0.0 {
+            return;
+        }
+        let top = (cy - ry).floor().max(0.0) as u32;
+        let left = (cx - rx).floor().max(0.0) as u32;
+        for y in top..((cy + ry).ceil() as u32).min(self.height) {
+            for x in left..((cx + rx).ceil() as u32).min(self.width) {
+                let dx = (x as f32 + 0.5 - cx) / rx;
+                let dy = (y as f32 + 0.5 - cy) / ry;
+                if dx * dx + dy * dy <= 1.0 {
+                    let i = (y as usize * self.width as usize + x as usize) * 3;
+                    self.pixels[i..i + 3].copy_from_slice(&color);
+                }
+            }
+        }
+    }
+}
+
+/// Draws a board.
+pub fn render(board: &Board, style: &Style) -> Image {
+    let mut image = empty_board(style);
+    let cell = cell_size(style);
+    if let Some(color) = style.hints {
+        for legal_move in board.legal_moves(color) {
+            let center = cell_center(legal_move.position, cell);
+            image.fill_ellipse(center, (cell as f32 * 0.1, cell as f32 * 0.1), style.theme.hint);
+        }
+    }
+    for (x, column) in board.cells.iter().enumerate() {
+        for (y, &found) in column.iter().enumerate() {
+            if let Some(color) = found {
+                draw_disk(&mut image, (x as i8, y as i8), color, 1.0, style);
+            }
+        }
+    }
+    for (position, radius) in marks(style) {
+        image.fill_ellipse(cell_center(position, cell), (cell as f32 * radius, cell as f32 * radius), style.theme.mark);
+    }
+    image
+}
+
+/// Draws a board as a standalone SVG image, with the same geometry and colors as
+/// [`render`](fn.render.html).
+pub fn svg(board: &Board, style: &Style) -> String {
+    let cell = cell_size(style);
+    let size = cell * 8;
+    let line = (cell / 40).max(1);
+    let mut svg = format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{0}\" viewBox=\"0 0 {0} {0}\">\n", size);
+    svg += &format!("<rect width=\"{0}\" height=\"{0}\" fill=\"{1}\"/>\n", size, hex(style.theme.board));
+    for i in 0..=8 {
+        let offset = (i * cell).min(size - line);
+        svg += &format!("<rect x=\"{}\" y=\"0\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n", offset, line, size, hex(style.theme.grid));
+        svg += &format!("<rect x=\"0\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n", offset, size, line, hex(style.theme.grid));
+    }
+    let circle = |position: Coord, radius: f32, fill: Rgb| {
+        let (cx, cy) = cell_center(position, cell);
+        // rounded to keep the numbers short
+        let radius = (radius * 100.0).round() / 100.0;
+        format!("<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\"/>\n", cx, cy, radius, hex(fill))
+    };
+    if let Some(color) = style.hints {
+        for legal_move in board.legal_moves(color) {
+            svg += &circle(legal_move.position, cell as f32 * 0.1, style.theme.hint);
+        }
+    }
+    for (x, column) in board.cells.iter().enumerate() {
+        for (y, &found) in column.iter().enumerate() {
+            let fill = match found {
+                Some(Color::Black) => style.theme.black,
+                Some(Color::White) => style.theme.white,
+                None => continue
+            };
+            svg += &circle((x as i8, y as i8), cell as f32 * 0.4, fill);
+        }
+    }
+    for (position, radius) in marks(style) {
+        svg += &circle(position, cell as f32 * radius, style.theme.mark);
+    }
+    svg += "</svg>\n";
+    svg
+}
+
+/// Draws the frames of a game: the starting position, then for each move the disk played, its
+/// flips and the position it leads to, with the number of moves played below the board. Fails
+/// with the index of the first illegal move.
+pub fn animate(record: &GameRecord, animation: &Animation) -> Result<Vec<Frame>, usize> {
+    let positions = record.positions()?;
+    let style = &animation.style;
+    let theme = &style.theme;
+    let mut frames = vec![Frame { image: render(&positions[0].0, style).with_counter(0, theme), delay: animation.move_delay }];
+    for (i, (&position, window)) in record.moves.iter().zip(positions.windows(2)).enumerate() {
+        let (before, after) = (window[0].0, window[1].0);
+        let mut placed = before;
+        placed.cells[position.0 as usize][position.1 as usize] = after.cells[position.0 as usize][position.1 as usize];
+        let mut flips = Vec::new();
+        for x in 0..8 {
+            for y in 0..8 {
+                if let (Some(old), Some(new)) = (before.cells[x][y], after.cells[x][y]) {
+                    if old != new {
+                        placed.cells[x][y] = None;
+                        flips.push(((x as i8, y as i8), old, new));
+                    }
+                }
+            }
+        }
+        for step in 1..animation.flip_frames {
+            let progress = step as f32 / animation.flip_frames as f32;
+            let width = (progress * std::f32::consts::PI).cos();
+            let mut image = render(&placed, style);
+            for &(cell, old, new) in &flips {
+                draw_disk(&mut image, cell, if width > 0.0 { old } else { new }, width.abs(), style);
+            }
+            frames.push(Frame { image: image.with_counter(i + 1, theme), delay: animation.frame_delay });
+        }
+        frames.push(Frame { image: render(&after, style).with_counter(i + 1, theme), delay: animation.move_delay });
+    }
+    Ok(frames)
+}
+
+/// Encodes frames of the same size as an animated PNG, repeated forever.
+#[cfg(feature = "image")]
+pub fn write_apng<W: Write>(writer: W, frames: &[Frame]) -> io::Result<()> {
+    let (width, height) = frame_size(frames)?;
+    let mut encoder = png::Encoder::new(writer, width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_animated(frames.len() as u32, 0)?;
+    let mut writer = encoder.write_header()?;
+    for frame in frames {
+        writer.set_frame_delay(frame.delay.as_millis().min(u128::from(u16::MAX)) as u16, 1000)?;
+        writer.write_image_data(&frame.image.pixels)?;
+    }
+    Ok(writer.finish()?)
+}
+
+/// Encodes frames of the same size as an animated GIF, repeated forever. The frames may use at
+/// most 256 colors, which the themes do.
+#[cfg(feature = "image")]
+pub fn write_gif<W: Write>(writer: W, frames: &[Frame]) -> io::Result<()> {
+    let (width, height) = frame_size(frames)?;
+    if width > u32::from(u16::MAX) || height > u32::from(u16::MAX) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "image too large for GIF"));
+    }
+    let mut palette: Vec<Rgb> = Vec::new();
+    let mut indexed = Vec::with_capacity(frames.len());
+    for frame in frames {
+        let mut indices = Vec::with_capacity(frame.image.pixels.len() / 3);
+        for pixel in frame.image.pixels.chunks(3) {
+            let color = [pixel[0], pixel[1], pixel[2]];
+            let index = match palette.iter().position(|&x| x == color) {
+                Some(index) => index,
+                None => {
+                    palette.push(color);
+                    palette.len() - 1
+                }
+            };
+            if index > 255 {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, "more than 256 colors"));
+            }
+            indices.push(index as u8);
+        }
+        indexed.push(indices);
+    }
+    let palette = palette.concat();
+    let mut encoder = gif::Encoder::new(writer, width as u16, height as u16, &palette).map_err(io::Error::other)?;
+    encoder.set_repeat(gif::Repeat::Infinite).map_err(io::Error::other)?;
+    for (frame, indices) in frames.iter().zip(indexed) {
+        let mut encoded = gif::Frame::from_indexed_pixels(width as u16, height as u16, indices, None);
+        encoded.delay = (frame.delay.as_millis() / 10).min(u128::from(u16::MAX)) as u16;
+        encoder.write_frame(&encoded).map_err(io::Error::other)?;
+    }
+    Ok(())
+}
+
+/// Saves frames as a GIF file if the path ends with `.gif`, otherwise as an animated PNG.
+#[cfg(feature = "image")]
+pub fn save_animation<P: AsRef<Path>>(path: P, frames: &[Frame]) -> io::Result<()> {
+    let path = path.as_ref();
+    let mut writer = BufWriter::new(File::create(path)?);
+    match path.extension().and_then(|x| x.to_str()) {
+        Some(extension) if extension.eq_ignore_ascii_case("gif") => write_gif(&mut writer, frames)?,
+        _ => write_apng(&mut writer, frames)?
+    }
+    writer.flush()
+}
+
+// The size shared by the frames.
+#[cfg(feature = "image")]
+fn frame_size(frames: &[Frame]) -> io::Result<(u32, u32)> {
+    let first = frames.first().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no frames"))?;
+    let size = (first.image.width, first.image.height);
+    if frames.iter().any(|x| (x.image.width, x.image.height) != size) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "frames of different sizes"));
+    }
+    Ok(size)
+}
+
+// The width of a cell, at least a pixel.
+fn cell_size(style: &Style) -> u32 {
+    (style.size / 8).max(1)
+}
+
+// The board with its grid, without disks.
+fn empty_board(style: &Style) -> Image {
+    let cell = cell_size(style);
+    let mut image = Image::new(cell * 8, cell * 8, style.theme.board);
+    let line = (cell / 40).max(1);
+    for i in 0..=8 {
+        let offset = (i * cell).min(cell * 8 - line);
+        image.fill_rect(offset, 0, line, cell * 8, style.theme.grid);
+        image.fill_rect(0, offset, cell * 8, line, style.theme.grid);
+    }
+    image
+}
+
+// Draws a disk, squeezed horizontally to `width` times its size.
+fn draw_disk(image: &mut Image, position: Coord, color: Color, width: f32, style: &Style) {
+    let cell = image.width / 8;
+    let radius = cell as f32 * 0.4;
+    let fill = match color {
+        Color::Black => style.theme.black,
+        Color::White => style.theme.white
+    };
+    image.fill_ellipse(cell_center(position, cell), (radius * width, radius), fill);
+}
+
+// The marks of the last move, with their radius relative to a cell.
+fn marks(style: &Style) -> Vec<(Coord, f32)> {
+    match style.last_move {
+        Some(last) => {
+            let mut marks = vec![(last.position, 0.12)];
+            marks.extend(last.flipped().into_iter().map(|x| (x, 0.06)));
+            marks
+        }
+        None => Vec::new()
+    }
+}
+
+fn hex(color: Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
+
+// The center of a cell in pixels, rows numbered from the top.
+fn cell_center((x, y): Coord, cell: u32) -> (f32, f32) {
+    let row = 7 - y as u32;
+    ((x as u32 * cell) as f32 + cell as f32 / 2.0, (row * cell) as f32 + cell as f32 / 2.0)
+}
@@ -0,0 +1,66 @@
+//! This module estimates positions by Monte Carlo rollouts: games finished with random moves
+//! from the position. The share of them won by the player to move is a rough but knowledge-free
+//! estimate of its chances.
+
+use std::cell::RefCell;
+use std::cmp::Ordering;
+
+use eval::{Evaluator, Phase, Score};
+use game::{Board, Color};
+use random::Random;
+
+/// Evaluates positions by random playouts.
+pub struct Rollouts {
+    /// Number of games played per evaluation.
+    pub playouts: u32,
+    /// The score of winning every playout. Win rates in between are scaled linearly, so an even
+    /// position scores zero.
+    pub scale: Score,
+    random: RefCell<Random>
+}
+impl Rollouts {
+    pub fn new(playouts: u32, seed: u64) -> Rollouts {
+        Rollouts { playouts, scale: 1000, random: RefCell::new(Random::new(seed)) }
+    }
+
+    /// Share of the playouts won by the player to move, counting draws as half a win.
+    pub fn win_rate(&self, board: &Board, color: Color) -> f64 {
+        let mut random = self.random.borrow_mut();
+        let playouts = self.playouts.max(1);
+        let points: u32 = (0..playouts).map(|_| {
+            let final_board = play_out(board, color, &mut random);
+            match final_board.count(color).cmp(&final_board.count(color.flip())) {
+                Ordering::Greater => 2,
+                Ordering::Equal => 1,
+                Ordering::Less => 0
+            }
+        }).sum();
+        f64::from(points) / f64::from(2 * playouts)
+    }
+
+    /// The expected outcome for the player to move, from -1 for a loss to 1 for a win.
+    pub fn value(&self, board: &Board, color: Color) -> f64 {
+        2.0 * self.win_rate(board, color) - 1.0
+    }
+}
+impl Evaluator for Rollouts {
+    fn evaluate(&self, board: &Board, color: Color, _: Phase) -> Score {
+        (self.value(board, color) * f64::from(self.scale)) as Score
+    }
+}
+
+// Plays random moves until the end of the game.
+fn play_out(board: &Board, color: Color, random: &mut Random) -> Board {
+    let (mut board, mut color) = (*board, color);
+    loop {
+        let moves = board.legal_moves(color);
+        if moves.is_empty() {
+            if board.legal_moves(color.flip()).is_empty() {
+                return board;
+            }
+        } else {
+            board = moves[random.below(moves.len())].apply(board);
+        }
+        color = color.flip();
+    }
+}
@@ -0,0 +1,119 @@
+//! This module plays with bots written as [Rhai](https://rhai.rs) scripts, so that they can be
+//! tried without compiling Rust. It needs the `scripting` feature.
+//!
+//! A script defines a function `choose(board, player, moves)` called for each move, given the
+//! 64 cells of the board from a1 to h8 row by row as a string of `X` for Black, `O` for White
+//! and `-` for an empty cell, the player to move as `"X"` or `"O"`, and the legal moves as an
+//! array of maps, like `#{ cell: "f5", index: 37, flips: 1 }`, `index` being the position of the
+//! cell in `board`. It returns the cell of its move, or one of the maps given:
+//!
+//! ```text
+//! fn choose(board, player, moves) {
+//!     for move in moves {
+//!         if move.cell in ["a1", "h1", "a8", "h8"] {
+//!             return move;
+//!         }
+//!     }
+//!     moves[0]
+//! }
+//! ```
+//!
+//! What scripts print is traced rather than written out, as the standard output may be the one
+//! of a protocol.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use rhai::{Array, Dynamic, Map, AST};
+
+use engine::{parse_option, unknown_option, Engine, Limits, SearchResult};
+use files::invalid;
+use game::{Board, Color, Coord};
+use record;
+
+/// Number of operations a script may run for each move by default, which stops the ones that
+/// never return.
+pub const DEFAULT_OPERATIONS: u64 = 10_000_000;
+
+/// A bot written as a script, playing as an engine.
+///
+/// Its option is `operations`, the number of operations the script may run for each move, `0`
+/// for no limit.
+pub struct Script {
+    path: PathBuf,
+    interpreter: rhai::Engine,
+    ast: AST
+}
+impl Script {
+    /// Compiles a script file.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Script> {
+        let path = path.as_ref();
+        let mut interpreter = rhai::Engine::new();
+        interpreter.set_max_operations(DEFAULT_OPERATIONS);
+        interpreter.on_print(|_text| {
+            #[cfg(feature = "trace")]
+            tracing::debug!(text = _text, "printed by the script");
+        });
+        let ast = interpreter.compile_file(path.to_path_buf()).map_err(|e| invalid(&e.to_string()))?;
+        if !ast.iter_functions().any(|x| x.name == "choose" && x.params.len() == 3) {
+            return Err(invalid("the script should define choose(board, player, moves)"));
+        }
+        Ok(Script { path: path.to_path_buf(), interpreter, ast })
+    }
+
+    /// The path the script was loaded from.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Asks the script for its move, checking that it is legal.
+    pub fn choose(&self, board: &Board, color: Color) -> io::Result<Coord> {
+        let position = record::format_position(board, color);
+        let (cells, player) = position.split_at(64);
+        let moves = board.legal_moves(color).iter().map(|legal_move| {
+            let (x, y) = legal_move.position;
+            let mut map = Map::new();
+            map.insert("cell".into(), record::format_move(legal_move.position).into());
+            map.insert("index".into(), Dynamic::from_int(i64::from((7 - y) * 8 + x)));
+            map.insert("flips".into(), Dynamic::from_int(legal_move.flips.iter().map(|&x| i64::from(x)).sum()));
+            Dynamic::from_map(map)
+        }).collect::<Array>();
+        let args = (cells.to_string(), player.trim().to_string(), moves);
+        let answer: Dynamic = self.interpreter.call_fn(&mut rhai::Scope::new(), &self.ast, "choose", args).map_err(|e| invalid(&e.to_string()))?;
+        let cell = match answer.clone().try_cast::<Map>() {
+            Some(map) => map.get("cell").and_then(|x| x.clone().into_string().ok()),
+            None => answer.into_string().ok()
+        };
+        let cell = cell.ok_or_else(|| invalid("the script should return a cell or a move"))?;
+        let position = record::parse_move(cell.trim()).ok_or_else(|| invalid(&format!("invalid move: {}", cell)))?;
+        if board.test_move(color, position).is_err() {
+            return Err(invalid(&format!("illegal move: {}", cell)));
+        }
+        Ok(position)
+    }
+}
+impl Engine for Script {
+    /// Plays the move of the script. A script failing, or choosing an illegal move, plays no
+    /// move, which forfeits games.
+    #[cfg_attr(feature = "trace", tracing::instrument(name = "script", skip_all, fields(%color, path = %self.path.display())))]
+    fn best_move(&mut self, board: &Board, color: Color, _: &Limits) -> SearchResult {
+        let start = Instant::now();
+        let answer = self.choose(board, color);
+        #[cfg(feature = "trace")]
+        if let Err(ref error) = answer {
+            tracing::warn!(%error, "no move from the script");
+        }
+        SearchResult::new(0, 0, 0, 0, answer.into_iter().collect(), start.elapsed())
+    }
+
+    fn set_option(&mut self, name: &str, value: &str) -> io::Result<()> {
+        match name {
+            "operations" => {
+                self.interpreter.set_max_operations(parse_option(name, value)?);
+            }
+            _ => return Err(unknown_option(name))
+        }
+        Ok(())
+    }
+}
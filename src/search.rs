@@ -0,0 +1,696 @@
+//! This module implements the game tree search engines.
+//!
+//! The engines use the negamax formulation: scores are always from the point of view of the
+//! player to move, and the score of a position is the negation of the best score of the opponent
+//! after any move.
+//!
+//! The searches are available as functions, and as [engines](../engine/trait.Engine.html)
+//! keeping their settings: [`Minimax`](struct.Minimax.html) and
+//! [`AlphaBeta`](struct.AlphaBeta.html). The search of `AlphaBeta` can also run in small steps
+//! with an [`IncrementalSearch`](struct.IncrementalSearch.html), for single-threaded hosts like
+//! the browser.
+
+use std::io;
+use std::mem;
+use std::str::FromStr;
+use std::time::Duration;
+
+use endgame;
+use engine::{parse_option, Engine, Iteration, Limits, MemoryUsage, SearchResult, SearchStats};
+use eval::{Evaluator, Phase, Score};
+use game::{Board, Color, Coord, LegalMove};
+use time::Instant;
+use tree::{Outcome, SearchTree};
+use tt::{Bound, Replacement, TranspositionTable};
+use zobrist;
+
+/// Score of a won game, before adding the final disk differential.
+pub const WIN: Score = 1_000_000;
+
+/// Scores a finished game for `color`, so that any win is better than any heuristic value.
+pub fn final_score(board: &Board, color: Color) -> Score {
+    let diff = board.count(color) as Score - board.count(color.flip()) as Score;
+    diff.signum() * WIN + diff
+}
+
+/// Searches the full game tree up to `depth` moves and returns the best move with its score.
+///
+/// There is no move to return when the player has to skip or the game has ended.
+pub fn minimax<E: Evaluator>(board: &Board, color: Color, depth: u32, evaluator: &E) -> SearchResult {
+    // returns the score with the principal variation
+    fn negamax<E: Evaluator>(board: &mut Board, color: Color, depth: u32, evaluator: &E, nodes: &mut u64) -> (Score, Vec<Coord>) {
+        *nodes += 1;
+        if depth == 0 {
+            return (evaluator.evaluate(board, color, Phase::of(board)), Vec::new());
+        }
+        let moves = board.legal_moves(color);
+        if moves.is_empty() {
+            return if board.legal_moves(color.flip()).is_empty() {
+                (final_score(board, color), Vec::new())
+            } else {
+                // skipping does not use up depth, as two skips in a row end the game
+                let (score, pv) = negamax(board, color.flip(), depth, evaluator, nodes);
+                (-score, pv)
+            };
+        }
+        let mut best = (-Score::MAX, Vec::new());
+        for legal_move in moves {
+            let (score, pv) = search_move(board, &legal_move, evaluator, |board| negamax(board, color.flip(), depth - 1, evaluator, nodes));
+            if -score > best.0 {
+                best = (-score, prepend(legal_move.position, pv));
+            }
+        }
+        best
+    }
+    let start = Instant::now();
+    let mut nodes = 0;
+    let (score, pv) = negamax(&mut { *board }, color, depth, evaluator, &mut nodes);
+    SearchResult::new(score, depth, nodes, 0, pv, start.elapsed())
+}
+
+/// Same as [`minimax`](fn.minimax.html), but skips the subtrees that cannot affect the result.
+pub fn alpha_beta<E: Evaluator>(board: &Board, color: Color, depth: u32, evaluator: &E) -> SearchResult {
+    alpha_beta_with_options(board, color, depth, evaluator, &mut Options::default())
+}
+
+/// Same as [`alpha_beta`](fn.alpha_beta.html), but stores the results of the positions searched
+/// in a transposition table, and reuses them when a position is reached again, in this search or
+/// in later ones. The stored best moves are searched first, which makes the search prune more.
+pub fn alpha_beta_with_table<E: Evaluator>(board: &Board, color: Color, depth: u32, evaluator: &E, table: &mut TranspositionTable) -> SearchResult {
+    alpha_beta_with_options(board, color, depth, evaluator, &mut Options { table: Some(table), ..Options::default() })
+}
+
+/// How positions near the end of the game are scored.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EndgameMode {
+    /// Finds the exact final disk differential.
+    Exact,
+    /// Only finds whether the game is won, lost or drawn, which is faster.
+    WinLossDraw
+}
+
+impl FromStr for EndgameMode {
+    type Err = ();
+
+    /// Parses `exact` or `wld`.
+    fn from_str(text: &str) -> Result<EndgameMode, ()> {
+        match text {
+            "exact" => Ok(EndgameMode::Exact),
+            "wld" => Ok(EndgameMode::WinLossDraw),
+            _ => Err(())
+        }
+    }
+}
+
+/// Solves positions with few empty cells exactly instead of evaluating them.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Endgame {
+    /// Positions with at most this many empty cells are solved.
+    pub empties: usize,
+    pub mode: EndgameMode
+}
+impl Default for Endgame {
+    fn default() -> Endgame {
+        Endgame { empties: 12, mode: EndgameMode::Exact }
+    }
+}
+impl Endgame {
+    // Solves a position, scoring it like a finished game.
+    fn solve(&self, board: &Board, color: Color) -> (Option<Coord>, Score) {
+        match self.mode {
+            EndgameMode::Exact => {
+                let solution = endgame::solve(board, color);
+                let diff = Score::from(solution.score);
+                (solution.best_move, diff.signum() * WIN + diff)
+            }
+            EndgameMode::WinLossDraw => {
+                let solution = endgame::solve_win_loss_draw(board, color);
+                (solution.best_move, Score::from(solution.score) * WIN)
+            }
+        }
+    }
+}
+
+/// Optional features of [`alpha_beta_with_options`](fn.alpha_beta_with_options.html).
+#[derive(Default)]
+pub struct Options<'a> {
+    /// Stores and reuses the results of positions, see
+    /// [`alpha_beta_with_table`](fn.alpha_beta_with_table.html).
+    pub table: Option<&'a mut TranspositionTable>,
+    /// Searches to the end of the game once few cells are empty.
+    pub endgame: Option<Endgame>,
+    /// How much the searching player dislikes draws. A positive contempt makes an engine
+    /// expecting to outplay its opponent avoid drawn lines, a negative one makes a weaker engine
+    /// accept them. It only changes the scores of finished games, a draw being worth `-contempt`
+    /// to the searching player and `contempt` to the opponent.
+    pub contempt: Score,
+    /// Records the nodes searched, to see how the search pruned.
+    pub tree: Option<&'a mut SearchTree>
+}
+
+/// Same as [`alpha_beta`](fn.alpha_beta.html), with the optional features enabled in the options.
+pub fn alpha_beta_with_options<E: Evaluator>(board: &Board, color: Color, depth: u32, evaluator: &E, options: &mut Options) -> SearchResult {
+    let start = Instant::now();
+    if let Some(ref mut table) = options.table {
+        table.new_search();
+    }
+    let mut context = Context { evaluator, options, root: color, depth, nodes: 0, stats: SearchStats::default() };
+    let (score, pv) = alpha_beta_negamax(&mut { *board }, color, depth, -Score::MAX, Score::MAX, &mut context);
+    let time = start.elapsed();
+    let mut result = SearchResult::new(score, depth, context.nodes, context.stats.tt_hits, pv, time);
+    result.stats = context.stats;
+    result.stats.iterations.push(Iteration { depth, nodes: context.nodes, time });
+    result
+}
+
+// Same as `alpha_beta_with_options`, given up at the deadline. Fails with the number of
+// positions searched until then.
+fn alpha_beta_until<E: Evaluator>(board: &Board, color: Color, depth: u32, evaluator: &E, options: &mut Options, deadline: Instant) -> Result<SearchResult, u64> {
+    let start = Instant::now();
+    if let Some(ref mut table) = options.table {
+        table.new_search();
+    }
+    let mut context = Context { evaluator, options, root: color, depth, nodes: 0, stats: SearchStats::default() };
+    let mut board = *board;
+    let found = enter(&board, color, depth, -Score::MAX, Score::MAX, &mut context).or_else(|root| {
+        resume(&mut board, &mut vec![root], &mut Vec::new(), &mut context, deadline).ok_or(())
+    });
+    let (score, pv) = found.map_err(|_| context.nodes)?;
+    let time = start.elapsed();
+    let mut result = SearchResult::new(score, depth, context.nodes, context.stats.tt_hits, pv, time);
+    result.stats = context.stats;
+    result.stats.iterations.push(Iteration { depth, nodes: context.nodes, time });
+    Ok(result)
+}
+
+// What stays the same during an alpha-beta search.
+struct Context<'a, 'b: 'a, E: Evaluator + 'a> {
+    evaluator: &'a E,
+    options: &'a mut Options<'b>,
+    /// The searching player, and the depth of the search from the root.
+    root: Color,
+    depth: u32,
+    nodes: u64,
+    stats: SearchStats
+}
+impl<'a, 'b, E: Evaluator> Context<'a, 'b, E> {
+    // Ends a node recorded in the tree.
+    fn close(&mut self, recorded: Option<usize>, score: Score, outcome: Outcome) {
+        if let (Some(index), Some(tree)) = (recorded, self.options.tree.as_mut()) {
+            tree.close(index, score, outcome);
+        }
+    }
+
+    // Applies contempt to the score of a finished game.
+    fn finished(&self, color: Color, score: Score) -> Score {
+        match score {
+            0 if color == self.root => -self.options.contempt,
+            0 => self.options.contempt,
+            _ => score
+        }
+    }
+
+    // The key of a position in the transposition table. With contempt, draws are scored for the
+    // searching player, so the scores of a position searched by one player don't hold for the
+    // other.
+    fn key(&self, board: &Board, color: Color) -> u64 {
+        let key = zobrist::hash(board, color);
+        if self.options.contempt != 0 && self.root == Color::White { key ^ WHITE_SEARCHING } else { key }
+    }
+}
+
+// Key of White being the searching player, for the scores depending on it.
+const WHITE_SEARCHING: u64 = 0x3c6e_f372_fe94_f82b;
+
+// Returns the score with the principal variation. The score is exact only if it falls within
+// (alpha, beta), otherwise it is a bound.
+fn alpha_beta_negamax<E: Evaluator>(board: &mut Board, color: Color, depth: u32, alpha: Score, beta: Score, context: &mut Context<E>) -> (Score, Vec<Coord>) {
+    let recorded = context.options.tree.as_mut().and_then(|tree| tree.open(color, depth, alpha, beta));
+    let mut node = match enter(board, color, depth, alpha, beta, context) {
+        Ok(found) => {
+            context.close(recorded, found.0, Outcome::Leaf);
+            return found;
+        }
+        Err(node) => node
+    };
+    while let Some(legal_move) = node.next_move() {
+        let (color, depth, alpha, beta) = node.child();
+        let evaluator = context.evaluator;
+        if let Some(ref mut tree) = context.options.tree {
+            tree.moving(legal_move.position);
+        }
+        let found = search_move(board, &legal_move, evaluator, |board| alpha_beta_negamax(board, color, depth, alpha, beta, context));
+        node.update(legal_move.position, found);
+    }
+    let skipped = node.moves.len().saturating_sub(node.next);
+    let found = node.finish(context);
+    context.close(recorded, found.0, if skipped > 0 { Outcome::Cutoff(skipped) } else { Outcome::Searched });
+    found
+}
+
+// Starts searching a position: returns its result if it is known without searching its moves,
+// otherwise the node to search them from. A player without moves passes.
+fn enter<E: Evaluator>(board: &Board, mut color: Color, depth: u32, mut alpha: Score, mut beta: Score, context: &mut Context<E>) -> Result<(Score, Vec<Coord>), Node> {
+    let mut negate = false;
+    let ply = (context.depth - depth) as usize;
+    let found = loop {
+        context.nodes += 1;
+        if context.stats.nodes_by_ply.len() <= ply {
+            context.stats.nodes_by_ply.resize(ply + 1, 0);
+        }
+        context.stats.nodes_by_ply[ply] += 1;
+        if let Some(endgame) = context.options.endgame {
+            if board.empties() <= endgame.empties {
+                let (best_move, score) = endgame.solve(board, color);
+                break (context.finished(color, score), best_move.into_iter().collect());
+            }
+        }
+        if depth == 0 {
+            break (context.evaluator.evaluate(board, color, Phase::of(board)), Vec::new());
+        }
+        let mut moves = board.legal_moves(color);
+        if moves.is_empty() {
+            if board.legal_moves(color.flip()).is_empty() {
+                break (context.finished(color, final_score(board, color)), Vec::new());
+            }
+            color = color.flip();
+            (alpha, beta) = (-beta, -alpha);
+            negate = !negate;
+            continue;
+        }
+        let key = context.options.table.as_ref().map(|_| context.key(board, color));
+        context.stats.tt_probes += key.is_some() as u64;
+        if let Some(entry) = key.and_then(|key| context.options.table.as_ref().and_then(|table| table.probe(key))) {
+            context.stats.tt_hits += 1;
+            let cutoff = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => entry.score >= beta,
+                Bound::Upper => entry.score <= alpha
+            };
+            if u32::from(entry.depth) >= depth && cutoff {
+                break (entry.score, entry.best_move.into_iter().collect());
+            }
+            if let Some(index) = moves.iter().position(|x| Some(x.position) == entry.best_move) {
+                moves.swap(0, index);
+            }
+        }
+        return Err(Node { color, depth, alpha, beta, original_alpha: alpha, moves, next: 0, best: (-Score::MAX, Vec::new()), key, negate });
+    };
+    Ok(if negate { (-found.0, found.1) } else { found })
+}
+
+// A position whose moves are being searched.
+struct Node {
+    color: Color,
+    depth: u32,
+    alpha: Score,
+    beta: Score,
+    original_alpha: Score,
+    moves: Vec<LegalMove>,
+    /// The index of the next move to search.
+    next: usize,
+    best: (Score, Vec<Coord>),
+    key: Option<u64>,
+    /// Whether the node was reached by a pass, so its result is negated for the parent.
+    negate: bool
+}
+impl Node {
+    // The next move to search, none once all are searched or one is good enough to cut off.
+    fn next_move(&mut self) -> Option<LegalMove> {
+        if self.next > 0 && self.alpha >= self.beta {
+            return None;
+        }
+        let legal_move = self.moves.get(self.next).cloned();
+        self.next += 1;
+        legal_move
+    }
+
+    // The player, depth and window to search the positions after the moves with.
+    fn child(&self) -> (Color, u32, Score, Score) {
+        (self.color.flip(), self.depth - 1, -self.beta, -self.alpha)
+    }
+
+    // Takes the result of the position after a move.
+    fn update(&mut self, position: Coord, (score, pv): (Score, Vec<Coord>)) {
+        let score = -score;
+        if score > self.best.0 {
+            self.best = (score, prepend(position, pv));
+        }
+        if score > self.alpha {
+            self.alpha = score;
+        }
+    }
+
+    // Stores the result in the transposition table, counts the cutoff if there was one and
+    // returns the result for the parent.
+    fn finish<E: Evaluator>(self, context: &mut Context<E>) -> (Score, Vec<Coord>) {
+        context.stats.expanded += 1;
+        if self.best.0 >= self.beta {
+            // the move cutting off is the last one taken
+            let index = self.next - 1;
+            if context.stats.cutoffs_by_move.len() <= index {
+                context.stats.cutoffs_by_move.resize(index + 1, 0);
+            }
+            context.stats.cutoffs_by_move[index] += 1;
+        }
+        if let (Some(key), Some(table)) = (self.key, context.options.table.as_mut()) {
+            let bound = if self.best.0 <= self.original_alpha {
+                Bound::Upper
+            } else if self.best.0 >= self.beta {
+                Bound::Lower
+            } else {
+                Bound::Exact
+            };
+            table.store(key, self.best.1.first().cloned(), self.best.0, self.depth, bound);
+        }
+        if self.negate { (-self.best.0, self.best.1) } else { self.best }
+    }
+}
+
+/// The [`minimax`](fn.minimax.html) search as an engine.
+///
+/// Its option is `depth`, besides the options of its evaluator.
+pub struct Minimax<E: Evaluator> {
+    pub depth: u32,
+    pub evaluator: E
+}
+impl<E: Evaluator> Engine for Minimax<E> {
+    #[cfg_attr(feature = "trace", tracing::instrument(name = "minimax", skip_all, fields(%color, depth = limits.depth.unwrap_or(self.depth))))]
+    fn best_move(&mut self, board: &Board, color: Color, limits: &Limits) -> SearchResult {
+        minimax(board, color, limits.depth.unwrap_or(self.depth), &self.evaluator)
+    }
+
+    fn set_option(&mut self, name: &str, value: &str) -> io::Result<()> {
+        match name {
+            "depth" => self.depth = parse_option(name, value)?,
+            _ => return self.evaluator.set_option(name, value)
+        }
+        Ok(())
+    }
+
+    fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage { weights: self.evaluator.memory_usage(), ..MemoryUsage::default() }
+    }
+}
+
+/// The [`alpha_beta`](fn.alpha_beta.html) search as an engine, with its optional features.
+///
+/// The search deepens iteratively, so with a time limit it returns the result of the deepest
+/// search finished in time, after at least one. The iterations after the first are given up once
+/// the time is over, the clock being looked at every few hundred positions.
+///
+/// Its options are `depth`, `hash` (the size of the transposition table in MiB, 0 disables it),
+/// `endgame` (the number of empty cells below which positions are solved, 0 disables solving),
+/// `endgame_mode` (`exact` or `wld`) and `contempt`, besides the options of its evaluator.
+pub struct AlphaBeta<E: Evaluator> {
+    pub depth: u32,
+    pub evaluator: E,
+    pub table: Option<TranspositionTable>,
+    pub endgame: Option<Endgame>,
+    pub contempt: Score
+}
+impl<E: Evaluator> AlphaBeta<E> {
+    /// A plain search without the optional features.
+    pub fn new(depth: u32, evaluator: E) -> AlphaBeta<E> {
+        AlphaBeta { depth, evaluator, table: None, endgame: None, contempt: 0 }
+    }
+}
+impl<E: Evaluator> Engine for AlphaBeta<E> {
+    #[cfg_attr(feature = "trace", tracing::instrument(name = "alpha_beta", skip_all, fields(%color, ?limits)))]
+    fn best_move(&mut self, board: &Board, color: Color, limits: &Limits) -> SearchResult {
+        let start = Instant::now();
+        let depth = limits.depth.unwrap_or(self.depth).max(1);
+        // without a time limit, only the last iteration matters
+        let first = if limits.time.is_some() { 1 } else { depth };
+        let (mut nodes, mut tt_hits) = (0, 0);
+        let mut stats = SearchStats::default();
+        let mut result = None;
+        let deadline = limits.time.map(|x| start + x);
+        for iteration in first..=depth {
+            let mut options = Options { table: self.table.as_mut(), endgame: self.endgame, contempt: self.contempt, tree: None };
+            let found = match deadline {
+                // the first iteration is always finished, so that there is a move to play
+                Some(deadline) if result.is_some() => match alpha_beta_until(board, color, iteration, &self.evaluator, &mut options, deadline) {
+                    Ok(found) => found,
+                    Err(given_up) => {
+                        nodes += given_up;
+                        break;
+                    }
+                },
+                _ => alpha_beta_with_options(board, color, iteration, &self.evaluator, &mut options)
+            };
+            nodes += found.nodes;
+            tt_hits += found.tt_hits;
+            stats.add(&found.stats);
+            #[cfg(feature = "trace")]
+            tracing::debug!(depth = iteration, score = found.score, nodes = found.nodes, elapsed = ?start.elapsed(), "iteration finished");
+            result = Some(found);
+            if limits.time.map(|x| start.elapsed() >= x).unwrap_or(false) {
+                break;
+            }
+        }
+        let result = result.expect("at least one iteration is searched");
+        // the first iteration is not interrupted, so it may run past the time limit
+        #[cfg(feature = "trace")]
+        match limits.time {
+            Some(time) if start.elapsed() > time => tracing::warn!(limit = ?time, elapsed = ?start.elapsed(), "time limit exceeded"),
+            _ => {}
+        }
+        SearchResult { nodes, tt_hits, time: start.elapsed(), stats, ..result }
+    }
+
+    fn set_option(&mut self, name: &str, value: &str) -> io::Result<()> {
+        match name {
+            "depth" => self.depth = parse_option(name, value)?,
+            "hash" => {
+                let mib: usize = parse_option(name, value)?;
+                match self.table {
+                    _ if mib == 0 => self.table = None,
+                    Some(ref mut table) => table.resize(mib)?,
+                    None => self.table = Some(TranspositionTable::reserved(mib, Replacement::DepthPreferred)?)
+                }
+            }
+            "endgame" => {
+                let empties: usize = parse_option(name, value)?;
+                let mode = self.endgame.map(|x| x.mode).unwrap_or(EndgameMode::Exact);
+                self.endgame = if empties == 0 { None } else { Some(Endgame { empties, mode }) };
+            }
+            "endgame_mode" => {
+                let mode = parse_option(name, value)?;
+                let empties = self.endgame.map(|x| x.empties).unwrap_or(Endgame::default().empties);
+                self.endgame = Some(Endgame { empties, mode });
+            }
+            "contempt" => self.contempt = parse_option(name, value)?,
+            _ => return self.evaluator.set_option(name, value)
+        }
+        Ok(())
+    }
+
+    fn memory_usage(&self) -> MemoryUsage {
+        let table = self.table.as_ref().map(TranspositionTable::memory_usage).unwrap_or(0);
+        MemoryUsage { table, weights: self.evaluator.memory_usage(), ..MemoryUsage::default() }
+    }
+}
+
+/// The outcome of a [step](struct.IncrementalSearch.html#method.step) of an incremental search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    /// The search needs more steps.
+    Pending,
+    Done(SearchResult)
+}
+
+/// The search of an [`AlphaBeta`](struct.AlphaBeta.html) engine, run in steps of a few
+/// milliseconds instead of all at once, so that it can share a thread with other work, like the
+/// event loop of a browser. It finds the same results as
+/// [`best_move`](trait.Engine.html#tymethod.best_move).
+///
+/// The search keeps the engine while it runs, and gives it back with
+/// [`into_engine`](#method.into_engine). Its time limit only counts the time spent in steps.
+pub struct IncrementalSearch<E: Evaluator> {
+    engine: AlphaBeta<E>,
+    /// The searched position, with the moves of the line being searched made.
+    board: Board,
+    color: Color,
+    depth: u32,
+    time: Option<Duration>,
+    /// The depth of the iteration being searched, or searched next.
+    iteration: u32,
+    /// The positions whose moves are being searched, from the root, and the moves between them.
+    stack: Vec<Node>,
+    line: Vec<LegalMove>,
+    /// When the iteration started, in time spent.
+    iteration_start: Duration,
+    iteration_nodes: u64,
+    iteration_stats: SearchStats,
+    nodes: u64,
+    stats: SearchStats,
+    spent: Duration,
+    /// The deepest iteration finished.
+    result: Option<SearchResult>,
+    done: bool
+}
+impl<E: Evaluator> IncrementalSearch<E> {
+    /// Prepares the search of a position within the limits, without searching yet.
+    pub fn new(engine: AlphaBeta<E>, board: &Board, color: Color, limits: &Limits) -> IncrementalSearch<E> {
+        let depth = limits.depth.unwrap_or(engine.depth).max(1);
+        // without a time limit, only the last iteration matters
+        let iteration = if limits.time.is_some() { 1 } else { depth };
+        IncrementalSearch {
+            engine,
+            board: *board,
+            color,
+            depth,
+            time: limits.time,
+            iteration,
+            stack: Vec::new(),
+            line: Vec::new(),
+            iteration_start: Duration::from_secs(0),
+            iteration_nodes: 0,
+            iteration_stats: SearchStats::default(),
+            nodes: 0,
+            stats: SearchStats::default(),
+            spent: Duration::from_secs(0),
+            result: None,
+            done: false
+        }
+    }
+
+    /// Searches for about `budget`, and returns the result once the search is over. Like the
+    /// engine, the search deepens up to its depth or, with a time limit, until an iteration ends
+    /// past the limit.
+    ///
+    /// A step searches a few hundred positions even without a budget, so the search always ends.
+    pub fn step(&mut self, budget: Duration) -> Step {
+        let start = Instant::now();
+        let deadline = start + budget;
+        let searching = !self.done;
+        while !self.done {
+            let (board, stack, line) = (&mut self.board, &mut self.stack, &mut self.line);
+            let mut options = Options { table: self.engine.table.as_mut(), endgame: self.engine.endgame, contempt: self.engine.contempt, tree: None };
+            let mut context = Context { evaluator: &self.engine.evaluator, options: &mut options, root: self.color, depth: self.iteration, nodes: 0, stats: SearchStats::default() };
+            let found = if stack.is_empty() {
+                self.iteration_start = self.spent + start.elapsed();
+                if let Some(ref mut table) = context.options.table {
+                    table.new_search();
+                }
+                enter(board, self.color, self.iteration, -Score::MAX, Score::MAX, &mut context).or_else(|root| {
+                    stack.push(root);
+                    resume(board, stack, line, &mut context, deadline).ok_or(())
+                })
+            } else {
+                resume(board, stack, line, &mut context, deadline).ok_or(())
+            };
+            self.nodes += context.nodes;
+            self.iteration_nodes += context.nodes;
+            self.iteration_stats.add(&context.stats);
+            let (score, pv) = match found {
+                Ok(found) => found,
+                Err(()) => break
+            };
+            let spent = self.spent + start.elapsed();
+            let time = spent - self.iteration_start;
+            self.iteration_stats.iterations.push(Iteration { depth: self.iteration, nodes: self.iteration_nodes, time });
+            let mut found = SearchResult::new(score, self.iteration, self.iteration_nodes, self.iteration_stats.tt_hits, pv, time);
+            found.stats = mem::take(&mut self.iteration_stats);
+            self.stats.add(&found.stats);
+            #[cfg(feature = "trace")]
+            tracing::debug!(depth = self.iteration, score = found.score, nodes = found.nodes, elapsed = ?spent, "iteration finished");
+            self.result = Some(found);
+            self.iteration_nodes = 0;
+            self.done = self.iteration == self.depth || self.time.map(|x| spent >= x).unwrap_or(false);
+            self.iteration += 1;
+            if Instant::now() >= deadline {
+                break;
+            }
+        }
+        if searching {
+            self.spent += start.elapsed();
+        }
+        match self.result {
+            Some(ref result) if self.done => Step::Done(SearchResult { nodes: self.nodes, tt_hits: self.stats.tt_hits, time: self.spent, stats: self.stats.clone(), ..result.clone() }),
+            _ => Step::Pending
+        }
+    }
+
+    /// The result of the deepest iteration finished so far.
+    pub fn result(&self) -> Option<&SearchResult> {
+        self.result.as_ref()
+    }
+
+    /// Stops the search, finished or not, and gives the engine back.
+    pub fn into_engine(mut self) -> AlphaBeta<E> {
+        while let Some(legal_move) = self.line.pop() {
+            unmake(&mut self.board, &legal_move, &self.engine.evaluator);
+        }
+        self.engine
+    }
+}
+
+// How many positions are searched between looks at the clock.
+const CLOCK_INTERVAL: u64 = 256;
+
+// Searches the nodes of the stack, deepest first, until the root is finished. Returns `None` at
+// the deadline, leaving the stack and the line to resume from.
+fn resume<E: Evaluator>(board: &mut Board, stack: &mut Vec<Node>, line: &mut Vec<LegalMove>, context: &mut Context<E>, deadline: Instant) -> Option<(Score, Vec<Coord>)> {
+    let mut clock = context.nodes;
+    loop {
+        if context.nodes >= clock + CLOCK_INTERVAL {
+            if Instant::now() >= deadline {
+                return None;
+            }
+            clock = context.nodes;
+        }
+        let node = stack.last_mut().expect("the root stays on the stack until it is finished");
+        match node.next_move() {
+            Some(legal_move) => {
+                let (color, depth, alpha, beta) = node.child();
+                make(board, &legal_move, context.evaluator);
+                match enter(board, color, depth, alpha, beta, context) {
+                    Ok(found) => {
+                        unmake(board, &legal_move, context.evaluator);
+                        node.update(legal_move.position, found);
+                    }
+                    Err(child) => {
+                        line.push(legal_move);
+                        stack.push(child);
+                    }
+                }
+            }
+            None => {
+                let found = stack.pop().expect("the node was on the stack").finish(context);
+                match stack.last_mut() {
+                    Some(parent) => {
+                        let legal_move = line.pop().expect("a move leads to every node but the root");
+                        unmake(board, &legal_move, context.evaluator);
+                        parent.update(legal_move.position, found);
+                    }
+                    None => return Some(found)
+                }
+            }
+        }
+    }
+}
+
+// Makes a move on the board, searches the resulting position and takes the move back.
+fn search_move<E: Evaluator, T, F: FnOnce(&mut Board) -> T>(board: &mut Board, legal_move: &LegalMove, evaluator: &E, search: F) -> T {
+    make(board, legal_move, evaluator);
+    let result = search(board);
+    unmake(board, legal_move, evaluator);
+    result
+}
+
+fn make<E: Evaluator>(board: &mut Board, legal_move: &LegalMove, evaluator: &E) {
+    evaluator.make(board, legal_move);
+    board.make(legal_move);
+}
+
+fn unmake<E: Evaluator>(board: &mut Board, legal_move: &LegalMove, evaluator: &E) {
+    board.unmake(legal_move);
+    evaluator.unmake(board, legal_move);
+}
+
+fn prepend(position: Coord, mut line: Vec<Coord>) -> Vec<Coord> {
+    line.insert(0, position);
+    line
+}
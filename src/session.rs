@@ -0,0 +1,441 @@
+//! This module follows a game being played: who plays each side, the moves with the time spent
+//! on them and the clocks, so that it can be saved, resumed and turned into a
+//! [record](../record/struct.GameRecord.html).
+//!
+//! Sessions are saved in a compact binary format, see [`GameSession::write`](struct.GameSession.html#method.write).
+//! Engines are saved by reference, their name and options, as their internal state, like the
+//! transposition table, is only a cache.
+//!
+//! A session can also report its events as they happen to an [`EventLog`](struct.EventLog.html),
+//! so that dashboards and log pipelines can follow games in real time.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use files::{self, invalid, read_bytes, read_string, read_u16, write_string};
+use formats::json::Value;
+use game::{Board, Color, Coord, LegalMove};
+use record::{self, GameRecord};
+
+const MAGIC: &[u8; 6] = b"RRSESS";
+const VERSION: u16 = 1;
+
+/// Who plays a side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Player {
+    Human(String),
+    /// An engine by name, with the options set on it.
+    Engine { name: String, options: Vec<(String, String)> }
+}
+impl Player {
+    pub fn name(&self) -> &str {
+        match *self {
+            Player::Human(ref name) => name,
+            Player::Engine { ref name, .. } => name
+        }
+    }
+}
+
+/// The clock of a player.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Clock {
+    /// Time left.
+    pub remaining: Duration,
+    /// Time added after each move.
+    pub increment: Duration
+}
+
+/// A move of the session.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Turn {
+    pub color: Color,
+    pub position: Coord,
+    /// Time spent on the move.
+    pub time: Duration
+}
+
+/// Something that happened in a session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameEvent {
+    /// The session started reporting to a log.
+    Started { black: String, white: String },
+    Moved { color: Color, position: Coord, time: Duration },
+    /// A player had no legal move.
+    Skipped { color: Color },
+    /// A move was taken back.
+    Undone { color: Color, position: Coord },
+    /// Neither player can move anymore.
+    Ended { black_disks: u8, white_disks: u8 }
+}
+
+/// Writes events as NDJSON, a JSON object per line, like
+/// `{"time":1700000000000,"game":"g1","event":"moved","color":"black","cell":"f5","time_ms":1200}`.
+///
+/// Each line has the milliseconds since the Unix epoch, the id of the game and the kind of event:
+/// `started` with the names of `black` and `white`, `moved` with the `color`, the `cell` and the
+/// `time_ms` spent, `skipped` with the `color`, `undone` with the `color` and the `cell`, or
+/// `ended` with `black_disks` and `white_disks`. Lines are flushed as they are written.
+///
+/// Clones share the writer, so several games can be logged to the same stream. A failed write
+/// doesn't stop the game: the log stops writing and keeps the error for
+/// [`check`](#method.check).
+#[derive(Clone)]
+pub struct EventLog {
+    game: String,
+    writer: Arc<Mutex<LogWriter>>
+}
+impl EventLog {
+    pub fn new<W: Write + Send + 'static>(writer: W, game: &str) -> EventLog {
+        EventLog { game: game.to_string(), writer: Arc::new(Mutex::new(LogWriter { writer: Box::new(writer), error: None })) }
+    }
+
+    /// The id of the game.
+    pub fn game(&self) -> &str {
+        &self.game
+    }
+
+    /// The same writer for another game.
+    pub fn with_game(&self, game: &str) -> EventLog {
+        EventLog { game: game.to_string(), writer: self.writer.clone() }
+    }
+
+    /// Writes an event as a line.
+    pub fn log(&self, event: &GameEvent) {
+        let time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        let (name, fields) = match *event {
+            GameEvent::Started { ref black, ref white } => {
+                ("started", vec![("black", Value::String(black.clone())), ("white", Value::String(white.clone()))])
+            }
+            GameEvent::Moved { color, position, time } => {
+                ("moved", vec![("color", color_value(color)), ("cell", cell_value(position)), ("time_ms", millis(time))])
+            }
+            GameEvent::Skipped { color } => ("skipped", vec![("color", color_value(color))]),
+            GameEvent::Undone { color, position } => ("undone", vec![("color", color_value(color)), ("cell", cell_value(position))]),
+            GameEvent::Ended { black_disks, white_disks } => {
+                ("ended", vec![("black_disks", Value::Number(f64::from(black_disks))), ("white_disks", Value::Number(f64::from(white_disks)))])
+            }
+        };
+        let mut members = vec![
+            ("time".to_string(), millis(time)),
+            ("game".to_string(), Value::String(self.game.clone())),
+            ("event".to_string(), Value::String(name.to_string()))
+        ];
+        members.extend(fields.into_iter().map(|(key, value)| (key.to_string(), value)));
+        let line = format!("{}\n", Value::Object(members));
+        let mut log = self.writer.lock().unwrap_or_else(|x| x.into_inner());
+        if log.error.is_none() {
+            if let Err(error) = log.writer.write_all(line.as_bytes()).and_then(|_| log.writer.flush()) {
+                log.error = Some(error);
+            }
+        }
+    }
+
+    /// Fails with the first error of the writer, if any.
+    pub fn check(&self) -> io::Result<()> {
+        let log = self.writer.lock().unwrap_or_else(|x| x.into_inner());
+        match log.error {
+            Some(ref error) => Err(io::Error::new(error.kind(), error.to_string())),
+            None => Ok(())
+        }
+    }
+}
+impl fmt::Debug for EventLog {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EventLog").field("game", &self.game).finish()
+    }
+}
+impl PartialEq for EventLog {
+    /// Logs are equal when they log the same game to the same writer.
+    fn eq(&self, other: &EventLog) -> bool {
+        self.game == other.game && Arc::ptr_eq(&self.writer, &other.writer)
+    }
+}
+
+// The writer of a log and the first error it returned.
+struct LogWriter {
+    writer: Box<dyn Write + Send>,
+    error: Option<io::Error>
+}
+
+/// A game being played.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameSession {
+    pub black: Player,
+    pub white: Player,
+    pub event: String,
+    pub date: String,
+    /// Clocks of Black and White, `None` for untimed games.
+    pub clocks: Option<[Clock; 2]>,
+    board: Board,
+    color: Color,
+    history: Vec<Turn>,
+    last_move: Option<LegalMove>,
+    log: Option<EventLog>
+}
+impl GameSession {
+    /// A game from the starting position.
+    pub fn new(black: Player, white: Player) -> GameSession {
+        GameSession {
+            black,
+            white,
+            event: String::new(),
+            date: String::new(),
+            clocks: None,
+            board: Board::new(),
+            color: Color::Black,
+            history: Vec::new(),
+            last_move: None,
+            log: None
+        }
+    }
+
+    /// Reports the events of the session from now on to a log, starting with `Started`. The log
+    /// isn't saved with the session.
+    pub fn set_log(&mut self, log: EventLog) {
+        log.log(&GameEvent::Started { black: self.black.name().to_string(), white: self.white.name().to_string() });
+        self.log = Some(log);
+    }
+
+    pub fn log(&self) -> Option<&EventLog> {
+        self.log.as_ref()
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// The player to move.
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    /// The moves played, skips left out.
+    pub fn history(&self) -> &[Turn] {
+        &self.history
+    }
+
+    /// The last move played, with the disks it flipped.
+    pub fn last_move(&self) -> Option<LegalMove> {
+        self.last_move
+    }
+
+    /// Whether neither player can move.
+    pub fn is_over(&self) -> bool {
+        self.board.legal_moves(self.color).is_empty() && self.board.legal_moves(self.color.flip()).is_empty()
+    }
+
+    pub fn player(&self, color: Color) -> &Player {
+        match color {
+            Color::Black => &self.black,
+            Color::White => &self.white
+        }
+    }
+
+    pub fn clock(&self, color: Color) -> Option<Clock> {
+        self.clocks.map(|x| x[color as usize])
+    }
+
+    /// Plays a move of the player to move, charging the time spent to their clock. The opponent
+    /// skips if they have no legal move.
+    pub fn play(&mut self, position: Coord, time: Duration) -> io::Result<()> {
+        let legal_move = self.board.test_move(self.color, position).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "illegal move"))?;
+        if let Some(ref mut clocks) = self.clocks {
+            let clock = &mut clocks[self.color as usize];
+            clock.remaining = clock.remaining.checked_sub(time).unwrap_or_default() + clock.increment;
+        }
+        self.history.push(Turn { color: self.color, position, time });
+        self.last_move = Some(legal_move);
+        self.emit(GameEvent::Moved { color: self.color, position, time });
+        self.board = legal_move.apply(self.board);
+        self.color = self.color.flip();
+        if self.is_over() {
+            let black_disks = self.board.count(Color::Black) as u8;
+            self.emit(GameEvent::Ended { black_disks, white_disks: self.board.count(Color::White) as u8 });
+        } else if self.board.legal_moves(self.color).is_empty() {
+            self.emit(GameEvent::Skipped { color: self.color });
+            self.color = self.color.flip();
+        }
+        Ok(())
+    }
+
+    /// Takes back the last move and gives its time back to the clock. Returns the move, `None`
+    /// if there was none.
+    pub fn undo(&mut self) -> Option<Turn> {
+        let turn = self.history.pop()?;
+        if let Some(ref mut clocks) = self.clocks {
+            let clock = &mut clocks[turn.color as usize];
+            clock.remaining = clock.remaining.checked_sub(clock.increment).unwrap_or_default() + turn.time;
+        }
+        let mut board = Board::new();
+        self.last_move = None;
+        for previous in &self.history {
+            let legal_move = board.legal_moves(previous.color).into_iter().find(|x| x.position == previous.position);
+            let legal_move = legal_move.expect("the history is legal");
+            board = legal_move.apply(board);
+            self.last_move = Some(legal_move);
+        }
+        self.board = board;
+        self.color = turn.color;
+        self.emit(GameEvent::Undone { color: turn.color, position: turn.position });
+        Some(turn)
+    }
+
+    /// The record of the game, with the final disk count once it is over.
+    pub fn record(&self) -> GameRecord {
+        GameRecord {
+            black: self.black.name().to_string(),
+            white: self.white.name().to_string(),
+            event: self.event.clone(),
+            date: self.date.clone(),
+            moves: self.history.iter().map(|x| x.position).collect(),
+            black_disks: if self.is_over() { Some(self.board.count(Color::Black) as u8) } else { None },
+            theoretical_disks: None,
+            tags: Vec::new()
+        }
+    }
+
+    fn emit(&self, event: GameEvent) {
+        if let Some(ref log) = self.log {
+            log.log(&event);
+        }
+    }
+
+    /// Reads a session written by [`write`](#method.write), replaying its moves.
+    ///
+    /// The format starts with the magic bytes `RRSESS` and a 16 bit version. Then come the event
+    /// and the date, the black and the white player, the clocks and the moves. Strings are
+    /// written as their 16 bit length followed by their UTF-8 bytes. A player is a byte, 0 for a
+    /// human and 1 for an engine, its name, and for an engine the number of its options as a
+    /// byte followed by their names and values. The clocks are a byte, 0 if the game is untimed,
+    /// otherwise followed by the time left and the increment of Black and White in 64 bit
+    /// milliseconds. The moves are their 16 bit number, then each move as its cell `y * 8 + x`
+    /// and the 32 bit milliseconds spent on it. All integers are little endian.
+    pub fn read<R: Read>(mut reader: R) -> io::Result<GameSession> {
+        let mut header = [0; 8];
+        reader.read_exact(&mut header)?;
+        if &header[..6] != MAGIC {
+            return Err(invalid("not a saved game"));
+        }
+        if u16::from_le_bytes([header[6], header[7]]) != VERSION {
+            return Err(invalid("unsupported saved game version"));
+        }
+        let event = read_string(&mut reader)?;
+        let date = read_string(&mut reader)?;
+        let black = read_player(&mut reader)?;
+        let white = read_player(&mut reader)?;
+        let clocks = if read_bytes::<_, 1>(&mut reader)?[0] != 0 {
+            let mut clock = || -> io::Result<Clock> {
+                Ok(Clock {
+                    remaining: Duration::from_millis(u64::from_le_bytes(read_bytes(&mut reader)?)),
+                    increment: Duration::from_millis(u64::from_le_bytes(read_bytes(&mut reader)?))
+                })
+            };
+            Some([clock()?, clock()?])
+        } else {
+            None
+        };
+        let mut session = GameSession::new(black, white);
+        session.event = event;
+        session.date = date;
+        for _ in 0..read_u16(&mut reader)? {
+            let cell = read_bytes::<_, 1>(&mut reader)?[0];
+            let time = Duration::from_millis(u64::from(u32::from_le_bytes(read_bytes(&mut reader)?)));
+            if cell >= 64 {
+                return Err(invalid("move outside of the board"));
+            }
+            session.play(((cell % 8) as i8, (cell / 8) as i8), time).map_err(|_| invalid("illegal move"))?;
+        }
+        // the clocks are saved as they are, not replayed
+        session.clocks = clocks;
+        Ok(session)
+    }
+
+    /// Writes the session in the format accepted by [`read`](#method.read).
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        write_string(&mut writer, &self.event)?;
+        write_string(&mut writer, &self.date)?;
+        write_player(&mut writer, &self.black)?;
+        write_player(&mut writer, &self.white)?;
+        match self.clocks {
+            Some(clocks) => {
+                writer.write_all(&[1])?;
+                for clock in &clocks {
+                    writer.write_all(&(clock.remaining.as_millis() as u64).to_le_bytes())?;
+                    writer.write_all(&(clock.increment.as_millis() as u64).to_le_bytes())?;
+                }
+            }
+            None => writer.write_all(&[0])?
+        }
+        writer.write_all(&(self.history.len() as u16).to_le_bytes())?;
+        for turn in &self.history {
+            let (x, y) = turn.position;
+            writer.write_all(&[(y * 8 + x) as u8])?;
+            writer.write_all(&(turn.time.as_millis().min(u128::from(u32::MAX)) as u32).to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Reads a session from a file.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<GameSession> {
+        GameSession::read(BufReader::new(File::open(path)?))
+    }
+
+    /// Writes the session to a file, replacing its contents atomically.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        files::save_atomically(path, |writer| self.write(writer))
+    }
+}
+
+fn color_value(color: Color) -> Value {
+    Value::String(color.to_string().to_lowercase())
+}
+
+fn cell_value(position: Coord) -> Value {
+    Value::String(record::format_move(position))
+}
+
+fn millis(time: Duration) -> Value {
+    Value::Number(time.as_millis() as f64)
+}
+
+fn read_player<R: Read>(reader: &mut R) -> io::Result<Player> {
+    let kind = read_bytes::<_, 1>(reader)?[0];
+    let name = read_string(reader)?;
+    match kind {
+        0 => Ok(Player::Human(name)),
+        1 => {
+            let count = read_bytes::<_, 1>(reader)?[0];
+            let options = (0..count).map(|_| Ok((read_string(reader)?, read_string(reader)?))).collect::<io::Result<_>>()?;
+            Ok(Player::Engine { name, options })
+        }
+        _ => Err(invalid("unknown kind of player"))
+    }
+}
+
+fn write_player<W: Write>(writer: &mut W, player: &Player) -> io::Result<()> {
+    match *player {
+        Player::Human(ref name) => {
+            writer.write_all(&[0])?;
+            write_string(writer, name)
+        }
+        Player::Engine { ref name, ref options } => {
+            if options.len() > 255 {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, "too many options"));
+            }
+            writer.write_all(&[1])?;
+            write_string(writer, name)?;
+            writer.write_all(&[options.len() as u8])?;
+            for (option, value) in options {
+                write_string(writer, option)?;
+                write_string(writer, value)?;
+            }
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,225 @@
+//! This module plays many games between two players at once, without showing them, to gather
+//! statistics or positions to train networks.
+//!
+//! The games are shared between threads, each with engines of its own. Game `i` starts from a
+//! random opening drawn from the seed and `i / 2`, the two games of an opening swapping colors
+//! like in [matches](../arena/index.html), so the games don't depend on the number of threads.
+//! Finished games are passed to [sinks](trait.Sink.html) in the order they finish, on the thread
+//! calling [`run`](fn.run.html).
+
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use arena::{self, Options};
+use engine::{Engine, Limits};
+use formats::{pgn, training};
+use formats::training::Sample;
+use random::Random;
+use record::GameRecord;
+use stats::MatchResult;
+
+/// Settings of a batch of games.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchConfig {
+    /// Names of the players in the records.
+    pub first: String,
+    pub second: String,
+    pub games: u32,
+    /// Number of threads playing, the number of processors by default.
+    pub threads: usize,
+    /// Number of random moves in the openings.
+    pub opening_plies: usize,
+    pub seed: u64,
+    /// Limits of the searches of both players.
+    pub limits: Limits
+}
+impl Default for BatchConfig {
+    fn default() -> BatchConfig {
+        BatchConfig {
+            first: "first".to_string(),
+            second: "second".to_string(),
+            games: 1000,
+            threads: thread::available_parallelism().map(|x| x.get()).unwrap_or(1),
+            opening_plies: 6,
+            seed: 0,
+            limits: Limits::default()
+        }
+    }
+}
+
+/// Receives the games of a batch as they finish.
+pub trait Sink {
+    /// Takes game number `index` of the batch.
+    fn game(&mut self, index: u32, record: &GameRecord) -> io::Result<()>;
+
+    /// Called once all the games are played.
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+impl Sink for Vec<GameRecord> {
+    /// Keeps the games in the order they finished.
+    fn game(&mut self, _: u32, record: &GameRecord) -> io::Result<()> {
+        self.push(record.clone());
+        Ok(())
+    }
+}
+
+/// Writes the games in PGN, separated by blank lines.
+pub struct Pgn<W: Write> {
+    writer: W,
+    written: bool
+}
+impl<W: Write> Pgn<W> {
+    pub fn new(writer: W) -> Pgn<W> {
+        Pgn { writer, written: false }
+    }
+}
+impl<W: Write> Sink for Pgn<W> {
+    fn game(&mut self, _: u32, record: &GameRecord) -> io::Result<()> {
+        if self.written {
+            writeln!(self.writer)?;
+        }
+        write!(self.writer, "{}", pgn::format(record)?)?;
+        self.written = true;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Writes the positions of the finished games as [training samples](../formats/training/index.html).
+/// The format starts with the number of samples, so they are written once all the games are played.
+pub struct Samples<W: Write> {
+    writer: W,
+    samples: Vec<Sample>
+}
+impl<W: Write> Samples<W> {
+    pub fn new(writer: W) -> Samples<W> {
+        Samples { writer, samples: Vec::new() }
+    }
+}
+impl<W: Write> Sink for Samples<W> {
+    fn game(&mut self, _: u32, record: &GameRecord) -> io::Result<()> {
+        self.samples.extend(training::samples(record).unwrap_or_default());
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        training::write(&mut self.writer, &self.samples)?;
+        self.writer.flush()
+    }
+}
+
+/// The outcome of a batch.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Summary {
+    /// Results of the first player.
+    pub result: MatchResult,
+    /// Results of Black, whoever played it.
+    pub black: MatchResult,
+    /// Games lost by forfeit.
+    pub forfeits: u32,
+    /// Average number of moves of a game.
+    pub average_moves: f64,
+    pub time: Duration
+}
+impl Summary {
+    /// Writes the summary on a few lines.
+    pub fn format(&self) -> String {
+        let (result, black) = (self.result, self.black);
+        let games = result.games();
+        let mut text = format!(
+            "{} games in {:.1}s ({:.1} games/s), {:.1} moves per game\n",
+            games, self.time.as_secs_f64(), f64::from(games) / self.time.as_secs_f64().max(1e-9), self.average_moves
+        );
+        text += &format!("first: {} wins, {} draws, {} losses, score {:.3}\n", result.wins, result.draws, result.losses, result.score());
+        text += &format!("black: {} wins, {} draws, {} losses, score {:.3}\n", black.wins, black.draws, black.losses, black.score());
+        if self.forfeits > 0 {
+            text += &format!("{} games forfeited\n", self.forfeits);
+        }
+        text
+    }
+}
+
+/// Plays a batch of games between engines made by `first` and `second` on each thread, passing
+/// them to the sinks. Stops at the first error of a sink.
+pub fn run<A, B, F, G>(config: &BatchConfig, first: F, second: G, sinks: &mut [&mut dyn Sink]) -> io::Result<Summary>
+where A: Engine, B: Engine, F: Fn() -> A + Sync, G: Fn() -> B + Sync {
+    let start = Instant::now();
+    let options = Options { limits: config.limits, ..Options::default() };
+    let next = AtomicU32::new(0);
+    let stop = AtomicBool::new(false);
+    let (sender, receiver) = mpsc::channel();
+    let mut summary = Summary::default();
+    let mut moves = 0;
+    let mut error = None;
+    thread::scope(|scope| {
+        for _ in 0..config.threads.max(1) {
+            let sender = sender.clone();
+            let (first, second, options, next, stop) = (&first, &second, &options, &next, &stop);
+            scope.spawn(move || {
+                let (mut first, mut second) = (first(), second());
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    if index >= config.games || stop.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    let mut random = Random::new(Random::new(config.seed ^ u64::from(index / 2)).next_u64());
+                    let (board, color, opening) = arena::opening(config.opening_plies, &mut random);
+                    let first_black = index % 2 == 0;
+                    let (mut record, diff) = if first_black {
+                        arena::game(&board, color, &opening, &mut first, &mut second, options)
+                    } else {
+                        arena::game(&board, color, &opening, &mut second, &mut first, options)
+                    };
+                    let (black, white) = if first_black { (&config.first, &config.second) } else { (&config.second, &config.first) };
+                    record.black = black.clone();
+                    record.white = white.clone();
+                    if sender.send((index, record, diff, first_black)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
+        for (index, record, diff, first_black) in receiver {
+            if error.is_some() {
+                continue;
+            }
+            let black = outcome(diff);
+            summary.black.add(&black);
+            summary.result.add(&if first_black { black } else { black.reversed() });
+            summary.forfeits += record.tag("termination").is_some() as u32;
+            moves += record.moves.len();
+            if let Err(e) = sinks.iter_mut().try_for_each(|sink| sink.game(index, &record)) {
+                stop.store(true, Ordering::Relaxed);
+                error = Some(e);
+            }
+        }
+    });
+    if let Some(error) = error {
+        return Err(error);
+    }
+    for sink in sinks {
+        sink.finish()?;
+    }
+    let games = summary.result.games();
+    summary.average_moves = if games == 0 { 0.0 } else { moves as f64 / f64::from(games) };
+    summary.time = start.elapsed();
+    Ok(summary)
+}
+
+// A game seen by a player with this disk differential.
+fn outcome(diff: i32) -> MatchResult {
+    match diff.signum() {
+        1 => MatchResult { wins: 1, draws: 0, losses: 0 },
+        0 => MatchResult { wins: 0, draws: 1, losses: 0 },
+        _ => MatchResult { wins: 0, draws: 0, losses: 1 }
+    }
+}
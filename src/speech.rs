@@ -0,0 +1,81 @@
+//! This module describes boards and moves in plain sentences instead of grids, for players
+//! using screen readers or braille displays.
+//!
+//! Cells are named in algebraic notation, like in [records](../record/index.html), and scores
+//! are given as the disks of Black, then of White.
+
+use game::{Board, Color, LegalMove};
+use record::format_move;
+
+/// Describes a move played on the board, which is the board after the move, like
+/// "White plays d6, flipping e5 and e6; score 5–4".
+pub fn describe_move(board: &Board, legal_move: &LegalMove) -> String {
+    let flipped = legal_move.flipped().into_iter().map(format_move).collect::<Vec<_>>();
+    format!("{} plays {}, flipping {}; score {}", legal_move.color, format_move(legal_move.position), join(&flipped, "and"), score(board))
+}
+
+/// Describes the disks of both players, then the moves of the player given, if any.
+pub fn describe_board(board: &Board, hints: Option<Color>) -> String {
+    let mut text = String::new();
+    for &color in [Color::Black, Color::White].iter() {
+        let cells = cells(board, color);
+        text += &match cells.len() {
+            0 => format!("{} has no disks.\n", color),
+            1 => format!("{} has 1 disk: {}.\n", color, cells[0]),
+            n => format!("{} has {} disks: {}.\n", color, n, join(&cells, "and"))
+        };
+    }
+    if let Some(color) = hints {
+        let moves = board.legal_moves(color).iter().map(|x| x.position).collect::<Vec<_>>();
+        text += &if moves.is_empty() {
+            format!("{} has no legal move.\n", color)
+        } else {
+            format!("{} can play {}.\n", color, join(&sorted(moves), "or"))
+        };
+    }
+    text
+}
+
+/// Describes the result of a finished game, like "Game over: Black wins 40–24".
+pub fn describe_result(board: &Board) -> String {
+    let (black, white) = (board.count(Color::Black), board.count(Color::White));
+    let winner = if black > white {
+        "Black wins"
+    } else if white > black {
+        "White wins"
+    } else {
+        "draw"
+    };
+    format!("Game over: {} {}", winner, score(board))
+}
+
+fn score(board: &Board) -> String {
+    format!("{}–{}", board.count(Color::Black), board.count(Color::White))
+}
+
+// The cells of a color, row by row from row 1.
+fn cells(board: &Board, color: Color) -> Vec<String> {
+    let mut positions = Vec::new();
+    for (x, column) in board.cells.iter().enumerate() {
+        for (y, &cell) in column.iter().enumerate() {
+            if cell == Some(color) {
+                positions.push((x as i8, y as i8));
+            }
+        }
+    }
+    sorted(positions)
+}
+
+fn sorted(mut positions: Vec<(i8, i8)>) -> Vec<String> {
+    positions.sort_by_key(|&(x, y)| (-y, x));
+    positions.into_iter().map(format_move).collect()
+}
+
+// Joins words like "a, b and c".
+fn join(words: &[String], conjunction: &str) -> String {
+    match words.split_last() {
+        None => "nothing".to_string(),
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} {} {}", rest.join(", "), conjunction, last)
+    }
+}
@@ -0,0 +1,155 @@
+//! This module contains the statistics of match results used to compare engine versions.
+
+/// Results of a series of games, counted from the point of view of one of the players.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct MatchResult {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32
+}
+impl MatchResult {
+    /// Number of games played.
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Points per game, counting a win as 1 and a draw as 0.5.
+    pub fn score(&self) -> f64 {
+        if self.games() == 0 {
+            return 0.5;
+        }
+        (self.wins as f64 + 0.5 * self.draws as f64) / self.games() as f64
+    }
+
+    /// The same results seen from the opponent.
+    pub fn reversed(&self) -> MatchResult {
+        MatchResult { wins: self.losses, draws: self.draws, losses: self.wins }
+    }
+
+    /// Adds the results of another series.
+    pub fn add(&mut self, other: &MatchResult) {
+        self.wins += other.wins;
+        self.draws += other.draws;
+        self.losses += other.losses;
+    }
+
+    /// Estimates the Elo difference to the opponent.
+    pub fn elo(&self) -> f64 {
+        elo(self.score())
+    }
+
+    /// Half width of the 95% confidence interval of [`elo`](#method.elo).
+    pub fn elo_error(&self) -> f64 {
+        let n = self.games() as f64;
+        if n == 0.0 {
+            return f64::INFINITY;
+        }
+        let score = self.score();
+        let margin = 1.96 * (self.variance() / n).sqrt();
+        (elo(score + margin) - elo(score - margin)) / 2.0
+    }
+
+    /// Variance of the points scored in a game.
+    pub fn variance(&self) -> f64 {
+        if self.games() == 0 {
+            return 0.0;
+        }
+        let score = self.score();
+        (self.wins as f64 * (1.0 - score).powi(2)
+            + self.draws as f64 * (0.5 - score).powi(2)
+            + self.losses as f64 * score.powi(2)) / self.games() as f64
+    }
+
+    /// Likelihood of superiority: the probability that the player is stronger than the opponent,
+    /// based on the decisive games.
+    pub fn los(&self) -> f64 {
+        let decisive = (self.wins + self.losses) as f64;
+        if decisive == 0.0 {
+            return 0.5;
+        }
+        0.5 * (1.0 + erf((self.wins as f64 - self.losses as f64) / (2.0 * decisive).sqrt()))
+    }
+}
+
+/// The outcome of a [sequential probability ratio test](struct.Sprt.html).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Decision {
+    /// The player is at most `elo0` stronger, the test failed.
+    AcceptH0,
+    /// The player is at least `elo1` stronger, the test passed.
+    AcceptH1,
+    /// More games are needed.
+    Continue
+}
+
+/// A sequential probability ratio test, deciding between the hypotheses that the player is
+/// `elo0` (H0) or `elo1` (H1) stronger than the opponent. It stops as soon as the results are
+/// significant enough, with error rates `alpha` for a false pass and `beta` for a false fail.
+///
+/// The log-likelihood ratio uses the normal approximation of the score per game, as the usual
+/// engine testing frameworks do.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64
+}
+impl Default for Sprt {
+    fn default() -> Sprt {
+        Sprt {
+            elo0: 0.0,
+            elo1: 5.0,
+            alpha: 0.05,
+            beta: 0.05
+        }
+    }
+}
+impl Sprt {
+    /// The log-likelihood ratio of H1 against H0.
+    pub fn llr(&self, result: &MatchResult) -> f64 {
+        // half a game of each outcome keeps the variance positive when all games end the same
+        let smoothed = MatchResult { wins: 2 * result.wins + 1, draws: 2 * result.draws + 1, losses: 2 * result.losses + 1 };
+        let variance = smoothed.variance();
+        let (score0, score1) = (expected_score(self.elo0), expected_score(self.elo1));
+        let n = result.games() as f64;
+        n * (score1 - score0) * (2.0 * result.score() - score0 - score1) / (2.0 * variance)
+    }
+
+    /// The log-likelihood ratios below and above which the test stops.
+    pub fn bounds(&self) -> (f64, f64) {
+        ((self.beta / (1.0 - self.alpha)).ln(), ((1.0 - self.beta) / self.alpha).ln())
+    }
+
+    pub fn decide(&self, result: &MatchResult) -> Decision {
+        let llr = self.llr(result);
+        let (lower, upper) = self.bounds();
+        if llr <= lower {
+            Decision::AcceptH0
+        } else if llr >= upper {
+            Decision::AcceptH1
+        } else {
+            Decision::Continue
+        }
+    }
+}
+
+/// Converts an Elo difference to an expected score.
+pub fn expected_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+/// Converts an expected score to an Elo difference.
+pub fn elo(score: f64) -> f64 {
+    let score = score.clamp(1e-6, 1.0 - 1e-6);
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+/// The error function, with an absolute error below 1.5e-7.
+pub fn erf(x: f64) -> f64 {
+    // Abramowitz and Stegun, formula 7.1.26
+    let t = 1.0 / (1.0 + 0.327_591_1 * x.abs());
+    let polynomial = t * (0.254_829_592 + t * (-0.284_496_736 + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    let y = 1.0 - polynomial * (-x * x).exp();
+    if x < 0.0 { -y } else { y }
+}
@@ -0,0 +1,165 @@
+//! This module connects a [chat bot](../chat/index.html) to Telegram, for games played by
+//! message at any pace, like correspondence games. Members of a chat play with commands like
+//! `/challenge` and cells like `d3`, or pick their move on the buttons under the board.
+//!
+//! [`run`](fn.run.html) polls the Bot API with the token of a bot, and saves the game of a chat
+//! in a directory after each of its messages, so that games survive restarts. Members are told
+//! apart by their username, the only part of a mention like `@ann` Telegram passes on.
+
+use std::io;
+use std::path::Path;
+
+use ureq;
+
+use chat::{Bot, Reply, User};
+use engine::Engine;
+use files::invalid;
+use formats::json::Value;
+use record;
+
+const API: &str = "https://api.telegram.org";
+
+// Seconds an update is waited for before asking again.
+const POLL_SECONDS: f64 = 50.0;
+
+// Buttons in each row of the keyboard of the moves.
+const BUTTONS: usize = 4;
+
+const BOUNDARY: &str = "rusty-reversi-board";
+
+/// Plays on Telegram with the token of a bot, first reading back the games saved in a directory,
+/// until a request fails. Commands start with `/`.
+pub fn run<E: Engine, P: AsRef<Path>>(token: &str, bot: &mut Bot<E>, directory: P) -> io::Result<()> {
+    let directory = directory.as_ref();
+    bot.prefix = '/';
+    bot.load(directory)?;
+    let me = call(token, "getMe", Vec::new())?;
+    let own = me.get("username").and_then(Value::as_str).ok_or_else(|| invalid("missing username"))?.to_lowercase();
+    let mut offset = 0.0;
+    loop {
+        let parameters = vec![
+            ("offset", Value::Number(offset)),
+            ("timeout", Value::Number(POLL_SECONDS)),
+            ("allowed_updates", Value::Array(vec![string("message"), string("callback_query")]))
+        ];
+        let updates = call(token, "getUpdates", parameters)?;
+        for update in updates.as_array().ok_or_else(|| invalid("invalid updates"))? {
+            offset = update.get("update_id").and_then(Value::as_f64).ok_or_else(|| invalid("missing update id"))? + 1.0;
+            // a chat the bot can't write in doesn't stop the others
+            if let Err(_error) = answer(token, bot, directory, &own, update) {
+                #[cfg(feature = "trace")]
+                tracing::warn!(error = %_error, "update failed");
+            }
+        }
+    }
+}
+
+// Answers a message or the press of a button, and saves the game of its chat.
+#[cfg_attr(feature = "trace", tracing::instrument(name = "telegram", skip_all))]
+fn answer<E: Engine>(token: &str, bot: &mut Bot<E>, directory: &Path, own: &str, update: &Value) -> io::Result<()> {
+    if let Some(message) = update.get("message") {
+        let (chat, author) = match (message.get("chat").and_then(|x| x.get("id")).and_then(Value::as_f64), message.get("from").and_then(user)) {
+            (Some(chat), Some(author)) => (chat.to_string(), author),
+            _ => return Ok(())
+        };
+        let text = message.get("text").and_then(Value::as_str).unwrap_or("");
+        // commands in groups name the bot, like /challenge@reversi_bot
+        let content = text.split_whitespace().map(|word| match word.split_once('@') {
+            Some((command, name)) if command.starts_with('/') && name.eq_ignore_ascii_case(own) => command,
+            _ => word
+        }).collect::<Vec<_>>().join(" ");
+        let mentions = content.split_whitespace()
+            .filter_map(|x| x.strip_prefix('@'))
+            .filter(|x| !x.is_empty() && !x.eq_ignore_ascii_case(own))
+            .map(|x| User { id: format!("@{}", x.to_lowercase()), name: x.to_string() })
+            .collect::<Vec<_>>();
+        if let Some(reply) = bot.handle(&chat, &author, &content, &mentions) {
+            bot.save(directory, &chat)?;
+            send(token, bot, &chat, &reply)?;
+        }
+    } else if let Some(query) = update.get("callback_query") {
+        let id = query.get("id").and_then(Value::as_str).ok_or_else(|| invalid("missing query id"))?;
+        let author = query.get("from").and_then(user).ok_or_else(|| invalid("missing sender"))?;
+        let message = query.get("message").ok_or_else(|| invalid("missing message"))?;
+        let chat = message.get("chat").and_then(|x| x.get("id")).and_then(Value::as_f64).ok_or_else(|| invalid("missing chat"))?.to_string();
+        let cell = query.get("data").and_then(Value::as_str).unwrap_or("");
+        let reply = bot.handle(&chat, &author, cell, &[]);
+        // refusals show up briefly over the chat, boards as new messages
+        let mut parameters = vec![("callback_query_id", string(id))];
+        parameters.extend(reply.as_ref().filter(|x| x.image.is_none()).map(|x| ("text", string(&x.text))));
+        call(token, "answerCallbackQuery", parameters)?;
+        if let Some(reply) = reply.filter(|x| x.image.is_some()) {
+            bot.save(directory, &chat)?;
+            let message_id = message.get("message_id").cloned().unwrap_or(Value::Null);
+            call(token, "editMessageReplyMarkup", vec![("chat_id", string(&chat)), ("message_id", message_id)])?;
+            send(token, bot, &chat, &reply)?;
+        }
+    }
+    Ok(())
+}
+
+// Sends a reply to a chat, the board with the moves of the player to move as buttons.
+fn send<E: Engine>(token: &str, bot: &Bot<E>, chat: &str, reply: &Reply) -> io::Result<()> {
+    let image = match reply.image {
+        Some(ref image) => image,
+        None => return call(token, "sendMessage", vec![("chat_id", string(chat)), ("text", string(&reply.text))]).map(|_| ())
+    };
+    let mut fields = vec![("chat_id", chat.to_string()), ("caption", reply.text.clone())];
+    if let Some(session) = bot.session(chat).filter(|_| bot.is_playing(chat)) {
+        let buttons = session.board().legal_moves(session.color()).iter().map(|x| {
+            let cell = record::format_move(x.position);
+            object(vec![("text", string(&cell)), ("callback_data", string(&cell))])
+        }).collect::<Vec<_>>();
+        let rows = buttons.chunks(BUTTONS).map(|x| Value::Array(x.to_vec())).collect();
+        fields.push(("reply_markup", object(vec![("inline_keyboard", Value::Array(rows))]).to_string()));
+    }
+    let mut body = Vec::new();
+    for (name, value) in fields {
+        body.extend_from_slice(format!("--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n", BOUNDARY, name, value).as_bytes());
+    }
+    body.extend_from_slice(format!("--{}\r\nContent-Disposition: form-data; name=\"photo\"; filename=\"board.png\"\r\nContent-Type: image/png\r\n\r\n", BOUNDARY).as_bytes());
+    body.extend_from_slice(image);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", BOUNDARY).as_bytes());
+    let mut response = ureq::post(format!("{}/bot{}/sendPhoto", API, token))
+        .content_type(format!("multipart/form-data; boundary={}", BOUNDARY))
+        .send(body)
+        .map_err(ureq::Error::into_io)?;
+    result(&response.body_mut().read_to_string().map_err(ureq::Error::into_io)?).map(|_| ())
+}
+
+// Calls a method of the Bot API, returning its result.
+fn call(token: &str, method: &str, parameters: Vec<(&str, Value)>) -> io::Result<Value> {
+    let mut response = ureq::post(format!("{}/bot{}/{}", API, token, method))
+        .content_type("application/json")
+        .send(object(parameters).to_string())
+        .map_err(ureq::Error::into_io)?;
+    result(&response.body_mut().read_to_string().map_err(ureq::Error::into_io)?)
+}
+
+fn result(body: &str) -> io::Result<Value> {
+    let document = Value::parse(body)?;
+    match document.get("result") {
+        Some(result) if document.get("ok") == Some(&Value::Bool(true)) => Ok(result.clone()),
+        _ => Err(io::Error::other(document.get("description").and_then(Value::as_str).unwrap_or("request failed").to_string()))
+    }
+}
+
+// A member by username, or by number without one.
+fn user(document: &Value) -> Option<User> {
+    match document.get("username").and_then(Value::as_str) {
+        Some(name) => Some(User { id: format!("@{}", name.to_lowercase()), name: name.to_string() }),
+        None => {
+            let id = document.get("id").and_then(Value::as_f64)?;
+            let name = document.get("first_name").and_then(Value::as_str).unwrap_or("anonymous");
+            Some(User { id: id.to_string(), name: name.to_string() })
+        }
+    }
+}
+
+fn object(members: Vec<(&str, Value)>) -> Value {
+    Value::Object(members.into_iter().map(|(key, value)| (key.to_string(), value)).collect())
+}
+
+fn string(text: &str) -> Value {
+    Value::String(text.to_string())
+}
@@ -0,0 +1,116 @@
+//! This module colors the boards drawn in terminals, like the ones of
+//! [`Board::draw`](../game/struct.Board.html#method.draw), with ANSI escape sequences.
+//!
+//! The themes are `classic`, a green board like the ones of tournaments, `high-contrast`, black
+//! and blue disks on a white board, and `monochrome`, which leaves the text as it is. The theme
+//! is chosen by name, or by [`Theme::from_env`](enum.Theme.html#method.from_env) from the
+//! environment, which follows the [`NO_COLOR`](https://no-color.org) convention.
+
+use std::env;
+use std::str::FromStr;
+
+/// Environment variable giving the name of the theme.
+pub const THEME_VARIABLE: &str = "RUSTY_REVERSI_THEME";
+
+// Ends a colored cell.
+const RESET: &str = "\u{1b}[0m";
+
+/// The colors of a board in a terminal.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Theme {
+    #[default]
+    Classic,
+    HighContrast,
+    /// No colors at all.
+    Monochrome
+}
+impl Theme {
+    /// The theme named in the environment, or monochrome if `NO_COLOR` is set to anything but an
+    /// empty string, the classic theme otherwise.
+    pub fn from_env() -> Theme {
+        if env::var_os("NO_COLOR").is_some_and(|x| !x.is_empty()) {
+            return Theme::Monochrome;
+        }
+        env::var(THEME_VARIABLE).ok().and_then(|x| x.parse().ok()).unwrap_or_default()
+    }
+
+    /// Colors a drawn board: the rows and the borders of the board, leaving the labels of the
+    /// columns and the text outside of the board as they are.
+    pub fn paint(&self, text: &str) -> String {
+        if *self == Theme::Monochrome {
+            return text.to_string();
+        }
+        let mut painted = String::with_capacity(text.len() * 2);
+        for line in text.split_inclusive('\n') {
+            let (line, newline) = match line.strip_suffix('\n') {
+                Some(line) => (line, "\n"),
+                None => (line, "")
+            };
+            let mut chars = line.chars();
+            match chars.next() {
+                Some(first) if first.is_ascii_digit() || line.contains(['┌', '├', '└']) => {
+                    painted.push(first);
+                    painted += &self.paint_cells(chars.as_str());
+                }
+                _ => painted += line
+            }
+            painted += newline;
+        }
+        painted
+    }
+
+    /// Colors a part of a row of the board, like a single cell.
+    pub fn paint_cells(&self, text: &str) -> String {
+        let colors = match self.colors() {
+            Some(colors) => colors,
+            None => return text.to_string()
+        };
+        let mut painted = format!("\u{1b}[{};{}m", colors.board, colors.grid);
+        for c in text.chars() {
+            let color = match c {
+                'X' | 'x' | '⚫' | '●' => colors.black,
+                'O' | 'o' | '⚪' | '○' => colors.white,
+                '+' | '·' => colors.hint,
+                '>' | '▶' => colors.mark,
+                _ => {
+                    painted.push(c);
+                    continue;
+                }
+            };
+            painted += &format!("\u{1b}[{}m{}\u{1b}[22;{}m", color, c, colors.grid);
+        }
+        painted += RESET;
+        painted
+    }
+
+    fn colors(&self) -> Option<Colors> {
+        match *self {
+            Theme::Classic => Some(Colors { board: "48;5;28", grid: "38;5;22", black: "30", white: "97", hint: "38;5;22", mark: "91;1" }),
+            Theme::HighContrast => Some(Colors { board: "107", grid: "30", black: "30;1", white: "94;1", hint: "35", mark: "31;1" }),
+            Theme::Monochrome => None
+        }
+    }
+}
+impl FromStr for Theme {
+    type Err = ();
+
+    /// Parses `classic`, `high-contrast` or `monochrome`.
+    fn from_str(name: &str) -> Result<Theme, ()> {
+        match name {
+            "classic" => Ok(Theme::Classic),
+            "high-contrast" => Ok(Theme::HighContrast),
+            "monochrome" => Ok(Theme::Monochrome),
+            _ => Err(())
+        }
+    }
+}
+
+// Select Graphic Rendition parameters of the parts of a board.
+struct Colors {
+    board: &'static str,
+    grid: &'static str,
+    black: &'static str,
+    white: &'static str,
+    hint: &'static str,
+    mark: &'static str
+}
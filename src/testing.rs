@@ -0,0 +1,82 @@
+//! This module provides [proptest](https://docs.rs/proptest) strategies generating boards, games
+//! and positions, so that crates built on this one can property-test what they do with them. It
+//! needs the `testing` feature.
+//!
+//! Games are generated as legal move sequences from the starting position, so that they shrink
+//! towards shorter games and earlier moves, and the positions reached in them are realistic.
+//! [`boards`](fn.boards.html) generates any board, reachable or not.
+//!
+//! ```ignore
+//! proptest! {
+//!     #[test]
+//!     fn games_are_replayed(game in testing::games(60)) {
+//!         prop_assert!(game.positions().is_ok());
+//!     }
+//! }
+//! ```
+
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+use game::{Board, Color, Coord};
+use record::GameRecord;
+
+/// Any board, each cell being empty or holding a disk of either color, which may not be
+/// reachable in a game.
+pub fn boards() -> impl Strategy<Value = Board> {
+    let cell = prop_oneof![Just(None), Just(Some(Color::Black)), Just(Some(Color::White))];
+    vec(cell, 64).prop_map(|cells| {
+        let mut board = Board { cells: [[None; 8]; 8] };
+        for (i, cell) in cells.into_iter().enumerate() {
+            board.cells[i % 8][i / 8] = cell;
+        }
+        board
+    })
+}
+
+/// Legal moves from the starting position, up to `plies` of them. The sequences stop early when
+/// the game ends.
+pub fn moves(plies: usize) -> impl Strategy<Value = Vec<Coord>> {
+    vec(any::<u8>(), 0..=plies).prop_map(|choices| {
+        let (mut board, mut color) = (Board::new(), Color::Black);
+        let mut moves = Vec::with_capacity(choices.len());
+        for choice in choices {
+            let mut legal_moves = board.legal_moves(color);
+            if legal_moves.is_empty() {
+                color = color.flip();
+                legal_moves = board.legal_moves(color);
+                if legal_moves.is_empty() {
+                    break;
+                }
+            }
+            let legal_move = legal_moves[usize::from(choice) % legal_moves.len()];
+            board = legal_move.apply(board);
+            moves.push(legal_move.position);
+            color = color.flip();
+        }
+        moves
+    })
+}
+
+/// Games of up to `plies` moves, finished or not, between named players. The final disks of
+/// Black are given for the finished ones.
+pub fn games(plies: usize) -> impl Strategy<Value = GameRecord> {
+    (moves(plies), "[A-Za-z][A-Za-z ]{0,11}", "[A-Za-z][A-Za-z ]{0,11}").prop_map(|(moves, black, white)| {
+        let mut game = GameRecord { black, white, moves, ..GameRecord::default() };
+        let positions = game.positions().expect("the moves are legal");
+        let &(board, color) = positions.last().expect("the last position is always there");
+        if board.legal_moves(color).is_empty() && board.legal_moves(color.flip()).is_empty() {
+            game.black_disks = Some(board.count(Color::Black) as u8);
+        }
+        game
+    })
+}
+
+/// Positions reached after up to `plies` moves, with the player to move. The player may have
+/// no legal move when the game is over.
+pub fn positions(plies: usize) -> impl Strategy<Value = (Board, Color)> {
+    moves(plies).prop_map(|moves| {
+        let game = GameRecord { moves, ..GameRecord::default() };
+        *game.positions().expect("the moves are legal").last().expect("the last position is always there")
+    })
+}
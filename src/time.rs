@@ -0,0 +1,7 @@
+//! The clock of the searches. Browsers give WebAssembly no clock the standard library can read,
+//! so with the `wasm` feature it comes from JavaScript there.
+
+#[cfg(not(feature = "wasm"))]
+pub use std::time::Instant;
+#[cfg(feature = "wasm")]
+pub use web_time::Instant;
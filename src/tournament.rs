@@ -0,0 +1,343 @@
+//! This module runs tournaments between several engines, in rounds where every player meets one
+//! opponent in a short [match](../arena/index.html), each opening being played with both colors.
+//!
+//! Pairings are either a round robin, every player meeting every other once, or Swiss, players
+//! with the same points meeting each other without rematches. With an odd number of players, one
+//! of them sits out each round and scores a bye, counted as winning all the games of a match.
+//! Standings are ordered by points, then by the disk differential of all the games.
+//!
+//! A tournament is saved after each round in a compact binary format, see
+//! [`Tournament::write`](struct.Tournament.html#method.write), so that it can be stopped and
+//! resumed.
+
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use arena::{self, Options};
+use engine::Engine;
+use files::{self, invalid, invalid_input, read_bytes, read_u16};
+use game::Color;
+use record::GameRecord;
+
+const MAGIC: &[u8; 6] = b"RRTOUR";
+const VERSION: u16 = 1;
+
+/// How players are paired in each round.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Pairing {
+    RoundRobin,
+    Swiss
+}
+impl FromStr for Pairing {
+    type Err = ();
+
+    /// Parses `round-robin` or `swiss`.
+    fn from_str(text: &str) -> Result<Pairing, ()> {
+        match text {
+            "round-robin" => Ok(Pairing::RoundRobin),
+            "swiss" => Ok(Pairing::Swiss),
+            _ => Err(())
+        }
+    }
+}
+
+/// A game of a tournament, the players given by their index.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Game {
+    /// Round of the game, from 0.
+    pub round: u32,
+    pub black: usize,
+    pub white: usize,
+    /// Final disk differential for Black, 64 or -64 for a forfeit.
+    pub difference: i8
+}
+
+/// The place of a player in a tournament.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Standing {
+    pub player: usize,
+    /// A point for each win, half a point for each draw, including byes.
+    pub points: f64,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    pub byes: u32,
+    /// Disk differential of all the games, breaking ties in points.
+    pub disks: i32
+}
+
+/// A tournament, with the results of the rounds played.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tournament {
+    /// Names of the players, like the engines given on the command line.
+    pub players: Vec<String>,
+    pub pairing: Pairing,
+    /// Number of rounds.
+    pub rounds: u32,
+    /// Games between two players in a round, rounded up to an even number.
+    pub games: u32,
+    /// Seed of the random openings.
+    pub seed: u64,
+    /// Games played, in order.
+    pub results: Vec<Game>,
+    /// Players who sat out, with the round.
+    pub byes: Vec<(u32, usize)>,
+    /// Number of rounds played.
+    pub completed: u32
+}
+impl Tournament {
+    /// A tournament not started yet. A round robin has a round per opponent, a Swiss
+    /// tournament as many rounds as needed to single out a winner of a knockout.
+    pub fn new(players: Vec<String>, pairing: Pairing, games: u32) -> Tournament {
+        let count = players.len() as u32;
+        let rounds = match pairing {
+            Pairing::RoundRobin => count.saturating_sub(1) + count % 2,
+            Pairing::Swiss => 32 - count.saturating_sub(1).leading_zeros()
+        };
+        Tournament { players, pairing, rounds, games, seed: 0, results: Vec::new(), byes: Vec::new(), completed: 0 }
+    }
+
+    /// Whether all the rounds have been played.
+    pub fn is_over(&self) -> bool {
+        self.completed >= self.rounds
+    }
+
+    /// The pairings of the next round, `None` for the player sitting out.
+    pub fn pairings(&self) -> Vec<(usize, Option<usize>)> {
+        match self.pairing {
+            Pairing::RoundRobin => self.round_robin(),
+            Pairing::Swiss => self.swiss()
+        }
+    }
+
+    /// Plays the next round with the engines of the players, in the same order, and returns its
+    /// games. Each match starts from random openings depending on the seed, the round and the
+    /// pairing, so a resumed tournament plays the games it would have played.
+    pub fn play_round<E: Engine>(&mut self, engines: &mut [E], options: &Options) -> Vec<GameRecord> {
+        let round = self.completed;
+        let mut records = Vec::new();
+        for (i, (first, second)) in self.pairings().into_iter().enumerate() {
+            let second = match second {
+                Some(second) => second,
+                None => {
+                    self.byes.push((round, first));
+                    continue;
+                }
+            };
+            let seed = self.seed.wrapping_add(u64::from(round) * 1000 + i as u64);
+            let options = Options { games: self.games, seed, sprt: None, ..*options };
+            let (a, b) = pair(engines, first, second);
+            let report = arena::run(&options, a, b);
+            for mut record in report.games {
+                let (black, white) = if record.black == "first" { (first, second) } else { (second, first) };
+                self.results.push(Game { round, black, white, difference: difference(&record) });
+                record.black = self.players[black].clone();
+                record.white = self.players[white].clone();
+                records.push(record);
+            }
+        }
+        self.completed += 1;
+        records
+    }
+
+    /// The players ordered by points, then by disk differential.
+    pub fn standings(&self) -> Vec<Standing> {
+        let mut standings = (0..self.players.len()).map(|player| {
+            Standing { player, points: 0.0, wins: 0, draws: 0, losses: 0, byes: 0, disks: 0 }
+        }).collect::<Vec<_>>();
+        for game in &self.results {
+            for &(player, difference) in &[(game.black, i32::from(game.difference)), (game.white, -i32::from(game.difference))] {
+                let standing = &mut standings[player];
+                standing.disks += difference;
+                match difference.signum() {
+                    1 => standing.wins += 1,
+                    0 => standing.draws += 1,
+                    _ => standing.losses += 1
+                }
+            }
+        }
+        for &(_, player) in &self.byes {
+            standings[player].byes += 1;
+        }
+        let games = self.games + self.games % 2;
+        for standing in &mut standings {
+            standing.points = f64::from(standing.wins) + 0.5 * f64::from(standing.draws) + f64::from(standing.byes * games);
+        }
+        standings.sort_by(|a, b| b.points.total_cmp(&a.points).then(b.disks.cmp(&a.disks)).then(a.player.cmp(&b.player)));
+        standings
+    }
+
+    /// Writes the standings as a table with a line per player.
+    pub fn format_standings(&self) -> String {
+        let width = self.players.iter().map(String::len).max().unwrap_or(0).max(6);
+        let mut text = format!("  # {:width$} points  wins draws losses byes  disks\n", "player", width = width);
+        for (i, standing) in self.standings().iter().enumerate() {
+            text += &format!("{:>3} {:width$} {:>6.1} {:>5} {:>5} {:>6} {:>4} {:>+6}\n", i + 1, self.players[standing.player], standing.points,
+                standing.wins, standing.draws, standing.losses, standing.byes, standing.disks, width = width);
+        }
+        text
+    }
+
+    /// Reads a tournament written by [`write`](#method.write).
+    ///
+    /// The format starts with the magic bytes `RRTOUR` and a 16 bit version. Then come the
+    /// pairing as a byte, 0 for a round robin and 1 for Swiss, the 16 bit number of rounds and
+    /// of games per match, the 64 bit seed and the 16 bit number of rounds played. The players
+    /// follow as their 16 bit number and each as its 16 bit length and UTF-8 bytes, then the byes
+    /// as their 16 bit number and each as its 16 bit round and player, and the games as their 32
+    /// bit number and each as its 16 bit round, black and white player and its disk differential
+    /// as a signed byte. All integers are little endian.
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Tournament> {
+        let mut header = [0; 8];
+        reader.read_exact(&mut header)?;
+        if &header[..6] != MAGIC {
+            return Err(invalid("not a tournament"));
+        }
+        if u16::from_le_bytes([header[6], header[7]]) != VERSION {
+            return Err(invalid("unsupported tournament version"));
+        }
+        let pairing = match read_bytes::<_, 1>(&mut reader)?[0] {
+            0 => Pairing::RoundRobin,
+            1 => Pairing::Swiss,
+            _ => return Err(invalid("unknown pairing"))
+        };
+        let rounds = u32::from(read_u16(&mut reader)?);
+        let games = u32::from(read_u16(&mut reader)?);
+        let seed = u64::from_le_bytes(read_bytes(&mut reader)?);
+        let completed = u32::from(read_u16(&mut reader)?);
+        let players = (0..read_u16(&mut reader)?).map(|_| {
+            let mut bytes = vec![0; usize::from(read_u16(&mut reader)?)];
+            reader.read_exact(&mut bytes)?;
+            String::from_utf8(bytes).map_err(|_| invalid("invalid player name"))
+        }).collect::<io::Result<Vec<_>>>()?;
+        let player = |index: u16| if usize::from(index) < players.len() { Ok(usize::from(index)) } else { Err(invalid("unknown player")) };
+        let byes = (0..read_u16(&mut reader)?).map(|_| {
+            Ok((u32::from(read_u16(&mut reader)?), player(read_u16(&mut reader)?)?))
+        }).collect::<io::Result<Vec<_>>>()?;
+        let results = (0..u32::from_le_bytes(read_bytes(&mut reader)?)).map(|_| {
+            let round = u32::from(read_u16(&mut reader)?);
+            let black = player(read_u16(&mut reader)?)?;
+            let white = player(read_u16(&mut reader)?)?;
+            let difference = read_bytes::<_, 1>(&mut reader)?[0] as i8;
+            Ok(Game { round, black, white, difference })
+        }).collect::<io::Result<Vec<_>>>()?;
+        Ok(Tournament { players, pairing, rounds, games, seed, results, byes, completed })
+    }
+
+    /// Writes the tournament in the format accepted by [`read`](#method.read).
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&[if self.pairing == Pairing::RoundRobin { 0 } else { 1 }])?;
+        write_u16(&mut writer, self.rounds as usize, "rounds")?;
+        write_u16(&mut writer, self.games as usize, "games")?;
+        writer.write_all(&self.seed.to_le_bytes())?;
+        write_u16(&mut writer, self.completed as usize, "rounds")?;
+        write_u16(&mut writer, self.players.len(), "players")?;
+        for player in &self.players {
+            write_u16(&mut writer, player.len(), "bytes in a player name")?;
+            writer.write_all(player.as_bytes())?;
+        }
+        write_u16(&mut writer, self.byes.len(), "byes")?;
+        for &(round, player) in &self.byes {
+            write_u16(&mut writer, round as usize, "rounds")?;
+            write_u16(&mut writer, player, "players")?;
+        }
+        let results = u32::try_from(self.results.len()).map_err(|_| invalid_input("too many games"))?;
+        writer.write_all(&results.to_le_bytes())?;
+        for game in &self.results {
+            write_u16(&mut writer, game.round as usize, "rounds")?;
+            write_u16(&mut writer, game.black, "players")?;
+            write_u16(&mut writer, game.white, "players")?;
+            writer.write_all(&[game.difference as u8])?;
+        }
+        Ok(())
+    }
+
+    /// Reads a tournament from a file.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Tournament> {
+        Tournament::read(BufReader::new(File::open(path)?))
+    }
+
+    /// Writes the tournament to a file, replacing the previous save atomically.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        files::save_atomically(path, |writer| self.write(writer))
+    }
+
+    // The circle method: the first player stays in place while the others rotate by a place
+    // each round, and players facing each other in the circle meet.
+    fn round_robin(&self) -> Vec<(usize, Option<usize>)> {
+        let mut circle = (0..self.players.len()).map(Some).collect::<Vec<_>>();
+        if circle.len() % 2 == 1 {
+            circle.push(None);
+        }
+        let n = circle.len();
+        if n < 2 {
+            return Vec::new();
+        }
+        circle[1..].rotate_right(self.completed as usize % (n - 1));
+        (0..n / 2).map(|i| (circle[i], circle[n - 1 - i])).filter_map(|pair| match pair {
+            (Some(a), b) => Some((a, b)),
+            (None, Some(b)) => Some((b, None)),
+            (None, None) => None
+        }).collect()
+    }
+
+    // Pairs the players in order of standing with the next one they haven't met, the last one
+    // without a bye sitting out when they are odd.
+    fn swiss(&self) -> Vec<(usize, Option<usize>)> {
+        let mut order = self.standings().iter().map(|x| x.player).collect::<Vec<_>>();
+        let mut bye = None;
+        if order.len() % 2 == 1 {
+            let had_bye = |player: &usize| self.byes.iter().any(|x| x.1 == *player);
+            let i = order.iter().rposition(|x| !had_bye(x)).unwrap_or(order.len() - 1);
+            bye = Some(order.remove(i));
+        }
+        let met = |a: usize, b: usize| self.results.iter().any(|x| (x.black, x.white) == (a, b) || (x.black, x.white) == (b, a));
+        let mut pairings = Vec::new();
+        while !order.is_empty() {
+            let player = order.remove(0);
+            let i = order.iter().position(|&x| !met(player, x)).unwrap_or(0);
+            pairings.push((player, Some(order.remove(i))));
+        }
+        pairings.extend(bye.map(|x| (x, None)));
+        pairings
+    }
+}
+
+// Two distinct engines of a slice.
+fn pair<E>(engines: &mut [E], first: usize, second: usize) -> (&mut E, &mut E) {
+    if first < second {
+        let (left, right) = engines.split_at_mut(second);
+        (&mut left[first], &mut right[0])
+    } else {
+        let (left, right) = engines.split_at_mut(first);
+        (&mut right[0], &mut left[second])
+    }
+}
+
+// The disk differential for Black of a game of a match, from its final position unless forfeited.
+fn difference(record: &GameRecord) -> i8 {
+    let black_disks = i32::from(record.black_disks.unwrap_or(32));
+    if record.tag("termination").is_some() {
+        return (2 * black_disks - 64) as i8;
+    }
+    match record.positions() {
+        Ok(positions) => {
+            let board = positions[positions.len() - 1].0;
+            (board.count(Color::Black) as i32 - board.count(Color::White) as i32) as i8
+        }
+        Err(_) => 0
+    }
+}
+
+// Counts and indices are saved on 16 bits, larger ones are refused rather than truncated.
+fn write_u16<W: Write>(writer: &mut W, number: usize, what: &str) -> io::Result<()> {
+    match u16::try_from(number) {
+        Ok(number) => writer.write_all(&number.to_le_bytes()),
+        Err(_) => Err(invalid_input(&format!("too many {}", what)))
+    }
+}
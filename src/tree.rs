@@ -0,0 +1,144 @@
+//! This module records the tree explored by an [alpha-beta search](../search/fn.alpha_beta_with_options.html),
+//! up to a number of nodes, and writes it in the DOT language of [Graphviz](https://graphviz.org)
+//! to see how the search pruned, like with `dot -Tsvg tree.dot -o tree.svg`.
+//!
+//! Each node shows the move reaching it, the player to move, the depth left, the window it was
+//! searched with and its score, marked as exact, as an upper bound when every move failed low
+//! or as a lower bound when a move failed high. Nodes cut off before all their moves were
+//! searched are drawn in red with the number of moves skipped, and the nodes known without
+//! searching their moves, evaluated or found in the transposition table, as ellipses.
+
+use std::io::{self, Write};
+
+use eval::Score;
+use game::{Color, Coord};
+use record;
+
+/// How the search of a node ended.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// The score was known without searching the moves: the position was evaluated, solved,
+    /// finished or found in the transposition table.
+    Leaf,
+    /// All the moves were searched.
+    Searched,
+    /// A move failed high, and the given number of moves were left unsearched.
+    Cutoff(usize)
+}
+
+/// A position searched.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeNode {
+    /// The index of the parent node, `None` for the root.
+    pub parent: Option<usize>,
+    /// The move reaching the node, `None` for the root.
+    pub position: Option<Coord>,
+    pub color: Color,
+    pub depth: u32,
+    pub alpha: Score,
+    pub beta: Score,
+    /// The score for the player to move, and how the search ended, `None` if it didn't.
+    pub result: Option<(Score, Outcome)>
+}
+
+/// The nodes explored by a search, up to a limit.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SearchTree {
+    /// Number of nodes recorded at most.
+    pub limit: usize,
+    nodes: Vec<TreeNode>,
+    /// The nodes being searched, from the root.
+    stack: Vec<usize>,
+    /// The move about to be searched.
+    next_move: Option<Coord>,
+    truncated: bool
+}
+impl SearchTree {
+    /// An empty tree recording up to `limit` nodes.
+    pub fn new(limit: usize) -> SearchTree {
+        SearchTree { limit, ..SearchTree::default() }
+    }
+
+    /// The nodes recorded, parents before their children.
+    pub fn nodes(&self) -> &[TreeNode] {
+        &self.nodes
+    }
+
+    /// Whether nodes were left out for the limit.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    // Announces the move searched next from the node being searched.
+    pub(crate) fn moving(&mut self, position: Coord) {
+        self.next_move = Some(position);
+    }
+
+    // Starts a node, returning its index, or `None` once the limit is reached.
+    pub(crate) fn open(&mut self, color: Color, depth: u32, alpha: Score, beta: Score) -> Option<usize> {
+        let position = self.next_move.take();
+        if self.nodes.len() >= self.limit {
+            self.truncated = true;
+            return None;
+        }
+        let parent = self.stack.last().cloned();
+        self.nodes.push(TreeNode { parent, position, color, depth, alpha, beta, result: None });
+        self.stack.push(self.nodes.len() - 1);
+        Some(self.nodes.len() - 1)
+    }
+
+    // Ends the node opened last.
+    pub(crate) fn close(&mut self, index: usize, score: Score, outcome: Outcome) {
+        self.nodes[index].result = Some((score, outcome));
+        self.stack.pop();
+    }
+
+    /// The tree in the DOT language.
+    pub fn to_dot(&self) -> String {
+        let mut dot = Vec::new();
+        self.write_dot(&mut dot).expect("writing to memory doesn't fail");
+        String::from_utf8(dot).expect("the tree is written as UTF-8")
+    }
+
+    /// Writes the tree in the DOT language.
+    pub fn write_dot<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "digraph search {{")?;
+        writeln!(writer, "    node [shape=box, fontname=\"monospace\"];")?;
+        if self.truncated {
+            writeln!(writer, "    label=\"truncated at {} nodes\";", self.limit)?;
+        }
+        for (i, node) in self.nodes.iter().enumerate() {
+            let reached = node.position.map(record::format_move).unwrap_or_else(|| "root".to_string());
+            let mut label = format!("{} {} depth {}\\n[{}, {}]", reached, node.color, node.depth, bound(node.alpha), bound(node.beta));
+            let mut style = String::new();
+            match node.result {
+                Some((score, outcome)) => {
+                    let kind = if score <= node.alpha { "<=" } else if score >= node.beta { ">=" } else { "=" };
+                    label += &format!("\\n{} {}", kind, bound(score));
+                    match outcome {
+                        Outcome::Leaf => style = ", shape=ellipse".to_string(),
+                        Outcome::Searched => {}
+                        Outcome::Cutoff(skipped) => {
+                            label += &format!("\\ncutoff, {} skipped", skipped);
+                            style = ", color=red, fontcolor=red".to_string();
+                        }
+                    }
+                }
+                None => style = ", style=dashed".to_string()
+            }
+            writeln!(writer, "    n{} [label=\"{}\"{}];", i, label, style)?;
+            if let Some(parent) = node.parent {
+                writeln!(writer, "    n{} -> n{};", parent, i)?;
+            }
+        }
+        writeln!(writer, "}}")
+    }
+}
+
+fn bound(score: Score) -> String {
+    match score {
+        Score::MAX => "inf".to_string(),
+        x if x == -Score::MAX => "-inf".to_string(),
+        x => x.to_string()
+    }
+}
@@ -0,0 +1,173 @@
+//! This module contains the transposition table: a cache of search results shared by the
+//! positions reached through different move orders.
+//!
+//! The table is an array of buckets of a few entries, and a position may be stored in any entry
+//! of the bucket its hash selects. When the bucket is full, the
+//! [replacement scheme](enum.Replacement.html) decides which entry to overwrite. Entries written
+//! by earlier searches are replaced first: every search starts a new generation with
+//! [`new_search`](struct.TranspositionTable.html#method.new_search), and entries of older
+//! generations are considered stale.
+
+use std::io;
+use std::mem;
+
+use engine::Reservation;
+use eval::Score;
+use game::Coord;
+
+/// Number of entries in a bucket.
+pub const BUCKET_SIZE: usize = 4;
+
+/// How the score of an entry relates to the exact score of the position.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Bound {
+    Exact,
+    /// The score is at least this much.
+    Lower,
+    /// The score is at most this much.
+    Upper
+}
+
+/// Chooses the entry of a full bucket overwritten by a new one.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Replacement {
+    /// Keeps the deepest results of the current search. The last entry of each bucket is always
+    /// replaced, so shallow results near the leaves still get stored.
+    DepthPreferred,
+    /// Always stores the new entry, over the stale or shallowest one.
+    AlwaysReplace
+}
+
+/// A stored search result.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Entry {
+    pub key: u64,
+    pub best_move: Option<Coord>,
+    pub score: Score,
+    /// Remaining depth of the search producing the score.
+    pub depth: u8,
+    pub bound: Bound,
+    generation: u8
+}
+
+/// A fixed-size hash table of search results.
+pub struct TranspositionTable {
+    entries: Vec<Option<Entry>>,
+    replacement: Replacement,
+    generation: u8,
+    /// The memory of the entries, when taken from the budget of the process.
+    reservation: Reservation
+}
+impl TranspositionTable {
+    /// Creates a table taking about `mib` MiB of memory, with room for at least one bucket.
+    pub fn new(mib: usize, replacement: Replacement) -> TranspositionTable {
+        TranspositionTable { entries: vec![None; entries(mib)], replacement, generation: 0, reservation: Reservation::default() }
+    }
+
+    /// Creates a table like [`new`](#method.new), taking its memory from the
+    /// [budget](../engine/fn.set_memory_cap.html) of the process. Fails if the budget is exceeded.
+    pub fn reserved(mib: usize, replacement: Replacement) -> io::Result<TranspositionTable> {
+        let reservation = Reservation::new(entries(mib) * mem::size_of::<Option<Entry>>(), "transposition table")?;
+        Ok(TranspositionTable { reservation, ..TranspositionTable::new(mib, replacement) })
+    }
+
+    /// Empties the table and changes its size, taking its memory from the budget of the process.
+    /// Fails keeping the table as it is if the budget is exceeded.
+    pub fn resize(&mut self, mib: usize) -> io::Result<()> {
+        let length = entries(mib);
+        self.reservation.resize(length * mem::size_of::<Option<Entry>>(), "transposition table")?;
+        self.entries = Vec::new();
+        self.entries = vec![None; length];
+        self.generation = 0;
+        Ok(())
+    }
+
+    /// Number of entries the table can hold.
+    pub fn capacity(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// The memory taken by the entries, in bytes.
+    pub fn memory_usage(&self) -> usize {
+        self.entries.capacity() * mem::size_of::<Option<Entry>>()
+    }
+
+    /// Removes every entry.
+    pub fn clear(&mut self) {
+        for entry in &mut self.entries {
+            *entry = None;
+        }
+        self.generation = 0;
+    }
+
+    /// Starts a new generation, making the entries of earlier searches stale.
+    pub fn new_search(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    /// Finds the entry of a position.
+    pub fn probe(&self, key: u64) -> Option<Entry> {
+        self.bucket(key).iter().filter_map(|&x| x).find(|x| x.key == key)
+    }
+
+    /// Stores a search result, keeping the best move already known for the position when the
+    /// new result has none.
+    pub fn store(&mut self, key: u64, best_move: Option<Coord>, score: Score, depth: u32, bound: Bound) {
+        let generation = self.generation;
+        let replacement = self.replacement;
+        let bucket = self.bucket_mut(key);
+        let mut entry = Entry { key, best_move, score, depth: depth.min(255) as u8, bound, generation };
+        let slot = match bucket.iter().position(|x| x.map(|x| x.key) == Some(key)) {
+            Some(slot) => {
+                let old = bucket[slot].expect("the slot holds the key");
+                if replacement == Replacement::DepthPreferred && old.generation == generation && old.depth > entry.depth && bound != Bound::Exact {
+                    return;
+                }
+                entry.best_move = entry.best_move.or(old.best_move);
+                slot
+            }
+            None => match bucket.iter().position(|x| x.is_none()) {
+                Some(slot) => slot,
+                None => {
+                    // stale entries first, then the shallowest
+                    let value = |x: &Entry| (x.generation == generation, x.depth);
+                    let victim = (0..BUCKET_SIZE).min_by_key(|&i| value(&bucket[i].expect("the bucket is full"))).expect("buckets are not empty");
+                    let kept = bucket[victim].expect("the bucket is full");
+                    if replacement == Replacement::DepthPreferred && kept.generation == generation && kept.depth > entry.depth {
+                        BUCKET_SIZE - 1
+                    } else {
+                        victim
+                    }
+                }
+            }
+        };
+        bucket[slot] = Some(entry);
+    }
+
+    /// Permille of the first thousand entries used by the current search, as reported by the
+    /// usual engine protocols.
+    pub fn hashfull(&self) -> u32 {
+        let sample = &self.entries[..self.entries.len().min(1000)];
+        let used = sample.iter().filter(|x| x.map(|x| x.generation) == Some(self.generation)).count();
+        (used * 1000 / sample.len()) as u32
+    }
+
+    fn bucket(&self, key: u64) -> &[Option<Entry>] {
+        let start = self.index(key);
+        &self.entries[start..start + BUCKET_SIZE]
+    }
+
+    fn bucket_mut(&mut self, key: u64) -> &mut [Option<Entry>] {
+        let start = self.index(key);
+        &mut self.entries[start..start + BUCKET_SIZE]
+    }
+
+    fn index(&self, key: u64) -> usize {
+        (key % (self.entries.len() / BUCKET_SIZE) as u64) as usize * BUCKET_SIZE
+    }
+}
+
+// The number of entries of a table of about `mib` MiB, at least a bucket.
+fn entries(mib: usize) -> usize {
+    (mib.saturating_mul(1024 * 1024) / (mem::size_of::<Option<Entry>>() * BUCKET_SIZE)).max(1) * BUCKET_SIZE
+}
@@ -0,0 +1,534 @@
+//! This module is a full-screen interface to play against an engine in a terminal. The player
+//! moves a cursor over the board with the arrow keys and places a disk with Enter, while a panel
+//! beside the board shows the disk counts, the clocks and the evaluation of the engine. The
+//! mouse works too: clicking a cell places a disk there, and hovering over a legal move shows
+//! the disks it would flip.
+//!
+//! The state of the interface, [`App`](struct.App.html), is kept apart from the terminal, which
+//! [`run`](fn.run.html) drives with crossterm, so that it can be tested without one.
+//!
+//! Played games are reviewed full-screen with [`view`](fn.view.html), stepping through the moves
+//! of a [`Viewer`](../viewer/struct.Viewer.html) with the arrow keys.
+
+use std::cmp::Ordering;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+
+use engine::{Engine, Limits};
+use eval::Score;
+use game::{Charset, Color, Coord};
+use record;
+use search::WIN;
+use session::GameSession;
+use terminal::Theme;
+use viewer::{Command, Viewer};
+
+// Columns of a cell, of the row labels before the board and of the board with its labels.
+const CELL_WIDTH: usize = 3;
+const LABEL_WIDTH: usize = 2;
+const PANEL_COLUMN: usize = LABEL_WIDTH + 8 * CELL_WIDTH + 4;
+
+/// What the player asks for.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    /// Places a disk under the cursor.
+    Place,
+    /// Takes back the last move of the player, with the answers of the engine.
+    Undo,
+    Quit,
+    /// Places a disk on a cell clicked.
+    Click(Coord),
+    /// The mouse moved over a cell, or off the board.
+    Hover(Option<Coord>)
+}
+
+/// The keys of the actions of the player.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keymap {
+    bindings: Vec<(KeyCode, Action)>
+}
+impl Default for Keymap {
+    /// The arrows move, Enter and space place, `u` undoes, `q` and Esc quit.
+    fn default() -> Keymap {
+        Keymap {
+            bindings: vec![
+                (KeyCode::Up, Action::Up),
+                (KeyCode::Down, Action::Down),
+                (KeyCode::Left, Action::Left),
+                (KeyCode::Right, Action::Right),
+                (KeyCode::Enter, Action::Place),
+                (KeyCode::Char(' '), Action::Place),
+                (KeyCode::Char('u'), Action::Undo),
+                (KeyCode::Char('q'), Action::Quit),
+                (KeyCode::Esc, Action::Quit)
+            ]
+        }
+    }
+}
+impl Keymap {
+    /// Binds a key, a character or one of `up`, `down`, `left`, `right`, `enter`, `space`, `tab`,
+    /// `backspace`, `home`, `end` and `esc`, to an action: `up`, `down`, `left`, `right`, `place`,
+    /// `undo` or `quit`. Other keys of the action keep working.
+    pub fn bind(&mut self, action: &str, key: &str) -> io::Result<()> {
+        let action = match action {
+            "up" => Action::Up,
+            "down" => Action::Down,
+            "left" => Action::Left,
+            "right" => Action::Right,
+            "place" => Action::Place,
+            "undo" => Action::Undo,
+            "quit" => Action::Quit,
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("unknown action: {}", action)))
+        };
+        let code = match key {
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "enter" => KeyCode::Enter,
+            "space" => KeyCode::Char(' '),
+            "tab" => KeyCode::Tab,
+            "backspace" => KeyCode::Backspace,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "esc" => KeyCode::Esc,
+            _ if key.chars().count() == 1 => KeyCode::Char(key.chars().next().expect("one character")),
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("unknown key: {}", key)))
+        };
+        self.bindings.retain(|x| x.0 != code);
+        self.bindings.push((code, action));
+        Ok(())
+    }
+
+    /// The action of a key, if it has one.
+    pub fn action(&self, code: KeyCode) -> Option<Action> {
+        self.bindings.iter().find(|x| x.0 == code).map(|x| x.1)
+    }
+}
+
+/// A game between the player and an engine.
+pub struct App<E: Engine> {
+    pub engine: E,
+    pub limits: Limits,
+    /// The color of the player.
+    pub human: Color,
+    pub charset: Charset,
+    /// Colors of the board.
+    pub theme: Theme,
+    /// Marks the legal moves of the player.
+    pub hints: bool,
+    /// Underlines the disk placed by the last move and the disks it flipped, the placed one in
+    /// bold.
+    pub last_move: bool,
+    pub keys: Keymap,
+    session: GameSession,
+    cursor: Coord,
+    // the cell under the mouse
+    hover: Option<Coord>,
+    // score of the last search of the engine, for Black
+    evaluation: Option<Score>,
+    message: String,
+    // when the player to move started thinking
+    turn_start: Instant,
+    quit: bool
+}
+impl<E: Engine> App<E> {
+    /// Continues a session, the cursor starting on d3.
+    pub fn new(engine: E, session: GameSession, human: Color) -> App<E> {
+        App {
+            engine,
+            limits: Limits::default(),
+            human,
+            charset: Charset::default(),
+            theme: Theme::default(),
+            hints: true,
+            last_move: true,
+            keys: Keymap::default(),
+            session,
+            cursor: (3, 5),
+            hover: None,
+            evaluation: None,
+            message: String::new(),
+            turn_start: Instant::now(),
+            quit: false
+        }
+    }
+
+    pub fn session(&self) -> &GameSession {
+        &self.session
+    }
+
+    pub fn cursor(&self) -> Coord {
+        self.cursor
+    }
+
+    /// The score of the last search of the engine, for Black.
+    pub fn evaluation(&self) -> Option<Score> {
+        self.evaluation
+    }
+
+    /// The outcome of the last action, like an illegal move.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Whether the player asked to quit.
+    pub fn is_done(&self) -> bool {
+        self.quit
+    }
+
+    /// Whether the engine is to move.
+    pub fn is_engine_turn(&self) -> bool {
+        !self.session.is_over() && self.session.color() != self.human
+    }
+
+    /// Carries out an action of the player.
+    pub fn handle(&mut self, action: Action) {
+        let (x, y) = self.cursor;
+        // row 1 is on top, so moving up goes towards row 1
+        match action {
+            Action::Up => self.cursor = (x, (y + 1).min(7)),
+            Action::Down => self.cursor = (x, (y - 1).max(0)),
+            Action::Left => self.cursor = ((x - 1).max(0), y),
+            Action::Right => self.cursor = ((x + 1).min(7), y),
+            Action::Place => self.place(self.cursor),
+            Action::Undo => self.undo(),
+            Action::Quit => self.quit = true,
+            Action::Click(position) => {
+                self.cursor = position;
+                self.place(position);
+            }
+            Action::Hover(position) => self.hover = position
+        }
+    }
+
+    /// The disks the move under the mouse would flip, if it is a legal move of the player.
+    pub fn preview(&self) -> Vec<Coord> {
+        if self.session.is_over() || self.session.color() != self.human {
+            return Vec::new();
+        }
+        let moves = self.session.board().legal_moves(self.human);
+        match moves.iter().find(|x| Some(x.position) == self.hover) {
+            Some(legal_move) => legal_move.flipped(),
+            None => Vec::new()
+        }
+    }
+
+    /// Plays a move of the player, if it is their turn.
+    pub fn place(&mut self, position: Coord) {
+        if self.session.is_over() || self.session.color() != self.human {
+            return;
+        }
+        match self.session.play(position, self.turn_start.elapsed()) {
+            Ok(()) => {
+                self.message.clear();
+                self.turn_start = Instant::now();
+            }
+            Err(_) => self.message = format!("{} is not a legal move", record::format_move(position))
+        }
+    }
+
+    fn undo(&mut self) {
+        if !self.session.history().iter().any(|x| x.color == self.human) {
+            self.message = "no move to take back".to_string();
+            return;
+        }
+        while let Some(turn) = self.session.undo() {
+            if turn.color == self.human {
+                break;
+            }
+        }
+        self.message.clear();
+        self.turn_start = Instant::now();
+    }
+
+    /// Lets the engine play if it is its turn, and returns whether it played.
+    pub fn engine_turn(&mut self) -> bool {
+        if !self.is_engine_turn() {
+            return false;
+        }
+        let color = self.session.color();
+        let result = self.engine.best_move(self.session.board(), color, &self.limits);
+        let position = match result.best {
+            Some(position) if self.session.play(position, result.time).is_ok() => position,
+            _ => {
+                self.message = "the engine found no legal move".to_string();
+                self.quit = true;
+                return false;
+            }
+        };
+        self.evaluation = Some(if color == Color::Black { result.score } else { -result.score });
+        self.message = format!("{} played {}", color, record::format_move(position));
+        self.turn_start = Instant::now();
+        true
+    }
+
+    /// The time left to a player, or the time they spent in untimed games, counting the move
+    /// being thought about.
+    pub fn clock(&self, color: Color) -> Duration {
+        let thinking = if color == self.session.color() && !self.session.is_over() { self.turn_start.elapsed() } else { Duration::default() };
+        match self.session.clock(color) {
+            Some(clock) => clock.remaining.checked_sub(thinking).unwrap_or_default(),
+            None => self.session.history().iter().filter(|x| x.color == color).map(|x| x.time).sum::<Duration>() + thinking
+        }
+    }
+
+    /// The text of the screen, line by line: the board with its labels on the left and the
+    /// panel on the right.
+    pub fn screen(&self) -> Vec<String> {
+        let mut lines = vec![format!("{:1$}", "", LABEL_WIDTH) + &*"abcdefgh".chars().map(|x| format!(" {} ", x)).collect::<String>()];
+        for row in 0..8 {
+            let mut line = format!("{:<1$}", row + 1, LABEL_WIDTH);
+            for x in 0..8 {
+                line += &self.cell_text((x, 7 - row as i8));
+            }
+            lines.push(line);
+        }
+        let board = self.session.board();
+        let mut panel = Vec::new();
+        for &color in &[Color::Black, Color::White] {
+            let who = if color == self.human { "you" } else { "engine" };
+            let who = format!("{} ({})", color, who);
+            panel.push(format!("{:<14} {:>2}  {}", who, board.count(color), format_duration(self.clock(color))));
+        }
+        panel.push(String::new());
+        if self.session.is_over() {
+            let (black, white) = (board.count(Color::Black), board.count(Color::White));
+            let winner = match black.cmp(&white) {
+                Ordering::Greater => "Black wins",
+                Ordering::Less => "White wins",
+                Ordering::Equal => "Draw"
+            };
+            panel.push(format!("Game over: {}-{}, {}", black, white, winner));
+        } else {
+            panel.push(format!("{} to move", self.session.color()));
+        }
+        panel.push(match self.evaluation {
+            Some(score) => format!("Evaluation: {}", format_score(score)),
+            None => "Evaluation: -".to_string()
+        });
+        panel.push(self.message.clone());
+        let preview = self.preview();
+        panel.push(match self.hover {
+            Some(position) if !preview.is_empty() => format!("{} flips {}", record::format_move(position), preview.len()),
+            _ => String::new()
+        });
+        panel.push(String::new());
+        panel.push("Arrows move, Enter or a click places".to_string());
+        panel.push("u undoes, q quits".to_string());
+        // board lines are all as wide, whatever the width of the characters of the disks
+        for (i, text) in panel.into_iter().enumerate() {
+            if i + 1 < lines.len() {
+                lines[i + 1] += &format!("{:1$}{2}", "", PANEL_COLUMN - LABEL_WIDTH - 8 * CELL_WIDTH, text);
+            } else {
+                lines.push(format!("{:1$}{2}", "", PANEL_COLUMN, text));
+            }
+        }
+        lines
+    }
+
+    /// The text of a cell, `CELL_WIDTH` columns wide.
+    pub fn cell_text(&self, position: Coord) -> String {
+        let (x, y) = position;
+        let board = self.session.board();
+        let hint = self.hints && self.session.color() == self.human && board.legal_moves(self.human).iter().any(|x| x.position == position);
+        disk_text(board.cells[x as usize][y as usize], hint, self.charset).to_string()
+    }
+}
+
+/// The column and the row of the screen where a cell is drawn.
+pub fn screen_position(position: Coord) -> (u16, u16) {
+    let (x, y) = position;
+    ((LABEL_WIDTH + x as usize * CELL_WIDTH) as u16, (8 - y) as u16)
+}
+
+/// The cell drawn at a column and a row of the screen, if any.
+pub fn screen_cell(column: u16, row: u16) -> Option<Coord> {
+    let column = usize::from(column).checked_sub(LABEL_WIDTH)?;
+    if column >= 8 * CELL_WIDTH || !(1..=8).contains(&row) {
+        return None;
+    }
+    Some(((column / CELL_WIDTH) as i8, 8 - row as i8))
+}
+
+/// Plays in the terminal until the player quits, restoring the terminal afterwards.
+pub fn run<E: Engine>(app: &mut App<E>) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    terminal::enable_raw_mode()?;
+    let result = execute!(stdout, EnterAlternateScreen, EnableMouseCapture, Hide).and_then(|_| event_loop(app, &mut stdout));
+    let restored = execute!(stdout, Show, DisableMouseCapture, LeaveAlternateScreen);
+    terminal::disable_raw_mode()?;
+    result.and(restored)
+}
+
+fn event_loop<E: Engine, W: Write>(app: &mut App<E>, out: &mut W) -> io::Result<()> {
+    while !app.is_done() {
+        draw(app, out)?;
+        if app.engine_turn() {
+            continue;
+        }
+        // wakes up regularly to update the clocks
+        if event::poll(Duration::from_millis(200))? {
+            let action = match event::read()? {
+                Event::Key(key) => action(&app.keys, key),
+                Event::Mouse(mouse) => mouse_action(mouse),
+                _ => None
+            };
+            if let Some(action) = action {
+                app.handle(action);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Reviews a game in the terminal until the reviewer quits, restoring the terminal afterwards.
+///
+/// The right and left arrows step through the moves, Home and End go to the start and the end,
+/// a number followed by Enter jumps to that move, `f` flips the board, `v` shows or hides the
+/// evaluation and `q` quits.
+pub fn view<E: Engine>(viewer: &mut Viewer<E>) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    terminal::enable_raw_mode()?;
+    let result = execute!(stdout, EnterAlternateScreen, Hide).and_then(|_| view_loop(viewer, &mut stdout));
+    let restored = execute!(stdout, Show, LeaveAlternateScreen);
+    terminal::disable_raw_mode()?;
+    result.and(restored)
+}
+
+fn view_loop<E: Engine, W: Write>(viewer: &mut Viewer<E>, out: &mut W) -> io::Result<()> {
+    // digits typed before Enter
+    let mut number = String::new();
+    while !viewer.is_done() {
+        queue!(out, Clear(ClearType::All))?;
+        let text = viewer.draw();
+        let help = format!("Arrows step, Home and End, number and Enter jumps: {}", number);
+        for (row, line) in text.lines().chain(["", &help, "f flips, v shows the evaluation, q quits"]).enumerate() {
+            queue!(out, MoveTo(0, row as u16), Print(line))?;
+        }
+        out.flush()?;
+        let key = match event::read()? {
+            Event::Key(key) if key.kind != KeyEventKind::Release => key,
+            _ => continue
+        };
+        let command = match key.code {
+            KeyCode::Char(digit @ '0'..='9') => {
+                number.push(digit);
+                None
+            }
+            KeyCode::Backspace => {
+                number.pop();
+                None
+            }
+            KeyCode::Enter => number.drain(..).as_str().parse().ok().map(Command::Jump),
+            KeyCode::Right | KeyCode::Char('n') | KeyCode::Char(' ') => Some(Command::Forward),
+            KeyCode::Left | KeyCode::Char('p') => Some(Command::Back),
+            KeyCode::Home => Some(Command::Start),
+            KeyCode::End => Some(Command::End),
+            KeyCode::Char('f') => Some(Command::Flip),
+            KeyCode::Char('v') => Some(Command::Evaluate),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Some(Command::Quit),
+            KeyCode::Char('q') | KeyCode::Esc => Some(Command::Quit),
+            _ => None
+        };
+        if let Some(command) = command {
+            viewer.handle(command);
+        }
+    }
+    Ok(())
+}
+
+fn action(keys: &Keymap, key: KeyEvent) -> Option<Action> {
+    if key.kind == KeyEventKind::Release {
+        return None;
+    }
+    match key.code {
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Some(Action::Quit),
+        code => keys.action(code)
+    }
+}
+
+fn mouse_action(mouse: MouseEvent) -> Option<Action> {
+    let position = screen_cell(mouse.column, mouse.row);
+    match mouse.kind {
+        MouseEventKind::Down(MouseButton::Left) => position.map(Action::Click),
+        MouseEventKind::Moved | MouseEventKind::Drag(_) => Some(Action::Hover(position)),
+        _ => None
+    }
+}
+
+fn draw<E: Engine, W: Write>(app: &App<E>, out: &mut W) -> io::Result<()> {
+    queue!(out, Clear(ClearType::All))?;
+    for (row, line) in app.screen().iter().enumerate() {
+        queue!(out, MoveTo(0, row as u16), Print(line))?;
+    }
+    for x in 0..8 {
+        for y in 0..8 {
+            let (column, row) = screen_position((x, y));
+            queue!(out, MoveTo(column, row), Print(app.theme.paint_cells(&app.cell_text((x, y)))))?;
+        }
+    }
+    if let Some(last) = app.session().last_move().filter(|_| app.last_move) {
+        for position in last.flipped() {
+            let (column, row) = screen_position(position);
+            queue!(out, MoveTo(column, row), SetAttribute(Attribute::Underlined), Print(app.theme.paint_cells(&app.cell_text(position))), SetAttribute(Attribute::Reset))?;
+        }
+        let (column, row) = screen_position(last.position);
+        queue!(out, MoveTo(column, row), SetAttribute(Attribute::Bold), SetAttribute(Attribute::Underlined))?;
+        queue!(out, Print(app.theme.paint_cells(&app.cell_text(last.position))), SetAttribute(Attribute::Reset))?;
+    }
+    // the disks of the previewed move are drawn dimmed in their new color
+    let preview = app.preview();
+    for &position in preview.iter().chain(app.hover.iter().filter(|_| !preview.is_empty())) {
+        let (column, row) = screen_position(position);
+        let disk = disk_text(Some(app.human), false, app.charset);
+        queue!(out, MoveTo(column, row), SetAttribute(Attribute::Dim), Print(app.theme.paint_cells(disk)), SetAttribute(Attribute::Reset))?;
+    }
+    let (column, row) = screen_position(app.cursor());
+    let cursor = app.theme.paint_cells(&app.cell_text(app.cursor()));
+    queue!(out, MoveTo(column, row), SetAttribute(Attribute::Reverse), Print(cursor), SetAttribute(Attribute::Reset))?;
+    out.flush()
+}
+
+// The text of a cell, `CELL_WIDTH` columns wide, `hint` marking an empty cell as a legal move.
+fn disk_text(cell: Option<Color>, hint: bool, charset: Charset) -> &'static str {
+    match (cell, charset) {
+        (Some(Color::Black), Charset::Ascii) => " X ",
+        (Some(Color::White), Charset::Ascii) => " O ",
+        (None, Charset::Ascii) if hint => " + ",
+        (None, Charset::Ascii) => " . ",
+        (Some(Color::Black), Charset::Unicode) => "⚫ ",
+        (Some(Color::White), Charset::Unicode) => "⚪ ",
+        (None, Charset::Unicode) if hint => " · ",
+        (None, Charset::Unicode) => "   "
+    }
+}
+
+// Scores of finished games are shown as their disk differential.
+fn format_score(score: Score) -> String {
+    if score.abs() >= WIN {
+        let diff = score - score.signum() * WIN;
+        match score.signum() {
+            1 => format!("Black wins by {}", diff),
+            _ => format!("White wins by {}", -diff)
+        }
+    } else {
+        format!("{:+} for Black", score)
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
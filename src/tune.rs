@@ -0,0 +1,274 @@
+//! This module fits the weights of the evaluation to the outcome of played games.
+//!
+//! The method is the one popularized by the Texel chess engine: the score of a position is mapped
+//! to an expected result by a sigmoid, and the weights are adjusted by gradient descent to
+//! minimize the mean squared error against the actual results of the games the positions come
+//! from.
+//!
+//! Parameters that are not evaluation weights are tuned with [`spsa`](fn.spsa.html) instead,
+//! which only needs the results of matches between engines.
+
+use std::io;
+use std::path::Path;
+
+use eval::{mobility, octant_index, phase_factors, potential_mobility, stability, Heuristic, Phase, Phased, Score, WeightedSquares, MIDGAME_EMPTIES};
+use game::{Board, Color};
+use random::Random;
+use stats::MatchResult;
+
+/// A position labeled with the result of the game it was played in.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Sample {
+    pub board: Board,
+    /// The player the result is given for.
+    pub color: Color,
+    /// 1 for a win, 0.5 for a draw and 0 for a loss.
+    pub result: f64
+}
+
+/// Labels each position of a game with its final result for the player to move.
+pub fn label_game(positions: &[(Board, Color)], final_board: &Board) -> Vec<Sample> {
+    positions.iter().map(|&(board, color)| {
+        let own = final_board.count(color);
+        let opponent = final_board.count(color.flip());
+        let result = if own > opponent { 1.0 } else if own < opponent { 0.0 } else { 0.5 };
+        Sample { board, color, result }
+    }).collect()
+}
+
+/// Controls the gradient descent.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Options {
+    /// Number of passes over the samples.
+    pub iterations: usize,
+    /// Multiplier of the gradient when updating the weights.
+    pub learning_rate: f64,
+    /// Steepness of the sigmoid mapping scores to expected results.
+    pub scale: f64
+}
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            iterations: 1000,
+            learning_rate: 200.0,
+            scale: 0.01
+        }
+    }
+}
+
+// The heuristic is linear in its weights, laid out as the octants of the squares in the opening,
+// the midgame and the endgame, followed by the three phase weights of mobility, potential mobility
+// and stability.
+const WEIGHT_COUNT: usize = 39;
+
+fn to_weights(heuristic: &Heuristic) -> [f64; WEIGHT_COUNT] {
+    let mut weights = [0.0; WEIGHT_COUNT];
+    let squares = [&heuristic.squares.opening, &heuristic.squares.midgame, &heuristic.squares.endgame];
+    for (phase, squares) in squares.iter().enumerate() {
+        for (i, &octant) in squares.octant().iter().enumerate() {
+            weights[10 * phase + i] = octant as f64;
+        }
+    }
+    let terms = [heuristic.mobility, heuristic.potential_mobility, heuristic.stability];
+    for (i, term) in terms.iter().enumerate() {
+        weights[30 + 3 * i] = term.opening as f64;
+        weights[31 + 3 * i] = term.midgame as f64;
+        weights[32 + 3 * i] = term.endgame as f64;
+    }
+    weights
+}
+
+fn from_weights(weights: &[f64; WEIGHT_COUNT]) -> Heuristic {
+    let squares = |phase: usize| {
+        let mut octant = [0; 10];
+        for (i, octant) in octant.iter_mut().enumerate() {
+            *octant = weights[10 * phase + i].round() as Score;
+        }
+        WeightedSquares::from_octant(octant)
+    };
+    let term = |i: usize| Phased {
+        opening: weights[30 + 3 * i].round() as Score,
+        midgame: weights[31 + 3 * i].round() as Score,
+        endgame: weights[32 + 3 * i].round() as Score
+    };
+    Heuristic {
+        squares: Phased { opening: squares(0), midgame: squares(1), endgame: squares(2) },
+        mobility: term(0),
+        potential_mobility: term(1),
+        stability: term(2)
+    }
+}
+
+// The coefficients of the weights in the score of a sample.
+fn terms(sample: &Sample) -> [f64; WEIGHT_COUNT] {
+    let (board, color) = (&sample.board, sample.color);
+    let (opening, midgame, endgame) = phase_factors(Phase::of(board));
+    let factors = [opening, midgame, endgame].iter().map(|&x| x as f64 / MIDGAME_EMPTIES as f64).collect::<Vec<_>>();
+    let mut terms = [0.0; WEIGHT_COUNT];
+    for (x, column) in board.cells.iter().enumerate() {
+        for (y, cell) in column.iter().enumerate() {
+            let sign = match *cell {
+                Some(found) if found == color => 1.0,
+                Some(_) => -1.0,
+                None => continue
+            };
+            for (phase, factor) in factors.iter().enumerate() {
+                terms[10 * phase + octant_index(x, y)] += sign * factor;
+            }
+        }
+    }
+    let opponent = color.flip();
+    let differences = [
+        mobility(board, color) as f64 - mobility(board, opponent) as f64,
+        potential_mobility(board, color) as f64 - potential_mobility(board, opponent) as f64,
+        stability(board, color) as f64 - stability(board, opponent) as f64
+    ];
+    for (i, difference) in differences.iter().enumerate() {
+        for (phase, factor) in factors.iter().enumerate() {
+            terms[30 + 3 * i + phase] = difference * factor;
+        }
+    }
+    terms
+}
+
+fn sigmoid(score: f64, scale: f64) -> f64 {
+    1.0 / (1.0 + (-scale * score).exp())
+}
+
+fn dot(weights: &[f64; WEIGHT_COUNT], terms: &[f64; WEIGHT_COUNT]) -> f64 {
+    weights.iter().zip(terms.iter()).map(|(w, t)| w * t).sum()
+}
+
+/// Computes the mean squared error of the expected results predicted by `heuristic`.
+pub fn error(heuristic: &Heuristic, samples: &[Sample], scale: f64) -> f64 {
+    let weights = to_weights(heuristic);
+    let sum: f64 = samples.iter().map(|sample| {
+        let difference = sample.result - sigmoid(dot(&weights, &terms(sample)), scale);
+        difference * difference
+    }).sum();
+    sum / samples.len().max(1) as f64
+}
+
+/// Fits the weights of `heuristic` to the samples, starting the descent from its current weights.
+pub fn tune(heuristic: &Heuristic, samples: &[Sample], options: &Options) -> Heuristic {
+    let mut weights = to_weights(heuristic);
+    let terms = samples.iter().map(terms).collect::<Vec<_>>();
+    let n = samples.len().max(1) as f64;
+    for _ in 0..options.iterations {
+        let mut gradient = [0.0; WEIGHT_COUNT];
+        for (sample, terms) in samples.iter().zip(terms.iter()) {
+            let predicted = sigmoid(dot(&weights, terms), options.scale);
+            // derivative of the squared error through the sigmoid
+            let factor = -2.0 * (sample.result - predicted) * options.scale * predicted * (1.0 - predicted) / n;
+            for (g, t) in gradient.iter_mut().zip(terms.iter()) {
+                *g += factor * t;
+            }
+        }
+        for (w, g) in weights.iter_mut().zip(gradient.iter()) {
+            *w -= options.learning_rate * g;
+        }
+    }
+    from_weights(&weights)
+}
+
+/// Tunes the weights stored in a weight file and writes the result back to it.
+///
+/// Returns the error of the tuned weights on the samples.
+pub fn tune_file<P: AsRef<Path>>(path: P, samples: &[Sample], options: &Options) -> io::Result<f64> {
+    let tuned = tune(&Heuristic::load(&path)?, samples, options);
+    tuned.save(&path)?;
+    Ok(error(&tuned, samples, options.scale))
+}
+
+/// An engine parameter tuned by [`spsa`](fn.spsa.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter {
+    pub name: String,
+    pub value: f64,
+    /// Smallest allowed value.
+    pub min: f64,
+    /// Largest allowed value.
+    pub max: f64,
+    /// The initial size of the perturbations. It should be large enough to make a measurable
+    /// difference in strength.
+    pub step: f64
+}
+
+/// Controls the simultaneous perturbation stochastic approximation.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SpsaOptions {
+    /// Number of perturbed matches played.
+    pub iterations: u32,
+    /// Number of games in each perturbed match. Should be even so both sides play both colors.
+    pub games: u32,
+    /// Number of games in the final match of the tuned values against the initial ones.
+    pub verification_games: u32,
+    /// Scale of the updates, relative to the step of each parameter.
+    pub learning_rate: f64,
+    pub seed: u64
+}
+impl Default for SpsaOptions {
+    fn default() -> SpsaOptions {
+        SpsaOptions {
+            iterations: 100,
+            games: 2,
+            verification_games: 100,
+            learning_rate: 1.0,
+            seed: 0
+        }
+    }
+}
+
+/// The outcome of a [`spsa`](fn.spsa.html) run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpsaReport {
+    /// The parameters with their tuned values.
+    pub parameters: Vec<Parameter>,
+    /// Results of the tuned values against the initial ones. Its
+    /// [`los`](../stats/struct.MatchResult.html#method.los) is the confidence in the improvement.
+    pub verification: MatchResult
+}
+
+/// Tunes engine parameters by playing matches between randomly perturbed values.
+///
+/// In each iteration every parameter is shifted up or down at random, and `play` is called to
+/// play a match between the values shifted one way and the ones shifted the opposite way. The
+/// values then move towards the winner. `play` receives the values of both sides and the number
+/// of games, and returns the results of the first side. As the engines configure themselves from
+/// plain numbers, any engine parameter can be tuned this way.
+pub fn spsa<F>(parameters: &[Parameter], options: &SpsaOptions, mut play: F) -> SpsaReport
+    where F: FnMut(&[f64], &[f64], u32) -> MatchResult
+{
+    // the usual gain sequence exponents, with a stability constant of a tenth of the iterations
+    const ALPHA: f64 = 0.602;
+    const GAMMA: f64 = 0.101;
+    let stability = options.iterations as f64 / 10.0;
+    let mut random = Random::new(options.seed);
+    let clamp = |parameter: &Parameter, value: f64| value.max(parameter.min).min(parameter.max);
+    let initial = parameters.iter().map(|x| x.value).collect::<Vec<_>>();
+    let mut values = initial.clone();
+    for k in 1..=options.iterations {
+        let a = options.learning_rate / (k as f64 + stability).powf(ALPHA) * (1.0 + stability).powf(ALPHA);
+        let c = 1.0 / (k as f64).powf(GAMMA);
+        let signs = parameters.iter().map(|_| if random.next_u64() & 1 == 0 { 1.0 } else { -1.0 }).collect::<Vec<f64>>();
+        let shifted = |sign: f64| parameters.iter().zip(values.iter()).zip(signs.iter())
+            .map(|((parameter, &value), &delta)| clamp(parameter, value + sign * c * parameter.step * delta))
+            .collect::<Vec<_>>();
+        let (plus, minus) = (shifted(1.0), shifted(-1.0));
+        let result = play(&plus, &minus, options.games);
+        // in [-1, 1], positive if the values shifted up won
+        let gradient = 2.0 * result.score() - 1.0;
+        for ((value, parameter), &delta) in values.iter_mut().zip(parameters.iter()).zip(signs.iter()) {
+            *value = clamp(parameter, *value + a * parameter.step * gradient * delta / c);
+        }
+    }
+    let verification = if options.verification_games > 0 {
+        play(&values, &initial, options.verification_games)
+    } else {
+        MatchResult::default()
+    };
+    SpsaReport {
+        parameters: parameters.iter().zip(values.iter()).map(|(parameter, &value)| Parameter { value, ..parameter.clone() }).collect(),
+        verification
+    }
+}
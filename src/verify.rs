@@ -0,0 +1,194 @@
+//! This module checks games against the rules, replaying them through a
+//! [session](../session/struct.GameSession.html) as they would be played, so that regressions
+//! of the rules are caught.
+//!
+//! The [reference games](constant.GOLDEN_GAMES.html) are fully played games with the position
+//! expected after each move, computed apart from this crate. They include a wipeout, skips and
+//! games ending with empty cells. Each game is a header line with its name, its moves and the
+//! final disks of Black and White, like `wipeout e6f4e3f6g5d6e7f5c5 13-0`, followed by the
+//! positions as in the [records](../record/index.html). Lines starting with `%` are comments.
+//!
+//! Games submitted by people are read from their [transcripts](fn.transcript.html), checked
+//! before they are accepted.
+
+use std::fmt;
+use std::time::Duration;
+
+use game::{Board, Color};
+use record::{self, GameRecord};
+use session::{GameSession, Player};
+
+/// The reference games.
+pub const GOLDEN_GAMES: &str = include_str!("golden.txt");
+
+/// Why a game doesn't follow the rules, or differs from what was expected.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VerifyError {
+    /// The move at an index, from 0, is illegal.
+    Illegal(usize),
+    /// The position after the move at an index, with the player to move, isn't the one expected.
+    Position { ply: usize, expected: Box<(Board, Color)>, found: Box<(Board, Color)> },
+    /// The game ended with other disks of Black than recorded.
+    Disks { expected: u8, found: u8 },
+    /// The game has another number of moves than expected.
+    Length { expected: usize, found: usize },
+    /// The move at an index, from 0, can't be read.
+    Unreadable(usize),
+    /// The result written at the end, like `33-31`, can't be read.
+    UnreadableResult,
+    /// A skip was written before the move at an index, while the player had a legal move or the
+    /// game was over.
+    UnforcedSkip(usize),
+    /// A result was written for a game still going on.
+    Unfinished,
+    /// The disks of Black and White written as the result aren't the ones on the final board.
+    Result { declared: (u8, u8), found: (u8, u8) }
+}
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            VerifyError::Illegal(ply) => write!(f, "illegal move {}", ply + 1),
+            VerifyError::Position { ply, ref expected, ref found } => write!(f, "after move {}, expected {} but found {}",
+                ply + 1, record::format_position(&expected.0, expected.1), record::format_position(&found.0, found.1)),
+            VerifyError::Disks { expected, found } => write!(f, "expected {} disks of Black but found {}", expected, found),
+            VerifyError::Length { expected, found } => write!(f, "expected {} moves but found {}", expected, found),
+            VerifyError::Unreadable(ply) => write!(f, "unreadable move {}", ply + 1),
+            VerifyError::UnreadableResult => write!(f, "unreadable result"),
+            VerifyError::UnforcedSkip(ply) => write!(f, "unforced skip before move {}", ply + 1),
+            VerifyError::Unfinished => write!(f, "result of an unfinished game"),
+            VerifyError::Result { declared, found } => write!(f, "declared {}-{} but the game ended {}-{}", declared.0, declared.1, found.0, found.1)
+        }
+    }
+}
+
+/// A reference game with the positions expected after each move.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Golden {
+    pub name: String,
+    /// The moves with the final disks of Black.
+    pub record: GameRecord,
+    pub positions: Vec<(Board, Color)>
+}
+
+/// Reads the reference games of a text written like [`GOLDEN_GAMES`](constant.GOLDEN_GAMES.html).
+/// Fails with the number of the first line that can't be read, from 1.
+pub fn parse_golden(text: &str) -> Result<Vec<Golden>, usize> {
+    let mut games: Vec<Golden> = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('%') {
+            continue;
+        }
+        if let Some(position) = record::parse_position(line) {
+            games.last_mut().ok_or(i + 1)?.positions.push(position);
+            continue;
+        }
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        let (name, moves, disks) = match words[..] {
+            [name, moves, disks] => (name, moves, disks),
+            _ => return Err(i + 1)
+        };
+        let moves = record::parse_transcript(moves).ok_or(i + 1)?;
+        let black_disks = disks.split('-').next().and_then(|x| x.parse().ok()).ok_or(i + 1)?;
+        let record = GameRecord { moves, black_disks: Some(black_disks), ..GameRecord::default() };
+        games.push(Golden { name: name.to_string(), record, positions: Vec::new() });
+    }
+    Ok(games)
+}
+
+/// The reference games shipped with the crate.
+pub fn golden_games() -> Vec<Golden> {
+    parse_golden(GOLDEN_GAMES).expect("the reference games are valid")
+}
+
+/// Replays a game through a session, checking that every move is legal, that the session agrees
+/// with the [positions](../record/struct.GameRecord.html#method.positions) of the record, and
+/// that a game played to its end has the final disks recorded. Games ended otherwise, like by
+/// time or resignation, keep their disks. Returns the position after each move with the player
+/// to move.
+pub fn verify_record(record: &GameRecord) -> Result<Vec<(Board, Color)>, VerifyError> {
+    let replayed = record.positions().map_err(VerifyError::Illegal)?;
+    let mut session = GameSession::new(Player::Human(record.black.clone()), Player::Human(record.white.clone()));
+    let mut positions = Vec::with_capacity(record.moves.len());
+    for (ply, &position) in record.moves.iter().enumerate() {
+        session.play(position, Duration::default()).map_err(|_| VerifyError::Illegal(ply))?;
+        let found = (*session.board(), session.color());
+        if found != replayed[ply + 1] {
+            return Err(VerifyError::Position { ply, expected: Box::new(replayed[ply + 1]), found: Box::new(found) });
+        }
+        positions.push(found);
+    }
+    if let (true, None, Some(expected)) = (session.is_over(), record.termination(), record.black_disks) {
+        let found = session.board().count(Color::Black) as u8;
+        if found != expected {
+            return Err(VerifyError::Disks { expected, found });
+        }
+    }
+    Ok(positions)
+}
+
+/// Replays a reference game, checking it like [`verify_record`](fn.verify_record.html) and
+/// comparing the positions with the expected ones.
+pub fn verify_golden(golden: &Golden) -> Result<(), VerifyError> {
+    let positions = verify_record(&golden.record)?;
+    if positions.len() != golden.positions.len() {
+        return Err(VerifyError::Length { expected: golden.positions.len(), found: positions.len() });
+    }
+    for (ply, (&found, &expected)) in positions.iter().zip(&golden.positions).enumerate() {
+        if found != expected {
+            return Err(VerifyError::Position { ply, expected: Box::new(expected), found: Box::new(found) });
+        }
+    }
+    Ok(())
+}
+
+/// Reads a transcript, checking that every move is legal, that the skips written, as `pa`, `ps`
+/// or `pass`, were forced, and that the result written at the end, like `f5d6c3 ... 33-31`, is
+/// the count of the final board. Skips may be left out, as in the transcripts of this crate, and
+/// so may the result, the final disks of Black being then counted for a finished game.
+pub fn transcript(text: &str) -> Result<GameRecord, VerifyError> {
+    let text = text.trim();
+    // the last word is a result only if it is made of two numbers, as moves may be separated by
+    // dashes too, like `f5-d6-c3`
+    let digits = |x: &str| !x.is_empty() && x.bytes().all(|x| x.is_ascii_digit());
+    let (rest, last) = text.rsplit_once(char::is_whitespace).unwrap_or(("", text));
+    let (moves_text, declared) = match last.split_once('-') {
+        Some((black, white)) if digits(black) && digits(white) => {
+            let disks = black.parse::<u8>().ok().zip(white.parse::<u8>().ok());
+            (rest, Some(disks.ok_or(VerifyError::UnreadableResult)?))
+        }
+        _ => (text, None)
+    };
+    let cells = moves_text.to_ascii_lowercase().replace("pass", "pa").chars().filter(|x| x.is_ascii_alphanumeric()).collect::<String>();
+    let (mut board, mut color) = (Board::new(), Color::Black);
+    let mut moves = Vec::new();
+    for i in (0..cells.len()).step_by(2) {
+        let cell = cells.get(i..i + 2).ok_or(VerifyError::Unreadable(moves.len()))?;
+        if cell == "pa" || cell == "ps" {
+            if !board.legal_moves(color).is_empty() || board.legal_moves(color.flip()).is_empty() {
+                return Err(VerifyError::UnforcedSkip(moves.len()));
+            }
+            color = color.flip();
+            continue;
+        }
+        let position = record::parse_move(cell).ok_or(VerifyError::Unreadable(moves.len()))?;
+        if board.legal_moves(color).is_empty() {
+            color = color.flip();
+        }
+        let legal_move = board.test_move(color, position).map_err(|_| VerifyError::Illegal(moves.len()))?;
+        board = legal_move.apply(board);
+        color = color.flip();
+        moves.push(position);
+    }
+    let over = board.legal_moves(color).is_empty() && board.legal_moves(color.flip()).is_empty();
+    let found = (board.count(Color::Black) as u8, board.count(Color::White) as u8);
+    let black_disks = match declared {
+        Some(_) if !over => return Err(VerifyError::Unfinished),
+        Some(declared) if declared != found => return Err(VerifyError::Result { declared, found }),
+        _ if over => Some(found.0),
+        _ => None
+    };
+    let record = GameRecord { moves, black_disks, ..GameRecord::default() };
+    verify_record(&record)?;
+    Ok(record)
+}
@@ -0,0 +1,211 @@
+//! This module steps through played games move by move, to review them on the console or in the
+//! [full-screen interface](../tui/fn.view.html).
+//!
+//! A [`Viewer`](struct.Viewer.html) keeps the positions of a game and the one shown, and carries
+//! out [commands](enum.Command.html): stepping forward and back, jumping to a move, turning the
+//! board upside down and showing the evaluation of an engine, computed once per position.
+
+use std::str::FromStr;
+
+use engine::{Engine, Limits};
+use eval::Score;
+use game::{Board, Charset, Color, Highlights, LegalMove, Symmetry};
+use record::{self, GameRecord};
+use search::WIN;
+use terminal::Theme;
+
+// Turns the board upside down.
+const UPSIDE_DOWN: Symmetry = Symmetry(3);
+
+/// What the reviewer asks for.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Command {
+    Forward,
+    Back,
+    Start,
+    End,
+    /// Shows the position after a number of moves.
+    Jump(usize),
+    /// Turns the board upside down, or back.
+    Flip,
+    /// Shows or hides the evaluation.
+    Evaluate,
+    Quit
+}
+impl FromStr for Command {
+    type Err = ();
+
+    /// Parses the commands of the console: an empty line or `n` for the next move, `p` for the
+    /// previous one, `s` and `e` for the start and the end, a number for that move, `f` to flip
+    /// the board, `v` to toggle the evaluation and `q` to quit.
+    fn from_str(s: &str) -> Result<Command, ()> {
+        match s.trim() {
+            "" | "n" | "next" => Ok(Command::Forward),
+            "p" | "previous" => Ok(Command::Back),
+            "s" | "start" => Ok(Command::Start),
+            "e" | "end" => Ok(Command::End),
+            "f" | "flip" => Ok(Command::Flip),
+            "v" | "eval" => Ok(Command::Evaluate),
+            "q" | "quit" => Ok(Command::Quit),
+            number => number.parse().map(Command::Jump).map_err(|_| ())
+        }
+    }
+}
+
+/// A game being reviewed.
+pub struct Viewer<E: Engine> {
+    pub engine: E,
+    pub limits: Limits,
+    pub charset: Charset,
+    pub theme: Theme,
+    /// Draws the board upside down, h8 in the top left corner.
+    pub flipped: bool,
+    /// Shows the evaluation of the engine.
+    pub evaluate: bool,
+    record: GameRecord,
+    positions: Vec<(Board, Color)>,
+    ply: usize,
+    // evaluations of the positions for Black, once computed
+    evaluations: Vec<Option<Score>>,
+    quit: bool
+}
+impl<E: Engine> Viewer<E> {
+    /// Reviews a game from its starting position. Fails with the index of the first illegal move.
+    pub fn new(engine: E, record: GameRecord) -> Result<Viewer<E>, usize> {
+        let positions = record.positions()?;
+        Ok(Viewer {
+            engine,
+            limits: Limits::default(),
+            charset: Charset::default(),
+            theme: Theme::default(),
+            flipped: false,
+            evaluate: false,
+            evaluations: vec![None; positions.len()],
+            record,
+            positions,
+            ply: 0,
+            quit: false
+        })
+    }
+
+    pub fn record(&self) -> &GameRecord {
+        &self.record
+    }
+
+    /// Number of moves played up to the position shown.
+    pub fn ply(&self) -> usize {
+        self.ply
+    }
+
+    /// The position shown with the player to move.
+    pub fn position(&self) -> (Board, Color) {
+        self.positions[self.ply]
+    }
+
+    /// The move leading to the position shown.
+    pub fn last_move(&self) -> Option<LegalMove> {
+        let ply = self.ply.checked_sub(1)?;
+        let (board, color) = self.positions[ply];
+        board.legal_moves(color).into_iter().find(|x| x.position == self.record.moves[ply])
+    }
+
+    /// Whether the reviewer asked to quit.
+    pub fn is_done(&self) -> bool {
+        self.quit
+    }
+
+    /// Carries out a command. Moves out of the game stop at its start or its end.
+    pub fn handle(&mut self, command: Command) {
+        let last = self.record.moves.len();
+        match command {
+            Command::Forward => self.ply = (self.ply + 1).min(last),
+            Command::Back => self.ply = self.ply.saturating_sub(1),
+            Command::Start => self.ply = 0,
+            Command::End => self.ply = last,
+            Command::Jump(ply) => self.ply = ply.min(last),
+            Command::Flip => self.flipped = !self.flipped,
+            Command::Evaluate => self.evaluate = !self.evaluate,
+            Command::Quit => self.quit = true
+        }
+    }
+
+    /// The evaluation of the position shown for Black, searching it the first time. Finished
+    /// games score their disk differential.
+    pub fn evaluation(&mut self) -> Score {
+        if let Some(score) = self.evaluations[self.ply] {
+            return score;
+        }
+        let (board, color) = self.positions[self.ply];
+        let score = if board.legal_moves(color).is_empty() {
+            let diff = board.count(Color::Black) as Score - board.count(Color::White) as Score;
+            diff.signum() * WIN + diff
+        } else {
+            let score = self.engine.best_move(&board, color, &self.limits).score;
+            if color == Color::Black { score } else { -score }
+        };
+        self.evaluations[self.ply] = Some(score);
+        score
+    }
+
+    /// Draws the position shown with the last move marked, followed by the move, the player to
+    /// move and the evaluation when shown.
+    pub fn draw(&mut self) -> String {
+        let (board, color) = self.position();
+        let last_move = self.last_move();
+        let mut text = if self.flipped {
+            // the last move is found again on the turned board, as flips are stored by direction
+            let turned = last_move.and_then(|last| {
+                let (before, _) = self.positions[self.ply - 1];
+                before.transform(UPSIDE_DOWN).legal_moves(last.color).into_iter().find(|x| x.position == UPSIDE_DOWN.apply(last.position))
+            });
+            turn_labels(&board.transform(UPSIDE_DOWN).draw_highlighted(self.charset, &Highlights { hints: None, last_move: turned }))
+        } else {
+            board.draw_highlighted(self.charset, &Highlights { hints: None, last_move })
+        };
+        text = self.theme.paint(&text);
+        let moves = self.record.moves.len();
+        text += &match last_move {
+            Some(last) => format!("Move {} of {}: {} {}\n", self.ply, moves, last.color, record::format_move(last.position)),
+            None => format!("Start of {} moves\n", moves)
+        };
+        if board.legal_moves(color).is_empty() {
+            text += &format!("Game over: {}-{}\n", board.count(Color::Black), board.count(Color::White));
+        } else {
+            text += &format!("{} to move\n", color);
+        }
+        if self.evaluate {
+            text += &format!("Evaluation: {}\n", format_score(self.evaluation()));
+        }
+        text
+    }
+}
+
+// Relabels a board drawn upside down: the columns from h to a and the rows from 8 to 1.
+fn turn_labels(text: &str) -> String {
+    let mut lines = text.lines();
+    let header = lines.next().unwrap_or_default();
+    let mut turned = header.chars().map(|x| match x {
+        'a'..='h' => (b'h' - (x as u8 - b'a')) as char,
+        x => x
+    }).collect::<String>() + "\n";
+    for line in lines {
+        match line.chars().next().and_then(|x| x.to_digit(10)) {
+            Some(row) => turned += &format!("{}{}\n", 9 - row, &line[1..]),
+            None => turned += &format!("{}\n", line)
+        }
+    }
+    turned
+}
+
+// Scores of finished games are shown as their disk differential.
+fn format_score(score: Score) -> String {
+    if score.abs() >= WIN {
+        let diff = score - score.signum() * WIN;
+        match score.signum() {
+            1 => format!("Black wins by {}", diff),
+            _ => format!("White wins by {}", -diff)
+        }
+    } else {
+        format!("{:+} for Black", score)
+    }
+}
@@ -0,0 +1,181 @@
+//! This module exposes games and the engine to JavaScript through `wasm-bindgen`, so that the
+//! crate can run a browser Othello app by itself. Build it for the web with
+//! `cargo rustc --lib --release --target wasm32-unknown-unknown --features wasm --crate-type cdylib`
+//! and generate the bindings with the `wasm-bindgen` command.
+//!
+//! Cells are written like `f5`, positions like in [records](../record/index.html) and games as
+//! the `game` documents of the [JSON schema](../formats/json/index.html). Functions failing
+//! throw their error message.
+//!
+//! The engine can think in [steps](../search/struct.IncrementalSearch.html) of a few
+//! milliseconds, called from the event loop of the page or a Web Worker, so that long thinks
+//! don't freeze it.
+
+use wasm_bindgen::prelude::*;
+
+use engine::{Engine, Limits};
+use eval::Heuristic;
+use formats::json::{self, Value};
+use game::Color;
+use record;
+use search::{AlphaBeta, IncrementalSearch, Step};
+use session::{GameSession, Player};
+use std::time::Duration;
+
+// Deepest search of the engine, which stops deepening once its time is up.
+const MAX_DEPTH: u32 = 20;
+
+/// Starts a game from the starting position.
+#[wasm_bindgen(js_name = newGame)]
+pub fn new_game() -> BrowserGame {
+    BrowserGame::new()
+}
+
+/// A game played in the browser, with the engine to play or analyse it.
+#[wasm_bindgen(js_name = Game)]
+pub struct BrowserGame {
+    session: GameSession,
+    /// The engine, kept by its search while it thinks.
+    engine: Option<AlphaBeta<Heuristic>>,
+    thinking: Option<IncrementalSearch<Heuristic>>
+}
+#[wasm_bindgen(js_class = Game)]
+impl BrowserGame {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> BrowserGame {
+        BrowserGame {
+            session: GameSession::new(Player::Human("Black".to_string()), Player::Human("White".to_string())),
+            engine: Some(AlphaBeta::new(MAX_DEPTH, Heuristic::default())),
+            thinking: None
+        }
+    }
+
+    /// Reads a game from its JSON document, replaying its moves.
+    #[wasm_bindgen(js_name = fromJson)]
+    pub fn from_json(text: &str) -> Result<BrowserGame, String> {
+        let record = Value::parse(text).and_then(|x| json::game_from_json(&x)).map_err(|e| e.to_string())?;
+        let mut game = BrowserGame::new();
+        game.session.black = Player::Human(record.black);
+        game.session.white = Player::Human(record.white);
+        game.session.event = record.event;
+        game.session.date = record.date;
+        for (i, &position) in record.moves.iter().enumerate() {
+            game.session.play(position, Duration::from_millis(0)).map_err(|e| format!("move {}: {}", i + 1, e))?;
+        }
+        Ok(game)
+    }
+
+    /// The JSON document of the game.
+    #[wasm_bindgen(js_name = toJson)]
+    pub fn to_json(&self) -> String {
+        json::game_to_json(&self.session.record()).to_string()
+    }
+
+    /// Plays a move of the player to move, stopping the engine if it thinks.
+    pub fn play(&mut self, cell: &str) -> Result<(), String> {
+        self.stop();
+        let position = record::parse_move(cell).ok_or_else(|| format!("invalid cell: {}", cell))?;
+        self.session.play(position, Duration::from_millis(0)).map_err(|e| e.to_string())
+    }
+
+    /// The cells the player to move can play, none once the game is over.
+    #[wasm_bindgen(js_name = legalMoves)]
+    pub fn legal_moves(&self) -> Vec<String> {
+        self.session.board().legal_moves(self.session.color()).iter().map(|x| record::format_move(x.position)).collect()
+    }
+
+    #[wasm_bindgen(js_name = isLegal)]
+    pub fn is_legal(&self, cell: &str) -> bool {
+        record::parse_move(cell).is_some_and(|x| self.session.board().test_move(self.session.color(), x).is_ok())
+    }
+
+    /// Searches the best move of the player to move for about `budget_ms` milliseconds, the
+    /// last depth searched finishing past it. `undefined` once the game is over.
+    #[wasm_bindgen(js_name = bestMove)]
+    pub fn best_move(&mut self, budget_ms: u32) -> Option<String> {
+        if self.session.is_over() {
+            return None;
+        }
+        let limits = Limits { time: Some(Duration::from_millis(u64::from(budget_ms))), ..Limits::default() };
+        let (board, color) = (*self.session.board(), self.session.color());
+        self.stop().best_move(&board, color, &limits).best.map(record::format_move)
+    }
+
+    /// Starts thinking about the best move for about `time_ms` milliseconds, in steps that don't
+    /// block the page, unlike [`bestMove`](#method.best_move). Fails once the game is over.
+    pub fn think(&mut self, time_ms: u32) -> Result<(), String> {
+        if self.session.is_over() {
+            return Err("the game is over".to_string());
+        }
+        let limits = Limits { time: Some(Duration::from_millis(u64::from(time_ms))), ..Limits::default() };
+        let engine = self.stop_engine();
+        self.thinking = Some(IncrementalSearch::new(engine, self.session.board(), self.session.color(), &limits));
+        Ok(())
+    }
+
+    /// Thinks for about `budget_ms` milliseconds. Returns `undefined` until the thinking is
+    /// over, then the best move. Fails if the engine doesn't think.
+    pub fn step(&mut self, budget_ms: u32) -> Result<Option<String>, String> {
+        let search = self.thinking.as_mut().ok_or_else(|| "the engine doesn't think".to_string())?;
+        match search.step(Duration::from_millis(u64::from(budget_ms))) {
+            Step::Pending => Ok(None),
+            Step::Done(found) => {
+                self.stop();
+                Ok(found.best.map(record::format_move))
+            }
+        }
+    }
+
+    /// The player to move, `black` or `white`, `undefined` once the game is over.
+    #[wasm_bindgen(js_name = toMove)]
+    pub fn to_move(&self) -> Option<String> {
+        if self.session.is_over() {
+            return None;
+        }
+        Some(if self.session.color() == Color::Black { "black" } else { "white" }.to_string())
+    }
+
+    #[wasm_bindgen(js_name = isOver)]
+    pub fn is_over(&self) -> bool {
+        self.session.is_over()
+    }
+
+    /// The position with the player to move, like `---...---X`.
+    pub fn position(&self) -> String {
+        record::format_position(self.session.board(), self.session.color())
+    }
+
+    /// The moves played, skips left out.
+    pub fn moves(&self) -> Vec<String> {
+        self.session.history().iter().map(|x| record::format_move(x.position)).collect()
+    }
+
+    #[wasm_bindgen(js_name = blackDisks)]
+    pub fn black_disks(&self) -> u32 {
+        self.session.board().count(Color::Black) as u32
+    }
+
+    #[wasm_bindgen(js_name = whiteDisks)]
+    pub fn white_disks(&self) -> u32 {
+        self.session.board().count(Color::White) as u32
+    }
+}
+impl BrowserGame {
+    // Stops the search in progress, if any, and returns the engine.
+    fn stop(&mut self) -> &mut AlphaBeta<Heuristic> {
+        let engine = self.stop_engine();
+        self.engine.get_or_insert(engine)
+    }
+
+    fn stop_engine(&mut self) -> AlphaBeta<Heuristic> {
+        match self.thinking.take() {
+            Some(search) => search.into_engine(),
+            None => self.engine.take().expect("the engine is kept by the game or its search")
+        }
+    }
+}
+impl Default for BrowserGame {
+    fn default() -> BrowserGame {
+        BrowserGame::new()
+    }
+}
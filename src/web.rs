@@ -0,0 +1,682 @@
+//! This module serves games to browser clients over WebSocket, in the documents of the
+//! [JSON schema](../formats/json/index.html).
+//!
+//! A player creates a game and gets a code to pass on, with which the opponent joins it; anyone
+//! joining once both seats are taken follows the game as a spectator. Players can also meet in
+//! the [lobby](../lobby/index.html) and challenge each other. Clients send text messages, each a
+//! document with the `schema` version and its `kind`:
+//!
+//! - `create` with the `name` of the player and optionally their `color`, `black` by default,
+//! - `join` with the `code` of a game and the `name` of the player, or the `token` of a seat to
+//!   take it back after losing the connection,
+//! - `move` with the `cell` to play, `resign` and `sync` to get the state again,
+//! - `watch` with the `code` of a game to follow it as a spectator, even with a seat free,
+//! - `lobby` with the `name` to be listed under,
+//! - `challenge` with the `name` of a player of the lobby, optionally the `color` of the player
+//!   challenging, `black` by default, and the clocks as `minutes` and an `increment` in seconds,
+//!   untimed when absent. Challenging back someone who challenged you replaces their challenge,
+//!   to propose another time control,
+//! - `accept` and `decline` with the `name` of the player challenging.
+//!
+//! The server answers `create` and `join` with `joined`, giving the `code`, the `color` of the
+//! seat and its `token`, both `null` for spectators, followed by the `state` of the game. After
+//! each move it sends everyone in the game the `move` and the new `state`: the `code`, the
+//! `black` and `white` players, `null` while the seat is free, the `position`, the `moves` as
+//! cells, whether the game is `over`, the `black_disks` and `white_disks`, the color that
+//! `resigned` and the one that `flagged`, running out of time, if any, the milliseconds left
+//! on the `black_clock` and the `white_clock` when the turn started, `null` for untimed games,
+//! the milliseconds charged for each move as `times` and the `timestamp` of the state in
+//! milliseconds since the Unix epoch.
+//!
+//! The server keeps the clocks: a move takes the time since the previous one, less the
+//! [lag compensation](struct.Server.html#method.set_lag_compensation), and a player whose time
+//! runs out loses even without moving.
+//!
+//! Spectators get the messages of the game after a [delay](struct.Server.html#method.set_spectator_delay),
+//! so that they can't pass the moves of the engines they run on to a player, and after each move
+//! the `eval` of the new position when [enabled](struct.Server.html#method.set_evaluation): the
+//! `black_score` of the engine, positive when Black is ahead, its `depth` and its `best` move.
+//!
+//! Players of the lobby get the `players` with their `name` and whether they are `playing`
+//! whenever it changes, the `challenge`s they send and receive with the `from` and `to` players,
+//! the `color`, `minutes` and `increment`, and `declined` with the same players. Accepting a
+//! challenge puts both players in a new game, as if they had joined it. Requests that can't be
+//! carried out are answered with an `error` and its `message`. Clients sending what no honest
+//! client sends, like text that isn't a document or moves to cells off the board, get the
+//! `error` and are disconnected.
+
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::path::Path;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use tungstenite::protocol::WebSocketConfig;
+use tungstenite::{self, Message};
+
+use engine::{Engine, Limits};
+use files::invalid;
+use formats::json::{self, Value};
+use game::Color;
+use lobby::{Archive, Challenge, Lobby};
+use net;
+use record::{self, GameRecord, Termination};
+use session::{Clock, GameSession, Player};
+
+// How often a connection looks for messages to send while waiting for its client.
+const POLL: Duration = Duration::from_millis(50);
+
+// The largest message read from a client, far above the size of any request.
+const MAX_MESSAGE: usize = 64 * 1024;
+
+// The longest time of a clock, and the longest increment.
+const MAX_CLOCK: Duration = Duration::from_secs(24 * 60 * 60);
+
+// Characters of the codes of games, leaving out the ones easily mistaken for others.
+const CODE_CHARACTERS: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+
+/// A server of games, each client on a thread of its own.
+pub struct Server {
+    listener: TcpListener,
+    games: Arc<Mutex<Games>>
+}
+impl Server {
+    pub fn bind<A: ToSocketAddrs>(address: A) -> io::Result<Server> {
+        let games = Games {
+            games: HashMap::new(),
+            delay: Duration::from_secs(0),
+            lag: Duration::from_secs(0),
+            evaluation: None,
+            codes: HashMap::new(),
+            lobby: Lobby::new(),
+            listed: HashMap::new(),
+            archive: None,
+            clients: 0
+        };
+        Ok(Server { listener: TcpListener::bind(address)?, games: Arc::new(Mutex::new(games)) })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Appends the finished games to a PGN file.
+    pub fn set_archive<P: AsRef<Path>>(&mut self, path: P) {
+        self.games.lock().expect("no thread panics holding the games").archive = Some(Archive::new(path));
+    }
+
+    /// Holds back the messages of the games sent to spectators, none by default.
+    pub fn set_spectator_delay(&mut self, delay: Duration) {
+        self.games.lock().expect("no thread panics holding the games").delay = delay;
+    }
+
+    /// Doesn't charge players for up to `allowance` of each move, put down to the latency of their
+    /// connection. None by default.
+    pub fn set_lag_compensation(&mut self, allowance: Duration) {
+        self.games.lock().expect("no thread panics holding the games").lag = allowance;
+    }
+
+    /// Sends the spectators the evaluation of the engine after each move. The searches hold up
+    /// the other games, so the limits should be small.
+    pub fn set_evaluation<E: Engine + Send + 'static>(&mut self, engine: E, limits: Limits) {
+        self.games.lock().expect("no thread panics holding the games").evaluation = Some((Box::new(engine), limits));
+    }
+
+    /// Serves clients until accepting connections fails.
+    pub fn run(&self) -> io::Result<()> {
+        for stream in self.listener.incoming() {
+            let stream = stream?;
+            let games = self.games.clone();
+            thread::spawn(move || {
+                // a client going away only ends its own connection
+                let _ = serve(stream, &games);
+            });
+        }
+        Ok(())
+    }
+}
+
+// A connection, through which the messages of the games it takes part in are sent, each with
+// the time it is due.
+#[derive(Clone)]
+struct Client {
+    id: u64,
+    sender: mpsc::Sender<(Instant, String)>
+}
+
+// A seat of a game, kept for its player when the connection is lost.
+struct Seat {
+    name: String,
+    token: String,
+    client: Option<Client>
+}
+
+struct Game {
+    session: GameSession,
+    seats: [Option<Seat>; 2],
+    spectators: Vec<Client>,
+    resigned: Option<Color>,
+    flagged: Option<Color>,
+    // when the player to move started thinking
+    turn_started: Instant,
+    archived: bool,
+    // delay of the messages to the spectators
+    delay: Duration,
+    // time of each move not charged to the players
+    lag: Duration
+}
+impl Game {
+    fn new(delay: Duration, lag: Duration) -> Game {
+        Game {
+            session: GameSession::new(Player::Human(String::new()), Player::Human(String::new())),
+            seats: [None, None],
+            spectators: Vec::new(),
+            resigned: None,
+            flagged: None,
+            turn_started: Instant::now(),
+            archived: false,
+            delay,
+            lag
+        }
+    }
+
+    fn is_over(&self) -> bool {
+        self.resigned.is_some() || self.flagged.is_some() || self.session.is_over()
+    }
+
+    fn clients(&self) -> impl Iterator<Item = &Client> {
+        self.seats.iter().flatten().filter_map(|x| x.client.as_ref()).chain(&self.spectators)
+    }
+
+    fn broadcast(&self, document: &Value) {
+        for client in self.clients() {
+            self.send(client, document);
+        }
+    }
+
+    // Sends a document to a client of the game, later if they are a spectator.
+    fn send(&self, client: &Client, document: &Value) {
+        let delay = if self.spectators.iter().any(|x| x.id == client.id) { self.delay } else { Duration::from_secs(0) };
+        // clients gone are left out until they come back
+        let _ = client.sender.send((Instant::now() + delay, document.to_string()));
+    }
+
+    // Flags the player to move if their time ran out. Returns whether they were flagged.
+    fn check_time(&mut self) -> bool {
+        if self.is_over() || self.seats.iter().any(Option::is_none) {
+            return false;
+        }
+        let color = self.session.color();
+        match self.session.clock(color) {
+            Some(clock) if self.turn_started.elapsed() > clock.remaining + self.lag => {
+                self.flagged = Some(color);
+                true
+            }
+            _ => false
+        }
+    }
+
+    // The record of a finished game, forfeits counting as losing all the disks.
+    fn record(&self) -> GameRecord {
+        let mut record = self.session.record();
+        match (self.resigned, self.flagged) {
+            (Some(color), _) => record.forfeit(color, Termination::Resignation),
+            (None, Some(color)) => record.forfeit(color, Termination::Timeout),
+            (None, None) => ()
+        }
+        record
+    }
+
+    fn state(&self, code: &str) -> Value {
+        let session = &self.session;
+        let board = session.board();
+        let name = |color: Color| self.seats[color as usize].as_ref().map_or(Value::Null, |x| Value::String(x.name.clone()));
+        let clock = |color: Color| session.clock(color).map_or(Value::Null, |x| Value::Number(x.remaining.as_millis() as f64));
+        json::document("state", vec![
+            ("code", Value::String(code.to_string())),
+            ("black", name(Color::Black)),
+            ("white", name(Color::White)),
+            ("position", json::position_to_json(board, session.color())),
+            ("moves", Value::Array(session.history().iter().map(|x| Value::String(record::format_move(x.position))).collect())),
+            ("over", Value::Bool(self.is_over())),
+            ("black_disks", Value::Number(board.count(Color::Black) as f64)),
+            ("white_disks", Value::Number(board.count(Color::White) as f64)),
+            ("resigned", self.resigned.map_or(Value::Null, color_to_json)),
+            ("flagged", self.flagged.map_or(Value::Null, color_to_json)),
+            ("black_clock", clock(Color::Black)),
+            ("white_clock", clock(Color::White)),
+            ("times", Value::Array(session.history().iter().map(|x| Value::Number(x.time.as_millis() as f64)).collect())),
+            ("timestamp", Value::Number(SystemTime::now().duration_since(UNIX_EPOCH).map_or(0.0, |x| x.as_millis() as f64)))
+        ])
+    }
+}
+
+// The games being played by their code, and the lobby.
+struct Games {
+    games: HashMap<String, Game>,
+    delay: Duration,
+    lag: Duration,
+    evaluation: Option<(Box<dyn Engine + Send>, Limits)>,
+    // the game each client is in
+    codes: HashMap<u64, String>,
+    lobby: Lobby,
+    // the clients listed in the lobby
+    listed: HashMap<u64, Client>,
+    archive: Option<Archive>,
+    clients: u64
+}
+impl Games {
+    // Carries out a request of a client.
+    fn handle(&mut self, client: &Client, document: &Value) -> io::Result<()> {
+        let kind = document.get("kind").and_then(Value::as_str).ok_or_else(|| invalid("missing kind"))?.to_string();
+        json::negotiate(document, &kind)?;
+        let text = |key: &str| document.get(key).and_then(Value::as_str).ok_or_else(|| invalid(&format!("missing {}", key)));
+        match kind.as_str() {
+            "create" => {
+                let color = color_from_json(document.get("color"))?;
+                let new = self.code()?;
+                let mut game = Game::new(self.delay, self.lag);
+                let token = net::token()?;
+                game.seats[color as usize] = Some(Seat { name: text("name")?.to_string(), token: token.clone(), client: Some(client.clone()) });
+                self.leave(client);
+                self.games.insert(new.clone(), game);
+                self.codes.insert(client.id, new.clone());
+                self.joined(client, &new, Some((color, token)))
+            }
+            "join" => {
+                let wanted = text("code")?.to_ascii_uppercase();
+                if !self.games.contains_key(&wanted) {
+                    return Err(invalid("no game with this code"));
+                }
+                let token = net::token()?;
+                self.leave(client);
+                let game = self.games.get_mut(&wanted).ok_or_else(|| invalid("no game with this code"))?;
+                let seat = match document.get("token").and_then(Value::as_str) {
+                    Some(old) => {
+                        let color = [Color::Black, Color::White].iter().cloned()
+                            .find(|&x| game.seats[x as usize].as_ref().is_some_and(|seat| net::same_token(old, &seat.token)));
+                        let color = color.ok_or_else(|| invalid("invalid token"))?;
+                        let seat = game.seats[color as usize].as_mut().expect("the seat is taken");
+                        seat.client = Some(client.clone());
+                        Some((color, seat.token.clone()))
+                    }
+                    None => {
+                        let name = text("name")?.to_string();
+                        match game.seats.iter().position(Option::is_none) {
+                            Some(i) => {
+                                game.seats[i] = Some(Seat { name, token: token.clone(), client: Some(client.clone()) });
+                                // the clock of the first player starts once the opponent is there
+                                game.turn_started = Instant::now();
+                                let color = if i == 0 { Color::Black } else { Color::White };
+                                Some((color, token))
+                            }
+                            None => {
+                                game.spectators.push(client.clone());
+                                None
+                            }
+                        }
+                    }
+                };
+                self.codes.insert(client.id, wanted.clone());
+                self.joined(client, &wanted, seat)
+            }
+            "watch" => {
+                let wanted = text("code")?.to_ascii_uppercase();
+                if !self.games.contains_key(&wanted) {
+                    return Err(invalid("no game with this code"));
+                }
+                self.leave(client);
+                self.games.get_mut(&wanted).expect("the game exists").spectators.push(client.clone());
+                self.codes.insert(client.id, wanted.clone());
+                self.joined(client, &wanted, None)
+            }
+            "move" => {
+                let (code, game, color) = self.seat(client)?;
+                let position = text("cell").ok().and_then(record::parse_move).ok_or_else(|| invalid("invalid cell"))?;
+                if game.seats.iter().any(Option::is_none) {
+                    return Err(invalid("waiting for an opponent"));
+                }
+                if !game.check_time() && (game.is_over() || game.session.color() != color) {
+                    return Err(invalid("not your turn"));
+                }
+                if game.flagged.is_none() {
+                    game.session.play(position, game.turn_started.elapsed().saturating_sub(game.lag))?;
+                    game.turn_started = Instant::now();
+                    game.broadcast(&json::move_to_json(color, Some(position)));
+                }
+                game.broadcast(&game.state(&code));
+                self.evaluate(&code);
+                self.finish(&code)
+            }
+            "resign" => {
+                let (code, game, color) = self.seat(client)?;
+                if game.is_over() {
+                    return Err(invalid("the game is over"));
+                }
+                game.resigned = Some(color);
+                game.broadcast(&game.state(&code));
+                self.finish(&code)
+            }
+            "sync" => {
+                let code = self.codes.get(&client.id).ok_or_else(|| invalid("not in a game"))?;
+                let game = self.games.get(code).ok_or_else(|| invalid("no game with this code"))?;
+                game.send(client, &game.state(code));
+                Ok(())
+            }
+            "lobby" => {
+                self.lobby.enter(client.id, text("name")?)?;
+                self.listed.insert(client.id, client.clone());
+                self.players();
+                Ok(())
+            }
+            "challenge" => {
+                let color = color_from_json(document.get("color"))?;
+                let clock = match document.get("minutes") {
+                    None | Some(&Value::Null) => None,
+                    Some(minutes) => {
+                        let remaining = minutes.as_f64().filter(|&x| x > 0.0).and_then(|x| clock_time(x * 60.0)).ok_or_else(|| invalid("invalid minutes"))?;
+                        let increment = match document.get("increment") {
+                            None | Some(&Value::Null) => Duration::ZERO,
+                            Some(seconds) => seconds.as_f64().and_then(clock_time).ok_or_else(|| invalid("invalid increment"))?
+                        };
+                        Some(Clock { remaining, increment })
+                    }
+                };
+                let challenge = self.lobby.challenge(client.id, text("name")?, color, clock)?;
+                self.tell(&challenge, &self.challenge_to_json("challenge", &challenge));
+                Ok(())
+            }
+            "accept" => {
+                let pairing = self.lobby.accept(client.id, text("name")?)?;
+                let code = self.code()?;
+                let mut game = Game::new(self.delay, self.lag);
+                game.session.clocks = pairing.clock.map(|x| [x, x]);
+                for (color, id) in [(Color::Black, pairing.black), (Color::White, pairing.white)] {
+                    let player = self.listed.get(&id).cloned().expect("the players of the lobby are listed");
+                    let name = self.lobby.get(id).map(|x| x.name.clone()).unwrap_or_default();
+                    let token = net::token()?;
+                    self.leave(&player);
+                    self.codes.insert(player.id, code.clone());
+                    let document = vec![("code", Value::String(code.clone())), ("color", color_to_json(color)), ("token", Value::String(token.clone()))];
+                    send(&player, &json::document("joined", document));
+                    match color {
+                        Color::Black => game.session.black = Player::Human(name.clone()),
+                        Color::White => game.session.white = Player::Human(name.clone())
+                    }
+                    game.seats[color as usize] = Some(Seat { name, token, client: Some(player) });
+                }
+                game.broadcast(&game.state(&code));
+                self.games.insert(code, game);
+                self.players();
+                Ok(())
+            }
+            "decline" => {
+                let challenge = self.lobby.decline(client.id, text("name")?)?;
+                self.tell(&challenge, &self.challenge_to_json("declined", &challenge));
+                Ok(())
+            }
+            _ => Err(invalid(&format!("unknown kind: {}", kind)))
+        }
+    }
+
+    // Tells a client it joined a game, and everyone the new state.
+    fn joined(&mut self, client: &Client, code: &str, seat: Option<(Color, String)>) -> io::Result<()> {
+        let game = self.games.get_mut(code).expect("the game was joined");
+        let (color, token) = match seat {
+            Some((color, token)) => {
+                let players = game.seats.iter().map(|x| Player::Human(x.as_ref().map_or(String::new(), |x| x.name.clone()))).collect::<Vec<_>>();
+                game.session.black = players[0].clone();
+                game.session.white = players[1].clone();
+                (color_to_json(color), Value::String(token))
+            }
+            None => (Value::Null, Value::Null)
+        };
+        game.send(client, &json::document("joined", vec![("code", Value::String(code.to_string())), ("color", color), ("token", token)]));
+        game.broadcast(&game.state(code));
+        Ok(())
+    }
+
+    // The game of a client with the color of its seat.
+    fn seat(&mut self, client: &Client) -> io::Result<(String, &mut Game, Color)> {
+        let code = self.codes.get(&client.id).cloned().ok_or_else(|| invalid("not in a game"))?;
+        let game = self.games.get_mut(&code).ok_or_else(|| invalid("no game with this code"))?;
+        let seated = |color: Color| game.seats[color as usize].as_ref().and_then(|x| x.client.as_ref()).is_some_and(|x| x.id == client.id);
+        let color = [Color::Black, Color::White].iter().cloned().find(|&x| seated(x)).ok_or_else(|| invalid("only players can do this"))?;
+        Ok((code, game, color))
+    }
+
+    // Sends the spectators of a game the evaluation of its position, if enabled.
+    fn evaluate(&mut self, code: &str) {
+        let (engine, limits) = match self.evaluation {
+            Some((ref mut engine, ref limits)) => (engine, limits),
+            None => return
+        };
+        let game = match self.games.get(code) {
+            Some(game) if !game.spectators.is_empty() && !game.is_over() => game,
+            _ => return
+        };
+        let (board, color) = (game.session.board(), game.session.color());
+        let found = engine.best_move(board, color, limits);
+        let score = if color == Color::Black { found.score } else { -found.score };
+        let document = json::document("eval", vec![
+            ("black_score", Value::Number(f64::from(score))),
+            ("depth", Value::Number(f64::from(found.depth))),
+            ("best", found.best.map_or(Value::Null, |x| Value::String(record::format_move(x))))
+        ]);
+        for spectator in &game.spectators {
+            game.send(spectator, &document);
+        }
+    }
+
+    // Flags the player to move in the game of a client if their time ran out.
+    fn tick(&mut self, client: &Client) -> io::Result<()> {
+        let code = match self.codes.get(&client.id) {
+            Some(code) => code.clone(),
+            None => return Ok(())
+        };
+        let game = match self.games.get_mut(&code) {
+            Some(game) => game,
+            None => return Ok(())
+        };
+        if !game.check_time() {
+            return Ok(());
+        }
+        game.broadcast(&game.state(&code));
+        self.finish(&code)
+    }
+
+    // Archives a game once it is over, and puts its players back in the lobby.
+    fn finish(&mut self, code: &str) -> io::Result<()> {
+        let game = match self.games.get_mut(code) {
+            Some(game) if game.is_over() && !game.archived => game,
+            _ => return Ok(())
+        };
+        game.archived = true;
+        let record = game.record();
+        for client in game.seats.iter().flatten().filter_map(|x| x.client.as_ref()) {
+            self.lobby.set_playing(client.id, false);
+        }
+        self.players();
+        match self.archive {
+            Some(ref archive) => archive.append(&record),
+            None => Ok(())
+        }
+    }
+
+    // Takes a client out of its game, which is forgotten once over and left by everyone.
+    fn leave(&mut self, client: &Client) {
+        let code = match self.codes.remove(&client.id) {
+            Some(code) => code,
+            None => return
+        };
+        let forget = match self.games.get_mut(&code) {
+            Some(game) => {
+                for seat in game.seats.iter_mut().flatten() {
+                    if seat.client.as_ref().is_some_and(|x| x.id == client.id) {
+                        seat.client = None;
+                    }
+                }
+                game.spectators.retain(|x| x.id != client.id);
+                game.is_over() && game.clients().next().is_none()
+            }
+            None => false
+        };
+        if forget {
+            self.games.remove(&code);
+        }
+    }
+
+    // Takes a client going away out of its game and the lobby.
+    fn disconnect(&mut self, client: &Client) {
+        self.leave(client);
+        self.listed.remove(&client.id);
+        if self.lobby.leave(client.id) {
+            self.players();
+        }
+    }
+
+    // Sends the players of the lobby to everyone in it.
+    fn players(&self) {
+        let players = self.lobby.entries().iter().map(|x| {
+            Value::Object(vec![("name".to_string(), Value::String(x.name.clone())), ("playing".to_string(), Value::Bool(x.playing))])
+        }).collect();
+        let document = json::document("players", vec![("players", Value::Array(players))]);
+        for client in self.listed.values() {
+            send(client, &document);
+        }
+    }
+
+    // Sends a document to both players of a challenge.
+    fn tell(&self, challenge: &Challenge, document: &Value) {
+        for id in &[challenge.from, challenge.to] {
+            if let Some(client) = self.listed.get(id) {
+                send(client, document);
+            }
+        }
+    }
+
+    fn challenge_to_json(&self, kind: &str, challenge: &Challenge) -> Value {
+        let name = |id| Value::String(self.lobby.get(id).map(|x| x.name.clone()).unwrap_or_default());
+        let (minutes, increment) = match challenge.clock {
+            Some(clock) => (Value::Number(clock.remaining.as_secs_f64() / 60.0), Value::Number(clock.increment.as_secs_f64())),
+            None => (Value::Null, Value::Null)
+        };
+        json::document(kind, vec![
+            ("from", name(challenge.from)),
+            ("to", name(challenge.to)),
+            ("color", color_to_json(challenge.color)),
+            ("minutes", minutes),
+            ("increment", increment)
+        ])
+    }
+
+    fn code(&self) -> io::Result<String> {
+        loop {
+            let mut bytes = [0; 6];
+            net::secure_random(&mut bytes)?;
+            // the number of characters divides 256, so that all are as likely
+            let code = bytes.iter().map(|&x| CODE_CHARACTERS[usize::from(x) % CODE_CHARACTERS.len()] as char).collect::<String>();
+            if !self.games.contains_key(&code) {
+                return Ok(code);
+            }
+        }
+    }
+}
+
+// Talks with a client until it goes away.
+fn serve(stream: TcpStream, games: &Mutex<Games>) -> io::Result<()> {
+    let config = WebSocketConfig { max_message_size: Some(MAX_MESSAGE), max_frame_size: Some(MAX_MESSAGE), ..WebSocketConfig::default() };
+    let mut socket = tungstenite::accept_with_config(stream, Some(config)).map_err(|x| io::Error::other(x.to_string()))?;
+    socket.get_ref().set_read_timeout(Some(POLL))?;
+    let (sender, receiver) = mpsc::channel();
+    let client = {
+        let mut games = games.lock().expect("no thread panics holding the games");
+        games.clients += 1;
+        Client { id: games.clients, sender }
+    };
+    // messages not due yet, in the order they are due
+    let mut pending = VecDeque::new();
+    let result = loop {
+        pending.extend(receiver.try_iter());
+        let now = Instant::now();
+        let mut sent = Ok(());
+        while pending.front().is_some_and(|x: &(Instant, String)| x.0 <= now) && sent.is_ok() {
+            let (_, text) = pending.pop_front().expect("a message is due");
+            sent = socket.send(Message::Text(text)).map_err(closed);
+        }
+        if let Err(error) = sent {
+            break Err(error);
+        }
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                // parsed before taking the games, which other clients wait for
+                let document = Value::parse(&text);
+                if let Some(reason) = impossible(&document) {
+                    #[cfg(feature = "trace")]
+                    tracing::warn!(client = client.id, reason, "client disconnected");
+                    let error = json::document("error", vec![("message", Value::String(format!("{}, disconnecting", reason)))]);
+                    // the client is dropped anyway
+                    let _ = socket.send(Message::Text(error.to_string()));
+                    break Err(invalid(reason));
+                }
+                let mut games = games.lock().expect("no thread panics holding the games");
+                if let Err(error) = games.handle(&client, document.as_ref().expect("the document was parsed")) {
+                    send(&client, &json::document("error", vec![("message", Value::String(error.to_string()))]));
+                }
+            }
+            Ok(Message::Close(_)) => break Ok(()),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(ref error)) if error.kind() == io::ErrorKind::WouldBlock || error.kind() == io::ErrorKind::TimedOut => {
+                // an archive that can't be written doesn't stop the games
+                let _ = games.lock().expect("no thread panics holding the games").tick(&client);
+            }
+            Err(error) => break Err(closed(error))
+        }
+    };
+    games.lock().expect("no thread panics holding the games").disconnect(&client);
+    result
+}
+
+// Why a message can't come from an honest client, if it can't: it isn't a document, or moves to
+// a cell that isn't on the board. The board checks the other moves.
+fn impossible(document: &io::Result<Value>) -> Option<&'static str> {
+    let document = match *document {
+        Ok(ref document @ Value::Object(_)) => document,
+        _ => return Some("not a document")
+    };
+    match (document.get("kind").and_then(Value::as_str), document.get("cell")) {
+        (Some("move"), Some(cell)) if cell.as_str().and_then(record::parse_move).is_none() => Some("no such cell"),
+        _ => None
+    }
+}
+
+fn closed(error: tungstenite::Error) -> io::Error {
+    match error {
+        tungstenite::Error::Io(error) => error,
+        error => io::Error::other(error.to_string())
+    }
+}
+
+// A time of a clock in seconds, if it is neither negative nor longer than a day.
+fn clock_time(seconds: f64) -> Option<Duration> {
+    Duration::try_from_secs_f64(seconds).ok().filter(|&x| x <= MAX_CLOCK)
+}
+
+fn send(client: &Client, document: &Value) {
+    let _ = client.sender.send((Instant::now(), document.to_string()));
+}
+
+fn color_to_json(color: Color) -> Value {
+    Value::String(if color == Color::Black { "black" } else { "white" }.to_string())
+}
+
+// A color, black when absent.
+fn color_from_json(value: Option<&Value>) -> io::Result<Color> {
+    match value.and_then(Value::as_str) {
+        None | Some("black") => Ok(Color::Black),
+        Some("white") => Ok(Color::White),
+        Some(_) => Err(invalid("invalid color"))
+    }
+}
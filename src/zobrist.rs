@@ -0,0 +1,62 @@
+//! This module hashes positions with Zobrist keys.
+//!
+//! Every piece on every cell has a random 64 bit key, and the hash of a position is the
+//! exclusive or of the keys of its pieces, and of an extra key when White is to move. Equal
+//! positions always have equal hashes, different positions almost never.
+
+use game::{Board, Color, Symmetry};
+
+const fn keys() -> [[u64; 64]; 2] {
+    // SplitMix64 with a fixed seed, so hashes are the same in every build and can be stored
+    let mut keys = [[0; 64]; 2];
+    let mut state: u64 = 0x5245_5645_5253_4921;
+    let mut i = 0;
+    while i < 128 {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        keys[i / 64][i % 64] = z ^ (z >> 31);
+        i += 1;
+    }
+    keys
+}
+
+/// The keys of the black and white pieces, indexed by `y * 8 + x`.
+pub const KEYS: [[u64; 64]; 2] = keys();
+
+/// Key of White being the player to move.
+pub const WHITE_TO_MOVE: u64 = 0x8f1b_bcdc_bfa5_3e0b;
+
+/// The key of a piece on a cell.
+pub fn key(color: Color, x: i8, y: i8) -> u64 {
+    let index = y as usize * 8 + x as usize;
+    match color {
+        Color::Black => KEYS[0][index],
+        Color::White => KEYS[1][index]
+    }
+}
+
+/// Hashes a position with the player to move.
+pub fn hash(board: &Board, color: Color) -> u64 {
+    let mut hash = if color == Color::White { WHITE_TO_MOVE } else { 0 };
+    for (x, column) in board.cells.iter().enumerate() {
+        for (y, cell) in column.iter().enumerate() {
+            if let Some(piece) = *cell {
+                hash ^= key(piece, x as i8, y as i8);
+            }
+        }
+    }
+    hash
+}
+
+/// Hashes a position so that all its rotations and reflections have the same hash.
+///
+/// Returns the smallest hash of the symmetric images with the symmetry producing it. Moves in
+/// the position are mapped by this symmetry to the moves in the canonical image.
+pub fn canonical(board: &Board, color: Color) -> (u64, Symmetry) {
+    Symmetry::all().iter()
+        .map(|&symmetry| (hash(&board.transform(symmetry), color), symmetry))
+        .min_by_key(|&(hash, _)| hash)
+        .expect("there are symmetries")
+}
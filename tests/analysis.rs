@@ -0,0 +1,62 @@
+#![cfg(feature = "ai")]
+
+extern crate rusty_reversi;
+
+use std::time::Duration;
+
+use rusty_reversi::analysis;
+use rusty_reversi::engine::Limits;
+use rusty_reversi::eval::WeightedSquares;
+use rusty_reversi::formats::csv;
+use rusty_reversi::record::{self, GameRecord};
+use rusty_reversi::search::AlphaBeta;
+
+#[test]
+fn annotations_compare_with_the_best_move() {
+    let game = GameRecord { moves: record::parse_transcript("f5d6c3d3c4").unwrap(), ..GameRecord::default() };
+    let mut engine = AlphaBeta::new(2, WeightedSquares::default());
+    let annotated = analysis::annotate(&game, &mut engine, &Limits::default()).unwrap();
+    assert_eq!(annotated.annotations.len(), 5);
+    for annotation in &annotated.annotations {
+        assert!(annotation.best_score >= annotation.score);
+        assert_eq!(annotation.loss(), annotation.best_score - annotation.score);
+    }
+    let illegal = GameRecord { moves: record::parse_transcript("f5a1").unwrap(), ..GameRecord::default() };
+    assert_eq!(analysis::annotate(&illegal, &mut engine, &Limits::default()), Err(1));
+}
+
+#[test]
+fn csv_lists_the_moves() {
+    let game = GameRecord { moves: record::parse_transcript("f5d6").unwrap(), ..GameRecord::default() };
+    let mut engine = AlphaBeta::new(1, WeightedSquares::default());
+    let mut annotated = analysis::annotate(&game, &mut engine, &Limits::default()).unwrap();
+    annotated.annotations[0].time = Some(Duration::from_millis(1200));
+    let mut bytes = Vec::new();
+    csv::write_moves(&mut bytes, &annotated).unwrap();
+    let text = String::from_utf8(bytes).unwrap();
+    let lines = text.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], csv::HEADER);
+    let first = lines[1].split(',').collect::<Vec<_>>();
+    assert_eq!((first[0], first[1], first[2], first[6], first[7], first[8]), ("1", "black", "f5", "3", "4", "1200"));
+    let second = lines[2].split(',').collect::<Vec<_>>();
+    assert_eq!((second[1], second[2], second[6], second[7], second[8]), ("white", "d6", "0", "3", ""));
+}
+
+#[test]
+fn logs_mark_blunders() {
+    let game = GameRecord { moves: record::parse_transcript("f5d6").unwrap(), ..GameRecord::default() };
+    let mut engine = AlphaBeta::new(1, WeightedSquares::default());
+    let mut annotated = analysis::annotate(&game, &mut engine, &Limits::default()).unwrap();
+    annotated.annotations[1].best_move = Some((2, 3));
+    annotated.annotations[1].best_score = annotated.annotations[1].score + 40;
+    assert_eq!(annotated.annotations[1].mark(40), "??");
+    assert_eq!(annotated.annotations[1].mark(60), "?");
+    assert_eq!(annotated.annotations[0].mark(40), "");
+    let log = analysis::format_log(&annotated, 40).unwrap();
+    let lines = log.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("  1. Black f5 ") && lines[0].ends_with(", best"));
+    assert!(lines[1].starts_with("  2. White d6 ") && lines[1].ends_with(" ??"));
+    assert!(lines[1].contains(", best c5 "));
+    assert_eq!(lines[2], "Final score 3-3, 1 blunder");
+}
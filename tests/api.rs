@@ -0,0 +1,95 @@
+#![cfg(feature = "api")]
+
+extern crate rusty_reversi;
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+
+use rusty_reversi::api::Api;
+use rusty_reversi::eval::WeightedSquares;
+use rusty_reversi::formats::json::Value;
+use rusty_reversi::search::AlphaBeta;
+
+fn api() -> Api<AlphaBeta<WeightedSquares>> {
+    Api::new(AlphaBeta::new(2, WeightedSquares::default()))
+}
+
+fn text<'a>(document: &'a Value, key: &str) -> Option<&'a str> {
+    document.get(key).and_then(Value::as_str)
+}
+
+fn cells(document: &Value, key: &str) -> Vec<String> {
+    document.get(key).and_then(Value::as_array).unwrap().iter().map(|x| x.as_str().unwrap().to_string()).collect()
+}
+
+#[test]
+fn games_are_played_through_the_resources() {
+    let mut api = api();
+    let created = api.handle("POST", "/games", r#"{"black": "ann"}"#);
+    assert_eq!(created.status, 201);
+    assert_eq!(text(&created.body, "kind"), Some("state"));
+    assert_eq!(text(&created.body, "black"), Some("ann"));
+    assert_eq!(created.body.get("id"), Some(&Value::Number(1.0)));
+
+    let mut legal = cells(&api.handle("GET", "/games/1/moves", "").body, "legal");
+    legal.sort();
+    assert_eq!(legal, vec!["c4", "d3", "e6", "f5"]);
+
+    let played = api.handle("POST", "/games/1/moves", r#"{"cell": "f5"}"#);
+    assert_eq!(played.status, 200);
+    assert_eq!(cells(&played.body, "moves"), vec!["f5"]);
+    let document = r#"{"schema": 1, "kind": "move", "color": "black", "cell": "d6"}"#;
+    assert_eq!(api.handle("POST", "/games/1/moves", document).status, 400);
+
+    let answered = api.handle("POST", "/games/1/engine", "");
+    assert_eq!(answered.status, 200);
+    assert_eq!(answered.body.get("move").and_then(|x| x.get("color")).and_then(Value::as_str), Some("white"));
+    let stats = answered.body.get("stats").unwrap();
+    assert_eq!(stats.get("depth").and_then(Value::as_f64), Some(2.0));
+    let nodes_by_ply = stats.get("nodes_by_ply").and_then(Value::as_array).unwrap();
+    assert_eq!(nodes_by_ply.iter().map(|x| x.as_f64().unwrap()).sum::<f64>(), stats.get("nodes").and_then(Value::as_f64).unwrap());
+    assert_eq!(stats.get("iterations").and_then(Value::as_array).map(|x| x.len()), Some(1));
+    let state = answered.body.get("state").unwrap();
+    assert_eq!(cells(state, "moves").len(), 2);
+
+    let history = api.handle("GET", "/games/1/history?format=json", "");
+    assert_eq!(text(&history.body, "kind"), Some("game"));
+    assert_eq!(text(&history.body, "black"), Some("ann"));
+    assert_eq!(cells(&history.body, "moves"), cells(state, "moves"));
+}
+
+#[test]
+fn mistakes_are_answered_with_errors() {
+    let mut api = api();
+    assert_eq!(api.handle("GET", "/games/1", "").status, 404);
+    assert_eq!(api.handle("GET", "/players", "").status, 404);
+    assert_eq!(api.handle("POST", "/games", "{").status, 400);
+    let nested = "[".repeat(60_000);
+    assert_eq!(api.handle("POST", "/games", &nested).status, 400);
+    api.handle("POST", "/games", "");
+    assert_eq!(api.handle("POST", "/games/1/moves", &nested).status, 400);
+    assert_eq!(api.handle("PUT", "/games/1", "").status, 405);
+    assert_eq!(api.handle("POST", "/games/1/moves", r#"{"cell": "a1"}"#).status, 409);
+    let error = api.handle("POST", "/games/1/moves", r#"{"cell": "z9"}"#);
+    assert_eq!(error.status, 400);
+    assert_eq!(text(&error.body, "kind"), Some("error"));
+    assert_eq!(api.handle("DELETE", "/games/1", "").status, 204);
+    assert_eq!(api.handle("GET", "/games", "").body, Value::Array(Vec::new()));
+}
+
+#[test]
+fn requests_are_served_over_http() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    thread::spawn(move || api().serve(listener));
+
+    let mut stream = TcpStream::connect(address).unwrap();
+    write!(stream, "POST /games HTTP/1.1\r\nHost: localhost\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{{}}").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 201"), "{}", response);
+    assert!(response.contains("Access-Control-Allow-Origin: *"), "{}", response);
+    let (_, body) = response.split_once("\r\n\r\n").unwrap();
+    assert_eq!(text(&Value::parse(body).unwrap(), "kind"), Some("state"));
+}
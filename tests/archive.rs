@@ -0,0 +1,53 @@
+#![cfg(feature = "archive")]
+
+extern crate rusty_reversi;
+
+use std::io::Cursor;
+
+use rusty_reversi::formats::archive::{Archive, ArchiveWriter};
+use rusty_reversi::random::Random;
+use rusty_reversi::record::{self, GameRecord};
+
+fn game(i: usize) -> GameRecord {
+    GameRecord {
+        black: format!("player {}", i),
+        white: "Rusty Reversi".to_string(),
+        moves: record::parse_transcript(if i.is_multiple_of(2) { "f5d6c3d3c4" } else { "f5f6e6f4" }).unwrap(),
+        black_disks: if i.is_multiple_of(3) { None } else { Some((i % 65) as u8) },
+        tags: vec![("round".to_string(), i.to_string())],
+        ..GameRecord::default()
+    }
+}
+
+#[test]
+fn games_are_read_by_number() {
+    let mut writer = ArchiveWriter::new(Vec::new(), 16).unwrap();
+    for i in 0..100 {
+        assert_eq!(writer.push(&game(i)).unwrap(), i as u64);
+    }
+    let too_long = GameRecord { moves: vec![(0, 0); 256], ..GameRecord::default() };
+    assert!(writer.push(&too_long).is_err());
+    let bytes = writer.finish().unwrap();
+    // the games repeat, they compress well
+    assert!(bytes.len() < 100 * 30);
+    let mut archive = Archive::new(Cursor::new(bytes.clone())).unwrap();
+    assert_eq!(archive.len(), 100);
+    for &i in &[99, 0, 17, 16, 15, 63, 64] {
+        assert_eq!(archive.get(i).unwrap(), game(i as usize));
+    }
+    assert!(archive.get(100).is_err());
+    let sample = archive.sample(10, &mut Random::new(7)).unwrap();
+    assert_eq!(sample.len(), 10);
+    let mut rounds = sample.iter().map(|x| x.tag("round").unwrap().parse::<usize>().unwrap()).collect::<Vec<_>>();
+    rounds.dedup();
+    assert_eq!(rounds.len(), 10);
+    assert!(Archive::new(Cursor::new(bytes[..bytes.len() - 1].to_vec())).is_err());
+}
+
+#[test]
+fn empty_archives_are_valid() {
+    let bytes = ArchiveWriter::new(Vec::new(), 1).unwrap().finish().unwrap();
+    let archive = Archive::new(Cursor::new(bytes)).unwrap();
+    assert!(archive.is_empty());
+    assert!(ArchiveWriter::new(Vec::new(), 0).is_err());
+}
@@ -0,0 +1,160 @@
+#![cfg(feature = "ai")]
+
+extern crate rusty_reversi;
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::thread;
+use std::time::Duration;
+
+use rusty_reversi::arena;
+use rusty_reversi::engine;
+use rusty_reversi::eval;
+use rusty_reversi::game;
+use rusty_reversi::protocol::edax::Edax;
+use rusty_reversi::protocol::external::{Dialect, External};
+use rusty_reversi::search;
+use rusty_reversi::session::Clock;
+use rusty_reversi::stats;
+
+use arena::{run, Options};
+use engine::{Engine, Limits, SearchResult, SearchStats};
+use eval::Heuristic;
+use game::{Board, Color};
+use search::AlphaBeta;
+use stats::{Decision, Sprt};
+
+// Plays the first legal move, or forfeits.
+struct Naive {
+    forfeits: bool
+}
+impl Engine for Naive {
+    fn best_move(&mut self, board: &Board, color: Color, _: &Limits) -> SearchResult {
+        let best = board.legal_moves(color).first().map(|x| x.position).filter(|_| !self.forfeits);
+        SearchResult::new(0, 0, 1, 0, best.into_iter().collect(), Duration::default())
+    }
+
+    fn set_option(&mut self, name: &str, _: &str) -> io::Result<()> {
+        Err(engine::unknown_option(name))
+    }
+}
+
+// Plays the first legal move, slowly.
+struct Slow;
+impl Engine for Slow {
+    fn best_move(&mut self, board: &Board, color: Color, limits: &Limits) -> SearchResult {
+        thread::sleep(Duration::from_millis(30));
+        Naive { forfeits: false }.best_move(board, color, limits)
+    }
+
+    fn set_option(&mut self, name: &str, _: &str) -> io::Result<()> {
+        Err(engine::unknown_option(name))
+    }
+}
+
+#[test]
+fn stronger_player_passes_sprt() {
+    let options = Options { games: 200, sprt: Some(Sprt { elo0: 0.0, elo1: 100.0, ..Sprt::default() }), ..Options::default() };
+    let report = run(&options, &mut AlphaBeta::new(2, Heuristic::default()), &mut Naive { forfeits: false });
+    assert_eq!(report.decision, Some(Decision::AcceptH1));
+    assert!(report.result.games() < 200);
+    let (elo, error) = report.elo();
+    assert!(elo - error > 0.0);
+}
+
+#[test]
+fn both_colors_are_played() {
+    // a player always forfeiting loses every game, whatever its color
+    let report = run(&Options { games: 4, ..Options::default() }, &mut Naive { forfeits: true }, &mut Naive { forfeits: false });
+    assert_eq!(report.result.losses, 4);
+    assert_eq!(report.decision, None);
+    assert_eq!(report.games.len(), 4);
+    assert_eq!((report.games[0].black.as_str(), report.games[1].black.as_str()), ("first", "second"));
+    for record in &report.games {
+        assert_eq!(record.tag("termination"), Some("forfeit"));
+        assert_eq!(record.black_disks, Some(if record.black == "first" { 0 } else { 64 }));
+    }
+}
+
+#[test]
+fn games_are_recorded() {
+    let options = Options { games: 2, opening_plies: 4, ..Options::default() };
+    let report = run(&options, &mut AlphaBeta::new(1, Heuristic::default()), &mut Naive { forfeits: false });
+    for record in &report.games {
+        assert!(record.moves.len() > 4);
+        let positions = record.positions().unwrap();
+        let (board, _) = positions[positions.len() - 1];
+        assert_eq!(record.black_disks, Some(board.count(Color::Black) as u8));
+        assert_eq!(record.tag("termination"), None);
+    }
+    assert_eq!(report.games[0].moves[..4], report.games[1].moves[..4]);
+    assert!(!report.stats[0].iterations.is_empty() && report.stats[0].iterations.iter().all(|x| x.depth == 1));
+    assert_eq!(report.stats[1], SearchStats::default());
+}
+
+#[test]
+fn slow_players_lose_on_time() {
+    let clock = Clock { remaining: Duration::from_millis(50), increment: Duration::default() };
+    let options = Options { games: 2, clock: Some(clock), ..Options::default() };
+    let report = run(&options, &mut Slow, &mut Naive { forfeits: false });
+    assert_eq!(report.result.losses, 2);
+    assert!(report.games.iter().all(|x| x.tag("termination") == Some("time")));
+}
+
+// the program is only built with the terminal interface
+#[cfg(feature = "ui")]
+#[test]
+fn external_engines_play_through_the_edax_protocol() {
+    let command = format!("{} edax --depth 1", env!("CARGO_BIN_EXE_rusty_reversi"));
+    let mut external = External::spawn(&command).unwrap();
+    let board = Board::new();
+    let result = external.best_move(&board, Color::Black, &Limits { depth: Some(1), ..Limits::default() });
+    assert!(board.legal_moves(Color::Black).iter().any(|x| Some(x.position) == result.best));
+    let report = run(&Options { games: 2, ..Options::default() }, &mut external, &mut Naive { forfeits: false });
+    assert!(report.games.iter().all(|x| x.tag("termination").is_none()));
+}
+
+#[test]
+fn remote_engines_play_over_tcp() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut edax = Edax::new(AlphaBeta::new(1, Heuristic::default()));
+        let _ = edax.run(BufReader::new(stream.try_clone().unwrap()), stream);
+    });
+    let mut remote = engine::from_spec(&format!("remote:{}", address)).unwrap();
+    let report = run(&Options { games: 2, ..Options::default() }, &mut remote, &mut Naive { forfeits: false });
+    assert_eq!(report.games.len(), 2);
+    assert!(report.games.iter().all(|x| x.tag("termination").is_none()));
+}
+
+#[test]
+fn external_engines_play_through_the_nboard_protocol() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    let server = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut writer = stream.try_clone().unwrap();
+        let mut received = Vec::new();
+        for line in BufReader::new(stream).lines() {
+            let line = line.unwrap();
+            if line == "go" {
+                writeln!(writer, "status thinking\n=== F5/0.00/0.1").unwrap();
+            }
+            if line == "quit" {
+                break;
+            }
+            received.push(line);
+        }
+        received
+    });
+    let mut external = External::connect(&address).unwrap().speaking(Dialect::NBoard).unwrap();
+    let result = external.best_move(&Board::new(), Color::Black, &Limits { depth: Some(3), ..Limits::default() });
+    assert_eq!(result.best, Some((5, 3)));
+    drop(external);
+    let received = server.join().unwrap();
+    assert_eq!(received[..2], ["nboard 2", "set depth 3"]);
+    assert_eq!(received[2], "set game (;GM[Othello]PC[rusty_reversi]PB[Black]PW[White]TY[8]BO[8 -------- -------- -------- ---O*--- ---*O--- -------- -------- -------- *];)");
+    assert_eq!(received[3], "go");
+}
@@ -0,0 +1,18 @@
+#![cfg(feature = "ai")]
+
+extern crate rusty_reversi;
+
+use rusty_reversi::bench::{self, Stage};
+use rusty_reversi::game::Color;
+
+#[test]
+fn positions_cover_every_stage() {
+    let positions = bench::positions();
+    assert_eq!(positions.len(), 11);
+    for stage in Stage::ALL {
+        assert!(!bench::stage(stage).is_empty(), "no {} position", stage);
+    }
+    assert!(positions.windows(2).all(|x| x[0].stage <= x[1].stage));
+    assert!(positions.iter().all(|x| x.color == Color::Black && !x.board.legal_moves(x.color).is_empty()));
+    assert!(bench::stage(Stage::Endgame).iter().all(|x| x.board.empties() <= bench::ENDGAME_EMPTIES));
+}
@@ -0,0 +1,55 @@
+extern crate rusty_reversi;
+
+use rusty_reversi::bitboard::{self, Bitboards, Mismatch};
+use rusty_reversi::game::{Board, Color};
+use rusty_reversi::random::Random;
+
+#[test]
+fn bitboards_generate_the_same_moves() {
+    let mut random = Random::new(3);
+    for _ in 0..20 {
+        let (mut board, mut color) = (Board::new(), Color::Black);
+        loop {
+            assert_eq!(bitboard::cross_check(&board, color), Vec::new());
+            assert_eq!(bitboard::cross_check(&board, color.flip()), Vec::new());
+            let moves = board.legal_moves(color);
+            let bitboards = Bitboards::new(&board, color);
+            assert_eq!(bitboard::cells(bitboards.moves()), {
+                let mut cells = moves.iter().map(|x| x.position).collect::<Vec<_>>();
+                cells.sort_by_key(|&(x, y)| (y, x));
+                cells
+            });
+            if moves.is_empty() {
+                if board.legal_moves(color.flip()).is_empty() {
+                    break;
+                }
+                color = color.flip();
+                continue;
+            }
+            board = moves[random.below(moves.len())].apply(board);
+            color = color.flip();
+        }
+    }
+}
+
+#[test]
+fn moves_dont_wrap_around_the_board() {
+    // a white disk on h4 next to a black one on a5 doesn't make g4 or b5 a move
+    let mut board = Board { cells: [[None; 8]; 8] };
+    board.cells[7][4] = Some(Color::White);
+    board.cells[0][3] = Some(Color::Black);
+    board.cells[1][3] = Some(Color::White);
+    let bitboards = Bitboards::new(&board, Color::Black);
+    assert_eq!(bitboard::cells(bitboards.moves()), vec![(2, 3)]);
+    assert_eq!(bitboard::cells(bitboards.flips((2, 3))), vec![(1, 3)]);
+    assert_eq!(bitboards.flips((6, 4)), 0);
+    assert_eq!(bitboard::cross_check(&board, Color::Black), Vec::new());
+}
+
+#[test]
+fn mismatches_name_the_cell_and_the_disks() {
+    let mismatch = Mismatch { board: Board::new(), color: Color::Black, position: (5, 4), expected: vec![(4, 4)], found: Vec::new(), listed: false };
+    let text = mismatch.to_string();
+    assert!(text.starts_with("the move generators disagree on f4 for Black in "));
+    assert!(text.ends_with(": the arrays flip e4, the bitboards flip nothing and don't list the move"));
+}
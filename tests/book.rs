@@ -0,0 +1,200 @@
+#![cfg(feature = "ai")]
+
+extern crate rusty_reversi;
+
+use rusty_reversi::book;
+use rusty_reversi::engine;
+use rusty_reversi::eval;
+use rusty_reversi::formats::wthor;
+use rusty_reversi::game;
+use rusty_reversi::random::Random;
+use rusty_reversi::search;
+use rusty_reversi::stats;
+use rusty_reversi::zobrist;
+
+use book::{build, import, Book, BookMove, Booked, BuildOptions, MappedBook, Variety};
+use engine::{Engine, Limits};
+use eval::Heuristic;
+use game::{Board, Color, Symmetry};
+use search::AlphaBeta;
+use stats::MatchResult;
+
+#[test]
+fn book_finds_symmetric_positions() {
+    let mut book = Book::new();
+    // f5 d6
+    assert_eq!(book.insert_line(&[(5, 3), (3, 2)], 2), 2);
+    let after_f5 = Board::new().legal_moves(Color::Black).into_iter().find(|x| x.position == (5, 3)).unwrap().apply(Board::new());
+    assert_eq!(book.best(&after_f5, Color::White), Some(BookMove { position: (3, 2), score: -2, results: MatchResult::default() }));
+    // d3 is the reflection of f5 over the diagonal, where d6 becomes c5
+    let after_d3 = after_f5.transform(Symmetry(4));
+    assert_eq!(zobrist::canonical(&after_d3, Color::White).0, zobrist::canonical(&after_f5, Color::White).0);
+    assert_eq!(book.best(&after_d3, Color::White).map(|x| x.position), Some((2, 3)));
+    assert!(book.probe(&after_f5, Color::Black).is_empty());
+}
+
+#[test]
+fn book_round_trips_through_bytes() {
+    let mut book = Book::new();
+    book.insert_line(&[(5, 3), (3, 2), (2, 3)], -4);
+    let mut bytes = Vec::new();
+    book.write(&mut bytes).unwrap();
+    assert_eq!(Book::read(&bytes[..]).unwrap(), book);
+    bytes[6] = 99;
+    assert!(Book::read(&bytes[..]).is_err());
+}
+
+#[test]
+fn build_expands_from_the_start() {
+    let mut book = Book::new();
+    let options = BuildOptions { positions: 3, depth: 1, ..BuildOptions::default() };
+    assert_eq!(build(&mut book, &options, &Heuristic::default()), 3);
+    // the four first moves are symmetric, so they are a single position
+    let first = book.probe(&Board::new(), Color::Black);
+    assert_eq!(first.len(), 4);
+    assert!(first.iter().all(|x| x.score == first[0].score));
+    assert!(book.len() >= 3);
+}
+
+#[test]
+fn import_counts_results_of_games() {
+    let game = |moves: Vec<(i8, i8)>, black_disks| wthor::Game {
+        tournament: 0,
+        black: 0,
+        white: 0,
+        black_disks,
+        theoretical_disks: black_disks,
+        moves
+    };
+    // f5 d6 twice, d3 c5 is the same line reflected, and an illegal first move
+    let games = [
+        game(vec![(5, 3), (3, 2)], 40),
+        game(vec![(3, 5), (2, 3)], 30),
+        game(vec![(5, 3), (3, 2)], 32),
+        game(vec![(0, 0)], 64)
+    ];
+    let mut book = Book::new();
+    assert_eq!(import(&mut book, &games, 60), 3);
+    // the start is symmetric, but its moves are kept apart
+    let first = book.probe(&Board::new(), Color::Black);
+    assert_eq!(first.len(), 2);
+    // differentials 16 and 0 after f5
+    assert_eq!(first[0], BookMove { position: (5, 3), score: 8, results: MatchResult { wins: 1, draws: 1, losses: 0 } });
+    assert_eq!(first[1], BookMove { position: (3, 5), score: -4, results: MatchResult { wins: 0, draws: 0, losses: 1 } });
+    // after d3, the games continuing with d6 after f5 are found as c5
+    let after_d3 = Board::new().legal_moves(Color::Black).into_iter().find(|x| x.position == (3, 5)).unwrap().apply(Board::new());
+    let reply = book.best(&after_d3, Color::White).unwrap();
+    assert_eq!((reply.position, reply.results.games()), ((2, 3), 3));
+}
+
+#[test]
+fn choose_varies_among_good_moves() {
+    let mut book = Book::new();
+    let board = Board::new();
+    for &(position, score, games) in &[((5, 3), 2, 10), ((3, 5), 1, 10), ((2, 4), 0, 1), ((4, 2), -10, 10)] {
+        let results = MatchResult { wins: games, ..MatchResult::default() };
+        book.insert(&board, Color::Black, BookMove { position, score, results });
+    }
+    let mut random = Random::new(1);
+    let best = book.choose(&board, Color::Black, &Variety::default(), &mut random);
+    assert_eq!(best.map(|x| x.position), Some((5, 3)));
+    let variety = Variety { temperature: 2.0, min_games: 5, max_deviation: 4 };
+    let mut chosen = (0..100).map(|_| book.choose(&board, Color::Black, &variety, &mut random).unwrap().position).collect::<Vec<_>>();
+    chosen.sort();
+    chosen.dedup();
+    // c4 is played too rarely, e6 is too bad
+    assert_eq!(chosen, vec![(3, 5), (5, 3)]);
+    let strict = Variety { min_games: 20, ..variety };
+    assert_eq!(book.choose(&board, Color::Black, &strict, &mut random), None);
+}
+
+#[test]
+fn mapped_book_agrees_with_loaded_book() {
+    let mut book = Book::new();
+    book.insert_line(&[(5, 3), (3, 2), (2, 3)], -4);
+    book.insert_line(&[(5, 3), (5, 2)], 6);
+    let mut bytes = Vec::new();
+    book.write(&mut bytes).unwrap();
+    let mapped = MappedBook::new(&bytes[..]).unwrap();
+    assert_eq!(mapped.len(), book.len());
+    let (mut board, mut color) = (Board::new(), Color::Black);
+    for &position in &[(5, 3), (3, 2), (2, 3)] {
+        assert_eq!(mapped.probe(&board, color).unwrap(), book.probe(&board, color));
+        board = board.legal_moves(color).into_iter().find(|x| x.position == position).unwrap().apply(board);
+        color = color.flip();
+    }
+    assert!(mapped.probe(&board, color).unwrap().is_empty());
+    assert!(MappedBook::new(&bytes[..20]).is_err());
+
+    // engines play from mapped books, and search where their moves can't be read
+    let mut engine = Booked::new(mapped, AlphaBeta::new(1, Heuristic::default()));
+    assert_eq!(engine.best_move(&Board::new(), Color::Black, &Limits::default()).nodes, 0);
+    let mut corrupt = bytes.clone();
+    for i in 0..book.len() {
+        corrupt[20 + 16 * i..28 + 16 * i].copy_from_slice(&u64::MAX.to_le_bytes());
+    }
+    let mapped = MappedBook::new(&corrupt[..]).unwrap();
+    assert!(mapped.probe(&Board::new(), Color::Black).is_err());
+    let mut engine = Booked::new(mapped, AlphaBeta::new(1, Heuristic::default()));
+    assert!(engine.best_move(&Board::new(), Color::Black, &Limits::default()).nodes > 0);
+}
+
+#[test]
+fn book_reads_first_version() {
+    // one position with f5 scored 3
+    let (key, symmetry) = zobrist::canonical(&Board::new(), Color::Black);
+    let (x, y) = symmetry.apply((5, 3));
+    let mut bytes = b"RRBOOK".to_vec();
+    bytes.extend_from_slice(&[1, 0, 1, 0, 0, 0]);
+    bytes.extend_from_slice(&key.to_le_bytes());
+    bytes.extend_from_slice(&[1, (y * 8 + x) as u8, 3]);
+    let book = Book::read(&bytes[..]).unwrap();
+    assert_eq!(book.best(&Board::new(), Color::Black), Some(BookMove { position: (5, 3), score: 3, results: MatchResult::default() }));
+}
+
+#[test]
+fn booked_engine_plays_the_book_first() {
+    let mut book = Book::new();
+    // f5
+    book.insert_line(&[(5, 3)], 6);
+    let mut engine = Booked::new(book, AlphaBeta::new(1, Heuristic::default()));
+    let result = engine.best_move(&Board::new(), Color::Black, &Limits::default());
+    assert_eq!(result.best, Some((5, 3)));
+    assert_eq!(result.score, 6);
+    assert_eq!(result.nodes, 0);
+    let after = Board::new().legal_moves(Color::Black).into_iter().find(|x| x.position == (5, 3)).unwrap().apply(Board::new());
+    assert!(engine.best_move(&after, Color::White, &Limits::default()).nodes > 0);
+}
+
+#[test]
+fn booked_engine_varies_its_book_moves() {
+    // the replies to f5 score the same
+    let after = Board::new().legal_moves(Color::Black).into_iter().find(|x| x.position == (5, 3)).unwrap().apply(Board::new());
+    let mut book = Book::new();
+    for legal_move in after.legal_moves(Color::White) {
+        book.insert(&after, Color::White, BookMove { position: legal_move.position, score: 0, results: MatchResult::default() });
+    }
+    let replies = |options: &[(&str, &str)]| {
+        let mut moves = (0..20).map(|seed| {
+            let mut engine = Booked::new(book.clone(), AlphaBeta::new(1, Heuristic::default()));
+            engine.random = Random::new(seed);
+            for &(name, value) in options {
+                engine.set_option(name, value).unwrap();
+            }
+            engine.best_move(&after, Color::White, &Limits::default()).best.unwrap()
+        }).collect::<Vec<_>>();
+        moves.sort();
+        moves.dedup();
+        moves
+    };
+    assert_eq!(replies(&[]).len(), 1);
+    assert_eq!(replies(&[("book_temperature", "1")]).len(), book.probe(&after, Color::White).len());
+    assert_eq!(book.probe(&after, Color::White).len(), 3);
+    // moves without results are never played
+    let mut engine = Booked::new(book, AlphaBeta::new(1, Heuristic::default()));
+    engine.set_option("book_min_games", "1").unwrap();
+    assert!(engine.best_move(&after, Color::White, &Limits::default()).nodes > 0);
+    assert!(engine.set_option("book_temperature", "-1").is_err());
+    assert!(engine.set_option("book_max_deviation", "x").is_err());
+    assert!(engine.set_option("depth", "2").is_ok());
+}
@@ -0,0 +1,133 @@
+#![cfg(feature = "chat")]
+
+extern crate rusty_reversi;
+
+use std::env;
+use std::fs;
+use std::slice;
+
+use std::io;
+
+use rusty_reversi::chat::{Bot, Score, User};
+use rusty_reversi::engine::{Engine, Limits, SearchResult};
+use rusty_reversi::eval::WeightedSquares;
+use rusty_reversi::game::{Board, Color, Coord};
+use rusty_reversi::record;
+use rusty_reversi::search::AlphaBeta;
+
+fn user(id: &str, name: &str) -> User {
+    User { id: id.to_string(), name: name.to_string() }
+}
+
+fn bot() -> Bot<AlphaBeta<WeightedSquares>> {
+    Bot::new(AlphaBeta::new(2, WeightedSquares::default()), "bot")
+}
+
+#[test]
+fn members_play_the_engine() {
+    let (mut bot, ann) = (bot(), user("1", "ann"));
+    assert!(bot.handle("general", &ann, "f5", &[]).is_none());
+    let reply = bot.handle("general", &ann, "!challenge", &[]).unwrap();
+    assert!(reply.text.starts_with("ann (Black) against bot (White).\nann (Black) 2 - 2 bot (White).\nann to move."));
+    assert!(reply.image.unwrap().starts_with(b"\x89PNG"));
+
+    let reply = bot.handle("general", &ann, "a1", &[]).unwrap();
+    assert_eq!((reply.text.as_str(), reply.image), ("a1 isn't a legal move.", None));
+    let reply = bot.handle("general", &ann, "f5", &[]).unwrap();
+    assert!(reply.text.starts_with("ann plays f5. bot plays "));
+    assert!(reply.text.ends_with("ann to move."));
+    assert_eq!(bot.session("general").unwrap().history().len(), 2);
+    assert!(bot.handle("general", &ann, "hello", &[]).is_none());
+    assert!(bot.handle("random", &ann, "!board", &[]).unwrap().image.is_none());
+    assert!(bot.handle("general", &ann, "!board", &[]).unwrap().image.is_some());
+    assert_eq!(bot.handle("general", &ann, "!challenge", &[]).unwrap().text, "A game is being played in this channel, finish it first.");
+
+    let reply = bot.handle("general", &ann, "!resign", &[]).unwrap();
+    assert!(reply.text.starts_with("ann resigns."));
+    assert!(reply.text.ends_with("bot wins."));
+    assert_eq!(bot.scores("general").unwrap()["ann"], Score { wins: 0, losses: 1, draws: 0 });
+    assert_eq!(bot.handle("general", &ann, "!scores", &[]).unwrap().text, "ann: 0 won, 1 lost, 0 drawn\nbot: 1 won, 0 lost, 0 drawn");
+}
+
+// An engine playing a given move, or none, like a program that died.
+struct Broken(Option<Coord>);
+impl Engine for Broken {
+    fn best_move(&mut self, _: &Board, _: Color, _: &Limits) -> SearchResult {
+        SearchResult::new(0, 0, 0, 0, self.0.into_iter().collect(), Default::default())
+    }
+
+    fn set_option(&mut self, name: &str, _: &str) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::InvalidInput, name.to_string()))
+    }
+}
+
+#[test]
+fn engines_failing_to_move_forfeit() {
+    let ann = user("1", "ann");
+    for engine in [Broken(None), Broken(Some((0, 0)))] {
+        let mut bot = Bot::new(engine, "bot");
+        bot.handle("general", &ann, "!challenge", &[]).unwrap();
+        let reply = bot.handle("general", &ann, "f5", &[]).unwrap();
+        assert!(reply.text.starts_with("ann plays f5. bot fails to move and forfeits."), "{}", reply.text);
+        assert!(reply.text.ends_with("ann wins."), "{}", reply.text);
+        assert_eq!(bot.scores("general").unwrap()["ann"], Score { wins: 1, losses: 0, draws: 0 });
+        assert!(!bot.is_playing("general"));
+    }
+}
+
+#[test]
+fn members_play_each_other() {
+    let (mut bot, ann, bob, eve) = (bot(), user("1", "ann"), user("2", "bob"), user("3", "eve"));
+    assert_eq!(bot.handle("general", &ann, "!challenge @ann", slice::from_ref(&ann)).unwrap().text, "You can't challenge yourself.");
+    bot.handle("general", &ann, "!challenge @bob", slice::from_ref(&bob)).unwrap();
+    assert_eq!(bot.handle("general", &bob, "f5", &[]).unwrap().text, "It is the turn of ann.");
+    assert!(bot.handle("general", &eve, "f5", &[]).is_none());
+    assert!(bot.handle("general", &eve, "!resign", &[]).is_none());
+    assert!(bot.handle("general", &ann, "e6", &[]).unwrap().text.ends_with("bob to move."));
+
+    // the fastest win, wiping out White
+    for (i, cell) in ["f4", "e3", "f6", "g5", "d6", "e7", "f5"].iter().enumerate() {
+        let player = if i % 2 == 0 { &bob } else { &ann };
+        assert!(bot.handle("general", player, cell, &[]).unwrap().text.ends_with(" to move."));
+    }
+    let reply = bot.handle("general", &ann, "c5", &[]).unwrap();
+    assert!(reply.text.ends_with("ann (Black) 13 - 0 bob (White).\nann wins."));
+    assert!(bot.handle("general", &bob, "!resign", &[]).is_none());
+    assert_eq!(bot.scores("general").unwrap()["bob"], Score { wins: 0, losses: 1, draws: 0 });
+    assert!(bot.handle("general", &bob, "!challenge @ann", slice::from_ref(&ann)).unwrap().text.ends_with("bob to move."));
+}
+
+#[test]
+fn games_are_saved_and_loaded() {
+    let (mut bot, ann, bob) = (bot(), user("1", "ann"), user("2", "bob"));
+    bot.prefix = '/';
+    assert!(bot.handle("-100", &ann, "!challenge", &[]).is_none());
+    bot.handle("-100", &ann, "/challenge", &[]).unwrap();
+    bot.handle("-100", &ann, "f5", &[]).unwrap();
+    bot.handle("7", &ann, "/challenge @bob", slice::from_ref(&bob)).unwrap();
+    bot.handle("7", &ann, "/resign", &[]).unwrap();
+    bot.handle("7", &bob, "/challenge", &[]).unwrap();
+
+    let directory = env::temp_dir().join("rusty_reversi_chat_test");
+    fs::create_dir_all(&directory).unwrap();
+    for channel in ["-100", "7"] {
+        bot.save(&directory, channel).unwrap();
+    }
+    assert!(bot.save(&directory, "../7").is_err());
+    let mut loaded = self::bot();
+    loaded.prefix = '/';
+    assert_eq!(loaded.load(&directory).unwrap(), vec!["-100", "7"]);
+    for channel in ["-100", "7"] {
+        assert_eq!(loaded.session(channel), bot.session(channel));
+        assert_eq!(loaded.scores(channel), bot.scores(channel));
+    }
+    // the engine and the members keep their seats
+    let session = loaded.session("-100").unwrap();
+    let cell = record::format_move(session.board().legal_moves(session.color())[0].position);
+    assert!(loaded.handle("-100", &bob, &cell, &[]).is_none());
+    assert!(loaded.handle("-100", &ann, &cell, &[]).unwrap().text.starts_with(&format!("ann plays {}. bot plays ", cell)));
+    assert_eq!(loaded.session("-100").unwrap().history().len(), 4);
+    assert_eq!(loaded.handle("7", &bob, "/challenge", &[]).unwrap().text, "A game is being played in this channel, finish it first.");
+    assert_eq!(loaded.scores("7").unwrap()["bob"], Score { wins: 1, losses: 0, draws: 0 });
+    fs::remove_dir_all(&directory).unwrap();
+}
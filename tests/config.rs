@@ -0,0 +1,76 @@
+#![cfg(feature = "ui")]
+
+extern crate rusty_reversi;
+
+use std::time::Duration;
+
+use rusty_reversi::config::Config;
+use rusty_reversi::terminal::Theme;
+
+const EXAMPLE: &str = r##"
+# defaults of rusty_reversi
+[engine]
+name = "alphabeta"      # as given to --engine
+book = "/home/me/othello.book"
+book_temperature = 2
+book_max_deviation = 4
+depth = 10
+time = 5.0
+memory = 512
+
+[engine.options]
+hash = 64
+endgame = 18
+
+[ui]
+theme = "high-contrast"
+unicode = true
+
+[keys]
+up = "k"
+undo = 'backspace'
+quit = "#"
+"##;
+
+#[test]
+fn example_is_read() {
+    let config = Config::parse(EXAMPLE).unwrap();
+    assert_eq!(config.engine.name.as_deref(), Some("alphabeta"));
+    assert_eq!(config.engine.book.as_ref().and_then(|x| x.to_str()), Some("/home/me/othello.book"));
+    assert_eq!(config.engine.book_temperature, Some(2.0));
+    assert_eq!(config.engine.book_min_games, None);
+    assert_eq!(config.engine.book_max_deviation, Some(4));
+    assert_eq!(config.engine.depth, Some(10));
+    assert_eq!(config.engine.nodes, None);
+    assert_eq!(config.engine.time, Some(Duration::from_secs(5)));
+    assert_eq!(config.engine.memory, Some(512));
+    assert_eq!(config.engine.options, vec![("hash".to_string(), "64".to_string()), ("endgame".to_string(), "18".to_string())]);
+    assert_eq!(config.theme, Some(Theme::HighContrast));
+    assert!(config.unicode);
+    assert!(!config.accessible);
+    assert_eq!(config.keys, vec![
+        ("up".to_string(), "k".to_string()),
+        ("undo".to_string(), "backspace".to_string()),
+        ("quit".to_string(), "#".to_string())
+    ]);
+    assert_eq!(Config::parse("").unwrap(), Config::default());
+}
+
+#[test]
+fn mistakes_are_errors() {
+    assert!(Config::parse("[engine]\ndepht = 10\n").is_err());
+    assert!(Config::parse("[engine]\ndepth = \"10\"\n").is_err());
+    assert!(Config::parse("[engine]\ndepth = -1\n").is_err());
+    assert!(Config::parse("[engine]\ntime = -1\n").is_err());
+    assert!(Config::parse("[engine]\nbook_temperature = -0.5\n").is_err());
+    assert!(Config::parse("[engine]\nbook_min_games = -1\n").is_err());
+    assert!(Config::parse("[engine]\nbook_max_deviation = 200\n").is_err());
+    let error = Config::parse("[engine]\n\ntime = 1e30\n").unwrap_err();
+    assert_eq!(error.to_string(), "line 3: invalid value for engine.time");
+    assert!(Config::parse("[ui]\ntheme = \"pink\"\n").is_err());
+    assert!(Config::parse("[ui]\nunicode = yes\n").is_err());
+    assert!(Config::parse("[keys]\nup = 1\n").is_err());
+    assert!(Config::parse("[engine\n").is_err());
+    assert!(Config::parse("depth\n").is_err());
+    assert!(Config::parse("depth = 10\n").is_err());
+}
@@ -0,0 +1,54 @@
+extern crate rusty_reversi;
+
+use rusty_reversi::correspondence::{self, VERSION};
+use rusty_reversi::game::Color;
+use rusty_reversi::record;
+
+#[test]
+fn tokens_carry_the_game_and_the_proposed_move() {
+    let token = correspondence::encode(&[], (5, 3)).unwrap();
+    assert!(token.starts_with(VERSION));
+    assert_eq!(token.len(), 3);
+    let received = correspondence::decode(&token).unwrap();
+    assert_eq!((received.history.len(), received.proposed, received.color), (0, (5, 3), Color::White));
+
+    let answer = received.reply(record::parse_move("d6").unwrap()).unwrap();
+    let received = correspondence::decode(&answer).unwrap();
+    assert_eq!(received.moves(), record::parse_transcript("f5d6").unwrap());
+    assert_eq!(received.color, Color::Black);
+    assert!(received.reply((0, 0)).is_err());
+    assert!(token.bytes().all(|x| x.is_ascii_alphanumeric() || x == b'-' || x == b'_'));
+
+    // the fastest win ends the game
+    let moves = record::parse_transcript("e6f4e3f6g5d6e7f5c5").unwrap();
+    let token = correspondence::encode(&moves[..8], moves[8]).unwrap();
+    let received = correspondence::decode(&token).unwrap();
+    assert!(received.is_over());
+    assert_eq!(received.board.count(Color::White), 0);
+    assert!(received.reply((0, 0)).is_err());
+}
+
+#[test]
+fn tokens_are_checked() {
+    let moves = record::parse_transcript("f5d6c3d3c4").unwrap();
+    assert_eq!(correspondence::encode(&moves, (0, 0)), Err(5));
+    assert_eq!(correspondence::encode(&moves[..1], (0, 0)), Err(1));
+    let token = correspondence::encode(&moves[..4], moves[4]).unwrap();
+    assert_eq!(correspondence::decode(&format!(" {}\n", token)).unwrap().moves(), moves);
+
+    // every single mistyped character is caught
+    let alphabet = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+    for i in 1..token.len() {
+        for replacement in alphabet.chars().filter(|&x| x != token.as_bytes()[i] as char) {
+            let mistyped = format!("{}{}{}", &token[..i], replacement, &token[i + 1..]);
+            assert!(correspondence::decode(&mistyped).is_err(), "{}", mistyped);
+        }
+    }
+    assert!(correspondence::decode("").is_err());
+    assert!(correspondence::decode(&token[..1]).is_err());
+    assert!(correspondence::decode(&format!("9{}", &token[1..])).unwrap_err().to_string().contains("version"));
+    assert!(correspondence::decode(&format!("{}*", token)).is_err());
+
+    // a well formed token opening with a1
+    assert_eq!(correspondence::decode("1AA").unwrap_err().to_string(), "illegal move 1");
+}
@@ -0,0 +1,72 @@
+#![cfg(feature = "db")]
+
+extern crate rusty_reversi;
+
+use rusty_reversi::analysis::Annotation;
+use rusty_reversi::db::{Database, Filter, Winner};
+use rusty_reversi::record::{self, GameRecord};
+
+fn game(moves: &str) -> GameRecord {
+    GameRecord {
+        black: "Tastet".to_string(),
+        white: "Shaman".to_string(),
+        event: "Paris".to_string(),
+        date: "1996".to_string(),
+        moves: record::parse_transcript(moves).unwrap(),
+        black_disks: Some(36),
+        theoretical_disks: None,
+        tags: vec![("round".to_string(), "3".to_string())]
+    }
+}
+
+#[test]
+fn games_are_stored() {
+    let mut database = Database::open_in_memory().unwrap();
+    let first = database.add_game(&game("f5d6c3d3c4"), "test").unwrap();
+    let second = database.add_game(&game("f5f6e6f4"), "test").unwrap();
+    assert_ne!(first, second);
+    assert_eq!(database.count().unwrap(), 2);
+    assert_eq!(database.game(first).unwrap(), Some(game("f5d6c3d3c4")));
+    assert_eq!(database.game(second + 1).unwrap(), None);
+    assert!(database.add_game(&game("f5f5"), "test").is_err());
+    assert_eq!(database.add_games(&[game("f5f5"), game("c4c3")], "test").unwrap(), 1);
+    assert_eq!(database.count().unwrap(), 3);
+}
+
+#[test]
+fn analysis_is_stored() {
+    let mut database = Database::open_in_memory().unwrap();
+    let id = database.add_game(&game("f5d6"), "test").unwrap();
+    let annotation = |score, best_move, comment: &str| Annotation { score, best_move, best_score: 0, time: None, comment: comment.to_string() };
+    database.add_analysis(id, &[annotation(-2, record::parse_move("c4"), ""), annotation(0, record::parse_move("d6"), "best")]).unwrap();
+    database.add_analysis(id, &[annotation(0, None, "again")]).unwrap();
+    assert_eq!(database.analysis(id).unwrap(), vec![annotation(0, None, "again")]);
+}
+
+#[test]
+fn games_are_found_by_position() {
+    let mut database = Database::open_in_memory().unwrap();
+    let mut lost = game("f5d6c3d3c4");
+    lost.black_disks = Some(20);
+    lost.date = "2001-05-04".to_string();
+    let first = database.add_game(&game("f5d6c3d3c4"), "test").unwrap();
+    let second = database.add_game(&lost, "test").unwrap();
+    // the same opening rotated
+    let third = database.add_game(&game("e6f4e3"), "test").unwrap();
+    let fourth = database.add_game(&game("f5f6"), "test").unwrap();
+    let (board, color) = game("f5d6").positions().unwrap()[2];
+    assert_eq!(database.games_reaching(&board, color, &Filter::default()).unwrap(), vec![first, second, third]);
+    let filter = Filter { winner: Some(Winner::Black), ..Filter::default() };
+    assert_eq!(database.games_reaching(&board, color, &filter).unwrap(), vec![first, third]);
+    assert_eq!(database.games(&Filter { year: Some(2001), ..Filter::default() }).unwrap(), vec![second]);
+    assert_eq!(database.games(&Filter { player: Some("Tastet".to_string()), ..Filter::default() }).unwrap(), vec![first, second, third, fourth]);
+    assert_eq!(database.games(&Filter { player: Some("nobody".to_string()), ..Filter::default() }).unwrap(), vec![]);
+
+    let continuations = database.continuations(&board, color, &Filter::default()).unwrap();
+    assert_eq!(continuations.len(), 2);
+    let c3 = continuations[0];
+    assert_eq!((c3.position, c3.games), (record::parse_move("c3").unwrap(), 2));
+    assert_eq!((c3.results.wins, c3.results.losses), (1, 1));
+    assert_eq!(c3.disks, -8.0);
+    assert_eq!(continuations[1].position, record::parse_move("c5").unwrap());
+}
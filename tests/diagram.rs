@@ -0,0 +1,23 @@
+#![cfg(feature = "formats")]
+
+extern crate rusty_reversi;
+
+use rusty_reversi::formats::diagram;
+use rusty_reversi::game::{Board, Color};
+
+#[test]
+fn positions_are_written_as_tables() {
+    let board = Board::new();
+    let markdown = diagram::markdown(&board, Color::Black);
+    let lines = markdown.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], "|   | a | b | c | d | e | f | g | h |");
+    assert_eq!(lines[1], "|:-:|:-:|:-:|:-:|:-:|:-:|:-:|:-:|:-:|");
+    assert_eq!(lines[4], "| **3** |   |   |   | · |   |   |   |   |");
+    assert_eq!(lines[5], "| **4** |   |   | · | ⚪ | ⚫ |   |   |   |");
+    assert_eq!(lines[11], "Black to move");
+    let html = diagram::html(&board, Color::White);
+    assert_eq!(html.matches("<tr>").count(), 9);
+    assert_eq!(html.matches("<td").count(), 64);
+    assert_eq!(html.matches("border-radius").count(), 4 + 4);
+    assert!(html.ends_with("<p>White to move</p>\n"));
+}
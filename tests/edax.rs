@@ -0,0 +1,47 @@
+#![cfg(feature = "ai")]
+
+extern crate rusty_reversi;
+
+use rusty_reversi::endgame;
+use rusty_reversi::eval::Heuristic;
+use rusty_reversi::game::{Board, Color};
+use rusty_reversi::protocol::edax::Edax;
+use rusty_reversi::record;
+use rusty_reversi::search::AlphaBeta;
+
+#[test]
+fn edax_commands_play_a_game() {
+    let mut edax = Edax::new(AlphaBeta::new(2, Heuristic::default()));
+    assert_eq!(edax.execute("f5 d6").unwrap(), "");
+    assert_eq!(edax.execute("undo").unwrap(), "");
+    assert_eq!(edax.position.color, Color::White);
+    assert_eq!(edax.execute("level 1").unwrap(), "");
+    assert!(edax.execute("go").unwrap().starts_with("Edax plays "));
+    assert_eq!(edax.position.color, Color::Black);
+    assert_eq!(edax.execute("hint 3").unwrap().lines().count(), 3);
+    assert!(edax.execute("play a1").is_err());
+    assert!(edax.execute("fly").is_err());
+
+    let mut output = Vec::new();
+    edax.run(&b"init\nboard\nquit\ngo\n"[..], &mut output).unwrap();
+    assert!(String::from_utf8(output).unwrap().ends_with("Black to move\n"));
+}
+
+#[test]
+fn edax_solves_positions() {
+    let (mut board, mut color) = (Board::new(), Color::Black);
+    while board.empties() > 8 {
+        match board.legal_moves(color).first() {
+            Some(legal_move) => board = legal_move.apply(board),
+            None if board.legal_moves(color.flip()).is_empty() => break,
+            None => {}
+        }
+        color = color.flip();
+    }
+    let position = record::format_position(&board, color);
+    assert_eq!(record::parse_position(&position), Some((board, color)));
+    let solution = endgame::solve(&board, color);
+    let mut edax = Edax::new(AlphaBeta::new(2, Heuristic::default()));
+    let answer = edax.execute(&format!("solve {}", position)).unwrap();
+    assert_eq!(answer.split_whitespace().nth(1), Some(&format!("{:+}", solution.score)[..]));
+}
@@ -0,0 +1,85 @@
+#![cfg(feature = "ai")]
+
+extern crate rusty_reversi;
+
+use rusty_reversi::endgame;
+use rusty_reversi::eval;
+use rusty_reversi::game;
+use rusty_reversi::random::Random;
+use rusty_reversi::search;
+use rusty_reversi::small::{self, SmallBoard};
+
+use endgame::{solve, solve_cached, Solution, SolutionCache};
+use eval::WeightedSquares;
+use game::{Board, Color, Symmetry};
+use search::{minimax, EndgameMode, WIN};
+
+// Plays random moves until the given number of empty cells.
+fn random_position(empties: usize, seed: u64) -> (Board, Color) {
+    let mut random = Random::new(seed);
+    let (mut board, mut color) = (Board::new(), Color::Black);
+    while board.empties() > empties {
+        let moves = board.legal_moves(color);
+        if !moves.is_empty() {
+            board = moves[random.below(moves.len())].apply(board);
+        } else if board.legal_moves(color.flip()).is_empty() {
+            break;
+        }
+        color = color.flip();
+    }
+    (board, color)
+}
+
+#[test]
+fn solve_agrees_with_minimax() {
+    for seed in 0..4 {
+        let (board, color) = random_position(7, seed);
+        let score = minimax(&board, color, 20, &WeightedSquares::default()).score;
+        let diff = score - score.signum() * WIN;
+        assert_eq!(i32::from(solve(&board, color).score), diff);
+    }
+}
+
+#[test]
+fn solve_agrees_with_the_small_board_solver() {
+    for seed in 0..4 {
+        let (board, color) = random_position(10, seed);
+        assert_eq!(i32::from(solve(&board, color).score), small::solve(&SmallBoard::from_board(&board), color).score);
+    }
+}
+
+#[test]
+fn cache_finds_symmetric_positions() {
+    let (board, color) = random_position(11, 7);
+    let mut cache = SolutionCache::new();
+    let solution = solve_cached(&board, color, &mut cache);
+    assert_eq!(solution, solve(&board, color));
+    assert!(!cache.is_empty());
+    let mut bytes = Vec::new();
+    cache.write(&mut bytes).unwrap();
+    let loaded = SolutionCache::read(&bytes[..]).unwrap();
+    assert_eq!(loaded, cache);
+    let symmetry = Symmetry(5);
+    let expected = Solution { best_move: solution.best_move.map(|x| symmetry.apply(x)), ..solution };
+    assert_eq!(loaded.get(&board.transform(symmetry), color), Some(expected));
+}
+
+#[test]
+fn best_lines_reach_the_solved_score() {
+    for seed in 0..4 {
+        let (board, color) = random_position(9, seed);
+        let (solution, line, nodes) = endgame::solve_line(&board, color, EndgameMode::Exact);
+        assert_eq!(solution, solve(&board, color));
+        assert!(nodes > 0);
+        let (mut end, mut turn) = (board, color);
+        for &position in &line {
+            if end.legal_moves(turn).is_empty() {
+                turn = turn.flip();
+            }
+            end = end.legal_moves(turn).into_iter().find(|x| x.position == position).unwrap().apply(end);
+            turn = turn.flip();
+        }
+        assert!(end.legal_moves(Color::Black).is_empty() && end.legal_moves(Color::White).is_empty());
+        assert_eq!(end.count(color) as i8 - end.count(color.flip()) as i8, solution.score);
+    }
+}
@@ -0,0 +1,89 @@
+#![cfg(feature = "ai")]
+
+extern crate rusty_reversi;
+
+use std::time::Duration;
+
+use rusty_reversi::endgame;
+use rusty_reversi::engine;
+use rusty_reversi::eval;
+use rusty_reversi::game;
+use rusty_reversi::mcts;
+use rusty_reversi::search;
+
+use endgame::Solver;
+use engine::{verify, Engine, Failure, Limits, VerifyOptions};
+use eval::{Heuristic, WeightedSquares};
+use game::{Board, Color};
+use mcts::{Config, Mcts};
+use search::{alpha_beta, AlphaBeta, Minimax, WIN};
+
+fn corner_position() -> Board {
+    let mut board = Board { cells: [[None; 8]; 8] };
+    board.cells[4][4] = Some(Color::Black);
+    board.cells[5][5] = Some(Color::White);
+    board.cells[6][6] = Some(Color::Black);
+    board
+}
+
+#[test]
+fn every_engine_takes_the_corner() {
+    let mut engines: Vec<Box<dyn Engine>> = vec![
+        Box::new(Minimax { depth: 1, evaluator: WeightedSquares::default() }),
+        Box::new(AlphaBeta::new(1, WeightedSquares::default())),
+        Box::new(Mcts::new(Config { simulations: 200, ..Config::default() }).unwrap()),
+        Box::new(Solver::default())
+    ];
+    for engine in &mut engines {
+        let result = engine.best_move(&corner_position(), Color::White, &Limits::default());
+        assert_eq!(result.best, Some((7, 7)));
+        assert!(result.score > 0);
+    }
+    // White wins 5 to 0
+    assert_eq!(engines[3].best_move(&corner_position(), Color::White, &Limits::default()).score, WIN + 5);
+}
+
+#[test]
+fn options_configure_alpha_beta() {
+    let mut engine = AlphaBeta::new(1, WeightedSquares::default());
+    engine.set_option("depth", "3").unwrap();
+    engine.set_option("hash", "1").unwrap();
+    engine.set_option("endgame_mode", "wld").unwrap();
+    assert_eq!(engine.depth, 3);
+    assert!(engine.table.is_some());
+    assert!(engine.endgame.is_some());
+    engine.set_option("endgame", "0").unwrap();
+    assert!(engine.endgame.is_none());
+    assert!(engine.set_option("depth", "deep").is_err());
+    assert!(engine.set_option("colour", "black").is_err());
+    // a time limit deepens iteratively up to the depth
+    let limits = Limits { time: Some(Duration::from_secs(60)), ..Limits::default() };
+    let expected = alpha_beta(&Board::new(), Color::Black, 3, &WeightedSquares::default());
+    let result = engine.best_move(&Board::new(), Color::Black, &limits);
+    assert_eq!((result.score, result.depth), (expected.score, 3));
+    assert!(result.tt_hits > 0);
+}
+
+#[cfg(not(all(feature = "plugins", unix)))]
+#[test]
+fn plugins_are_unknown_without_their_feature() {
+    let error = engine::from_spec("alphabeta:plugin=evaluation.so").err().unwrap();
+    assert_eq!(error.to_string(), "unknown option: plugin");
+}
+
+#[test]
+fn alpha_beta_is_consistent() {
+    let mut engine = AlphaBeta::new(3, Heuristic::default());
+    let options = VerifyOptions { positions: 5, ..VerifyOptions::default() };
+    assert_eq!(verify(&mut engine, &options), Vec::new());
+    assert!(engine.table.is_none());
+}
+
+#[test]
+fn verify_finds_asymmetric_evaluations() {
+    let mut weights = WeightedSquares::default();
+    weights.weights[0][0] = 500;
+    let mut engine = Minimax { depth: 1, evaluator: weights };
+    let failures = verify(&mut engine, &VerifyOptions { positions: 5, depth: 1, seed: 1 });
+    assert!(failures.iter().any(|x| matches!(*x, Failure::Symmetry { .. })));
+}
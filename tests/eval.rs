@@ -0,0 +1,182 @@
+#![cfg(feature = "ai")]
+
+extern crate rusty_reversi;
+
+use rusty_reversi::eval;
+use rusty_reversi::game;
+use rusty_reversi::random::Random;
+
+use eval::{features, mobility, potential_mobility, stability, stable_disks, Evaluator, Heuristic, Phase, Phased, WeightedSquares, FEATURE_NAMES};
+use game::{Board, Color};
+
+#[test]
+fn weighted_squares_are_symmetric() {
+    let weights = WeightedSquares::default().weights;
+    for x in 0..8 {
+        for y in 0..8 {
+            assert_eq!(weights[x][y], weights[7 - x][y]);
+            assert_eq!(weights[x][y], weights[x][7 - y]);
+            assert_eq!(weights[x][y], weights[y][x]);
+        }
+    }
+    assert_eq!(weights[0][0], 100);
+    assert_eq!(weights[1][1], -50);
+}
+
+#[test]
+fn weighted_squares_are_zero_sum() {
+    let evaluator = WeightedSquares::default();
+    let mut board = Board::new();
+    board.cells[0][0] = Some(Color::White);
+    let white = evaluator.evaluate(&board, Color::White, Phase::of(&board));
+    assert_eq!(white, 100);
+    assert_eq!(evaluator.evaluate(&board, Color::Black, Phase::of(&board)), -white);
+}
+
+#[test]
+fn heuristic_breakdown_adds_up() {
+    let evaluator = Heuristic::default();
+    let mut board = Board::new();
+    board = board.legal_moves(Color::Black)[0].apply(board);
+    let breakdown = evaluator.breakdown(&board, Color::White, Phase::of(&board));
+    assert_eq!(breakdown.total(), evaluator.evaluate(&board, Color::White, Phase::of(&board)));
+    assert_eq!(breakdown.mobility, -evaluator.breakdown(&board, Color::Black, Phase::of(&board)).mobility);
+}
+
+#[test]
+fn mobility_in_starting_position() {
+    let board = Board::new();
+    assert_eq!(mobility(&board, Color::Black), 4);
+    assert_eq!(potential_mobility(&board, Color::Black), 10);
+}
+
+#[test]
+fn stability_grows_from_corners() {
+    let mut board = Board::new();
+    assert_eq!(stability(&board, Color::Black), 0);
+    board.cells[0][0] = Some(Color::White);
+    board.cells[1][0] = Some(Color::White);
+    board.cells[2][0] = Some(Color::Black);
+    board.cells[1][1] = Some(Color::White);
+    let stable = stable_disks(&board, Color::White);
+    assert!(stable[0][0] && stable[1][0]);
+    // the X square can still be flipped along the diagonal
+    assert!(!stable[1][1]);
+    assert_eq!(stability(&board, Color::Black), 0);
+}
+
+// The pieces found stable by checking every cell again until nothing changes.
+fn swept_stable_disks(board: &Board, color: Color) -> [[bool; 8]; 8] {
+    let full = |x: i8, y: i8, dx: i8, dy: i8| {
+        let (mut x, mut y) = (x, y);
+        while (0..8).contains(&(x - dx)) && (0..8).contains(&(y - dy)) {
+            x -= dx;
+            y -= dy;
+        }
+        while (0..8).contains(&x) && (0..8).contains(&y) {
+            if board.cells[x as usize][y as usize].is_none() {
+                return false;
+            }
+            x += dx;
+            y += dy;
+        }
+        true
+    };
+    let mut stable = [[false; 8]; 8];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for x in 0..8 {
+            for y in 0..8 {
+                if stable[x as usize][y as usize] || board.cells[x as usize][y as usize] != Some(color) {
+                    continue;
+                }
+                let anchored = |x: i8, y: i8| !(0..8).contains(&x) || !(0..8).contains(&y) || stable[x as usize][y as usize];
+                if [(1, 0), (0, 1), (1, 1), (1, -1)].iter().all(|&(dx, dy)| full(x, y, dx, dy) || anchored(x - dx, y - dy) || anchored(x + dx, y + dy)) {
+                    stable[x as usize][y as usize] = true;
+                    changed = true;
+                }
+            }
+        }
+    }
+    stable
+}
+
+// Whether some piece found stable changes color in any continuation of the game.
+fn stable_piece_flips(board: &Board, color: Color, stable: &[[bool; 8]; 8], owner: Color) -> bool {
+    let moves = board.legal_moves(color);
+    if moves.is_empty() {
+        return !board.legal_moves(color.flip()).is_empty() && stable_piece_flips(board, color.flip(), stable, owner);
+    }
+    moves.iter().any(|legal_move| {
+        let next = legal_move.apply(*board);
+        (0..8).any(|x| (0..8).any(|y| stable[x][y] && next.cells[x][y] != Some(owner))) || stable_piece_flips(&next, color.flip(), stable, owner)
+    })
+}
+
+#[test]
+fn stability_matches_sweeps_and_holds() {
+    let mut random = Random::new(11);
+    for _ in 0..40 {
+        let (mut board, mut color) = (Board::new(), Color::Black);
+        loop {
+            for owner in [Color::Black, Color::White] {
+                let stable = stable_disks(&board, owner);
+                assert_eq!(stable, swept_stable_disks(&board, owner), "{:?}", board);
+                if board.empties() <= 5 {
+                    assert!(!stable_piece_flips(&board, color, &stable, owner), "{:?}", board);
+                }
+            }
+            let mut moves = board.legal_moves(color);
+            if moves.is_empty() {
+                color = color.flip();
+                moves = board.legal_moves(color);
+                if moves.is_empty() {
+                    break;
+                }
+            }
+            board = moves[random.below(moves.len())].apply(board);
+            color = color.flip();
+        }
+    }
+}
+
+#[test]
+fn features_follow_documented_layout() {
+    let mut board = Board::new();
+    board.cells[0][0] = Some(Color::Black);
+    board.cells[1][0] = Some(Color::White);
+    let values = features(&board, Color::Black).values;
+    assert_eq!(&values[0..3], &[3, 3, 58]);
+    assert_eq!(values[9], 0);
+    // a1 own, b1 opponent
+    assert_eq!(values[10], 1 + 2 * 3);
+    // the corner square is read column by column from the corner
+    assert_eq!(values[14], 1 + 2 * 27);
+    assert_eq!(FEATURE_NAMES.len(), values.len());
+}
+
+#[test]
+fn weights_round_trip_through_text() {
+    let mut heuristic = Heuristic::default();
+    heuristic.stability.endgame = 17;
+    heuristic.squares.midgame = WeightedSquares::from_octant([90, -20, 10, 5, -40, -2, -2, -1, -1, -1]);
+    let mut text = Vec::new();
+    heuristic.write(&mut text).unwrap();
+    assert_eq!(Heuristic::read(&text[..]).unwrap(), heuristic);
+    assert!(Heuristic::read(&b"mobility = 1 2"[..]).is_err());
+}
+
+#[test]
+fn phases_blend_without_jumps() {
+    let weights = Phased { opening: 90, midgame: 30, endgame: 0 };
+    assert_eq!(weights.at(Phase { empties: 60 }), 90);
+    assert_eq!(weights.at(Phase { empties: 45 }), 60);
+    assert_eq!(weights.at(Phase { empties: 30 }), 30);
+    assert_eq!(weights.at(Phase { empties: 15 }), 15);
+    assert_eq!(weights.at(Phase { empties: 0 }), 0);
+    for empties in 1..61 {
+        let step = weights.at(Phase { empties }) - weights.at(Phase { empties: empties - 1 });
+        assert!((0..=4).contains(&step));
+    }
+}
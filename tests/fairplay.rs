@@ -0,0 +1,40 @@
+#![cfg(feature = "ai")]
+
+extern crate rusty_reversi;
+
+use rusty_reversi::analysis::{AnnotatedGame, Annotation};
+use rusty_reversi::fairplay::{self, Options};
+use rusty_reversi::record::{self, GameRecord};
+use rusty_reversi::search::WIN;
+
+fn annotation(score: i32, best_score: i32) -> Annotation {
+    Annotation { score, best_move: None, best_score, time: None, comment: String::new() }
+}
+
+#[test]
+fn reports_compare_moves_with_the_engine() {
+    // the first two moves of Black have no choice in score but do in moves
+    let record = GameRecord {
+        black: "cheater".to_string(),
+        white: "honest".to_string(),
+        moves: record::parse_transcript("f5d6c3d3c4f4").unwrap(),
+        ..GameRecord::default()
+    };
+    let annotations = vec![annotation(0, 0), annotation(-10, 0), annotation(5, 5), annotation(-20, -4), annotation(WIN + 2, WIN + 2), annotation(-WIN - 6, WIN + 2)];
+    let game = AnnotatedGame { record, annotations };
+
+    let cheater = fairplay::report(std::slice::from_ref(&game), "cheater", &Options::default());
+    assert_eq!((cheater.games, cheater.moves, cheater.best_moves), (1, 3, 3));
+    assert_eq!((cheater.accuracy, cheater.average_loss), (100.0, 0.0));
+    assert!(cheater.suspicion > 1.0);
+
+    let honest = fairplay::report(std::slice::from_ref(&game), "honest", &Options::default());
+    assert_eq!((honest.moves, honest.best_moves), (3, 0));
+    // solved scores count their disks
+    assert_eq!(honest.average_loss, (10.0 + 16.0 + 8.0) / 3.0);
+    assert!(honest.suspicion < 0.0);
+    assert_eq!(honest.to_json().get("best_moves").and_then(|x| x.as_f64()), Some(0.0));
+
+    let absent = fairplay::report(&[game], "nobody", &Options::default());
+    assert_eq!((absent.games, absent.moves, absent.suspicion), (0, 0, 0.0));
+}
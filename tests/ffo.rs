@@ -0,0 +1,52 @@
+#![cfg(feature = "ai")]
+
+extern crate rusty_reversi;
+
+use rusty_reversi::endgame::Solver;
+use rusty_reversi::ffo;
+use rusty_reversi::game::Color;
+
+#[test]
+fn ffo_suite_is_included() {
+    let problems = ffo::ffo_40_43();
+    assert_eq!(problems.iter().map(|x| x.name.as_str()).collect::<Vec<_>>(), vec!["40", "41", "42", "43"]);
+    assert_eq!(problems[0].color, Color::Black);
+    assert_eq!(problems[0].best_moves, vec![(0, 6)]);
+    assert_eq!(problems[0].score, 38);
+    assert_eq!(problems[3].color, Color::White);
+    assert_eq!(problems[3].best_moves, vec![(6, 5), (2, 1)]);
+    assert_eq!(problems[3].score, -12);
+    // the numbers of empty cells of the suite, and best moves the solver could play
+    let empties = problems.iter().map(|x| x.board.empties()).collect::<Vec<_>>();
+    assert_eq!(empties, vec![20, 22, 22, 23]);
+    for problem in &problems {
+        let moves = problem.board.legal_moves(problem.color);
+        assert!(problem.best_moves.iter().all(|&x| moves.iter().any(|y| y.position == x)), "{}", problem.name);
+    }
+}
+
+// Each position takes minutes in release builds: cargo test --release -- --ignored
+#[test]
+#[ignore]
+fn ffo_suite_is_solved() {
+    for report in ffo::run(&ffo::ffo_40_43(), &mut Solver::default(), |_| ()) {
+        assert!(report.correct, "{:?}", report);
+    }
+}
+
+#[test]
+fn ffo_runner_checks_scores() {
+    // the first one solved, the second with a wrong score
+    let text = "\
+        XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXOOO--- X; F8:+62; % easy\n\
+        XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXOOO--- X; F8:+60;\n";
+    let problems = ffo::parse(text).unwrap();
+    assert_eq!(problems[1].name, "2");
+    let mut names = Vec::new();
+    let reports = ffo::run(&problems, &mut Solver::default(), |x| names.push(x.name.clone()));
+    assert_eq!(names, vec!["easy", "2"]);
+    assert_eq!(reports[0].best_move, Some((5, 0)));
+    assert_eq!(reports[0].score, 62);
+    assert!(reports[0].correct && !reports[1].correct);
+    assert!(ffo::parse("XXXX X; A1:+2;").is_err());
+}
@@ -0,0 +1,46 @@
+#![cfg(feature = "formats")]
+
+extern crate rusty_reversi;
+
+use rusty_reversi::formats::{self, diagram, ggf, json, link, pgn, Format, Pasted};
+use rusty_reversi::game::{Board, Charset, Color, Highlights};
+use rusty_reversi::record::{self, GameRecord};
+
+#[test]
+fn pasted_positions_are_recognized() {
+    let game = GameRecord { moves: record::parse_transcript("f5d6c3").unwrap(), ..GameRecord::default() };
+    let (board, color) = game.positions().unwrap()[3];
+    let expected = Pasted::Position(board, color);
+    let (previous, mover) = game.positions().unwrap()[2];
+    let last_move = previous.legal_moves(mover).into_iter().find(|x| x.position == (2, 5));
+    let highlights = Highlights { hints: Some(color), last_move };
+    let texts = vec![
+        (Format::Position, record::format_position(&board, color)),
+        (Format::Diagram, format!("{}{} to move", board, color)),
+        (Format::Diagram, format!("{}{} to move", board.draw(Charset::Unicode), color)),
+        (Format::Diagram, format!("{}{} to move", board.draw_highlighted(Charset::Ascii, &highlights), color)),
+        (Format::Diagram, format!("{}{} to move", board.draw_highlighted(Charset::Unicode, &highlights), color)),
+        (Format::Diagram, diagram::markdown(&board, color)),
+        (Format::Json, json::position_to_json(&board, color).to_string()),
+        (Format::Link, link::position_link("https://example.org/", &board, color))
+    ];
+    for (format, text) in texts {
+        assert_eq!(formats::sniff(&text).unwrap(), (format, expected.clone()), "{}", text);
+    }
+    let start = formats::sniff(&Board::new().to_string()).unwrap();
+    assert_eq!(start, (Format::Diagram, Pasted::Position(Board::new(), Color::Black)));
+}
+
+#[test]
+fn pasted_games_are_recognized() {
+    let game = GameRecord { moves: record::parse_transcript("f5d6c3").unwrap(), black_disks: Some(33), ..GameRecord::default() };
+    let games = Pasted::Games(vec![game.clone()]);
+    assert_eq!(formats::sniff(&ggf::format(&game).unwrap()).unwrap(), (Format::Ggf, games.clone()));
+    assert_eq!(formats::sniff(&pgn::format(&game).unwrap()).unwrap(), (Format::Pgn, games.clone()));
+    assert_eq!(formats::sniff(&json::game_to_json(&game).to_string()).unwrap(), (Format::Json, games.clone()));
+    assert_eq!(formats::sniff(&link::game_link("", &game)).unwrap(), (Format::Link, games));
+    let transcript = GameRecord { moves: game.moves.clone(), ..GameRecord::default() };
+    assert_eq!(formats::sniff(" F5 D6 C3\n").unwrap(), (Format::Transcript, Pasted::Games(vec![transcript])));
+    assert!(formats::sniff("f5a1").is_err());
+    assert!(formats::sniff("hello").is_err());
+}
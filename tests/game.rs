@@ -5,6 +5,11 @@ use rusty_reversi::game;
 use game::new_game;
 use game::Game;
 use game::Board;
+use game::Charset;
+use game::Color;
+use game::Highlights;
+use game::IllegalMove;
+use game::Symmetry;
 
 #[test]
 fn new_game_initializes_board() {
@@ -17,3 +22,82 @@ fn new_game_initializes_board() {
         _ => panic!("should be a Game::Place")
     }
 }
+
+#[test]
+fn symmetries_are_inverted() {
+    for &symmetry in Symmetry::all().iter() {
+        for x in 0..8 {
+            for y in 0..8 {
+                assert_eq!(symmetry.inverse().apply(symmetry.apply((x, y))), (x, y));
+            }
+        }
+    }
+    // the starting position is symmetric to both diagonals
+    assert_eq!(Board::new().transform(Symmetry(4)), Board::new());
+    assert!(Board::new().transform(Symmetry(1)) != Board::new());
+}
+
+#[test]
+fn boards_are_drawn_in_both_charsets() {
+    let board = Board::new();
+    assert_eq!(board.draw(Charset::Ascii), board.to_string());
+    let text = board.draw(Charset::Unicode);
+    let lines = text.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 1 + 8 * 2 + 1);
+    assert_eq!(lines[0], "  a  b  c  d  e  f  g  h");
+    assert_eq!(lines[1], " ┌──┬──┬──┬──┬──┬──┬──┬──┐");
+    assert_eq!(lines[8], "4│  │  │  │⚪│⚫│  │  │  │");
+    assert_eq!(lines[17], " └──┴──┴──┴──┴──┴──┴──┴──┘");
+    // every cell takes two columns, the circles being wide characters
+    let width = |line: &str| line.chars().map(|c| if c == '⚫' || c == '⚪' { 2 } else { 1 }).sum::<usize>();
+    assert!(lines[1..].iter().all(|x| width(x) == 26));
+}
+
+#[test]
+fn boards_are_drawn_with_hints() {
+    let board = Board::new();
+    let text = board.draw_with_hints(Charset::Ascii, Some(Color::Black));
+    assert_eq!(text.lines().nth(3), Some("3 . . . + . . . ."));
+    assert_eq!(text.matches('+').count(), 4);
+    assert_eq!(board.draw_with_hints(Charset::Ascii, None), board.to_string());
+    let text = board.draw_with_hints(Charset::Unicode, Some(Color::White));
+    assert_eq!(text.matches('·').count(), 4);
+    assert_eq!(text.lines().nth(6), Some("3│  │  │  │  │ ·│  │  │  │"));
+}
+
+#[test]
+fn last_moves_are_highlighted() {
+    let board = Board::new();
+    let last_move = board.legal_moves(Color::Black).into_iter().find(|x| x.position == (5, 3));
+    let board = last_move.unwrap().apply(board);
+    let highlights = Highlights { hints: None, last_move };
+    let text = board.draw_highlighted(Charset::Ascii, &highlights);
+    assert_eq!(text.lines().nth(4), Some("4 . . . O X . . ."));
+    assert_eq!(text.lines().nth(5), Some("5 . . . X x>X . ."));
+    let text = board.draw_highlighted(Charset::Unicode, &highlights);
+    assert_eq!(text.lines().nth(10), Some("5│  │  │  │⚫│● ▶⚫│  │  │"));
+    assert_eq!(text.lines().nth(8), Some("4│  │  │  │⚪│⚫│  │  │  │"));
+}
+
+#[test]
+fn perft_counts_move_sequences() {
+    let board = Board::new();
+    let counts = (1..=6).map(|depth| board.perft(Color::Black, depth)).collect::<Vec<_>>();
+    assert_eq!(counts, vec![4, 12, 56, 244, 1396, 8200]);
+    let divided = board.divide(Color::Black, 4);
+    assert_eq!(divided.len(), 4);
+    assert_eq!(divided.iter().map(|x| x.1).sum::<u64>(), 244);
+    assert!(divided.iter().all(|x| x.0.is_some() && x.1 == 61));
+    assert_eq!(board.perft(Color::Black, 0), 1);
+}
+
+#[test]
+fn moves_are_tested_against_the_board() {
+    let board = Board::new();
+    let f5 = board.test_move(Color::Black, (5, 3)).unwrap();
+    assert_eq!(f5.flipped(), vec![(4, 3)]);
+    assert_eq!(board.test_move(Color::Black, (3, 3)), Err(IllegalMove::Occupied(Color::Black)));
+    assert_eq!(board.test_move(Color::Black, (0, 0)), Err(IllegalMove::Ineffective));
+    assert_eq!(board.test_move(Color::Black, (8, 0)), Err(IllegalMove::Outside));
+    assert_eq!(board.test_move(Color::White, (-1, 3)), Err(IllegalMove::Outside));
+}
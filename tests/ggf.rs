@@ -0,0 +1,64 @@
+#![cfg(feature = "formats")]
+
+extern crate rusty_reversi;
+
+use rusty_reversi::formats::ggf;
+use rusty_reversi::record::{self, GameRecord};
+
+#[test]
+fn ggf_reads_games() {
+    let text = "(;GM[Othello]PC[GGS/os]DT[2003.12.15_13:24:03.MST]PB[Saio1200]PW[Saio3000]RB[2197.72]RW[2199.47]TI[05:00//02:00]TY[8]RE[+34.000]\
+        BO[8 -------- -------- -------- ---O*--- ---*O--- -------- -------- -------- *]B[f5//0.01]W[d6/-1.50/0.02]B[c3];)\n\
+        (;GM[Othello]TY[8]BO[8 -------- -------- -------- ---O*--- ---*O--- -------- -------- -------- *];)";
+    let records = ggf::parse(text).unwrap();
+    assert_eq!(records.len(), 2);
+    let game = &records[0];
+    assert_eq!((game.black.as_str(), game.white.as_str()), ("Saio1200", "Saio3000"));
+    assert_eq!(game.event, "GGS/os");
+    assert_eq!(game.moves, record::parse_transcript("f5d6c3").unwrap());
+    assert_eq!(game.black_disks, Some(49));
+    assert_eq!(game.tag("RB"), Some("2197.72"));
+    assert_eq!(game.tag("TI"), Some("05:00//02:00"));
+    assert!(records[1].moves.is_empty());
+    // another board size
+    assert!(ggf::parse("(;GM[Othello]TY[10];)").is_err());
+}
+
+#[test]
+fn ggf_writes_what_it_reads() {
+    let game = GameRecord {
+        black: "Black".to_string(),
+        white: "White".to_string(),
+        event: "Self-play".to_string(),
+        date: "2024-03-15".to_string(),
+        moves: record::parse_transcript("f5d6c3d3c4").unwrap(),
+        black_disks: Some(40),
+        tags: vec![("TI".to_string(), "15:00//02:00".to_string())],
+        ..GameRecord::default()
+    };
+    let text = ggf::format(&game).unwrap();
+    assert!(text.contains("B[f5]W[d6]B[c3]W[d3]B[c4]"));
+    assert_eq!(ggf::parse(&text).unwrap(), vec![game.clone()]);
+    let illegal = GameRecord { moves: record::parse_transcript("a1").unwrap(), ..game };
+    assert!(ggf::format(&illegal).is_err());
+}
+
+#[test]
+fn archives_are_imported_game_by_game() {
+    let start = "BO[8 -------- -------- -------- ---O*--- ---*O--- -------- -------- -------- *]";
+    let text = format!("(;GM[Othello]PC[GGS/os]PB[a]PW[b]TY[8]RE[+2.000]{0}B[f5]W[d6];)\n\
+        (2 (;GM[Othello]PB[a]PW[b]TY[s8]RE[-4.000]{0}B[f5];)(;GM[Othello]PB[b]PW[a]TY[s8]RE[+4.000]{0}B[f5];))\n\
+        (;GM[Othello]TY[10]BO[10 *];)\n\
+        (;GM[Othello]PB[a]PW[b]TY[8]{0}B[f5]W[d6];)\n\
+        (;GM[Othello]TY[8]{0}B[a1];)\n\
+        (;GM[Othello]TY[8]{0}B[f5]W[d", start);
+    let import = ggf::import(&text);
+    assert_eq!(import.records.len(), 4);
+    assert_eq!(import.records[0].black_disks, Some(33));
+    assert_eq!((import.records[1].black_disks, import.records[2].black_disks), (Some(30), Some(34)));
+    assert_eq!(import.records[1].tag("TY"), Some("s8"));
+    assert_eq!(import.records[3].black_disks, None);
+    let skipped = import.skipped.iter().map(|x| x.0).collect::<Vec<_>>();
+    assert_eq!(skipped, vec![4, 6, 7]);
+    assert_eq!(import.skipped[1].1, "illegal move 1");
+}
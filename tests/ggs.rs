@@ -0,0 +1,95 @@
+#![cfg(feature = "ai")]
+
+extern crate rusty_reversi;
+
+use std::io::Cursor;
+
+use rusty_reversi::eval::WeightedSquares;
+use rusty_reversi::formats::ggf;
+use rusty_reversi::game::{Board, Color, Coord};
+use rusty_reversi::protocol::ggs::{Ggs, Policy};
+use rusty_reversi::random::Random;
+use rusty_reversi::record::{self, GameRecord};
+use rusty_reversi::search::AlphaBeta;
+
+fn client() -> Ggs<AlphaBeta<WeightedSquares>> {
+    Ggs::new(AlphaBeta::new(2, WeightedSquares::default()), "bot", "secret")
+}
+
+fn game(black: &str, white: &str, moves: &[Coord]) -> String {
+    ggf::format(&GameRecord { black: black.to_string(), white: white.to_string(), moves: moves.to_vec(), ..GameRecord::default() }).unwrap()
+}
+
+#[test]
+fn requests_are_accepted_by_the_policy() {
+    let mut ggs = client();
+    ggs.policy = Policy { games: 1, unrated: false, min_rating: Some(1500.0) };
+    let request = |ggs: &mut Ggs<_>, line: &str| ggs.handle(line).unwrap();
+    assert_eq!(request(&mut ggs, "/os: + .7 1850.3 ann 15:00//02:00 1700.0 bot 15:00//02:00 8 R"), vec!["tell /os accept .7"]);
+    assert!(request(&mut ggs, "/os: + .8 1850.3 ann 15:00//02:00 1700.0 bot 15:00//02:00 s8 R").is_empty());
+    assert!(request(&mut ggs, "/os: + .9 1850.3 ann 15:00//02:00 1700.0 bot 15:00//02:00 8 U").is_empty());
+    assert!(request(&mut ggs, "/os: + .10 1400.0 eve 15:00//02:00 1700.0 bot 15:00//02:00 8 R").is_empty());
+    assert!(request(&mut ggs, "/os: + .11 1850.3 ann 15:00//02:00 1700.0 bob 15:00//02:00 8 R").is_empty());
+
+    // busy with a game
+    request(&mut ggs, &format!("/os: join .7 {}", game("ann", "bot", &[])));
+    assert_eq!(ggs.matches().len(), 1);
+    assert!(request(&mut ggs, "/os: + .12 1850.3 ann 15:00//02:00 1700.0 bot 15:00//02:00 8 R").is_empty());
+}
+
+#[test]
+fn the_engine_plays_its_turns() {
+    let mut ggs = client();
+    let input = format!(": Enter login (yours, or one you'd like to use).\n: Enter your password.\n/os: join .7 {}\n", game("bot", "ann", &[]));
+    let mut output = Vec::new();
+    ggs.run(Cursor::new(input), &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    let lines = output.lines().collect::<Vec<_>>();
+    assert_eq!(&lines[..4], &["bot", "secret", "tell /os request +", "tell /os open 1"]);
+    let first = record::parse_move(lines[4].strip_prefix("tell /os play .7 ").unwrap()).unwrap();
+    assert!(Board::new().legal_moves(Color::Black).iter().any(|x| x.position == first));
+
+    // the turn of the opponent
+    assert!(ggs.handle(&format!("/os: update .7 {}", game("bot", "ann", &[first]))).unwrap().is_empty());
+    let reply = ggs.handle(&format!("/os: update .7 {}", game("bot", "ann", &record::parse_transcript("f5d6").unwrap()))).unwrap();
+    assert_eq!(reply.len(), 1);
+    assert!(ggs.handle("/os: update .7 (;GM[Othello]PB[ann]PW[eve];)").is_err());
+
+    ggs.handle("/os: - match .7 1700.0 bot 1850.3 ann").unwrap();
+    assert!(ggs.matches().is_empty());
+    let finished = ggs.take_finished();
+    assert_eq!(finished.len(), 1);
+    assert_eq!(finished[0].moves.len(), 2);
+    assert!(ggs.take_finished().is_empty());
+}
+
+#[test]
+fn passes_are_played() {
+    // random moves until a player has to pass
+    let mut random = Random::new(1);
+    let moves = 'games: loop {
+        let (mut board, mut color, mut moves) = (Board::new(), Color::Black, Vec::new());
+        loop {
+            let legal = board.legal_moves(color);
+            if legal.is_empty() {
+                if board.legal_moves(color.flip()).is_empty() {
+                    continue 'games;
+                }
+                break 'games (moves, color);
+            }
+            let chosen = legal[random.below(legal.len())];
+            board = chosen.apply(board);
+            moves.push(chosen.position);
+            color = color.flip();
+        }
+    };
+    let (moves, passing) = moves;
+    let (black, white) = if passing == Color::Black { ("bot", "ann") } else { ("ann", "bot") };
+    let mut ggs = client();
+    let text = game(black, white, &moves);
+    assert_eq!(ggs.handle(&format!("/os: join .3 {}", text)).unwrap(), vec!["tell /os play .3 pa"]);
+    // once passed, the opponent has the turn
+    let key = if passing == Color::Black { "B" } else { "W" };
+    let passed = text.replace(";)", &format!("{}[PA];)", key));
+    assert!(ggs.handle(&format!("/os: update .3 {}", passed)).unwrap().is_empty());
+}
@@ -0,0 +1,35 @@
+#![cfg(feature = "ai")]
+
+extern crate rusty_reversi;
+
+use rusty_reversi::eval::Heuristic;
+use rusty_reversi::game::Color;
+use rusty_reversi::protocol::gtp::Gtp;
+use rusty_reversi::search::AlphaBeta;
+
+#[test]
+fn gtp_commands_play_a_game() {
+    let mut gtp = Gtp::new(AlphaBeta::new(1, Heuristic::default()));
+    assert_eq!(gtp.execute("known_command genmove").unwrap(), "true");
+    assert!(gtp.execute("boardsize 10").is_err());
+    assert_eq!(gtp.execute("play black f5").unwrap(), "");
+    assert!(gtp.execute("play black d6").is_err());
+    assert!(gtp.execute("play white pass").is_err());
+    assert_eq!(gtp.execute("genmove black").unwrap(), "pass");
+    let answer = gtp.execute("genmove white").unwrap();
+    assert_eq!(answer.len(), 2);
+    assert_eq!(gtp.position.color, Color::Black);
+    assert_eq!(gtp.execute("undo").unwrap(), "");
+    assert_eq!(gtp.position.color, Color::White);
+    assert!(gtp.execute("set_time -1").is_err());
+    assert!(gtp.execute("set_time inf").is_err());
+    assert!(gtp.execute("set_time 1e30").is_err());
+}
+
+#[test]
+fn gtp_answers_with_ids() {
+    let mut gtp = Gtp::new(AlphaBeta::new(1, Heuristic::default()));
+    let mut output = Vec::new();
+    gtp.run(&b"1 name\n# comment\nfly\n2 clear_board\n3 quit\nname\n"[..], &mut output).unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), "=1 Rusty Reversi\n\n? unknown command\n\n=2\n\n=3\n\n");
+}
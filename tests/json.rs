@@ -0,0 +1,89 @@
+extern crate rusty_reversi;
+
+use rusty_reversi::formats::json::{self, Value};
+use rusty_reversi::game::{Board, Color};
+use rusty_reversi::record::{self, GameRecord};
+
+#[test]
+fn json_values_roundtrip() {
+    let text = r#" {"a": [1, -2.5e1, true, null], "b": "x\"\\\u00e9\ud83d\ude00\n", "c": {}} "#;
+    let value = Value::parse(text).unwrap();
+    assert_eq!(value.get("a").and_then(Value::as_array).map(|x| x.len()), Some(4));
+    assert_eq!(value.get("a").unwrap().as_array().unwrap()[1], Value::Number(-25.0));
+    assert_eq!(value.get("b").and_then(Value::as_str), Some("x\"\\é😀\n"));
+    assert_eq!(value.to_string(), r#"{"a":[1,-25,true,null],"b":"x\"\\é😀\n","c":{}}"#);
+    assert_eq!(Value::parse(&value.to_string()).unwrap(), value);
+    assert!(Value::parse("[1,]").is_err());
+    assert!(Value::parse("{} x").is_err());
+}
+
+#[test]
+fn json_documents_roundtrip() {
+    let board = Board::new();
+    let document = json::position_to_json(&board, Color::White);
+    assert!(document.to_string().starts_with(r#"{"schema":1,"kind":"position","board":["--------","--------","--------","---OX---""#));
+    assert_eq!(json::position_from_json(&document).unwrap(), (board, Color::White));
+
+    let document = json::move_to_json(Color::Black, Some((5, 3)));
+    assert_eq!(document.to_string(), r#"{"schema":1,"kind":"move","color":"black","cell":"f5"}"#);
+    assert_eq!(json::move_from_json(&document).unwrap(), (Color::Black, Some((5, 3))));
+    assert_eq!(json::move_from_json(&json::move_to_json(Color::White, None)).unwrap(), (Color::White, None));
+
+    let game = GameRecord {
+        black: "Black".to_string(),
+        moves: record::parse_transcript("f5d6").unwrap(),
+        black_disks: Some(40),
+        tags: vec![("TI".to_string(), "15:00".to_string())],
+        ..GameRecord::default()
+    };
+    let text = json::game_to_json(&game).to_string();
+    assert_eq!(json::game_from_json(&Value::parse(&text).unwrap()).unwrap(), game);
+    // not a position
+    assert!(json::position_from_json(&Value::parse(&text).unwrap()).is_err());
+}
+
+#[test]
+fn json_refuses_newer_schemas() {
+    let document = Value::parse(r#"{"schema":2,"kind":"move","color":"black","cell":"f5"}"#).unwrap();
+    let error = json::move_from_json(&document).unwrap_err();
+    assert!(error.to_string().contains("newer"));
+    let document = Value::parse(r#"{"kind":"move","color":"black","cell":"f5"}"#).unwrap();
+    assert!(json::negotiate(&document, "move").is_err());
+    let document = Value::parse(r#"{"schema":1,"kind":"move","color":"black","cell":"f5"}"#).unwrap();
+    assert_eq!(json::negotiate(&document, "move").unwrap(), 1);
+}
+
+#[test]
+fn json_refuses_deep_nesting() {
+    let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+    assert!(Value::parse(&nested(json::MAX_DEPTH)).is_ok());
+    let error = Value::parse(&nested(json::MAX_DEPTH + 1)).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    // deep enough to overflow the stack of a parser recursing without a limit
+    assert!(Value::parse(&"[".repeat(1 << 16)).is_err());
+    assert!(Value::parse(&r#"{"a":"#.repeat(1 << 16)).is_err());
+}
+
+#[test]
+fn json_refuses_unpaired_surrogates() {
+    assert!(Value::parse(r#""\ud800\u0041""#).is_err());
+    assert!(Value::parse(r#""\ud800""#).is_err());
+    assert!(Value::parse(r#""\ud800x""#).is_err());
+    assert!(Value::parse(r#""\ude00""#).is_err());
+    assert!(Value::parse(r#""\ud800\ud800""#).is_err());
+    assert_eq!(Value::parse(r#""\ud83d\ude00""#).unwrap().as_str(), Some("😀"));
+}
+
+#[test]
+fn json_refuses_unknown_escapes_and_raw_control_characters() {
+    assert_eq!(Value::parse(r#""\"\\\/\b\f\n\r\t\u0041""#).unwrap().as_str(), Some("\"\\/\u{8}\u{c}\n\r\tA"));
+    assert!(Value::parse(r#""\q""#).is_err());
+    assert!(Value::parse(r#""\x41""#).is_err());
+    assert!(Value::parse(r#""\'""#).is_err());
+    assert!(Value::parse("\"a\nb\"").is_err());
+    assert!(Value::parse("\"a\tb\"").is_err());
+    assert!(Value::parse("\"\u{0}\"").is_err());
+    assert!(Value::parse("\"\u{1f}\"").is_err());
+    // whitespace between values is not in a string
+    assert!(Value::parse("[\n\t\"a\"\r\n]").is_ok());
+}
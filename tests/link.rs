@@ -0,0 +1,71 @@
+extern crate rusty_reversi;
+
+use rusty_reversi::formats::link::{self, Shared};
+use rusty_reversi::game::{Board, Color};
+use rusty_reversi::record::{self, GameRecord};
+
+#[test]
+fn games_roundtrip() {
+    let moves = record::parse_transcript("f5d6c3d3c4f4f6f3e6e7").unwrap();
+    let game = GameRecord {
+        black: "Ann".to_string(),
+        white: "Bø".to_string(),
+        event: "Club night".to_string(),
+        date: "2024-03-15".to_string(),
+        moves,
+        black_disks: Some(40),
+        theoretical_disks: None,
+        tags: vec![("round".to_string(), "3".to_string()), ("long".to_string(), "x".repeat(200))]
+    };
+    let text = link::encode_game(&game);
+    assert!(text.bytes().all(|x| x.is_ascii_alphanumeric() || x == b'-' || x == b'_'));
+    assert_eq!(link::decode_game(&text).unwrap(), game);
+
+    // a bare game takes a character a move and a few more
+    let bare = GameRecord { moves: game.moves.clone(), ..GameRecord::default() };
+    let text = link::encode_game(&bare);
+    assert_eq!(text.len(), 22);
+    assert_eq!(link::decode_game(&text).unwrap(), bare);
+    assert_eq!(link::decode_game(&link::encode_game(&GameRecord::default())).unwrap(), GameRecord::default());
+}
+
+#[test]
+fn positions_roundtrip() {
+    let game = GameRecord { moves: record::parse_transcript("f5d6c3").unwrap(), ..GameRecord::default() };
+    for &(board, color) in &game.positions().unwrap() {
+        let text = link::encode_position(&board, color);
+        assert_eq!(text.len(), 24);
+        assert_eq!(link::decode_position(&text).unwrap(), (board, color));
+    }
+}
+
+#[test]
+fn links_are_read() {
+    let game = GameRecord { moves: record::parse_transcript("f5d6").unwrap(), ..GameRecord::default() };
+    let url = link::game_link("https://example.org/", &game);
+    assert!(url.starts_with("https://example.org/?game="));
+    assert_eq!(link::parse_link(&url).unwrap(), Shared::Game(game.clone()));
+    let fragment = format!("https://example.org/view?theme=dark#game={}", link::encode_game(&game));
+    assert_eq!(link::parse_link(&fragment).unwrap(), Shared::Game(game));
+    let url = link::position_link("", &Board::new(), Color::White);
+    assert_eq!(link::parse_link(&url).unwrap(), Shared::Position(Board::new(), Color::White));
+    assert!(link::parse_link("https://example.org/?theme=dark").is_err());
+}
+
+#[test]
+fn invalid_encodings_are_refused() {
+    let game = GameRecord { moves: record::parse_transcript("f5d6c3").unwrap(), event: "x".to_string(), ..GameRecord::default() };
+    let text = link::encode_game(&game);
+    for end in 0..text.len() {
+        assert!(link::decode_game(&text[..end]).is_err(), "{}", &text[..end]);
+    }
+    assert!(link::decode_game(&format!("{}AA", text)).is_err());
+    assert!(link::decode_game("*").is_err());
+    // a1 isn't legal from the starting position
+    let illegal = GameRecord { moves: vec![(5, 3), (0, 7)], ..GameRecord::default() };
+    assert_eq!(link::decode_game(&link::encode_game(&illegal)).unwrap_err().to_string(), "illegal move 2");
+    assert!(link::decode_position(&link::encode_position(&Board::new(), Color::Black)[..20]).is_err());
+    let mut position = link::encode_position(&Board::new(), Color::Black);
+    position.replace_range(..2, "Ag");
+    assert!(link::decode_position(&position).unwrap_err().to_string().contains("version"));
+}
@@ -0,0 +1,64 @@
+#![cfg(feature = "net")]
+
+extern crate rusty_reversi;
+
+use std::env;
+use std::fs;
+use std::time::Duration;
+
+use rusty_reversi::formats::pgn;
+use rusty_reversi::game::Color;
+use rusty_reversi::lobby::{Archive, Lobby, Pairing};
+use rusty_reversi::record::{self, GameRecord};
+use rusty_reversi::session::Clock;
+
+#[test]
+fn challenges_are_negotiated_and_paired() {
+    let mut lobby = Lobby::new();
+    lobby.enter(1, "Ann").unwrap();
+    lobby.enter(2, "Bob").unwrap();
+    lobby.enter(3, "Eve").unwrap();
+    assert!(lobby.enter(4, "ann").is_err());
+    assert!(lobby.challenge(1, "Ann", Color::Black, None).is_err());
+
+    let blitz = Clock { remaining: Duration::from_secs(180), increment: Duration::from_secs(2) };
+    let rapid = Clock { remaining: Duration::from_secs(600), increment: Duration::from_secs(0) };
+    lobby.challenge(1, "bob", Color::White, Some(blitz)).unwrap();
+    lobby.challenge(3, "Bob", Color::Black, None).unwrap();
+    // Bob asks Ann for a longer game instead
+    lobby.challenge(2, "Ann", Color::Black, Some(rapid)).unwrap();
+    assert_eq!(lobby.challenges().len(), 2);
+    assert!(lobby.accept(2, "Ann").is_err());
+
+    assert_eq!(lobby.accept(1, "Bob").unwrap(), Pairing { black: 2, white: 1, clock: Some(rapid) });
+    assert!(lobby.challenges().is_empty());
+    assert!(lobby.get(1).unwrap().playing && lobby.get(2).unwrap().playing);
+    assert!(lobby.challenge(3, "Ann", Color::Black, None).is_err());
+
+    lobby.set_playing(1, false);
+    lobby.challenge(3, "Ann", Color::Black, None).unwrap();
+    assert_eq!(lobby.decline(1, "Eve").unwrap().from, 3);
+    lobby.challenge(3, "Ann", Color::Black, None).unwrap();
+    assert!(lobby.leave(3));
+    assert!(lobby.challenges().is_empty());
+    assert_eq!(lobby.entries().iter().map(|x| x.name.as_str()).collect::<Vec<_>>(), vec!["Ann", "Bob"]);
+}
+
+#[test]
+fn archives_keep_the_finished_games() {
+    let path = env::temp_dir().join("rusty_reversi_lobby_test.pgn");
+    let _ = fs::remove_file(&path);
+    let archive = Archive::new(&path);
+    let game = |black: &str| GameRecord {
+        black: black.to_string(),
+        white: "Bob".to_string(),
+        moves: record::parse_transcript("f5d6c3").unwrap(),
+        ..GameRecord::default()
+    };
+    archive.append(&game("Ann")).unwrap();
+    archive.append(&game("Eve")).unwrap();
+    let games = pgn::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(games.iter().map(|x| x.black.as_str()).collect::<Vec<_>>(), vec!["Ann", "Eve"]);
+    assert_eq!(games[1].moves.len(), 3);
+}
@@ -0,0 +1,63 @@
+#![cfg(feature = "ai")]
+
+extern crate rusty_reversi;
+
+use rusty_reversi::game;
+use rusty_reversi::mcts;
+use rusty_reversi::nn;
+
+use game::{Board, Color};
+use mcts::{Config, Mcts};
+use nn::{planes, Network, Prediction};
+
+#[test]
+fn mcts_takes_the_corner() {
+    let mut board = Board { cells: [[None; 8]; 8] };
+    board.cells[4][4] = Some(Color::Black);
+    board.cells[5][5] = Some(Color::White);
+    board.cells[6][6] = Some(Color::Black);
+    let mut engine = Mcts::new(Config { simulations: 200, ..Config::default() }).unwrap();
+    let result = engine.search(&board, Color::White);
+    assert_eq!(result.best, Some((7, 7)));
+    assert!(result.score > 0);
+    assert_eq!(result.pv[0], (7, 7));
+}
+
+#[test]
+fn mcts_rollouts_find_the_win() {
+    // after d5, the only other move, Black takes every white disk with c6
+    let mut board = Board { cells: [[None; 8]; 8] };
+    board.cells[4][4] = Some(Color::Black);
+    board.cells[5][5] = Some(Color::White);
+    board.cells[6][6] = Some(Color::Black);
+    let mut engine = Mcts::new(Config { simulations: 50, rollouts: 4, ..Config::default() }).unwrap();
+    let result = engine.search(&board, Color::White);
+    assert_eq!(result.best, Some((7, 7)));
+    assert!(result.score > 500);
+}
+
+struct Prefers(usize);
+impl Network for Prefers {
+    fn predict(&self, _: &Board, _: Color) -> Prediction {
+        let mut policy = [0.0; 64];
+        policy[self.0] = 1.0;
+        Prediction { value: 0.0, policy }
+    }
+}
+
+#[test]
+fn mcts_follows_the_policy() {
+    // f5 is (5, 3)
+    let mut engine = Mcts::with_network(Config { simulations: 50, ..Config::default() }, Box::new(Prefers(3 * 8 + 5)));
+    let result = engine.search(&Board::new(), Color::Black);
+    assert_eq!(result.best, Some((5, 3)));
+}
+
+#[test]
+fn planes_separate_the_players() {
+    let input = planes(&Board::new(), Color::White);
+    // d4, (3, 3), is black
+    assert_eq!(input[3 * 8 + 3], 0.0);
+    assert_eq!(input[64 + 3 * 8 + 3], 1.0);
+    assert_eq!(input.iter().sum::<f32>(), 4.0);
+}
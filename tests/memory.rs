@@ -0,0 +1,66 @@
+#![cfg(feature = "ai")]
+
+extern crate rusty_reversi;
+
+use std::io::ErrorKind;
+
+use rusty_reversi::endgame::{Solution, SolutionCache};
+use rusty_reversi::engine::{self, Engine, Limits, Reservation};
+use rusty_reversi::eval::Heuristic;
+use rusty_reversi::game::{Board, Color};
+use rusty_reversi::mcts::{Config, Mcts};
+use rusty_reversi::search::AlphaBeta;
+
+const MIB: usize = 1024 * 1024;
+
+// The budget is shared by the whole process, so it is only changed by this test.
+#[test]
+fn engines_share_the_memory_budget_of_the_process() {
+    let mut first = AlphaBeta::new(4, Heuristic::default());
+    assert_eq!(first.memory_usage().table, 0);
+    assert!(first.memory_usage().weights > 0);
+    first.set_option("hash", "1").unwrap();
+    let usage = first.memory_usage();
+    assert!(usage.table > MIB / 2 && usage.table <= MIB, "{:?}", usage);
+    assert_eq!(usage.total(), usage.table + usage.weights);
+    assert_eq!(engine::memory_reserved(), usage.table);
+
+    // two tables fit, a third one doesn't
+    engine::set_memory_cap(Some(2 * MIB));
+    assert_eq!(engine::memory_cap(), Some(2 * MIB));
+    let mut second = AlphaBeta::new(4, Heuristic::default());
+    second.set_option("hash", "1").unwrap();
+    let mut third = AlphaBeta::new(4, Heuristic::default());
+    assert_eq!(third.set_option("hash", "1").unwrap_err().kind(), ErrorKind::OutOfMemory);
+    assert_eq!(first.set_option("hash", "2").unwrap_err().kind(), ErrorKind::OutOfMemory);
+    assert_eq!(first.memory_usage(), usage, "the table is kept when a larger one is refused");
+    // the memory of a table dropped is given back
+    drop(second);
+    third.set_option("hash", "1").unwrap();
+    third.set_option("hash", "0").unwrap();
+    assert_eq!(engine::memory_reserved(), usage.table);
+
+    // caches stop growing once the budget is exhausted
+    engine::set_memory_cap(Some(engine::memory_reserved()));
+    let mut cache = SolutionCache::new();
+    assert!(!cache.insert(&Board::new(), Color::Black, Solution { best_move: None, score: 0 }));
+    assert!(cache.is_empty());
+
+    // and so do trees, which still search a move
+    let mut mcts = Mcts::new(Config { simulations: 100_000, ..Config::default() }).unwrap();
+    let exhausted = mcts.best_move(&Board::new(), Color::Black, &Limits::default());
+    assert!(!exhausted.pv.is_empty());
+    assert!(exhausted.nodes < 100, "{} nodes", exhausted.nodes);
+    engine::set_memory_cap(Some(engine::memory_reserved() + 256 * 1024));
+    let capped = mcts.best_move(&Board::new(), Color::Black, &Limits::default());
+    assert!(capped.nodes > 100 && capped.nodes < 100_000, "{} nodes", capped.nodes);
+    assert_eq!(engine::memory_reserved(), usage.table, "the tree is given back after the search");
+
+    assert!(Reservation::new(MIB, "test").is_err());
+    engine::set_memory_cap(None);
+    assert_eq!(engine::memory_cap(), None);
+    let reservation = Reservation::new(MIB, "test").unwrap();
+    assert_eq!(engine::memory_reserved(), usage.table + MIB);
+    drop(reservation);
+    assert_eq!(engine::memory_reserved(), usage.table);
+}
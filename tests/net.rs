@@ -0,0 +1,254 @@
+#![cfg(feature = "net")]
+
+extern crate rusty_reversi;
+
+use std::io::BufReader;
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+use rusty_reversi::game::{Board, Color};
+use rusty_reversi::net::{self, proto};
+use rusty_reversi::net::proto::message::Body;
+use rusty_reversi::net::remote::{self, Ending, RemoteGame, Update};
+use rusty_reversi::record::{self, Termination};
+use rusty_reversi::session::Clock;
+
+#[test]
+fn positions_and_moves_convert() {
+    let board = Board::new();
+    let position = proto::Position::new(&board, Color::White);
+    // d5 and e4 are black, the bits 3 * 8 + 3 and 4 * 8 + 4
+    assert_eq!(position.black, (1 << 27) | (1 << 36));
+    assert_eq!(position.to_board().unwrap(), (board, Color::White));
+    let f5 = record::parse_move("f5").unwrap();
+    assert_eq!(proto::Move::new(Color::Black, Some(f5)).to_move().unwrap(), (Color::Black, Some(f5)));
+    assert_eq!(proto::Move::new(Color::White, None).cell, 64);
+    assert!(proto::Move { color: 2, cell: 0 }.to_move().is_err());
+    assert!(proto::Position { black: 1, white: 1, to_move: 0 }.to_board().is_err());
+}
+
+#[test]
+fn messages_are_framed_by_their_length() {
+    let hello = proto::Message { body: Some(Body::Hello(proto::Hello { protocol_version: net::PROTOCOL_VERSION, name: "test".to_string(), token: String::new() })) };
+    let play = proto::Message {
+        body: Some(Body::Play(proto::Play { r#move: Some(proto::Move::new(Color::Black, record::parse_move("f5"))), time_ms: 1500 }))
+    };
+    let mut bytes = Vec::new();
+    net::send(&mut bytes, &hello).unwrap();
+    net::send(&mut bytes, &play).unwrap();
+    let mut reader = &bytes[..];
+    assert_eq!(net::receive(&mut reader).unwrap(), Some(hello));
+    assert_eq!(net::receive(&mut reader).unwrap(), Some(play));
+    assert_eq!(net::receive(&mut reader).unwrap(), None);
+    assert!(net::receive(&bytes[..bytes.len() - 1][..]).is_ok());
+    assert!(net::receive(&[200, 1, 0][..]).is_err());
+}
+
+// Hosts a game on a free port, with the guest played by `guest` on another thread.
+#[test]
+fn tokens_are_random_and_compared_whole() {
+    let (first, second) = (net::token().unwrap(), net::token().unwrap());
+    assert_eq!(first.len(), 32);
+    assert!(first.bytes().all(|x| x.is_ascii_hexdigit()));
+    assert_ne!(first, second);
+    assert!(net::same_token(&first, &first.clone()));
+    assert!(!net::same_token(&first, &second));
+    assert!(!net::same_token(&first[..31], &first));
+    assert!(!net::same_token("", &first));
+}
+
+fn hosted<F: FnOnce(RemoteGame) + Send + 'static>(color: Color, clock: Option<Clock>, guest: F) -> (RemoteGame, thread::JoinHandle<()>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let guest = thread::spawn(move || guest(RemoteGame::join(address, "guest").unwrap()));
+    (RemoteGame::host(&listener, "host", color, clock).unwrap(), guest)
+}
+
+#[test]
+fn host_checks_the_moves_of_the_guest() {
+    let f5 = record::parse_move("f5").unwrap();
+    let d6 = record::parse_move("d6").unwrap();
+    let (mut host, guest) = hosted(Color::White, None, move |mut game| {
+        assert_eq!((game.color(), game.opponent()), (Color::Black, "host"));
+        assert_eq!(game.session().white.name(), "host");
+        assert!(game.is_my_turn());
+        // a1 is illegal, the host says so and the game goes on
+        let error = game.play(record::parse_move("a1").unwrap(), Duration::from_millis(10)).unwrap_err();
+        assert_eq!(error.to_string(), "illegal move");
+        game.play(f5, Duration::from_millis(10)).unwrap();
+        assert!(!game.is_my_turn());
+        match game.wait().unwrap() {
+            Update::Moved(turn) => assert_eq!((turn.color, turn.position), (Color::White, d6)),
+            update => panic!("unexpected update: {:?}", update)
+        }
+        game.resign().unwrap();
+        assert_eq!(game.ending(), Some(Ending::Resigned(Color::Black)));
+    });
+    assert_eq!(host.opponent(), "guest");
+    assert!(host.play(d6, Duration::from_millis(0)).is_err());
+    match host.wait().unwrap() {
+        Update::Moved(turn) => assert_eq!((turn.color, turn.position), (Color::Black, f5)),
+        update => panic!("unexpected update: {:?}", update)
+    }
+    host.play(d6, Duration::from_millis(0)).unwrap();
+    assert_eq!(host.wait().unwrap(), Update::Over(Ending::Resigned(Color::Black)));
+    guest.join().unwrap();
+    assert_eq!(host.session().history().len(), 2);
+}
+
+#[test]
+fn guest_loses_on_time() {
+    let clock = Clock { remaining: Duration::from_millis(200), increment: Duration::from_millis(0) };
+    let (mut host, guest) = hosted(Color::Black, Some(clock), |mut game| {
+        match game.wait().unwrap() {
+            Update::Moved(turn) => assert_eq!(turn.time, Duration::from_millis(50)),
+            update => panic!("unexpected update: {:?}", update)
+        }
+        assert_eq!(game.session().clock(Color::Black).unwrap().remaining, Duration::from_millis(150));
+        assert_eq!(game.wait().unwrap(), Update::Over(Ending::Time(Color::White)));
+    });
+    host.play(record::parse_move("f5").unwrap(), Duration::from_millis(50)).unwrap();
+    assert_eq!(host.wait().unwrap(), Update::Over(Ending::Time(Color::White)));
+    assert_eq!((host.record().termination(), host.record().black_disks), (Some(Termination::Timeout), Some(64)));
+    guest.join().unwrap();
+}
+
+#[test]
+fn guest_comes_back_with_its_token() {
+    let f5 = record::parse_move("f5").unwrap();
+    let d6 = record::parse_move("d6").unwrap();
+    let clock = Clock { remaining: Duration::from_secs(10), increment: Duration::from_millis(0) };
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let guest = thread::spawn(move || {
+        let mut game = RemoteGame::join(address, "guest").unwrap();
+        let token = game.token().to_string();
+        assert!(!token.is_empty());
+        assert!(matches!(game.wait().unwrap(), Update::Moved(_)));
+        // the connection drops on the turn of the guest
+        drop(game);
+        thread::sleep(Duration::from_millis(100));
+        let error = RemoteGame::rejoin(address, "eve", "guessed").err().unwrap();
+        assert_eq!(error.to_string(), "no game with this token");
+
+        let mut game = RemoteGame::rejoin(address, "guest", &token).unwrap();
+        assert_eq!((game.color(), game.opponent()), (Color::White, "host"));
+        assert_eq!(game.session().history().len(), 1);
+        assert!(game.session().clock(Color::White).unwrap().remaining <= Duration::from_millis(9900));
+        assert!(game.is_my_turn());
+        game.play(d6, Duration::from_millis(10)).unwrap();
+        assert_eq!(game.wait().unwrap(), Update::Over(Ending::Resigned(Color::Black)));
+    });
+    let mut host = RemoteGame::host(&listener, "host", Color::Black, Some(clock)).unwrap();
+    host.play(f5, Duration::from_millis(0)).unwrap();
+    match host.wait().unwrap() {
+        Update::Moved(turn) => assert_eq!((turn.color, turn.position), (Color::White, d6)),
+        update => panic!("unexpected update: {:?}", update)
+    }
+    assert!(host.session().clock(Color::White).unwrap().remaining <= Duration::from_millis(9900));
+    host.resign().unwrap();
+    guest.join().unwrap();
+}
+
+#[test]
+fn players_chat_between_moves() {
+    let (mut host, guest) = hosted(Color::Black, None, |mut game| {
+        game.say("good luck").unwrap();
+        assert_eq!(game.say("have fun").unwrap_err().to_string(), "too many messages, wait a moment");
+        assert_eq!(game.wait().unwrap(), Update::Chat("you too".to_string()));
+        assert!(matches!(game.wait().unwrap(), Update::Moved(_)));
+    });
+    assert_eq!(host.wait().unwrap(), Update::Chat("good luck".to_string()));
+    assert!(host.say(&"x".repeat(remote::MAX_CHAT_LENGTH + 1)).is_err());
+    host.say("you too").unwrap();
+    host.play(record::parse_move("f5").unwrap(), Duration::from_millis(0)).unwrap();
+    guest.join().unwrap();
+}
+
+#[test]
+fn host_checks_the_chat_of_the_guest() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let guest = thread::spawn(move || {
+        let mut stream = TcpStream::connect(address).unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut send = |body| net::send(&mut stream, &proto::Message { body: Some(body) }).unwrap();
+        send(Body::Hello(proto::Hello { protocol_version: net::PROTOCOL_VERSION, name: "guest".to_string(), token: String::new() }));
+        send(Body::Chat(proto::Chat { text: "x".repeat(remote::MAX_CHAT_LENGTH + 1) }));
+        send(Body::Chat(proto::Chat { text: "hello".to_string() }));
+        send(Body::Chat(proto::Chat { text: "hello again".to_string() }));
+        send(Body::Resign(proto::Resign { color: proto::Color::White as i32 }));
+        let mut errors = Vec::new();
+        while let Some(message) = net::receive(&mut reader).unwrap() {
+            match message.body {
+                Some(Body::Error(error)) => errors.push(error.message),
+                Some(Body::GameOver(_)) => break,
+                _ => ()
+            }
+        }
+        assert_eq!(errors, vec!["message too long", "too many messages, wait a moment"]);
+    });
+    let mut host = RemoteGame::host(&listener, "host", Color::Black, None).unwrap();
+    assert_eq!(host.wait().unwrap(), Update::Chat("hello".to_string()));
+    assert_eq!(host.wait().unwrap(), Update::Over(Ending::Resigned(Color::White)));
+    guest.join().unwrap();
+}
+
+#[test]
+fn lag_is_not_charged_to_the_guest() {
+    let clock = Clock { remaining: Duration::from_millis(200), increment: Duration::from_millis(0) };
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let guest = thread::spawn(move || {
+        let mut game = RemoteGame::join(address, "guest").unwrap();
+        assert!(matches!(game.wait().unwrap(), Update::Moved(_)));
+        // longer than the clock of the guest, but within the lag
+        thread::sleep(Duration::from_millis(300));
+        game.play(record::parse_move("d6").unwrap(), Duration::from_millis(20)).unwrap();
+        let time = game.session().history()[1].time;
+        assert!(time >= Duration::from_millis(20) && time < Duration::from_millis(150), "{:?}", time);
+        game.resign().unwrap();
+    });
+    let mut host = RemoteGame::host(&listener, "host", Color::Black, Some(clock)).unwrap();
+    host.set_lag_compensation(Duration::from_millis(400));
+    host.play(record::parse_move("f5").unwrap(), Duration::from_millis(0)).unwrap();
+    let time = match host.wait().unwrap() {
+        Update::Moved(turn) => turn.time,
+        update => panic!("unexpected update: {:?}", update)
+    };
+    assert_eq!(host.wait().unwrap(), Update::Over(Ending::Resigned(Color::White)));
+    guest.join().unwrap();
+    assert_eq!(host.session().history()[1].time, time);
+    assert_eq!(host.record().termination(), Some(Termination::Resignation));
+}
+
+#[test]
+fn guests_sending_impossible_data_are_disconnected() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let guest = thread::spawn(move || {
+        let mut stream = TcpStream::connect(address).unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut send = |body| net::send(&mut stream, &proto::Message { body: Some(body) }).unwrap();
+        send(Body::Hello(proto::Hello { protocol_version: net::PROTOCOL_VERSION, name: "guest".to_string(), token: String::new() }));
+        // a move of the host, with its color
+        send(Body::Play(proto::Play { r#move: Some(proto::Move::new(Color::Black, record::parse_move("d3"))), time_ms: 0 }));
+        let mut token = String::new();
+        let mut errors = Vec::new();
+        while let Ok(Some(message)) = net::receive(&mut reader) {
+            match message.body {
+                Some(Body::NewGame(new_game)) => token = new_game.token,
+                Some(Body::Error(error)) => errors.push(error.message),
+                _ => ()
+            }
+        }
+        assert_eq!(errors, vec!["move of the host, disconnecting"]);
+        let mut game = RemoteGame::rejoin(address, "guest", &token).unwrap();
+        game.resign().unwrap();
+    });
+    let mut host = RemoteGame::host(&listener, "host", Color::Black, None).unwrap();
+    host.play(record::parse_move("f5").unwrap(), Duration::from_millis(0)).unwrap();
+    assert_eq!(host.wait().unwrap(), Update::Over(Ending::Resigned(Color::White)));
+    guest.join().unwrap();
+}
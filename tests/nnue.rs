@@ -0,0 +1,60 @@
+#![cfg(feature = "ai")]
+
+extern crate rusty_reversi;
+
+use rusty_reversi::eval;
+use rusty_reversi::game;
+use rusty_reversi::nnue;
+use rusty_reversi::search;
+
+use eval::{Evaluator, Phase, Score};
+use game::{Board, Color};
+use nnue::{Nnue, Weights};
+use search::alpha_beta;
+
+// Evaluates every position from scratch.
+struct Fresh(Weights);
+impl Evaluator for Fresh {
+    fn evaluate(&self, board: &Board, color: Color, phase: Phase) -> Score {
+        Nnue::new(self.0.clone()).evaluate(board, color, phase)
+    }
+}
+
+#[test]
+fn incremental_updates_match_full_evaluation() {
+    let weights = Weights::random(16, 7);
+    let nnue = Nnue::new(weights.clone());
+    let fresh = Fresh(weights);
+    let mut board = Board::new();
+    let mut color = Color::Black;
+    for _ in 0..10 {
+        let legal_move = board.legal_moves(color)[0];
+        nnue.make(&board, &legal_move);
+        board.make(&legal_move);
+        color = color.flip();
+        let phase = Phase::of(&board);
+        assert_eq!(nnue.evaluate(&board, color, phase), fresh.evaluate(&board, color, phase));
+    }
+    let (incremental, fresh) = (alpha_beta(&board, color, 3, &nnue), alpha_beta(&board, color, 3, &fresh));
+    assert_eq!((incremental.best, incremental.score), (fresh.best, fresh.score));
+}
+
+#[test]
+fn unmake_restores_the_board() {
+    let mut board = Board::new();
+    let original = board;
+    let legal_move = board.legal_moves(Color::Black)[1];
+    board.make(&legal_move);
+    assert_eq!(board.count(Color::Black), 4);
+    board.unmake(&legal_move);
+    assert_eq!(board, original);
+}
+
+#[test]
+fn weights_round_trip_through_bytes() {
+    let weights = Weights::random(8, 1);
+    let mut bytes = Vec::new();
+    weights.write(&mut bytes).unwrap();
+    assert_eq!(Weights::read(&bytes[..]).unwrap(), weights);
+    assert!(Weights::read(&bytes[1..]).is_err());
+}
@@ -0,0 +1,56 @@
+#![cfg(feature = "ai")]
+
+extern crate rusty_reversi;
+
+use std::time::Duration;
+
+use rusty_reversi::analysis;
+use rusty_reversi::engine::Limits;
+use rusty_reversi::eval::WeightedSquares;
+use rusty_reversi::formats::pgn;
+use rusty_reversi::record::{self, GameRecord};
+use rusty_reversi::search::AlphaBeta;
+
+#[test]
+fn games_survive_a_round_trip() {
+    // White has to skip after the last move, Black wins with all disks
+    let game = GameRecord {
+        black: "Alice \"the wall\"".to_string(),
+        white: "Bob".to_string(),
+        event: "Club championship".to_string(),
+        date: "2024.05.04".to_string(),
+        moves: record::parse_transcript("e6f4e3f6g5d6e7f5c5").unwrap(),
+        black_disks: Some(64),
+        theoretical_disks: None,
+        tags: vec![("Round".to_string(), "3".to_string())]
+    };
+    let text = pgn::format(&game).unwrap();
+    assert!(text.starts_with("[Event \"Club championship\"]\n[Date \"2024.05.04\"]\n[Black \"Alice \\\"the wall\\\"\"]\n"));
+    assert!(text.ends_with("\n\n1. e6 f4 2. e3 f6 3. g5 d6 4. e7 f5 5. c5 64-0\n"));
+    assert_eq!(pgn::parse(&text).unwrap(), vec![game.clone()]);
+    let mut bytes = Vec::new();
+    pgn::write(&mut bytes, &[game.clone(), GameRecord::default()]).unwrap();
+    assert_eq!(pgn::read(&bytes[..]).unwrap(), vec![game, GameRecord::default()]);
+    let loose = "[Black \"A\"]\n1.f5 d6 {opening\n  comment} 2. c3 {first} {second} *";
+    let games = pgn::parse_commented(loose).unwrap();
+    assert_eq!(games[0].0.moves, record::parse_transcript("f5d6c3").unwrap());
+    assert_eq!(games[0].1, vec!["", "opening comment", "first second"]);
+    assert!(pgn::parse("1. f5 z9 *").is_err());
+    assert!(pgn::parse("[Result \"70-3\"]").is_err());
+}
+
+#[test]
+fn analyses_are_written_as_comments() {
+    let game = GameRecord { moves: record::parse_transcript("f5d6c3").unwrap(), ..GameRecord::default() };
+    let mut engine = AlphaBeta::new(1, WeightedSquares::default());
+    let mut annotated = analysis::annotate(&game, &mut engine, &Limits::default()).unwrap();
+    annotated.annotations[0].time = Some(Duration::from_millis(1200));
+    annotated.annotations[2].comment = "the usual".to_string();
+    let text = pgn::format_annotated(&annotated).unwrap();
+    let games = pgn::parse_commented(&text).unwrap();
+    assert_eq!(games[0].0, game);
+    let comments = &games[0].1;
+    assert!(comments[0].ends_with(", 1.2s"), "{}", comments[0]);
+    assert!(comments[2].ends_with(", the usual"), "{}", comments[2]);
+    assert!(comments.iter().all(|x| x.contains("best")));
+}
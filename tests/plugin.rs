@@ -0,0 +1,94 @@
+#![cfg(all(feature = "plugins", unix))]
+
+extern crate rusty_reversi;
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use rusty_reversi::engine;
+use rusty_reversi::eval;
+use rusty_reversi::game;
+use rusty_reversi::plugin;
+
+use engine::Limits;
+use eval::{Evaluator, Phase};
+use game::{Board, Color};
+use plugin::{Pluggable, Plugin};
+
+// The evaluation of a plugin counting the disks, weighing a1 more.
+const MATERIAL: &str = "
+    let corner = (player & 1) as i32 * 100;
+    player.count_ones() as i32 - opponent.count_ones() as i32 + corner + empties as i32 * 1000";
+
+// Builds a plugin for an interface version, evaluating with the body of a function of `player`,
+// `opponent` and `empties`.
+fn build(name: &str, version: u32, evaluation: &str) -> PathBuf {
+    let directory = env::temp_dir().join("rusty_reversi_plugin_test");
+    fs::create_dir_all(&directory).unwrap();
+    let source = directory.join(format!("{}.rs", name));
+    fs::write(&source, format!("
+        #[no_mangle]
+        pub extern \"C\" fn rusty_reversi_plugin_version() -> u32 {{
+            {}
+        }}
+
+        #[no_mangle]
+        pub extern \"C\" fn rusty_reversi_evaluate(player: u64, opponent: u64, empties: u32) -> i32 {{
+            {}
+        }}
+    ", version, evaluation)).unwrap();
+    let library = directory.join(format!("lib{}.so", name));
+    let status = Command::new(env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string()))
+        .args(["--crate-type", "cdylib", "-o"]).arg(&library).arg(&source)
+        .status().unwrap();
+    assert!(status.success());
+    library
+}
+
+#[test]
+fn plugins_evaluate_positions() {
+    let plugin = Plugin::load(build("evaluate", plugin::VERSION, MATERIAL)).unwrap();
+    let mut board = Board::new();
+    board.cells[0][7] = Some(Color::Black);
+    let phase = Phase::of(&board);
+    assert_eq!(plugin.evaluate(&board, Color::Black, phase), 1 + 100 + 59 * 1000);
+    assert_eq!(plugin.evaluate(&board, Color::White, phase), -1 + 59 * 1000);
+}
+
+#[test]
+fn plugin_scores_are_clamped_below_won_positions() {
+    let path = build("extreme", plugin::VERSION, "let _ = (player, opponent); if empties % 2 == 0 { i32::MIN } else { i32::MAX }");
+    let plugin = Plugin::load(&path).unwrap();
+    let mut board = Board::new();
+    assert_eq!(plugin.evaluate(&board, Color::Black, Phase::of(&board)), -plugin::MAX_SCORE);
+    board.cells[0][0] = Some(Color::Black);
+    assert_eq!(plugin.evaluate(&board, Color::Black, Phase::of(&board)), plugin::MAX_SCORE);
+    let board = Board::new();
+    let mut engine = engine::from_spec(&format!("alphabeta:depth=3,plugin={}", path.display())).unwrap();
+    let result = engine.best_move(&board, Color::Black, &Limits::default());
+    assert!(result.score.abs() <= plugin::MAX_SCORE);
+}
+
+#[test]
+fn plugins_of_other_versions_are_refused() {
+    let error = Plugin::load(build("refused", plugin::VERSION + 1, MATERIAL)).err().unwrap();
+    assert!(error.to_string().contains("unsupported plugin version"));
+    assert!(Plugin::load("/nonexistent/libmaterial.so").is_err());
+}
+
+#[test]
+fn engines_select_plugins_by_path() {
+    let path = build("select", plugin::VERSION, MATERIAL);
+    let mut evaluator = Pluggable::default();
+    evaluator.set_option("plugin", path.to_str().unwrap()).unwrap();
+    assert_eq!(evaluator.plugin.as_ref().map(|x| x.path()), Some(path.as_path()));
+    evaluator.set_option("plugin", "").unwrap();
+    assert!(evaluator.plugin.is_none());
+    let mut engine = engine::from_spec(&format!("alphabeta:depth=1,plugin={}", path.display())).unwrap();
+    let board = Board::new();
+    let result = engine.best_move(&board, Color::Black, &Limits::default());
+    assert!(board.legal_moves(Color::Black).iter().any(|x| Some(x.position) == result.best));
+    assert!(engine.set_option("plugins", "").is_err());
+}
@@ -0,0 +1,55 @@
+#![cfg(feature = "ai")]
+
+extern crate rusty_reversi;
+
+use rusty_reversi::endgame;
+use rusty_reversi::ffo;
+use rusty_reversi::puzzle::{self, Options};
+use rusty_reversi::random::Random;
+use rusty_reversi::record;
+
+#[test]
+fn puzzles_have_a_single_solution() {
+    let (board, color) = record::parse_position("OXXXXXXXOOOOOOXO-XOOOXO-OXOOXO-XOXOXOXXXOOXXOX-XOOOOOOOO-XOOO--- X").unwrap();
+    let problem = puzzle::find(&board, color, &Options::default()).unwrap();
+    assert_eq!(problem.best_moves, vec![(0, 0)]);
+    assert_eq!(problem.score, 6);
+    assert_eq!(problem.name, "8 empties, gap 6");
+    assert_eq!(puzzle::find(&board, color, &Options { gap: 7, ..Options::default() }), None);
+    assert_eq!(puzzle::find(&board, color, &Options { min_empties: 9, ..Options::default() }), None);
+    let parsed = ffo::parse(&ffo::format(std::slice::from_ref(&problem))).unwrap();
+    assert_eq!(parsed, vec![problem]);
+}
+
+#[test]
+fn generated_puzzles_are_solved() {
+    let options = Options { min_empties: 6, max_empties: 8, gap: 2 };
+    let problems = puzzle::generate(&mut Random::new(3), 2, 50, &options);
+    assert_eq!(problems.len(), 2);
+    for problem in &problems {
+        let empties = problem.board.empties();
+        assert!((6..=8).contains(&empties));
+        let solution = endgame::solve(&problem.board, problem.color);
+        assert_eq!(solution.score, problem.score);
+        assert_eq!(solution.best_move, Some(problem.best_moves[0]));
+    }
+}
+
+#[test]
+fn answers_are_checked_and_counted() {
+    let problems = ffo::parse("OXXXXXXXOOOOOOXO-XOOOXO-OXOOXO-XOXOXOXXXOOXXOX-XOOOOOOOO-XOOO--- X; A8:+6;").unwrap();
+    assert!(puzzle::check(&problems[0], (0, 0)));
+    for legal_move in problems[0].board.legal_moves(problems[0].color) {
+        assert_eq!(puzzle::check(&problems[0], legal_move.position), legal_move.position == (0, 0));
+    }
+    assert!(!puzzle::check(&problems[0], (0, 7)));
+    // another move reaching the same score is as good
+    let mut problem = problems[0].clone();
+    problem.best_moves = vec![(5, 0)];
+    assert!(puzzle::check(&problem, (0, 0)));
+    let mut streak = puzzle::Streak::default();
+    for &correct in &[true, true, false, true] {
+        streak.answer(correct);
+    }
+    assert_eq!(streak, puzzle::Streak { current: 1, best: 2, solved: 3, attempted: 4 });
+}
@@ -0,0 +1,44 @@
+#![cfg(feature = "ai")]
+
+extern crate rusty_reversi;
+
+use rusty_reversi::rating::{glicko, Outcome, Rating, Ratings};
+
+#[test]
+fn glicko_matches_the_example_of_the_paper() {
+    let player = Rating { rating: 1500.0, deviation: 200.0, ..Rating::default() };
+    let opponent = |rating, deviation| Rating { rating, deviation, ..Rating::default() };
+    let games = [(opponent(1400.0, 30.0), 1.0), (opponent(1550.0, 100.0), 0.0), (opponent(1700.0, 300.0), 0.0)];
+    let updated = glicko(&player, &games, 0.5);
+    assert!((updated.rating - 1464.06).abs() < 0.01, "{}", updated.rating);
+    assert!((updated.deviation - 151.52).abs() < 0.01, "{}", updated.deviation);
+    assert!((updated.volatility - 0.05999).abs() < 1e-5, "{}", updated.volatility);
+}
+
+#[test]
+fn ratings_follow_results() {
+    let mut ratings = Ratings::new();
+    ratings.update(&[Outcome::new("strong", "weak", 10), Outcome::new("strong", "weak", 2), Outcome::new("weak", "strong", 0)]);
+    let (strong, weak) = (ratings.get("strong"), ratings.get("weak"));
+    assert!((strong.elo + weak.elo - 3000.0).abs() < 1e-9);
+    assert!(strong.elo > weak.elo && strong.rating > weak.rating);
+    assert!(strong.deviation < 350.0);
+    assert_eq!(strong.games, 3);
+    // sitting out a period makes the rating less certain
+    ratings.update(&[Outcome::new("strong", "other", 0)]);
+    assert!(ratings.get("weak").deviation > weak.deviation);
+    assert_eq!(ratings.get("weak").rating, weak.rating);
+    assert!(ratings.format().lines().nth(1).unwrap().contains("strong"));
+}
+
+#[test]
+fn ratings_are_saved() {
+    let mut ratings = Ratings::new();
+    ratings.update(&[Outcome::new("alphabeta", "human", 4)]);
+    let mut bytes = Vec::new();
+    ratings.write(&mut bytes).unwrap();
+    assert_eq!(Ratings::read(&bytes[..]).unwrap(), ratings);
+    assert!(Ratings::read(&bytes[..10]).is_err());
+    bytes[0] = b'X';
+    assert!(Ratings::read(&bytes[..]).is_err());
+}
@@ -0,0 +1,38 @@
+extern crate rusty_reversi;
+
+use rusty_reversi::game::Color;
+use rusty_reversi::record::{self, GameRecord, Termination};
+
+#[test]
+fn transcripts_roundtrip() {
+    assert_eq!(record::parse_move("a1"), Some((0, 7)));
+    assert_eq!(record::parse_move("H8"), Some((7, 0)));
+    assert_eq!(record::parse_move("i1"), None);
+    let moves = record::parse_transcript("f5 d6 C3").unwrap();
+    assert_eq!(moves, vec![(5, 3), (3, 2), (2, 5)]);
+    assert_eq!(record::format_transcript(&moves), "f5d6c3");
+    assert_eq!(record::parse_transcript("f5d"), None);
+}
+
+#[test]
+fn records_replay_their_moves() {
+    let game = GameRecord { moves: record::parse_transcript("f5d6c3").unwrap(), ..GameRecord::default() };
+    let positions = game.positions().unwrap();
+    assert_eq!(positions.len(), 4);
+    assert_eq!(positions[0].1, Color::Black);
+    assert_eq!(positions[3].1, Color::White);
+    assert_eq!(positions[3].0.count(Color::Black), 5);
+    let illegal = GameRecord { moves: record::parse_transcript("f5a1").unwrap(), ..GameRecord::default() };
+    assert_eq!(illegal.positions(), Err(1));
+}
+
+#[test]
+fn forfeits_are_tagged_with_their_termination() {
+    let mut game = GameRecord { tags: vec![("termination".to_string(), "forfeit".to_string())], ..GameRecord::default() };
+    assert_eq!(game.termination(), Some(Termination::Forfeit));
+    game.forfeit(Color::White, Termination::Timeout);
+    assert_eq!((game.tag("termination"), game.black_disks), (Some("time"), Some(64)));
+    assert_eq!(game.tags.len(), 1);
+    assert_eq!(Termination::parse("resignation"), Some(Termination::Resignation));
+    assert_eq!(Termination::parse("draw"), None);
+}
@@ -0,0 +1,102 @@
+#![cfg(feature = "ui")]
+
+extern crate rusty_reversi;
+
+use rusty_reversi::game::{Board, Color};
+use rusty_reversi::record::{self, GameRecord};
+use rusty_reversi::render::{self, Animation, Style, Theme};
+
+#[test]
+fn disks_are_drawn_in_their_cells() {
+    let style = Style { size: 405, hints: Some(Color::Black), ..Style::default() };
+    let image = render::render(&Board::new(), &style);
+    assert_eq!((image.width, image.height, image.pixels.len()), (400, 400, 400 * 400 * 3));
+    let theme = Theme::classic();
+    let center = |cell: &str| {
+        let (x, y) = record::parse_move(cell).unwrap();
+        image.pixel(x as u32 * 50 + 25, (7 - y as u32) * 50 + 25)
+    };
+    assert_eq!(center("d4"), theme.white);
+    assert_eq!(center("e4"), theme.black);
+    assert_eq!(center("f5"), theme.hint);
+    assert_eq!(center("a1"), theme.board);
+    assert_eq!(image.pixel(0, 0), theme.grid);
+    assert_eq!(image.pixel(399, 399), theme.grid);
+    for size in 0..16 {
+        let image = render::render(&Board::new(), &Style { size, ..style });
+        assert_eq!((image.width, image.height), (size.max(8) / 8 * 8, size.max(8) / 8 * 8));
+    }
+}
+
+#[cfg(feature = "image")]
+#[test]
+fn images_are_encoded_as_png() {
+    let image = render::render(&Board::new(), &Style::default());
+    let mut bytes = Vec::new();
+    image.write_png(&mut bytes).unwrap();
+    assert_eq!(&bytes[..8], b"\x89PNG\r\n\x1a\n");
+    assert_eq!(&bytes[16..24], &[0, 0, 1, 144, 0, 0, 1, 144]);
+    let game = GameRecord { moves: record::parse_transcript("f5d6").unwrap(), ..GameRecord::default() };
+    let frames = render::animate(&game, &Animation::default()).unwrap();
+    let mut gif = Vec::new();
+    render::write_gif(&mut gif, &frames).unwrap();
+    assert_eq!(&gif[..6], b"GIF89a");
+    let mut apng = Vec::new();
+    render::write_apng(&mut apng, &frames).unwrap();
+    assert!(apng.windows(4).any(|x| x == b"acTL"));
+}
+
+#[test]
+fn games_are_animated_move_by_move() {
+    let game = GameRecord { moves: record::parse_transcript("f5d6c3").unwrap(), ..GameRecord::default() };
+    let animation = Animation { flip_frames: 4, style: Style { size: 160, ..Style::default() }, ..Animation::default() };
+    let frames = render::animate(&game, &animation).unwrap();
+    assert_eq!(frames.len(), 1 + 3 * 4);
+    assert!(frames.iter().all(|x| (x.image.width, x.image.height) == (160, 170)));
+    assert_eq!(frames[0].delay, animation.move_delay);
+    assert_eq!(frames[1].delay, animation.frame_delay);
+    // the disk played appears first, then e5 flips
+    let (x, y) = record::parse_move("f5").unwrap();
+    let theme = Theme::classic();
+    let center = (x as u32 * 20 + 10, (7 - y as u32) * 20 + 10);
+    assert_eq!(frames[1].image.pixel(center.0, center.1), theme.black);
+    assert_eq!(frames[4].image.pixel(center.0, center.1), theme.black);
+    assert_eq!(frames[4].image.pixel(center.0 - 20, center.1), theme.black);
+    assert_eq!(frames[1].image.pixel(center.0 - 20, center.1), theme.white);
+    let position = render::render(&game.positions().unwrap()[1].0, &animation.style);
+    assert_eq!(&frames[4].image.pixels[..position.pixels.len()], &position.pixels[..]);
+    let illegal = GameRecord { moves: record::parse_transcript("f5a1").unwrap(), ..GameRecord::default() };
+    assert_eq!(render::animate(&illegal, &animation), Err(1));
+}
+
+#[test]
+fn boards_are_drawn_as_svg() {
+    let board = Board::new();
+    let style = Style { size: 80, hints: Some(Color::Black), ..Style::default() };
+    let svg = render::svg(&board, &style);
+    assert!(svg.starts_with("<svg") && svg.ends_with("</svg>\n"));
+    assert_eq!(svg.matches("<circle").count(), 4 + 4);
+    // d3 is a legal move of Black, drawn in the third row from the top
+    assert!(svg.contains("<circle cx=\"35\" cy=\"25\" r=\"1\" fill=\"#005a2d\"/>"));
+    assert!(svg.contains("<circle cx=\"35\" cy=\"35\" r=\"4\" fill=\"#f0f0f0\"/>"));
+    let svg = render::svg(&board, &Style { hints: None, ..style });
+    assert_eq!(svg.matches("<circle").count(), 4);
+    for size in 0..16 {
+        let svg = render::svg(&board, &Style { size, ..style });
+        assert!(svg.starts_with(&format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{0}\"", size.max(8) / 8 * 8)));
+    }
+}
+
+#[test]
+fn last_moves_are_marked() {
+    let board = Board::new();
+    let last_move = board.legal_moves(Color::Black).into_iter().find(|x| x.position == (5, 3));
+    let style = Style { size: 80, last_move, ..Style::default() };
+    let board = last_move.unwrap().apply(board);
+    let svg = render::svg(&board, &style);
+    assert!(svg.contains("<circle cx=\"55\" cy=\"45\" r=\"1.2\" fill=\"#dc2828\"/>"));
+    assert!(svg.contains("<circle cx=\"45\" cy=\"45\" r=\"0.6\" fill=\"#dc2828\"/>"));
+    let image = render::render(&board, &Style { size: 400, ..style });
+    assert_eq!(image.pixel(275, 225), style.theme.mark);
+    assert_eq!(image.pixel(275, 240), style.theme.black);
+}
@@ -0,0 +1,30 @@
+#![cfg(feature = "ai")]
+
+extern crate rusty_reversi;
+
+use rusty_reversi::eval;
+use rusty_reversi::game;
+use rusty_reversi::rollout;
+
+use eval::{Evaluator, Phase};
+use game::{Board, Color};
+use rollout::Rollouts;
+
+#[test]
+fn rollouts_score_finished_games() {
+    let mut board = Board { cells: [[None; 8]; 8] };
+    board.cells[0][0] = Some(Color::Black);
+    let rollouts = Rollouts::new(10, 0);
+    assert_eq!(rollouts.win_rate(&board, Color::Black), 1.0);
+    assert_eq!(rollouts.win_rate(&board, Color::White), 0.0);
+    assert_eq!(rollouts.evaluate(&board, Color::White, Phase::of(&board)), -rollouts.scale);
+    board.cells[7][7] = Some(Color::White);
+    assert_eq!(rollouts.value(&board, Color::Black), 0.0);
+}
+
+#[test]
+fn rollouts_are_even_at_the_start() {
+    let rollouts = Rollouts::new(400, 1);
+    let rate = rollouts.win_rate(&Board::new(), Color::Black);
+    assert!(rate > 0.35 && rate < 0.65);
+}
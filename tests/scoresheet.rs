@@ -0,0 +1,33 @@
+#![cfg(feature = "formats")]
+
+extern crate rusty_reversi;
+
+use rusty_reversi::formats::scoresheet;
+use rusty_reversi::record::{self, GameRecord};
+
+#[test]
+fn moves_are_numbered_in_their_cells() {
+    let game = GameRecord {
+        black: "Alice".to_string(),
+        white: "Bob <B>".to_string(),
+        moves: record::parse_transcript("f5d6c3d3c4").unwrap(),
+        black_disks: Some(40),
+        ..GameRecord::default()
+    };
+    let text = scoresheet::text(&game).unwrap();
+    let lines = text.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], "Black (X): Alice   White (O): Bob <B>   Result: 40-24");
+    assert_eq!(lines[2], "    a   b   c   d   e   f   g   h");
+    assert_eq!(lines[3], "  +---+---+---+---+---+---+---+---+");
+    assert_eq!(lines[8], "3 |   |   | 3 | 4 |   |   |   |   |");
+    assert_eq!(lines[10], "4 |   |   | 5 | O | X |   |   |   |");
+    assert_eq!(lines[12], "5 |   |   |   | X | O | 1 |   |   |");
+    assert_eq!(lines[14], "6 |   |   |   | 2 |   |   |   |   |");
+    let svg = scoresheet::svg(&game).unwrap();
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(svg.contains("Bob &lt;B&gt;"));
+    assert_eq!(svg.matches("<circle").count(), 4);
+    assert!(svg.contains(">5</text>"));
+    let illegal = GameRecord { moves: record::parse_transcript("a1").unwrap(), ..GameRecord::default() };
+    assert!(scoresheet::text(&illegal).is_err());
+}
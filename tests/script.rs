@@ -0,0 +1,86 @@
+#![cfg(feature = "scripting")]
+
+extern crate rusty_reversi;
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use rusty_reversi::arena;
+use rusty_reversi::engine;
+use rusty_reversi::game;
+use rusty_reversi::script::Script;
+
+use arena::{run, Options};
+use engine::{Engine, Limits};
+use game::{Board, Color};
+
+fn write(name: &str, script: &str) -> PathBuf {
+    let directory = env::temp_dir().join("rusty_reversi_script_test");
+    fs::create_dir_all(&directory).unwrap();
+    let path = directory.join(name);
+    fs::write(&path, script).unwrap();
+    path
+}
+
+#[test]
+fn scripts_choose_moves() {
+    let path = write("corners.rhai", "
+        fn choose(board, player, moves) {
+            for move in moves {
+                if move.cell in [\"a1\", \"h1\", \"a8\", \"h8\"] {
+                    return move;
+                }
+            }
+            moves[moves.len() - 1].cell
+        }
+    ");
+    let script = Script::load(&path).unwrap();
+    assert_eq!(script.path(), path.as_path());
+    let board = Board::new();
+    let position = script.choose(&board, Color::Black).unwrap();
+    assert!(board.test_move(Color::Black, position).is_ok());
+    // a1 and e5 are legal
+    let mut board = Board { cells: [[None; 8]; 8] };
+    board.cells[1][6] = Some(Color::White);
+    board.cells[2][5] = Some(Color::Black);
+    board.cells[3][4] = Some(Color::White);
+    assert_eq!(board.legal_moves(Color::Black).len(), 2);
+    assert_eq!(script.choose(&board, Color::Black).unwrap(), (0, 7));
+}
+
+#[test]
+fn scripts_receive_the_board() {
+    let path = write("board.rhai", "
+        fn choose(board, player, moves) {
+            if board.len() != 64 || board[27] != 'O' || board[28] != 'X' || player != \"X\" {
+                throw \"unexpected board\";
+            }
+            moves.filter(|x| x.index == 19 && x.flips == 1)[0]
+        }
+    ");
+    let script = Script::load(path).unwrap();
+    assert_eq!(script.choose(&Board::new(), Color::Black).unwrap(), (3, 5));
+}
+
+#[test]
+fn scripts_play_matches() {
+    let path = write("first.rhai", "fn choose(board, player, moves) { moves[0] }");
+    let mut script = engine::from_spec(&format!("script:{}", path.display())).unwrap();
+    let mut opponent = engine::from_spec("alphabeta:depth=1").unwrap();
+    let report = run(&Options { games: 2, ..Options::default() }, &mut script, &mut opponent);
+    assert!(report.games.iter().all(|x| x.tag("termination").is_none()));
+}
+
+#[test]
+fn failing_scripts_play_no_move() {
+    assert!(Script::load(write("empty.rhai", "let x = 1;")).is_err());
+    assert!(Script::load(write("syntax.rhai", "fn choose(board, player, moves) {")).is_err());
+    let mut illegal = Script::load(write("illegal.rhai", "fn choose(board, player, moves) { \"a1\" }")).unwrap();
+    assert!(illegal.choose(&Board::new(), Color::Black).is_err());
+    assert_eq!(illegal.best_move(&Board::new(), Color::Black, &Limits::default()).best, None);
+    let mut endless = Script::load(write("endless.rhai", "fn choose(board, player, moves) { loop {} }")).unwrap();
+    endless.set_option("operations", "1000").unwrap();
+    assert!(endless.choose(&Board::new(), Color::Black).is_err());
+    assert!(endless.set_option("depth", "1").is_err());
+}
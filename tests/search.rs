@@ -0,0 +1,200 @@
+#![cfg(feature = "ai")]
+
+extern crate rusty_reversi;
+
+use std::time::{Duration, Instant};
+
+use rusty_reversi::endgame;
+use rusty_reversi::engine::{Engine, Limits, SearchResult};
+use rusty_reversi::eval;
+use rusty_reversi::game;
+use rusty_reversi::random::Random;
+use rusty_reversi::search;
+use rusty_reversi::tt;
+
+use eval::WeightedSquares;
+use game::{Board, Color};
+use search::{alpha_beta, alpha_beta_with_options, alpha_beta_with_table, minimax, AlphaBeta, Endgame, EndgameMode, IncrementalSearch, Options, Step, WIN};
+use tt::{Replacement, TranspositionTable};
+
+#[test]
+fn alpha_beta_agrees_with_minimax() {
+    let evaluator = WeightedSquares::default();
+    let board = Board::new();
+    for depth in 1..5 {
+        let (pruned, full) = (alpha_beta(&board, Color::Black, depth, &evaluator), minimax(&board, Color::Black, depth, &evaluator));
+        assert_eq!((pruned.best, pruned.score, pruned.depth), (full.best, full.score, full.depth));
+        assert_eq!(pruned.pv.len(), depth as usize);
+        assert!(pruned.nodes <= full.nodes);
+    }
+}
+
+#[test]
+fn search_takes_the_corner() {
+    let mut board = Board { cells: [[None; 8]; 8] };
+    board.cells[4][4] = Some(Color::Black);
+    board.cells[5][5] = Some(Color::White);
+    board.cells[6][6] = Some(Color::Black);
+    let result = alpha_beta(&board, Color::White, 1, &WeightedSquares::default());
+    assert_eq!(result.best, Some((7, 7)));
+    assert_eq!(result.pv, vec![(7, 7)]);
+}
+
+#[test]
+fn transposition_table_keeps_scores() {
+    let evaluator = WeightedSquares::default();
+    let board = Board::new();
+    for &replacement in &[Replacement::DepthPreferred, Replacement::AlwaysReplace] {
+        // a single bucket forces replacements all the time
+        for &mib in &[0, 1] {
+            let mut table = TranspositionTable::new(mib, replacement);
+            for depth in 1..6 {
+                let expected = minimax(&board, Color::Black, depth, &evaluator).score;
+                assert_eq!(alpha_beta_with_table(&board, Color::Black, depth, &evaluator, &mut table).score, expected);
+            }
+            assert!(table.hashfull() > 0);
+        }
+    }
+}
+
+#[test]
+fn searches_collect_their_details() {
+    let mut table = TranspositionTable::new(1, Replacement::DepthPreferred);
+    let result = alpha_beta_with_table(&Board::new(), Color::Black, 4, &WeightedSquares::default(), &mut table);
+    let stats = &result.stats;
+    assert_eq!(stats.nodes(), result.nodes);
+    assert_eq!(stats.nodes_by_ply[..2], [1, 4]);
+    assert_eq!(stats.nodes_by_ply.len(), 5);
+    assert!(stats.tt_probes >= stats.expanded);
+    assert_eq!(stats.tt_hits, result.tt_hits);
+    assert!(stats.cutoff_rate() > 0.0 && stats.cutoff_rate() <= 1.0);
+    assert_eq!(stats.cutoff_rate_by_move().len(), stats.cutoffs_by_move.len());
+    assert_eq!(stats.iterations.iter().map(|x| (x.depth, x.nodes)).collect::<Vec<_>>(), vec![(4, result.nodes)]);
+
+    // the details of the iterations add up
+    let limits = Limits { depth: Some(5), time: Some(Duration::from_secs(60)), ..Limits::default() };
+    let result = AlphaBeta::new(5, WeightedSquares::default()).best_move(&Board::new(), Color::Black, &limits);
+    assert_eq!(result.stats.iterations.iter().map(|x| x.depth).collect::<Vec<_>>(), vec![1, 2, 3, 4, 5]);
+    assert_eq!(result.stats.iterations.iter().map(|x| x.nodes).sum::<u64>(), result.nodes);
+    assert_eq!(result.stats.nodes(), result.nodes);
+}
+
+#[test]
+fn endgame_is_solved_exactly() {
+    // random moves until 10 empty cells
+    let mut random = Random::new(3);
+    let (mut board, mut color) = (Board::new(), Color::Black);
+    while board.empties() > 10 {
+        let moves = board.legal_moves(color);
+        if !moves.is_empty() {
+            board = moves[random.below(moves.len())].apply(board);
+        }
+        color = color.flip();
+    }
+    let diff = i32::from(endgame::solve(&board, color).score);
+    let evaluator = WeightedSquares::default();
+    let mut exact = Options { endgame: Some(Endgame { empties: 10, mode: EndgameMode::Exact }), ..Options::default() };
+    assert_eq!(alpha_beta_with_options(&board, color, 1, &evaluator, &mut exact).score, diff.signum() * WIN + diff);
+    let mut win_loss_draw = Options { endgame: Some(Endgame { empties: 10, mode: EndgameMode::WinLossDraw }), ..Options::default() };
+    assert_eq!(alpha_beta_with_options(&board, color, 1, &evaluator, &mut win_loss_draw).score, diff.signum() * WIN);
+    // with a lower threshold, the positions after the first move are solved
+    let mut extended = Options { endgame: Some(Endgame { empties: 9, mode: EndgameMode::Exact }), ..Options::default() };
+    assert_eq!(alpha_beta_with_options(&board, color, 1, &evaluator, &mut extended).score, diff.signum() * WIN + diff);
+}
+
+#[test]
+fn contempt_scores_draws() {
+    // a finished drawn game
+    let mut board = Board { cells: [[None; 8]; 8] };
+    board.cells[0][0] = Some(Color::Black);
+    board.cells[7][7] = Some(Color::White);
+    let evaluator = WeightedSquares::default();
+    let mut options = Options { contempt: 50, ..Options::default() };
+    assert_eq!(alpha_beta_with_options(&board, Color::Black, 3, &evaluator, &mut options).score, -50);
+    assert_eq!(alpha_beta_with_options(&board, Color::White, 3, &evaluator, &mut options).score, -50);
+    let result = alpha_beta(&board, Color::White, 3, &evaluator);
+    assert_eq!((result.best, result.score), (None, 0));
+}
+
+#[test]
+fn contempt_is_kept_apart_for_each_player_in_the_table() {
+    // the opponent finds in the table the positions after the moves, searched to the end
+    let engine = || AlphaBeta { table: Some(TranspositionTable::new(1, Replacement::DepthPreferred)), contempt: 50, ..AlphaBeta::new(12, WeightedSquares::default()) };
+    let exact = Limits { depth: Some(11), ..Limits::default() };
+    for seed in 0..8 {
+        let mut random = Random::new(seed);
+        let (mut board, mut color) = (Board::new(), Color::Black);
+        while board.empties() > 10 || board.legal_moves(color).is_empty() {
+            let moves = board.legal_moves(color);
+            if !moves.is_empty() {
+                board = moves[random.below(moves.len())].apply(board);
+            }
+            color = color.flip();
+        }
+        let mut shared = engine();
+        shared.best_move(&board, color, &Limits::default());
+        for legal_move in board.legal_moves(color) {
+            let after = legal_move.apply(board);
+            if !after.legal_moves(color.flip()).is_empty() {
+                let found = shared.best_move(&after, color.flip(), &exact).score;
+                assert_eq!(found, engine().best_move(&after, color.flip(), &exact).score, "seed {}", seed);
+            }
+        }
+    }
+}
+
+#[test]
+fn incremental_search_finds_the_same_results() {
+    // random moves until 40 empty cells
+    let mut random = Random::new(5);
+    let (mut board, mut color) = (Board::new(), Color::Black);
+    while board.empties() > 40 {
+        let moves = board.legal_moves(color);
+        board = moves[random.below(moves.len())].apply(board);
+        color = color.flip();
+    }
+    let engine = || AlphaBeta { table: Some(TranspositionTable::new(1, Replacement::DepthPreferred)), ..AlphaBeta::new(5, WeightedSquares::default()) };
+    let expected = engine().best_move(&board, color, &Limits::default());
+
+    let mut search = IncrementalSearch::new(engine(), &board, color, &Limits::default());
+    let mut steps = 1;
+    let found = loop {
+        match search.step(Duration::from_secs(0)) {
+            Step::Done(found) => break found,
+            Step::Pending => steps += 1
+        }
+    };
+    assert!(steps > 1);
+    let mut stats = found.stats.clone();
+    for (iteration, timed) in stats.iterations.iter_mut().zip(&expected.stats.iterations) {
+        iteration.time = timed.time;
+    }
+    assert_eq!(SearchResult { time: expected.time, stats, ..found.clone() }, expected);
+    assert_eq!(search.step(Duration::from_secs(0)), Step::Done(found));
+
+    // a time limit deepens from the first iteration, and the engine can be taken back midway
+    let limits = Limits { time: Some(Duration::from_secs(60)), ..Limits::default() };
+    let mut search = IncrementalSearch::new(engine(), &board, color, &limits);
+    assert_eq!(search.step(Duration::from_millis(5)), Step::Pending);
+    assert!(search.result().is_some_and(|x| x.depth >= 1));
+    let mut engine = search.into_engine();
+    assert_eq!(engine.best_move(&board, color, &Limits::default()).score, expected.score);
+}
+
+#[test]
+fn deep_iterations_are_given_up_at_the_time_limit() {
+    // random moves until 40 empty cells, where iterations take long
+    let mut random = Random::new(5);
+    let (mut board, mut color) = (Board::new(), Color::Black);
+    while board.empties() > 40 {
+        let moves = board.legal_moves(color);
+        board = moves[random.below(moves.len())].apply(board);
+        color = color.flip();
+    }
+    let mut engine = AlphaBeta::new(60, WeightedSquares::default());
+    let start = Instant::now();
+    let found = engine.best_move(&board, color, &Limits { time: Some(Duration::from_millis(100)), ..Limits::default() });
+    assert!(start.elapsed() < Duration::from_millis(250), "{:?}", start.elapsed());
+    assert!(found.best.is_some() && found.depth < 60);
+    assert_eq!(found.stats.iterations.last().map(|x| x.depth), Some(found.depth));
+}
@@ -0,0 +1,93 @@
+extern crate rusty_reversi;
+
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rusty_reversi::formats::json::Value;
+use rusty_reversi::game::Color;
+use rusty_reversi::record;
+use rusty_reversi::session::{Clock, EventLog, GameSession, Player};
+
+fn session() -> GameSession {
+    let engine = Player::Engine { name: "alphabeta".to_string(), options: vec![("depth".to_string(), "6".to_string())] };
+    let mut session = GameSession::new(Player::Human("Ann".to_string()), engine);
+    session.event = "Club".to_string();
+    let clock = Clock { remaining: Duration::from_secs(60), increment: Duration::from_secs(1) };
+    session.clocks = Some([clock, clock]);
+    session
+}
+
+#[test]
+fn sessions_charge_clocks() {
+    let mut session = session();
+    session.play((5, 3), Duration::from_secs(10)).unwrap();
+    assert_eq!(session.color(), Color::White);
+    assert_eq!(session.clock(Color::Black).unwrap().remaining, Duration::from_secs(51));
+    assert!(session.play((0, 0), Duration::from_secs(1)).is_err());
+    session.play((3, 2), Duration::from_secs(2)).unwrap();
+    assert_eq!(session.last_move().map(|x| (x.position, x.flipped())), Some(((3, 2), vec![(3, 3)])));
+    let turn = session.undo().unwrap();
+    assert_eq!(session.last_move().map(|x| x.position), Some((5, 3)));
+    assert_eq!((turn.color, turn.position), (Color::White, (3, 2)));
+    assert_eq!(session.color(), Color::White);
+    assert_eq!(session.clock(Color::White).unwrap().remaining, Duration::from_secs(60));
+    let record = session.record();
+    assert_eq!((record.black.as_str(), record.white.as_str()), ("Ann", "alphabeta"));
+    assert_eq!(record.moves, vec![(5, 3)]);
+    assert_eq!(record.black_disks, None);
+}
+
+#[test]
+fn sessions_save_and_resume() {
+    let mut session = session();
+    session.play((5, 3), Duration::from_millis(1500)).unwrap();
+    session.play((3, 2), Duration::from_millis(250)).unwrap();
+    let mut bytes = Vec::new();
+    session.write(&mut bytes).unwrap();
+    assert_eq!(&bytes[..6], b"RRSESS");
+    assert_eq!(GameSession::read(&bytes[..]).unwrap(), session);
+    assert!(GameSession::read(&bytes[..bytes.len() - 1]).is_err());
+
+    let path = env::temp_dir().join("rusty_reversi_session_test.rrs");
+    session.save(&path).unwrap();
+    assert_eq!(GameSession::load(&path).unwrap(), session);
+    fs::remove_file(&path).unwrap();
+}
+
+// A writer whose output the test can read.
+#[derive(Clone, Default)]
+struct Shared(Arc<Mutex<Vec<u8>>>);
+impl Write for Shared {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(bytes)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn sessions_log_events() {
+    let output = Shared::default();
+    let mut session = session();
+    session.set_log(EventLog::new(output.clone(), "g1"));
+    for position in record::parse_transcript("e6f4e3f6g5d6e7f5c5").unwrap() {
+        session.play(position, Duration::from_millis(1500)).unwrap();
+    }
+    session.undo().unwrap();
+    session.log().unwrap().check().unwrap();
+    let text = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+    let lines = text.lines().map(|x| Value::parse(x).unwrap()).collect::<Vec<_>>();
+    let events = lines.iter().map(|x| x.get("event").and_then(|x| x.as_str()).unwrap()).collect::<Vec<_>>();
+    assert_eq!(events.len(), 12);
+    assert_eq!((events[0], events[1], events[10], events[11]), ("started", "moved", "ended", "undone"));
+    assert!(lines.iter().all(|x| x.get("game").and_then(|x| x.as_str()) == Some("g1") && x.get("time").is_some()));
+    assert_eq!(lines[0].get("white").and_then(|x| x.as_str()), Some("alphabeta"));
+    assert_eq!(lines[1].get("cell").and_then(|x| x.as_str()), Some("e6"));
+    assert_eq!(lines[1].get("time_ms").and_then(|x| x.as_f64()), Some(1500.0));
+    assert_eq!(lines[10].get("black_disks").and_then(|x| x.as_f64()), Some(13.0));
+}
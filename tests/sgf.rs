@@ -0,0 +1,53 @@
+#![cfg(feature = "formats")]
+
+extern crate rusty_reversi;
+
+use rusty_reversi::formats::sgf;
+use rusty_reversi::game::{Board, Color};
+use rusty_reversi::random::Random;
+use rusty_reversi::record::{self, GameRecord};
+
+#[test]
+fn sgf_writes_games() {
+    let game = GameRecord {
+        black: "Black".to_string(),
+        white: "W]hite".to_string(),
+        moves: record::parse_transcript("f5d6").unwrap(),
+        black_disks: Some(24),
+        ..GameRecord::default()
+    };
+    assert_eq!(sgf::format(&game).unwrap(), "(;FF[4]GM[2]SZ[8]PB[Black]PW[W\\]hite]RE[W+16];B[fe];W[df])");
+    let illegal = GameRecord { moves: record::parse_transcript("a1").unwrap(), ..game };
+    assert!(sgf::format(&illegal).is_err());
+}
+
+// Plays random moves until the end of the game.
+fn random_game(seed: u64) -> GameRecord {
+    let mut random = Random::new(seed);
+    let (mut board, mut color) = (Board::new(), Color::Black);
+    let mut game = GameRecord::default();
+    loop {
+        let moves = board.legal_moves(color);
+        if !moves.is_empty() {
+            let legal_move = moves[random.below(moves.len())];
+            game.moves.push(legal_move.position);
+            board = legal_move.apply(board);
+        } else if board.legal_moves(color.flip()).is_empty() {
+            return game;
+        }
+        color = color.flip();
+    }
+}
+
+#[test]
+fn sgf_writes_skips() {
+    let mut found = false;
+    for seed in 0..20 {
+        let game = random_game(seed);
+        let positions = game.positions().unwrap();
+        let skips = positions.windows(2).filter(|pair| pair[0].1 == pair[1].1).count();
+        assert_eq!(sgf::format(&game).unwrap().matches("[]").count(), skips);
+        found |= skips > 0;
+    }
+    assert!(found);
+}
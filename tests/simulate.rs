@@ -0,0 +1,71 @@
+#![cfg(feature = "ai")]
+
+extern crate rusty_reversi;
+
+use std::io;
+
+use rusty_reversi::eval::WeightedSquares;
+use rusty_reversi::formats::training;
+use rusty_reversi::record::GameRecord;
+use rusty_reversi::search::AlphaBeta;
+use rusty_reversi::simulate::{self, BatchConfig, Samples, Sink};
+
+// Keeps the games with their index.
+#[derive(Default)]
+struct Games(Vec<(u32, GameRecord)>);
+impl Sink for Games {
+    fn game(&mut self, index: u32, record: &GameRecord) -> io::Result<()> {
+        self.0.push((index, record.clone()));
+        Ok(())
+    }
+}
+
+// Fails on the first game.
+struct Broken;
+impl Sink for Broken {
+    fn game(&mut self, _: u32, _: &GameRecord) -> io::Result<()> {
+        Err(io::Error::other("disk full"))
+    }
+}
+
+fn config(threads: usize) -> BatchConfig {
+    BatchConfig { games: 6, threads, ..BatchConfig::default() }
+}
+
+fn engine() -> AlphaBeta<WeightedSquares> {
+    AlphaBeta::new(1, WeightedSquares::default())
+}
+
+#[test]
+fn games_do_not_depend_on_threads() {
+    let mut games = [Games::default(), Games::default()];
+    for (threads, games) in [1, 3].iter().zip(&mut games) {
+        let summary = simulate::run(&config(*threads), engine, engine, &mut [games]).unwrap();
+        assert_eq!(summary.result.games(), 6);
+        assert_eq!(summary.black.games(), 6);
+        games.0.sort_by_key(|x| x.0);
+    }
+    assert_eq!(games[0].0, games[1].0);
+    let records = &games[0].0;
+    assert_eq!((records[0].1.black.as_str(), records[1].1.black.as_str()), ("first", "second"));
+    assert_eq!(records[0].1.moves[..6], records[1].1.moves[..6]);
+    assert_ne!(records[0].1.moves[..6], records[2].1.moves[..6]);
+}
+
+#[test]
+fn sinks_receive_every_game() {
+    let mut records = Vec::new();
+    let mut bytes = Vec::new();
+    let summary = simulate::run(&config(2), engine, engine, &mut [&mut records, &mut Samples::new(&mut bytes)]).unwrap();
+    assert_eq!(records.len(), 6);
+    let moves = records.iter().map(|x| x.moves.len()).sum::<usize>();
+    assert_eq!(summary.average_moves, moves as f64 / 6.0);
+    assert!(summary.format().starts_with("6 games in "));
+    assert_eq!(training::read(&bytes[..]).unwrap().len(), moves);
+}
+
+#[test]
+fn sink_errors_stop_the_batch() {
+    let error = simulate::run(&config(2), engine, engine, &mut [&mut Broken]).unwrap_err();
+    assert_eq!(error.to_string(), "disk full");
+}
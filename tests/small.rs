@@ -0,0 +1,77 @@
+extern crate rusty_reversi;
+
+use rusty_reversi::game::{Board, Color};
+use rusty_reversi::random::Random;
+use rusty_reversi::small::{self, SmallBoard};
+
+// A position reached by random moves, with the player to move, once at most `empties` cells are
+// empty or the game is over.
+fn random_position<const N: usize>(empties: usize, random: &mut Random) -> (SmallBoard<N>, Color) {
+    let (mut board, mut color) = (SmallBoard::<N>::new(), Color::Black);
+    while board.empties() > empties && !board.is_over() {
+        let moves = board.legal_moves(color);
+        if !moves.is_empty() {
+            board = board.play(color, moves[random.below(moves.len())]).unwrap();
+        }
+        color = color.flip();
+    }
+    (board, color)
+}
+
+#[test]
+fn four_by_four_is_won_by_white() {
+    let board = SmallBoard::<4>::new();
+    let exhaustive = small::solve_exhaustive(&board, Color::Black);
+    let pruned = small::solve(&board, Color::Black);
+    assert_eq!(Some(exhaustive.score), small::known_value(4));
+    assert_eq!(pruned.score, exhaustive.score);
+    assert!(pruned.nodes < exhaustive.nodes);
+    // the best move found is played along a line keeping the value
+    let next = board.play(Color::Black, pruned.best_move.unwrap()).unwrap();
+    assert_eq!(small::solve(&next, Color::White).score, -exhaustive.score);
+}
+
+#[test]
+fn pruning_keeps_the_values_of_six_by_six_positions() {
+    let mut random = Random::new(7);
+    for _ in 0..20 {
+        let (board, color) = random_position::<6>(10, &mut random);
+        let (exhaustive, pruned) = (small::solve_exhaustive(&board, color), small::solve(&board, color));
+        assert_eq!(pruned.score, exhaustive.score);
+        if let Some(position) = pruned.best_move {
+            assert_eq!(-small::solve_exhaustive(&board.play(color, position).unwrap(), color.flip()).score, exhaustive.score);
+        }
+    }
+    assert_eq!(small::known_value(8), None);
+}
+
+#[test]
+fn eight_by_eight_boards_follow_the_same_rules() {
+    let mut random = Random::new(11);
+    for _ in 0..10 {
+        let (mut board, mut color) = (Board::new(), Color::Black);
+        assert_eq!(SmallBoard::from_board(&board), SmallBoard::<8>::new());
+        loop {
+            let small = SmallBoard::from_board(&board);
+            let moves = board.legal_moves(color);
+            assert_eq!(small.legal_moves(color), moves.iter().map(|x| x.position).collect::<Vec<_>>());
+            for legal_move in &moves {
+                let mut flipped = legal_move.flipped();
+                flipped.sort();
+                let mut found = small.flipped(color, legal_move.position);
+                found.sort();
+                assert_eq!(found, flipped);
+                assert_eq!(small.play(color, legal_move.position), Some(SmallBoard::from_board(&legal_move.apply(board))));
+            }
+            if moves.is_empty() {
+                if small.is_over() {
+                    break;
+                }
+                color = color.flip();
+                continue;
+            }
+            board = moves[random.below(moves.len())].apply(board);
+            color = color.flip();
+        }
+    }
+}
@@ -0,0 +1,22 @@
+#![cfg(feature = "ui")]
+
+extern crate rusty_reversi;
+
+use rusty_reversi::game::{Board, Color};
+use rusty_reversi::speech;
+
+#[test]
+fn moves_are_described() {
+    let board = Board::new();
+    let legal_move = board.legal_moves(Color::White).into_iter().find(|x| x.position == (3, 2)).unwrap();
+    let board = legal_move.apply(board);
+    assert_eq!(speech::describe_move(&board, &legal_move), "White plays d6, flipping d5; score 1–4");
+}
+
+#[test]
+fn boards_are_described() {
+    assert_eq!(speech::describe_board(&Board::new(), Some(Color::Black)), "Black has 2 disks: e4 and d5.\n\
+        White has 2 disks: d4 and e5.\n\
+        Black can play d3, c4, f5 or e6.\n");
+    assert_eq!(speech::describe_result(&Board::new()), "Game over: draw 2–2");
+}
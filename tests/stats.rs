@@ -0,0 +1,35 @@
+extern crate rusty_reversi;
+
+use rusty_reversi::stats;
+
+use stats::{Decision, MatchResult, Sprt};
+
+#[test]
+fn even_results_mean_equal_strength() {
+    let result = MatchResult { wins: 10, draws: 5, losses: 10 };
+    assert_eq!(result.score(), 0.5);
+    assert!(result.elo().abs() < 1e-9);
+    assert!((result.los() - 0.5).abs() < 1e-9);
+    assert!(result.elo_error() > 0.0);
+}
+
+#[test]
+fn winning_more_means_positive_elo() {
+    let result = MatchResult { wins: 60, draws: 0, losses: 40 };
+    assert!((result.elo() - 70.4).abs() < 0.1);
+    assert!(result.los() > 0.95);
+    assert!((result.reversed().elo() + result.elo()).abs() < 1e-9);
+}
+
+#[test]
+fn sprt_decides_clear_results() {
+    let sprt = Sprt::default();
+    let (lower, upper) = sprt.bounds();
+    assert!((upper - 2.944).abs() < 1e-3 && (lower + 2.944).abs() < 1e-3);
+    assert_eq!(sprt.decide(&MatchResult { wins: 10, draws: 0, losses: 10 }), Decision::Continue);
+    assert_eq!(sprt.decide(&MatchResult { wins: 2000, draws: 0, losses: 1500 }), Decision::AcceptH1);
+    assert_eq!(sprt.decide(&MatchResult { wins: 1500, draws: 0, losses: 2000 }), Decision::AcceptH0);
+    // even when the variance of the results is still zero
+    assert_eq!(sprt.decide(&MatchResult { wins: 2, draws: 0, losses: 0 }), Decision::Continue);
+    assert_eq!(sprt.decide(&MatchResult { wins: 1000, draws: 0, losses: 0 }), Decision::AcceptH1);
+}
@@ -0,0 +1,37 @@
+#![cfg(feature = "ui")]
+
+extern crate rusty_reversi;
+
+use rusty_reversi::game::{Board, Charset};
+use rusty_reversi::terminal::Theme;
+
+#[test]
+fn themes_are_parsed() {
+    assert_eq!("classic".parse(), Ok(Theme::Classic));
+    assert_eq!("high-contrast".parse(), Ok(Theme::HighContrast));
+    assert_eq!("monochrome".parse(), Ok(Theme::Monochrome));
+    assert_eq!("green".parse::<Theme>(), Err(()));
+}
+
+#[test]
+fn boards_are_painted() {
+    for &charset in [Charset::Ascii, Charset::Unicode].iter() {
+        let text = Board::new().draw(charset);
+        assert_eq!(Theme::Monochrome.paint(&text), text);
+        let painted = Theme::Classic.paint(&text);
+        let lines = painted.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), text.lines().count());
+        assert_eq!(lines[0], text.lines().next().unwrap());
+        let row = lines.iter().find(|x| x.starts_with('4')).unwrap();
+        assert!(row.starts_with("4\u{1b}[48;5;28;38;5;22m"));
+        assert!(row.ends_with("\u{1b}[0m"));
+        let plain = row.split('\u{1b}').map(|x| x.split_once('m').map_or(x, |(_, x)| x)).collect::<String>();
+        assert_eq!(plain, text.lines().find(|x| x.starts_with('4')).unwrap());
+    }
+}
+
+#[test]
+fn cells_are_painted() {
+    assert_eq!(Theme::HighContrast.paint_cells(" X "), "\u{1b}[107;30m \u{1b}[30;1mX\u{1b}[22;30m \u{1b}[0m");
+    assert_eq!(Theme::Monochrome.paint_cells(" X "), " X ");
+}
@@ -0,0 +1,43 @@
+#![cfg(feature = "testing")]
+
+extern crate proptest;
+extern crate rusty_reversi;
+
+use proptest::prelude::*;
+
+use rusty_reversi::game::Color;
+use rusty_reversi::record;
+use rusty_reversi::testing;
+
+proptest! {
+    #[test]
+    fn boards_are_written_and_read(board in testing::boards()) {
+        let text = record::format_position(&board, Color::White);
+        prop_assert_eq!(record::parse_position(&text), Some((board, Color::White)));
+    }
+
+    #[test]
+    fn moves_are_legal(moves in testing::moves(70)) {
+        prop_assert!(moves.len() <= 60);
+        let game = record::GameRecord { moves, ..record::GameRecord::default() };
+        prop_assert!(game.positions().is_ok());
+    }
+
+    #[test]
+    fn finished_games_have_their_disks(game in testing::games(60)) {
+        let positions = game.positions().unwrap();
+        let &(board, color) = positions.last().unwrap();
+        let over = board.legal_moves(color).is_empty();
+        prop_assert_eq!(game.black_disks.is_some(), over);
+        if over {
+            prop_assert_eq!(game.black_disks, Some(board.count(Color::Black) as u8));
+        }
+        prop_assert!(!game.black.is_empty() && !game.white.is_empty());
+    }
+
+    #[test]
+    fn positions_are_reached((board, color) in testing::positions(60)) {
+        prop_assert!(board.empties() <= 60);
+        prop_assert!(!board.legal_moves(color).is_empty() || board.legal_moves(color.flip()).is_empty());
+    }
+}
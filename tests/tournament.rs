@@ -0,0 +1,93 @@
+#![cfg(feature = "ai")]
+
+extern crate rusty_reversi;
+
+use std::io::ErrorKind;
+
+use rusty_reversi::arena::Options;
+use rusty_reversi::engine::Limits;
+use rusty_reversi::eval::Heuristic;
+use rusty_reversi::search::AlphaBeta;
+use rusty_reversi::tournament::{Game, Pairing, Tournament};
+
+fn players(count: usize) -> Vec<String> {
+    (0..count).map(|x| x.to_string()).collect()
+}
+
+#[test]
+fn round_robins_pair_everyone_once() {
+    for count in 2..8 {
+        let mut tournament = Tournament::new(players(count), Pairing::RoundRobin, 2);
+        let mut met = Vec::new();
+        while !tournament.is_over() {
+            let pairings = tournament.pairings();
+            let mut seen = pairings.iter().flat_map(|&(a, b)| Some(a).into_iter().chain(b)).collect::<Vec<_>>();
+            seen.sort();
+            assert_eq!(seen, (0..count).collect::<Vec<_>>());
+            for &(a, b) in &pairings {
+                match b {
+                    Some(b) => met.push((a.min(b), a.max(b))),
+                    None => tournament.byes.push((tournament.completed, a))
+                }
+            }
+            tournament.completed += 1;
+        }
+        met.sort();
+        met.dedup();
+        assert_eq!(met.len(), count * (count - 1) / 2);
+    }
+}
+
+#[test]
+fn standings_break_ties_by_disks() {
+    let mut tournament = Tournament::new(players(3), Pairing::Swiss, 2);
+    tournament.results = vec![
+        Game { round: 0, black: 0, white: 1, difference: 10 },
+        Game { round: 0, black: 1, white: 0, difference: 2 }
+    ];
+    tournament.byes = vec![(0, 2)];
+    tournament.completed = 1;
+    let standings = tournament.standings();
+    assert_eq!(standings.iter().map(|x| x.player).collect::<Vec<_>>(), vec![2, 0, 1]);
+    assert_eq!((standings[0].points, standings[1].points, standings[1].disks), (2.0, 1.0, 8));
+    // no rematch, and the bye goes to a player who hasn't had one
+    let pairings = tournament.pairings();
+    assert_eq!(pairings, vec![(2, Some(0)), (1, None)]);
+    let mut bytes = Vec::new();
+    tournament.write(&mut bytes).unwrap();
+    assert_eq!(Tournament::read(&bytes[..]).unwrap(), tournament);
+    assert!(Tournament::read(&bytes[..10]).is_err());
+}
+
+#[test]
+fn tournaments_are_played_and_resumed() {
+    let mut tournament = Tournament::new(players(3), Pairing::RoundRobin, 2);
+    let mut engines = (1..4).map(|depth| AlphaBeta::new(depth, Heuristic::default())).collect::<Vec<_>>();
+    let options = Options { limits: Limits::default(), opening_plies: 2, ..Options::default() };
+    let records = tournament.play_round(&mut engines, &options);
+    assert_eq!((records.len(), tournament.results.len(), tournament.byes.len()), (2, 2, 1));
+    let mut bytes = Vec::new();
+    tournament.write(&mut bytes).unwrap();
+    let mut resumed = Tournament::read(&bytes[..]).unwrap();
+    while !tournament.is_over() {
+        tournament.play_round(&mut engines, &options);
+        resumed.play_round(&mut engines, &options);
+    }
+    assert_eq!(resumed, tournament);
+    let standings = tournament.standings();
+    assert_eq!(standings.iter().map(|x| x.wins + x.draws + x.losses).sum::<u32>(), 12);
+    assert_eq!(standings.iter().map(|x| x.byes).sum::<u32>(), 3);
+    assert_eq!(tournament.format_standings().lines().count(), 4);
+}
+
+#[test]
+fn tournaments_too_large_to_save_are_refused() {
+    let mut bytes = Vec::new();
+    let tournament = Tournament::new(players(70_000), Pairing::Swiss, 2);
+    assert_eq!(tournament.write(&mut bytes).unwrap_err().kind(), ErrorKind::InvalidInput);
+    let tournament = Tournament::new(players(2), Pairing::RoundRobin, 70_000);
+    assert_eq!(tournament.write(&mut bytes).unwrap_err().kind(), ErrorKind::InvalidInput);
+    let mut tournament = Tournament::new(players(2), Pairing::RoundRobin, 2);
+    tournament.players[1] = "x".repeat(70_000);
+    assert_eq!(tournament.write(&mut bytes).unwrap_err().kind(), ErrorKind::InvalidInput);
+}
@@ -0,0 +1,66 @@
+#![cfg(all(feature = "trace", feature = "ai"))]
+
+extern crate rusty_reversi;
+extern crate tracing;
+extern crate tracing_subscriber;
+
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rusty_reversi::engine::{Engine, Limits};
+use rusty_reversi::eval::Heuristic;
+use rusty_reversi::game::{Board, Color};
+use rusty_reversi::protocol::gtp::Gtp;
+use rusty_reversi::search::AlphaBeta;
+
+// Collects the events written by the subscriber.
+#[derive(Clone, Default)]
+struct Output(Arc<Mutex<Vec<u8>>>);
+impl Write for Output {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn traced<F: FnOnce()>(f: F) -> String {
+    let output = Output::default();
+    let writer = output.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::DEBUG)
+        .with_ansi(false)
+        .with_writer(move || writer.clone())
+        .finish();
+    tracing::subscriber::with_default(subscriber, f);
+    let bytes = output.0.lock().unwrap().clone();
+    String::from_utf8(bytes).unwrap()
+}
+
+#[test]
+fn search_logs_its_iterations() {
+    let mut engine = AlphaBeta::new(3, Heuristic::default());
+    let limits = Limits { time: Some(Duration::from_secs(60)), ..Limits::default() };
+    let log = traced(|| {
+        engine.best_move(&Board::new(), Color::Black, &limits);
+    });
+    assert!(log.contains("alpha_beta{color=Black"));
+    assert_eq!(log.matches("iteration finished").count(), 3);
+    assert!(log.contains("depth=3"));
+    assert!(!log.contains("time limit exceeded"));
+}
+
+#[test]
+fn protocol_logs_commands_and_failures() {
+    let mut gtp = Gtp::new(AlphaBeta::new(1, Heuristic::default()));
+    let log = traced(|| {
+        gtp.run(&b"1 name\n2 fly\n"[..], io::sink()).unwrap();
+    });
+    assert!(log.contains("received id=\"1\" command=\"name\""));
+    assert!(log.contains("WARN"));
+    assert!(log.contains("command failed id=\"2\" command=\"fly\""));
+}
@@ -0,0 +1,49 @@
+#![cfg(feature = "ai")]
+
+extern crate rusty_reversi;
+
+use rusty_reversi::arena::{self, Options};
+use rusty_reversi::eval::WeightedSquares;
+use rusty_reversi::formats::training;
+use rusty_reversi::game::Color;
+use rusty_reversi::record::{self, GameRecord};
+use rusty_reversi::search::AlphaBeta;
+
+#[test]
+fn self_play_games_give_samples() {
+    let options = Options { games: 2, ..Options::default() };
+    let games = arena::self_play(&options, &mut AlphaBeta::new(1, WeightedSquares::default()));
+    assert_eq!(games.len(), 2);
+    for game in &games {
+        let samples = training::samples(game).unwrap();
+        assert_eq!(samples.len(), game.moves.len());
+        let (last, _) = *game.positions().unwrap().last().unwrap();
+        let black_diff = last.count(Color::Black) as i8 - last.count(Color::White) as i8;
+        assert_eq!(game.black_disks, Some(last.count(Color::Black) as u8));
+        for sample in &samples {
+            let diff = if sample.color == Color::Black { black_diff } else { -black_diff };
+            assert_eq!(sample.diff, diff);
+        }
+        assert_eq!(samples[0].best_move, game.moves[0]);
+    }
+}
+
+#[test]
+fn samples_survive_a_round_trip() {
+    // the shortest game, all disks black
+    let game = GameRecord { moves: record::parse_transcript("e6f4e3f6g5d6e7f5c5").unwrap(), ..GameRecord::default() };
+    let samples = training::samples(&game).unwrap();
+    assert_eq!(samples.len(), 9);
+    assert_eq!(samples[0].diff, 13);
+    assert_eq!(samples[1].diff, -13);
+    let mut bytes = Vec::new();
+    training::write(&mut bytes, &samples).unwrap();
+    assert_eq!(bytes.len(), 12 + 20 * 9);
+    assert_eq!(&bytes[..6], b"RRDATA");
+    // the first outcome is a win for Black, the second a loss for White
+    assert_eq!((bytes[12 + 19], bytes[32 + 19]), (2, 0));
+    assert_eq!(training::read(&bytes[..]).unwrap(), samples);
+    assert!(training::read(&bytes[..40]).is_err());
+    let unfinished = GameRecord { moves: record::parse_transcript("f5d6").unwrap(), ..GameRecord::default() };
+    assert_eq!(training::samples(&unfinished), Ok(Vec::new()));
+}
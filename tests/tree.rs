@@ -0,0 +1,44 @@
+#![cfg(feature = "ai")]
+
+extern crate rusty_reversi;
+
+use rusty_reversi::eval::Heuristic;
+use rusty_reversi::game::{Board, Color};
+use rusty_reversi::search::{alpha_beta, alpha_beta_with_options, Options};
+use rusty_reversi::tree::{Outcome, SearchTree};
+
+fn search(depth: u32, limit: usize) -> (SearchTree, i32, u64) {
+    let mut tree = SearchTree::new(limit);
+    let result = alpha_beta_with_options(&Board::new(), Color::Black, depth, &Heuristic::default(), &mut Options { tree: Some(&mut tree), ..Options::default() });
+    let expected = alpha_beta(&Board::new(), Color::Black, depth, &Heuristic::default());
+    assert_eq!((result.score, result.pv, result.nodes), (expected.score, expected.pv, expected.nodes));
+    (tree, result.score, result.nodes)
+}
+
+#[test]
+fn trees_hold_the_nodes_searched() {
+    let (tree, score, nodes) = search(4, 100_000);
+    assert!(!tree.is_truncated());
+    assert_eq!(tree.nodes().len() as u64, nodes);
+    let root = &tree.nodes()[0];
+    assert_eq!((root.parent, root.position, root.depth), (None, None, 4));
+    assert_eq!(root.result, Some((score, Outcome::Searched)));
+    assert!(tree.nodes().iter().all(|x| x.result.is_some() && x.parent.map(|y| tree.nodes()[y].depth == x.depth + 1).unwrap_or(true)));
+    let cutoffs = tree.nodes().iter().filter(|x| matches!(x.result, Some((_, Outcome::Cutoff(_))))).collect::<Vec<_>>();
+    assert!(!cutoffs.is_empty());
+    assert!(cutoffs.iter().all(|x| x.result.unwrap().0 >= x.beta));
+    assert!(tree.nodes().iter().filter(|x| x.depth == 0).all(|x| x.result.unwrap().1 == Outcome::Leaf));
+}
+
+#[test]
+fn trees_are_written_as_dot() {
+    let (tree, _, _) = search(3, 20);
+    assert!(tree.is_truncated());
+    assert_eq!(tree.nodes().len(), 20);
+    let dot = tree.to_dot();
+    assert!(dot.starts_with("digraph search {"));
+    assert!(dot.contains("n0 [label=\"root Black depth 3\\n[-inf, inf]"));
+    assert!(dot.contains("label=\"truncated at 20 nodes\";"));
+    assert!(dot.contains("cutoff, "));
+    assert_eq!(dot.matches(" -> ").count(), 19);
+}
@@ -0,0 +1,43 @@
+#![cfg(feature = "ai")]
+
+extern crate rusty_reversi;
+
+use rusty_reversi::tt;
+
+use tt::{Bound, Replacement, TranspositionTable, BUCKET_SIZE};
+
+#[test]
+fn depth_preferred_keeps_deep_entries() {
+    let mut table = TranspositionTable::new(0, Replacement::DepthPreferred);
+    assert_eq!(table.capacity(), BUCKET_SIZE);
+    for key in 0..BUCKET_SIZE as u64 {
+        table.store(key, Some((0, 0)), 1, 10, Bound::Exact);
+    }
+    // the shallow entry takes the always replaced slot
+    table.store(100, None, 2, 1, Bound::Lower);
+    assert_eq!(table.probe(100).map(|x| (x.score, x.bound)), Some((2, Bound::Lower)));
+    assert_eq!((0..BUCKET_SIZE as u64).filter(|&x| table.probe(x).is_some()).count(), BUCKET_SIZE - 1);
+    assert_eq!(table.hashfull(), 1000);
+    // a shallower bound doesn't overwrite a deeper result
+    table.store(0, None, 5, 3, Bound::Upper);
+    assert_eq!(table.probe(0).map(|x| x.depth), Some(10));
+    // stale entries go first, whatever their depth
+    table.new_search();
+    assert_eq!(table.hashfull(), 0);
+    table.store(200, None, 3, 1, Bound::Exact);
+    assert_eq!(table.probe(200).map(|x| x.depth), Some(1));
+    assert_eq!(table.hashfull(), 250);
+}
+
+#[test]
+fn always_replace_stores_every_entry() {
+    let mut table = TranspositionTable::new(0, Replacement::AlwaysReplace);
+    for key in 0..BUCKET_SIZE as u64 {
+        table.store(key, None, 0, 10, Bound::Exact);
+    }
+    table.store(100, None, 0, 1, Bound::Exact);
+    table.store(100, None, 7, 0, Bound::Upper);
+    assert_eq!(table.probe(100).map(|x| (x.score, x.depth)), Some((7, 0)));
+    table.clear();
+    assert_eq!(table.probe(100), None);
+}
@@ -0,0 +1,95 @@
+#![cfg(feature = "tui")]
+
+extern crate crossterm;
+extern crate rusty_reversi;
+
+use crossterm::event::KeyCode;
+
+use rusty_reversi::eval::Heuristic;
+use rusty_reversi::game::Color;
+use rusty_reversi::search::AlphaBeta;
+use rusty_reversi::session::{GameSession, Player};
+use rusty_reversi::tui::{self, Action, App, Keymap};
+
+fn app(human: Color) -> App<AlphaBeta<Heuristic>> {
+    let session = GameSession::new(Player::Human("Ann".to_string()), Player::Human("Bob".to_string()));
+    App::new(AlphaBeta::new(2, Heuristic::default()), session, human)
+}
+
+#[test]
+fn cursor_moves_and_places() {
+    let mut app = app(Color::Black);
+    assert_eq!(app.cursor(), (3, 5));
+    app.handle(Action::Place);
+    assert_eq!(app.session().history()[0].position, (3, 5));
+    assert!(app.is_engine_turn());
+    assert!(app.engine_turn());
+    assert!(app.evaluation().is_some());
+    assert_eq!(app.session().color(), Color::Black);
+    app.handle(Action::Left);
+    app.handle(Action::Left);
+    app.handle(Action::Left);
+    app.handle(Action::Left);
+    app.handle(Action::Up);
+    assert_eq!(app.cursor(), (0, 6));
+    app.handle(Action::Place);
+    assert_eq!(app.session().history().len(), 2);
+    assert!(app.message().contains("a2"));
+    app.handle(Action::Undo);
+    assert!(app.session().history().is_empty());
+    app.handle(Action::Quit);
+    assert!(app.is_done());
+}
+
+#[test]
+fn screen_shows_board_and_panel() {
+    let app = app(Color::White);
+    let screen = app.screen();
+    assert_eq!(screen[0].trim(), "a  b  c  d  e  f  g  h");
+    assert!(screen[4].starts_with("4  .  .  .  O  X  .  .  . "));
+    assert!(screen[1].contains("Black (engine)  2"));
+    assert!(screen[2].contains("White (you)     2"));
+    assert_eq!(tui::screen_position((0, 7)), (2, 1));
+    // the engine moves first, so there are no hints yet
+    assert!(!screen.iter().any(|x| x.contains('+')));
+}
+
+#[test]
+fn mouse_previews_and_places() {
+    let mut app = app(Color::Black);
+    assert_eq!(tui::screen_cell(11, 3), Some((3, 5)));
+    assert_eq!(tui::screen_cell(1, 3), None);
+    assert_eq!(tui::screen_cell(26, 3), None);
+    app.handle(Action::Hover(Some((3, 5))));
+    assert_eq!(app.preview(), vec![(3, 4)]);
+    assert!(app.screen().iter().any(|x| x.contains("d3 flips 1")));
+    app.handle(Action::Hover(Some((0, 0))));
+    assert!(app.preview().is_empty());
+    app.handle(Action::Click((4, 2)));
+    assert_eq!(app.cursor(), (4, 2));
+    assert_eq!(app.session().history()[0].position, (4, 2));
+}
+
+#[test]
+fn hints_can_be_hidden() {
+    let mut app = app(Color::Black);
+    assert_eq!(app.cell_text((3, 5)), " + ");
+    app.hints = false;
+    assert_eq!(app.cell_text((3, 5)), " . ");
+}
+
+#[test]
+fn keys_can_be_rebound() {
+    let mut keys = Keymap::default();
+    assert_eq!(keys.action(KeyCode::Char('u')), Some(Action::Undo));
+    keys.bind("undo", "backspace").unwrap();
+    keys.bind("up", "k").unwrap();
+    keys.bind("place", "q").unwrap();
+    assert_eq!(keys.action(KeyCode::Backspace), Some(Action::Undo));
+    assert_eq!(keys.action(KeyCode::Char('u')), Some(Action::Undo));
+    assert_eq!(keys.action(KeyCode::Char('k')), Some(Action::Up));
+    assert_eq!(keys.action(KeyCode::Char('q')), Some(Action::Place));
+    assert_eq!(keys.action(KeyCode::Esc), Some(Action::Quit));
+    assert!(keys.bind("jump", "j").is_err());
+    assert!(keys.bind("up", "pageup").is_err());
+}
@@ -0,0 +1,59 @@
+#![cfg(feature = "ai")]
+
+extern crate rusty_reversi;
+
+use rusty_reversi::eval;
+use rusty_reversi::game;
+use rusty_reversi::search;
+use rusty_reversi::stats;
+use rusty_reversi::tune;
+
+use eval::{Heuristic, WeightedSquares};
+use game::{Board, Color};
+use search::alpha_beta;
+use stats::MatchResult;
+use tune::{error, label_game, spsa, tune, Options, Parameter, SpsaOptions};
+
+#[test]
+fn tuning_reduces_error() {
+    // a greedy player against a shallow searcher gives games with a clear winner
+    let mut positions = Vec::new();
+    let mut board = Board::new();
+    let mut color = Color::Black;
+    loop {
+        let moves = board.legal_moves(color);
+        if moves.is_empty() {
+            if board.legal_moves(color.flip()).is_empty() {
+                break;
+            }
+        } else {
+            positions.push((board, color));
+            let depth = if color == Color::Black { 3 } else { 1 };
+            let best = alpha_beta(&board, color, depth, &WeightedSquares::default()).best;
+            board = moves.into_iter().find(|x| Some(x.position) == best).unwrap().apply(board);
+        }
+        color = color.flip();
+    }
+    let samples = label_game(&positions, &board);
+    let options = Options { iterations: 50, ..Options::default() };
+    let heuristic = Heuristic::default();
+    let tuned = tune(&heuristic, &samples, &options);
+    assert!(error(&tuned, &samples, options.scale) <= error(&heuristic, &samples, options.scale));
+}
+
+#[test]
+fn spsa_moves_towards_the_stronger_value() {
+    let parameters = vec![Parameter { name: "x".to_string(), value: 0.0, min: -10.0, max: 10.0, step: 1.0 }];
+    let options = SpsaOptions { iterations: 200, ..SpsaOptions::default() };
+    // the closer to 3 the stronger
+    let report = spsa(&parameters, &options, |plus, minus, games| {
+        let better = (plus[0] - 3.0).abs() < (minus[0] - 3.0).abs();
+        if better {
+            MatchResult { wins: games, draws: 0, losses: 0 }
+        } else {
+            MatchResult { wins: 0, draws: 0, losses: games }
+        }
+    });
+    assert!((report.parameters[0].value - 3.0).abs() < 1.0);
+    assert!(report.verification.los() > 0.99);
+}
@@ -0,0 +1,98 @@
+extern crate rusty_reversi;
+
+use rusty_reversi::game::{Board, Color};
+use rusty_reversi::record::{self, GameRecord, Termination};
+use rusty_reversi::verify::{golden_games, parse_golden, transcript, verify_golden, verify_record, VerifyError};
+
+#[test]
+fn reference_games_are_replayed() {
+    let games = golden_games();
+    assert_eq!(games.len(), 5);
+    for game in &games {
+        assert_eq!(verify_golden(game), Ok(()), "{}", game.name);
+        let &(board, color) = game.positions.last().unwrap();
+        assert!(board.legal_moves(color).is_empty() && board.legal_moves(color.flip()).is_empty(), "{}", game.name);
+    }
+    assert!(games.iter().any(|x| x.positions.iter().zip(&x.positions[1..]).any(|(a, b)| a.1 == b.1)));
+    assert!(games.iter().any(|x| x.positions.last().unwrap().0.empties() > 0 && x.record.black_disks != Some(0)));
+}
+
+#[test]
+fn differences_from_the_reference_are_found() {
+    let mut game = golden_games().remove(0);
+    assert_eq!(game.name, "wipeout");
+    let expected = game.positions[3];
+    game.positions[3].1 = expected.1.flip();
+    assert_eq!(verify_golden(&game), Err(VerifyError::Position { ply: 3, expected: Box::new(game.positions[3]), found: Box::new(expected) }));
+    game.positions.truncate(4);
+    assert_eq!(verify_golden(&game), Err(VerifyError::Length { expected: 4, found: 9 }));
+    assert_eq!(parse_golden("wipeout e6f4 2-4\nX\n"), Err(2));
+    assert_eq!(parse_golden(&record::format_position(&Board::new(), Color::Black)), Err(1));
+}
+
+#[test]
+fn records_are_verified() {
+    let moves = record::parse_transcript("e6f4e3f6g5d6e7f5c5").unwrap();
+    let mut game = GameRecord { moves, black_disks: Some(13), ..GameRecord::default() };
+    assert_eq!(verify_record(&game).map(|x| x.len()), Ok(9));
+    game.black_disks = Some(12);
+    assert_eq!(verify_record(&game), Err(VerifyError::Disks { expected: 12, found: 13 }));
+    game.forfeit(Color::Black, Termination::Resignation);
+    assert!(verify_record(&game).is_ok());
+    game.moves.swap(1, 2);
+    assert_eq!(verify_record(&game), Err(VerifyError::Illegal(1)));
+}
+
+#[test]
+fn transcripts_are_checked() {
+    let game = transcript("e6 f4 e3 f6 g5 d6 e7 f5 c5 13-0").unwrap();
+    assert_eq!(game.moves, record::parse_transcript("e6f4e3f6g5d6e7f5c5").unwrap());
+    assert_eq!(game.black_disks, Some(13));
+    assert_eq!(transcript("E6F4E3F6G5D6E7F5C5").unwrap().black_disks, Some(13));
+    assert_eq!(transcript("f5d6").unwrap().black_disks, None);
+    assert_eq!(transcript("e6f4e3f6g5d6e7f5c5 12-1"), Err(VerifyError::Result { declared: (12, 1), found: (13, 0) }));
+    assert_eq!(transcript("f5d6 33-31"), Err(VerifyError::Unfinished));
+    assert_eq!(transcript("f5 pass d6"), Err(VerifyError::UnforcedSkip(1)));
+    assert_eq!(transcript("e6f4e3f6g5d6e7f5c5 ps"), Err(VerifyError::UnforcedSkip(9)));
+    assert_eq!(transcript("f5 f5"), Err(VerifyError::Illegal(1)));
+    assert_eq!(transcript("f5 z9"), Err(VerifyError::Unreadable(1)));
+    assert_eq!(transcript("f5 d"), Err(VerifyError::Unreadable(1)));
+    assert_eq!(transcript("f5 x-y"), Err(VerifyError::Unreadable(1)));
+    assert_eq!(transcript("f5d6 3x-1"), Err(VerifyError::Unreadable(2)));
+    assert_eq!(transcript("f5d6 33-"), Err(VerifyError::Unreadable(2)));
+    assert_eq!(transcript("f5d6 300-1"), Err(VerifyError::UnreadableResult));
+    // moves separated by dashes are not taken for a result
+    assert_eq!(transcript("f5-d6-c3").map(|x| x.moves), Ok(record::parse_transcript("f5d6c3").unwrap()));
+    assert_eq!(transcript("e6-f4-e3-f6-g5-d6-e7-f5-c5 13-0").map(|x| x.black_disks), Ok(Some(13)));
+}
+
+#[test]
+fn skips_and_results_are_checked_against_the_board() {
+    assert_eq!(transcript("pass f5"), Err(VerifyError::UnforcedSkip(0)));
+    assert_eq!(transcript("f5 d6 ps c3"), Err(VerifyError::UnforcedSkip(2)));
+    assert_eq!(transcript("e6f4e3f6g5d6e7f5c5 pa pa"), Err(VerifyError::UnforcedSkip(9)));
+    assert_eq!(transcript("33-31"), Err(VerifyError::Unfinished));
+    assert_eq!(transcript("e6f4e3f6g5d6e7 0-0"), Err(VerifyError::Unfinished));
+    assert_eq!(transcript("e6f4e3f6g5d6e7f5c5 0-13"), Err(VerifyError::Result { declared: (0, 13), found: (13, 0) }));
+    assert_eq!(transcript("e6f4e3f6g5d6e7f5c5 13-0").map(|x| x.black_disks), Ok(Some(13)));
+}
+
+#[test]
+fn forced_skips_may_be_written() {
+    let games = golden_games();
+    let game = games.iter().find(|x| x.positions.iter().zip(&x.positions[1..]).any(|(a, b)| a.1 == b.1)).unwrap();
+    let mut text = String::new();
+    let mut color = Color::Black;
+    for (&position, &(_, next)) in game.record.moves.iter().zip(&game.positions) {
+        text += &record::format_move(position);
+        if next == color {
+            text += " pass ";
+        } else {
+            color = next;
+        }
+    }
+    let disks = game.record.black_disks.unwrap();
+    let written = transcript(&format!("{} {}-{}", text, disks, 64 - disks - game.positions.last().unwrap().0.empties() as u8)).unwrap();
+    assert_eq!(written.moves, game.record.moves);
+    assert_eq!(transcript(&record::format_transcript(&game.record.moves)).unwrap().black_disks, Some(disks));
+}
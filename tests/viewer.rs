@@ -0,0 +1,60 @@
+#![cfg(feature = "ui")]
+
+extern crate rusty_reversi;
+
+use rusty_reversi::eval::Heuristic;
+use rusty_reversi::game::Color;
+use rusty_reversi::record::{self, GameRecord};
+use rusty_reversi::search::AlphaBeta;
+use rusty_reversi::terminal::Theme;
+use rusty_reversi::viewer::{Command, Viewer};
+
+fn viewer(moves: &str) -> Viewer<AlphaBeta<Heuristic>> {
+    let record = GameRecord { moves: record::parse_transcript(moves).unwrap(), ..GameRecord::default() };
+    let mut viewer = Viewer::new(AlphaBeta::new(2, Heuristic::default()), record).unwrap();
+    viewer.theme = Theme::Monochrome;
+    viewer
+}
+
+#[test]
+fn commands_step_through_the_game() {
+    let mut viewer = viewer("f5d6c3d3c4");
+    assert_eq!(viewer.ply(), 0);
+    viewer.handle(Command::Back);
+    assert_eq!(viewer.ply(), 0);
+    viewer.handle("".parse().unwrap());
+    viewer.handle("n".parse().unwrap());
+    assert_eq!(viewer.ply(), 2);
+    assert_eq!(viewer.last_move().map(|x| (x.color, x.position)), Some((Color::White, record::parse_move("d6").unwrap())));
+    assert_eq!(viewer.position().1, Color::Black);
+    viewer.handle("9".parse().unwrap());
+    assert_eq!(viewer.ply(), 5);
+    viewer.handle(Command::Start);
+    assert_eq!(viewer.last_move(), None);
+    viewer.handle(Command::End);
+    viewer.handle(Command::Back);
+    assert_eq!(viewer.ply(), 4);
+    assert_eq!("x".parse::<Command>(), Err(()));
+    viewer.handle("q".parse().unwrap());
+    assert!(viewer.is_done());
+    assert!(Viewer::new(AlphaBeta::new(2, Heuristic::default()), GameRecord { moves: vec![(0, 0)], ..GameRecord::default() }).is_err());
+}
+
+#[test]
+fn boards_flip_and_show_the_evaluation() {
+    let mut viewer = viewer("f5");
+    viewer.handle(Command::Forward);
+    let text = viewer.draw();
+    assert!(text.starts_with("  a b c d e f g h\n1 . . ."));
+    assert!(text.contains("5 . . . X x>X . .\n"));
+    assert!(text.contains("Move 1 of 1: Black f5\nWhite to move\n"));
+    assert!(!text.contains("Evaluation"));
+    viewer.handle(Command::Flip);
+    let text = viewer.draw();
+    assert!(text.starts_with("  h g f e d c b a\n8 . . ."));
+    assert!(text.contains("5 . .>X x X . . .\n"));
+    viewer.handle(Command::Evaluate);
+    let text = viewer.draw();
+    assert!(text.contains("Evaluation: "));
+    assert!(text.ends_with("for Black\n"));
+}
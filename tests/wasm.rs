@@ -0,0 +1,50 @@
+#![cfg(feature = "wasm")]
+
+extern crate rusty_reversi;
+
+use rusty_reversi::wasm::{self, BrowserGame};
+
+#[test]
+fn games_are_played_through_the_bindings() {
+    let mut game = wasm::new_game();
+    assert_eq!(game.to_move().unwrap(), "black");
+    assert_eq!(game.legal_moves(), vec!["c4", "d3", "e6", "f5"]);
+    assert!(game.is_legal("f5") && !game.is_legal("a1") && !game.is_legal("z9"));
+    assert!(game.play("a1").is_err());
+    assert!(game.play("z9").is_err());
+
+    game.play("f5").unwrap();
+    assert_eq!(game.to_move().unwrap(), "white");
+    assert_eq!((game.black_disks(), game.white_disks()), (4, 1));
+    let best = game.best_move(50).unwrap();
+    assert!(game.is_legal(&best));
+    game.play(&best).unwrap();
+    assert_eq!(game.moves(), vec!["f5".to_string(), best]);
+    assert!(game.position().ends_with('X'));
+
+    let copy = BrowserGame::from_json(&game.to_json()).unwrap();
+    assert_eq!(copy.moves(), game.moves());
+    assert_eq!(copy.position(), game.position());
+    assert!(BrowserGame::from_json("{").is_err());
+}
+
+#[test]
+fn the_engine_thinks_in_steps() {
+    let mut game = wasm::new_game();
+    assert!(game.step(1).is_err());
+    game.think(20).unwrap();
+    let best = loop {
+        if let Some(best) = game.step(1).unwrap() {
+            break best;
+        }
+    };
+    assert!(game.is_legal(&best));
+    assert!(game.step(1).is_err());
+
+    // playing stops the engine
+    game.think(1000).unwrap();
+    assert_eq!(game.step(0).unwrap(), None);
+    game.play("f5").unwrap();
+    assert!(game.step(1).is_err());
+    assert!(game.best_move(10).is_some());
+}
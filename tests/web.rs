@@ -0,0 +1,246 @@
+#![cfg(feature = "web")]
+
+extern crate rusty_reversi;
+extern crate tungstenite;
+
+use std::env;
+use std::fs;
+use std::net::{SocketAddr, TcpStream};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rusty_reversi::engine::Limits;
+use rusty_reversi::eval::Heuristic;
+use rusty_reversi::formats::json::Value;
+use rusty_reversi::formats::pgn;
+use rusty_reversi::search::AlphaBeta;
+use rusty_reversi::web::Server;
+use tungstenite::{Message, WebSocket};
+
+fn start() -> SocketAddr {
+    let server = Server::bind("127.0.0.1:0").unwrap();
+    let address = server.local_addr().unwrap();
+    thread::spawn(move || server.run());
+    address
+}
+
+fn connect(address: SocketAddr) -> WebSocket<TcpStream> {
+    let stream = TcpStream::connect(address).unwrap();
+    tungstenite::client(format!("ws://{}/", address), stream).unwrap().0
+}
+
+fn send(socket: &mut WebSocket<TcpStream>, text: &str) {
+    socket.send(Message::Text(text.to_string())).unwrap();
+}
+
+// The next document sent by the server, which must be of the given kind.
+fn receive(socket: &mut WebSocket<TcpStream>, kind: &str) -> Value {
+    loop {
+        if let Message::Text(text) = socket.read().unwrap() {
+            let document = Value::parse(&text).unwrap();
+            assert_eq!(document.get("kind").and_then(Value::as_str), Some(kind), "{}", text);
+            return document;
+        }
+    }
+}
+
+fn text<'a>(document: &'a Value, key: &str) -> Option<&'a str> {
+    document.get(key).and_then(Value::as_str)
+}
+
+#[test]
+fn players_join_by_code_and_get_the_moves() {
+    let address = start();
+    let mut ann = connect(address);
+    send(&mut ann, r#"{"schema":1,"kind":"create","name":"Ann"}"#);
+    let joined = receive(&mut ann, "joined");
+    assert_eq!(text(&joined, "color"), Some("black"));
+    let code = text(&joined, "code").unwrap().to_string();
+    assert_eq!(receive(&mut ann, "state").get("white"), Some(&Value::Null));
+
+    let mut bob = connect(address);
+    send(&mut bob, &format!(r#"{{"schema":1,"kind":"join","code":"{}","name":"Bob"}}"#, code.to_lowercase()));
+    let joined = receive(&mut bob, "joined");
+    assert_eq!(text(&joined, "color"), Some("white"));
+    let token = text(&joined, "token").unwrap().to_string();
+    receive(&mut bob, "state");
+    assert_eq!(text(&receive(&mut ann, "state"), "white"), Some("Bob"));
+
+    send(&mut bob, r#"{"schema":1,"kind":"move","cell":"f5"}"#);
+    assert_eq!(text(&receive(&mut bob, "error"), "message"), Some("not your turn"));
+    send(&mut ann, r#"{"schema":1,"kind":"move","cell":"f5"}"#);
+    for socket in [&mut ann, &mut bob] {
+        let played = receive(socket, "move");
+        assert_eq!((text(&played, "color"), text(&played, "cell")), (Some("black"), Some("f5")));
+        let state = receive(socket, "state");
+        assert_eq!(state.get("moves"), Some(&Value::Array(vec![Value::String("f5".to_string())])));
+        assert_eq!(text(state.get("position").unwrap(), "to_move"), Some("white"));
+        assert_eq!(state.get("times").and_then(Value::as_array).map(|x| x.len()), Some(1));
+        assert!(state.get("timestamp").and_then(Value::as_f64).is_some_and(|x| x > 0.0));
+    }
+
+    // Bob loses the connection and takes his seat back
+    bob.close(None).unwrap();
+    drop(bob);
+    let mut bob = connect(address);
+    send(&mut bob, &format!(r#"{{"schema":1,"kind":"join","code":"{}","token":"{}"}}"#, code, token));
+    assert_eq!(text(&receive(&mut bob, "joined"), "color"), Some("white"));
+    let state = receive(&mut bob, "state");
+    assert_eq!(state.get("moves").and_then(Value::as_array).map(|x| x.len()), Some(1));
+    receive(&mut ann, "state");
+
+    let mut eve = connect(address);
+    send(&mut eve, &format!(r#"{{"schema":1,"kind":"join","code":"{}","name":"Eve"}}"#, code));
+    assert_eq!(receive(&mut eve, "joined").get("color"), Some(&Value::Null));
+    for socket in [&mut ann, &mut bob, &mut eve] {
+        receive(socket, "state");
+    }
+    send(&mut eve, r#"{"schema":1,"kind":"resign"}"#);
+    assert_eq!(text(&receive(&mut eve, "error"), "message"), Some("only players can do this"));
+
+    send(&mut bob, r#"{"schema":1,"kind":"resign"}"#);
+    for socket in [&mut ann, &mut bob, &mut eve] {
+        let state = receive(socket, "state");
+        assert_eq!((state.get("over"), text(&state, "resigned")), (Some(&Value::Bool(true)), Some("white")));
+    }
+}
+
+#[test]
+fn requests_are_checked() {
+    let address = start();
+    let mut socket = connect(address);
+    send(&mut socket, r#"{"schema":2,"kind":"create","name":"Ann"}"#);
+    assert!(text(&receive(&mut socket, "error"), "message").unwrap().contains("newer"));
+    send(&mut socket, r#"{"schema":1,"kind":"join","code":"NOGAME","name":"Ann"}"#);
+    assert_eq!(text(&receive(&mut socket, "error"), "message"), Some("no game with this code"));
+    send(&mut socket, r#"{"schema":1,"kind":"move","cell":"f5"}"#);
+    assert_eq!(text(&receive(&mut socket, "error"), "message"), Some("not in a game"));
+    send(&mut socket, r#"{"schema":1,"kind":"move","cell":"z9"}"#);
+    assert_eq!(text(&receive(&mut socket, "error"), "message"), Some("no such cell, disconnecting"));
+    assert!(!matches!(socket.read(), Ok(Message::Text(_))));
+
+    let mut socket = connect(address);
+    send(&mut socket, "[");
+    receive(&mut socket, "error");
+    assert!(!matches!(socket.read(), Ok(Message::Text(_))));
+
+    // nested deeply enough to overflow the stack of a parser without a limit, which would stop
+    // the server for everyone
+    let mut socket = connect(address);
+    send(&mut socket, &"[".repeat(60_000));
+    assert_eq!(text(&receive(&mut socket, "error"), "message"), Some("not a document, disconnecting"));
+    let mut socket = connect(address);
+    let _ = socket.send(Message::Text(" ".repeat(100 * 1024)));
+    assert!(!matches!(socket.read(), Ok(Message::Text(_))));
+    let mut socket = connect(address);
+    send(&mut socket, r#"{"schema":1,"kind":"create","name":"Ann"}"#);
+    receive(&mut socket, "joined");
+
+    // clocks too long for a duration, which would stop the server for everyone
+    let mut socket = connect(address);
+    send(&mut socket, r#"{"schema":1,"kind":"lobby","name":"Cid"}"#);
+    receive(&mut socket, "players");
+    send(&mut socket, r#"{"schema":1,"kind":"challenge","name":"Cid","minutes":1e300}"#);
+    assert_eq!(text(&receive(&mut socket, "error"), "message"), Some("invalid minutes"));
+    send(&mut socket, r#"{"schema":1,"kind":"challenge","name":"Cid","minutes":1,"increment":1e300}"#);
+    assert_eq!(text(&receive(&mut socket, "error"), "message"), Some("invalid increment"));
+    let mut socket = connect(address);
+    send(&mut socket, r#"{"schema":1,"kind":"create","name":"Ann"}"#);
+    receive(&mut socket, "joined");
+}
+
+#[test]
+fn lobby_players_challenge_each_other() {
+    let path = env::temp_dir().join("rusty_reversi_web_test.pgn");
+    let _ = fs::remove_file(&path);
+    let mut server = Server::bind("127.0.0.1:0").unwrap();
+    server.set_archive(&path);
+    let address = server.local_addr().unwrap();
+    thread::spawn(move || server.run());
+
+    let mut ann = connect(address);
+    send(&mut ann, r#"{"schema":1,"kind":"lobby","name":"Ann"}"#);
+    receive(&mut ann, "players");
+    let mut bob = connect(address);
+    send(&mut bob, r#"{"schema":1,"kind":"lobby","name":"Bob"}"#);
+    for socket in [&mut ann, &mut bob] {
+        let players = receive(socket, "players");
+        assert_eq!(players.get("players").and_then(Value::as_array).map(|x| x.len()), Some(2));
+    }
+
+    send(&mut ann, r#"{"schema":1,"kind":"challenge","name":"Bob","minutes":10}"#);
+    for socket in [&mut ann, &mut bob] {
+        let challenge = receive(socket, "challenge");
+        assert_eq!((text(&challenge, "from"), challenge.get("minutes")), (Some("Ann"), Some(&Value::Number(10.0))));
+    }
+    // Bob asks for a game too short to be played
+    send(&mut bob, r#"{"schema":1,"kind":"challenge","name":"Ann","color":"white","minutes":0.001}"#);
+    receive(&mut ann, "challenge");
+    receive(&mut bob, "challenge");
+    send(&mut ann, r#"{"schema":1,"kind":"accept","name":"Bob"}"#);
+    for (socket, color) in [(&mut ann, "black"), (&mut bob, "white")] {
+        assert_eq!(text(&receive(socket, "joined"), "color"), Some(color));
+        let state = receive(socket, "state");
+        assert_eq!((text(&state, "black"), text(&state, "white")), (Some("Ann"), Some("Bob")));
+        assert_eq!(state.get("black_clock"), Some(&Value::Number(60.0)));
+        let players = receive(socket, "players");
+        assert!(players.get("players").and_then(Value::as_array).unwrap().iter().all(|x| x.get("playing") == Some(&Value::Bool(true))));
+    }
+
+    for socket in [&mut ann, &mut bob] {
+        let state = receive(socket, "state");
+        assert_eq!((state.get("over"), text(&state, "flagged")), (Some(&Value::Bool(true)), Some("black")));
+        receive(socket, "players");
+    }
+    let mut games = Vec::new();
+    for _ in 0..100 {
+        games = pgn::load(&path).unwrap_or_default();
+        if !games.is_empty() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    let _ = fs::remove_file(&path);
+    assert_eq!(games.len(), 1);
+    assert_eq!((games[0].tag("termination"), games[0].black_disks), (Some("time"), Some(0)));
+}
+
+#[test]
+fn spectators_watch_with_a_delay() {
+    let mut server = Server::bind("127.0.0.1:0").unwrap();
+    server.set_spectator_delay(Duration::from_millis(300));
+    server.set_evaluation(AlphaBeta::new(2, Heuristic::default()), Limits { depth: Some(2), ..Limits::default() });
+    let address = server.local_addr().unwrap();
+    thread::spawn(move || server.run());
+
+    let mut ann = connect(address);
+    send(&mut ann, r#"{"schema":1,"kind":"create","name":"Ann"}"#);
+    let code = text(&receive(&mut ann, "joined"), "code").unwrap().to_string();
+    receive(&mut ann, "state");
+    let mut bob = connect(address);
+    send(&mut bob, &format!(r#"{{"schema":1,"kind":"join","code":"{}","name":"Bob"}}"#, code));
+    receive(&mut bob, "joined");
+    receive(&mut bob, "state");
+    receive(&mut ann, "state");
+    let mut eve = connect(address);
+    send(&mut eve, r#"{"schema":1,"kind":"watch","code":"NOGAME"}"#);
+    assert_eq!(text(&receive(&mut eve, "error"), "message"), Some("no game with this code"));
+    send(&mut eve, &format!(r#"{{"schema":1,"kind":"watch","code":"{}"}}"#, code));
+    assert_eq!(receive(&mut eve, "joined").get("color"), Some(&Value::Null));
+    for socket in [&mut eve, &mut ann, &mut bob] {
+        receive(socket, "state");
+    }
+
+    let start = Instant::now();
+    send(&mut ann, r#"{"schema":1,"kind":"move","cell":"f5"}"#);
+    receive(&mut ann, "move");
+    receive(&mut ann, "state");
+    assert!(start.elapsed() < Duration::from_millis(300));
+    assert_eq!(text(&receive(&mut eve, "move"), "cell"), Some("f5"));
+    assert!(start.elapsed() >= Duration::from_millis(300));
+    receive(&mut eve, "state");
+    let eval = receive(&mut eve, "eval");
+    assert_eq!(eval.get("depth"), Some(&Value::Number(2.0)));
+    assert!(["d6", "f6", "f4"].contains(&text(&eval, "best").unwrap()));
+    assert!(eval.get("black_score").and_then(Value::as_f64).is_some());
+}
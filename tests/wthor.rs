@@ -0,0 +1,78 @@
+#![cfg(feature = "formats")]
+
+extern crate rusty_reversi;
+
+use rusty_reversi::formats::wthor;
+use rusty_reversi::record::GameRecord;
+
+#[test]
+fn wthor_reads_games() {
+    let mut bytes = vec![20, 24, 1, 2, 1, 0, 0, 0, 0, 0, 0xe8, 0x07, 8, 0, 22, 0];
+    let mut game = vec![3, 0, 5, 0, 6, 0, 36, 34, 56, 64];
+    game.resize(68, 0);
+    bytes.extend_from_slice(&game);
+    let (header, games) = wthor::read(&bytes[..]).unwrap();
+    assert_eq!(header, wthor::Header { created: (2024, 1, 2), games: 1, names: 0, year: 2024, depth: 22 });
+    assert_eq!(games, vec![wthor::Game {
+        tournament: 3,
+        black: 5,
+        white: 6,
+        black_disks: 36,
+        theoretical_disks: 34,
+        // f5 d6
+        moves: vec![(5, 3), (3, 2)]
+    }]);
+    // a missing game
+    bytes[4] = 2;
+    assert!(wthor::read(&bytes[..]).is_err());
+}
+
+#[test]
+fn wthor_reads_names_into_records() {
+    let mut players = vec![20, 24, 1, 2, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0];
+    for name in &["Tastet Marc", "Shaman Brian"] {
+        let mut bytes = name.as_bytes().to_vec();
+        bytes.resize(20, 0);
+        players.extend_from_slice(&bytes);
+    }
+    let mut tournaments = vec![20, 24, 1, 2, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0];
+    let mut bytes = b"Championnat du Monde".to_vec();
+    bytes.resize(26, 0);
+    tournaments.extend_from_slice(&bytes);
+    let players = wthor::read_players(&players[..]).unwrap();
+    let tournaments = wthor::read_tournaments(&tournaments[..]).unwrap();
+    assert_eq!(players, vec!["Tastet Marc", "Shaman Brian"]);
+    assert_eq!(tournaments, vec!["Championnat du Monde"]);
+
+    let header = wthor::Header { created: (2024, 1, 2), games: 1, names: 0, year: 2024, depth: 22 };
+    let game = wthor::Game { tournament: 0, black: 1, white: 0, black_disks: 36, theoretical_disks: 34, moves: vec![(5, 3)] };
+    let records = wthor::to_records(&header, &[game], &players, &tournaments);
+    assert_eq!(records.len(), 1);
+    assert_eq!((records[0].black.as_str(), records[0].white.as_str()), ("Shaman Brian", "Tastet Marc"));
+    assert_eq!(records[0].event, "Championnat du Monde");
+    assert_eq!(records[0].date, "2024");
+    assert_eq!((records[0].black_disks, records[0].theoretical_disks), (Some(36), Some(34)));
+    assert_eq!(records[0].tag("theoretical_depth"), Some("22"));
+}
+
+#[test]
+fn wthor_writes_what_it_reads() {
+    let records = vec![
+        GameRecord { black: "Black".to_string(), white: "White".to_string(), event: "Self-play".to_string(), moves: vec![(5, 3), (3, 2), (2, 5)], ..GameRecord::default() },
+        GameRecord { black: "White".to_string(), white: "Black".to_string(), event: "Self-play".to_string(), moves: vec![(5, 3)], black_disks: Some(40), ..GameRecord::default() }
+    ];
+    let (games, players, tournaments) = wthor::from_records(&records);
+    assert_eq!(players, vec!["Black", "White"]);
+    assert_eq!(tournaments, vec!["Self-play"]);
+    assert_eq!((games[0].black_disks, games[0].theoretical_disks), (5, 5));
+    assert_eq!((games[1].black, games[1].white, games[1].black_disks), (1, 0, 40));
+
+    let header = wthor::Header { created: (2024, 3, 15), games: 0, names: 0, year: 2024, depth: 0 };
+    let mut bytes = Vec::new();
+    wthor::write(&mut bytes, &header, &games).unwrap();
+    assert_eq!(bytes.len(), 16 + 2 * 68);
+    assert_eq!(wthor::read(&bytes[..]).unwrap(), (wthor::Header { games: 2, ..header }, games));
+    let mut bytes = Vec::new();
+    wthor::write_players(&mut bytes, &header, &players).unwrap();
+    assert_eq!(wthor::read_players(&bytes[..]).unwrap(), players);
+}